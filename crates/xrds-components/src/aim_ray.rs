@@ -0,0 +1,230 @@
+use bevy::{gizmos::config::GizmoConfigStore, prelude::*};
+
+/// Shape of an [`AimRay`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AimRayShape {
+    /// Straight selection ray
+    Straight,
+    /// Ballistic teleport arc landing on the horizontal plane at `floor_height`
+    Arc {
+        /// Launch speed in m/s
+        speed: f32,
+        /// Downward acceleration in m/s²
+        gravity: f32,
+        floor_height: f32,
+    },
+}
+
+/// Ray drawn along the forward (-Z) axis of the entity, e.g. a controller aim pose
+///
+/// The computed path is stored in [`AimRayPath`]. Straight rays end at the
+/// [`AimRayTarget`] if there is one, arcs end where they land on the floor.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[require(Transform, Visibility, AimRayPath)]
+pub struct AimRay {
+    pub shape: AimRayShape,
+    /// Length of the ray, or of the arc before it is cut off
+    pub max_distance: f32,
+    /// Number of line segments of arcs
+    pub segments: u32,
+    pub color: Color,
+    /// Color of the ray while it hits something
+    pub hit_color: Color,
+    /// Hide the ray but keep the reticle, e.g. for gaze pointers
+    pub show_ray: bool,
+}
+
+/// Hit reported by an interaction system, e.g. a raycast against UI panels
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct AimRayTarget {
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+/// Points of an [`AimRay`] in world space and where it hits
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct AimRayPath {
+    pub points: Vec<Vec3>,
+    pub hit: Option<AimRayTarget>,
+}
+
+/// Marker drawn where an [`AimRay`] hits
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(AimRay)]
+pub struct Reticle {
+    pub radius: f32,
+    pub color: Color,
+}
+
+#[derive(Debug, Default)]
+pub struct AimRayPlugin;
+
+impl AimRay {
+    pub fn straight(max_distance: f32) -> Self {
+        Self {
+            max_distance,
+            ..Default::default()
+        }
+    }
+
+    /// Teleport arc with a launch speed of `speed` m/s landing on the floor at height 0
+    pub fn arc(speed: f32) -> Self {
+        Self {
+            shape: AimRayShape::Arc {
+                speed,
+                gravity: 9.81,
+                floor_height: 0.0,
+            },
+            max_distance: 20.0,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_color(mut self, color: Color, hit_color: Color) -> Self {
+        self.color = color;
+        self.hit_color = hit_color;
+        self
+    }
+
+    pub fn without_ray(mut self) -> Self {
+        self.show_ray = false;
+        self
+    }
+}
+
+impl Default for AimRay {
+    fn default() -> Self {
+        Self {
+            shape: AimRayShape::Straight,
+            max_distance: 5.0,
+            segments: 32,
+            color: Color::srgba(1.0, 1.0, 1.0, 0.6),
+            hit_color: Color::srgb(0.45, 0.72, 1.0),
+            show_ray: true,
+        }
+    }
+}
+
+impl Default for Reticle {
+    fn default() -> Self {
+        Self {
+            radius: 0.02,
+            color: Color::WHITE,
+        }
+    }
+}
+
+impl AimRayPath {
+    /// Compute the path of `ray` starting at `origin` towards `direction`
+    pub fn compute(
+        ray: &AimRay,
+        origin: Vec3,
+        direction: Dir3,
+        target: Option<&AimRayTarget>,
+    ) -> Self {
+        match ray.shape {
+            AimRayShape::Straight => {
+                let hit = target
+                    .filter(|target| target.point.distance(origin) <= ray.max_distance)
+                    .copied();
+                let end = match hit {
+                    Some(hit) => hit.point,
+                    None => origin + direction * ray.max_distance,
+                };
+                Self {
+                    points: vec![origin, end],
+                    hit,
+                }
+            }
+            AimRayShape::Arc {
+                speed,
+                gravity,
+                floor_height,
+            } => {
+                let velocity = direction * speed;
+                let acceleration = Vec3::NEG_Y * gravity;
+                let segments = ray.segments.max(1);
+                // Time to travel the max distance at launch speed
+                let step = ray.max_distance / speed.max(f32::EPSILON) / segments as f32;
+
+                let mut points = vec![origin];
+                let mut hit = None;
+                for i in 1..=segments {
+                    let t = step * i as f32;
+                    let point = origin + velocity * t + 0.5 * acceleration * t * t;
+                    let previous = points[points.len() - 1];
+                    if point.y <= floor_height && previous.y > floor_height {
+                        let s = (previous.y - floor_height) / (previous.y - point.y);
+                        let landing = previous.lerp(point, s);
+                        points.push(landing);
+                        hit = Some(AimRayTarget {
+                            point: landing,
+                            normal: Vec3::Y,
+                        });
+                        break;
+                    }
+                    points.push(point);
+                }
+                Self { points, hit }
+            }
+        }
+    }
+}
+
+impl Plugin for AimRayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (
+                update_aim_ray_paths,
+                draw_aim_rays.run_if(resource_exists::<GizmoConfigStore>),
+            )
+                .chain()
+                .after(TransformSystems::Propagate),
+        );
+    }
+}
+
+fn update_aim_ray_paths(
+    mut query: Query<(
+        &AimRay,
+        &GlobalTransform,
+        Option<&AimRayTarget>,
+        &mut AimRayPath,
+    )>,
+) {
+    for (ray, transform, target, mut path) in query.iter_mut() {
+        let computed =
+            AimRayPath::compute(ray, transform.translation(), transform.forward(), target);
+        path.set_if_neq(computed);
+    }
+}
+
+fn draw_aim_rays(
+    mut gizmos: Gizmos,
+    query: Query<(&AimRay, &AimRayPath, Option<&Reticle>, &InheritedVisibility)>,
+) {
+    for (ray, path, reticle, visibility) in query.iter() {
+        if !visibility.get() {
+            continue;
+        }
+        if ray.show_ray {
+            let color = if path.hit.is_some() {
+                ray.hit_color
+            } else {
+                ray.color
+            };
+            gizmos.linestrip(path.points.iter().copied(), color);
+        }
+        if let (Some(reticle), Some(hit)) = (reticle, path.hit) {
+            let normal = Dir3::new(hit.normal).unwrap_or(Dir3::Y);
+            // Circles are drawn in the XY plane, face them along the surface normal
+            let rotation = Quat::from_rotation_arc(Vec3::Z, *normal);
+            gizmos.circle(
+                Isometry3d::new(hit.point + *normal * 0.001, rotation),
+                reticle.radius,
+                reticle.color,
+            );
+        }
+    }
+}
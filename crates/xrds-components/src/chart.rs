@@ -0,0 +1,527 @@
+use bevy::{
+    asset::RenderAssetUsages, mesh::PrimitiveTopology, prelude::*, render::render_resource::Face,
+};
+
+use crate::{EntityLabel, LabelOcclusion};
+
+/// Thickness of the axis lines in meters
+const AXIS_THICKNESS: f32 = 0.002;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChartKind {
+    /// A bar per value, series are rows along z
+    #[default]
+    Bar,
+    /// A ribbon through the values, series are rows along z
+    Line,
+    /// A point per value
+    Scatter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartSeries {
+    pub name: String,
+    pub color: Color,
+    /// Values in data space
+    pub points: Vec<Vec3>,
+}
+
+/// Bar, line or scatter plot of numeric series in 3D
+///
+/// The data bounds are mapped to a box of `size` starting at the origin of
+/// the chart. Bars and points share a mesh per chart kind and a material per
+/// series, so they are drawn instanced. Replace the series every frame to
+/// animate the chart, marks are moved instead of respawned while the number
+/// of values stays the same.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[require(Transform, Visibility)]
+pub struct Chart {
+    pub kind: ChartKind,
+    pub series: Vec<ChartSeries>,
+    /// Size of the plot box in meters
+    pub size: Vec3,
+    /// Data bounds mapped to the plot box, fit to the data if `None`
+    pub bounds: Option<(Vec3, Vec3)>,
+    /// Width of bars and ribbons and diameter of points in meters
+    pub mark_size: f32,
+    /// Number of tick labels on value axes, none if less than 2
+    pub ticks: usize,
+    /// Tick labels of the x axis of bar and line charts
+    pub categories: Vec<String>,
+}
+
+/// Bar or point of a [`Chart`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChartMark {
+    pub series: usize,
+    pub index: usize,
+}
+
+/// Line of a [`ChartKind::Line`] chart
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChartRibbon {
+    pub series: usize,
+}
+
+/// Axis line or tick label of a [`Chart`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChartAxis;
+
+#[derive(Debug, Default)]
+pub struct ChartPlugin;
+
+/// Entities and assets of a chart reused between updates
+#[derive(Component, Debug, Clone, Default)]
+struct ChartParts {
+    kind: Option<ChartKind>,
+    marks: Vec<Vec<Entity>>,
+    ribbons: Vec<(Entity, Handle<Mesh>)>,
+    materials: Vec<Handle<StandardMaterial>>,
+    axes: Vec<Entity>,
+    ticks: Vec<Entity>,
+}
+
+#[derive(Debug, Clone)]
+struct ChartAssets {
+    cube: Handle<Mesh>,
+    sphere: Handle<Mesh>,
+    axis: Handle<StandardMaterial>,
+}
+
+impl ChartSeries {
+    pub fn new(name: impl Into<String>, color: Color) -> Self {
+        Self {
+            name: name.into(),
+            color,
+            points: vec![],
+        }
+    }
+
+    /// Values at x = 0, 1, 2...
+    pub fn with_values(mut self, values: impl IntoIterator<Item = f32>) -> Self {
+        self.points = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| Vec3::new(index as f32, value, 0.0))
+            .collect();
+        self
+    }
+
+    pub fn with_points(mut self, points: impl IntoIterator<Item = Vec3>) -> Self {
+        self.points = points.into_iter().collect();
+        self
+    }
+}
+
+impl Chart {
+    pub fn new(kind: ChartKind) -> Self {
+        Self {
+            kind,
+            series: vec![],
+            size: Vec3::new(1.0, 0.5, 0.3),
+            bounds: None,
+            mark_size: 0.05,
+            ticks: 5,
+            categories: vec![],
+        }
+    }
+
+    pub fn bar() -> Self {
+        Self::new(ChartKind::Bar)
+    }
+
+    pub fn line() -> Self {
+        Self::new(ChartKind::Line)
+    }
+
+    pub fn scatter() -> Self {
+        Self::new(ChartKind::Scatter)
+    }
+
+    pub fn with_series(mut self, series: ChartSeries) -> Self {
+        self.series.push(series);
+        self
+    }
+
+    pub fn with_size(mut self, size: Vec3) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_bounds(mut self, min: Vec3, max: Vec3) -> Self {
+        self.bounds = Some((min, max));
+        self
+    }
+
+    pub fn with_mark_size(mut self, mark_size: f32) -> Self {
+        self.mark_size = mark_size;
+        self
+    }
+
+    pub fn with_ticks(mut self, ticks: usize) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    pub fn with_categories(
+        mut self,
+        categories: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.categories = categories.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Point of a series in data space, bar and line series are moved to their row
+    pub fn data_point(&self, series: usize, point: Vec3) -> Vec3 {
+        match self.kind {
+            ChartKind::Bar | ChartKind::Line => point.with_z(point.z + series as f32),
+            ChartKind::Scatter => point,
+        }
+    }
+
+    /// Data bounds mapped to the plot box
+    pub fn data_bounds(&self) -> (Vec3, Vec3) {
+        if let Some(bounds) = self.bounds {
+            return bounds;
+        }
+        let points = self.series.iter().enumerate().flat_map(|(index, series)| {
+            series
+                .points
+                .iter()
+                .map(move |point| self.data_point(index, *point))
+        });
+        let (mut min, mut max) = points.fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), point| (min.min(point), max.max(point)),
+        );
+        if min.x > max.x {
+            return (Vec3::ZERO, Vec3::ONE);
+        }
+        if self.kind == ChartKind::Bar {
+            // Bars grow from zero and are centered on their category
+            min.y = min.y.min(0.0);
+            max.y = max.y.max(0.0);
+            min -= Vec3::new(0.5, 0.0, 0.5);
+            max += Vec3::new(0.5, 0.0, 0.5);
+        }
+        (min, max)
+    }
+
+    /// Position of a point in data space in the local space of the chart
+    pub fn to_local(&self, point: Vec3) -> Vec3 {
+        let (min, max) = self.data_bounds();
+        self.map(point, min, max)
+    }
+
+    fn map(&self, point: Vec3, min: Vec3, max: Vec3) -> Vec3 {
+        let extent = max - min;
+        let t = Vec3::select(
+            extent.cmpgt(Vec3::splat(f32::EPSILON)),
+            (point - min) / extent,
+            Vec3::splat(0.5),
+        );
+        t * self.size
+    }
+
+    /// Tick labels with their position in the local space of the chart
+    fn tick_labels(&self, min: Vec3, max: Vec3) -> Vec<(Vec3, String)> {
+        let mut labels = vec![];
+        let mut value_ticks = |axis: usize| {
+            if self.ticks < 2 || max[axis] <= min[axis] {
+                return;
+            }
+            for tick in 0..self.ticks {
+                let value =
+                    min[axis] + (max[axis] - min[axis]) * tick as f32 / (self.ticks - 1) as f32;
+                let mut point = min;
+                point[axis] = value;
+                labels.push((self.map(point, min, max), format_tick(value)));
+            }
+        };
+        value_ticks(1);
+        match self.kind {
+            ChartKind::Bar | ChartKind::Line => {
+                for (index, category) in self.categories.iter().enumerate() {
+                    let point = Vec3::new(index as f32, min.y, min.z);
+                    labels.push((self.map(point, min, max), category.clone()));
+                }
+                if self.series.len() > 1 {
+                    for (index, series) in self.series.iter().enumerate() {
+                        let point = Vec3::new(min.x, min.y, index as f32);
+                        labels.push((self.map(point, min, max), series.name.clone()));
+                    }
+                }
+            }
+            ChartKind::Scatter => {
+                value_ticks(0);
+                value_ticks(2);
+            }
+        }
+        labels
+    }
+}
+
+impl Default for Chart {
+    fn default() -> Self {
+        Self::bar()
+    }
+}
+
+impl Plugin for ChartPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            update_charts
+                .run_if(resource_exists::<Assets<Mesh>>)
+                .run_if(resource_exists::<Assets<StandardMaterial>>)
+                .before(TransformSystems::Propagate),
+        );
+    }
+}
+
+fn format_tick(value: f32) -> String {
+    let text = format!("{value:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// Spawn or despawn entities until there are `len`
+fn resize_parts(
+    commands: &mut Commands,
+    parts: &mut Vec<Entity>,
+    len: usize,
+    mut spawn: impl FnMut(&mut Commands, usize) -> Entity,
+) {
+    for entity in parts.drain(len.min(parts.len())..) {
+        commands.entity(entity).despawn();
+    }
+    for index in parts.len()..len {
+        let entity = spawn(commands, index);
+        parts.push(entity);
+    }
+}
+
+/// Triangles along the line through `points` in the xy plane
+fn ribbon_mesh(points: &[Vec3], width: f32) -> Mesh {
+    let mut positions = vec![];
+    for (index, point) in points.iter().enumerate() {
+        let previous = points[index.saturating_sub(1)];
+        let next = points[(index + 1).min(points.len() - 1)];
+        let direction = (next - previous).truncate().normalize_or(Vec2::X);
+        let offset = direction.perp().extend(0.0) * width / 2.0;
+        positions.push(*point - offset);
+        positions.push(*point + offset);
+    }
+    let indices = (0..points.len().saturating_sub(1) as u32)
+        .flat_map(|segment| {
+            let i = segment * 2;
+            [i, i + 2, i + 1, i + 1, i + 2, i + 3]
+        })
+        .collect::<Vec<_>>();
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(bevy::mesh::Indices::U32(indices))
+}
+
+fn update_charts(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut charts: Query<(Entity, &Chart, Option<&mut ChartParts>), Changed<Chart>>,
+    mut assets: Local<Option<ChartAssets>>,
+) {
+    if charts.is_empty() {
+        return;
+    }
+    let assets = assets
+        .get_or_insert_with(|| ChartAssets {
+            cube: meshes.add(Cuboid::from_length(1.0)),
+            sphere: meshes.add(Sphere::new(0.5)),
+            axis: materials.add(StandardMaterial {
+                base_color: Color::srgb(0.6, 0.6, 0.6),
+                unlit: true,
+                ..Default::default()
+            }),
+        })
+        .clone();
+
+    for (chart_entity, chart, parts) in charts.iter_mut() {
+        let is_new = parts.is_none();
+        let mut new_parts = ChartParts::default();
+        let parts = match parts {
+            Some(parts) => parts.into_inner(),
+            None => &mut new_parts,
+        };
+        // Marks of another kind use another mesh and material
+        if parts.kind != Some(chart.kind) {
+            for entity in parts.marks.drain(..).flatten() {
+                commands.entity(entity).despawn();
+            }
+            parts.materials.clear();
+            parts.kind = Some(chart.kind);
+        }
+
+        // A material per series
+        parts.materials.truncate(chart.series.len());
+        for (index, series) in chart.series.iter().enumerate() {
+            match parts.materials.get(index) {
+                Some(handle) => {
+                    if let Some(material) = materials
+                        .get_mut(handle)
+                        .filter(|material| material.base_color != series.color)
+                    {
+                        material.base_color = series.color;
+                    }
+                }
+                None => parts.materials.push(materials.add(StandardMaterial {
+                    base_color: series.color,
+                    cull_mode: (chart.kind != ChartKind::Line).then_some(Face::Back),
+                    ..Default::default()
+                })),
+            }
+        }
+
+        let (min, max) = chart.data_bounds();
+        let mark_series = if chart.kind == ChartKind::Line {
+            0
+        } else {
+            chart.series.len()
+        };
+        for entities in parts.marks.drain(mark_series.min(parts.marks.len())..) {
+            for entity in entities {
+                commands.entity(entity).despawn();
+            }
+        }
+        parts.marks.resize_with(mark_series, Vec::new);
+        for (series_index, entities) in parts.marks.iter_mut().enumerate() {
+            let series = &chart.series[series_index];
+            let mesh = match chart.kind {
+                ChartKind::Scatter => assets.sphere.clone(),
+                _ => assets.cube.clone(),
+            };
+            let material = parts.materials[series_index].clone();
+            resize_parts(
+                &mut commands,
+                entities,
+                series.points.len(),
+                |commands, index| {
+                    commands
+                        .spawn((
+                            ChartMark {
+                                series: series_index,
+                                index,
+                            },
+                            Mesh3d(mesh.clone()),
+                            MeshMaterial3d(material.clone()),
+                            ChildOf(chart_entity),
+                        ))
+                        .id()
+                },
+            );
+            for (entity, point) in entities.iter().zip(&series.points) {
+                let point = chart.data_point(series_index, *point);
+                let top = chart.map(point, min, max);
+                let transform = match chart.kind {
+                    ChartKind::Bar => {
+                        let base = chart.map(point.with_y(0.0_f32.clamp(min.y, max.y)), min, max);
+                        Transform::from_translation((base + top) / 2.0).with_scale(Vec3::new(
+                            chart.mark_size,
+                            (top.y - base.y).abs().max(1e-4),
+                            chart.mark_size,
+                        ))
+                    }
+                    _ => Transform::from_translation(top).with_scale(Vec3::splat(chart.mark_size)),
+                };
+                commands.entity(*entity).insert(transform);
+            }
+        }
+
+        // Ribbons keep their mesh and get new vertices
+        let ribbon_series = if chart.kind == ChartKind::Line {
+            chart.series.len()
+        } else {
+            0
+        };
+        for (entity, handle) in parts
+            .ribbons
+            .drain(ribbon_series.min(parts.ribbons.len())..)
+        {
+            commands.entity(entity).despawn();
+            meshes.remove(&handle);
+        }
+        for (series_index, series) in chart.series.iter().enumerate().take(ribbon_series) {
+            let points = series
+                .points
+                .iter()
+                .map(|point| chart.map(chart.data_point(series_index, *point), min, max))
+                .collect::<Vec<_>>();
+            let mesh = ribbon_mesh(&points, chart.mark_size);
+            match parts.ribbons.get(series_index) {
+                Some((_, handle)) => {
+                    if let Some(existing) = meshes.get_mut(handle) {
+                        *existing = mesh;
+                    }
+                }
+                None => {
+                    let handle = meshes.add(mesh);
+                    let entity = commands
+                        .spawn((
+                            ChartRibbon {
+                                series: series_index,
+                            },
+                            Mesh3d(handle.clone()),
+                            MeshMaterial3d(parts.materials[series_index].clone()),
+                            ChildOf(chart_entity),
+                        ))
+                        .id();
+                    parts.ribbons.push((entity, handle));
+                }
+            }
+        }
+
+        // Axes along the edges of the plot box from the origin
+        resize_parts(&mut commands, &mut parts.axes, 3, |commands, _| {
+            commands
+                .spawn((
+                    ChartAxis,
+                    Mesh3d(assets.cube.clone()),
+                    MeshMaterial3d(assets.axis.clone()),
+                    ChildOf(chart_entity),
+                ))
+                .id()
+        });
+        for (axis, entity) in parts.axes.iter().enumerate() {
+            let mut scale = Vec3::splat(AXIS_THICKNESS);
+            scale[axis] = chart.size[axis];
+            let mut translation = Vec3::ZERO;
+            translation[axis] = chart.size[axis] / 2.0;
+            commands
+                .entity(*entity)
+                .insert(Transform::from_translation(translation).with_scale(scale));
+        }
+
+        let labels = chart.tick_labels(min, max);
+        resize_parts(
+            &mut commands,
+            &mut parts.ticks,
+            labels.len(),
+            |commands, _| commands.spawn((ChartAxis, ChildOf(chart_entity))).id(),
+        );
+        for (entity, (position, text)) in parts.ticks.iter().zip(labels) {
+            commands.entity(*entity).insert((
+                Transform::from_translation(position),
+                EntityLabel::new(text)
+                    .with_priority(-1)
+                    .with_occlusion(LabelOcclusion::Ignore),
+            ));
+        }
+
+        if is_new {
+            commands.entity(chart_entity).insert(new_parts);
+        }
+    }
+}
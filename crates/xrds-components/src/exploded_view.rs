@@ -0,0 +1,210 @@
+use bevy::{camera::primitives::Aabb, prelude::*};
+
+/// Separates the parts of an assembly, e.g. a CAD-derived glTF scene
+///
+/// Parts are the children of the first level of the hierarchy below the
+/// entity with more than one child, so the single root nodes CAD exporters
+/// add are skipped. Each part moves away from the center of the assembly
+/// along the direction from that center to its own center. Only the
+/// translation of parts changes, the hierarchy stays as it is.
+///
+/// `factor` follows `target` at `speed` per second. Set `target` from a
+/// slider, or use [`ExplodedView::explode`] and [`ExplodedView::collapse`].
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(Transform, Visibility)]
+pub struct ExplodedView {
+    /// Offset of parts in meters when fully exploded
+    pub distance: f32,
+    /// Current explosion, 0 assembled and 1 fully exploded
+    pub factor: f32,
+    pub target: f32,
+    /// Change of `factor` per second
+    pub speed: f32,
+}
+
+/// Part moved by an [`ExplodedView`]
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ExplodedPart {
+    pub view: Entity,
+    /// Translation when assembled
+    pub origin: Vec3,
+    /// Direction to move in world space
+    pub direction: Vec3,
+}
+
+#[derive(Debug, Default)]
+pub struct ExplodedViewPlugin;
+
+impl ExplodedView {
+    pub fn new(distance: f32) -> Self {
+        Self {
+            distance,
+            factor: 0.0,
+            target: 0.0,
+            speed: 1.0,
+        }
+    }
+
+    pub fn explode(&mut self) {
+        self.target = 1.0;
+    }
+
+    pub fn collapse(&mut self) {
+        self.target = 0.0;
+    }
+
+    /// Jump to `factor` without animating
+    pub fn set(&mut self, factor: f32) {
+        self.factor = factor.clamp(0.0, 1.0);
+        self.target = self.factor;
+    }
+}
+
+impl Default for ExplodedView {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl Plugin for ExplodedViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                find_exploded_parts,
+                animate_exploded_views,
+                move_exploded_parts,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Children of the first level with more than one child
+fn assembly_parts(entity: Entity, children: &Query<&Children>) -> Vec<Entity> {
+    let mut current = entity;
+    loop {
+        let Ok(level) = children.get(current) else {
+            return vec![];
+        };
+        match level.len() {
+            0 => return vec![],
+            1 => current = level[0],
+            _ => return level.to_vec(),
+        }
+    }
+}
+
+/// World space center of the meshes below `entity`, or its origin without meshes
+fn part_center(
+    entity: Entity,
+    children: &Query<&Children>,
+    bounds: &Query<(&GlobalTransform, Option<&Aabb>)>,
+) -> Option<Vec3> {
+    let mut min = Vec3::INFINITY;
+    let mut max = Vec3::NEG_INFINITY;
+    for descendant in std::iter::once(entity).chain(children.iter_descendants(entity)) {
+        let Ok((transform, Some(aabb))) = bounds.get(descendant) else {
+            continue;
+        };
+        let center = Vec3::from(aabb.center);
+        let half_extents = Vec3::from(aabb.half_extents);
+        // Corners of the box in world space
+        for corner in 0..8 {
+            let sign = Vec3::new(
+                if corner & 1 == 0 { -1.0 } else { 1.0 },
+                if corner & 2 == 0 { -1.0 } else { 1.0 },
+                if corner & 4 == 0 { -1.0 } else { 1.0 },
+            );
+            let point = transform.transform_point(center + half_extents * sign);
+            min = min.min(point);
+            max = max.max(point);
+        }
+    }
+    if min.cmple(max).all() {
+        return Some((min + max) * 0.5);
+    }
+    bounds
+        .get(entity)
+        .ok()
+        .map(|(transform, _)| transform.translation())
+}
+
+fn find_exploded_parts(
+    mut commands: Commands,
+    views: Query<Entity, With<ExplodedView>>,
+    parts: Query<&ExplodedPart>,
+    children: Query<&Children>,
+    bounds: Query<(&GlobalTransform, Option<&Aabb>)>,
+    transforms: Query<&Transform>,
+) {
+    for view in views.iter() {
+        let assembly = assembly_parts(view, &children);
+        // Scenes spawn their parts a few frames after the view
+        if assembly.len() < 2 || assembly.iter().any(|part| parts.contains(*part)) {
+            continue;
+        }
+
+        let centers = assembly
+            .iter()
+            .filter_map(|part| Some((*part, part_center(*part, &children, &bounds)?)))
+            .collect::<Vec<_>>();
+        if centers.is_empty() {
+            continue;
+        }
+        let assembly_center =
+            centers.iter().map(|(_, center)| *center).sum::<Vec3>() / centers.len() as f32;
+
+        for (part, center) in centers {
+            let Ok(transform) = transforms.get(part) else {
+                continue;
+            };
+            // Parts in the center of the assembly move up
+            let direction = (center - assembly_center)
+                .try_normalize()
+                .unwrap_or(Vec3::Y);
+            commands.entity(part).insert(ExplodedPart {
+                view,
+                origin: transform.translation,
+                direction,
+            });
+        }
+    }
+}
+
+fn animate_exploded_views(time: Res<Time>, mut views: Query<&mut ExplodedView>) {
+    for mut view in views.iter_mut() {
+        let target = view.target.clamp(0.0, 1.0);
+        if view.factor == target {
+            continue;
+        }
+        let step = view.speed * time.delta_secs();
+        view.factor = if view.factor < target {
+            (view.factor + step).min(target)
+        } else {
+            (view.factor - step).max(target)
+        };
+    }
+}
+
+fn move_exploded_parts(
+    views: Query<Ref<ExplodedView>>,
+    parent_transforms: Query<&GlobalTransform>,
+    mut parts: Query<(Ref<ExplodedPart>, &ChildOf, &mut Transform)>,
+) {
+    for (part, child_of, mut transform) in parts.iter_mut() {
+        let Ok(view) = views.get(part.view) else {
+            continue;
+        };
+        if !view.is_changed() && !part.is_added() {
+            continue;
+        }
+        // Offset in meters, converted to the space of the parent
+        let offset = part.direction * view.distance * view.factor;
+        let local_offset = parent_transforms
+            .get(child_of.parent())
+            .map(|parent| parent.affine().inverse().transform_vector3(offset))
+            .unwrap_or(offset);
+        transform.translation = part.origin + local_offset;
+    }
+}
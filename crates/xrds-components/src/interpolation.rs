@@ -0,0 +1,84 @@
+use bevy::{app::RunFixedMainLoopSystems, prelude::*};
+
+/// Smooths the `Transform` of an entity moved in `FixedUpdate`
+///
+/// The rendered transform is interpolated between the last two simulation
+/// steps, so the entity does not judder when the frame rate is higher than
+/// the fixed timestep. The rendered transform lags one step behind the
+/// simulation. Move the entity in `FixedUpdate` only, or call
+/// [`TransformInterpolation::reset`] after moving it somewhere else.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[require(Transform)]
+pub struct TransformInterpolation {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+}
+
+#[derive(Debug, Default)]
+pub struct TransformInterpolationPlugin;
+
+impl TransformInterpolation {
+    /// Simulation state of the last fixed step
+    pub fn simulated(&self) -> Option<Transform> {
+        self.current
+    }
+
+    /// Skip interpolation until the next step, e.g. after teleporting
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.current = None;
+    }
+}
+
+impl Plugin for TransformInterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            RunFixedMainLoop,
+            (
+                restore_simulated_transform.in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop),
+                interpolate_transform.in_set(RunFixedMainLoopSystems::AfterFixedMainLoop),
+            ),
+        )
+        .add_systems(FixedFirst, store_previous_transform)
+        .add_systems(FixedLast, store_current_transform);
+    }
+}
+
+/// Put the simulation state back before running fixed steps
+fn restore_simulated_transform(mut query: Query<(&TransformInterpolation, &mut Transform)>) {
+    for (interpolation, mut transform) in query.iter_mut() {
+        if let Some(current) = interpolation.current {
+            *transform = current;
+        }
+    }
+}
+
+fn store_previous_transform(mut query: Query<(&mut TransformInterpolation, &Transform)>) {
+    for (mut interpolation, transform) in query.iter_mut() {
+        interpolation.previous = Some(*transform);
+    }
+}
+
+fn store_current_transform(mut query: Query<(&mut TransformInterpolation, &Transform)>) {
+    for (mut interpolation, transform) in query.iter_mut() {
+        interpolation.current = Some(*transform);
+    }
+}
+
+fn interpolate_transform(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&TransformInterpolation, &mut Transform)>,
+) {
+    let t = time.overstep_fraction();
+    for (interpolation, mut transform) in query.iter_mut() {
+        let (Some(previous), Some(current)) = (interpolation.previous, interpolation.current)
+        else {
+            continue;
+        };
+        *transform = Transform {
+            translation: previous.translation.lerp(current.translation, t),
+            rotation: previous.rotation.slerp(current.rotation, t),
+            scale: previous.scale.lerp(current.scale, t),
+        };
+    }
+}
@@ -0,0 +1,317 @@
+use bevy::{
+    picking::mesh_picking::ray_cast::{MeshRayCast, MeshRayCastSettings, RayCastVisibility},
+    prelude::*,
+};
+use xrds_graphics::{ObjectIdBuffer, ObjectIds};
+
+use crate::{PaletteRole, UiTheme};
+
+/// Upward moves tried before an overlapping label is hidden
+const MAX_LABEL_SHIFTS: usize = 3;
+
+/// Text anchored to an entity, always facing the viewer
+///
+/// Labels are UI text following the anchor on screen, so many of them are
+/// batched into a few draws. When the anchor is hidden behind other geometry
+/// the label reacts according to its [`LabelOcclusion`], and overlapping
+/// labels are moved up or hidden, keeping the higher priority and closer ones.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct EntityLabel {
+    pub text: String,
+    /// Offset of the anchor from the entity in world space
+    pub offset: Vec3,
+    /// Labels with a higher priority are kept when labels overlap
+    pub priority: i32,
+    pub occlusion: LabelOcclusion,
+}
+
+/// How a label is shown while its anchor is occluded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LabelOcclusion {
+    /// Draw the label with [`LabelSettings::occluded_alpha`]
+    #[default]
+    Fade,
+    /// Keep the label and add an arrow pointing at the hidden anchor
+    Arrow,
+    Hide,
+    /// Show the label as if it was visible
+    Ignore,
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct LabelSettings {
+    pub font_size: f32,
+    /// Alpha of faded labels
+    pub occluded_alpha: f32,
+    /// Move or hide overlapping labels
+    pub declutter: bool,
+    /// Space between decluttered labels in logical pixels
+    pub margin: f32,
+}
+
+/// UI text showing an [`EntityLabel`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct EntityLabelText {
+    pub target: Entity,
+    /// The anchor is behind other geometry
+    pub occluded: bool,
+    /// Hidden because it overlapped labels with a higher priority
+    pub decluttered: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct EntityLabelPlugin;
+
+struct LabelCandidate {
+    label: Entity,
+    rect: Rect,
+    priority: i32,
+    distance: f32,
+}
+
+impl EntityLabel {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            offset: Vec3::ZERO,
+            priority: 0,
+            occlusion: LabelOcclusion::default(),
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_occlusion(mut self, occlusion: LabelOcclusion) -> Self {
+        self.occlusion = occlusion;
+        self
+    }
+}
+
+impl Default for LabelSettings {
+    fn default() -> Self {
+        Self {
+            font_size: 14.0,
+            occluded_alpha: 0.3,
+            declutter: true,
+            margin: 2.0,
+        }
+    }
+}
+
+impl Plugin for EntityLabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LabelSettings>().add_systems(
+            PostUpdate,
+            update_entity_labels.after(TransformSystems::Propagate),
+        );
+    }
+}
+
+/// Whether `entity` is `target`, one of its descendants or one of its ancestors
+fn is_related(entity: Entity, target: Entity, parents: &Query<&ChildOf>) -> bool {
+    entity == target
+        || parents
+            .iter_ancestors(entity)
+            .any(|ancestor| ancestor == target)
+        || parents
+            .iter_ancestors(target)
+            .any(|ancestor| ancestor == entity)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_entity_labels(
+    mut commands: Commands,
+    labels: Query<(Entity, &EntityLabel, &GlobalTransform)>,
+    mut texts: Query<(
+        Entity,
+        &mut EntityLabelText,
+        &mut Text,
+        &mut TextColor,
+        &mut Node,
+        &mut Visibility,
+        &ComputedNode,
+    )>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform)>,
+    parents: Query<&ChildOf>,
+    object_ids: Option<Res<ObjectIds>>,
+    id_buffers: Query<&ObjectIdBuffer>,
+    mut ray_cast: Option<MeshRayCast>,
+    settings: Res<LabelSettings>,
+    theme: Option<Res<UiTheme>>,
+) {
+    let camera = cameras
+        .iter()
+        .filter(|(_, camera, _)| camera.is_active)
+        .min_by_key(|(_, camera, _)| camera.order);
+    let color = theme.map_or(Color::WHITE, |theme| theme.color(PaletteRole::Text));
+
+    let mut labeled = vec![];
+    let mut candidates = vec![];
+    for (
+        text_entity,
+        mut label_text,
+        mut text,
+        mut text_color,
+        mut node,
+        mut visibility,
+        computed,
+    ) in texts.iter_mut()
+    {
+        let Ok((_, label, transform)) = labels.get(label_text.target) else {
+            commands.entity(text_entity).despawn();
+            continue;
+        };
+        let target = label_text.target;
+        labeled.push(target);
+
+        let anchor = transform.translation() + label.offset;
+        let Some((camera_entity, camera, camera_transform)) = camera else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        let Ok(position) = camera.world_to_viewport(camera_transform, anchor) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        // Prefer the depth tested id buffer, ray cast against meshes without one
+        let distance = camera_transform.translation().distance(anchor);
+        let occluded = match (
+            object_ids.as_ref(),
+            id_buffers.get(camera_entity),
+            ray_cast.as_mut(),
+        ) {
+            _ if label.occlusion == LabelOcclusion::Ignore => false,
+            (Some(ids), Ok(buffer), _) => ids
+                .get(buffer.id_at(position))
+                .is_some_and(|hit| !is_related(hit, target, &parents)),
+            (_, _, Some(ray_cast)) => {
+                let origin = camera_transform.translation();
+                match Dir3::new(anchor - origin) {
+                    Ok(direction) => {
+                        let filter = |entity| !is_related(entity, target, &parents);
+                        let settings = MeshRayCastSettings::default()
+                            .with_visibility(RayCastVisibility::Visible)
+                            .with_filter(&filter);
+                        ray_cast
+                            .cast_ray(Ray3d::new(origin, direction), &settings)
+                            .first()
+                            .is_some_and(|(_, hit)| hit.distance < distance - 1e-3)
+                    }
+                    Err(_) => false,
+                }
+            }
+            _ => false,
+        };
+        label_text.occluded = occluded;
+        label_text.decluttered = false;
+
+        let value = match (occluded, label.occlusion) {
+            (true, LabelOcclusion::Arrow) => format!("{}\n▼", label.text),
+            _ => label.text.clone(),
+        };
+        if value != text.0 {
+            text.0 = value;
+        }
+        let alpha = match (occluded, label.occlusion) {
+            (true, LabelOcclusion::Fade) => settings.occluded_alpha,
+            _ => 1.0,
+        };
+        text_color.set_if_neq(TextColor(color.with_alpha(alpha)));
+        if occluded && label.occlusion == LabelOcclusion::Hide {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+
+        // Laid out size, estimated from the text before the first layout
+        let mut size = computed.size() * computed.inverse_scale_factor();
+        if size.x <= 0.0 || size.y <= 0.0 {
+            let lines = text.0.lines().count().max(1) as f32;
+            let columns = text.0.lines().map(|line| line.chars().count()).max();
+            size = Vec2::new(
+                columns.unwrap_or_default() as f32 * settings.font_size * 0.6,
+                lines * settings.font_size * 1.2,
+            );
+        }
+        // Centered above the anchor
+        let min = Vec2::new(position.x - size.x / 2.0, position.y - size.y);
+        candidates.push(LabelCandidate {
+            label: text_entity,
+            rect: Rect::from_corners(min, min + size),
+            priority: label.priority,
+            distance,
+        });
+        node.left = Val::Px(min.x);
+        node.top = Val::Px(min.y);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+
+    if settings.declutter {
+        candidates.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.distance.total_cmp(&b.distance))
+        });
+        let mut placed: Vec<Rect> = vec![];
+        for mut candidate in candidates {
+            let mut shifts = 0;
+            while let Some(overlap) = placed
+                .iter()
+                .find(|rect| !rect.intersect(candidate.rect).is_empty())
+            {
+                if shifts == MAX_LABEL_SHIFTS {
+                    break;
+                }
+                let offset = candidate.rect.max.y - overlap.min.y + settings.margin;
+                candidate.rect.min.y -= offset;
+                candidate.rect.max.y -= offset;
+                shifts += 1;
+            }
+            let Ok((_, mut label_text, _, _, mut node, mut visibility, _)) =
+                texts.get_mut(candidate.label)
+            else {
+                continue;
+            };
+            if placed
+                .iter()
+                .any(|rect| !rect.intersect(candidate.rect).is_empty())
+            {
+                label_text.decluttered = true;
+                visibility.set_if_neq(Visibility::Hidden);
+                continue;
+            }
+            node.top = Val::Px(candidate.rect.min.y);
+            placed.push(candidate.rect);
+        }
+    }
+
+    for (entity, ..) in labels.iter() {
+        if labeled.contains(&entity) {
+            continue;
+        }
+        commands.spawn((
+            EntityLabelText {
+                target: entity,
+                occluded: false,
+                decluttered: false,
+            },
+            Text::default(),
+            TextFont::from_font_size(settings.font_size),
+            TextLayout::new_with_justify(Justify::Center),
+            TextColor(color),
+            Node {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            Visibility::Hidden,
+        ));
+    }
+}
@@ -1 +1,50 @@
+mod aim_ray;
+mod chart;
+mod exploded_view;
+mod interpolation;
+mod label;
+mod localization;
+mod measurement;
+mod minimap;
+mod state_machine;
+mod theme;
+mod units;
 
+pub use aim_ray::*;
+pub use chart::*;
+pub use exploded_view::*;
+pub use interpolation::*;
+pub use label::*;
+pub use localization::*;
+pub use measurement::*;
+pub use minimap::*;
+pub use state_machine::*;
+pub use theme::*;
+pub use units::*;
+
+#[cfg(test)]
+mod tests;
+
+use bevy::prelude::*;
+
+/// Registers every component plugin provided by this crate
+#[derive(Debug, Default)]
+pub struct XrdsComponentsPlugin;
+
+impl Plugin for XrdsComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            AimRayPlugin,
+            AssetUnitsPlugin,
+            ChartPlugin,
+            EntityLabelPlugin,
+            ExplodedViewPlugin,
+            LocalizationPlugin,
+            MeasurementPlugin,
+            MiniMapPlugin,
+            StateMachinePlugin,
+            TransformInterpolationPlugin,
+            UiThemePlugin,
+        ));
+    }
+}
@@ -0,0 +1,313 @@
+use std::{borrow::Cow, collections::HashMap, path::Path};
+
+use anyhow::anyhow;
+use bevy::prelude::*;
+use serde_json::Value;
+
+/// Flat key-value string table of one language
+#[derive(Debug, Clone, Default)]
+pub struct StringTable {
+    strings: HashMap<String, String>,
+}
+
+/// Writing direction of a language, used only to pick the justification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+/// String tables and fonts for every loaded language
+///
+/// Right-to-left languages only switch the justification to `Justify::Right`.
+/// Strings are neither reordered nor shaped here, so RTL and bidirectional
+/// text shows up the way the font and text renderer lay it out.
+#[derive(Resource, Debug, Clone)]
+pub struct Localization {
+    tables: HashMap<String, StringTable>,
+    fonts: HashMap<String, Handle<Font>>,
+    language: String,
+    fallback_language: String,
+}
+
+/// Text whose content is looked up from the current language
+#[derive(Component, Debug, Clone, Default)]
+#[require(Text)]
+pub struct LocalizedText {
+    pub key: String,
+    pub args: Vec<(String, String)>,
+}
+
+#[derive(Debug, Default)]
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Localization>()
+            .add_systems(PostUpdate, update_localized_texts);
+    }
+}
+
+impl StringTable {
+    /// Load a string table. The format is chosen from the file extension (`json` or `ftl`)
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&source),
+            Some("ftl") => Self::from_ftl(&source),
+            _ => Err(anyhow!(
+                "Unsupported string table format: {}",
+                path.display()
+            )),
+        }
+    }
+
+    /// Parse a JSON object. Nested objects are flattened with '.' separated keys
+    pub fn from_json(source: &str) -> anyhow::Result<Self> {
+        fn flatten(prefix: &str, value: &Value, out: &mut HashMap<String, String>) {
+            match value {
+                Value::Object(map) => {
+                    for (k, v) in map {
+                        let key = if prefix.is_empty() {
+                            k.clone()
+                        } else {
+                            format!("{}.{}", prefix, k)
+                        };
+                        flatten(&key, v, out);
+                    }
+                }
+                Value::String(s) => {
+                    out.insert(prefix.to_owned(), s.clone());
+                }
+                Value::Null => {}
+                other => {
+                    out.insert(prefix.to_owned(), other.to_string());
+                }
+            }
+        }
+
+        let value: Value = serde_json::from_str(source)?;
+        if !value.is_object() {
+            return Err(anyhow!("String table root must be a JSON object"));
+        }
+        let mut strings = HashMap::new();
+        flatten("", &value, &mut strings);
+        Ok(Self { strings })
+    }
+
+    /// Parse simple Fluent messages (`key = value`, indented continuation lines, `#` comments)
+    ///
+    /// Attributes, terms and selectors are not supported.
+    pub fn from_ftl(source: &str) -> anyhow::Result<Self> {
+        let mut strings = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+
+        for (line_number, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                let Some((_, value)) = current.as_mut() else {
+                    return Err(anyhow!(
+                        "Unexpected continuation at line {}",
+                        line_number + 1
+                    ));
+                };
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(anyhow!(
+                    "Expected 'key = value' at line {}",
+                    line_number + 1
+                ));
+            };
+            if let Some((key, value)) = current.take() {
+                strings.insert(key, value);
+            }
+            current = Some((key.trim().to_owned(), value.trim().to_owned()));
+        }
+        if let Some((key, value)) = current {
+            strings.insert(key, value);
+        }
+
+        Ok(Self { strings })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.strings.insert(key.into(), value.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new("en")
+    }
+}
+
+impl Localization {
+    pub fn new(language: impl Into<String>) -> Self {
+        let language = language.into();
+        Self {
+            tables: HashMap::new(),
+            fonts: HashMap::new(),
+            fallback_language: language.clone(),
+            language,
+        }
+    }
+
+    pub fn add_table(&mut self, language: impl Into<String>, table: StringTable) {
+        let language = language.into();
+        match self.tables.get_mut(&language) {
+            Some(existing) => existing.strings.extend(table.strings),
+            None => {
+                self.tables.insert(language, table);
+            }
+        }
+    }
+
+    pub fn load_table(
+        &mut self,
+        language: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<()> {
+        let table = StringTable::load(path)?;
+        self.add_table(language, table);
+        Ok(())
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn set_language(&mut self, language: impl Into<String>) {
+        let language = language.into();
+        if !self.tables.contains_key(&language) {
+            warn!("No string table for language '{}'", language);
+        }
+        self.language = language;
+    }
+
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.tables.keys().map(String::as_str)
+    }
+
+    pub fn set_fallback_language(&mut self, language: impl Into<String>) {
+        self.fallback_language = language.into();
+    }
+
+    /// Font used for texts of `language`. Languages without a font use the default font
+    pub fn set_font(&mut self, language: impl Into<String>, font: Handle<Font>) {
+        self.fonts.insert(language.into(), font);
+    }
+
+    pub fn font(&self) -> Option<&Handle<Font>> {
+        self.fonts
+            .get(&self.language)
+            .or_else(|| self.fonts.get(&self.fallback_language))
+    }
+
+    /// Look up `key` in the current language, then the fallback language.
+    /// The key itself is returned when neither has it
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        [&self.language, &self.fallback_language]
+            .into_iter()
+            .filter_map(|language| self.tables.get(language))
+            .find_map(|table| table.get(key))
+            .unwrap_or(key)
+    }
+
+    /// Look up `key` and substitute `{ $name }` and `{name}` placeholders
+    pub fn format<'a, K, V>(&'a self, key: &'a str, args: &[(K, V)]) -> Cow<'a, str>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let text = self.get(key);
+        if args.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let mut result = text.to_owned();
+        for (name, value) in args {
+            let (name, value) = (name.as_ref(), value.as_ref());
+            result = result
+                .replace(&format!("{{ ${} }}", name), value)
+                .replace(&format!("{{${}}}", name), value)
+                .replace(&format!("{{{}}}", name), value);
+        }
+        Cow::Owned(result)
+    }
+
+    pub fn direction(&self) -> TextDirection {
+        let primary = self
+            .language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match primary.as_str() {
+            "ar" | "fa" | "he" | "iw" | "ps" | "ur" | "yi" | "dv" | "ckb" | "sd" | "ug" => {
+                TextDirection::RightToLeft
+            }
+            _ => TextDirection::LeftToRight,
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_localized_texts(
+    localization: Res<Localization>,
+    mut query: Query<(
+        Ref<LocalizedText>,
+        &mut Text,
+        Option<&mut TextFont>,
+        Option<&mut TextLayout>,
+    )>,
+) {
+    for (localized, mut text, font, layout) in query.iter_mut() {
+        if !localization.is_changed() && !localized.is_changed() {
+            continue;
+        }
+        text.0 = localization
+            .format(&localized.key, &localized.args)
+            .into_owned();
+        if let Some(mut text_font) = font {
+            match localization.font() {
+                Some(font) => text_font.font = font.clone(),
+                // Drop the font of the previous language, but keep fonts set by the application
+                None if localization
+                    .fonts
+                    .values()
+                    .any(|font| *font == text_font.font) =>
+                {
+                    text_font.font = Handle::default();
+                }
+                None => {}
+            }
+        }
+        if let Some(mut layout) = layout {
+            layout.justify = match localization.direction() {
+                TextDirection::LeftToRight => Justify::Left,
+                TextDirection::RightToLeft => Justify::Right,
+            };
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt;
+
+use bevy::{gizmos::config::GizmoConfigStore, prelude::*};
+use xrds_core::{Degrees, Meters};
+
+use crate::{PaletteRole, ThemedText};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasurementKind {
+    /// Length of the path through all points
+    Distance,
+    /// Angle at the second of three points
+    Angle,
+    /// Area of the polygon through all points
+    Area,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeasurementValue {
+    Distance(Meters),
+    Angle(Degrees),
+    /// Area in square meters
+    Area(f32),
+}
+
+/// Distance, angle or area between points in world space
+///
+/// Points are added with [`Measurement::push`] or by clicking on pickable
+/// meshes while the measurement is the [`MeasurementPlacement`] target. Guide
+/// lines are drawn with gizmos and the value is shown in a label following
+/// the measurement.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub kind: MeasurementKind,
+    pub points: Vec<Vec3>,
+    pub color: Color,
+}
+
+/// Measurement that picked points are added to
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeasurementPlacement {
+    pub target: Option<Entity>,
+}
+
+/// UI text showing the value of a [`Measurement`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct MeasurementLabel {
+    pub measurement: Entity,
+}
+
+#[derive(Debug, Default)]
+pub struct MeasurementPlugin;
+
+impl Measurement {
+    pub fn new(kind: MeasurementKind) -> Self {
+        Self {
+            kind,
+            points: vec![],
+            color: Color::srgb(1.0, 0.8, 0.2),
+        }
+    }
+
+    pub fn distance() -> Self {
+        Self::new(MeasurementKind::Distance)
+    }
+
+    pub fn angle() -> Self {
+        Self::new(MeasurementKind::Angle)
+    }
+
+    pub fn area() -> Self {
+        Self::new(MeasurementKind::Area)
+    }
+
+    pub fn with_points(mut self, points: impl IntoIterator<Item = Vec3>) -> Self {
+        self.points.extend(points);
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Add a point. Angles keep their first two points and move the third
+    pub fn push(&mut self, point: Vec3) {
+        if self.kind == MeasurementKind::Angle && self.points.len() >= 3 {
+            self.points.truncate(2);
+        }
+        self.points.push(point);
+    }
+
+    /// `None` until enough points are placed
+    pub fn value(&self) -> Option<MeasurementValue> {
+        let points = &self.points;
+        match self.kind {
+            MeasurementKind::Distance => {
+                if points.len() < 2 {
+                    return None;
+                }
+                let length = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+                Some(MeasurementValue::Distance(Meters(length)))
+            }
+            MeasurementKind::Angle => {
+                let [a, vertex, b] = points.get(..3)? else {
+                    return None;
+                };
+                let angle = (*a - *vertex).angle_between(*b - *vertex);
+                Some(MeasurementValue::Angle(Degrees(angle.to_degrees())))
+            }
+            MeasurementKind::Area => {
+                if points.len() < 3 {
+                    return None;
+                }
+                // Newell's method, for polygons in any plane
+                let normal = points
+                    .iter()
+                    .zip(points.iter().cycle().skip(1))
+                    .map(|(a, b)| a.cross(*b))
+                    .sum::<Vec3>();
+                Some(MeasurementValue::Area(normal.length() * 0.5))
+            }
+        }
+    }
+
+    /// Where the label is shown
+    pub fn anchor(&self) -> Option<Vec3> {
+        match self.kind {
+            MeasurementKind::Angle => self.points.get(1).copied(),
+            _ if self.points.is_empty() => None,
+            _ => Some(self.points.iter().sum::<Vec3>() / self.points.len() as f32),
+        }
+    }
+}
+
+impl fmt::Display for MeasurementValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Distance(meters) if meters.get() < 1.0 => {
+                write!(f, "{:.1} cm", meters.centimeters())
+            }
+            Self::Distance(meters) => write!(f, "{:.2} m", meters.get()),
+            Self::Angle(degrees) => write!(f, "{:.1}°", degrees.get()),
+            Self::Area(area) => write!(f, "{:.2} m²", area),
+        }
+    }
+}
+
+impl Plugin for MeasurementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MeasurementPlacement>()
+            .add_message::<Pointer<Click>>()
+            .add_systems(Update, place_measurement_points)
+            .add_systems(
+                PostUpdate,
+                (
+                    update_measurement_labels,
+                    draw_measurements.run_if(resource_exists::<GizmoConfigStore>),
+                )
+                    .after(TransformSystems::Propagate),
+            );
+    }
+}
+
+fn place_measurement_points(
+    placement: Res<MeasurementPlacement>,
+    mut clicks: MessageReader<Pointer<Click>>,
+    mut measurements: Query<&mut Measurement>,
+) {
+    for click in clicks.read() {
+        let Some(target) = placement.target else {
+            continue;
+        };
+        let (Some(position), Ok(mut measurement)) =
+            (click.hit.position, measurements.get_mut(target))
+        else {
+            continue;
+        };
+        measurement.push(position);
+    }
+}
+
+fn update_measurement_labels(
+    mut commands: Commands,
+    measurements: Query<(Entity, &Measurement)>,
+    mut labels: Query<(
+        Entity,
+        &MeasurementLabel,
+        &mut Text,
+        &mut Node,
+        &mut Visibility,
+    )>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    let camera = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .min_by_key(|(camera, _)| camera.order);
+
+    let mut labeled = vec![];
+    for (label_entity, label, mut text, mut node, mut visibility) in labels.iter_mut() {
+        let Ok((_, measurement)) = measurements.get(label.measurement) else {
+            commands.entity(label_entity).despawn();
+            continue;
+        };
+        labeled.push(label.measurement);
+
+        let value = measurement.value().map(|value| value.to_string());
+        if let Some(value) = value.as_ref().filter(|value| **value != text.0) {
+            text.0.clone_from(value);
+        }
+        // Follow the anchor on screen
+        let position =
+            camera
+                .zip(measurement.anchor())
+                .and_then(|((camera, camera_transform), anchor)| {
+                    camera.world_to_viewport(camera_transform, anchor).ok()
+                });
+        match (position, value) {
+            (Some(position), Some(_)) => {
+                node.left = Val::Px(position.x);
+                node.top = Val::Px(position.y);
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            _ => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+
+    for (entity, _) in measurements.iter() {
+        if labeled.contains(&entity) {
+            continue;
+        }
+        commands.spawn((
+            MeasurementLabel {
+                measurement: entity,
+            },
+            Text::default(),
+            ThemedText(PaletteRole::Text),
+            Node {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn draw_measurements(mut gizmos: Gizmos, measurements: Query<&Measurement>) {
+    for measurement in measurements.iter() {
+        let color = measurement.color;
+        let points = &measurement.points;
+        for point in points {
+            gizmos.sphere(Isometry3d::from_translation(*point), 0.01, color);
+        }
+        match measurement.kind {
+            MeasurementKind::Distance | MeasurementKind::Angle => {
+                gizmos.linestrip(points.iter().copied(), color);
+            }
+            MeasurementKind::Area => {
+                let closed = points.first().filter(|_| points.len() >= 3);
+                gizmos.linestrip(points.iter().chain(closed).copied(), color);
+            }
+        }
+        if let (MeasurementKind::Angle, Some(MeasurementValue::Angle(_))) =
+            (measurement.kind, measurement.value())
+        {
+            let (a, vertex, b) = (points[0], points[1], points[2]);
+            // Arc between the legs, at most half as long as the shorter one
+            let radius = a.distance(vertex).min(b.distance(vertex)).min(0.2) * 0.5;
+            let from = vertex + (a - vertex).normalize_or_zero() * radius;
+            let to = vertex + (b - vertex).normalize_or_zero() * radius;
+            gizmos.short_arc_3d_between(vertex, from, to, color);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use bevy::{
+    camera::{visibility::RenderLayers, RenderTarget, ScalingMode},
+    prelude::*,
+    render::render_resource::TextureFormat,
+};
+use xrds_graphics::CameraOrder;
+
+/// Render layer of mini-map icons, only seen by mini-map cameras
+pub const MINIMAP_LAYER: usize = 30;
+
+/// Height of the mini-map camera above the followed entity
+const CAMERA_HEIGHT: f32 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MiniMapPlacement {
+    /// Top right corner of the window, for desktop
+    Corner {
+        /// Size in logical pixels
+        size: f32,
+        margin: f32,
+    },
+    /// Panel attached to an entity, e.g. a wrist panel on the left controller
+    Panel {
+        anchor: Entity,
+        /// Size in meters
+        size: f32,
+        /// Transform relative to the anchor
+        offset: Transform,
+    },
+}
+
+/// Top-down overview of the area around an entity
+///
+/// An orthographic camera looking down renders the map into a texture,
+/// which is shown in a corner of the window or on a panel in the scene.
+/// Entities with a [`MiniMapIcon`], e.g. the player, are marked on it.
+#[derive(Component, Debug, Clone)]
+#[require(Transform, Visibility)]
+pub struct MiniMap {
+    pub follow: Option<Entity>,
+    /// Width of the area shown, in meters
+    pub extent: f32,
+    /// Width and height of the texture in pixels
+    pub resolution: u32,
+    pub placement: MiniMapPlacement,
+    /// Turn the map with the followed entity instead of keeping -Z up
+    pub rotate_with_target: bool,
+    pub background: Color,
+    image: Option<Handle<Image>>,
+}
+
+/// Marks an entity on every [`MiniMap`]
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct MiniMapIcon {
+    pub color: Color,
+    /// Radius in meters
+    pub radius: f32,
+}
+
+/// Camera rendering a [`MiniMap`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct MiniMapCamera {
+    pub map: Entity,
+}
+
+/// UI node or panel showing a [`MiniMap`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct MiniMapDisplay {
+    pub map: Entity,
+}
+
+/// Disc drawn for a [`MiniMapIcon`]
+#[derive(Component, Debug, Clone, Copy)]
+struct MiniMapMarker {
+    target: Entity,
+}
+
+#[derive(Debug, Default)]
+pub struct MiniMapPlugin;
+
+impl MiniMap {
+    pub fn new(extent: f32) -> Self {
+        Self {
+            follow: None,
+            extent,
+            resolution: 512,
+            placement: MiniMapPlacement::Corner {
+                size: 200.0,
+                margin: 16.0,
+            },
+            rotate_with_target: false,
+            background: Color::srgb(0.1, 0.11, 0.13),
+            image: None,
+        }
+    }
+
+    pub fn following(mut self, target: Entity) -> Self {
+        self.follow = Some(target);
+        self
+    }
+
+    /// Show the map on a panel of `size` meters attached to `anchor`
+    pub fn on_panel(mut self, anchor: Entity, size: f32, offset: Transform) -> Self {
+        self.placement = MiniMapPlacement::Panel {
+            anchor,
+            size,
+            offset,
+        };
+        self
+    }
+
+    pub fn rotating_with_target(mut self) -> Self {
+        self.rotate_with_target = true;
+        self
+    }
+
+    /// Texture the map is rendered to, once it is set up
+    pub fn image(&self) -> Option<&Handle<Image>> {
+        self.image.as_ref()
+    }
+}
+
+impl MiniMapIcon {
+    pub fn new(color: Color, radius: f32) -> Self {
+        Self { color, radius }
+    }
+
+    pub fn player() -> Self {
+        Self::new(Color::srgb(0.26, 0.55, 0.96), 0.5)
+    }
+}
+
+impl Plugin for MiniMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (setup_mini_maps, spawn_mini_map_markers)
+                    .run_if(resource_exists::<Assets<Image>>)
+                    .run_if(resource_exists::<Assets<Mesh>>)
+                    .run_if(resource_exists::<Assets<StandardMaterial>>),
+                despawn_mini_map_parts,
+            )
+                .chain(),
+        )
+        .add_systems(
+            PostUpdate,
+            (follow_mini_map_targets, move_mini_map_markers).before(TransformSystems::Propagate),
+        );
+    }
+}
+
+fn setup_mini_maps(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut maps: Query<(Entity, &mut MiniMap)>,
+) {
+    for (map_entity, mut map) in maps.iter_mut() {
+        if map.image.is_some() {
+            continue;
+        }
+        let image = images.add(Image::new_target_texture(
+            map.resolution,
+            map.resolution,
+            TextureFormat::Rgba8UnormSrgb,
+        ));
+
+        commands.spawn((
+            Name::new("Mini-map camera"),
+            MiniMapCamera { map: map_entity },
+            Camera3d::default(),
+            Camera {
+                target: RenderTarget::Image(image.clone().into()),
+                ..Default::default()
+            },
+            // Before the cameras showing it
+            CameraOrder::new(-10).with_clear_color(map.background),
+            Projection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: map.extent,
+                    height: map.extent,
+                },
+                ..OrthographicProjection::default_3d()
+            }),
+            RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
+            Transform::from_xyz(0.0, CAMERA_HEIGHT, 0.0).looking_to(Vec3::NEG_Y, Vec3::NEG_Z),
+        ));
+
+        match map.placement {
+            MiniMapPlacement::Corner { size, margin } => {
+                commands.spawn((
+                    Name::new("Mini-map"),
+                    MiniMapDisplay { map: map_entity },
+                    ImageNode::new(image.clone()),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(margin),
+                        right: Val::Px(margin),
+                        width: Val::Px(size),
+                        height: Val::Px(size),
+                        ..Default::default()
+                    },
+                ));
+            }
+            MiniMapPlacement::Panel {
+                anchor,
+                size,
+                offset,
+            } => {
+                commands.spawn((
+                    Name::new("Mini-map panel"),
+                    MiniMapDisplay { map: map_entity },
+                    Mesh3d(meshes.add(Rectangle::new(size, size))),
+                    MeshMaterial3d(materials.add(StandardMaterial {
+                        base_color_texture: Some(image.clone()),
+                        unlit: true,
+                        ..Default::default()
+                    })),
+                    offset,
+                    ChildOf(anchor),
+                ));
+            }
+        }
+        map.image = Some(image);
+    }
+}
+
+fn spawn_mini_map_markers(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    icons: Query<(Entity, &MiniMapIcon), Added<MiniMapIcon>>,
+) {
+    for (entity, icon) in icons.iter() {
+        commands.spawn((
+            MiniMapMarker { target: entity },
+            Mesh3d(meshes.add(Circle::new(icon.radius))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: icon.color,
+                unlit: true,
+                ..Default::default()
+            })),
+            RenderLayers::layer(MINIMAP_LAYER),
+            Transform::default(),
+        ));
+    }
+}
+
+fn despawn_mini_map_parts(
+    mut commands: Commands,
+    maps: Query<(), With<MiniMap>>,
+    icons: Query<(), With<MiniMapIcon>>,
+    cameras: Query<(Entity, &MiniMapCamera)>,
+    displays: Query<(Entity, &MiniMapDisplay)>,
+    markers: Query<(Entity, &MiniMapMarker)>,
+) {
+    let parts = cameras
+        .iter()
+        .map(|(entity, camera)| (entity, camera.map))
+        .chain(
+            displays
+                .iter()
+                .map(|(entity, display)| (entity, display.map)),
+        );
+    for (entity, map) in parts {
+        if !maps.contains(map) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (entity, marker) in markers.iter() {
+        if !icons.contains(marker.target) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn follow_mini_map_targets(
+    maps: Query<&MiniMap>,
+    targets: Query<&GlobalTransform>,
+    mut cameras: Query<(&MiniMapCamera, &mut Transform)>,
+) {
+    for (camera, mut transform) in cameras.iter_mut() {
+        let Ok(map) = maps.get(camera.map) else {
+            continue;
+        };
+        let Some(target) = map.follow.and_then(|target| targets.get(target).ok()) else {
+            continue;
+        };
+        let up = if map.rotate_with_target {
+            // Forward of the target on the floor, or north when looking straight down
+            let forward = target.forward().with_y(0.0);
+            forward.try_normalize().unwrap_or(Vec3::NEG_Z)
+        } else {
+            Vec3::NEG_Z
+        };
+        let camera_transform =
+            Transform::from_translation(target.translation() + Vec3::Y * CAMERA_HEIGHT)
+                .looking_to(Vec3::NEG_Y, up);
+        transform.set_if_neq(camera_transform);
+    }
+}
+
+fn move_mini_map_markers(
+    targets: Query<&GlobalTransform, With<MiniMapIcon>>,
+    mut markers: Query<(&MiniMapMarker, &mut Transform)>,
+) {
+    for (marker, mut transform) in markers.iter_mut() {
+        let Ok(target) = targets.get(marker.target) else {
+            continue;
+        };
+        // Discs face up and float over the scene, below the cameras
+        let translation = target.translation() + Vec3::Y * CAMERA_HEIGHT * 0.5;
+        let marker_transform = Transform::from_translation(translation)
+            .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2));
+        transform.set_if_neq(marker_transform);
+    }
+}
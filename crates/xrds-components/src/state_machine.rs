@@ -0,0 +1,306 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use bevy::prelude::*;
+
+/// Action invoked when a state is entered or exited
+pub type StateAction = Arc<dyn Fn(&mut Commands, Entity) + Send + Sync>;
+/// Condition evaluated every frame against the world
+pub type StateCondition = Arc<dyn Fn(&World, Entity) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+pub enum StateTrigger {
+    /// Fired when the named event is sent to the state machine
+    Event(String),
+    /// Fired when the condition returns true
+    Condition(StateCondition),
+    /// Fired after staying in the source state for the duration
+    After(Duration),
+}
+
+#[derive(Clone)]
+struct StateTransition {
+    from: Option<String>,
+    to: String,
+    trigger: StateTrigger,
+}
+
+#[derive(Clone, Default)]
+struct StateDescriptor {
+    name: String,
+    on_enter: Vec<StateAction>,
+    on_exit: Vec<StateAction>,
+}
+
+/// Lightweight state machine for scripting interactive objects
+///
+/// Transitions are checked in declaration order and at most one transition
+/// is taken per frame.
+#[derive(Component, Clone)]
+pub struct StateMachine {
+    states: Vec<StateDescriptor>,
+    transitions: Vec<StateTransition>,
+    current: usize,
+    elapsed: Duration,
+    pending_events: VecDeque<String>,
+    entered: bool,
+}
+
+pub struct StateMachineBuilder {
+    states: Vec<StateDescriptor>,
+    transitions: Vec<StateTransition>,
+    initial: String,
+}
+
+/// Send an event to the state machine of `entity`
+#[derive(Message, Clone, Debug)]
+pub struct StateMachineEvent {
+    pub entity: Entity,
+    pub event: String,
+}
+
+/// Written after a state machine moved to another state
+#[derive(Message, Clone, Debug)]
+pub struct StateChanged {
+    pub entity: Entity,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Default)]
+pub struct StateMachinePlugin;
+
+impl Plugin for StateMachinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<StateMachineEvent>()
+            .add_message::<StateChanged>()
+            .add_systems(
+                Update,
+                (queue_state_machine_events, update_state_machines).chain(),
+            );
+    }
+}
+
+impl StateMachine {
+    pub fn builder(initial: impl Into<String>) -> StateMachineBuilder {
+        let initial = initial.into();
+        StateMachineBuilder {
+            states: vec![StateDescriptor {
+                name: initial.clone(),
+                ..Default::default()
+            }],
+            transitions: vec![],
+            initial,
+        }
+    }
+
+    pub fn current(&self) -> &str {
+        &self.states[self.current].name
+    }
+
+    /// Time spent in the current state
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn is_in(&self, state: &str) -> bool {
+        self.current() == state
+    }
+
+    /// Queue an event which is consumed on the next update
+    pub fn send(&mut self, event: impl Into<String>) {
+        self.pending_events.push_back(event.into());
+    }
+
+    fn state_index(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|s| s.name == name)
+    }
+
+    fn find_transition(&self, world: &World, entity: Entity) -> Option<usize> {
+        let current = self.current();
+        self.transitions
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.from.as_deref().is_none_or(|from| from == current))
+            .find(|(_, t)| match &t.trigger {
+                StateTrigger::Event(event) => self.pending_events.contains(event),
+                StateTrigger::Condition(condition) => condition(world, entity),
+                StateTrigger::After(duration) => self.elapsed >= *duration,
+            })
+            .map(|(i, _)| i)
+    }
+}
+
+impl StateMachineBuilder {
+    fn state_mut(&mut self, name: &str) -> &mut StateDescriptor {
+        let index = match self.states.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => {
+                self.states.push(StateDescriptor {
+                    name: name.to_owned(),
+                    ..Default::default()
+                });
+                self.states.len() - 1
+            }
+        };
+        &mut self.states[index]
+    }
+
+    pub fn state(mut self, name: &str) -> Self {
+        self.state_mut(name);
+        self
+    }
+
+    pub fn on_enter<F>(mut self, state: &str, action: F) -> Self
+    where
+        F: Fn(&mut Commands, Entity) + Send + Sync + 'static,
+    {
+        self.state_mut(state).on_enter.push(Arc::new(action));
+        self
+    }
+
+    pub fn on_exit<F>(mut self, state: &str, action: F) -> Self
+    where
+        F: Fn(&mut Commands, Entity) + Send + Sync + 'static,
+    {
+        self.state_mut(state).on_exit.push(Arc::new(action));
+        self
+    }
+
+    /// Add a transition. `from == None` means the transition is valid from any state
+    pub fn transition(mut self, from: Option<&str>, to: &str, trigger: StateTrigger) -> Self {
+        if let Some(from) = from {
+            self.state_mut(from);
+        }
+        self.state_mut(to);
+        self.transitions.push(StateTransition {
+            from: from.map(str::to_owned),
+            to: to.to_owned(),
+            trigger,
+        });
+        self
+    }
+
+    pub fn on_event(self, from: &str, to: &str, event: &str) -> Self {
+        self.transition(Some(from), to, StateTrigger::Event(event.to_owned()))
+    }
+
+    pub fn when<F>(self, from: &str, to: &str, condition: F) -> Self
+    where
+        F: Fn(&World, Entity) -> bool + Send + Sync + 'static,
+    {
+        self.transition(Some(from), to, StateTrigger::Condition(Arc::new(condition)))
+    }
+
+    pub fn after(self, from: &str, to: &str, duration: Duration) -> Self {
+        self.transition(Some(from), to, StateTrigger::After(duration))
+    }
+
+    pub fn build(self) -> StateMachine {
+        let current = self
+            .states
+            .iter()
+            .position(|s| s.name == self.initial)
+            .unwrap_or_default();
+        StateMachine {
+            states: self.states,
+            transitions: self.transitions,
+            current,
+            elapsed: Duration::ZERO,
+            pending_events: VecDeque::new(),
+            entered: false,
+        }
+    }
+}
+
+fn queue_state_machine_events(
+    mut events: MessageReader<StateMachineEvent>,
+    mut query: Query<&mut StateMachine>,
+) {
+    for event in events.read() {
+        if let Ok(mut state_machine) = query.get_mut(event.entity) {
+            state_machine.send(event.event.clone());
+        } else {
+            warn!("State machine event for unknown entity {:?}", event.entity);
+        }
+    }
+}
+
+fn update_state_machines(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+
+    let mut query = world.query::<(Entity, &StateMachine)>();
+    let fired: Vec<(Entity, Option<usize>)> = query
+        .iter(world)
+        .map(|(entity, state_machine)| (entity, state_machine.find_transition(world, entity)))
+        .collect();
+
+    let mut actions: Vec<(Entity, StateAction)> = vec![];
+    let mut changes: Vec<StateChanged> = vec![];
+    for (entity, transition) in fired {
+        let Some(mut state_machine) = world.get_mut::<StateMachine>(entity) else {
+            continue;
+        };
+
+        // Run enter actions of the initial state once
+        if !state_machine.entered {
+            state_machine.entered = true;
+            let current = state_machine.current;
+            actions.extend(
+                state_machine.states[current]
+                    .on_enter
+                    .iter()
+                    .map(|a| (entity, a.clone())),
+            );
+        }
+
+        match transition {
+            Some(index) => {
+                let transition = state_machine.transitions[index].clone();
+                let Some(next) = state_machine.state_index(&transition.to) else {
+                    continue;
+                };
+                let prev = state_machine.current;
+                actions.extend(
+                    state_machine.states[prev]
+                        .on_exit
+                        .iter()
+                        .map(|a| (entity, a.clone())),
+                );
+                actions.extend(
+                    state_machine.states[next]
+                        .on_enter
+                        .iter()
+                        .map(|a| (entity, a.clone())),
+                );
+                changes.push(StateChanged {
+                    entity,
+                    from: state_machine.states[prev].name.clone(),
+                    to: transition.to.clone(),
+                });
+                state_machine.current = next;
+                state_machine.elapsed = Duration::ZERO;
+            }
+            None => {
+                state_machine.elapsed += delta;
+            }
+        }
+        // Events not consumed by a transition in this frame are dropped
+        state_machine.pending_events.clear();
+    }
+
+    let mut commands = world.commands();
+    for (entity, action) in actions {
+        action(&mut commands, entity);
+    }
+    world.flush();
+
+    for change in changes {
+        trace!(
+            "State machine {:?}: {} -> {}",
+            change.entity,
+            change.from,
+            change.to
+        );
+        world.write_message(change);
+    }
+}
@@ -0,0 +1,644 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{camera::primitives::MeshAabb, prelude::*};
+
+use crate::{
+    AimRay, AimRayPath, AimRayPlugin, AimRayTarget, AssetUnits, AssetUnitsPlugin, Chart, ChartKind,
+    ChartMark, ChartPlugin, ChartRibbon, ChartSeries, EntityLabel, EntityLabelPlugin,
+    EntityLabelText, ExplodedPart, ExplodedView, ExplodedViewPlugin, LabelOcclusion, Localization,
+    LocalizationPlugin, LocalizedText, Measurement, MeasurementLabel, MeasurementPlugin,
+    MeasurementValue, MiniMap, MiniMapCamera, MiniMapDisplay, MiniMapIcon, MiniMapPlugin,
+    PaletteRole, SourceUnits, StateMachine, StateMachineEvent, StateMachinePlugin, StringTable,
+    TextDirection, ThemedBackground, TransformInterpolation, TransformInterpolationPlugin,
+    UiPalette, UiTheme, UiThemePlugin,
+};
+
+#[derive(Component)]
+struct Opened;
+
+fn test_app() -> App {
+    let mut app = App::new();
+    app.init_resource::<Time>().add_plugins(StateMachinePlugin);
+    app
+}
+
+fn door() -> StateMachine {
+    StateMachine::builder("closed")
+        .on_event("closed", "open", "toggle")
+        .on_event("open", "closed", "toggle")
+        .on_enter("open", |commands, entity| {
+            commands.entity(entity).insert(Opened);
+        })
+        .on_exit("open", |commands, entity| {
+            commands.entity(entity).remove::<Opened>();
+        })
+        .build()
+}
+
+#[test]
+fn state_machine_event_transition() {
+    let mut app = test_app();
+    let entity = app.world_mut().spawn(door()).id();
+
+    app.update();
+    assert!(app
+        .world()
+        .get::<StateMachine>(entity)
+        .unwrap()
+        .is_in("closed"));
+
+    app.world_mut().write_message(StateMachineEvent {
+        entity,
+        event: "toggle".to_owned(),
+    });
+    app.update();
+    assert!(app
+        .world()
+        .get::<StateMachine>(entity)
+        .unwrap()
+        .is_in("open"));
+    assert!(app.world().get::<Opened>(entity).is_some());
+
+    app.world_mut()
+        .get_mut::<StateMachine>(entity)
+        .unwrap()
+        .send("toggle");
+    app.update();
+    assert!(app
+        .world()
+        .get::<StateMachine>(entity)
+        .unwrap()
+        .is_in("closed"));
+    assert!(app.world().get::<Opened>(entity).is_none());
+}
+
+#[test]
+fn state_machine_condition_and_timer() {
+    let mut app = test_app();
+    let entity = app
+        .world_mut()
+        .spawn(
+            StateMachine::builder("idle")
+                .when("idle", "active", |world, entity| {
+                    world.get::<Opened>(entity).is_some()
+                })
+                .after("active", "idle", Duration::ZERO)
+                .build(),
+        )
+        .id();
+
+    app.update();
+    assert!(app
+        .world()
+        .get::<StateMachine>(entity)
+        .unwrap()
+        .is_in("idle"));
+
+    app.world_mut().entity_mut(entity).insert(Opened);
+    app.update();
+    assert!(app
+        .world()
+        .get::<StateMachine>(entity)
+        .unwrap()
+        .is_in("active"));
+
+    app.world_mut().entity_mut(entity).remove::<Opened>();
+    app.update();
+    assert!(app
+        .world()
+        .get::<StateMachine>(entity)
+        .unwrap()
+        .is_in("idle"));
+}
+
+#[test]
+fn localization_lookup_and_fallback() {
+    let en =
+        StringTable::from_json(r#"{ "menu": { "start": "Start", "greet": "Hello, {name}" } }"#)
+            .unwrap();
+    let ko =
+        StringTable::from_ftl("# Korean\nmenu.start = 시작\nmenu.help =\n    첫 줄\n    둘째 줄\n")
+            .unwrap();
+
+    let mut localization = Localization::new("en");
+    localization.add_table("en", en);
+    localization.add_table("ko", ko);
+
+    assert_eq!(localization.get("menu.start"), "Start");
+    assert_eq!(
+        localization.format("menu.greet", &[("name", "XRDS")]),
+        "Hello, XRDS"
+    );
+
+    localization.set_language("ko");
+    assert_eq!(localization.get("menu.start"), "시작");
+    assert_eq!(localization.get("menu.help"), "첫 줄\n둘째 줄");
+    assert_eq!(localization.get("menu.greet"), "Hello, {name}");
+    assert_eq!(localization.get("missing.key"), "missing.key");
+
+    localization.set_language("ar-EG");
+    assert_eq!(localization.direction(), TextDirection::RightToLeft);
+}
+
+#[test]
+fn localized_text_font_follows_language() {
+    let mut app = App::new();
+    app.add_plugins(LocalizationPlugin);
+    let korean = Handle::<Font>::Uuid(bevy::asset::uuid::Uuid::from_u128(1), default());
+    {
+        let mut localization = app.world_mut().resource_mut::<Localization>();
+        localization.add_table("ko", StringTable::from_ftl("menu.start = 시작\n").unwrap());
+        localization.set_font("ko", korean.clone());
+    }
+    let entity = app
+        .world_mut()
+        .spawn((
+            LocalizedText {
+                key: "menu.start".to_owned(),
+                args: vec![],
+            },
+            TextFont::default(),
+        ))
+        .id();
+    let font = |app: &App| app.world().get::<TextFont>(entity).unwrap().font.clone();
+
+    app.update();
+    assert_eq!(font(&app), Handle::default());
+
+    app.world_mut()
+        .resource_mut::<Localization>()
+        .set_language("ko");
+    app.update();
+    assert_eq!(font(&app), korean);
+
+    // Back to a language without its own font
+    app.world_mut()
+        .resource_mut::<Localization>()
+        .set_language("en");
+    app.update();
+    assert_eq!(font(&app), Handle::default());
+}
+
+#[test]
+fn high_contrast_theme_updates_colors() {
+    let mut app = App::new();
+    app.add_plugins(UiThemePlugin);
+    let entity = app
+        .world_mut()
+        .spawn(ThemedBackground(PaletteRole::Background))
+        .id();
+
+    app.update();
+    let background = app.world().get::<BackgroundColor>(entity).unwrap().0;
+    assert_eq!(background, UiPalette::DEFAULT.background);
+
+    app.world_mut()
+        .resource_mut::<UiTheme>()
+        .set_high_contrast(true);
+    app.update();
+    let background = app.world().get::<BackgroundColor>(entity).unwrap().0;
+    assert_eq!(background, UiPalette::HIGH_CONTRAST.background);
+}
+
+#[test]
+fn transform_interpolates_between_fixed_steps() {
+    let mut app = App::new();
+    app.add_plugins((bevy::time::TimePlugin, TransformInterpolationPlugin))
+        .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(100)))
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(25),
+        ))
+        .add_systems(FixedUpdate, |mut query: Query<&mut Transform>| {
+            for mut transform in query.iter_mut() {
+                transform.translation.x += 1.0;
+            }
+        });
+    let entity = app
+        .world_mut()
+        .spawn(TransformInterpolation::default())
+        .id();
+
+    let mut fractional = false;
+    for _ in 0..40 {
+        app.update();
+        let interpolation = *app.world().get::<TransformInterpolation>(entity).unwrap();
+        let x = app.world().get::<Transform>(entity).unwrap().translation.x;
+        let Some(simulated) = interpolation.simulated() else {
+            continue;
+        };
+        // Rendered between the previous and the last simulation step
+        assert!(x <= simulated.translation.x && x >= simulated.translation.x - 1.0);
+        fractional |= x.fract() != 0.0;
+    }
+    assert!(fractional);
+
+    // Interpolation does not feed back into the simulation
+    let steps = app.world().resource::<Time<Fixed>>().elapsed().as_millis() / 100;
+    let interpolation = app.world().get::<TransformInterpolation>(entity).unwrap();
+    assert_eq!(
+        interpolation.simulated().unwrap().translation.x,
+        steps as f32
+    );
+}
+
+#[test]
+fn scenes_are_scaled_to_meters() {
+    let mut units = AssetUnits::default();
+    units
+        .declare("models/", xrds_core::LengthUnit::Centimeters)
+        .declare("models/cad/", xrds_core::LengthUnit::Millimeters);
+    assert_eq!(
+        units.unit_for("models/cad/engine.glb"),
+        xrds_core::LengthUnit::Millimeters
+    );
+    assert_eq!(
+        units.unit_for("models/chair.glb"),
+        xrds_core::LengthUnit::Centimeters
+    );
+    assert_eq!(
+        units.unit_for("props/box.glb"),
+        xrds_core::LengthUnit::Meters
+    );
+
+    let mut app = App::new();
+    app.add_plugins(AssetUnitsPlugin);
+    app.world_mut().resource_mut::<AssetUnits>().default = xrds_core::LengthUnit::Centimeters;
+    let centimeters = app
+        .world_mut()
+        .spawn((
+            SceneRoot::default(),
+            Transform::from_scale(Vec3::splat(2.0)),
+        ))
+        .id();
+    let feet = app
+        .world_mut()
+        .spawn((
+            SceneRoot::default(),
+            SourceUnits(xrds_core::LengthUnit::Feet),
+        ))
+        .id();
+
+    app.update();
+    app.update();
+    let scale = |entity| app.world().get::<Transform>(entity).unwrap().scale;
+    assert_eq!(scale(centimeters), Vec3::splat(0.02));
+    assert_eq!(scale(feet), Vec3::splat(0.3048));
+}
+
+#[test]
+fn aim_ray_ends_at_target_or_floor() {
+    let ray = AimRay::straight(5.0);
+    let path = AimRayPath::compute(&ray, Vec3::ZERO, Dir3::NEG_Z, None);
+    assert_eq!(path.points, vec![Vec3::ZERO, Vec3::new(0.0, 0.0, -5.0)]);
+    assert_eq!(path.hit, None);
+
+    let target = AimRayTarget {
+        point: Vec3::new(0.0, 0.0, -2.0),
+        normal: Vec3::Z,
+    };
+    let path = AimRayPath::compute(&ray, Vec3::ZERO, Dir3::NEG_Z, Some(&target));
+    assert_eq!(path.points[1], target.point);
+    assert_eq!(path.hit, Some(target));
+
+    // Targets out of reach are ignored
+    let far = AimRayTarget {
+        point: Vec3::new(0.0, 0.0, -8.0),
+        normal: Vec3::Z,
+    };
+    let path = AimRayPath::compute(&ray, Vec3::ZERO, Dir3::NEG_Z, Some(&far));
+    assert_eq!(path.hit, None);
+
+    // An arc thrown forward from 1.5 m lands on the floor in front
+    let path = AimRayPath::compute(
+        &AimRay::arc(5.0),
+        Vec3::new(0.0, 1.5, 0.0),
+        Dir3::new(Vec3::new(0.0, 1.0, -1.0)).unwrap(),
+        None,
+    );
+    let hit = path.hit.expect("arc should land");
+    assert!(hit.point.y.abs() < 1e-4);
+    assert!(hit.point.z < -1.0);
+    assert_eq!(hit.normal, Vec3::Y);
+    assert_eq!(path.points.last(), Some(&hit.point));
+
+    let mut app = App::new();
+    app.add_plugins(AimRayPlugin);
+    let entity = app
+        .world_mut()
+        .spawn((ray, GlobalTransform::from_xyz(1.0, 0.0, 0.0)))
+        .id();
+    app.update();
+    let path = app.world().get::<AimRayPath>(entity).unwrap();
+    assert_eq!(path.points[1], Vec3::new(1.0, 0.0, -5.0));
+}
+
+#[test]
+fn measurements_in_world_space() {
+    let distance = Measurement::distance().with_points([
+        Vec3::ZERO,
+        Vec3::new(3.0, 0.0, 0.0),
+        Vec3::new(3.0, 4.0, 0.0),
+    ]);
+    assert_eq!(
+        distance.value(),
+        Some(MeasurementValue::Distance(xrds_core::Meters(7.0)))
+    );
+    assert_eq!(distance.value().unwrap().to_string(), "7.00 m");
+
+    let mut angle = Measurement::angle().with_points([Vec3::X, Vec3::ZERO]);
+    assert_eq!(angle.value(), None);
+    angle.push(Vec3::Y);
+    assert_eq!(angle.value().unwrap().to_string(), "90.0°");
+    // The third point moves instead of adding a fourth
+    angle.push(Vec3::new(-1.0, 0.0, 0.0));
+    assert_eq!(angle.points.len(), 3);
+    assert_eq!(angle.value().unwrap().to_string(), "180.0°");
+
+    // A 2 m by 0.5 m rectangle on the floor, away from the origin
+    let area = Measurement::area().with_points([
+        Vec3::new(1.0, 0.0, 1.0),
+        Vec3::new(3.0, 0.0, 1.0),
+        Vec3::new(3.0, 0.0, 1.5),
+        Vec3::new(1.0, 0.0, 1.5),
+    ]);
+    let Some(MeasurementValue::Area(square_meters)) = area.value() else {
+        panic!("area should have a value");
+    };
+    assert!((square_meters - 1.0).abs() < 1e-5);
+    assert_eq!(
+        Measurement::distance()
+            .with_points([Vec3::ZERO, Vec3::new(0.0, 0.0, 0.25)])
+            .value()
+            .unwrap()
+            .to_string(),
+        "25.0 cm"
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MeasurementPlugin);
+    let measurement = app.world_mut().spawn(distance).id();
+    app.update();
+    app.update();
+    let mut labels = app.world_mut().query::<(&MeasurementLabel, &Text)>();
+    let (label, text) = labels.single(app.world()).unwrap();
+    assert_eq!(label.measurement, measurement);
+    assert_eq!(text.0, "7.00 m");
+
+    app.world_mut().despawn(measurement);
+    app.update();
+    assert_eq!(labels.iter(app.world()).count(), 0);
+}
+
+#[test]
+fn exploded_view_moves_parts_apart() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .add_plugins((TransformPlugin, ExplodedViewPlugin));
+    let view = app.world_mut().spawn(ExplodedView::new(2.0)).id();
+    // CAD exports wrap the assembly in a single root node
+    let root = app
+        .world_mut()
+        .spawn((Transform::from_scale(Vec3::splat(0.5)), ChildOf(view)))
+        .id();
+    let parts = [Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)].map(|translation| {
+        app.world_mut()
+            .spawn((Transform::from_translation(translation), ChildOf(root)))
+            .id()
+    });
+    app.update();
+    app.update();
+
+    let part = *app.world().get::<ExplodedPart>(parts[0]).unwrap();
+    assert_eq!(part.view, view);
+    assert_eq!(part.direction, Vec3::NEG_X);
+
+    app.world_mut()
+        .get_mut::<ExplodedView>(view)
+        .unwrap()
+        .set(0.5);
+    app.update();
+    // 1 m in world space is 2 units below the scaled root
+    let translations = parts.map(|part| app.world().get::<Transform>(part).unwrap().translation);
+    assert_eq!(
+        translations,
+        [Vec3::new(-3.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0)]
+    );
+    assert!(app.world().get::<ChildOf>(parts[0]).unwrap().parent() == root);
+
+    app.world_mut()
+        .get_mut::<ExplodedView>(view)
+        .unwrap()
+        .set(0.0);
+    app.update();
+    assert_eq!(
+        app.world().get::<Transform>(parts[1]).unwrap().translation,
+        Vec3::X
+    );
+}
+
+#[test]
+fn mini_map_follows_player() {
+    let mut app = App::new();
+    app.add_plugins((TransformPlugin, MiniMapPlugin))
+        .insert_resource(Assets::<Image>::default())
+        .insert_resource(Assets::<Mesh>::default())
+        .insert_resource(Assets::<StandardMaterial>::default());
+    let player = app
+        .world_mut()
+        .spawn((Transform::from_xyz(3.0, 1.6, -2.0), MiniMapIcon::player()))
+        .id();
+    let wrist = app.world_mut().spawn(Transform::default()).id();
+    let map = app
+        .world_mut()
+        .spawn(
+            MiniMap::new(20.0)
+                .following(player)
+                .on_panel(wrist, 0.1, Transform::default()),
+        )
+        .id();
+    app.update();
+    app.update();
+
+    assert!(app.world().get::<MiniMap>(map).unwrap().image().is_some());
+    let mut cameras = app.world_mut().query::<(&MiniMapCamera, &Transform)>();
+    let (camera, transform) = cameras.single(app.world()).unwrap();
+    assert_eq!(camera.map, map);
+    assert_eq!(transform.translation.xz(), Vec2::new(3.0, -2.0));
+    assert!(transform.forward().dot(Vec3::NEG_Y) > 0.999);
+
+    let mut displays = app.world_mut().query::<(&MiniMapDisplay, &ChildOf)>();
+    let (_, child_of) = displays.single(app.world()).unwrap();
+    assert_eq!(child_of.parent(), wrist);
+
+    app.world_mut().despawn(map);
+    app.update();
+    assert_eq!(cameras.iter(app.world()).count(), 0);
+    assert_eq!(displays.iter(app.world()).count(), 0);
+}
+
+#[test]
+fn entity_labels_fade_when_occluded_and_declutter() {
+    let mut app = App::new();
+    app.add_plugins((TransformPlugin, EntityLabelPlugin))
+        .init_resource::<Assets<Mesh>>();
+    let mut camera = Camera::default();
+    camera.computed.target_info = Some(bevy::camera::RenderTargetInfo {
+        physical_size: UVec2::new(800, 600),
+        scale_factor: 1.0,
+    });
+    camera.computed.clip_from_view =
+        Mat4::perspective_infinite_reverse_rh(std::f32::consts::FRAC_PI_4, 800.0 / 600.0, 0.1);
+    app.world_mut().spawn((
+        camera,
+        Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+
+    // A wall between the camera and the pump
+    let cube = Cuboid::new(1.0, 1.0, 0.1).mesh().build();
+    let aabb = cube.compute_aabb().unwrap();
+    let cube = app.world_mut().resource_mut::<Assets<Mesh>>().add(cube);
+    app.world_mut().spawn((
+        Mesh3d(cube),
+        aabb,
+        Transform::from_xyz(1.0, 0.0, 2.5),
+        Visibility::Visible,
+        InheritedVisibility::VISIBLE,
+    ));
+    let pump = app
+        .world_mut()
+        .spawn((Transform::from_xyz(2.0, 0.0, 0.0), EntityLabel::new("Pump")))
+        .id();
+    let valve = app
+        .world_mut()
+        .spawn((
+            Transform::from_xyz(2.0, 0.0, 0.0),
+            EntityLabel::new("Valve").with_occlusion(LabelOcclusion::Hide),
+        ))
+        .id();
+
+    // Five labels on the same spot, the first four are stacked
+    let important = app
+        .world_mut()
+        .spawn((
+            Transform::default(),
+            EntityLabel::new("Tank").with_priority(1),
+        ))
+        .id();
+    for index in 0..4 {
+        app.world_mut().spawn((
+            Transform::default(),
+            EntityLabel::new(format!("Sensor {index}")),
+        ));
+    }
+    app.update();
+    app.update();
+
+    let mut texts = app
+        .world_mut()
+        .query::<(&EntityLabelText, &TextColor, &Node, &Visibility)>();
+    let texts = texts
+        .iter(app.world())
+        .map(|(label, color, node, visibility)| {
+            (label.target, (*label, color.0, node.top, *visibility))
+        })
+        .collect::<HashMap<_, _>>();
+    assert_eq!(texts.len(), 7);
+
+    let (label, color, _, visibility) = texts[&pump];
+    assert!(label.occluded);
+    assert!((color.alpha() - 0.3).abs() < 1e-5);
+    assert_eq!(visibility, Visibility::Inherited);
+    let (label, _, _, visibility) = texts[&valve];
+    assert!(label.occluded);
+    assert_eq!(visibility, Visibility::Hidden);
+
+    let (label, color, top, visibility) = texts[&important];
+    assert!(!label.occluded && !label.decluttered);
+    assert_eq!(color.alpha(), 1.0);
+    assert_eq!(visibility, Visibility::Inherited);
+    let mut tops = vec![];
+    let mut decluttered = 0;
+    for (label, _, other_top, visibility) in texts.values() {
+        if label.target == pump || label.target == valve || label.target == important {
+            continue;
+        }
+        if label.decluttered {
+            decluttered += 1;
+            assert_eq!(*visibility, Visibility::Hidden);
+        } else {
+            let (Val::Px(top), Val::Px(other_top)) = (top, *other_top) else {
+                panic!("labels should be positioned in pixels");
+            };
+            // Moved above the label with the higher priority
+            assert!(other_top < top);
+            tops.push(other_top);
+        }
+    }
+    assert_eq!(decluttered, 1);
+    tops.sort_by(f32::total_cmp);
+    tops.dedup();
+    assert_eq!(tops.len(), 3);
+}
+
+#[test]
+fn charts_turn_series_into_marks() {
+    let mut app = App::new();
+    app.add_plugins((TransformPlugin, ChartPlugin))
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<StandardMaterial>>();
+    let chart = app
+        .world_mut()
+        .spawn(
+            Chart::bar()
+                .with_size(Vec3::new(3.0, 1.0, 2.0))
+                .with_categories(["Mon", "Tue", "Wed"])
+                .with_series(ChartSeries::new("Line 1", Color::WHITE).with_values([1.0, 2.0, 4.0]))
+                .with_series(ChartSeries::new("Line 2", Color::BLACK).with_values([0.0, 3.0, 2.0])),
+        )
+        .id();
+    app.update();
+
+    let mut marks = app
+        .world_mut()
+        .query::<(Entity, &ChartMark, &Transform, &ChildOf)>();
+    let bars = marks
+        .iter(app.world())
+        .map(|(entity, mark, transform, child_of)| {
+            assert_eq!(child_of.parent(), chart);
+            (*mark, (entity, *transform))
+        })
+        .collect::<HashMap<_, _>>();
+    assert_eq!(bars.len(), 6);
+    // The highest value fills the plot box, bars stand on the floor
+    let (tallest, transform) = bars[&ChartMark {
+        series: 0,
+        index: 2,
+    }];
+    assert!((transform.scale.y - 1.0).abs() < 1e-5);
+    assert!((transform.translation - Vec3::new(2.5, 0.5, 0.5)).length() < 1e-5);
+
+    let mut labels = app.world_mut().query::<&EntityLabel>();
+    let ticks = labels
+        .iter(app.world())
+        .map(|label| label.text.clone())
+        .collect::<Vec<_>>();
+    for tick in ["0", "1", "2", "4", "Tue", "Line 2"] {
+        assert!(ticks.iter().any(|text| text == tick), "{tick} in {ticks:?}");
+    }
+
+    // New values move the existing bars, the highest bar is now 3
+    app.world_mut().get_mut::<Chart>(chart).unwrap().series[0].points[2].y = 2.0;
+    app.update();
+    let transform = app.world().get::<Transform>(tallest).unwrap();
+    assert!((transform.scale.y - 2.0 / 3.0).abs() < 1e-5);
+    assert_eq!(marks.iter(app.world()).count(), 6);
+
+    app.world_mut().get_mut::<Chart>(chart).unwrap().kind = ChartKind::Line;
+    app.update();
+    assert_eq!(marks.iter(app.world()).count(), 0);
+    let mut ribbons = app.world_mut().query::<(&ChartRibbon, &Mesh3d)>();
+    assert_eq!(ribbons.iter(app.world()).count(), 2);
+    let (_, mesh) = ribbons.iter(app.world()).next().unwrap();
+    let meshes = app.world().resource::<Assets<Mesh>>();
+    assert_eq!(meshes.get(&mesh.0).unwrap().count_vertices(), 6);
+}
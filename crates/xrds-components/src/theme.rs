@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+/// Colors used by XRDS UI components
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiPalette {
+    pub background: Color,
+    pub surface: Color,
+    pub text: Color,
+    pub text_secondary: Color,
+    pub accent: Color,
+    pub focus: Color,
+    pub disabled: Color,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaletteRole {
+    Background,
+    Surface,
+    Text,
+    TextSecondary,
+    Accent,
+    Focus,
+    Disabled,
+}
+
+/// Active UI palette. Switch with [`UiTheme::set_high_contrast`]
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct UiTheme {
+    palette: UiPalette,
+    high_contrast: bool,
+}
+
+/// Background color taken from the active palette
+#[derive(Component, Debug, Clone, Copy)]
+#[require(BackgroundColor)]
+pub struct ThemedBackground(pub PaletteRole);
+
+/// Text color taken from the active palette
+#[derive(Component, Debug, Clone, Copy)]
+#[require(TextColor)]
+pub struct ThemedText(pub PaletteRole);
+
+#[derive(Debug, Default)]
+pub struct UiThemePlugin;
+
+impl Plugin for UiThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiTheme>()
+            .add_systems(PostUpdate, apply_ui_theme);
+    }
+}
+
+impl UiPalette {
+    pub const DEFAULT: Self = Self {
+        background: Color::srgba(0.08, 0.09, 0.11, 0.9),
+        surface: Color::srgba(0.16, 0.18, 0.21, 0.95),
+        text: Color::srgb(0.92, 0.93, 0.95),
+        text_secondary: Color::srgb(0.65, 0.68, 0.72),
+        accent: Color::srgb(0.26, 0.55, 0.96),
+        focus: Color::srgb(0.45, 0.72, 1.0),
+        disabled: Color::srgb(0.4, 0.42, 0.45),
+    };
+
+    /// Opaque black and white with yellow highlights (WCAG AAA contrast)
+    pub const HIGH_CONTRAST: Self = Self {
+        background: Color::BLACK,
+        surface: Color::BLACK,
+        text: Color::WHITE,
+        text_secondary: Color::WHITE,
+        accent: Color::srgb(1.0, 1.0, 0.0),
+        focus: Color::srgb(0.0, 1.0, 1.0),
+        disabled: Color::srgb(0.6, 0.6, 0.6),
+    };
+
+    pub fn color(&self, role: PaletteRole) -> Color {
+        match role {
+            PaletteRole::Background => self.background,
+            PaletteRole::Surface => self.surface,
+            PaletteRole::Text => self.text,
+            PaletteRole::TextSecondary => self.text_secondary,
+            PaletteRole::Accent => self.accent,
+            PaletteRole::Focus => self.focus,
+            PaletteRole::Disabled => self.disabled,
+        }
+    }
+}
+
+impl Default for UiPalette {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        Self {
+            palette: UiPalette::DEFAULT,
+            high_contrast: false,
+        }
+    }
+}
+
+impl UiTheme {
+    pub fn new(palette: UiPalette) -> Self {
+        Self {
+            palette,
+            high_contrast: false,
+        }
+    }
+
+    pub fn palette(&self) -> &UiPalette {
+        if self.high_contrast {
+            &UiPalette::HIGH_CONTRAST
+        } else {
+            &self.palette
+        }
+    }
+
+    /// Replace the regular palette. The high contrast palette is not affected
+    pub fn set_palette(&mut self, palette: UiPalette) {
+        self.palette = palette;
+    }
+
+    pub fn is_high_contrast(&self) -> bool {
+        self.high_contrast
+    }
+
+    pub fn set_high_contrast(&mut self, enabled: bool) {
+        self.high_contrast = enabled;
+    }
+
+    pub fn color(&self, role: PaletteRole) -> Color {
+        self.palette().color(role)
+    }
+}
+
+fn apply_ui_theme(
+    theme: Res<UiTheme>,
+    mut backgrounds: Query<(Ref<ThemedBackground>, &mut BackgroundColor)>,
+    mut texts: Query<(Ref<ThemedText>, &mut TextColor)>,
+) {
+    for (themed, mut background) in backgrounds.iter_mut() {
+        if theme.is_changed() || themed.is_changed() {
+            background.0 = theme.color(themed.0);
+        }
+    }
+    for (themed, mut text) in texts.iter_mut() {
+        if theme.is_changed() || themed.is_changed() {
+            text.0 = theme.color(themed.0);
+        }
+    }
+}
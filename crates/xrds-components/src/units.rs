@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use xrds_core::LengthUnit;
+
+/// Length units of scene assets, declared by asset path prefix
+///
+/// Scenes are scaled to meters when they are spawned, e.g. after
+/// `units.declare("models/cad/", LengthUnit::Millimeters)` every scene loaded
+/// from `models/cad/` is scaled by 0.001.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct AssetUnits {
+    /// Unit of assets without a matching declaration
+    pub default: LengthUnit,
+    declarations: Vec<(String, LengthUnit)>,
+}
+
+/// Unit of one scene, overrides [`AssetUnits`]
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct SourceUnits(pub LengthUnit);
+
+/// Marks a scene root whose transform already scales it to meters
+///
+/// Added when the scene is scaled, and kept in world files so scenes loaded
+/// again are not scaled twice.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScaledToMeters;
+
+#[derive(Debug, Default)]
+pub struct AssetUnitsPlugin;
+
+impl AssetUnits {
+    pub fn declare(&mut self, path_prefix: impl Into<String>, unit: LengthUnit) -> &mut Self {
+        self.declarations.push((path_prefix.into(), unit));
+        self
+    }
+
+    /// Unit of the longest matching prefix
+    pub fn unit_for(&self, path: &str) -> LengthUnit {
+        self.declarations
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, unit)| *unit)
+            .unwrap_or(self.default)
+    }
+}
+
+impl Plugin for AssetUnitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetUnits>()
+            .add_systems(PreUpdate, scale_scenes_to_meters);
+    }
+}
+
+/// Scale the root transform once, when the scene root is added
+fn scale_scenes_to_meters(
+    mut commands: Commands,
+    units: Res<AssetUnits>,
+    mut scenes: Query<
+        (Entity, &SceneRoot, Option<&SourceUnits>, &mut Transform),
+        (Added<SceneRoot>, Without<ScaledToMeters>),
+    >,
+) {
+    for (entity, scene, source, mut transform) in scenes.iter_mut() {
+        let unit = match source {
+            Some(SourceUnits(unit)) => *unit,
+            None => scene
+                .0
+                .path()
+                .map(|path| units.unit_for(&path.path().to_string_lossy().replace('\\', "/")))
+                .unwrap_or(units.default),
+        };
+        let scale = unit.meters_per_unit();
+        if scale != 1.0 {
+            transform.scale *= scale;
+        }
+        commands.entity(entity).insert(ScaledToMeters);
+    }
+}
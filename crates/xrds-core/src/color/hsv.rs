@@ -0,0 +1,83 @@
+use crate::{linear_to_srgb, srgb_to_linear, LinearRgba};
+
+/// Hue, saturation and value of the sRGB encoded color, as used by color pickers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    /// Degrees in 0..360
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+    pub alpha: f32,
+}
+
+impl Hsv {
+    pub fn new(hue: f32, saturation: f32, value: f32) -> Self {
+        Self {
+            hue: hue.rem_euclid(360.0),
+            saturation,
+            value,
+            alpha: 1.0,
+        }
+    }
+
+    pub fn with_hue(mut self, hue: f32) -> Self {
+        self.hue = hue.rem_euclid(360.0);
+        self
+    }
+
+    /// Rotate the hue by `degrees`
+    pub fn shift_hue(self, degrees: f32) -> Self {
+        self.with_hue(self.hue + degrees)
+    }
+}
+
+impl From<LinearRgba> for Hsv {
+    fn from(color: LinearRgba) -> Self {
+        let [r, g, b] = [color.r, color.g, color.b].map(|c| linear_to_srgb(c.clamp(0.0, 1.0)));
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        let hue = if delta <= f32::EPSILON {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max <= f32::EPSILON {
+            0.0
+        } else {
+            delta / max
+        };
+        Self {
+            hue,
+            saturation,
+            value: max,
+            alpha: color.a,
+        }
+    }
+}
+
+impl From<Hsv> for LinearRgba {
+    fn from(hsv: Hsv) -> Self {
+        let chroma = hsv.value * hsv.saturation;
+        let sector = hsv.hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = hsv.value - chroma;
+        LinearRgba::new(
+            srgb_to_linear(r + m),
+            srgb_to_linear(g + m),
+            srgb_to_linear(b + m),
+            hsv.alpha,
+        )
+    }
+}
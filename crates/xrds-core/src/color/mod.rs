@@ -0,0 +1,9 @@
+mod hsv;
+mod palette;
+mod rgba;
+mod temperature;
+
+pub use hsv::*;
+pub use palette::*;
+pub use rgba::*;
+pub use temperature::*;
@@ -0,0 +1,70 @@
+use crate::LinearRgba;
+
+/// Named set of colors shared by lights, materials and UI
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    colors: Vec<(String, LinearRgba)>,
+}
+
+impl Palette {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            colors: vec![],
+        }
+    }
+
+    /// Black, white, grays and the primary and secondary colors
+    pub fn basic() -> Self {
+        [
+            ("black", "#000000"),
+            ("dark_gray", "#404040"),
+            ("gray", "#808080"),
+            ("light_gray", "#C0C0C0"),
+            ("white", "#FFFFFF"),
+            ("red", "#FF0000"),
+            ("green", "#00FF00"),
+            ("blue", "#0000FF"),
+            ("yellow", "#FFFF00"),
+            ("cyan", "#00FFFF"),
+            ("magenta", "#FF00FF"),
+            ("orange", "#FF8000"),
+        ]
+        .into_iter()
+        .fold(Self::new("basic"), |palette, (name, hex)| {
+            palette.with(name, LinearRgba::from_hex(hex).unwrap())
+        })
+    }
+
+    /// Add or replace a color
+    pub fn with(mut self, name: impl Into<String>, color: LinearRgba) -> Self {
+        self.insert(name, color);
+        self
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, color: LinearRgba) {
+        let name = name.into();
+        match self.colors.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, c)) => *c = color,
+            None => self.colors.push((name, color)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<LinearRgba> {
+        self.colors.iter().find(|(n, _)| n == name).map(|(_, c)| *c)
+    }
+
+    /// Colors in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, LinearRgba)> {
+        self.colors.iter().map(|(n, c)| (n.as_str(), *c))
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+}
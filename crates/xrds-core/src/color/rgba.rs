@@ -0,0 +1,158 @@
+use glam::{Vec3, Vec4};
+
+/// Color with linear RGB channels and straight alpha
+///
+/// Lighting and blending work on linear values. Use the sRGB conversions for
+/// colors picked in image editors or written in hex.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearRgba {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+/// sRGB transfer function of one channel in 0..=1
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Inverse of [`srgb_to_linear`]
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl Default for LinearRgba {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+impl LinearRgba {
+    pub const WHITE: Self = Self::rgb(1.0, 1.0, 1.0);
+    pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+    pub const NONE: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+    pub const RED: Self = Self::rgb(1.0, 0.0, 0.0);
+    pub const GREEN: Self = Self::rgb(0.0, 1.0, 0.0);
+    pub const BLUE: Self = Self::rgb(0.0, 0.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(r, g, b, 1.0)
+    }
+
+    pub fn from_srgb(r: f32, g: f32, b: f32) -> Self {
+        Self::rgb(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
+    }
+
+    pub fn from_srgb_u8(r: u8, g: u8, b: u8) -> Self {
+        Self::from_srgba_u8(r, g, b, 255)
+    }
+
+    pub fn from_srgba_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        let c = |v: u8| srgb_to_linear(v as f32 / 255.0);
+        Self::new(c(r), c(g), c(b), a as f32 / 255.0)
+    }
+
+    /// Parse `RGB`, `RGBA`, `RRGGBB` or `RRGGBBAA` sRGB hex, with or without `#`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.is_ascii() {
+            return None;
+        }
+        let digits = match hex.len() {
+            3 | 4 => hex
+                .chars()
+                .map(|c| c.to_digit(16).map(|d| (d * 17) as u8))
+                .collect::<Option<Vec<_>>>()?,
+            6 | 8 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+                .collect::<Option<Vec<_>>>()?,
+            _ => return None,
+        };
+        let alpha = digits.get(3).copied().unwrap_or(255);
+        Some(Self::from_srgba_u8(digits[0], digits[1], digits[2], alpha))
+    }
+
+    /// sRGB channels and alpha in 0..=1
+    pub fn to_srgba(&self) -> [f32; 4] {
+        [
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        ]
+    }
+
+    pub fn to_srgba_u8(&self) -> [u8; 4] {
+        self.to_srgba()
+            .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// `#RRGGBB`, or `#RRGGBBAA` if not opaque
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.to_srgba_u8();
+        if a == 255 {
+            format!("#{:02X}{:02X}{:02X}", r, g, b)
+        } else {
+            format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+        }
+    }
+
+    /// Relative luminance (Rec. 709)
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn with_alpha(mut self, a: f32) -> Self {
+        self.a = a;
+        self
+    }
+
+    /// Multiply the color channels, e.g. by a light intensity
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self::new(self.r * factor, self.g * factor, self.b * factor, self.a)
+    }
+
+    pub fn lerp(&self, other: &LinearRgba, t: f32) -> Self {
+        Self::from(self.to_vec4().lerp(other.to_vec4(), t))
+    }
+
+    pub fn to_vec3(&self) -> Vec3 {
+        Vec3::new(self.r, self.g, self.b)
+    }
+
+    pub fn to_vec4(&self) -> Vec4 {
+        Vec4::new(self.r, self.g, self.b, self.a)
+    }
+}
+
+impl From<Vec4> for LinearRgba {
+    fn from(v: Vec4) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Vec3> for LinearRgba {
+    fn from(v: Vec3) -> Self {
+        Self::rgb(v.x, v.y, v.z)
+    }
+}
+
+impl From<LinearRgba> for Vec4 {
+    fn from(color: LinearRgba) -> Self {
+        color.to_vec4()
+    }
+}
@@ -0,0 +1,52 @@
+use crate::LinearRgba;
+
+/// Colors of common light sources
+pub struct LightColor;
+
+impl LightColor {
+    /// 1900 K
+    pub const CANDLE: LinearRgba = LinearRgba::rgb(1.0, 0.2918, 0.0222);
+    /// 2600 K
+    pub const TUNGSTEN_40W: LinearRgba = LinearRgba::rgb(1.0, 0.5583, 0.2747);
+    /// 2850 K
+    pub const TUNGSTEN_100W: LinearRgba = LinearRgba::rgb(1.0, 0.6724, 0.402);
+    /// 3200 K
+    pub const HALOGEN: LinearRgba = LinearRgba::rgb(1.0, 0.8796, 0.7454);
+    /// 5200 K
+    pub const CARBON_ARC: LinearRgba = LinearRgba::rgb(1.0, 0.956, 0.9047);
+    /// 5400 K
+    pub const HIGH_NOON_SUN: LinearRgba = LinearRgba::rgb(1.0, 1.0, 0.9647);
+    /// 6000 K
+    pub const DIRECT_SUNLIGHT: LinearRgba = LinearRgba::WHITE;
+    /// 7000 K
+    pub const OVERCAST_SKY: LinearRgba = LinearRgba::rgb(0.5841, 0.7605, 1.0);
+    /// 20000 K
+    pub const CLEAR_BLUE_SKY: LinearRgba = LinearRgba::rgb(0.0513, 0.3325, 1.0);
+}
+
+impl LinearRgba {
+    /// Color of a black body at `kelvin`, normalized so the largest channel is 1
+    ///
+    /// Uses Krystek's approximation of the Planckian locus, valid from 1000 K
+    /// to 15000 K. Temperatures outside are clamped. 6500 K is close to white,
+    /// slightly purple because D65 lies just off the Planckian locus.
+    pub fn from_temperature(kelvin: f32) -> Self {
+        let t = kelvin.clamp(1000.0, 15000.0) as f64;
+        let u = (0.860117757 + 1.54118254e-4 * t + 1.28641212e-7 * t * t)
+            / (1.0 + 8.42420235e-4 * t + 7.08145163e-7 * t * t);
+        let v = (0.317398726 + 4.22806245e-5 * t + 4.20481691e-8 * t * t)
+            / (1.0 - 2.89741816e-5 * t + 1.61456053e-7 * t * t);
+
+        // CIE 1960 uv to xy, then XYZ with Y = 1
+        let d = 2.0 * u - 8.0 * v + 4.0;
+        let (x, y) = (3.0 * u / d, 2.0 * v / d);
+        let (cx, cy, cz) = (x / y, 1.0, (1.0 - x - y) / y);
+
+        let r = 3.2404542 * cx - 1.5371385 * cy - 0.4985314 * cz;
+        let g = -0.9692660 * cx + 1.8760108 * cy + 0.0415560 * cz;
+        let b = 0.0556434 * cx - 0.2040259 * cy + 1.0572252 * cz;
+        let [r, g, b] = [r, g, b].map(|c| c.max(0.0));
+        let max = r.max(g).max(b);
+        Self::rgb((r / max) as f32, (g / max) as f32, (b / max) as f32)
+    }
+}
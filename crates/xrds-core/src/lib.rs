@@ -13,8 +13,15 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
+mod color;
+mod math;
 mod traits;
 mod types;
 
+pub use color::*;
+pub use math::*;
 pub use traits::*;
 pub use types::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,490 @@
+use glam::{Mat3, Mat4, Quat, Vec3, Vec4};
+
+use crate::Transform;
+
+/// Result of testing a volume against another volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Containment {
+    Outside,
+    Intersects,
+    Inside,
+}
+
+/// Plane of the points `p` with `normal.dot(p) + d == 0`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+/// Axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+/// Oriented bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obb {
+    pub center: Vec3,
+    pub half_extents: Vec3,
+    pub rotation: Quat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+/// Half line with a normalized direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    origin: Vec3,
+    direction: Vec3,
+}
+
+/// Convex volume bounded by six planes with normals pointing inside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far
+    pub planes: [Plane; 6],
+}
+
+impl Plane {
+    /// Returns `None` if `normal` is zero
+    pub fn new(normal: Vec3, d: f32) -> Option<Self> {
+        let length = normal.length();
+        (length > f32::EPSILON).then(|| Self {
+            normal: normal / length,
+            d: d / length,
+        })
+    }
+
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Option<Self> {
+        let normal = normal.try_normalize()?;
+        Some(Self {
+            normal,
+            d: -normal.dot(point),
+        })
+    }
+
+    /// Plane through three points, facing the side they appear counter-clockwise from
+    pub fn from_points(a: Vec3, b: Vec3, c: Vec3) -> Option<Self> {
+        Self::from_point_normal(a, (b - a).cross(c - a))
+    }
+
+    fn from_vec4(plane: Vec4) -> Option<Self> {
+        Self::new(plane.truncate(), plane.w)
+    }
+
+    /// Positive on the side the normal points to
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) + self.d
+    }
+
+    pub fn project_point(&self, point: Vec3) -> Vec3 {
+        point - self.normal * self.signed_distance(point)
+    }
+}
+
+impl Aabb {
+    /// The corners are sorted, so `a` and `b` can be any two opposite corners
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn from_center_half_extents(center: Vec3, half_extents: Vec3) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    /// Returns `None` if there are no points
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, p| aabb.expand_to(p)))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z),
+            Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z),
+            Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z),
+            Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z),
+            Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    pub fn expand_to(&self, point: Vec3) -> Self {
+        Self {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    pub fn merge(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Grown by `margin` on every side
+    pub fn inflate(&self, margin: f32) -> Self {
+        Self::new(
+            self.min - Vec3::splat(margin),
+            self.max + Vec3::splat(margin),
+        )
+    }
+
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        point.clamp(self.min, self.max)
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    pub fn contains_aabb(&self, other: &Aabb) -> bool {
+        other.min.cmpge(self.min).all() && other.max.cmple(self.max).all()
+    }
+
+    pub fn intersects_aabb(&self, other: &Aabb) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.closest_point(sphere.center)
+            .distance_squared(sphere.center)
+            <= sphere.radius * sphere.radius
+    }
+
+    /// Smallest axis aligned box around this box after transforming it
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Obb::from_aabb(self, transform).to_aabb()
+    }
+}
+
+impl Obb {
+    pub fn new(center: Vec3, half_extents: Vec3, rotation: Quat) -> Self {
+        Self {
+            center,
+            half_extents: half_extents.abs(),
+            rotation,
+        }
+    }
+
+    /// Box of `aabb`, given in the local space of `transform`, in parent space
+    pub fn from_aabb(aabb: &Aabb, transform: &Transform) -> Self {
+        Self::new(
+            transform.transform_point(aabb.center()),
+            aabb.half_extents() * transform.scale.abs(),
+            transform.rotation,
+        )
+    }
+
+    /// Local axes scaled by the half extents
+    fn axes(&self) -> [Vec3; 3] {
+        let basis = Mat3::from_quat(self.rotation);
+        [
+            basis.x_axis * self.half_extents.x,
+            basis.y_axis * self.half_extents.y,
+            basis.z_axis * self.half_extents.z,
+        ]
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let [x, y, z] = self.axes();
+        let c = self.center;
+        [
+            c - x - y - z,
+            c + x - y - z,
+            c - x + y - z,
+            c + x + y - z,
+            c - x - y + z,
+            c + x - y + z,
+            c - x + y + z,
+            c + x + y + z,
+        ]
+    }
+
+    pub fn to_aabb(&self) -> Aabb {
+        let [x, y, z] = self.axes();
+        let extents = x.abs() + y.abs() + z.abs();
+        Aabb::from_center_half_extents(self.center, extents)
+    }
+
+    /// Point in the box space, axis aligned and centered at the origin
+    fn local_point(&self, point: Vec3) -> Vec3 {
+        self.rotation.inverse() * (point - self.center)
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.local_point(point).abs().cmple(self.half_extents).all()
+    }
+
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        let local = self
+            .local_point(point)
+            .clamp(-self.half_extents, self.half_extents);
+        self.center + self.rotation * local
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.closest_point(sphere.center)
+            .distance_squared(sphere.center)
+            <= sphere.radius * sphere.radius
+    }
+
+    /// Separating axis test
+    pub fn intersects_obb(&self, other: &Obb) -> bool {
+        let a = Mat3::from_quat(self.rotation);
+        let b = Mat3::from_quat(other.rotation);
+        let a_axes = [a.x_axis, a.y_axis, a.z_axis];
+        let b_axes = [b.x_axis, b.y_axis, b.z_axis];
+        let offset = other.center - self.center;
+
+        let separated = |axis: Vec3| {
+            if axis.length_squared() < 1e-8 {
+                // Parallel edges, covered by the face axes
+                return false;
+            }
+            let radius = |axes: &[Vec3; 3], half: Vec3| {
+                half.x * axes[0].dot(axis).abs()
+                    + half.y * axes[1].dot(axis).abs()
+                    + half.z * axes[2].dot(axis).abs()
+            };
+            offset.dot(axis).abs()
+                > radius(&a_axes, self.half_extents) + radius(&b_axes, other.half_extents)
+        };
+
+        if a_axes
+            .iter()
+            .chain(b_axes.iter())
+            .any(|axis| separated(*axis))
+        {
+            return false;
+        }
+        !a_axes
+            .iter()
+            .any(|a| b_axes.iter().any(|b| separated(a.cross(*b))))
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.intersects_obb(&Obb::new(
+            aabb.center(),
+            aabb.half_extents(),
+            Quat::IDENTITY,
+        ))
+    }
+}
+
+impl From<Aabb> for Obb {
+    fn from(aabb: Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents(), Quat::IDENTITY)
+    }
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self {
+            center,
+            radius: radius.abs(),
+        }
+    }
+
+    /// Sphere around the box, not the smallest one in general
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents().length())
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    pub fn contains_sphere(&self, other: &Sphere) -> bool {
+        self.center.distance(other.center) + other.radius <= self.radius
+    }
+
+    pub fn intersects_sphere(&self, other: &Sphere) -> bool {
+        let radius = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radius * radius
+    }
+
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Self::new(
+            transform.transform_point(self.center),
+            self.radius * transform.scale.abs().max_element(),
+        )
+    }
+}
+
+impl Ray {
+    /// Returns `None` if `direction` is zero
+    pub fn new(origin: Vec3, direction: Vec3) -> Option<Self> {
+        Some(Self {
+            origin,
+            direction: direction.try_normalize()?,
+        })
+    }
+
+    pub fn origin(&self) -> Vec3 {
+        self.origin
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Distance to the plane, `None` if the ray is parallel or points away
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(self.direction);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let distance = -plane.signed_distance(self.origin) / denominator;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Distance to the first hit. 0 if the origin is inside the box
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inverse = self.direction.recip();
+        let t1 = (aabb.min - self.origin) * inverse;
+        let t2 = (aabb.max - self.origin) * inverse;
+        // NaN from 0 * inf is ignored by min and max
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element();
+        (near <= far).then_some(near)
+    }
+
+    pub fn intersect_obb(&self, obb: &Obb) -> Option<f32> {
+        let inverse = obb.rotation.inverse();
+        let local = Ray {
+            origin: inverse * (self.origin - obb.center),
+            direction: inverse * self.direction,
+        };
+        local.intersect_aabb(&Aabb::from_center_half_extents(
+            Vec3::ZERO,
+            obb.half_extents,
+        ))
+    }
+
+    /// Distance to the first hit. 0 if the origin is inside the sphere
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let offset = self.origin - sphere.center;
+        let b = offset.dot(self.direction);
+        let c = offset.length_squared() - sphere.radius * sphere.radius;
+        if c > 0.0 && b > 0.0 {
+            return None;
+        }
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        Some((-b - discriminant.sqrt()).max(0.0))
+    }
+
+    pub fn transformed(&self, transform: &Transform) -> Option<Self> {
+        Self::new(
+            transform.transform_point(self.origin),
+            transform.transform_vector(self.direction),
+        )
+    }
+}
+
+impl Frustum {
+    /// Frustum of a view projection matrix with depth in 0..1, e.g. `Mat4::perspective_rh`
+    pub fn from_view_projection(view_projection: Mat4) -> Option<Self> {
+        let m = view_projection.transpose();
+        let (r0, r1, r2, r3) = (m.x_axis, m.y_axis, m.z_axis, m.w_axis);
+        Some(Self {
+            planes: [
+                Plane::from_vec4(r3 + r0)?,
+                Plane::from_vec4(r3 - r0)?,
+                Plane::from_vec4(r3 + r1)?,
+                Plane::from_vec4(r3 - r1)?,
+                Plane::from_vec4(r2)?,
+                Plane::from_vec4(r3 - r2)?,
+            ],
+        })
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    pub fn classify_sphere(&self, sphere: &Sphere) -> Containment {
+        let mut result = Containment::Inside;
+        for plane in &self.planes {
+            let distance = plane.signed_distance(sphere.center);
+            if distance < -sphere.radius {
+                return Containment::Outside;
+            }
+            if distance < sphere.radius {
+                result = Containment::Intersects;
+            }
+        }
+        result
+    }
+
+    /// Conservative, boxes near frustum corners can be reported as intersecting
+    pub fn classify_aabb(&self, aabb: &Aabb) -> Containment {
+        let center = aabb.center();
+        let extents = aabb.half_extents();
+        let mut result = Containment::Inside;
+        for plane in &self.planes {
+            let distance = plane.signed_distance(center);
+            let radius = extents.dot(plane.normal.abs());
+            if distance < -radius {
+                return Containment::Outside;
+            }
+            if distance < radius {
+                result = Containment::Intersects;
+            }
+        }
+        result
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.classify_sphere(sphere) != Containment::Outside
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.classify_aabb(aabb) != Containment::Outside
+    }
+
+    pub fn intersects_obb(&self, obb: &Obb) -> bool {
+        let [x, y, z] = obb.axes();
+        self.planes.iter().all(|plane| {
+            let radius =
+                x.dot(plane.normal).abs() + y.dot(plane.normal).abs() + z.dot(plane.normal).abs();
+            plane.signed_distance(obb.center) >= -radius
+        })
+    }
+}
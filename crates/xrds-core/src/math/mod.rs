@@ -0,0 +1,9 @@
+mod bounds;
+mod transform;
+mod units;
+mod view_direction;
+
+pub use bounds::*;
+pub use transform::*;
+pub use units::*;
+pub use view_direction::*;
@@ -0,0 +1,218 @@
+use std::ops::Mul;
+
+use glam::{Affine3A, Mat3, Mat4, Quat, Vec3};
+
+/// Translation, rotation and scale of an object
+///
+/// Uses the OpenXR convention: right handed, +Y up and -Z forward.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn from_xyz(x: f32, y: f32, z: f32) -> Self {
+        Self::from_translation(Vec3::new(x, y, z))
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Decompose an affine matrix. Shear is lost
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Rotated so that forward points at `target`
+    pub fn looking_at(mut self, target: Vec3, up: Vec3) -> Self {
+        self.look_at(target, up);
+        self
+    }
+
+    /// Rotated so that forward points along `direction`
+    pub fn looking_to(mut self, direction: Vec3, up: Vec3) -> Self {
+        self.look_to(direction, up);
+        self
+    }
+
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        self.look_to(target - self.translation, up);
+    }
+
+    /// Keeps the current rotation if `direction` is zero. Falls back to
+    /// another up axis if `up` is parallel to `direction`
+    pub fn look_to(&mut self, direction: Vec3, up: Vec3) {
+        let Some(back) = (-direction).try_normalize() else {
+            return;
+        };
+        let up = up.try_normalize().unwrap_or(Vec3::Y);
+        let right = match up.cross(back).try_normalize() {
+            Some(right) => right,
+            None => back.any_orthonormal_vector(),
+        };
+        let up = back.cross(right);
+        self.rotation = Quat::from_mat3(&Mat3::from_cols(right, up, back));
+    }
+
+    pub fn to_matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    pub fn to_affine(&self) -> Affine3A {
+        Affine3A::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    /// Local +X axis in parent space
+    pub fn right(&self) -> Vec3 {
+        self.rotation * Vec3::X
+    }
+
+    /// Local +Y axis in parent space
+    pub fn up(&self) -> Vec3 {
+        self.rotation * Vec3::Y
+    }
+
+    /// Local -Z axis in parent space
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::NEG_Z
+    }
+
+    /// Local +Z axis in parent space
+    pub fn back(&self) -> Vec3 {
+        self.rotation * Vec3::Z
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.translation + self.rotation * (self.scale * point)
+    }
+
+    /// Rotate and scale `vector` without translating it
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotation * (self.scale * vector)
+    }
+
+    /// Exact for uniform scale. Non-uniform scale combined with rotation
+    /// cannot be inverted into a transform without shear
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let scale = self.scale.recip();
+        Self {
+            translation: rotation * (-self.translation) * scale,
+            rotation,
+            scale,
+        }
+    }
+
+    /// Apply `child` in the space of this transform
+    pub fn mul_transform(&self, child: Transform) -> Self {
+        Self {
+            translation: self.transform_point(child.translation),
+            rotation: self.rotation * child.rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+
+    /// Rotate around `point` in parent space
+    pub fn rotate_around(&mut self, point: Vec3, rotation: Quat) {
+        self.translation = point + rotation * (self.translation - point);
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    /// Linear interpolation of translation and scale, spherical of rotation
+    pub fn interpolate(&self, other: &Transform, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+
+    pub fn abs_diff_eq(&self, other: &Transform, max_abs_diff: f32) -> bool {
+        self.translation
+            .abs_diff_eq(other.translation, max_abs_diff)
+            // q and -q are the same rotation
+            && (self.rotation.abs_diff_eq(other.rotation, max_abs_diff)
+                || self.rotation.abs_diff_eq(-other.rotation, max_abs_diff))
+            && self.scale.abs_diff_eq(other.scale, max_abs_diff)
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, child: Transform) -> Transform {
+        self.mul_transform(child)
+    }
+}
+
+impl Mul<Vec3> for Transform {
+    type Output = Vec3;
+
+    fn mul(self, point: Vec3) -> Vec3 {
+        self.transform_point(point)
+    }
+}
+
+impl From<Mat4> for Transform {
+    fn from(matrix: Mat4) -> Self {
+        Self::from_matrix(matrix)
+    }
+}
+
+impl From<Transform> for Mat4 {
+    fn from(transform: Transform) -> Self {
+        transform.to_matrix()
+    }
+}
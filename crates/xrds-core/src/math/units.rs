@@ -0,0 +1,182 @@
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+/// Length unit of source assets, e.g. glTF files exported in centimeters
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LengthUnit {
+    Millimeters,
+    Centimeters,
+    #[default]
+    Meters,
+    Inches,
+    Feet,
+    /// Meters per unit
+    Custom(f32),
+}
+
+/// Length in meters, the unit of world space
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Meters(pub f32);
+
+/// Angle in degrees
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Degrees(pub f32);
+
+/// Angle in radians
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Radians(pub f32);
+
+impl LengthUnit {
+    pub fn meters_per_unit(&self) -> f32 {
+        match self {
+            Self::Millimeters => 0.001,
+            Self::Centimeters => 0.01,
+            Self::Meters => 1.0,
+            Self::Inches => 0.0254,
+            Self::Feet => 0.3048,
+            Self::Custom(meters) => *meters,
+        }
+    }
+
+    pub fn to_meters(&self, value: f32) -> Meters {
+        Meters(value * self.meters_per_unit())
+    }
+}
+
+impl Meters {
+    pub fn from_unit(value: f32, unit: LengthUnit) -> Self {
+        unit.to_meters(value)
+    }
+
+    pub fn from_centimeters(centimeters: f32) -> Self {
+        Self(centimeters * 0.01)
+    }
+
+    pub fn from_millimeters(millimeters: f32) -> Self {
+        Self(millimeters * 0.001)
+    }
+
+    pub fn get(self) -> f32 {
+        self.0
+    }
+
+    pub fn to_unit(self, unit: LengthUnit) -> f32 {
+        self.0 / unit.meters_per_unit()
+    }
+
+    pub fn centimeters(self) -> f32 {
+        self.0 * 100.0
+    }
+
+    pub fn millimeters(self) -> f32 {
+        self.0 * 1000.0
+    }
+}
+
+impl Degrees {
+    pub fn get(self) -> f32 {
+        self.0
+    }
+
+    pub fn to_radians(self) -> Radians {
+        Radians(self.0.to_radians())
+    }
+
+    /// Same angle in -180..180
+    pub fn wrapped(self) -> Self {
+        Self((self.0 + 180.0).rem_euclid(360.0) - 180.0)
+    }
+}
+
+impl Radians {
+    pub fn get(self) -> f32 {
+        self.0
+    }
+
+    pub fn to_degrees(self) -> Degrees {
+        Degrees(self.0.to_degrees())
+    }
+}
+
+impl From<Degrees> for Radians {
+    fn from(degrees: Degrees) -> Self {
+        degrees.to_radians()
+    }
+}
+
+impl From<Radians> for Degrees {
+    fn from(radians: Radians) -> Self {
+        radians.to_degrees()
+    }
+}
+
+macro_rules! impl_unit {
+    ($unit:ident, $suffix:literal) => {
+        impl Add for $unit {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $unit {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl AddAssign for $unit {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $unit {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Neg for $unit {
+            type Output = Self;
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl Mul<f32> for $unit {
+            type Output = Self;
+            fn mul(self, rhs: f32) -> Self {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl Div<f32> for $unit {
+            type Output = Self;
+            fn div(self, rhs: f32) -> Self {
+                Self(self.0 / rhs)
+            }
+        }
+
+        /// Ratio of two values of the same unit
+        impl Div for $unit {
+            type Output = f32;
+            fn div(self, rhs: Self) -> f32 {
+                self.0 / rhs.0
+            }
+        }
+
+        impl fmt::Display for $unit {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}{}", self.0, $suffix)
+            }
+        }
+    };
+}
+
+impl_unit!(Meters, " m");
+impl_unit!(Degrees, "°");
+impl_unit!(Radians, " rad");
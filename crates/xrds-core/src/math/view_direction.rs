@@ -0,0 +1,82 @@
+use glam::{EulerRot, Quat, Vec3};
+
+use crate::Transform;
+
+/// Orthonormal viewing direction
+///
+/// `forward` and `up` are always normalized and perpendicular.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewDirection {
+    forward: Vec3,
+    up: Vec3,
+}
+
+impl Default for ViewDirection {
+    fn default() -> Self {
+        Self::from_rotation(Quat::IDENTITY)
+    }
+}
+
+impl ViewDirection {
+    /// Returns `None` if `forward` is zero. `up` is made perpendicular to `forward`
+    pub fn new(forward: Vec3, up: Vec3) -> Option<Self> {
+        let transform = Transform::IDENTITY.looking_to(forward.try_normalize()?, up);
+        Some(Self::from_rotation(transform.rotation))
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            forward: (rotation * Vec3::NEG_Z).normalize(),
+            up: (rotation * Vec3::Y).normalize(),
+        }
+    }
+
+    /// Yaw around +Y and pitch around the local +X axis, in radians
+    pub fn from_yaw_pitch(yaw: f32, pitch: f32) -> Self {
+        Self::from_rotation(Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0))
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.forward
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.up
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.forward.cross(self.up)
+    }
+
+    pub fn to_rotation(&self) -> Quat {
+        Transform::IDENTITY
+            .looking_to(self.forward, self.up)
+            .rotation
+    }
+
+    /// Rotation around +Y in radians. 0 looks along -Z
+    pub fn yaw(&self) -> f32 {
+        (-self.forward.x).atan2(-self.forward.z)
+    }
+
+    /// Elevation in radians. Positive looks up
+    pub fn pitch(&self) -> f32 {
+        self.forward.y.clamp(-1.0, 1.0).asin()
+    }
+
+    pub fn slerp(&self, other: &ViewDirection, t: f32) -> Self {
+        Self::from_rotation(self.to_rotation().slerp(other.to_rotation(), t))
+    }
+}
+
+impl From<Quat> for ViewDirection {
+    fn from(rotation: Quat) -> Self {
+        Self::from_rotation(rotation)
+    }
+}
+
+impl From<ViewDirection> for Quat {
+    fn from(direction: ViewDirection) -> Self {
+        direction.to_rotation()
+    }
+}
@@ -0,0 +1,325 @@
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{Mat4, Quat, Vec3};
+
+use crate::{
+    linear_to_srgb, srgb_to_linear, Aabb, Containment, Degrees, Frustum, Hsv, LengthUnit,
+    LightColor, LinearRgba, Meters, Obb, Palette, Plane, Radians, Ray, Sphere, Transform,
+    ViewDirection,
+};
+
+const EPSILON: f32 = 1e-5;
+
+#[test]
+fn transform_matrix_round_trip() {
+    let transform = Transform::from_xyz(1.0, 2.0, -3.0)
+        .with_rotation(Quat::from_euler(glam::EulerRot::YXZ, 0.3, -0.7, 0.2))
+        .with_scale(Vec3::new(2.0, 0.5, 1.5));
+    let matrix = transform.to_matrix();
+    assert!(Transform::from_matrix(matrix).abs_diff_eq(&transform, EPSILON));
+
+    let point = Vec3::new(0.5, -1.0, 4.0);
+    assert!(transform
+        .transform_point(point)
+        .abs_diff_eq(matrix.transform_point3(point), EPSILON));
+    assert!(transform
+        .to_affine()
+        .transform_vector3(point)
+        .abs_diff_eq(transform.transform_vector(point), EPSILON));
+}
+
+#[test]
+fn transform_look_at_and_axes() {
+    let transform = Transform::from_xyz(0.0, 0.0, 0.0).looking_at(Vec3::X, Vec3::Y);
+    assert!(transform.forward().abs_diff_eq(Vec3::X, EPSILON));
+    assert!(transform.up().abs_diff_eq(Vec3::Y, EPSILON));
+    assert!(transform.right().abs_diff_eq(Vec3::Z, EPSILON));
+    assert!(transform.back().abs_diff_eq(-Vec3::X, EPSILON));
+
+    // Looking straight up with +Y as up still gives a valid rotation
+    let up = Transform::IDENTITY.looking_to(Vec3::Y, Vec3::Y);
+    assert!(up.forward().abs_diff_eq(Vec3::Y, EPSILON));
+    assert!(up.rotation.is_normalized());
+
+    // A zero direction keeps the rotation
+    let mut unchanged = transform;
+    unchanged.look_to(Vec3::ZERO, Vec3::Y);
+    assert_eq!(unchanged, transform);
+}
+
+#[test]
+fn transform_compose_and_inverse() {
+    let parent = Transform::from_xyz(1.0, 0.0, 0.0)
+        .with_rotation(Quat::from_rotation_y(FRAC_PI_2))
+        .with_scale(Vec3::splat(2.0));
+    let child = Transform::from_xyz(0.0, 0.0, -1.0);
+    let world = parent * child;
+    assert!(world
+        .to_matrix()
+        .abs_diff_eq(parent.to_matrix() * child.to_matrix(), EPSILON));
+    assert!(world
+        .translation
+        .abs_diff_eq(Vec3::new(-1.0, 0.0, 0.0), EPSILON));
+
+    let identity = parent * parent.inverse();
+    assert!(identity.abs_diff_eq(&Transform::IDENTITY, EPSILON));
+    assert!(parent
+        .inverse()
+        .to_matrix()
+        .abs_diff_eq(parent.to_matrix().inverse(), EPSILON));
+
+    let mut orbit = Transform::from_xyz(1.0, 0.0, 0.0);
+    orbit.rotate_around(Vec3::ZERO, Quat::from_rotation_y(FRAC_PI_2));
+    assert!(orbit
+        .translation
+        .abs_diff_eq(Vec3::new(0.0, 0.0, -1.0), EPSILON));
+}
+
+#[test]
+fn transform_interpolate() {
+    let a = Transform::IDENTITY;
+    let b = Transform::from_xyz(2.0, 0.0, 0.0)
+        .with_rotation(Quat::from_rotation_y(FRAC_PI_2))
+        .with_scale(Vec3::splat(3.0));
+    let half = a.interpolate(&b, 0.5);
+    assert!(half.translation.abs_diff_eq(Vec3::X, EPSILON));
+    assert!(half.scale.abs_diff_eq(Vec3::splat(2.0), EPSILON));
+    assert!((half.rotation.angle_between(Quat::IDENTITY) - FRAC_PI_2 / 2.0).abs() < EPSILON);
+    assert!(a.interpolate(&b, 1.0).abs_diff_eq(&b, EPSILON));
+    assert_eq!(Mat4::from(Transform::IDENTITY), Mat4::IDENTITY);
+}
+
+#[test]
+fn view_direction_yaw_pitch() {
+    let direction = ViewDirection::from_yaw_pitch(FRAC_PI_2, 0.25);
+    assert!((direction.yaw() - FRAC_PI_2).abs() < EPSILON);
+    assert!((direction.pitch() - 0.25).abs() < EPSILON);
+    // Yawing left from -Z looks along -X
+    assert!(direction.forward().x < 0.0);
+    assert!(direction.forward().dot(direction.up()).abs() < EPSILON);
+
+    let round_trip = ViewDirection::from_rotation(direction.to_rotation());
+    assert!(round_trip
+        .forward()
+        .abs_diff_eq(direction.forward(), EPSILON));
+    assert!(round_trip.up().abs_diff_eq(direction.up(), EPSILON));
+
+    let skewed = ViewDirection::new(Vec3::new(0.0, 0.0, -2.0), Vec3::new(0.0, 1.0, 1.0)).unwrap();
+    assert!(skewed.up().abs_diff_eq(Vec3::Y, EPSILON));
+    assert!(ViewDirection::new(Vec3::ZERO, Vec3::Y).is_none());
+
+    let halfway =
+        ViewDirection::default().slerp(&ViewDirection::from_yaw_pitch(FRAC_PI_2, 0.0), 0.5);
+    assert!((halfway.yaw() - FRAC_PI_2 / 2.0).abs() < EPSILON);
+}
+
+#[test]
+fn aabb_and_sphere_tests() {
+    let aabb = Aabb::from_points([Vec3::new(1.0, -1.0, 0.0), Vec3::new(-1.0, 1.0, 2.0)]).unwrap();
+    assert_eq!(aabb.center(), Vec3::new(0.0, 0.0, 1.0));
+    assert!(aabb.contains_point(Vec3::new(0.5, 0.5, 1.5)));
+    assert!(!aabb.contains_point(Vec3::new(0.0, 0.0, 2.5)));
+    assert!(aabb.contains_aabb(&Aabb::new(Vec3::ZERO, Vec3::ONE)));
+    assert!(aabb.intersects_aabb(&Aabb::new(Vec3::ONE, Vec3::splat(3.0))));
+    assert!(!aabb.intersects_aabb(&Aabb::new(Vec3::splat(1.5), Vec3::splat(3.0))));
+    assert!(aabb.intersects_sphere(&Sphere::new(Vec3::new(2.0, 0.0, 1.0), 1.0)));
+    assert!(!aabb.intersects_sphere(&Sphere::new(Vec3::new(2.0, 2.0, 1.0), 1.0)));
+    assert!(Aabb::from_points([]).is_none());
+
+    let sphere = Sphere::new(Vec3::ZERO, 2.0);
+    assert!(sphere.contains_sphere(&Sphere::new(Vec3::X, 1.0)));
+    assert!(!sphere.contains_sphere(&Sphere::new(Vec3::X * 1.5, 1.0)));
+    assert!(sphere.intersects_sphere(&Sphere::new(Vec3::X * 2.5, 1.0)));
+    assert!(Sphere::from_aabb(&aabb).contains_point(aabb.max));
+}
+
+#[test]
+fn obb_tests() {
+    let rotated = Obb::new(
+        Vec3::ZERO,
+        Vec3::new(2.0, 0.1, 0.1),
+        Quat::from_rotation_y(FRAC_PI_2 / 2.0),
+    );
+    let diagonal = Vec3::new(1.0, 0.0, -1.0).normalize() * 1.9;
+    assert!(rotated.contains_point(diagonal));
+    assert!(!rotated.contains_point(Vec3::X * 1.9));
+
+    let aabb = rotated.to_aabb();
+    assert!(rotated
+        .corners()
+        .iter()
+        .all(|c| aabb.inflate(1e-5).contains_point(*c)));
+
+    // Boxes whose bounding boxes overlap but which are separated along an edge axis
+    let other = Obb::new(
+        Vec3::new(1.0, 0.0, 1.0),
+        Vec3::new(2.0, 0.1, 0.1),
+        rotated.rotation,
+    );
+    assert!(rotated.to_aabb().intersects_aabb(&other.to_aabb()));
+    assert!(!rotated.intersects_obb(&other));
+    assert!(rotated.intersects_obb(&Obb::from(Aabb::new(Vec3::splat(-0.2), Vec3::splat(0.2)))));
+    assert!(rotated.intersects_sphere(&Sphere::new(diagonal * 1.1, 0.3)));
+
+    let transformed = Aabb::new(-Vec3::ONE, Vec3::ONE).transformed(&Transform::from_rotation(
+        Quat::from_rotation_z(FRAC_PI_2 / 2.0),
+    ));
+    assert!((transformed.max.x - 2f32.sqrt()).abs() < EPSILON);
+}
+
+#[test]
+fn ray_tests() {
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z * 2.0).unwrap();
+    assert_eq!(ray.direction(), Vec3::NEG_Z);
+
+    let aabb = Aabb::new(-Vec3::ONE, Vec3::ONE);
+    assert!((ray.intersect_aabb(&aabb).unwrap() - 4.0).abs() < EPSILON);
+    assert_eq!(
+        Ray::new(Vec3::ZERO, Vec3::X).unwrap().intersect_aabb(&aabb),
+        Some(0.0)
+    );
+    assert!(Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::NEG_Z)
+        .unwrap()
+        .intersect_aabb(&aabb)
+        .is_none());
+    assert!(Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::Z)
+        .unwrap()
+        .intersect_aabb(&aabb)
+        .is_none());
+
+    let sphere = Sphere::new(Vec3::ZERO, 1.0);
+    assert!((ray.intersect_sphere(&sphere).unwrap() - 4.0).abs() < EPSILON);
+    assert!(Ray::new(Vec3::new(0.0, 2.0, 5.0), Vec3::NEG_Z)
+        .unwrap()
+        .intersect_sphere(&sphere)
+        .is_none());
+
+    let floor = Plane::from_point_normal(Vec3::ZERO, Vec3::Y).unwrap();
+    let down = Ray::new(Vec3::new(1.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 1.0)).unwrap();
+    let hit = down.at(down.intersect_plane(&floor).unwrap());
+    assert!(hit.abs_diff_eq(Vec3::new(1.0, 0.0, 3.0), EPSILON));
+    assert!(Ray::new(Vec3::Y, Vec3::Y)
+        .unwrap()
+        .intersect_plane(&floor)
+        .is_none());
+
+    let obb = Obb::new(
+        Vec3::new(0.0, 0.0, -3.0),
+        Vec3::ONE,
+        Quat::from_rotation_y(FRAC_PI_2 / 2.0),
+    );
+    let distance = Ray::new(Vec3::ZERO, Vec3::NEG_Z)
+        .unwrap()
+        .intersect_obb(&obb)
+        .unwrap();
+    assert!((distance - (3.0 - 2f32.sqrt())).abs() < EPSILON);
+}
+
+#[test]
+fn frustum_tests() {
+    let projection = Mat4::perspective_rh(FRAC_PI_2, 1.0, 0.1, 100.0);
+    let view = Transform::from_xyz(0.0, 0.0, 10.0).to_matrix().inverse();
+    let frustum = Frustum::from_view_projection(projection * view).unwrap();
+
+    assert!(frustum.contains_point(Vec3::ZERO));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 20.0)));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -95.0)));
+    assert!(!frustum.contains_point(Vec3::new(12.0, 0.0, 0.0)));
+
+    let inside = Aabb::from_center_half_extents(Vec3::ZERO, Vec3::ONE);
+    let crossing = Aabb::from_center_half_extents(Vec3::new(10.0, 0.0, 0.0), Vec3::ONE);
+    let outside = Aabb::from_center_half_extents(Vec3::new(15.0, 0.0, 0.0), Vec3::ONE);
+    assert_eq!(frustum.classify_aabb(&inside), Containment::Inside);
+    assert_eq!(frustum.classify_aabb(&crossing), Containment::Intersects);
+    assert_eq!(frustum.classify_aabb(&outside), Containment::Outside);
+    assert_eq!(
+        frustum.classify_sphere(&Sphere::new(Vec3::ZERO, 1.0)),
+        Containment::Inside
+    );
+    assert!(!frustum.intersects_sphere(&Sphere::new(Vec3::new(0.0, 0.0, 15.0), 1.0)));
+    assert!(frustum.intersects_obb(&Obb::from(crossing)));
+    assert!(!frustum.intersects_obb(&Obb::from(outside)));
+}
+
+#[test]
+fn srgb_conversions() {
+    for i in 0..=255u8 {
+        let v = i as f32 / 255.0;
+        assert!((linear_to_srgb(srgb_to_linear(v)) - v).abs() < 1e-4);
+    }
+    assert!((srgb_to_linear(0.5) - 0.2140).abs() < 1e-4);
+
+    let orange = LinearRgba::from_hex("#FF8000").unwrap();
+    assert_eq!(orange.to_srgba_u8(), [255, 128, 0, 255]);
+    assert_eq!(orange.to_hex(), "#FF8000");
+    assert_eq!(LinearRgba::from_hex("f80"), LinearRgba::from_hex("#FF8800"));
+    assert_eq!(
+        LinearRgba::from_hex("#00000080").unwrap().to_hex(),
+        "#00000080"
+    );
+    assert!(LinearRgba::from_hex("#12345").is_none());
+    assert!(LinearRgba::from_hex("#GG0000").is_none());
+    assert!((LinearRgba::WHITE.luminance() - 1.0).abs() < EPSILON);
+}
+
+#[test]
+fn hsv_round_trip() {
+    let orange = LinearRgba::from_srgb_u8(255, 128, 0);
+    let hsv = Hsv::from(orange);
+    assert!((hsv.hue - 30.1).abs() < 0.1);
+    assert!((hsv.saturation - 1.0).abs() < EPSILON);
+    assert!((hsv.value - 1.0).abs() < EPSILON);
+    assert!(LinearRgba::from(hsv)
+        .to_vec4()
+        .abs_diff_eq(orange.to_vec4(), 1e-4));
+
+    let blue = LinearRgba::from(Hsv::new(120.0, 1.0, 1.0).shift_hue(120.0));
+    assert!(blue.to_vec4().abs_diff_eq(LinearRgba::BLUE.to_vec4(), 1e-4));
+    assert_eq!(Hsv::from(LinearRgba::BLACK).saturation, 0.0);
+}
+
+#[test]
+fn color_temperature() {
+    let white = LinearRgba::from_temperature(6504.0);
+    assert!(white.to_vec3().abs_diff_eq(Vec3::ONE, 0.07));
+
+    let warm = LinearRgba::from_temperature(2000.0);
+    assert!(warm.r > warm.g && warm.g > warm.b);
+    let cold = LinearRgba::from_temperature(12000.0);
+    assert!(cold.b > cold.g && cold.g > cold.r);
+    assert_eq!(cold.b, 1.0);
+
+    // Presets follow the same curve
+    let candle = LinearRgba::from_temperature(1900.0);
+    assert!(candle.b < 0.05 && LightColor::CANDLE.b < 0.05);
+    assert_eq!(LightColor::DIRECT_SUNLIGHT, LinearRgba::WHITE);
+}
+
+#[test]
+fn palette_lookup() {
+    let palette = Palette::basic().with("accent", LinearRgba::from_hex("#3366FF").unwrap());
+    assert_eq!(palette.get("white"), Some(LinearRgba::WHITE));
+    assert_eq!(palette.get("accent").unwrap().to_hex(), "#3366FF");
+    assert!(palette.get("missing").is_none());
+
+    let replaced = palette.clone().with("accent", LinearRgba::RED);
+    assert_eq!(replaced.len(), palette.len());
+    assert_eq!(replaced.iter().last(), Some(("accent", LinearRgba::RED)));
+}
+
+#[test]
+fn units_convert() {
+    assert_eq!(LengthUnit::Centimeters.to_meters(150.0), Meters(1.5));
+    assert!((Meters::from_unit(12.0, LengthUnit::Inches).get() - 0.3048).abs() < EPSILON);
+    assert!((Meters(0.3048).to_unit(LengthUnit::Feet) - 1.0).abs() < EPSILON);
+    assert_eq!(Meters::from_millimeters(64.0).centimeters(), 6.4);
+    assert_eq!(Meters(1.0) + Meters(0.5) * 2.0, Meters(2.0));
+    assert_eq!(Meters(3.0) / Meters(1.5), 2.0);
+    assert_eq!(Meters(1.75).to_string(), "1.75 m");
+
+    let angle = Degrees(90.0);
+    assert!((Radians::from(angle).get() - FRAC_PI_2).abs() < EPSILON);
+    assert!((Degrees::from(Radians(FRAC_PI_2)).get() - 90.0).abs() < 1e-4);
+    assert_eq!(Degrees(270.0).wrapped(), Degrees(-90.0));
+    assert_eq!(Degrees(-540.0).wrapped(), Degrees(-180.0));
+}
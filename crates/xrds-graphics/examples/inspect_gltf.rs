@@ -0,0 +1,13 @@
+//! Prints the scene tree, materials, textures and warnings of glTF files
+//!
+//! `cargo run -p xrds-graphics --example inspect_gltf -- robot.glb`
+
+use xrds_graphics::GltfInspection;
+
+fn main() -> anyhow::Result<()> {
+    for path in std::env::args().skip(1) {
+        println!("{path}");
+        print!("{}", GltfInspection::from_file(&path)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use bevy::{
+    pbr::{ScreenSpaceAmbientOcclusion, ScreenSpaceAmbientOcclusionQualityLevel},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{HalfResolution, PostProcessing};
+
+/// Number of directions and steps sampled per pixel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AmbientOcclusionQuality {
+    /// 4 samples per pixel
+    Low,
+    /// 8 samples per pixel
+    Medium,
+    /// 18 samples per pixel
+    #[default]
+    High,
+    /// 54 samples per pixel
+    Ultra,
+}
+
+/// Screen-space ambient occlusion of a camera
+///
+/// Runs Bevy's ground truth ambient occlusion on the depth and normal
+/// prepasses of the camera, followed by an edge aware denoise. The result
+/// darkens the indirect light of the main pass, on top of the occlusion
+/// textures of materials. Direct light is not occluded.
+///
+/// The camera renders without MSAA while ambient occlusion is enabled.
+/// Not supported on WebGL2, WebGPU and GPUs without storage textures.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraAmbientOcclusion {
+    pub enabled: bool,
+    pub quality: AmbientOcclusionQuality,
+    /// Estimated thickness of objects in world units. Surfaces further
+    /// behind a depth edge than this are not occluded by it
+    pub thickness: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct AmbientOcclusionPlugin;
+
+impl Default for CameraAmbientOcclusion {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            quality: AmbientOcclusionQuality::default(),
+            thickness: 0.25,
+        }
+    }
+}
+
+impl CameraAmbientOcclusion {
+    pub fn with_quality(mut self, quality: AmbientOcclusionQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    /// Bevy settings for the ambient occlusion pass
+    pub fn to_ssao(&self) -> ScreenSpaceAmbientOcclusion {
+        ScreenSpaceAmbientOcclusion {
+            quality_level: match self.quality {
+                AmbientOcclusionQuality::Low => ScreenSpaceAmbientOcclusionQualityLevel::Low,
+                AmbientOcclusionQuality::Medium => ScreenSpaceAmbientOcclusionQualityLevel::Medium,
+                AmbientOcclusionQuality::High => ScreenSpaceAmbientOcclusionQualityLevel::High,
+                AmbientOcclusionQuality::Ultra => ScreenSpaceAmbientOcclusionQualityLevel::Ultra,
+            },
+            constant_object_thickness: self.thickness.max(0.0),
+        }
+    }
+}
+
+impl Plugin for AmbientOcclusionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PostProcessing>()
+            .init_resource::<HalfResolution>()
+            .add_systems(PostUpdate, update_camera_ambient_occlusion);
+    }
+}
+
+fn update_camera_ambient_occlusion(
+    mut commands: Commands,
+    post_processing: Res<PostProcessing>,
+    half_resolution: Res<HalfResolution>,
+    cameras: Query<(Entity, Ref<CameraAmbientOcclusion>), With<Camera3d>>,
+    mut removed: RemovedComponents<CameraAmbientOcclusion>,
+) {
+    for (entity, ambient_occlusion) in cameras.iter() {
+        if !ambient_occlusion.is_changed()
+            && !post_processing.is_changed()
+            && !half_resolution.is_changed()
+        {
+            continue;
+        }
+        if ambient_occlusion.enabled && post_processing.enabled {
+            let ssao = if half_resolution.ambient_occlusion {
+                ambient_occlusion.with_quality(AmbientOcclusionQuality::Low)
+            } else {
+                *ambient_occlusion
+            };
+            commands.entity(entity).insert(ssao.to_ssao());
+        } else {
+            commands
+                .entity(entity)
+                .remove::<ScreenSpaceAmbientOcclusion>();
+        }
+    }
+    for entity in removed.read() {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.remove::<ScreenSpaceAmbientOcclusion>();
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+/// Plays a glTF animation clip on the scene spawned by the same entity
+///
+/// Skins are loaded and skinned on the GPU by the renderer. This starts the
+/// clip on every `AnimationPlayer` of the scene once it has been spawned, e.g.
+///
+/// ```ignore
+/// commands.spawn((
+///     SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("robot.glb"))),
+///     SceneAnimation::new(asset_server.load(GltfAssetLabel::Animation(0).from_asset("robot.glb"))),
+/// ));
+/// ```
+///
+/// Changing the component restarts the animation.
+#[derive(Component, Debug, Clone)]
+#[require(SceneAnimationState)]
+pub struct SceneAnimation {
+    pub clip: Handle<AnimationClip>,
+    pub repeat: bool,
+    pub speed: f32,
+}
+
+#[derive(Component, Debug, Clone, Default)]
+struct SceneAnimationState {
+    graph: Option<(Handle<AnimationGraph>, AnimationNodeIndex)>,
+    started: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct SceneAnimationPlugin;
+
+impl SceneAnimation {
+    pub fn new(clip: Handle<AnimationClip>) -> Self {
+        Self {
+            clip,
+            repeat: true,
+            speed: 1.0,
+        }
+    }
+
+    pub fn once(mut self) -> Self {
+        self.repeat = false;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+impl Plugin for SceneAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, play_scene_animations);
+    }
+}
+
+fn play_scene_animations(
+    mut commands: Commands,
+    mut graphs: ResMut<Assets<AnimationGraph>>,
+    mut scenes: Query<(Entity, Ref<SceneAnimation>, &mut SceneAnimationState)>,
+    children: Query<&Children>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    for (entity, animation, mut state) in scenes.iter_mut() {
+        if animation.is_changed() {
+            state.graph = None;
+            state.started = false;
+        }
+        if state.started {
+            continue;
+        }
+
+        // Players only exist once the scene is spawned
+        let scene_players = children
+            .iter_descendants(entity)
+            .filter(|descendant| players.contains(*descendant))
+            .collect::<Vec<_>>();
+        if scene_players.is_empty() {
+            continue;
+        }
+
+        let (graph, index) = state
+            .graph
+            .get_or_insert_with(|| {
+                let (graph, index) = AnimationGraph::from_clip(animation.clip.clone());
+                (graphs.add(graph), index)
+            })
+            .clone();
+        for player_entity in scene_players {
+            let Ok(mut player) = players.get_mut(player_entity) else {
+                continue;
+            };
+            player.stop_all();
+            let active = player.play(index);
+            active.set_speed(animation.speed);
+            if animation.repeat {
+                active.repeat();
+            }
+            commands
+                .entity(player_entity)
+                .insert(AnimationGraphHandle(graph.clone()));
+        }
+        state.started = true;
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+/// Generates the tangents anisotropic materials need
+///
+/// Bevy loads `KHR_materials_anisotropy` into the anisotropy strength,
+/// rotation and texture of standard materials and stretches the GGX
+/// highlights along the tangent, e.g. for brushed metal. Meshes without
+/// tangents render isotropic, and the glTF loader only generates them for
+/// normal mapped materials, so they are generated here for meshes with an
+/// anisotropic material. Like normal maps, anisotropy needs a UV set.
+#[derive(Debug, Default)]
+pub struct AnisotropyPlugin;
+
+impl Plugin for AnisotropyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, generate_anisotropy_tangents);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn generate_anisotropy_tangents(
+    mut events: MessageReader<AssetEvent<StandardMaterial>>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    all: Query<(&Mesh3d, &MeshMaterial3d<StandardMaterial>)>,
+    changed: Query<
+        (&Mesh3d, &MeshMaterial3d<StandardMaterial>),
+        Or<(Changed<Mesh3d>, Changed<MeshMaterial3d<StandardMaterial>>)>,
+    >,
+) {
+    let modified: HashSet<_> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let candidates = changed.iter().chain(
+        all.iter()
+            .filter(|(_, material)| modified.contains(&material.id())),
+    );
+
+    let mut done = HashSet::new();
+    for (mesh, material) in candidates {
+        if !done.insert(mesh.id()) {
+            continue;
+        }
+        let anisotropic = materials
+            .get(material)
+            .is_some_and(|material| material.anisotropy_strength > 0.0);
+        let needs_tangents = meshes.get(mesh).is_some_and(|mesh| {
+            !mesh.contains_attribute(Mesh::ATTRIBUTE_TANGENT)
+                && mesh.contains_attribute(Mesh::ATTRIBUTE_NORMAL)
+                && mesh.contains_attribute(Mesh::ATTRIBUTE_UV_0)
+        });
+        if !anisotropic || !needs_tangents {
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            if let Err(err) = mesh.generate_tangents() {
+                warn!("Failed to generate tangents for an anisotropic material: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::{collections::HashMap, error::Error, fmt, path::Path};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    image::ImageLoaderSettings,
+    prelude::*,
+};
+
+use crate::ImportSettings;
+
+/// Reads a mesh file format other than glTF, e.g. OBJ or FBX
+///
+/// Registered importers load files of their extensions as a [`Scene`] with
+/// one entity per mesh, so they are spawned with `SceneRoot` like glTF
+/// scenes. Meshes and materials are labeled `Mesh{index}` and
+/// `Material{index}`. [`ImportSettings`] next to the file are applied to the
+/// scene. Add a format, e.g. FBX through an SDK binding, by
+/// implementing this trait and calling
+/// [`AssetImporterAppExt::register_asset_importer`].
+pub trait AssetImporter: Send + Sync + 'static {
+    fn extensions(&self) -> &[&str];
+
+    /// Files next to the imported file it needs, e.g. material libraries
+    fn dependencies(&self, _bytes: &[u8]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Converts the file, `dependencies` holds the files found by path
+    fn import(
+        &self,
+        bytes: &[u8],
+        dependencies: &HashMap<String, Vec<u8>>,
+    ) -> Result<ImportedScene, ImportError>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportedScene {
+    pub meshes: Vec<ImportedMesh>,
+    pub materials: Vec<ImportedMaterial>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedMesh {
+    pub name: String,
+    pub mesh: Mesh,
+    /// Index into [`ImportedScene::materials`], a default material if `None`
+    pub material: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedMaterial {
+    pub name: String,
+    pub base_color: Color,
+    /// Texture paths are relative to the imported file
+    pub base_color_texture: Option<String>,
+    pub normal_map_texture: Option<String>,
+    pub emissive: LinearRgba,
+    pub perceptual_roughness: f32,
+    pub metallic: f32,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+pub trait AssetImporterAppExt {
+    fn register_asset_importer(&mut self, importer: impl AssetImporter) -> &mut Self;
+}
+
+/// Loads files with an [`AssetImporter`]
+struct ImporterLoader<I>(I);
+
+impl Default for ImportedMaterial {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: Color::WHITE,
+            base_color_texture: None,
+            normal_map_texture: None,
+            emissive: LinearRgba::BLACK,
+            perceptual_roughness: 0.5,
+            metallic: 0.0,
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read file: {error}"),
+            Self::Parse { line, message } => write!(f, "Line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for ImportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl AssetImporterAppExt for App {
+    fn register_asset_importer(&mut self, importer: impl AssetImporter) -> &mut Self {
+        self.register_asset_loader(ImporterLoader(importer))
+    }
+}
+
+impl<I: AssetImporter> AssetLoader for ImporterLoader<I> {
+    type Asset = Scene;
+    type Settings = ();
+    type Error = ImportError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Scene, ImportError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let settings = match ImportSettings::path_of(load_context.asset_path()) {
+            Some(path) => match load_context.read_asset_bytes(path).await {
+                Ok(settings) => ImportSettings::from_bytes(&settings, load_context.asset_path()),
+                Err(_) => ImportSettings::default(),
+            },
+            None => ImportSettings::default(),
+        };
+
+        let mut dependencies = HashMap::new();
+        for path in self.0.dependencies(&bytes) {
+            let Ok(asset_path) = load_context.asset_path().resolve_embed(&path) else {
+                continue;
+            };
+            match load_context.read_asset_bytes(asset_path).await {
+                Ok(data) => {
+                    dependencies.insert(path, data);
+                }
+                Err(error) => warn!("Could not read {path}: {error}"),
+            }
+        }
+        let imported = self.0.import(&bytes, &dependencies)?;
+
+        let materials = imported
+            .materials
+            .iter()
+            .enumerate()
+            .map(|(index, material)| {
+                let mut standard = standard_material(material, &settings, load_context);
+                settings.apply_to_material(&material.name, &mut standard);
+                let material = standard;
+                load_context.add_labeled_asset(format!("Material{index}"), material)
+            })
+            .collect::<Vec<_>>();
+        let mut default_material = None;
+
+        let mut world = World::new();
+        for (index, mut imported_mesh) in imported.meshes.into_iter().enumerate() {
+            let material = match imported_mesh
+                .material
+                .and_then(|index| materials.get(index))
+            {
+                Some(material) => material.clone(),
+                None => default_material
+                    .get_or_insert_with(|| {
+                        load_context.add_labeled_asset(
+                            "DefaultMaterial".to_owned(),
+                            StandardMaterial::default(),
+                        )
+                    })
+                    .clone(),
+            };
+            let normal_mapped = imported_mesh
+                .material
+                .and_then(|index| imported.materials.get(index))
+                .is_some_and(|material| material.normal_map_texture.is_some());
+            if normal_mapped
+                && !imported_mesh
+                    .mesh
+                    .contains_attribute(Mesh::ATTRIBUTE_TANGENT)
+            {
+                if let Err(error) = imported_mesh.mesh.generate_tangents() {
+                    warn!(
+                        "Could not generate tangents of {}: {error}",
+                        imported_mesh.name
+                    );
+                }
+            }
+            settings.mesh_optimization.apply(&mut imported_mesh.mesh);
+            let mesh = load_context.add_labeled_asset(format!("Mesh{index}"), imported_mesh.mesh);
+            world.spawn((
+                Name::new(imported_mesh.name),
+                Mesh3d(mesh),
+                MeshMaterial3d(material),
+                Transform::from_scale(Vec3::splat(settings.scale)),
+            ));
+        }
+        Ok(Scene::new(world))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.0.extensions()
+    }
+}
+
+fn standard_material(
+    material: &ImportedMaterial,
+    settings: &ImportSettings,
+    load_context: &mut LoadContext<'_>,
+) -> StandardMaterial {
+    let mut texture = |path: &Option<String>, srgb: bool| {
+        let path = settings
+            .texture_compression
+            .texture_path(Path::new(path.as_ref()?));
+        let path = load_context
+            .asset_path()
+            .resolve_embed(path.to_str()?)
+            .ok()?;
+        Some(
+            load_context
+                .loader()
+                .with_settings(move |settings: &mut ImageLoaderSettings| {
+                    settings.is_srgb = srgb;
+                })
+                .load::<Image>(path),
+        )
+    };
+    StandardMaterial {
+        base_color: material.base_color,
+        base_color_texture: texture(&material.base_color_texture, true),
+        normal_map_texture: texture(&material.normal_map_texture, false),
+        emissive: material.emissive,
+        perceptual_roughness: material.perceptual_roughness,
+        metallic: material.metallic,
+        alpha_mode: if material.base_color.alpha() < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        },
+        ..Default::default()
+    }
+}
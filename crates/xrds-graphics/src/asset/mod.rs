@@ -0,0 +1,7 @@
+mod importer;
+mod obj;
+mod settings;
+
+pub use importer::*;
+pub use obj::*;
+pub use settings::*;
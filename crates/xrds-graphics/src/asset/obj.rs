@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    gltf::Gltf,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+};
+
+use crate::{
+    asset::settings::apply_gltf_import_settings, AssetImporter, AssetImporterAppExt, ImportError,
+    ImportedMaterial, ImportedMesh, ImportedScene,
+};
+
+/// Wavefront OBJ files with their MTL material libraries
+///
+/// Faces are triangulated as fans and split into meshes by object, group
+/// and material. Meshes without normals get smooth normals.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjImporter;
+
+/// Registers the built-in importers and applies [`crate::ImportSettings`]
+#[derive(Debug, Default)]
+pub struct AssetImportPlugin;
+
+/// Vertex as indices of its position, texture coordinate and normal
+type ObjVertex = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct MeshBuilder {
+    name: String,
+    material: Option<String>,
+    vertices: HashMap<ObjVertex, u32>,
+    positions: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    has_uvs: bool,
+    has_normals: bool,
+    indices: Vec<u32>,
+}
+
+impl Plugin for AssetImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_importer(ObjImporter).add_systems(
+            PostUpdate,
+            apply_gltf_import_settings.run_if(resource_exists::<Assets<Gltf>>),
+        );
+    }
+}
+
+impl AssetImporter for ObjImporter {
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+
+    fn dependencies(&self, bytes: &[u8]) -> Vec<String> {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("mtllib "))
+            .flat_map(|libraries| libraries.split_whitespace().map(str::to_owned))
+            .collect()
+    }
+
+    fn import(
+        &self,
+        bytes: &[u8],
+        dependencies: &HashMap<String, Vec<u8>>,
+    ) -> Result<ImportedScene, ImportError> {
+        let mut materials = Vec::new();
+        for library in self.dependencies(bytes) {
+            if let Some(library) = dependencies.get(&library) {
+                materials.extend(parse_mtl(&String::from_utf8_lossy(library)));
+            }
+        }
+
+        let text = String::from_utf8_lossy(bytes);
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut normals = Vec::new();
+        let mut builders: Vec<MeshBuilder> = Vec::new();
+        let mut name = String::from("mesh");
+        let mut material: Option<String> = None;
+        let mut current: Option<usize> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: &str| ImportError::Parse {
+                line: line_number,
+                message: message.to_owned(),
+            };
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => positions
+                    .push(parse_vec3(&mut tokens).ok_or_else(|| error("invalid position"))?),
+                Some("vn") => {
+                    normals.push(parse_vec3(&mut tokens).ok_or_else(|| error("invalid normal"))?)
+                }
+                Some("vt") => {
+                    let u = tokens.next().and_then(|value| value.parse().ok());
+                    let v = tokens
+                        .next()
+                        .and_then(|value| value.parse().ok())
+                        .unwrap_or(0.0);
+                    let u: f32 = u.ok_or_else(|| error("invalid texture coordinate"))?;
+                    // OBJ puts the origin at the bottom left
+                    uvs.push(Vec2::new(u, 1.0 - v));
+                }
+                Some("o") | Some("g") => {
+                    name = tokens.collect::<Vec<_>>().join(" ");
+                    current = None;
+                }
+                Some("usemtl") => {
+                    material = tokens.next().map(str::to_owned);
+                    current = None;
+                }
+                Some("f") => {
+                    let index = *current.get_or_insert_with(|| {
+                        builders
+                            .iter()
+                            .position(|builder| {
+                                builder.name == name && builder.material == material
+                            })
+                            .unwrap_or_else(|| {
+                                builders.push(MeshBuilder::new(name.clone(), material.clone()));
+                                builders.len() - 1
+                            })
+                    });
+                    let face = tokens
+                        .map(|vertex| {
+                            parse_face_vertex(vertex, positions.len(), uvs.len(), normals.len())
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| error("invalid face"))?;
+                    if face.len() < 3 {
+                        return Err(error("face with less than 3 vertices"));
+                    }
+                    let builder = &mut builders[index];
+                    let face = face
+                        .into_iter()
+                        .map(|vertex| builder.vertex(vertex, &positions, &uvs, &normals))
+                        .collect::<Vec<_>>();
+                    for i in 1..face.len() - 1 {
+                        builder.indices.extend([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let meshes = builders
+            .into_iter()
+            .filter(|builder| !builder.indices.is_empty())
+            .map(|builder| {
+                let material = builder
+                    .material
+                    .as_ref()
+                    .and_then(|name| materials.iter().position(|material| &material.name == name));
+                ImportedMesh {
+                    name: builder.name.clone(),
+                    material,
+                    mesh: builder.build(),
+                }
+            })
+            .collect();
+        Ok(ImportedScene { meshes, materials })
+    }
+}
+
+impl MeshBuilder {
+    fn new(name: String, material: Option<String>) -> Self {
+        Self {
+            name,
+            material,
+            has_uvs: true,
+            has_normals: true,
+            ..Default::default()
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        vertex: ObjVertex,
+        positions: &[Vec3],
+        uvs: &[Vec2],
+        normals: &[Vec3],
+    ) -> u32 {
+        if let Some(index) = self.vertices.get(&vertex) {
+            return *index;
+        }
+        let (position, uv, normal) = vertex;
+        let index = self.positions.len() as u32;
+        self.positions.push(positions[position]);
+        self.uvs.push(uv.map_or(Vec2::ZERO, |uv| uvs[uv]));
+        self.normals
+            .push(normal.map_or(Vec3::ZERO, |normal| normals[normal]));
+        self.has_uvs &= uv.is_some();
+        self.has_normals &= normal.is_some();
+        self.vertices.insert(vertex, index);
+        index
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions)
+        .with_inserted_indices(Indices::U32(self.indices));
+        if self.has_uvs {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        }
+        if self.has_normals {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        } else {
+            mesh.compute_normals();
+        }
+        mesh
+    }
+}
+
+/// Materials of an MTL file
+pub fn parse_mtl(text: &str) -> Vec<ImportedMaterial> {
+    let mut materials: Vec<ImportedMaterial> = Vec::new();
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            materials.push(ImportedMaterial {
+                name: tokens.next().unwrap_or_default().to_owned(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+        let values = tokens
+            .clone()
+            .filter_map(|value| value.parse::<f32>().ok())
+            .collect::<Vec<_>>();
+        match (keyword, values.as_slice()) {
+            ("Kd", [r, g, b, ..]) => {
+                material.base_color = Color::srgba(*r, *g, *b, material.base_color.alpha())
+            }
+            ("d", [alpha, ..]) => material.base_color.set_alpha(*alpha),
+            ("Tr", [transparency, ..]) => material.base_color.set_alpha(1.0 - transparency),
+            ("Ke", [r, g, b, ..]) => material.emissive = LinearRgba::rgb(*r, *g, *b),
+            // Blinn-Phong exponent to GGX roughness
+            ("Ns", [exponent, ..]) => {
+                material.perceptual_roughness = (2.0 / (exponent + 2.0)).powf(0.25)
+            }
+            ("Pr", [roughness, ..]) => material.perceptual_roughness = *roughness,
+            ("Pm", [metallic, ..]) => material.metallic = *metallic,
+            // Options come before the path
+            ("map_Kd", _) => material.base_color_texture = tokens.last().map(str::to_owned),
+            ("map_Bump" | "map_bump" | "bump" | "norm", _) => {
+                material.normal_map_texture = tokens.last().map(str::to_owned)
+            }
+            _ => {}
+        }
+    }
+    materials
+}
+
+fn parse_vec3<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Option<Vec3> {
+    let mut value = || tokens.next()?.parse::<f32>().ok();
+    Some(Vec3::new(value()?, value()?, value()?))
+}
+
+/// Resolves the 1-based, or negative relative, indices of a face vertex
+fn parse_face_vertex(
+    vertex: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Option<ObjVertex> {
+    let resolve = |index: &str, count: usize| -> Option<usize> {
+        let index = index.parse::<isize>().ok()?;
+        let index = if index < 0 {
+            count as isize + index
+        } else {
+            index - 1
+        };
+        (0..count as isize)
+            .contains(&index)
+            .then_some(index as usize)
+    };
+    let mut parts = vertex.split('/');
+    let position = resolve(parts.next()?, positions)?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(uv) => Some(resolve(uv, uvs)?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(normal) => Some(resolve(normal, normals)?),
+    };
+    Some((position, uv, normal))
+}
@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{io::AssetReaderError, AssetPath},
+    gltf::{Gltf, GltfMesh},
+    image::ImageLoaderSettings,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+    render::render_resource::Face,
+};
+use serde::{Deserialize, Serialize};
+
+/// Size of the post-transform vertex cache the triangles are ordered for
+const VERTEX_CACHE_SIZE: i64 = 16;
+
+/// Import settings of an asset, read from a JSON file next to it
+///
+/// The settings of `models/robot.glb` are read from `models/robot.glb.meta`
+/// when it is loaded, so assets are tuned without changing code or the
+/// source file. They apply to glTF files and to the files of registered
+/// [`AssetImporter`](crate::AssetImporter)s. Bevy reads `.meta` files with
+/// loader settings of its own, so the asset plugin has to be added with
+/// `AssetMetaCheck::Never`, as the runtime does.
+///
+/// ```json
+/// {
+///     "scale": 0.01,
+///     "mesh_optimization": "VertexCache",
+///     "texture_compression": "Ktx2",
+///     "materials": { "Paint": { "base_color": [0.8, 0.1, 0.1, 1.0], "metallic": 1.0 } }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportSettings {
+    /// Uniform scale of the scene, e.g. 0.01 for files in centimeters
+    pub scale: f32,
+    pub mesh_optimization: MeshOptimization,
+    pub texture_compression: ImportTextureCompression,
+    /// Overrides by material name
+    pub materials: HashMap<String, MaterialOverride>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MeshOptimization {
+    /// Triangles keep the order of the file
+    #[default]
+    None,
+    /// Triangles are reordered so vertices are reused while they are still
+    /// in the post-transform cache of the GPU
+    VertexCache,
+}
+
+/// Which files the textures of the materials are loaded from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImportTextureCompression {
+    /// The images the file refers to
+    #[default]
+    Source,
+    /// KTX2 files with the name of the referred images next to them, e.g.
+    /// `wood.ktx2` for `wood.png`, made by an offline compressor like
+    /// `toktx`. Images embedded in the file are kept
+    Ktx2,
+}
+
+/// Material properties replacing the ones of the file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialOverride {
+    /// sRGB color and alpha
+    pub base_color: Option<[f32; 4]>,
+    /// Linear color
+    pub emissive: Option<[f32; 3]>,
+    pub perceptual_roughness: Option<f32>,
+    pub metallic: Option<f32>,
+    pub unlit: Option<bool>,
+    pub double_sided: Option<bool>,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            mesh_optimization: MeshOptimization::default(),
+            texture_compression: ImportTextureCompression::default(),
+            materials: HashMap::new(),
+        }
+    }
+}
+
+impl ImportSettings {
+    /// Path of the settings of the asset at `path`
+    pub fn path_of(path: &AssetPath) -> Option<AssetPath<'static>> {
+        let file_name = path.path().file_name()?.to_str()?;
+        path.resolve_embed(&format!("{file_name}.meta")).ok()
+    }
+
+    /// Settings in `bytes`, the defaults if they are invalid
+    pub fn from_bytes(bytes: &[u8], path: &AssetPath) -> Self {
+        serde_json::from_slice(bytes).unwrap_or_else(|error| {
+            warn!("Ignoring the import settings of {path}: {error}");
+            Self::default()
+        })
+    }
+
+    pub fn apply_to_material(&self, name: &str, material: &mut StandardMaterial) {
+        if let Some(material_override) = self.materials.get(name) {
+            material_override.apply(material);
+        }
+    }
+}
+
+impl MeshOptimization {
+    /// Only indexed triangle lists are optimized
+    pub fn apply(&self, mesh: &mut Mesh) {
+        if *self == Self::None || mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+            return;
+        }
+        let vertex_count = mesh.count_vertices();
+        let Some(indices) = mesh.indices_mut() else {
+            return;
+        };
+        let source: Vec<u32> = indices.iter().map(|index| index as u32).collect();
+        let Some(ordered) = optimize_vertex_cache(&source, vertex_count) else {
+            return;
+        };
+        *indices = match indices {
+            Indices::U16(_) => {
+                Indices::U16(ordered.into_iter().map(|index| index as u16).collect())
+            }
+            Indices::U32(_) => Indices::U32(ordered),
+        };
+    }
+}
+
+impl ImportTextureCompression {
+    /// Path a texture referred to by `path` is loaded from
+    pub fn texture_path(&self, path: &Path) -> PathBuf {
+        match self {
+            Self::Source => path.to_owned(),
+            Self::Ktx2 => path.with_extension("ktx2"),
+        }
+    }
+}
+
+impl MaterialOverride {
+    pub fn apply(&self, material: &mut StandardMaterial) {
+        if let Some([red, green, blue, alpha]) = self.base_color {
+            material.base_color = Color::srgba(red, green, blue, alpha);
+            if alpha < 1.0 && material.alpha_mode == AlphaMode::Opaque {
+                material.alpha_mode = AlphaMode::Blend;
+            }
+        }
+        if let Some([red, green, blue]) = self.emissive {
+            material.emissive = LinearRgba::rgb(red, green, blue);
+        }
+        if let Some(roughness) = self.perceptual_roughness {
+            material.perceptual_roughness = roughness;
+        }
+        if let Some(metallic) = self.metallic {
+            material.metallic = metallic;
+        }
+        if let Some(unlit) = self.unlit {
+            material.unlit = unlit;
+        }
+        if let Some(double_sided) = self.double_sided {
+            material.double_sided = double_sided;
+            material.cull_mode = if double_sided { None } else { Some(Face::Back) };
+        }
+    }
+}
+
+/// Triangles of `indices` reordered with Tipsify (Sander, Nehab and
+/// Barczak 2007), `None` if an index is out of range
+fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Option<Vec<u32>> {
+    if indices.iter().any(|&index| index as usize >= vertex_count) {
+        return None;
+    }
+    let triangles: Vec<&[u32]> = indices.chunks_exact(3).collect();
+    let mut adjacency = vec![Vec::new(); vertex_count];
+    for (triangle, vertices) in triangles.iter().enumerate() {
+        for &vertex in vertices.iter() {
+            adjacency[vertex as usize].push(triangle);
+        }
+    }
+    // Triangles left to emit per vertex
+    let mut live: Vec<i64> = adjacency.iter().map(|t| t.len() as i64).collect();
+    let mut cache_time = vec![0; vertex_count];
+    let mut emitted = vec![false; triangles.len()];
+    let mut dead_end = Vec::new();
+    let mut time = VERTEX_CACHE_SIZE + 1;
+    let mut cursor = 0;
+    let mut ordered = Vec::with_capacity(indices.len());
+
+    let mut fan = (0..vertex_count).find(|&vertex| live[vertex] > 0);
+    while let Some(vertex) = fan {
+        let mut candidates = Vec::new();
+        for &triangle in &adjacency[vertex] {
+            if std::mem::replace(&mut emitted[triangle], true) {
+                continue;
+            }
+            for &next in triangles[triangle] {
+                let next = next as usize;
+                ordered.push(next as u32);
+                dead_end.push(next);
+                candidates.push(next);
+                live[next] -= 1;
+                if time - cache_time[next] > VERTEX_CACHE_SIZE {
+                    cache_time[next] = time;
+                    time += 1;
+                }
+            }
+        }
+
+        // The candidate staying in the cache longest while its fan is emitted
+        fan = candidates
+            .into_iter()
+            .filter(|&candidate| live[candidate] > 0)
+            .max_by_key(|&candidate| {
+                let age = time - cache_time[candidate];
+                if age + 2 * live[candidate] <= VERTEX_CACHE_SIZE {
+                    age
+                } else {
+                    0
+                }
+            })
+            .or_else(|| {
+                while let Some(candidate) = dead_end.pop() {
+                    if live[candidate] > 0 {
+                        return Some(candidate);
+                    }
+                }
+                while cursor < vertex_count {
+                    if live[cursor] > 0 {
+                        return Some(cursor);
+                    }
+                    cursor += 1;
+                }
+                None
+            });
+    }
+    Some(ordered)
+}
+
+/// Applies the settings next to glTF files once they are loaded with their
+/// dependencies. Scenes already spawned are spawned again
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_gltf_import_settings(
+    mut events: MessageReader<AssetEvent<Gltf>>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut scenes: ResMut<Assets<Scene>>,
+) {
+    for event in events.read() {
+        let AssetEvent::LoadedWithDependencies { id } = event else {
+            continue;
+        };
+        let (Some(gltf), Some(path)) = (gltfs.get(*id), asset_server.get_path(*id)) else {
+            continue;
+        };
+        let Some(settings) = read_import_settings(&asset_server, &path) else {
+            continue;
+        };
+        info!("Applying the import settings of {path}");
+
+        for mesh in gltf.meshes.iter().filter_map(|mesh| gltf_meshes.get(mesh)) {
+            for primitive in &mesh.primitives {
+                if let Some(mesh) = meshes.get_mut(&primitive.mesh) {
+                    settings.mesh_optimization.apply(mesh);
+                }
+            }
+        }
+
+        let names: HashMap<_, _> = gltf
+            .named_materials
+            .iter()
+            .map(|(name, material)| (material.id(), name))
+            .collect();
+        for handle in &gltf.materials {
+            let Some(material) = materials.get_mut(handle) else {
+                continue;
+            };
+            if let Some(name) = names.get(&handle.id()) {
+                settings.apply_to_material(name, material);
+            }
+            if settings.texture_compression != ImportTextureCompression::Source {
+                replace_textures(&asset_server, settings.texture_compression, material);
+            }
+        }
+
+        if settings.scale != 1.0 {
+            for scene in &gltf.scenes {
+                let Some(scene) = scenes.get_mut(scene) else {
+                    continue;
+                };
+                let world = &mut scene.world;
+                let mut roots = world.query_filtered::<&mut Transform, Without<ChildOf>>();
+                for mut transform in roots.iter_mut(world) {
+                    transform.scale *= settings.scale;
+                    transform.translation *= settings.scale;
+                }
+            }
+        }
+    }
+}
+
+/// The asset is already loaded, so the small settings file is read in place
+fn read_import_settings(asset_server: &AssetServer, path: &AssetPath) -> Option<ImportSettings> {
+    let source = asset_server.get_source(path.source().clone_owned()).ok()?;
+    match bevy::tasks::block_on(source.reader().read_meta_bytes(path.path())) {
+        Ok(bytes) => Some(ImportSettings::from_bytes(&bytes, path)),
+        Err(AssetReaderError::NotFound(_)) => None,
+        Err(error) => {
+            warn!("Could not read the import settings of {path}: {error}");
+            None
+        }
+    }
+}
+
+/// Loads the textures of files, not the embedded ones, from `compression`
+fn replace_textures(
+    asset_server: &AssetServer,
+    compression: ImportTextureCompression,
+    material: &mut StandardMaterial,
+) {
+    let mut replace = |texture: &mut Option<Handle<Image>>, srgb: bool| {
+        let Some(path) = texture
+            .as_ref()
+            .and_then(|texture| asset_server.get_path(texture))
+        else {
+            return;
+        };
+        if path.label().is_some() {
+            return;
+        }
+        let replaced = AssetPath::from_path_buf(compression.texture_path(path.path()))
+            .with_source(path.source().clone_owned());
+        *texture = Some(asset_server.load_with_settings(
+            replaced,
+            move |settings: &mut ImageLoaderSettings| {
+                settings.is_srgb = srgb;
+            },
+        ));
+    };
+    replace(&mut material.base_color_texture, true);
+    replace(&mut material.emissive_texture, true);
+    replace(&mut material.normal_map_texture, false);
+    replace(&mut material.metallic_roughness_texture, false);
+    replace(&mut material.occlusion_texture, false);
+}
@@ -0,0 +1,149 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use bevy::{
+    camera::visibility::VisibilitySystems, prelude::*, render::batching::NoAutomaticBatching,
+};
+
+use crate::{
+    ClippedMaterial, FresnelMaterial, ObjectIdMaterial, OutlineMaterial, SheenMaterial,
+    SubsurfaceMaterial,
+};
+
+/// Visible meshes grouped by mesh and material, updated every frame
+///
+/// Only counts, nothing is batched here. Entities sharing a mesh and a
+/// material can always be drawn together, so `batches` is an upper bound of
+/// the draws. Bevy also merges draws of different materials when they are
+/// bindless and share a bind group, and with multi-draw indirect, so the
+/// actual draw count can be lower.
+///
+/// `StandardMaterial` and the materials of this crate are counted, other
+/// materials once they are added with [`MaterialDrawBatchesPlugin`].
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DrawBatches {
+    /// Visible mesh entities
+    pub instances: usize,
+    /// Groups sharing mesh and material, plus entities with
+    /// `NoAutomaticBatching`
+    pub batches: usize,
+    /// Instances of the biggest group. Entities with `NoAutomaticBatching`
+    /// are groups of one, so this is 1 if only those are visible and 0 if
+    /// nothing is visible
+    pub largest: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct DrawBatchesPlugin;
+
+/// Counts meshes with the material `M` in [`DrawBatches`], for materials of
+/// the application. Needs the [`DrawBatchesPlugin`]
+pub struct MaterialDrawBatchesPlugin<M: Material>(PhantomData<M>);
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+enum DrawBatchesSystems {
+    Count,
+    Finish,
+}
+
+/// Groups of the current frame by mesh and typed material id
+#[derive(Resource, Debug, Default)]
+struct DrawBatchGroups {
+    instances: usize,
+    unbatched: usize,
+    groups: HashMap<(AssetId<Mesh>, UntypedAssetId), usize>,
+}
+
+impl DrawBatches {
+    /// Instances per group, 1.0 if nothing is visible
+    pub fn instances_per_batch(&self) -> f32 {
+        if self.batches == 0 {
+            return 1.0;
+        }
+        self.instances as f32 / self.batches as f32
+    }
+}
+
+impl<M: Material> Default for MaterialDrawBatchesPlugin<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl Plugin for DrawBatchesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DrawBatches>()
+            .init_resource::<DrawBatchGroups>()
+            .configure_sets(
+                PostUpdate,
+                (DrawBatchesSystems::Count, DrawBatchesSystems::Finish)
+                    .chain()
+                    .after(VisibilitySystems::CheckVisibility),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    count_material_batches::<StandardMaterial>,
+                    count_material_batches::<ClippedMaterial>,
+                    count_material_batches::<SubsurfaceMaterial>,
+                    count_material_batches::<SheenMaterial>,
+                    count_material_batches::<OutlineMaterial>,
+                    count_material_batches::<FresnelMaterial>,
+                    count_material_batches::<ObjectIdMaterial>,
+                )
+                    .in_set(DrawBatchesSystems::Count),
+            )
+            .add_systems(
+                PostUpdate,
+                finish_draw_batches.in_set(DrawBatchesSystems::Finish),
+            );
+    }
+}
+
+impl<M: Material> Plugin for MaterialDrawBatchesPlugin<M> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            count_material_batches::<M>.in_set(DrawBatchesSystems::Count),
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn count_material_batches<M: Material>(
+    mut groups: ResMut<DrawBatchGroups>,
+    query: Query<(
+        &Mesh3d,
+        &MeshMaterial3d<M>,
+        &ViewVisibility,
+        Has<NoAutomaticBatching>,
+    )>,
+) {
+    for (mesh, material, visibility, no_batching) in query.iter() {
+        if !visibility.get() {
+            continue;
+        }
+        groups.instances += 1;
+        if no_batching {
+            groups.unbatched += 1;
+            continue;
+        }
+        *groups
+            .groups
+            .entry((mesh.id(), material.id().untyped()))
+            .or_default() += 1;
+    }
+}
+
+fn finish_draw_batches(mut draw_batches: ResMut<DrawBatches>, mut groups: ResMut<DrawBatchGroups>) {
+    let largest = groups.groups.values().copied().max();
+    let counted = DrawBatches {
+        instances: groups.instances,
+        batches: groups.groups.len() + groups.unbatched,
+        largest: largest.unwrap_or(groups.unbatched.min(1)),
+    };
+    draw_batches.set_if_neq(counted);
+
+    groups.instances = 0;
+    groups.unbatched = 0;
+    groups.groups.clear();
+}
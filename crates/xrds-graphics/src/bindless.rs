@@ -0,0 +1,76 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{AsBindGroup, BindingType, BindlessSlabResourceLimit},
+        renderer::RenderDevice,
+        settings::{WgpuFeatures, WgpuLimits},
+    },
+};
+
+/// Whether `StandardMaterial`s share bindless texture arrays
+///
+/// With bindless support, `StandardMaterial`s loaded from glTF scenes are
+/// packed into slabs whose textures live in one binding array, and materials
+/// index into it instead of getting a bind group each. Without it, every
+/// material falls back to its own bind group. The renderer picks the path on
+/// its own, this resource reports which one is used.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BindlessTextures {
+    pub supported: bool,
+    /// Textures in the binding array of one slab
+    pub max_textures: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct BindlessTexturesPlugin;
+
+impl BindlessTextures {
+    /// Bevy's conditions for a material with `samplers` samplers and the
+    /// automatic slab size: buffer and texture binding arrays, storage
+    /// buffers, and a sampler for every sampler of every slot in a slab
+    pub fn from_device(features: WgpuFeatures, limits: &WgpuLimits, samplers: u32) -> Self {
+        let slots = BindlessSlabResourceLimit::Auto.resolve();
+        let supported = features
+            .contains(WgpuFeatures::BUFFER_BINDING_ARRAY | WgpuFeatures::TEXTURE_BINDING_ARRAY)
+            && limits.max_storage_buffers_per_shader_stage > 0
+            && limits.max_samplers_per_shader_stage >= samplers * slots;
+        Self {
+            supported,
+            max_textures: if supported { slots } else { 0 },
+        }
+    }
+
+    /// Samplers of one `StandardMaterial`, which depend on Bevy's features
+    fn standard_material_samplers(render_device: &RenderDevice) -> u32 {
+        StandardMaterial::bind_group_layout_entries(render_device, true)
+            .iter()
+            .filter(|entry| matches!(entry.ty, BindingType::Sampler(_)))
+            .count() as u32
+    }
+}
+
+impl Plugin for BindlessTexturesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BindlessTextures>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_device) = app.world().get_resource::<RenderDevice>() else {
+            return;
+        };
+        let bindless = BindlessTextures::from_device(
+            render_device.features(),
+            &render_device.limits(),
+            BindlessTextures::standard_material_samplers(render_device),
+        );
+        if bindless.supported {
+            info!(
+                "Bindless textures enabled, up to {} textures per array",
+                bindless.max_textures
+            );
+        } else {
+            info!("Bindless textures not supported. Use a bind group per material");
+        }
+        app.insert_resource(bindless);
+    }
+}
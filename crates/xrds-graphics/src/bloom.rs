@@ -0,0 +1,124 @@
+use bevy::{
+    post_process::bloom::{Bloom, BloomPrefilter},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+/// Glow around bright pixels of a camera
+///
+/// Runs Bevy's bloom chain between the main pass and tonemapping: bright
+/// pixels are extracted with the threshold, blurred by downsampling and
+/// upsampling through `mip_count` mips and added back with `intensity`.
+/// The camera renders in HDR while bloom is enabled.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraBloom {
+    pub enabled: bool,
+    /// Brightness above which pixels bloom, 0 lets every pixel bloom
+    pub threshold: f32,
+    /// Smooth transition at the threshold, 0 is a hard cut and 1 fully smooth
+    pub threshold_softness: f32,
+    pub intensity: f32,
+    /// Downsample and upsample steps, more spread the glow wider
+    pub mip_count: u32,
+}
+
+/// Post-processing effects of all cameras, off to save GPU time
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PostProcessing {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct BloomPlugin;
+
+impl Default for CameraBloom {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 0.0,
+            threshold_softness: 0.0,
+            intensity: Bloom::NATURAL.intensity,
+            mip_count: 8,
+        }
+    }
+}
+
+impl CameraBloom {
+    /// Only pixels brighter than white bloom, e.g. emissive materials
+    pub fn emissive() -> Self {
+        Self {
+            threshold: 1.0,
+            threshold_softness: 0.5,
+            intensity: 0.3,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f32, softness: f32) -> Self {
+        self.threshold = threshold;
+        self.threshold_softness = softness;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_mip_count(mut self, mip_count: u32) -> Self {
+        self.mip_count = mip_count;
+        self
+    }
+
+    /// Bevy settings for the bloom chain
+    pub fn to_bloom(&self) -> Bloom {
+        // Bevy derives the number of mips from the size of the first one
+        let mip_count = self.mip_count.clamp(1, 12);
+        Bloom {
+            intensity: self.intensity.max(0.0),
+            prefilter: BloomPrefilter {
+                threshold: self.threshold.max(0.0),
+                threshold_softness: self.threshold_softness.clamp(0.0, 1.0),
+            },
+            max_mip_dimension: 1 << (mip_count + 1),
+            ..Bloom::NATURAL
+        }
+    }
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl Plugin for BloomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PostProcessing>()
+            .add_systems(PostUpdate, update_camera_blooms);
+    }
+}
+
+fn update_camera_blooms(
+    mut commands: Commands,
+    post_processing: Res<PostProcessing>,
+    cameras: Query<(Entity, Ref<CameraBloom>), With<Camera>>,
+    mut removed: RemovedComponents<CameraBloom>,
+) {
+    for (entity, bloom) in cameras.iter() {
+        if !bloom.is_changed() && !post_processing.is_changed() {
+            continue;
+        }
+        if bloom.enabled && post_processing.enabled {
+            commands.entity(entity).insert(bloom.to_bloom());
+        } else {
+            commands.entity(entity).remove::<Bloom>();
+        }
+    }
+    for entity in removed.read() {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.remove::<Bloom>();
+        }
+    }
+}
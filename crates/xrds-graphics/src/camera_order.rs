@@ -0,0 +1,99 @@
+use bevy::{camera::CameraUpdateSystems, prelude::*};
+
+/// How a camera clears its target before rendering
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CameraClear {
+    /// Clear to the `ClearColor` resource
+    #[default]
+    Default,
+    Color(Color),
+    /// Keep what previous cameras rendered, e.g. for HUD cameras. The
+    /// camera must use the same HDR and MSAA settings as the camera below it
+    Overlay,
+}
+
+/// Explicit render order and clear mode of a camera
+///
+/// Cameras are rendered in ascending `priority`. Cameras with the same
+/// priority are rendered in spawn order, so the result does not depend on
+/// query iteration order.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+#[require(Camera)]
+pub struct CameraOrder {
+    pub priority: isize,
+    pub clear: CameraClear,
+}
+
+/// Applies [`CameraOrder`] to cameras
+#[derive(Debug, Default)]
+pub struct CameraOrderPlugin;
+
+impl CameraOrder {
+    pub fn new(priority: isize) -> Self {
+        Self {
+            priority,
+            clear: CameraClear::Default,
+        }
+    }
+
+    /// Camera rendered over cameras with a lower priority without clearing
+    pub fn overlay(priority: isize) -> Self {
+        Self {
+            priority,
+            clear: CameraClear::Overlay,
+        }
+    }
+
+    pub fn with_clear_color(mut self, color: Color) -> Self {
+        self.clear = CameraClear::Color(color);
+        self
+    }
+}
+
+impl From<CameraClear> for ClearColorConfig {
+    fn from(clear: CameraClear) -> Self {
+        match clear {
+            CameraClear::Default => ClearColorConfig::Default,
+            CameraClear::Color(color) => ClearColorConfig::Custom(color),
+            CameraClear::Overlay => ClearColorConfig::None,
+        }
+    }
+}
+
+impl Plugin for CameraOrderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, apply_camera_order.before(CameraUpdateSystems));
+    }
+}
+
+fn apply_camera_order(
+    mut cameras: Query<(Entity, Ref<CameraOrder>, &mut Camera)>,
+    mut order: Local<Vec<(isize, u32, Entity)>>,
+) {
+    if !cameras.iter().any(|(_, o, _)| o.is_changed()) {
+        return;
+    }
+
+    order.clear();
+    order.extend(
+        cameras
+            .iter()
+            .map(|(entity, o, _)| (o.priority, entity.index(), entity)),
+    );
+    // Entity indices grow in spawn order unless they are reused
+    order.sort_unstable();
+
+    let mut next = isize::MIN;
+    for (priority, _, entity) in order.iter() {
+        let Ok((_, camera_order, mut camera)) = cameras.get_mut(*entity) else {
+            continue;
+        };
+        // Cameras with equal priority get consecutive orders
+        let value = (*priority).max(next);
+        next = value.saturating_add(1);
+        if camera.order != value {
+            camera.order = value;
+        }
+        camera.clear_color = camera_order.clear.into();
+    }
+}
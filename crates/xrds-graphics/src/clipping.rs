@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::embedded_asset,
+    pbr::{ExtendedMaterial, MaterialExtension, OpaqueRendererMethod},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderType},
+    shader::ShaderRef,
+};
+
+const CLIPPING_SHADER: &str = "embedded://xrds_graphics/clipping.wgsl";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClipShape {
+    /// Keep what is inside the unit cube around the entity, scaled by its transform
+    #[default]
+    Box,
+    /// Cut away what is in front of the entity, along its forward (-Z) axis
+    Plane,
+}
+
+/// Sectioning box or plane for inspecting the inside of models
+///
+/// Geometry of [`Clipped`] entities is cut in the fragment shader. Move,
+/// rotate and scale the entity to move the cut. With a `cap_color`, back faces
+/// seen through the cut are filled with it so closed meshes look solid.
+/// Shadows and depth prepasses are not clipped.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(Transform, Visibility)]
+pub struct ClipVolume {
+    pub shape: ClipShape,
+    pub enabled: bool,
+    pub cap_color: Option<Color>,
+}
+
+/// Clips the meshes of the entity and its descendants, e.g. a glTF scene, with a [`ClipVolume`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clipped {
+    pub volume: Entity,
+}
+
+pub type ClippedMaterial = ExtendedMaterial<StandardMaterial, ClipExtension>;
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct ClipExtension {
+    #[uniform(100)]
+    pub settings: ClipSettings,
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClipSettings {
+    pub world_to_clip: Mat4,
+    pub cap_color: Vec4,
+    /// 0 disabled, 1 box, 2 plane
+    pub shape: u32,
+    pub cap: u32,
+}
+
+/// Standard material a mesh had before it was clipped
+#[derive(Component, Debug, Clone)]
+struct UnclippedMaterial(Handle<StandardMaterial>);
+
+/// Clipped copies of standard materials, per material and volume
+#[derive(Resource, Debug, Default)]
+struct ClippedMaterials {
+    materials: HashMap<(AssetId<StandardMaterial>, Entity), Handle<ClippedMaterial>>,
+}
+
+#[derive(Debug, Default)]
+pub struct ClippingPlugin;
+
+impl ClipVolume {
+    pub fn clip_box() -> Self {
+        Self {
+            shape: ClipShape::Box,
+            ..Default::default()
+        }
+    }
+
+    pub fn plane() -> Self {
+        Self {
+            shape: ClipShape::Plane,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_cap(mut self, color: Color) -> Self {
+        self.cap_color = Some(color);
+        self
+    }
+
+    /// Uniform for a volume with the given world transform
+    pub fn settings(&self, transform: &GlobalTransform) -> ClipSettings {
+        let shape = match (self.enabled, self.shape) {
+            (false, _) => 0,
+            (true, ClipShape::Box) => 1,
+            (true, ClipShape::Plane) => 2,
+        };
+        ClipSettings {
+            world_to_clip: transform.to_matrix().inverse(),
+            cap_color: self
+                .cap_color
+                .map(|color| color.to_linear().to_vec4())
+                .unwrap_or_default(),
+            shape,
+            cap: self.cap_color.is_some() as u32,
+        }
+    }
+
+    /// Whether a point in world space is cut away
+    pub fn clips(&self, transform: &GlobalTransform, point: Vec3) -> bool {
+        let point = transform.affine().inverse().transform_point3(point);
+        match (self.enabled, self.shape) {
+            (false, _) => false,
+            (true, ClipShape::Box) => point.abs().cmpgt(Vec3::splat(0.5)).any(),
+            (true, ClipShape::Plane) => point.z < 0.0,
+        }
+    }
+}
+
+impl Default for ClipVolume {
+    fn default() -> Self {
+        Self {
+            shape: ClipShape::Box,
+            enabled: true,
+            cap_color: None,
+        }
+    }
+}
+
+impl MaterialExtension for ClipExtension {
+    fn fragment_shader() -> ShaderRef {
+        CLIPPING_SHADER.into()
+    }
+}
+
+impl Plugin for ClippingPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "clipping.wgsl");
+
+        app.add_plugins(MaterialPlugin::<ClippedMaterial>::default())
+            .init_resource::<ClippedMaterials>()
+            .add_systems(
+                PostUpdate,
+                (
+                    restore_unclipped_materials,
+                    clip_materials,
+                    update_clip_settings,
+                )
+                    .chain()
+                    .after(TransformSystems::Propagate),
+            );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn clip_materials(
+    mut commands: Commands,
+    mut clipped_materials: ResMut<ClippedMaterials>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<ClippedMaterial>>,
+    clipped: Query<(Entity, &Clipped)>,
+    volumes: Query<(&ClipVolume, &GlobalTransform)>,
+    children: Query<&Children>,
+    meshes: Query<&MeshMaterial3d<StandardMaterial>>,
+) {
+    for (root, clipped) in clipped.iter() {
+        let Ok((volume, transform)) = volumes.get(clipped.volume) else {
+            continue;
+        };
+        // New descendants show up as scenes spawn
+        for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+            let Ok(standard) = meshes.get(entity) else {
+                continue;
+            };
+            let Some(base) = standard_materials.get(&standard.0) else {
+                continue;
+            };
+            let material = clipped_materials
+                .materials
+                .entry((standard.id(), clipped.volume))
+                .or_insert_with(|| {
+                    materials.add(ClippedMaterial {
+                        base: clipped_base(base, volume),
+                        extension: ClipExtension {
+                            settings: volume.settings(transform),
+                        },
+                    })
+                })
+                .clone();
+            commands
+                .entity(entity)
+                .remove::<MeshMaterial3d<StandardMaterial>>()
+                .insert((
+                    MeshMaterial3d(material),
+                    UnclippedMaterial(standard.0.clone()),
+                ));
+        }
+    }
+}
+
+/// Clipped meshes are drawn in the forward pass, double sided when capped
+fn clipped_base(base: &StandardMaterial, volume: &ClipVolume) -> StandardMaterial {
+    let mut base = base.clone();
+    base.opaque_render_method = OpaqueRendererMethod::Forward;
+    if volume.cap_color.is_some() {
+        base.cull_mode = None;
+        base.double_sided = true;
+    }
+    base
+}
+
+fn update_clip_settings(
+    clipped_materials: Res<ClippedMaterials>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<ClippedMaterial>>,
+    volumes: Query<(Ref<ClipVolume>, Ref<GlobalTransform>)>,
+) {
+    for ((standard, volume), handle) in clipped_materials.materials.iter() {
+        let (settings, base) = match volumes.get(*volume) {
+            Ok((volume, transform)) => {
+                if !volume.is_changed() && !transform.is_changed() {
+                    continue;
+                }
+                let base = standard_materials
+                    .get(*standard)
+                    .map(|base| clipped_base(base, &volume));
+                (volume.settings(&transform), base)
+            }
+            // Volumes that are gone no longer clip anything
+            Err(_) => (ClipSettings::default(), None),
+        };
+        // Every change uploads the material again
+        if materials
+            .get(handle)
+            .is_none_or(|material| material.extension.settings == settings)
+        {
+            continue;
+        }
+        let Some(material) = materials.get_mut(handle) else {
+            continue;
+        };
+        material.extension.settings = settings;
+        if let Some(base) = base {
+            material.base = base;
+        }
+    }
+}
+
+fn restore_unclipped_materials(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Clipped>,
+    clipped: Query<(), With<Clipped>>,
+    children: Query<&Children>,
+    unclipped: Query<&UnclippedMaterial>,
+) {
+    for root in removed.read() {
+        if clipped.contains(root) {
+            continue;
+        }
+        for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+            let Ok(material) = unclipped.get(entity) else {
+                continue;
+            };
+            commands
+                .entity(entity)
+                .remove::<(MeshMaterial3d<ClippedMaterial>, UnclippedMaterial)>()
+                .insert(MeshMaterial3d(material.0.clone()));
+        }
+    }
+}
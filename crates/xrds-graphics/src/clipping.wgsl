@@ -0,0 +1,50 @@
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+
+const CLIP_NONE: u32 = 0u;
+const CLIP_BOX: u32 = 1u;
+const CLIP_PLANE: u32 = 2u;
+
+struct ClipSettings {
+    world_to_clip: mat4x4<f32>,
+    cap_color: vec4<f32>,
+    shape: u32,
+    cap: u32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> clip: ClipSettings;
+
+fn is_clipped(world_position: vec4<f32>) -> bool {
+    let position = (clip.world_to_clip * world_position).xyz;
+    if clip.shape == CLIP_BOX {
+        return any(abs(position) > vec3(0.5));
+    }
+    if clip.shape == CLIP_PLANE {
+        // Everything in front of the plane, along its forward axis
+        return position.z < 0.0;
+    }
+    return false;
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    if is_clipped(in.world_position) {
+        discard;
+    }
+
+    var out: FragmentOutput;
+    // Back faces seen through the cut cover the inside of closed meshes
+    if clip.shape != CLIP_NONE && clip.cap != 0u && !is_front {
+        out.color = clip.cap_color;
+        return out;
+    }
+
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
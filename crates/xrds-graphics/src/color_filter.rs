@@ -0,0 +1,295 @@
+use bevy::{
+    asset::{embedded_asset, load_embedded_asset},
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        FullscreenShader,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraph, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode,
+            ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        view::ViewTarget,
+        RenderApp, RenderStartup,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::VignetteLabel;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ColorFilterMode {
+    #[default]
+    None,
+    /// Daltonization for red-blind viewers
+    Protanopia,
+    /// Daltonization for green-blind viewers
+    Deuteranopia,
+    /// Daltonization for blue-blind viewers
+    Tritanopia,
+    Grayscale,
+}
+
+/// Accessibility color filter applied to a camera after tonemapping
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorFilter {
+    pub mode: ColorFilterMode,
+    /// Blend factor between the original and the filtered color
+    pub strength: f32,
+    /// Contrast multiplier around mid gray. 1.0 keeps the original contrast
+    pub contrast: f32,
+}
+
+#[derive(Component, Clone, Copy, ShaderType)]
+pub struct ColorFilterUniform {
+    mode: u32,
+    strength: f32,
+    contrast: f32,
+    _padding: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct ColorFilterPlugin;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct ColorFilterLabel;
+
+#[derive(Default)]
+struct ColorFilterNode;
+
+#[derive(Resource)]
+struct ColorFilterPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    ldr_pipeline_id: CachedRenderPipelineId,
+    hdr_pipeline_id: CachedRenderPipelineId,
+}
+
+impl Default for ColorFilter {
+    fn default() -> Self {
+        Self {
+            mode: ColorFilterMode::None,
+            strength: 1.0,
+            contrast: 1.0,
+        }
+    }
+}
+
+impl ColorFilter {
+    pub fn new(mode: ColorFilterMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if the filter does not change the image
+    pub fn is_identity(&self) -> bool {
+        (self.mode == ColorFilterMode::None || self.strength <= 0.0) && self.contrast == 1.0
+    }
+
+    /// This filter followed by `other`. A pass applies one mode, so the mode
+    /// of `other` replaces this one if it has any, contrast is multiplied
+    pub fn then(&self, other: &ColorFilter) -> ColorFilter {
+        let (mode, strength) = if other.mode == ColorFilterMode::None || other.strength <= 0.0 {
+            (self.mode, self.strength)
+        } else {
+            (other.mode, other.strength)
+        };
+        ColorFilter {
+            mode,
+            strength,
+            contrast: self.contrast * other.contrast,
+        }
+    }
+}
+
+impl ExtractComponent for ColorFilter {
+    type QueryData = &'static ColorFilter;
+    type QueryFilter = With<Camera>;
+    type Out = ColorFilterUniform;
+
+    fn extract_component(filter: QueryItem<'_, '_, Self::QueryData>) -> Option<Self::Out> {
+        if filter.is_identity() {
+            return None;
+        }
+        let mode = match filter.mode {
+            ColorFilterMode::None => 0,
+            ColorFilterMode::Protanopia => 1,
+            ColorFilterMode::Deuteranopia => 2,
+            ColorFilterMode::Tritanopia => 3,
+            ColorFilterMode::Grayscale => 4,
+        };
+        Some(ColorFilterUniform {
+            mode,
+            strength: filter.strength.clamp(0.0, 1.0),
+            contrast: filter.contrast.max(0.0),
+            _padding: 0.0,
+        })
+    }
+}
+
+impl Plugin for ColorFilterPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "color_filter.wgsl");
+
+        app.add_plugins((
+            ExtractComponentPlugin::<ColorFilter>::default(),
+            UniformComponentPlugin::<ColorFilterUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_systems(RenderStartup, init_color_filter_pipeline)
+            .add_render_graph_node::<ViewNodeRunner<ColorFilterNode>>(Core3d, ColorFilterLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    ColorFilterLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        // Filter the colors before the vignette darkens the border
+        let has_vignette = render_app
+            .world()
+            .resource::<RenderGraph>()
+            .get_sub_graph(Core3d)
+            .is_some_and(|graph| graph.get_node_state(VignetteLabel).is_ok());
+        if has_vignette {
+            render_app.add_render_graph_edges(Core3d, (ColorFilterLabel, VignetteLabel));
+        }
+    }
+}
+
+impl ViewNode for ColorFilterNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<ColorFilterUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let color_filter_pipeline = world.resource::<ColorFilterPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        let pipeline_id = if view_target.is_hdr() {
+            color_filter_pipeline.hdr_pipeline_id
+        } else {
+            color_filter_pipeline.ldr_pipeline_id
+        };
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+
+        let uniforms = world.resource::<ComponentUniforms<ColorFilterUniform>>();
+        let Some(uniform_binding) = uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "color_filter_bind_group",
+            &color_filter_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &color_filter_pipeline.sampler,
+                uniform_binding.clone(),
+            )),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("color_filter_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+fn init_color_filter_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    fullscreen_shader: Res<FullscreenShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "color_filter_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                uniform_buffer::<ColorFilterUniform>(true),
+            ),
+        ),
+    );
+    let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+    let shader = load_embedded_asset!(asset_server.as_ref(), "color_filter.wgsl");
+
+    let queue_pipeline = |format: TextureFormat| {
+        pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("color_filter_pipeline".into()),
+            layout: vec![layout.clone()],
+            vertex: fullscreen_shader.to_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: shader.clone(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    };
+    let ldr_pipeline_id = queue_pipeline(TextureFormat::bevy_default());
+    let hdr_pipeline_id = queue_pipeline(ViewTarget::TEXTURE_FORMAT_HDR);
+
+    commands.insert_resource(ColorFilterPipeline {
+        layout,
+        sampler,
+        ldr_pipeline_id,
+        hdr_pipeline_id,
+    });
+}
@@ -0,0 +1,69 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct ColorFilterUniform {
+    mode: u32,
+    strength: f32,
+    contrast: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: ColorFilterUniform;
+
+const MODE_PROTANOPIA: u32 = 1u;
+const MODE_DEUTERANOPIA: u32 = 2u;
+const MODE_TRITANOPIA: u32 = 3u;
+const MODE_GRAYSCALE: u32 = 4u;
+
+// WGSL matrices are column major
+const RGB_TO_LMS = mat3x3<f32>(
+    vec3<f32>(17.8824, 3.45565, 0.0299566),
+    vec3<f32>(43.5161, 27.1554, 0.184309),
+    vec3<f32>(4.11935, 3.86714, 1.46709),
+);
+const LMS_TO_RGB = mat3x3<f32>(
+    vec3<f32>(0.0809444479, -0.0102485335, -0.000365296938),
+    vec3<f32>(-0.130504409, 0.0540193266, -0.00412161469),
+    vec3<f32>(0.116721066, -0.113614708, 0.693511405),
+);
+
+fn simulate(color: vec3<f32>, mode: u32) -> vec3<f32> {
+    var lms = RGB_TO_LMS * color;
+    switch mode {
+        case MODE_PROTANOPIA: {
+            lms.x = 2.02344 * lms.y - 2.52581 * lms.z;
+        }
+        case MODE_DEUTERANOPIA: {
+            lms.y = 0.494207 * lms.x + 1.24827 * lms.z;
+        }
+        case MODE_TRITANOPIA: {
+            lms.z = -0.395913 * lms.x + 0.801109 * lms.y;
+        }
+        default: {}
+    }
+    return LMS_TO_RGB * lms;
+}
+
+// Shift the information lost by the deficiency into channels that are still visible
+fn daltonize(color: vec3<f32>, mode: u32) -> vec3<f32> {
+    let error = color - simulate(color, mode);
+    let shift = vec3<f32>(0.0, 0.7 * error.r + error.g, 0.7 * error.r + error.b);
+    return clamp(color + shift, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let source = textureSample(screen_texture, texture_sampler, in.uv);
+    var color = source.rgb;
+
+    if settings.mode == MODE_GRAYSCALE {
+        color = vec3<f32>(dot(color, vec3<f32>(0.2126, 0.7152, 0.0722)));
+    } else if settings.mode != 0u {
+        color = daltonize(color, settings.mode);
+    }
+    color = mix(source.rgb, color, settings.strength);
+    color = max((color - 0.5) * settings.contrast + 0.5, vec3<f32>(0.0));
+
+    return vec4<f32>(color, source.a);
+}
@@ -0,0 +1,512 @@
+use bevy::{
+    asset::{embedded_asset, load_embedded_asset},
+    camera::primitives::Aabb,
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        graph::CameraDriverLabel,
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel},
+        render_resource::{
+            binding_types::{
+                storage_buffer_read_only_sized, storage_buffer_sized, texture_storage_3d,
+                uniform_buffer,
+            },
+            *,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::GpuImage,
+        Extract, ExtractSchedule, Render, RenderApp, RenderStartup, RenderSystems,
+    },
+};
+
+use crate::{light_probes::LightProbeGather, GlobalIllumination, LightProbeSettings};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Rays traced from each probe are longer than this many probe spacings only
+/// for the visibility of the probe
+const VISIBILITY_SPACINGS: f32 = 1.5;
+
+/// Quality of [`GlobalIllumination::Dynamic`]
+///
+/// Every probe of every progressive [`LightProbeVolume`](crate::LightProbeVolume)
+/// traces its rays against the bounding boxes of the visible meshes each frame.
+/// Hit points are lit by the lights of the scene with shadow rays and by the
+/// probes of the previous frame, so bounces accumulate over frames. The mean
+/// distance to the hits is kept per probe so light does not leak through
+/// walls from probes on the other side.
+#[derive(Resource, Debug, Clone)]
+pub struct DynamicGiSettings {
+    pub rays_per_probe: u32,
+    /// Weight of the previous frame in 0..1. Higher values hide noise, lower
+    /// ones follow moving lights faster
+    pub hysteresis: f32,
+}
+
+/// Updates progressive light probe volumes in compute shaders
+#[derive(Debug, Default)]
+pub struct DynamicGiPlugin;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct DynamicGiLabel;
+
+#[derive(Default)]
+struct DynamicGiNode;
+
+#[derive(Clone, Copy, ShaderType)]
+struct DynamicGiUniform {
+    world_from_volume: Mat4,
+    volume_from_world: Mat4,
+    /// Rotation of the ray directions, changed every frame
+    ray_rotation: Mat3,
+    ambient: Vec3,
+    resolution: UVec3,
+    rays_per_probe: u32,
+    box_count: u32,
+    light_count: u32,
+    /// Weight of the previous frame, 0 on the first one
+    hysteresis: f32,
+    visibility_distance: f32,
+}
+
+#[derive(Clone, Copy, Default, ShaderType)]
+struct DynamicGiBox {
+    box_from_world: Mat4,
+    aabb_min: Vec3,
+    aabb_max: Vec3,
+    albedo: Vec3,
+    emissive: Vec3,
+}
+
+#[derive(Clone, Copy, Default, ShaderType)]
+struct DynamicGiLight {
+    position: Vec3,
+    range: f32,
+    /// Luminous power of point and spot lights, illuminance of directional ones
+    color: Vec3,
+    radius: f32,
+    /// Forward direction of spot and directional lights
+    direction: Vec3,
+    /// 0 for point, 1 for spot and 2 for directional lights
+    kind: u32,
+    /// Cosines of the outer and inner angle of spot lights
+    cone: Vec2,
+}
+
+struct ExtractedVolume {
+    entity: Entity,
+    image: AssetId<Image>,
+    resolution: UVec3,
+    world_from_volume: Mat4,
+}
+
+/// Scene as seen by the probe rays, extracted every frame
+#[derive(Resource, Default)]
+struct DynamicGiScene {
+    settings: Option<DynamicGiSettings>,
+    volumes: Vec<ExtractedVolume>,
+    boxes: Vec<DynamicGiBox>,
+    lights: Vec<DynamicGiLight>,
+    ambient: Vec3,
+    frame: u32,
+}
+
+/// Per-probe state kept on the GPU between frames
+struct VolumeBuffers {
+    resolution: UVec3,
+    rays_per_probe: u32,
+    /// Radiance and distance of each ray
+    rays: Buffer,
+    /// Ambient cube of each probe
+    irradiance: Buffer,
+    /// Mean distance and squared distance around each side of a probe
+    visibility: Buffer,
+    frames: u32,
+}
+
+#[derive(Resource, Default)]
+struct DynamicGiBuffers {
+    volumes: HashMap<Entity, VolumeBuffers>,
+    boxes: StorageBuffer<Vec<DynamicGiBox>>,
+    lights: StorageBuffer<Vec<DynamicGiLight>>,
+}
+
+/// Bind group, probe count and rays per probe of each volume traced this frame
+#[derive(Resource, Default)]
+struct DynamicGiBindGroups(Vec<(BindGroup, u32, u32)>);
+
+#[derive(Resource)]
+struct DynamicGiPipeline {
+    layout: BindGroupLayout,
+    trace: CachedComputePipelineId,
+    update: CachedComputePipelineId,
+}
+
+impl Default for DynamicGiSettings {
+    fn default() -> Self {
+        Self {
+            rays_per_probe: 128,
+            hysteresis: 0.97,
+        }
+    }
+}
+
+impl Plugin for DynamicGiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DynamicGiSettings>();
+        if app.get_sub_app(RenderApp).is_none() {
+            return;
+        }
+
+        embedded_asset!(app, "ddgi.wgsl");
+        let render_app = app.sub_app_mut(RenderApp);
+
+        render_app
+            .init_resource::<DynamicGiScene>()
+            .init_resource::<DynamicGiBuffers>()
+            .init_resource::<DynamicGiBindGroups>()
+            .add_systems(RenderStartup, init_dynamic_gi_pipeline)
+            .add_systems(ExtractSchedule, extract_dynamic_gi)
+            .add_systems(
+                Render,
+                (
+                    prepare_dynamic_gi_buffers.in_set(RenderSystems::PrepareResources),
+                    prepare_dynamic_gi_bind_groups.in_set(RenderSystems::PrepareBindGroups),
+                ),
+            );
+
+        // Probes are lit before any camera renders
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        render_graph.add_node(DynamicGiLabel, DynamicGiNode);
+        render_graph.add_node_edge(DynamicGiLabel, CameraDriverLabel);
+    }
+}
+
+impl Node for DynamicGiNode {
+    fn run<'w>(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        world: &'w World,
+    ) -> Result<(), NodeRunError> {
+        let bind_groups = world.resource::<DynamicGiBindGroups>();
+        if bind_groups.0.is_empty() {
+            return Ok(());
+        }
+        let pipeline = world.resource::<DynamicGiPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let (Some(trace), Some(update)) = (
+            pipeline_cache.get_compute_pipeline(pipeline.trace),
+            pipeline_cache.get_compute_pipeline(pipeline.update),
+        ) else {
+            return Ok(());
+        };
+
+        // The update of a volume reads all of its rays, so the passes are separate
+        for (bind_group, probes, rays_per_probe) in &bind_groups.0 {
+            let encoder = render_context.command_encoder();
+            {
+                let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("dynamic_gi_trace_pass"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(trace);
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(rays_per_probe.div_ceil(WORKGROUP_SIZE), *probes, 1);
+            }
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("dynamic_gi_update_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(update);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups((probes * 6).div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+
+        Ok(())
+    }
+}
+
+fn init_dynamic_gi_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "dynamic_gi_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::COMPUTE,
+            (
+                uniform_buffer::<DynamicGiUniform>(false),
+                storage_buffer_read_only_sized(false, None),
+                storage_buffer_read_only_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+                texture_storage_3d(TextureFormat::Rgba16Float, StorageTextureAccess::WriteOnly),
+            ),
+        ),
+    );
+    let shader = load_embedded_asset!(asset_server.as_ref(), "ddgi.wgsl");
+
+    let queue_pipeline = |entry_point: &'static str| {
+        pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some(format!("dynamic_gi_{entry_point}_pipeline").into()),
+            layout: vec![layout.clone()],
+            push_constant_ranges: vec![],
+            shader: shader.clone(),
+            shader_defs: vec![],
+            entry_point: Some(entry_point.into()),
+            zero_initialize_workgroup_memory: false,
+        })
+    };
+    let trace = queue_pipeline("trace");
+    let update = queue_pipeline("update");
+
+    commands.insert_resource(DynamicGiPipeline {
+        layout,
+        trace,
+        update,
+    });
+}
+
+#[allow(clippy::type_complexity)]
+fn extract_dynamic_gi(
+    mut scene: ResMut<DynamicGiScene>,
+    probe_settings: Extract<Option<Res<LightProbeSettings>>>,
+    settings: Extract<Res<DynamicGiSettings>>,
+    ambient: Extract<Option<Res<AmbientLight>>>,
+    materials: Extract<Option<Res<Assets<StandardMaterial>>>>,
+    volumes: Extract<Query<(Entity, &LightProbeGather, &GlobalTransform)>>,
+    meshes: Extract<
+        Query<(
+            &Aabb,
+            &GlobalTransform,
+            &InheritedVisibility,
+            Option<&MeshMaterial3d<StandardMaterial>>,
+        )>,
+    >,
+    point_lights: Extract<Query<(&PointLight, &GlobalTransform)>>,
+    spot_lights: Extract<Query<(&SpotLight, &GlobalTransform)>>,
+    directional_lights: Extract<Query<(&DirectionalLight, &GlobalTransform)>>,
+) {
+    scene.volumes.clear();
+    scene.boxes.clear();
+    scene.lights.clear();
+    let dynamic = probe_settings
+        .as_ref()
+        .is_some_and(|settings| settings.global_illumination == GlobalIllumination::Dynamic);
+    if !dynamic || volumes.is_empty() {
+        scene.settings = None;
+        return;
+    }
+    scene.settings = Some(settings.clone());
+    scene.frame = scene.frame.wrapping_add(1);
+    scene.ambient = ambient
+        .as_ref()
+        .map(|ambient| ambient.color.to_linear().to_vec3() * ambient.brightness)
+        .unwrap_or_default();
+
+    for (entity, gather, transform) in &volumes {
+        scene.volumes.push(ExtractedVolume {
+            entity,
+            image: gather.image.id(),
+            resolution: gather.resolution,
+            world_from_volume: Mat4::from(transform.affine()),
+        });
+    }
+
+    let default_albedo = probe_settings
+        .as_ref()
+        .map_or(LinearRgba::gray(0.5), |settings| settings.default_albedo);
+    for (aabb, transform, visibility, material) in &meshes {
+        if !visibility.get() {
+            continue;
+        }
+        let material = material.and_then(|material| materials.as_ref()?.get(&material.0));
+        let (albedo, emissive) = material.map_or((default_albedo, LinearRgba::BLACK), |material| {
+            (material.base_color.to_linear(), material.emissive)
+        });
+        scene.boxes.push(DynamicGiBox {
+            box_from_world: Mat4::from(transform.affine().inverse()),
+            aabb_min: aabb.min().into(),
+            aabb_max: aabb.max().into(),
+            albedo: albedo.to_vec3(),
+            emissive: emissive.to_vec3(),
+        });
+    }
+
+    for (light, transform) in &point_lights {
+        scene.lights.push(DynamicGiLight {
+            position: transform.translation(),
+            range: light.range,
+            color: light.color.to_linear().to_vec3() * light.intensity,
+            radius: light.radius,
+            kind: 0,
+            ..default()
+        });
+    }
+    for (light, transform) in &spot_lights {
+        scene.lights.push(DynamicGiLight {
+            position: transform.translation(),
+            range: light.range,
+            color: light.color.to_linear().to_vec3() * light.intensity,
+            radius: light.radius,
+            direction: *transform.forward(),
+            kind: 1,
+            cone: Vec2::new(light.outer_angle.cos(), light.inner_angle.cos()),
+        });
+    }
+    for (light, transform) in &directional_lights {
+        scene.lights.push(DynamicGiLight {
+            color: light.color.to_linear().to_vec3() * light.illuminance,
+            direction: *transform.forward(),
+            kind: 2,
+            ..default()
+        });
+    }
+}
+
+fn prepare_dynamic_gi_buffers(
+    scene: Res<DynamicGiScene>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut buffers: ResMut<DynamicGiBuffers>,
+) {
+    let Some(settings) = &scene.settings else {
+        buffers.volumes.clear();
+        return;
+    };
+    let rays_per_probe = settings.rays_per_probe.max(1);
+
+    buffers
+        .volumes
+        .retain(|entity, _| scene.volumes.iter().any(|volume| volume.entity == *entity));
+    for volume in &scene.volumes {
+        let current = buffers.volumes.get(&volume.entity);
+        if current.is_some_and(|current| {
+            current.resolution == volume.resolution && current.rays_per_probe == rays_per_probe
+        }) {
+            continue;
+        }
+        let probes = volume.resolution.element_product() as u64;
+        let create_buffer = |label: &str, size: u64| {
+            render_device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })
+        };
+        buffers.volumes.insert(
+            volume.entity,
+            VolumeBuffers {
+                resolution: volume.resolution,
+                rays_per_probe,
+                rays: create_buffer("dynamic_gi_rays", probes * rays_per_probe as u64 * 16),
+                irradiance: create_buffer("dynamic_gi_irradiance", probes * 6 * 16),
+                visibility: create_buffer("dynamic_gi_visibility", probes * 6 * 8),
+                frames: 0,
+            },
+        );
+    }
+
+    // Empty storage buffers can not be bound
+    let boxes = buffers.boxes.get_mut();
+    boxes.clear();
+    boxes.extend_from_slice(&scene.boxes);
+    if boxes.is_empty() {
+        boxes.push(DynamicGiBox::default());
+    }
+    let lights = buffers.lights.get_mut();
+    lights.clear();
+    lights.extend_from_slice(&scene.lights);
+    if lights.is_empty() {
+        lights.push(DynamicGiLight::default());
+    }
+    buffers.boxes.write_buffer(&render_device, &render_queue);
+    buffers.lights.write_buffer(&render_device, &render_queue);
+}
+
+fn prepare_dynamic_gi_bind_groups(
+    scene: Res<DynamicGiScene>,
+    pipeline: Option<Res<DynamicGiPipeline>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    mut buffers: ResMut<DynamicGiBuffers>,
+    mut bind_groups: ResMut<DynamicGiBindGroups>,
+) {
+    bind_groups.0.clear();
+    let (Some(settings), Some(pipeline)) = (&scene.settings, pipeline) else {
+        return;
+    };
+    let DynamicGiBuffers {
+        volumes: states,
+        boxes,
+        lights,
+    } = &mut *buffers;
+    let (Some(boxes), Some(lights)) = (boxes.binding(), lights.binding()) else {
+        return;
+    };
+
+    // Golden ratio steps spread the rays of consecutive frames evenly
+    let frame = scene.frame as f32;
+    let ray_rotation = Mat3::from_euler(
+        EulerRot::YXZ,
+        frame * 2.399_963,
+        (frame * 0.618_034).fract() * std::f32::consts::TAU,
+        (frame * 0.754_878).fract() * std::f32::consts::TAU,
+    );
+    let mut volumes = vec![];
+    for volume in &scene.volumes {
+        let Some(gpu_image) = gpu_images.get(volume.image) else {
+            continue;
+        };
+        let Some(state) = states.get_mut(&volume.entity) else {
+            continue;
+        };
+        let spacing =
+            volume.world_from_volume.transform_vector3(Vec3::ONE) / volume.resolution.as_vec3();
+        let mut uniform = UniformBuffer::from(DynamicGiUniform {
+            world_from_volume: volume.world_from_volume,
+            volume_from_world: volume.world_from_volume.inverse(),
+            ray_rotation,
+            ambient: scene.ambient,
+            resolution: volume.resolution,
+            rays_per_probe: state.rays_per_probe,
+            box_count: scene.boxes.len() as u32,
+            light_count: scene.lights.len() as u32,
+            hysteresis: if state.frames == 0 {
+                0.0
+            } else {
+                settings.hysteresis.clamp(0.0, 1.0)
+            },
+            visibility_distance: spacing.length() * VISIBILITY_SPACINGS,
+        });
+        uniform.write_buffer(&render_device, &render_queue);
+        state.frames = state.frames.saturating_add(1);
+
+        let bind_group = render_device.create_bind_group(
+            "dynamic_gi_bind_group",
+            &pipeline.layout,
+            &BindGroupEntries::sequential((
+                &uniform,
+                boxes.clone(),
+                lights.clone(),
+                state.rays.as_entire_binding(),
+                state.irradiance.as_entire_binding(),
+                state.visibility.as_entire_binding(),
+                &gpu_image.texture_view,
+            )),
+        );
+        volumes.push((
+            bind_group,
+            volume.resolution.element_product(),
+            state.rays_per_probe,
+        ));
+    }
+    bind_groups.0 = volumes;
+}
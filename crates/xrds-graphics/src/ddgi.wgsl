@@ -0,0 +1,309 @@
+// `trace` shades the rays of every probe, `update` folds them into the
+// ambient cube and distance moments of each probe
+
+const PI: f32 = 3.141592653589793;
+const GOLDEN_ANGLE: f32 = 2.399963229728653;
+const NO_HIT: f32 = 1e30;
+const SURFACE_OFFSET: f32 = 1e-3;
+
+struct DynamicGiUniform {
+    world_from_volume: mat4x4<f32>,
+    volume_from_world: mat4x4<f32>,
+    ray_rotation: mat3x3<f32>,
+    ambient: vec3<f32>,
+    resolution: vec3<u32>,
+    rays_per_probe: u32,
+    box_count: u32,
+    light_count: u32,
+    hysteresis: f32,
+    visibility_distance: f32,
+}
+
+struct DynamicGiBox {
+    box_from_world: mat4x4<f32>,
+    aabb_min: vec3<f32>,
+    aabb_max: vec3<f32>,
+    albedo: vec3<f32>,
+    emissive: vec3<f32>,
+}
+
+struct DynamicGiLight {
+    position: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    radius: f32,
+    direction: vec3<f32>,
+    kind: u32,
+    cone: vec2<f32>,
+}
+
+struct Hit {
+    normal: vec3<f32>,
+    // Negative if nothing was hit, 0 if the ray starts inside a box
+    distance: f32,
+    index: u32,
+}
+
+@group(0) @binding(0) var<uniform> gi: DynamicGiUniform;
+@group(0) @binding(1) var<storage, read> boxes: array<DynamicGiBox>;
+@group(0) @binding(2) var<storage, read> lights: array<DynamicGiLight>;
+@group(0) @binding(3) var<storage, read_write> rays: array<vec4<f32>>;
+@group(0) @binding(4) var<storage, read_write> irradiance: array<vec4<f32>>;
+@group(0) @binding(5) var<storage, read_write> visibility: array<vec2<f32>>;
+@group(0) @binding(6) var voxels: texture_storage_3d<rgba16float, write>;
+
+fn probe_count() -> u32 {
+    return gi.resolution.x * gi.resolution.y * gi.resolution.z;
+}
+
+fn probe_coords(probe: u32) -> vec3<u32> {
+    let r = gi.resolution;
+    return vec3(probe % r.x, probe / r.x % r.y, probe / (r.x * r.y));
+}
+
+fn probe_index(coords: vec3<u32>) -> u32 {
+    let r = gi.resolution;
+    return coords.x + (coords.y + coords.z * r.y) * r.x;
+}
+
+fn probe_position(coords: vec3<u32>) -> vec3<f32> {
+    let local = (vec3<f32>(coords) + 0.5) / vec3<f32>(gi.resolution) - 0.5;
+    return (gi.world_from_volume * vec4(local, 1.0)).xyz;
+}
+
+// Evenly spread directions on the unit sphere, rotated every frame
+fn ray_direction(ray: u32) -> vec3<f32> {
+    let y = 1.0 - (f32(ray) + 0.5) / f32(gi.rays_per_probe) * 2.0;
+    let radius = sqrt(max(1.0 - y * y, 0.0));
+    let angle = GOLDEN_ANGLE * f32(ray);
+    return gi.ray_rotation * vec3(radius * cos(angle), y, radius * sin(angle));
+}
+
+// -X, +X, -Y, +Y, -Z and +Z
+fn side_normal(side: u32) -> vec3<f32> {
+    var normal = vec3(0.0);
+    normal[side / 2u] = select(-1.0, 1.0, side % 2u == 1u);
+    return normal;
+}
+
+fn intersect_box(index: u32, origin: vec3<f32>, direction: vec3<f32>) -> Hit {
+    let b = boxes[index];
+    let o = (b.box_from_world * vec4(origin, 1.0)).xyz;
+    // Not normalized, so distances along the ray stay the same in box space
+    let d = (b.box_from_world * vec4(direction, 0.0)).xyz;
+    let safe = select(d, vec3(1e-12), abs(d) < vec3(1e-12));
+    let t0 = (b.aabb_min - o) / safe;
+    let t1 = (b.aabb_max - o) / safe;
+    let near = min(t0, t1);
+    let far = max(t0, t1);
+    let enter = max(max(near.x, near.y), near.z);
+    let exit = min(min(far.x, far.y), far.z);
+
+    var hit: Hit;
+    hit.index = index;
+    if enter > exit || exit < 0.0 {
+        hit.distance = -1.0;
+        return hit;
+    }
+    if enter < 0.0 {
+        hit.normal = -direction;
+        hit.distance = 0.0;
+        return hit;
+    }
+
+    var normal = vec3(0.0);
+    if enter == near.x {
+        normal.x = -sign(d.x);
+    } else if enter == near.y {
+        normal.y = -sign(d.y);
+    } else {
+        normal.z = -sign(d.z);
+    }
+    let linear = mat3x3(b.box_from_world[0].xyz, b.box_from_world[1].xyz, b.box_from_world[2].xyz);
+    hit.normal = normalize(transpose(linear) * normal);
+    hit.distance = enter;
+    return hit;
+}
+
+fn trace_scene(origin: vec3<f32>, direction: vec3<f32>) -> Hit {
+    var nearest: Hit;
+    nearest.distance = -1.0;
+    for (var i = 0u; i < gi.box_count; i++) {
+        let hit = intersect_box(i, origin, direction);
+        if hit.distance >= 0.0 && (nearest.distance < 0.0 || hit.distance < nearest.distance) {
+            nearest = hit;
+        }
+    }
+    return nearest;
+}
+
+fn occluded(origin: vec3<f32>, direction: vec3<f32>, distance: f32) -> bool {
+    for (var i = 0u; i < gi.box_count; i++) {
+        let hit = intersect_box(i, origin, direction);
+        if hit.distance >= 0.0 && hit.distance < distance {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Illuminance at a surface point from the unoccluded lights, with the
+// falloff of `light_importance`
+fn direct_irradiance(point: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    var sum = vec3(0.0);
+    for (var i = 0u; i < gi.light_count; i++) {
+        let light = lights[i];
+        var to_light = -light.direction;
+        var distance = NO_HIT;
+        var illuminance = light.color;
+        if light.kind != 2u {
+            let offset = light.position - point;
+            distance = length(offset);
+            to_light = offset / max(distance, 1e-4);
+            if distance >= light.range {
+                continue;
+            }
+            let ratio = distance / light.range;
+            let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+            let clamped = max(max(distance, light.radius), 0.01);
+            var attenuation = window * window / (4.0 * PI * clamped * clamped);
+            if light.kind == 1u {
+                let cosine = dot(light.direction, -to_light);
+                let t = clamp((cosine - light.cone.x) / max(light.cone.y - light.cone.x, 1e-4), 0.0, 1.0);
+                attenuation *= t * t * (3.0 - 2.0 * t);
+            }
+            illuminance *= attenuation;
+        }
+        let cosine = dot(normal, to_light);
+        if cosine <= 0.0 || all(illuminance == vec3(0.0)) {
+            continue;
+        }
+        if !occluded(point, to_light, distance) {
+            sum += illuminance * cosine;
+        }
+    }
+    return sum;
+}
+
+// Ambient cube of a probe evaluated towards `n`
+fn probe_irradiance(probe: u32, n: vec3<f32>) -> vec3<f32> {
+    let side = select(vec3(1u), vec3(0u), n < vec3(0.0));
+    let n2 = n * n;
+    return n2.x * irradiance[probe * 6u + side.x].rgb
+        + n2.y * irradiance[probe * 6u + 2u + side.y].rgb
+        + n2.z * irradiance[probe * 6u + 4u + side.z].rgb;
+}
+
+fn probe_visibility(probe: u32, n: vec3<f32>) -> vec2<f32> {
+    let side = select(vec3(1u), vec3(0u), n < vec3(0.0));
+    let n2 = n * n;
+    return n2.x * visibility[probe * 6u + side.x]
+        + n2.y * visibility[probe * 6u + 2u + side.y]
+        + n2.z * visibility[probe * 6u + 4u + side.z];
+}
+
+// Irradiance of the previous frame at a surface point, from the 8 probes
+// around it that can see the point
+fn volume_irradiance(point: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    let local = (gi.volume_from_world * vec4(point, 1.0)).xyz + 0.5;
+    if any(local < vec3(0.0)) || any(local > vec3(1.0)) {
+        return gi.ambient;
+    }
+    let last = vec3<f32>(gi.resolution - 1u);
+    let grid = clamp(local * vec3<f32>(gi.resolution) - 0.5, vec3(0.0), last);
+    let base = vec3<u32>(floor(grid));
+    let alpha = grid - floor(grid);
+
+    var sum = vec3(0.0);
+    var total = 0.0;
+    for (var i = 0u; i < 8u; i++) {
+        let offset = vec3(i & 1u, (i >> 1u) & 1u, (i >> 2u) & 1u);
+        let coords = min(base + offset, gi.resolution - 1u);
+        let probe = probe_index(coords);
+        let trilinear = mix(1.0 - alpha, alpha, vec3<f32>(offset));
+
+        let to_probe = probe_position(coords) - point;
+        let distance = length(to_probe);
+        let direction = to_probe / max(distance, 1e-4);
+        // Smooth backface test, probes behind the surface count less
+        let wrap = (dot(direction, normal) + 1.0) * 0.5;
+        var weight = wrap * wrap + 0.2;
+        // Chebyshev test of the point against the distances seen by the probe
+        let moments = probe_visibility(probe, -direction);
+        if distance > moments.x {
+            let variance = abs(moments.y - moments.x * moments.x);
+            let delta = distance - moments.x;
+            let chebyshev = variance / (variance + delta * delta);
+            weight *= max(chebyshev * chebyshev * chebyshev, 0.05);
+        }
+        weight = max(weight, 1e-6) * trilinear.x * trilinear.y * trilinear.z;
+        sum += probe_irradiance(probe, normal) * weight;
+        total += weight;
+    }
+    return sum / max(total, 1e-6);
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn trace(@builtin(global_invocation_id) id: vec3<u32>) {
+    let ray = id.x;
+    let probe = id.y;
+    if ray >= gi.rays_per_probe || probe >= probe_count() {
+        return;
+    }
+
+    let origin = probe_position(probe_coords(probe));
+    let direction = ray_direction(ray);
+    let hit = trace_scene(origin, direction);
+    var result = vec4(gi.ambient, NO_HIT);
+    if hit.distance == 0.0 {
+        // The probe is inside a mesh, its rays see nothing
+        result = vec4(0.0);
+    } else if hit.distance > 0.0 {
+        let b = boxes[hit.index];
+        let point = origin + direction * hit.distance + hit.normal * SURFACE_OFFSET;
+        let direct = direct_irradiance(point, hit.normal);
+        let indirect = volume_irradiance(point, hit.normal);
+        result = vec4(b.emissive + b.albedo * (direct / PI + indirect), hit.distance);
+    }
+    rays[probe * gi.rays_per_probe + ray] = result;
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn update(@builtin(global_invocation_id) id: vec3<u32>) {
+    let probe = id.x / 6u;
+    let side = id.x % 6u;
+    if probe >= probe_count() {
+        return;
+    }
+
+    let normal = side_normal(side);
+    var radiance = vec3(0.0);
+    var weight = 0.0;
+    var moments = vec2(0.0);
+    var moments_weight = 0.0;
+    for (var ray = 0u; ray < gi.rays_per_probe; ray++) {
+        let traced = rays[probe * gi.rays_per_probe + ray];
+        let cosine = max(dot(ray_direction(ray), normal), 0.0);
+        radiance += traced.rgb * cosine;
+        weight += cosine;
+        // A narrower lobe keeps the distances of the sides apart
+        let distance = min(traced.w, gi.visibility_distance);
+        let sharp = cosine * cosine * cosine * cosine;
+        moments += vec2(distance, distance * distance) * sharp;
+        moments_weight += sharp;
+    }
+
+    let index = probe * 6u + side;
+    let blended = mix(radiance / max(weight, 1e-4), irradiance[index].rgb, gi.hysteresis);
+    irradiance[index] = vec4(blended, 1.0);
+    visibility[index] = mix(moments / max(moments_weight, 1e-4), visibility[index], gi.hysteresis);
+
+    let coords = probe_coords(probe);
+    let texel = vec3(
+        coords.x,
+        coords.y + (side % 2u) * gi.resolution.y,
+        coords.z + (side / 2u) * gi.resolution.z,
+    );
+    // Positive sides in the upper half of the height, the axis in thirds of the depth
+    textureStore(voxels, texel, vec4(blended, 1.0));
+}
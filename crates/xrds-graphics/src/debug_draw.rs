@@ -0,0 +1,172 @@
+use bevy::{
+    camera::primitives::Aabb,
+    gizmos::config::{GizmoConfigGroup, GizmoConfigStore},
+    prelude::*,
+};
+
+/// Immediate mode debug lines, e.g. for bounding boxes and light frusta
+///
+/// Shapes queued during a frame are drawn as gizmos once in `PostUpdate`
+/// and then cleared, so they have to be queued again every frame. Gizmos
+/// are drawn in their own pass after the main pass, depth tested against
+/// the scene. Toggle and style them with the [`DebugDrawGizmos`] config.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DebugDraw {
+    shapes: Vec<DebugShape>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugShape {
+    Line {
+        start: Vec3,
+        end: Vec3,
+        color: Color,
+    },
+    /// Unit cube centered on the origin of `transform`
+    Box {
+        transform: GlobalTransform,
+        color: Color,
+    },
+    Sphere {
+        center: Vec3,
+        radius: f32,
+        color: Color,
+    },
+    /// Red, green and blue arrows along the X, Y and Z axes
+    Axes {
+        transform: GlobalTransform,
+        length: f32,
+    },
+    /// Frustum of a view, by the corners of its near and far planes
+    Frustum { corners: [Vec3; 8], color: Color },
+}
+
+/// Gizmo config of [`DebugDraw`]
+#[derive(Reflect, GizmoConfigGroup, Debug, Default)]
+pub struct DebugDrawGizmos;
+
+#[derive(Debug, Default)]
+pub struct DebugDrawPlugin;
+
+impl DebugDraw {
+    pub fn draw_line(&mut self, start: Vec3, end: Vec3, color: impl Into<Color>) {
+        self.shapes.push(DebugShape::Line {
+            start,
+            end,
+            color: color.into(),
+        });
+    }
+
+    /// Bounding box of a mesh, e.g. its `Aabb` and `GlobalTransform`
+    pub fn draw_aabb(&mut self, aabb: &Aabb, transform: &GlobalTransform, color: impl Into<Color>) {
+        let local = Transform::from_translation(aabb.center.into())
+            .with_scale(Vec3::from(aabb.half_extents) * 2.0);
+        self.shapes.push(DebugShape::Box {
+            transform: transform.mul_transform(local),
+            color: color.into(),
+        });
+    }
+
+    pub fn draw_sphere(&mut self, center: Vec3, radius: f32, color: impl Into<Color>) {
+        self.shapes.push(DebugShape::Sphere {
+            center,
+            radius,
+            color: color.into(),
+        });
+    }
+
+    pub fn draw_axes(&mut self, transform: impl Into<GlobalTransform>, length: f32) {
+        self.shapes.push(DebugShape::Axes {
+            transform: transform.into(),
+            length,
+        });
+    }
+
+    /// Frustum of a camera or shadow casting light, from the inverse of its
+    /// `clip_from_world` matrix. Needs a finite far plane, Bevy's default
+    /// perspective projection has none
+    pub fn draw_frustum(&mut self, world_from_clip: Mat4, color: impl Into<Color>) {
+        let corners = frustum_corners(world_from_clip);
+        self.shapes.push(DebugShape::Frustum {
+            corners,
+            color: color.into(),
+        });
+    }
+
+    /// Shapes queued this frame
+    pub fn shapes(&self) -> &[DebugShape] {
+        &self.shapes
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+}
+
+/// Near plane corners followed by far plane corners, counterclockwise from
+/// the bottom left
+fn frustum_corners(world_from_clip: Mat4) -> [Vec3; 8] {
+    let corner = |x: f32, y: f32, z: f32| world_from_clip.project_point3(Vec3::new(x, y, z));
+    [
+        corner(-1.0, -1.0, 1.0),
+        corner(1.0, -1.0, 1.0),
+        corner(1.0, 1.0, 1.0),
+        corner(-1.0, 1.0, 1.0),
+        corner(-1.0, -1.0, 0.0),
+        corner(1.0, -1.0, 0.0),
+        corner(1.0, 1.0, 0.0),
+        corner(-1.0, 1.0, 0.0),
+    ]
+}
+
+impl Plugin for DebugDrawPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugDraw>().add_systems(
+            PostUpdate,
+            (
+                draw_debug_shapes.run_if(resource_exists::<GizmoConfigStore>),
+                clear_debug_shapes,
+            )
+                .chain()
+                .after(TransformSystems::Propagate),
+        );
+    }
+
+    /// Gizmos are only available with Bevy's gizmo plugin, which may be added later
+    fn finish(&self, app: &mut App) {
+        if app.world().contains_resource::<GizmoConfigStore>() {
+            app.init_gizmo_group::<DebugDrawGizmos>();
+        }
+    }
+}
+
+fn draw_debug_shapes(mut gizmos: Gizmos<DebugDrawGizmos>, debug_draw: Res<DebugDraw>) {
+    for shape in debug_draw.shapes() {
+        match *shape {
+            DebugShape::Line { start, end, color } => gizmos.line(start, end, color),
+            DebugShape::Box { transform, color } => gizmos.cuboid(transform, color),
+            DebugShape::Sphere {
+                center,
+                radius,
+                color,
+            } => {
+                gizmos.sphere(Isometry3d::from_translation(center), radius, color);
+            }
+            DebugShape::Axes { transform, length } => gizmos.axes(transform, length),
+            DebugShape::Frustum { corners, color } => {
+                for i in 0..4 {
+                    let next = (i + 1) % 4;
+                    gizmos.line(corners[i], corners[next], color);
+                    gizmos.line(corners[i + 4], corners[next + 4], color);
+                    gizmos.line(corners[i], corners[i + 4], color);
+                }
+            }
+        }
+    }
+}
+
+fn clear_debug_shapes(mut debug_draw: ResMut<DebugDraw>) {
+    if !debug_draw.shapes.is_empty() {
+        debug_draw.clear();
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::{
+    core_pipeline::prepass::{DeferredPrepass, DepthPrepass},
+    pbr::ScreenSpaceAmbientOcclusion,
+    prelude::*,
+};
+
+use crate::SubsurfaceScattering;
+
+/// Depth pre-pass of cameras that do not set their own
+///
+/// Opaque and alpha masked meshes are first drawn depth only, so the main
+/// pass rejects hidden fragments by early-Z and expensive materials shade
+/// each pixel about once. Worth it for scenes with heavy overdraw, otherwise
+/// it only adds a pass. Off by default.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DepthPrepassing {
+    pub enabled: bool,
+}
+
+/// Depth pre-pass of a 3D camera, overriding [`DepthPrepassing`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(Camera3d)]
+pub struct CameraDepthPrepass {
+    pub enabled: bool,
+}
+
+/// `DepthPrepass` inserted by [`DepthPrepassPlugin`], which may remove it again
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub(crate) struct ManagedDepthPrepass;
+
+/// Applies [`DepthPrepassing`] and [`CameraDepthPrepass`] to 3D cameras
+#[derive(Debug, Default)]
+pub struct DepthPrepassPlugin;
+
+impl DepthPrepassing {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+impl CameraDepthPrepass {
+    pub fn on() -> Self {
+        Self { enabled: true }
+    }
+
+    pub fn off() -> Self {
+        Self { enabled: false }
+    }
+}
+
+impl Plugin for DepthPrepassPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DepthPrepassing>()
+            .add_systems(PostUpdate, apply_depth_prepass);
+    }
+}
+
+/// Pre-passes added by hand or needed by ambient occlusion, subsurface
+/// scattering or the deferred gbuffer are never removed
+#[allow(clippy::type_complexity)]
+fn apply_depth_prepass(
+    mut commands: Commands,
+    prepassing: Res<DepthPrepassing>,
+    cameras: Query<
+        (
+            Entity,
+            Option<&CameraDepthPrepass>,
+            Has<DepthPrepass>,
+            Has<ManagedDepthPrepass>,
+            Has<ScreenSpaceAmbientOcclusion>,
+            Has<SubsurfaceScattering>,
+            Has<DeferredPrepass>,
+        ),
+        With<Camera3d>,
+    >,
+) {
+    for (entity, camera, has_prepass, managed, ambient_occlusion, subsurface, deferred) in
+        cameras.iter()
+    {
+        let enabled = camera.map_or(prepassing.enabled, |camera| camera.enabled);
+        if enabled && !has_prepass {
+            commands
+                .entity(entity)
+                .insert((DepthPrepass, ManagedDepthPrepass));
+        } else if !enabled && managed && !ambient_occlusion && !subsurface && !deferred {
+            commands
+                .entity(entity)
+                .remove::<(DepthPrepass, ManagedDepthPrepass)>();
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    core_pipeline::Skybox,
+    light::GeneratedEnvironmentMapLight,
+    prelude::*,
+    render::render_resource::{
+        Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+    },
+};
+
+/// Image based lighting and skybox of a camera from an equirectangular map
+///
+/// The map, e.g. an `.hdr` file, is converted to a cubemap once it is loaded.
+/// Diffuse irradiance and prefiltered specular maps are then generated from
+/// the cubemap on the GPU, so PBR materials get ambient light and reflections
+/// from the environment. `.exr` maps need bevy's `exr` feature.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct EnvironmentLighting {
+    pub source: Handle<Image>,
+    /// Brightness of the lighting and skybox in cd/m²
+    pub intensity: f32,
+    /// Rotation of the environment around the scene
+    pub rotation: Quat,
+    /// Size of a cubemap face in pixels, rounded up to a power of two
+    pub face_size: u32,
+    pub skybox: bool,
+}
+
+/// Cubemaps converted from equirectangular maps, per map and face size
+#[derive(Resource, Debug, Default)]
+struct EnvironmentCubemaps {
+    cubemaps: HashMap<(AssetId<Image>, u32), Handle<Image>>,
+}
+
+/// Marks cameras whose lighting was generated
+#[derive(Component, Debug, Clone, Copy)]
+struct EnvironmentCubemap;
+
+#[derive(Debug, Default)]
+pub struct EnvironmentLightingPlugin;
+
+impl EnvironmentLighting {
+    pub fn new(source: Handle<Image>) -> Self {
+        Self {
+            source,
+            intensity: 1000.0,
+            rotation: Quat::IDENTITY,
+            face_size: 512,
+            skybox: true,
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Light the scene without drawing the environment behind it, e.g. in passthrough
+    pub fn without_skybox(mut self) -> Self {
+        self.skybox = false;
+        self
+    }
+}
+
+impl Plugin for EnvironmentLightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EnvironmentCubemaps>().add_systems(
+            PostUpdate,
+            (remove_environment_lighting, apply_environment_lighting)
+                .chain()
+                .run_if(resource_exists::<Assets<Image>>),
+        );
+    }
+}
+
+/// Converts an equirectangular image to a cubemap with `face_size` pixel faces
+///
+/// Faces are in wgpu order (+X, -X, +Y, -Y, +Z, -Z) and oriented the way
+/// bevy samples cubemaps, with the center of the image in the -Z direction.
+pub fn equirect_to_cubemap(image: &Image, face_size: u32) -> Image {
+    let mut cubemap = Image::new_fill(
+        Extent3d {
+            width: face_size,
+            height: face_size,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        &[0; 8],
+        TextureFormat::Rgba16Float,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    cubemap.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..Default::default()
+    });
+
+    for face in 0..6 {
+        for y in 0..face_size {
+            for x in 0..face_size {
+                // Pixel center in -1..1, v pointing down
+                let u = (x as f32 + 0.5) / face_size as f32 * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / face_size as f32 * 2.0 - 1.0;
+                let cube = match face {
+                    0 => Vec3::new(1.0, -v, -u),
+                    1 => Vec3::new(-1.0, -v, u),
+                    2 => Vec3::new(u, 1.0, v),
+                    3 => Vec3::new(u, -1.0, -v),
+                    4 => Vec3::new(u, -v, 1.0),
+                    _ => Vec3::new(-u, -v, -1.0),
+                };
+                // Cubemaps are left-handed
+                let direction = (cube * Vec3::new(1.0, 1.0, -1.0)).normalize();
+                let color = sample_equirect(image, direction);
+                // Only fails for out of bounds coordinates
+                let _ = cubemap.set_color_at_3d(x, y, face, color);
+            }
+        }
+    }
+    cubemap
+}
+
+/// Bilinear sample of an equirectangular image in a world direction
+fn sample_equirect(image: &Image, direction: Vec3) -> Color {
+    let width = image.width();
+    let height = image.height();
+    let longitude = direction.x.atan2(-direction.z);
+    let latitude = direction.y.clamp(-1.0, 1.0).acos();
+    let x = (0.5 + longitude / std::f32::consts::TAU) * width as f32 - 0.5;
+    let y = (latitude / std::f32::consts::PI * height as f32 - 0.5).clamp(0.0, height as f32 - 1.0);
+
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let (tx, ty) = (x - x0, y - y0);
+    let texel = |x: f32, y: f32| {
+        // Longitude wraps around, latitude stops at the poles
+        let x = (x as i64).rem_euclid(width as i64) as u32;
+        let y = (y as u32).min(height - 1);
+        image
+            .get_color_at(x, y)
+            .map(|color| color.to_linear().to_vec4())
+            .unwrap_or_default()
+    };
+    let top = texel(x0, y0).lerp(texel(x0 + 1.0, y0), tx);
+    let bottom = texel(x0, y0 + 1.0).lerp(texel(x0 + 1.0, y0 + 1.0), tx);
+    Color::LinearRgba(LinearRgba::from_vec4(top.lerp(bottom, ty)))
+}
+
+fn remove_environment_lighting(
+    mut commands: Commands,
+    mut removed: RemovedComponents<EnvironmentLighting>,
+    changed: Query<Entity, Changed<EnvironmentLighting>>,
+) {
+    // Changed lighting is generated again
+    for entity in removed.read().chain(changed.iter()) {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.remove::<(
+                EnvironmentCubemap,
+                GeneratedEnvironmentMapLight,
+                EnvironmentMapLight,
+                Skybox,
+            )>();
+        }
+    }
+}
+
+fn apply_environment_lighting(
+    mut commands: Commands,
+    mut cubemaps: ResMut<EnvironmentCubemaps>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<(Entity, &EnvironmentLighting), Without<EnvironmentCubemap>>,
+) {
+    for (entity, lighting) in cameras.iter() {
+        let face_size = lighting.face_size.max(1).next_power_of_two();
+        let key = (lighting.source.id(), face_size);
+        let cubemap = match cubemaps.cubemaps.get(&key) {
+            Some(cubemap) => cubemap.clone(),
+            None => {
+                // Tried again next frame until the map is loaded
+                let Some(source) = images.get(&lighting.source) else {
+                    continue;
+                };
+                let cubemap = equirect_to_cubemap(source, face_size);
+                let cubemap = images.add(cubemap);
+                cubemaps.cubemaps.insert(key, cubemap.clone());
+                cubemap
+            }
+        };
+
+        let mut camera = commands.entity(entity);
+        camera.insert((
+            EnvironmentCubemap,
+            GeneratedEnvironmentMapLight {
+                environment_map: cubemap.clone(),
+                intensity: lighting.intensity,
+                rotation: lighting.rotation,
+                ..Default::default()
+            },
+        ));
+        if lighting.skybox {
+            camera.insert(Skybox {
+                image: cubemap,
+                brightness: lighting.intensity,
+                rotation: lighting.rotation,
+            });
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    fmt::Write as _,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::ExtractedCamera,
+        render_graph::{Edge, RenderGraph},
+        sync_world::MainEntity,
+        Render, RenderApp, RenderSystems,
+    },
+};
+use serde::Serialize;
+
+/// Render graph of a frame with the views it ran for
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FrameGraph {
+    pub graph: FrameGraphPasses,
+    /// Cameras in the order they were rendered
+    pub views: Vec<FrameGraphView>,
+}
+
+/// Passes of a render graph and its sub graphs
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FrameGraphPasses {
+    pub name: String,
+    pub nodes: Vec<FrameGraphNode>,
+    pub edges: Vec<FrameGraphEdge>,
+    pub sub_graphs: Vec<FrameGraphPasses>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FrameGraphNode {
+    pub label: String,
+    pub type_name: String,
+    /// Input resources as `name: type`
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+/// Dependency between two nodes, the `from` node runs first
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FrameGraphEdge {
+    pub from: String,
+    pub to: String,
+    /// Output and input resource names of slot edges
+    pub slot: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameGraphView {
+    /// Camera entity in the main world
+    pub camera: Entity,
+    pub order: isize,
+    /// Sub graph run for the camera
+    pub graph: String,
+    pub target: Option<String>,
+    pub target_size: Option<UVec2>,
+    pub hdr: bool,
+    pub msaa_writeback: bool,
+}
+
+/// Written the frame after a [`FrameGraphCapture::request`]
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct FrameGraphCaptured(pub FrameGraph);
+
+/// Requests frame graph dumps from the render world
+#[derive(Resource, Clone)]
+pub struct FrameGraphCapture {
+    requested: Arc<AtomicBool>,
+    sender: Sender<FrameGraph>,
+}
+
+/// Dumps the render graph and the views of a frame on demand
+///
+/// Call [`FrameGraphCapture::request`] and read [`FrameGraphCaptured`], then
+/// save it with [`FrameGraph::save`] as DOT for GraphViz or as JSON, to see
+/// why passes run in an unexpected order or write the wrong attachments.
+#[derive(Debug, Default)]
+pub struct FrameGraphPlugin;
+
+#[derive(Resource)]
+struct FrameGraphReceiver(Mutex<Receiver<FrameGraph>>);
+
+impl FrameGraph {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// GraphViz graph with a cluster per sub graph, e.g. `dot -Tsvg frame.dot`
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph frame {\n  rankdir=LR;\n  node [shape=box];\n");
+        self.graph.write_dot(&mut dot, "", 1);
+        if !self.views.is_empty() {
+            dot.push_str("  subgraph cluster_views {\n    label=\"views\";\n");
+            for (index, view) in self.views.iter().enumerate() {
+                let _ = writeln!(
+                    dot,
+                    "    \"view/{index}\" [shape=note, label=\"{} order {}\\n{}\\n{}\"];",
+                    view.camera,
+                    view.order,
+                    view.graph,
+                    view.target.as_deref().unwrap_or("no target")
+                );
+            }
+            dot.push_str("  }\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes DOT for `.dot` and `.gv` files, JSON otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let text = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("dot" | "gv") => self.to_dot(),
+            _ => self.to_json(),
+        };
+        std::fs::write(path, text)
+    }
+}
+
+impl FrameGraphPasses {
+    pub fn from_render_graph(name: impl Into<String>, graph: &RenderGraph) -> Self {
+        let mut nodes = graph
+            .iter_nodes()
+            .map(|node| {
+                let slots = |slots: &bevy::render::render_graph::SlotInfos| {
+                    slots
+                        .iter()
+                        .map(|slot| format!("{}: {:?}", slot.name, slot.slot_type))
+                        .collect()
+                };
+                FrameGraphNode {
+                    label: format!("{:?}", node.label),
+                    type_name: node.type_name.to_owned(),
+                    inputs: slots(&node.input_slots),
+                    outputs: slots(&node.output_slots),
+                }
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.label.cmp(&b.label));
+
+        let mut edges = Vec::new();
+        for node in graph.iter_nodes() {
+            for edge in node.edges.input_edges() {
+                let slot = match edge {
+                    Edge::SlotEdge {
+                        input_node,
+                        input_index,
+                        output_node,
+                        output_index,
+                    } => {
+                        let name = |label, index: usize, output: bool| {
+                            let node = graph.get_node_state(label).ok()?;
+                            let slots = if output {
+                                &node.output_slots
+                            } else {
+                                &node.input_slots
+                            };
+                            Some(slots.get_slot(index)?.name.to_string())
+                        };
+                        Some((
+                            name(*output_node, *output_index, true).unwrap_or_default(),
+                            name(*input_node, *input_index, false).unwrap_or_default(),
+                        ))
+                    }
+                    Edge::NodeEdge { .. } => None,
+                };
+                edges.push(FrameGraphEdge {
+                    from: format!("{:?}", edge.get_output_node()),
+                    to: format!("{:?}", edge.get_input_node()),
+                    slot,
+                });
+            }
+        }
+        edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+
+        let mut sub_graphs = graph
+            .iter_sub_graphs()
+            .map(|(label, graph)| Self::from_render_graph(format!("{label:?}"), graph))
+            .collect::<Vec<_>>();
+        sub_graphs.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            name: name.into(),
+            nodes,
+            edges,
+            sub_graphs,
+        }
+    }
+
+    fn write_dot(&self, dot: &mut String, prefix: &str, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let id = |label: &str| format!("\"{prefix}{label}\"");
+        for node in &self.nodes {
+            let mut label = node.label.clone();
+            for slot in node.inputs.iter().map(|slot| format!("in {slot}")) {
+                label.push_str(&format!("\\n{slot}"));
+            }
+            for slot in node.outputs.iter().map(|slot| format!("out {slot}")) {
+                label.push_str(&format!("\\n{slot}"));
+            }
+            let _ = writeln!(dot, "{indent}{} [label=\"{label}\"];", id(&node.label));
+        }
+        for edge in &self.edges {
+            let _ = match &edge.slot {
+                Some((output, input)) => writeln!(
+                    dot,
+                    "{indent}{} -> {} [label=\"{output} -> {input}\"];",
+                    id(&edge.from),
+                    id(&edge.to)
+                ),
+                None => writeln!(
+                    dot,
+                    "{indent}{} -> {} [style=dashed];",
+                    id(&edge.from),
+                    id(&edge.to)
+                ),
+            };
+        }
+        for (index, sub_graph) in self.sub_graphs.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "{indent}subgraph cluster_{}{index} {{\n{indent}  label=\"{}\";",
+                prefix.replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+                sub_graph.name
+            );
+            sub_graph.write_dot(dot, &format!("{prefix}{}/", sub_graph.name), depth + 1);
+            let _ = writeln!(dot, "{indent}}}");
+        }
+    }
+}
+
+impl FrameGraphCapture {
+    /// Capture the graph of the next rendered frame
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::Release);
+    }
+}
+
+impl Plugin for FrameGraphPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        let capture = FrameGraphCapture {
+            requested: Arc::new(AtomicBool::new(false)),
+            sender,
+        };
+        app.insert_resource(capture.clone())
+            .insert_resource(FrameGraphReceiver(Mutex::new(receiver)))
+            .add_message::<FrameGraphCaptured>()
+            .add_systems(First, receive_frame_graphs);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(capture)
+            .add_systems(Render, capture_frame_graph.in_set(RenderSystems::Cleanup));
+    }
+}
+
+fn capture_frame_graph(
+    capture: Res<FrameGraphCapture>,
+    graph: Res<RenderGraph>,
+    cameras: Query<(&MainEntity, &ExtractedCamera)>,
+) {
+    if !capture.requested.swap(false, Ordering::AcqRel) {
+        return;
+    }
+    let mut views = cameras
+        .iter()
+        .map(|(main_entity, camera)| FrameGraphView {
+            camera: main_entity.id(),
+            order: camera.order,
+            graph: format!("{:?}", camera.render_graph),
+            target: camera.target.as_ref().map(|target| format!("{target:?}")),
+            target_size: camera.physical_target_size,
+            hdr: camera.hdr,
+            msaa_writeback: camera.msaa_writeback,
+        })
+        .collect::<Vec<_>>();
+    views.sort_by_key(|view| (view.order, view.camera));
+    let _ = capture.sender.send(FrameGraph {
+        graph: FrameGraphPasses::from_render_graph("main", &graph),
+        views,
+    });
+}
+
+fn receive_frame_graphs(
+    receiver: Res<FrameGraphReceiver>,
+    mut captured: MessageWriter<FrameGraphCaptured>,
+) {
+    let Ok(receiver) = receiver.0.lock() else {
+        return;
+    };
+    for graph in receiver.try_iter() {
+        captured.write(FrameGraphCaptured(graph));
+    }
+}
@@ -0,0 +1,25 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::{globals, view},
+}
+
+const TAU: f32 = 6.28318530718;
+
+struct FresnelSettings {
+    color: vec4<f32>,
+    power: f32,
+    pulse_speed: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> fresnel: FresnelSettings;
+
+// Rim light on the surfaces facing away from the viewer, blended over the mesh
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let view_direction = normalize(view.world_position - in.world_position.xyz);
+    let facing = saturate(dot(normalize(in.world_normal), view_direction));
+    let rim = pow(1.0 - facing, fresnel.power);
+    // Between half and full strength, steady without a pulse speed
+    let pulse = 0.75 + 0.25 * cos(globals.time * fresnel.pulse_speed * TAU);
+    return vec4(fresnel.color.rgb, fresnel.color.a * rim * pulse);
+}
@@ -0,0 +1,123 @@
+use bevy::{
+    anti_alias::{fxaa::Fxaa, taa::TemporalAntiAliasing},
+    core_pipeline::prepass::{DeferredPrepass, DepthPrepass},
+    pbr::DefaultOpaqueRendererMethod,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::ManagedDepthPrepass;
+
+/// Where opaque materials are lit
+///
+/// With [`GBufferLayout::Deferred`] opaque and alpha masked materials write
+/// Bevy's deferred gbuffer in the prepass and are lit once per pixel in a
+/// fullscreen pass, which pays off with many lights. The layout of that
+/// gbuffer is fixed by Bevy's deferred shaders, it is not generated here.
+/// Materials are prepared again when the layout changes, and materials with
+/// their own `opaque_render_method` keep it.
+///
+/// A deferred gbuffer can not be multisampled, so deferred cameras lose MSAA.
+/// Cameras without FXAA or TAA of their own get FXAA instead, which is
+/// removed again with the layout.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GBufferLayout {
+    /// No gbuffer, materials are lit in the main pass
+    #[default]
+    Forward,
+    /// Bevy's deferred gbuffer, a single `Rgba32Uint` target: base color and
+    /// emissive as 8 bit per channel, the normal octahedral encoded in 24 bits
+    /// and perceptual roughness, metallic, reflectance and occlusion in one
+    /// word
+    #[serde(alias = "Packed")]
+    Deferred,
+}
+
+/// `DeferredPrepass` inserted by [`GBufferPlugin`], which may remove it again
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct ManagedDeferredPrepass {
+    /// FXAA was inserted in place of MSAA
+    fxaa: bool,
+}
+
+/// Applies [`GBufferLayout`] to 3D cameras and materials
+#[derive(Debug, Default)]
+pub struct GBufferPlugin;
+
+impl Plugin for GBufferPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GBufferLayout>()
+            .init_resource::<DefaultOpaqueRendererMethod>()
+            .add_systems(PostUpdate, (apply_gbuffer_layout, reprepare_materials));
+    }
+}
+
+impl GBufferLayout {
+    pub fn is_deferred(&self) -> bool {
+        *self != Self::Forward
+    }
+}
+
+/// Deferred prepasses added by hand are never removed
+#[allow(clippy::type_complexity)]
+fn apply_gbuffer_layout(
+    mut commands: Commands,
+    layout: Res<GBufferLayout>,
+    cameras: Query<
+        (
+            Entity,
+            Has<DeferredPrepass>,
+            Option<&ManagedDeferredPrepass>,
+            Has<DepthPrepass>,
+            Has<Fxaa>,
+            Has<TemporalAntiAliasing>,
+        ),
+        With<Camera3d>,
+    >,
+) {
+    let deferred = layout.is_deferred();
+    for (entity, has_prepass, managed, has_depth, has_fxaa, has_taa) in cameras.iter() {
+        if deferred && !has_prepass {
+            let fxaa = !has_fxaa && !has_taa;
+            let mut entity = commands.entity(entity);
+            entity.insert((DeferredPrepass, ManagedDeferredPrepass { fxaa }));
+            // Deferred lighting reads the depth of the prepass
+            if !has_depth {
+                entity.insert((DepthPrepass, ManagedDepthPrepass));
+            }
+            if fxaa {
+                entity.insert(Fxaa::default());
+            }
+        } else if let Some(managed) = managed.filter(|_| !deferred) {
+            let mut entity = commands.entity(entity);
+            entity.remove::<(DeferredPrepass, ManagedDeferredPrepass)>();
+            if managed.fxaa {
+                entity.remove::<Fxaa>();
+            }
+        }
+    }
+}
+
+/// Materials resolve the opaque render method when they are prepared, so
+/// they are marked changed to pick up a new layout
+fn reprepare_materials(
+    layout: Res<GBufferLayout>,
+    mut method: ResMut<DefaultOpaqueRendererMethod>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+    if layout.is_deferred() {
+        method.set_to_deferred();
+    } else {
+        method.set_to_forward();
+    }
+    if let Some(mut materials) = materials {
+        let ids: Vec<_> = materials.ids().collect();
+        for id in ids {
+            // Marks the material modified
+            materials.get_mut(id);
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::{borrow::Cow, collections::HashMap, error::Error, f32::consts::PI, fmt, path::Path};
+
+use bevy::{
+    asset::UntypedAssetId,
+    mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+    prelude::*,
+};
+use serde_json::{json, Map, Value};
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Writes spawned entities to a binary glTF file for DCC tools
+///
+/// Exports the hierarchy below the roots with names, transforms, meshes,
+/// `StandardMaterial` values, lights and cameras. Meshes and materials
+/// shared by entities are written once, and the asset path they were loaded
+/// from is kept in their `extras.source`. Textures are not embedded, their
+/// paths are kept in the material extras.
+#[derive(Debug, Clone, Default)]
+pub struct GltfExporter {
+    /// Exported entities, every root entity with a transform if `None`
+    pub roots: Option<Vec<Entity>>,
+}
+
+#[derive(Debug)]
+pub enum GltfExportError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Glb(gltf::Error),
+}
+
+#[derive(Default)]
+struct GltfWriter {
+    nodes: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    accessors: Vec<Value>,
+    buffer_views: Vec<Value>,
+    cameras: Vec<Value>,
+    lights: Vec<Value>,
+    extensions_used: Vec<&'static str>,
+    buffer: Vec<u8>,
+    mesh_indices: HashMap<(AssetId<Mesh>, Option<AssetId<StandardMaterial>>), usize>,
+    material_indices: HashMap<AssetId<StandardMaterial>, usize>,
+}
+
+impl GltfExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_roots(mut self, roots: impl IntoIterator<Item = Entity>) -> Self {
+        self.roots = Some(roots.into_iter().collect());
+        self
+    }
+
+    /// `.glb` file contents
+    pub fn export(&self, world: &mut World) -> Result<Vec<u8>, GltfExportError> {
+        let roots = match &self.roots {
+            Some(roots) => roots.clone(),
+            None => world
+                .query_filtered::<Entity, (With<Transform>, Without<ChildOf>)>()
+                .iter(world)
+                .collect(),
+        };
+        let mut writer = GltfWriter::default();
+        let scene_nodes = roots
+            .into_iter()
+            .filter_map(|root| writer.write_node(world, root))
+            .collect::<Vec<_>>();
+        writer.finish(scene_nodes)
+    }
+
+    pub fn save(&self, world: &mut World, path: impl AsRef<Path>) -> Result<(), GltfExportError> {
+        std::fs::write(path, self.export(world)?)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for GltfExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not write glTF file: {error}"),
+            Self::Json(error) => write!(f, "Could not encode glTF JSON: {error}"),
+            Self::Glb(error) => write!(f, "Could not encode GLB: {error}"),
+        }
+    }
+}
+
+impl Error for GltfExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Json(error) => Some(error),
+            Self::Glb(error) => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for GltfExportError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for GltfExportError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl GltfWriter {
+    fn write_node(&mut self, world: &World, entity: Entity) -> Option<usize> {
+        let entity_ref = world.get_entity(entity).ok()?;
+        let transform = entity_ref.get::<Transform>()?;
+        let mut node = Map::new();
+        if let Some(name) = entity_ref.get::<Name>() {
+            node.insert("name".into(), json!(name.as_str()));
+        }
+        node.insert(
+            "translation".into(),
+            json!(transform.translation.to_array()),
+        );
+        node.insert("rotation".into(), json!(transform.rotation.to_array()));
+        node.insert("scale".into(), json!(transform.scale.to_array()));
+
+        if let Some(mesh) = entity_ref.get::<Mesh3d>() {
+            let material = entity_ref
+                .get::<MeshMaterial3d<StandardMaterial>>()
+                .map(|material| material.id());
+            if let Some(mesh) = self.write_mesh(world, mesh.id(), material) {
+                node.insert("mesh".into(), json!(mesh));
+            }
+        }
+        if let Some(light) = light_json(&entity_ref) {
+            self.use_extension("KHR_lights_punctual");
+            self.lights.push(light);
+            node.insert(
+                "extensions".into(),
+                json!({ "KHR_lights_punctual": { "light": self.lights.len() - 1 } }),
+            );
+        }
+        if let Some(projection) = entity_ref
+            .get::<Projection>()
+            .filter(|_| entity_ref.contains::<Camera>())
+        {
+            if let Some(camera) = camera_json(projection) {
+                self.cameras.push(camera);
+                node.insert("camera".into(), json!(self.cameras.len() - 1));
+            }
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(Value::Null);
+        let children = entity_ref
+            .get::<Children>()
+            .map(|children| {
+                children
+                    .iter()
+                    .filter_map(|child| self.write_node(world, child))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if !children.is_empty() {
+            node.insert("children".into(), json!(children));
+        }
+        self.nodes[index] = Value::Object(node);
+        Some(index)
+    }
+
+    fn write_mesh(
+        &mut self,
+        world: &World,
+        id: AssetId<Mesh>,
+        material: Option<AssetId<StandardMaterial>>,
+    ) -> Option<usize> {
+        if let Some(index) = self.mesh_indices.get(&(id, material)) {
+            return Some(*index);
+        }
+        let mesh = world.get_resource::<Assets<Mesh>>()?.get(id)?;
+        let mode = match mesh.primitive_topology() {
+            PrimitiveTopology::PointList => 0,
+            PrimitiveTopology::LineList => 1,
+            PrimitiveTopology::LineStrip => 3,
+            PrimitiveTopology::TriangleList => 4,
+            PrimitiveTopology::TriangleStrip => 5,
+        };
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            warn!("Mesh {id} has no positions, not exported");
+            return None;
+        };
+
+        let mut attributes = Map::new();
+        let position = self.write_accessor(
+            float_bytes(positions.iter().flatten()),
+            positions.len(),
+            "VEC3",
+            FLOAT,
+            ARRAY_BUFFER,
+        );
+        let min = positions
+            .iter()
+            .fold(Vec3::MAX, |min, p| min.min(Vec3::from(*p)));
+        let max = positions
+            .iter()
+            .fold(Vec3::MIN, |max, p| max.max(Vec3::from(*p)));
+        self.accessors[position]["min"] = json!(min.to_array());
+        self.accessors[position]["max"] = json!(max.to_array());
+        attributes.insert("POSITION".into(), json!(position));
+        for (name, attribute) in [
+            ("NORMAL", Mesh::ATTRIBUTE_NORMAL),
+            ("TANGENT", Mesh::ATTRIBUTE_TANGENT),
+            ("TEXCOORD_0", Mesh::ATTRIBUTE_UV_0),
+            ("TEXCOORD_1", Mesh::ATTRIBUTE_UV_1),
+            ("COLOR_0", Mesh::ATTRIBUTE_COLOR),
+        ] {
+            let (bytes, count, ty) = match mesh.attribute(attribute) {
+                Some(VertexAttributeValues::Float32x2(values)) => {
+                    (float_bytes(values.iter().flatten()), values.len(), "VEC2")
+                }
+                Some(VertexAttributeValues::Float32x3(values)) => {
+                    (float_bytes(values.iter().flatten()), values.len(), "VEC3")
+                }
+                Some(VertexAttributeValues::Float32x4(values)) => {
+                    (float_bytes(values.iter().flatten()), values.len(), "VEC4")
+                }
+                _ => continue,
+            };
+            let accessor = self.write_accessor(bytes, count, ty, FLOAT, ARRAY_BUFFER);
+            attributes.insert(name.into(), json!(accessor));
+        }
+
+        let mut primitive = Map::new();
+        primitive.insert("attributes".into(), Value::Object(attributes));
+        primitive.insert("mode".into(), json!(mode));
+        if let Some(indices) = mesh.indices() {
+            let (bytes, component_type) = match indices {
+                Indices::U16(indices) => (
+                    indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+                    UNSIGNED_SHORT,
+                ),
+                Indices::U32(indices) => (
+                    indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+                    UNSIGNED_INT,
+                ),
+            };
+            let accessor = self.write_accessor(
+                bytes,
+                indices.len(),
+                "SCALAR",
+                component_type,
+                ELEMENT_ARRAY_BUFFER,
+            );
+            primitive.insert("indices".into(), json!(accessor));
+        }
+        if let Some(material) = material.and_then(|material| self.write_material(world, material)) {
+            primitive.insert("material".into(), json!(material));
+        }
+
+        let mut gltf_mesh = json!({ "primitives": [primitive] });
+        if let Some(source) = asset_source(world, id.untyped()) {
+            gltf_mesh["extras"] = json!({ "source": source });
+        }
+        self.meshes.push(gltf_mesh);
+        let index = self.meshes.len() - 1;
+        self.mesh_indices.insert((id, material), index);
+        Some(index)
+    }
+
+    fn write_material(&mut self, world: &World, id: AssetId<StandardMaterial>) -> Option<usize> {
+        if let Some(index) = self.material_indices.get(&id) {
+            return Some(*index);
+        }
+        let material = world.get_resource::<Assets<StandardMaterial>>()?.get(id)?;
+        let base_color = material.base_color.to_linear();
+        let emissive = material.emissive;
+        let mut value = json!({
+            "pbrMetallicRoughness": {
+                "baseColorFactor": base_color.to_f32_array(),
+                "metallicFactor": material.metallic,
+                "roughnessFactor": material.perceptual_roughness,
+            },
+            "emissiveFactor": [emissive.red, emissive.green, emissive.blue],
+            "doubleSided": material.double_sided,
+        });
+        match material.alpha_mode {
+            AlphaMode::Opaque => {}
+            AlphaMode::Mask(cutoff) => {
+                value["alphaMode"] = json!("MASK");
+                value["alphaCutoff"] = json!(cutoff);
+            }
+            _ => value["alphaMode"] = json!("BLEND"),
+        }
+        let mut extensions = Map::new();
+        if material.unlit {
+            self.use_extension("KHR_materials_unlit");
+            extensions.insert("KHR_materials_unlit".into(), json!({}));
+        }
+        if !material.unlit && material.specular_transmission > 0.0 {
+            self.use_extension("KHR_materials_transmission");
+            self.use_extension("KHR_materials_volume");
+            self.use_extension("KHR_materials_ior");
+            let attenuation = material.attenuation_color.to_linear();
+            let mut volume = json!({
+                "thicknessFactor": material.thickness,
+                "attenuationColor": [attenuation.red, attenuation.green, attenuation.blue],
+            });
+            // Infinite distance is the default and can't be written as JSON
+            if material.attenuation_distance.is_finite() {
+                volume["attenuationDistance"] = json!(material.attenuation_distance);
+            }
+            extensions.insert(
+                "KHR_materials_transmission".into(),
+                json!({ "transmissionFactor": material.specular_transmission }),
+            );
+            extensions.insert("KHR_materials_volume".into(), volume);
+            extensions.insert("KHR_materials_ior".into(), json!({ "ior": material.ior }));
+        }
+        if !material.unlit && material.clearcoat > 0.0 {
+            self.use_extension("KHR_materials_clearcoat");
+            extensions.insert(
+                "KHR_materials_clearcoat".into(),
+                json!({
+                    "clearcoatFactor": material.clearcoat,
+                    "clearcoatRoughnessFactor": material.clearcoat_perceptual_roughness,
+                }),
+            );
+        }
+        if !material.unlit && material.anisotropy_strength > 0.0 {
+            self.use_extension("KHR_materials_anisotropy");
+            extensions.insert(
+                "KHR_materials_anisotropy".into(),
+                json!({
+                    "anisotropyStrength": material.anisotropy_strength,
+                    "anisotropyRotation": material.anisotropy_rotation,
+                }),
+            );
+        }
+        if !extensions.is_empty() {
+            value["extensions"] = Value::Object(extensions);
+        }
+        let mut extras = Map::new();
+        if let Some(source) = asset_source(world, id.untyped()) {
+            extras.insert("source".into(), json!(source));
+        }
+        for (name, texture) in [
+            ("baseColorTexture", &material.base_color_texture),
+            ("normalTexture", &material.normal_map_texture),
+            (
+                "metallicRoughnessTexture",
+                &material.metallic_roughness_texture,
+            ),
+            ("emissiveTexture", &material.emissive_texture),
+            ("occlusionTexture", &material.occlusion_texture),
+        ] {
+            if let Some(path) = texture
+                .as_ref()
+                .and_then(|texture| asset_source(world, texture.id().untyped()))
+            {
+                extras.insert(name.into(), json!(path));
+            }
+        }
+        if !extras.is_empty() {
+            value["extras"] = Value::Object(extras);
+        }
+        self.materials.push(value);
+        let index = self.materials.len() - 1;
+        self.material_indices.insert(id, index);
+        Some(index)
+    }
+
+    fn write_accessor(
+        &mut self,
+        bytes: Vec<u8>,
+        count: usize,
+        ty: &str,
+        component_type: u32,
+        target: u32,
+    ) -> usize {
+        let offset = self.buffer.len();
+        self.buffer.extend_from_slice(&bytes);
+        // Accessors must be aligned to their component size
+        self.buffer.resize(self.buffer.len().next_multiple_of(4), 0);
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.accessors.push(json!({
+            "bufferView": self.buffer_views.len() - 1,
+            "componentType": component_type,
+            "count": count,
+            "type": ty,
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn use_extension(&mut self, extension: &'static str) {
+        if !self.extensions_used.contains(&extension) {
+            self.extensions_used.push(extension);
+        }
+    }
+
+    fn finish(self, scene_nodes: Vec<usize>) -> Result<Vec<u8>, GltfExportError> {
+        let mut root = json!({
+            "asset": { "version": "2.0", "generator": "OpenXRDS" },
+            "scene": 0,
+            "scenes": [{ "nodes": scene_nodes }],
+            "nodes": self.nodes,
+        });
+        for (key, values) in [
+            ("meshes", self.meshes),
+            ("materials", self.materials),
+            ("accessors", self.accessors),
+            ("bufferViews", self.buffer_views),
+            ("cameras", self.cameras),
+        ] {
+            if !values.is_empty() {
+                root[key] = Value::Array(values);
+            }
+        }
+        if !self.lights.is_empty() {
+            root["extensions"] = json!({ "KHR_lights_punctual": { "lights": self.lights } });
+        }
+        if !self.extensions_used.is_empty() {
+            root["extensionsUsed"] = json!(self.extensions_used);
+        }
+        if !self.buffer.is_empty() {
+            root["buffers"] = json!([{ "byteLength": self.buffer.len() }]);
+        }
+
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                // Computed when written
+                length: 0,
+            },
+            json: Cow::Owned(serde_json::to_vec(&root)?),
+            bin: (!self.buffer.is_empty()).then_some(Cow::Owned(self.buffer)),
+        };
+        glb.to_vec().map_err(GltfExportError::Glb)
+    }
+}
+
+fn float_bytes<'a>(values: impl Iterator<Item = &'a f32>) -> Vec<u8> {
+    values.flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn asset_source(world: &World, id: UntypedAssetId) -> Option<String> {
+    let path = world.get_resource::<AssetServer>()?.get_path(id)?;
+    Some(path.to_string())
+}
+
+/// KHR_lights_punctual light, which uses candela for point and spot lights
+fn light_json(entity: &EntityRef) -> Option<Value> {
+    let color = |color: Color| {
+        let color = color.to_linear();
+        [color.red, color.green, color.blue]
+    };
+    if let Some(light) = entity.get::<PointLight>() {
+        Some(json!({
+            "type": "point",
+            "color": color(light.color),
+            "intensity": light.intensity / (4.0 * PI),
+            "range": light.range,
+        }))
+    } else if let Some(light) = entity.get::<SpotLight>() {
+        Some(json!({
+            "type": "spot",
+            "color": color(light.color),
+            "intensity": light.intensity / (4.0 * PI),
+            "range": light.range,
+            "spot": {
+                "innerConeAngle": light.inner_angle,
+                "outerConeAngle": light.outer_angle,
+            },
+        }))
+    } else {
+        entity.get::<DirectionalLight>().map(|light| {
+            json!({
+                "type": "directional",
+                "color": color(light.color),
+                "intensity": light.illuminance,
+            })
+        })
+    }
+}
+
+fn camera_json(projection: &Projection) -> Option<Value> {
+    match projection {
+        Projection::Perspective(projection) => Some(json!({
+            "type": "perspective",
+            "perspective": {
+                "yfov": projection.fov,
+                "aspectRatio": projection.aspect_ratio,
+                "znear": projection.near,
+                "zfar": projection.far,
+            },
+        })),
+        Projection::Orthographic(projection) => Some(json!({
+            "type": "orthographic",
+            "orthographic": {
+                "xmag": projection.area.width() * 0.5,
+                "ymag": projection.area.height() * 0.5,
+                "znear": projection.near,
+                "zfar": projection.far,
+            },
+        })),
+        Projection::Custom(_) => None,
+    }
+}
@@ -0,0 +1,434 @@
+use std::{fmt, path::Path};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{CompressedImageFormats, ImageSampler, ImageType},
+    prelude::*,
+};
+use gltf::{
+    image::Source,
+    mesh::{Mode, Semantic},
+};
+
+/// Extensions read by the glTF loader
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "KHR_lights_punctual",
+    "KHR_materials_anisotropy",
+    "KHR_materials_clearcoat",
+    "KHR_materials_emissive_strength",
+    "KHR_materials_ior",
+    "KHR_materials_sheen",
+    "KHR_materials_specular",
+    "KHR_materials_transmission",
+    "KHR_materials_unlit",
+    "KHR_materials_variants",
+    "KHR_materials_volume",
+    "KHR_texture_transform",
+];
+
+/// Largest texture width or height before it is reported as oversized
+pub const MAX_TEXTURE_SIZE: u32 = 4096;
+
+/// Contents of a glTF file, for vetting assets before deployment
+///
+/// The `Display` output lists the scene tree, materials, textures with an
+/// estimate of their texture memory, and the warnings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GltfInspection {
+    pub scenes: Vec<GltfSceneInfo>,
+    pub materials: Vec<GltfMaterialInfo>,
+    pub textures: Vec<GltfTextureInfo>,
+    pub warnings: Vec<GltfWarning>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GltfSceneInfo {
+    pub name: Option<String>,
+    pub nodes: Vec<GltfNodeInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GltfNodeInfo {
+    pub name: Option<String>,
+    pub mesh: Option<usize>,
+    pub children: Vec<GltfNodeInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfMaterialInfo {
+    pub name: Option<String>,
+    pub alpha_mode: gltf::material::AlphaMode,
+    pub double_sided: bool,
+    pub unlit: bool,
+    pub normal_map: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GltfTextureInfo {
+    pub name: Option<String>,
+    /// URI of the image, or the buffer view it is stored in
+    pub source: String,
+    /// Unknown if the image could not be read
+    pub size: Option<UVec2>,
+    /// Texture memory of all mip levels
+    pub bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GltfWarning {
+    /// Normal mapped primitive without tangents, they are generated at load
+    MissingTangents {
+        mesh: usize,
+        primitive: usize,
+    },
+    /// Extension the loader ignores
+    UnsupportedExtension(String),
+    /// Extension the file cannot be loaded without
+    UnsupportedRequiredExtension(String),
+    UnreadableImage {
+        image: usize,
+        reason: String,
+    },
+    /// Primitive the loader cannot load, which fails the whole file
+    UnsupportedPrimitiveMode {
+        mesh: usize,
+        primitive: usize,
+        mode: Mode,
+    },
+    /// Primitive without normals, flat normals are generated at load
+    MissingNormals {
+        mesh: usize,
+        primitive: usize,
+    },
+    /// Texture file that does not exist
+    MissingTexture(String),
+    /// Texture larger than [`MAX_TEXTURE_SIZE`]
+    OversizedTexture {
+        texture: String,
+        size: UVec2,
+    },
+}
+
+impl GltfInspection {
+    /// Inspects the document without reading its images
+    pub fn from_document(document: &gltf::Document) -> Self {
+        let mut inspection = Self {
+            scenes: document
+                .scenes()
+                .map(|scene| GltfSceneInfo {
+                    name: scene.name().map(str::to_owned),
+                    nodes: scene.nodes().map(|node| node_info(&node)).collect(),
+                })
+                .collect(),
+            materials: document
+                .materials()
+                .map(|material| GltfMaterialInfo {
+                    name: material.name().map(str::to_owned),
+                    alpha_mode: material.alpha_mode(),
+                    double_sided: material.double_sided(),
+                    unlit: material.unlit(),
+                    normal_map: material.normal_texture().is_some(),
+                })
+                .collect(),
+            textures: document
+                .images()
+                .map(|image| GltfTextureInfo {
+                    name: image.name().map(str::to_owned),
+                    source: match image.source() {
+                        Source::Uri { uri, .. } if uri.starts_with("data:") => {
+                            "data URI".to_owned()
+                        }
+                        Source::Uri { uri, .. } => uri.to_owned(),
+                        Source::View { view, .. } => format!("buffer view {}", view.index()),
+                    },
+                    size: None,
+                    bytes: None,
+                })
+                .collect(),
+            warnings: Vec::new(),
+        };
+
+        for mesh in document.meshes() {
+            for primitive in mesh.primitives() {
+                let (mesh, primitive_index) = (mesh.index(), primitive.index());
+                match primitive.mode() {
+                    mode @ (Mode::LineLoop | Mode::TriangleFan) => {
+                        inspection
+                            .warnings
+                            .push(GltfWarning::UnsupportedPrimitiveMode {
+                                mesh,
+                                primitive: primitive_index,
+                                mode,
+                            });
+                    }
+                    Mode::Triangles | Mode::TriangleStrip
+                        if primitive.get(&Semantic::Normals).is_none() =>
+                    {
+                        inspection.warnings.push(GltfWarning::MissingNormals {
+                            mesh,
+                            primitive: primitive_index,
+                        });
+                    }
+                    _ => {}
+                }
+                let normal_mapped = primitive.material().normal_texture().is_some();
+                if normal_mapped && primitive.get(&Semantic::Tangents).is_none() {
+                    inspection.warnings.push(GltfWarning::MissingTangents {
+                        mesh,
+                        primitive: primitive_index,
+                    });
+                }
+            }
+        }
+        let required = document.extensions_required().collect::<Vec<_>>();
+        for extension in document.extensions_used() {
+            if SUPPORTED_EXTENSIONS.contains(&extension) {
+                continue;
+            }
+            inspection.warnings.push(if required.contains(&extension) {
+                GltfWarning::UnsupportedRequiredExtension(extension.to_owned())
+            } else {
+                GltfWarning::UnsupportedExtension(extension.to_owned())
+            });
+        }
+        inspection
+    }
+
+    /// Inspects a `.gltf` or `.glb` file, reading images to estimate their memory
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let gltf = gltf::Gltf::open(path)?;
+        let mut inspection = Self::from_document(&gltf.document);
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let buffers = gltf
+            .document
+            .buffers()
+            .map(|buffer| buffer_data(&buffer, gltf.blob.as_deref(), &dir))
+            .collect::<Vec<_>>();
+        for image in gltf.document.images() {
+            let (data, mime_type) = match image.source() {
+                Source::View { view, mime_type } => (
+                    buffers[view.buffer().index()].as_ref().and_then(|buffer| {
+                        buffer
+                            .get(view.offset()..view.offset() + view.length())
+                            .map(<[u8]>::to_vec)
+                    }),
+                    Some(mime_type),
+                ),
+                Source::Uri { uri, mime_type } if !uri.starts_with("data:") => {
+                    (std::fs::read(dir.join(uri)).ok(), mime_type)
+                }
+                Source::Uri { .. } => (None, None),
+            };
+            let source = &inspection.textures[image.index()].source;
+            let Some(data) = data else {
+                inspection.warnings.push(match image.source() {
+                    Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+                        GltfWarning::MissingTexture(uri.to_owned())
+                    }
+                    _ => GltfWarning::UnreadableImage {
+                        image: image.index(),
+                        reason: "image data not found".to_owned(),
+                    },
+                });
+                continue;
+            };
+            let extension = Path::new(source)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or_default();
+            let image_type = match mime_type {
+                Some(mime_type) => ImageType::MimeType(mime_type),
+                None => ImageType::Extension(extension),
+            };
+            let decoded = Image::from_buffer(
+                &data,
+                image_type,
+                CompressedImageFormats::all(),
+                true,
+                ImageSampler::Default,
+                RenderAssetUsages::default(),
+            );
+            match decoded {
+                Ok(decoded) => {
+                    let size = decoded.size();
+                    if size.max_element() > MAX_TEXTURE_SIZE {
+                        inspection.warnings.push(GltfWarning::OversizedTexture {
+                            texture: source.clone(),
+                            size,
+                        });
+                    }
+                    let texture = &mut inspection.textures[image.index()];
+                    texture.size = Some(size);
+                    texture.bytes = Some(decoded.data.as_ref().map_or(0, Vec::len));
+                }
+                Err(error) => inspection.warnings.push(GltfWarning::UnreadableImage {
+                    image: image.index(),
+                    reason: error.to_string(),
+                }),
+            }
+        }
+        Ok(inspection)
+    }
+
+    /// Estimated texture memory of the images that could be read
+    pub fn texture_memory(&self) -> usize {
+        self.textures
+            .iter()
+            .filter_map(|texture| texture.bytes)
+            .sum()
+    }
+}
+
+impl fmt::Display for GltfInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, scene) in self.scenes.iter().enumerate() {
+            writeln!(f, "Scene {index} {}", scene.name.as_deref().unwrap_or(""))?;
+            for node in &scene.nodes {
+                write_node(f, node, 1)?;
+            }
+        }
+        writeln!(f, "Materials ({})", self.materials.len())?;
+        for (index, material) in self.materials.iter().enumerate() {
+            writeln!(
+                f,
+                "  {index} {} {:?}{}{}{}",
+                material.name.as_deref().unwrap_or(""),
+                material.alpha_mode,
+                if material.double_sided {
+                    " double sided"
+                } else {
+                    ""
+                },
+                if material.unlit { " unlit" } else { "" },
+                if material.normal_map {
+                    " normal map"
+                } else {
+                    ""
+                },
+            )?;
+        }
+        writeln!(
+            f,
+            "Textures ({}, {:.1} MiB)",
+            self.textures.len(),
+            self.texture_memory() as f64 / (1024.0 * 1024.0)
+        )?;
+        for (index, texture) in self.textures.iter().enumerate() {
+            write!(f, "  {index} {}", texture.source)?;
+            if let (Some(size), Some(bytes)) = (texture.size, texture.bytes) {
+                write!(f, " {}x{} {:.1} KiB", size.x, size.y, bytes as f64 / 1024.0)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "Warnings ({})", self.warnings.len())?;
+        for warning in &self.warnings {
+            writeln!(f, "  {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GltfWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingTangents { mesh, primitive } => write!(
+                f,
+                "Mesh {mesh} primitive {primitive} has a normal map but no tangents, they are generated at load"
+            ),
+            Self::UnsupportedExtension(extension) => {
+                write!(f, "Extension {extension} is not supported and ignored")
+            }
+            Self::UnsupportedRequiredExtension(extension) => write!(
+                f,
+                "Extension {extension} is required but not supported, the file cannot be loaded"
+            ),
+            Self::UnreadableImage { image, reason } => {
+                write!(f, "Image {image} cannot be read: {reason}")
+            }
+            Self::UnsupportedPrimitiveMode {
+                mesh,
+                primitive,
+                mode,
+            } => write!(
+                f,
+                "Mesh {mesh} primitive {primitive} uses the unsupported mode {mode:?}, the file cannot be loaded"
+            ),
+            Self::MissingNormals { mesh, primitive } => write!(
+                f,
+                "Mesh {mesh} primitive {primitive} has no normals, flat normals are generated at load"
+            ),
+            Self::MissingTexture(path) => write!(f, "Texture {path} does not exist"),
+            Self::OversizedTexture { texture, size } => write!(
+                f,
+                "Texture {texture} is {}x{}, larger than {MAX_TEXTURE_SIZE}",
+                size.x, size.y
+            ),
+        }
+    }
+}
+
+impl GltfWarning {
+    /// How to fix the file in the authoring tool or exporter
+    pub fn fix(&self) -> &'static str {
+        match self {
+            Self::MissingTangents { .. } => "Export tangents to skip generating them at load",
+            Self::UnsupportedExtension(_) => "Export without the extension",
+            Self::UnsupportedRequiredExtension(_) => {
+                "Export without the extension, e.g. without mesh or texture compression"
+            }
+            Self::UnreadableImage { .. } => "Convert the image to PNG, JPEG or KTX2",
+            Self::UnsupportedPrimitiveMode { .. } => "Triangulate the mesh before exporting",
+            Self::MissingNormals { .. } => "Export normals, or smooth the mesh before exporting",
+            Self::MissingTexture(_) => {
+                "Copy the texture next to the glTF file or fix its path, or embed textures"
+            }
+            Self::OversizedTexture { .. } => "Downscale the texture or split it into tiles",
+        }
+    }
+
+    /// Whether the file fails to load because of the warning
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            Self::UnsupportedRequiredExtension(_) | Self::UnsupportedPrimitiveMode { .. }
+        )
+    }
+}
+
+fn node_info(node: &gltf::Node) -> GltfNodeInfo {
+    GltfNodeInfo {
+        name: node.name().map(str::to_owned),
+        mesh: node.mesh().map(|mesh| mesh.index()),
+        children: node.children().map(|child| node_info(&child)).collect(),
+    }
+}
+
+fn write_node(f: &mut fmt::Formatter<'_>, node: &GltfNodeInfo, depth: usize) -> fmt::Result {
+    write!(
+        f,
+        "{:indent$}{}",
+        "",
+        node.name.as_deref().unwrap_or("<node>"),
+        indent = depth * 2
+    )?;
+    if let Some(mesh) = node.mesh {
+        write!(f, " (mesh {mesh})")?;
+    }
+    writeln!(f)?;
+    for child in &node.children {
+        write_node(f, child, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn buffer_data(buffer: &gltf::Buffer, blob: Option<&[u8]>, dir: &Path) -> Option<Vec<u8>> {
+    match buffer.source() {
+        gltf::buffer::Source::Bin => blob.map(<[u8]>::to_vec),
+        gltf::buffer::Source::Uri(uri) if !uri.starts_with("data:") => {
+            std::fs::read(dir.join(uri)).ok()
+        }
+        gltf::buffer::Source::Uri(_) => None,
+    }
+}
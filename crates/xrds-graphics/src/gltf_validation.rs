@@ -0,0 +1,182 @@
+use std::{collections::HashMap, fmt};
+
+use bevy::{
+    asset::{AssetPath, RecursiveDependencyLoadState},
+    gltf::{Gltf, GltfLoaderSettings},
+    prelude::*,
+};
+
+use crate::{GltfInspection, GltfWarning, MAX_TEXTURE_SIZE};
+
+/// Fixable problems of a loaded glTF file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GltfValidationReport {
+    pub path: Option<AssetPath<'static>>,
+    pub issues: Vec<GltfWarning>,
+}
+
+/// Reports of the glTF files loaded so far
+#[derive(Resource, Debug, Default)]
+pub struct GltfValidationReports(HashMap<AssetId<Gltf>, GltfValidationReport>);
+
+/// Written when the report of a glTF file and its dependencies is ready
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct GltfValidated {
+    pub gltf: AssetId<Gltf>,
+    pub report: GltfValidationReport,
+}
+
+/// Validates glTF files once they and their textures are loaded
+///
+/// Missing and oversized textures are always checked. Primitive modes,
+/// normals, tangents and extensions are checked for files loaded with
+/// `GltfLoaderSettings::include_source`, e.g. by [`load_validated_gltf`].
+/// Files with issues log one warning listing them with their fixes.
+#[derive(Debug, Default)]
+pub struct GltfValidationPlugin;
+
+/// glTF files waiting for their dependencies
+#[derive(Resource, Debug, Default)]
+struct PendingGltfValidations(Vec<AssetId<Gltf>>);
+
+impl GltfValidationReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for GltfValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{path}")?,
+            None => write!(f, "glTF")?,
+        }
+        writeln!(f, " has {} issues", self.issues.len())?;
+        for issue in &self.issues {
+            writeln!(f, "  {issue}\n    Fix: {}", issue.fix())?;
+        }
+        Ok(())
+    }
+}
+
+impl GltfValidationReports {
+    pub fn get(&self, gltf: impl Into<AssetId<Gltf>>) -> Option<&GltfValidationReport> {
+        self.0.get(&gltf.into())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AssetId<Gltf>, &GltfValidationReport)> {
+        self.0.iter().map(|(id, report)| (*id, report))
+    }
+}
+
+impl Plugin for GltfValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GltfValidationReports>()
+            .init_resource::<PendingGltfValidations>()
+            .add_message::<GltfValidated>()
+            .add_systems(
+                PostUpdate,
+                validate_gltfs.run_if(resource_exists::<Assets<Gltf>>),
+            );
+    }
+}
+
+/// Loads a glTF file with its source document, for a complete validation report
+pub fn load_validated_gltf<'a>(
+    asset_server: &AssetServer,
+    path: impl Into<AssetPath<'a>>,
+) -> Handle<Gltf> {
+    asset_server.load_with_settings(path, |settings: &mut GltfLoaderSettings| {
+        settings.include_source = true;
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn validate_gltfs(
+    mut events: MessageReader<AssetEvent<Gltf>>,
+    mut pending: ResMut<PendingGltfValidations>,
+    mut reports: ResMut<GltfValidationReports>,
+    mut validated: MessageWriter<GltfValidated>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    materials: Res<Assets<StandardMaterial>>,
+    images: Res<Assets<Image>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Added { id } | AssetEvent::Modified { id } = event {
+            if !pending.0.contains(id) {
+                pending.0.push(*id);
+            }
+        }
+    }
+
+    pending.0.retain(|id| {
+        let Some(gltf) = gltfs.get(*id) else {
+            return false;
+        };
+        // Failed dependencies are reported as missing textures
+        if matches!(
+            asset_server.get_recursive_dependency_load_state(*id),
+            Some(RecursiveDependencyLoadState::NotLoaded | RecursiveDependencyLoadState::Loading)
+        ) {
+            return true;
+        }
+
+        let mut issues = gltf
+            .source
+            .as_ref()
+            .map(|source| GltfInspection::from_document(&source.document).warnings)
+            .unwrap_or_default();
+        let mut textures = Vec::new();
+        for material in gltf
+            .materials
+            .iter()
+            .filter_map(|handle| materials.get(handle))
+        {
+            for texture in [
+                &material.base_color_texture,
+                &material.normal_map_texture,
+                &material.metallic_roughness_texture,
+                &material.emissive_texture,
+                &material.occlusion_texture,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                if !textures.contains(&texture.id()) {
+                    textures.push(texture.id());
+                }
+            }
+        }
+        for texture in textures {
+            let name = asset_server
+                .get_path(texture)
+                .map_or_else(|| format!("{texture}"), |path| path.to_string());
+            if asset_server
+                .get_load_state(texture)
+                .is_some_and(|state| state.is_failed())
+            {
+                issues.push(GltfWarning::MissingTexture(name));
+            } else if let Some(image) = images.get(texture) {
+                let size = image.size();
+                if size.max_element() > MAX_TEXTURE_SIZE {
+                    issues.push(GltfWarning::OversizedTexture {
+                        texture: name,
+                        size,
+                    });
+                }
+            }
+        }
+
+        let report = GltfValidationReport {
+            path: asset_server.get_path(*id).map(AssetPath::into_owned),
+            issues,
+        };
+        if !report.is_clean() {
+            warn!("{report}");
+        }
+        reports.0.insert(*id, report.clone());
+        validated.write(GltfValidated { gltf: *id, report });
+        false
+    });
+}
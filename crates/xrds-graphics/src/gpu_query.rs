@@ -0,0 +1,393 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_phase::TrackedRenderPass,
+        renderer::{RenderDevice, RenderQueue},
+        Render, RenderApp, RenderSystems,
+    },
+};
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Features, MapMode,
+    PipelineStatisticsTypes, PollType, QuerySet, QuerySetDescriptor, QueryType,
+};
+
+/// Frames a pool can have in flight before new queries are skipped
+const FRAMES_IN_FLIGHT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GpuQueryKind {
+    /// Number of samples passing the depth and stencil tests
+    Occlusion,
+    /// Shader invocation and primitive counts. Needs `PIPELINE_STATISTICS_QUERY`
+    PipelineStatistics,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    pub vertex_shader_invocations: u64,
+    pub clipper_invocations: u64,
+    pub clipper_primitives_out: u64,
+    pub fragment_shader_invocations: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuQueryResult {
+    Occlusion { samples: u64 },
+    PipelineStatistics(PipelineStatistics),
+}
+
+/// Latest query results by label, a few frames behind the queries
+///
+/// Besides the queries of the application's render graph nodes, this holds
+/// the occlusion queries of [`OcclusionQuery`](crate::OcclusionQuery) meshes.
+#[derive(Resource)]
+pub struct GpuQueryResults {
+    results: HashMap<String, GpuQueryResult>,
+    receiver: Mutex<Receiver<(String, GpuQueryResult)>>,
+}
+
+/// Creates query pools in the render world
+#[derive(Resource, Clone)]
+pub struct GpuQueries {
+    pools: Arc<Mutex<Vec<GpuQueryPool>>>,
+    sender: Sender<(String, GpuQueryResult)>,
+}
+
+/// Fixed size set of GPU queries of one kind
+///
+/// Queries are recorded with [`GpuQueryPool::scope`] in render graph nodes.
+/// They are resolved after the frame is submitted and their results show up
+/// in [`GpuQueryResults`] once the GPU finished the frame.
+#[derive(Clone)]
+pub struct GpuQueryPool(Arc<Mutex<QueryPool>>);
+
+/// Reads back query results and forwards them to the main world
+#[derive(Debug, Default)]
+pub struct GpuQueryPlugin;
+
+struct QueryPool {
+    kind: GpuQueryKind,
+    set: QuerySet,
+    capacity: u32,
+    resolve_buffer: Buffer,
+    frames: Vec<QueryFrame>,
+    current: usize,
+    overflow_warned: bool,
+}
+
+struct QueryFrame {
+    read_buffer: Buffer,
+    labels: Vec<String>,
+    /// Set by the map callback. `None` while the frame is recorded
+    mapped: Option<Arc<AtomicBool>>,
+}
+
+impl GpuQueryKind {
+    fn result_size(self) -> u64 {
+        match self {
+            Self::Occlusion => 8,
+            Self::PipelineStatistics => 4 * 8,
+        }
+    }
+
+    fn query_type(self) -> QueryType {
+        match self {
+            Self::Occlusion => QueryType::Occlusion,
+            Self::PipelineStatistics => QueryType::PipelineStatistics(
+                PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS
+                    | PipelineStatisticsTypes::CLIPPER_INVOCATIONS
+                    | PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT
+                    | PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS,
+            ),
+        }
+    }
+
+    pub(crate) fn parse(self, data: &[u8]) -> GpuQueryResult {
+        let values = data
+            .chunks_exact(8)
+            .map(|v| u64::from_le_bytes(v.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        match self {
+            Self::Occlusion => GpuQueryResult::Occlusion { samples: values[0] },
+            Self::PipelineStatistics => GpuQueryResult::PipelineStatistics(PipelineStatistics {
+                vertex_shader_invocations: values[0],
+                clipper_invocations: values[1],
+                clipper_primitives_out: values[2],
+                fragment_shader_invocations: values[3],
+            }),
+        }
+    }
+}
+
+impl GpuQueryResult {
+    /// Returns whether any sample passed for occlusion queries
+    pub fn is_visible(&self) -> Option<bool> {
+        match self {
+            Self::Occlusion { samples } => Some(*samples > 0),
+            Self::PipelineStatistics(_) => None,
+        }
+    }
+}
+
+impl GpuQueryResults {
+    /// Results and the sender of the render world
+    pub(crate) fn new() -> (Self, Sender<(String, GpuQueryResult)>) {
+        let (sender, receiver) = mpsc::channel();
+        (
+            Self {
+                results: HashMap::new(),
+                receiver: Mutex::new(receiver),
+            },
+            sender,
+        )
+    }
+
+    pub fn get(&self, label: &str) -> Option<&GpuQueryResult> {
+        self.results.get(label)
+    }
+
+    /// Result of the occlusion query `label`, `None` if no result arrived yet
+    pub fn is_visible(&self, label: &str) -> Option<bool> {
+        self.get(label)?.is_visible()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &GpuQueryResult)> {
+        self.results
+            .iter()
+            .map(|(label, result)| (label.as_str(), result))
+    }
+}
+
+impl GpuQueries {
+    pub fn is_supported(device: &RenderDevice, kind: GpuQueryKind) -> bool {
+        match kind {
+            GpuQueryKind::Occlusion => true,
+            GpuQueryKind::PipelineStatistics => device
+                .features()
+                .contains(Features::PIPELINE_STATISTICS_QUERY),
+        }
+    }
+
+    /// Create a pool of `capacity` queries per frame. `None` if the device does not support `kind`
+    pub fn create_pool(
+        &self,
+        device: &RenderDevice,
+        kind: GpuQueryKind,
+        capacity: u32,
+    ) -> Option<GpuQueryPool> {
+        if !Self::is_supported(device, kind) || capacity == 0 {
+            return None;
+        }
+        let device = device.wgpu_device();
+        let size = capacity as u64 * kind.result_size();
+        let set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("xrds_gpu_query_set"),
+            ty: kind.query_type(),
+            count: capacity,
+        });
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("xrds_gpu_query_resolve_buffer"),
+            size,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let frames = (0..FRAMES_IN_FLIGHT)
+            .map(|_| QueryFrame {
+                read_buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("xrds_gpu_query_read_buffer"),
+                    size,
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                labels: vec![],
+                mapped: None,
+            })
+            .collect();
+
+        let pool = GpuQueryPool(Arc::new(Mutex::new(QueryPool {
+            kind,
+            set,
+            capacity,
+            resolve_buffer,
+            frames,
+            current: 0,
+            overflow_warned: false,
+        })));
+        self.pools.lock().unwrap().push(pool.clone());
+        Some(pool)
+    }
+}
+
+impl GpuQueryPool {
+    pub fn kind(&self) -> GpuQueryKind {
+        self.0.lock().unwrap().kind
+    }
+
+    /// Query set to put in `RenderPassDescriptor::occlusion_query_set`
+    pub fn occlusion_query_set(&self) -> Option<QuerySet> {
+        let pool = self.0.lock().unwrap();
+        (pool.kind == GpuQueryKind::Occlusion).then(|| pool.set.clone())
+    }
+
+    /// Run the draws of `draw` inside a query named `label`
+    ///
+    /// Occlusion queries need a pass created with [`Self::occlusion_query_set`].
+    /// Scopes must not be nested. If the pool is full or all frames are still
+    /// in flight, the draws run without a query.
+    pub fn scope<'a, R>(
+        &self,
+        pass: &mut TrackedRenderPass<'a>,
+        label: impl Into<String>,
+        draw: impl FnOnce(&mut TrackedRenderPass<'a>) -> R,
+    ) -> R {
+        let begun = self.begin(pass, label.into());
+        let result = draw(pass);
+        if let Some(kind) = begun {
+            match kind {
+                GpuQueryKind::Occlusion => pass.wgpu_pass().end_occlusion_query(),
+                GpuQueryKind::PipelineStatistics => {
+                    pass.wgpu_pass().end_pipeline_statistics_query()
+                }
+            }
+        }
+        result
+    }
+
+    fn begin(&self, pass: &mut TrackedRenderPass, label: String) -> Option<GpuQueryKind> {
+        let mut guard = self.0.lock().unwrap();
+        let pool = &mut *guard;
+        let capacity = pool.capacity;
+        let current = pool.current;
+        let frame = &mut pool.frames[current];
+        if frame.mapped.is_some() {
+            return None;
+        }
+        if frame.labels.len() as u32 >= capacity {
+            if !pool.overflow_warned {
+                warn!("GPU query pool is full, {} queries per frame", capacity);
+                pool.overflow_warned = true;
+            }
+            return None;
+        }
+        let index = frame.labels.len() as u32;
+        frame.labels.push(label);
+        match pool.kind {
+            GpuQueryKind::Occlusion => pass.wgpu_pass().begin_occlusion_query(index),
+            GpuQueryKind::PipelineStatistics => pass
+                .wgpu_pass()
+                .begin_pipeline_statistics_query(&pool.set, index),
+        }
+        Some(pool.kind)
+    }
+
+    /// Resolve the queries of the submitted frame and start reading them back
+    fn submit(&self, device: &RenderDevice, queue: &RenderQueue) {
+        let mut pool = self.0.lock().unwrap();
+        let current = pool.current;
+        let count = pool.frames[current].labels.len() as u32;
+        if count == 0 || pool.frames[current].mapped.is_some() {
+            return;
+        }
+
+        let frame = &pool.frames[current];
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("xrds_gpu_query_resolve"),
+        });
+        encoder.resolve_query_set(&pool.set, 0..count, &pool.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &pool.resolve_buffer,
+            0,
+            &frame.read_buffer,
+            0,
+            count as u64 * pool.kind.result_size(),
+        );
+        queue.submit([encoder.finish()]);
+
+        let mapped = Arc::new(AtomicBool::new(false));
+        let callback = mapped.clone();
+        frame
+            .read_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| match result {
+                Ok(()) => callback.store(true, Ordering::Release),
+                Err(e) => warn!("Could not read back GPU queries: {}", e),
+            });
+        pool.frames[current].mapped = Some(mapped);
+        pool.current = (current + 1) % pool.frames.len();
+    }
+
+    /// Send results of frames the GPU finished
+    fn collect(&self, sender: &Sender<(String, GpuQueryResult)>) {
+        let mut pool = self.0.lock().unwrap();
+        let kind = pool.kind;
+        let size = kind.result_size() as usize;
+        for frame in pool.frames.iter_mut() {
+            let ready = frame
+                .mapped
+                .as_ref()
+                .is_some_and(|mapped| mapped.load(Ordering::Acquire));
+            if !ready {
+                continue;
+            }
+            {
+                let data = frame.read_buffer.slice(..).get_mapped_range();
+                for (label, data) in frame.labels.drain(..).zip(data.chunks_exact(size)) {
+                    let _ = sender.send((label, kind.parse(data)));
+                }
+            }
+            frame.read_buffer.unmap();
+            frame.mapped = None;
+        }
+    }
+}
+
+impl Plugin for GpuQueryPlugin {
+    fn build(&self, app: &mut App) {
+        let (results, sender) = GpuQueryResults::new();
+        app.insert_resource(results)
+            .add_systems(First, receive_gpu_query_results);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(GpuQueries {
+                pools: Arc::new(Mutex::new(vec![])),
+                sender,
+            })
+            .add_systems(Render, finish_gpu_queries.in_set(RenderSystems::Cleanup));
+    }
+}
+
+fn finish_gpu_queries(
+    queries: Res<GpuQueries>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+) {
+    // Map callbacks only run when the device is polled
+    let _ = device.poll(PollType::Poll);
+    let pools = queries.pools.lock().unwrap();
+    for pool in pools.iter() {
+        pool.collect(&queries.sender);
+        pool.submit(&device, &queue);
+    }
+}
+
+pub(crate) fn receive_gpu_query_results(mut results: ResMut<GpuQueryResults>) {
+    let results = &mut *results;
+    let Ok(receiver) = results.receiver.lock() else {
+        return;
+    };
+    for (label, result) in receiver.try_iter() {
+        results.results.insert(label, result);
+    }
+}
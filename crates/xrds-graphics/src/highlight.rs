@@ -0,0 +1,341 @@
+use bevy::{
+    asset::embedded_asset,
+    mesh::MeshVertexBufferLayoutRef,
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    render::render_resource::{
+        AsBindGroup, Face, RenderPipelineDescriptor, ShaderType, SpecializedMeshPipelineError,
+    },
+    shader::ShaderRef,
+};
+
+use crate::ObjectIdOverlay;
+
+const OUTLINE_SHADER: &str = "embedded://xrds_graphics/outline.wgsl";
+const FRESNEL_SHADER: &str = "embedded://xrds_graphics/fresnel.wgsl";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outline {
+    pub color: Color,
+    /// Width in pixels
+    pub thickness: f32,
+}
+
+/// Rim light over the surface, strongest where it faces away from the viewer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fresnel {
+    pub color: Color,
+    /// Higher values make the rim narrower
+    pub power: f32,
+    /// Pulses per second, 0 for a steady highlight
+    pub pulse_speed: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HighlightStyle {
+    pub outline: Option<Outline>,
+    pub fresnel: Option<Fresnel>,
+}
+
+/// Highlights the meshes of the entity and its descendants, e.g. a selected glTF scene
+///
+/// Outlines draw the back faces of the meshes again, pushed out along their
+/// normals, so meshes need normals and closed surfaces look best. The fresnel
+/// highlight is blended over the mesh. Both are drawn by child entities and
+/// leave the materials of the meshes as they are.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Highlight(pub HighlightStyle);
+
+/// Sets or clears the [`Highlight`] of an entity
+pub trait SetHighlight {
+    fn set_highlight(&mut self, entity: Entity, style: HighlightStyle);
+    fn clear_highlight(&mut self, entity: Entity);
+}
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct OutlineMaterial {
+    #[uniform(0)]
+    pub settings: OutlineSettings,
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy, Default, PartialEq)]
+pub struct OutlineSettings {
+    pub color: Vec4,
+    pub thickness: f32,
+}
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct FresnelMaterial {
+    #[uniform(0)]
+    pub settings: FresnelSettings,
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy, Default, PartialEq)]
+pub struct FresnelSettings {
+    pub color: Vec4,
+    pub power: f32,
+    pub pulse_speed: f32,
+}
+
+/// Child of a mesh drawing the highlight of `root`
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HighlightOverlay {
+    pub root: Entity,
+}
+
+/// Mesh with highlight overlays
+#[derive(Component, Debug, Clone, Copy)]
+struct HighlightedMesh;
+
+/// Materials shared by the overlays of a highlighted entity
+#[derive(Component, Debug, Clone, Default)]
+struct HighlightMaterials {
+    outline: Option<Handle<OutlineMaterial>>,
+    fresnel: Option<Handle<FresnelMaterial>>,
+}
+
+#[derive(Debug, Default)]
+pub struct HighlightPlugin;
+
+impl Outline {
+    pub fn new(color: Color, thickness: f32) -> Self {
+        Self { color, thickness }
+    }
+}
+
+impl Fresnel {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            power: 2.0,
+            pulse_speed: 0.0,
+        }
+    }
+
+    pub fn pulsing(mut self, pulse_speed: f32) -> Self {
+        self.pulse_speed = pulse_speed;
+        self
+    }
+}
+
+impl HighlightStyle {
+    pub fn outline(color: Color, thickness: f32) -> Self {
+        Self {
+            outline: Some(Outline::new(color, thickness)),
+            fresnel: None,
+        }
+    }
+
+    pub fn fresnel(fresnel: Fresnel) -> Self {
+        Self {
+            outline: None,
+            fresnel: Some(fresnel),
+        }
+    }
+
+    /// Orange outline with a slowly pulsing rim, for selected objects
+    pub fn selected() -> Self {
+        let color = Color::srgb(1.0, 0.6, 0.1);
+        Self {
+            outline: Some(Outline::new(color, 3.0)),
+            fresnel: Some(Fresnel::new(color.with_alpha(0.6)).pulsing(1.0)),
+        }
+    }
+
+    pub fn with_outline(mut self, outline: Outline) -> Self {
+        self.outline = Some(outline);
+        self
+    }
+
+    pub fn with_fresnel(mut self, fresnel: Fresnel) -> Self {
+        self.fresnel = Some(fresnel);
+        self
+    }
+}
+
+impl SetHighlight for World {
+    fn set_highlight(&mut self, entity: Entity, style: HighlightStyle) {
+        if let Ok(mut entity) = self.get_entity_mut(entity) {
+            entity.insert(Highlight(style));
+        }
+    }
+
+    fn clear_highlight(&mut self, entity: Entity) {
+        if let Ok(mut entity) = self.get_entity_mut(entity) {
+            entity.remove::<Highlight>();
+        }
+    }
+}
+
+impl SetHighlight for Commands<'_, '_> {
+    fn set_highlight(&mut self, entity: Entity, style: HighlightStyle) {
+        if let Ok(mut entity) = self.get_entity(entity) {
+            entity.insert(Highlight(style));
+        }
+    }
+
+    fn clear_highlight(&mut self, entity: Entity) {
+        if let Ok(mut entity) = self.get_entity(entity) {
+            entity.remove::<Highlight>();
+        }
+    }
+}
+
+impl Material for OutlineMaterial {
+    fn vertex_shader() -> ShaderRef {
+        OUTLINE_SHADER.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        OUTLINE_SHADER.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.primitive.cull_mode = Some(Face::Front);
+        Ok(())
+    }
+}
+
+impl Material for FresnelMaterial {
+    fn fragment_shader() -> ShaderRef {
+        FRESNEL_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    // Keep the highlight in front of the surface it lies on
+    fn depth_bias(&self) -> f32 {
+        1.0
+    }
+}
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "outline.wgsl");
+        embedded_asset!(app, "fresnel.wgsl");
+
+        app.add_plugins((
+            MaterialPlugin::<OutlineMaterial> {
+                prepass_enabled: false,
+                shadows_enabled: false,
+                ..Default::default()
+            },
+            MaterialPlugin::<FresnelMaterial> {
+                prepass_enabled: false,
+                shadows_enabled: false,
+                ..Default::default()
+            },
+        ))
+        .add_systems(Update, (clear_highlights, apply_highlights).chain());
+    }
+}
+
+fn clear_highlights(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Highlight>,
+    changed: Query<Entity, Changed<Highlight>>,
+    overlays: Query<(Entity, &HighlightOverlay, &ChildOf)>,
+) {
+    let roots = removed.read().chain(changed.iter()).collect::<Vec<_>>();
+    if roots.is_empty() {
+        return;
+    }
+    // Changed highlights are built again
+    for (overlay, highlight, child_of) in overlays.iter() {
+        if !roots.contains(&highlight.root) {
+            continue;
+        }
+        commands.entity(overlay).despawn();
+        if let Ok(mut mesh) = commands.get_entity(child_of.parent()) {
+            mesh.remove::<HighlightedMesh>();
+        }
+    }
+    for root in roots {
+        if let Ok(mut root) = commands.get_entity(root) {
+            root.remove::<HighlightMaterials>();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_highlights(
+    mut commands: Commands,
+    mut outline_materials: ResMut<Assets<OutlineMaterial>>,
+    mut fresnel_materials: ResMut<Assets<FresnelMaterial>>,
+    roots: Query<(Entity, &Highlight, Option<&HighlightMaterials>)>,
+    children: Query<&Children>,
+    meshes: Query<
+        &Mesh3d,
+        (
+            Without<HighlightedMesh>,
+            Without<HighlightOverlay>,
+            Without<ObjectIdOverlay>,
+        ),
+    >,
+) {
+    for (root, Highlight(style), materials) in roots.iter() {
+        let materials = match materials {
+            Some(materials) => materials.clone(),
+            None => {
+                let materials = HighlightMaterials {
+                    outline: style.outline.map(|outline| {
+                        outline_materials.add(OutlineMaterial {
+                            settings: OutlineSettings {
+                                color: outline.color.to_linear().to_vec4(),
+                                thickness: outline.thickness,
+                            },
+                        })
+                    }),
+                    fresnel: style.fresnel.map(|fresnel| {
+                        fresnel_materials.add(FresnelMaterial {
+                            settings: FresnelSettings {
+                                color: fresnel.color.to_linear().to_vec4(),
+                                power: fresnel.power,
+                                pulse_speed: fresnel.pulse_speed,
+                            },
+                        })
+                    }),
+                };
+                commands.entity(root).insert(materials.clone());
+                materials
+            }
+        };
+
+        // New descendants show up as scenes spawn
+        for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+            let Ok(mesh) = meshes.get(entity) else {
+                continue;
+            };
+            let mut mesh_entity = commands.entity(entity);
+            mesh_entity.insert(HighlightedMesh);
+            if let Some(outline) = &materials.outline {
+                mesh_entity.with_child((
+                    HighlightOverlay { root },
+                    Mesh3d(mesh.0.clone()),
+                    MeshMaterial3d(outline.clone()),
+                    Pickable::IGNORE,
+                ));
+            }
+            if let Some(fresnel) = &materials.fresnel {
+                mesh_entity.with_child((
+                    HighlightOverlay { root },
+                    Mesh3d(mesh.0.clone()),
+                    MeshMaterial3d(fresnel.clone()),
+                    Pickable::IGNORE,
+                ));
+            }
+        }
+    }
+}
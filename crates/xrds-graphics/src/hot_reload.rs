@@ -0,0 +1,72 @@
+use bevy::{asset::AssetPath, gltf::Gltf, prelude::*, shader::Shader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadKind {
+    Gltf,
+    Shader,
+}
+
+/// Written when a loaded asset changed on disk and was loaded again
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct AssetReloaded {
+    pub path: AssetPath<'static>,
+    pub kind: ReloadKind,
+}
+
+/// Reports glTF files and WGSL shaders reloaded while the app runs
+///
+/// With the `hot_reload` feature the asset server watches asset files and
+/// embedded shaders and loads changed files again. Scenes spawned from a
+/// reloaded glTF file are spawned again and pipelines using a reloaded
+/// shader are recompiled, without restarting the runtime. Shader compile
+/// errors are logged and the previous pipeline is kept.
+#[derive(Debug, Default)]
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<AssetReloaded>()
+            .add_systems(Startup, log_hot_reload)
+            .add_systems(
+                PostUpdate,
+                (
+                    report_reloads::<Gltf>(ReloadKind::Gltf)
+                        .run_if(resource_exists::<Assets<Gltf>>),
+                    report_reloads::<Shader>(ReloadKind::Shader)
+                        .run_if(resource_exists::<Assets<Shader>>),
+                ),
+            );
+    }
+}
+
+/// Whether asset files are watched for changes
+pub fn hot_reload_enabled() -> bool {
+    cfg!(feature = "hot_reload")
+}
+
+fn log_hot_reload() {
+    if hot_reload_enabled() {
+        info!("Hot reload enabled, watching glTF files and shaders for changes");
+    }
+}
+
+fn report_reloads<A: Asset>(
+    kind: ReloadKind,
+) -> impl FnMut(Res<AssetServer>, MessageReader<AssetEvent<A>>, MessageWriter<AssetReloaded>) {
+    move |asset_server, mut events, mut reloaded| {
+        for event in events.read() {
+            let AssetEvent::Modified { id } = event else {
+                continue;
+            };
+            // Assets created in code have no path
+            let Some(path) = asset_server.get_path(*id) else {
+                continue;
+            };
+            info!("Reloaded {path}");
+            reloaded.write(AssetReloaded {
+                path: path.into_owned(),
+                kind,
+            });
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::{prelude::*, ui::UiSystems};
+
+/// Camera that the HUD is drawn on
+///
+/// HUD elements are Bevy UI nodes, so text is laid out and cached in glyph
+/// atlases by Bevy and composited in the UI pass after post-processing and
+/// upscaling. A HUD layer on the XR camera also shows in the preview window.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[require(Camera)]
+pub struct HudLayer;
+
+/// Where a [`HudElement`] is pinned to its layer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HudAnchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// Text or quad on a [`HudLayer`]
+///
+/// Text is spawned with Bevy's `Text`, e.g.
+/// `(Text::new("72 fps"), HudElement::new(HudAnchor::TopRight))`. Drawn on the HUD layer camera with the lowest order unless `layer` is
+/// set. Children are laid out like any other UI node.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+#[require(Node)]
+pub struct HudElement {
+    pub anchor: HudAnchor,
+    /// Logical pixels from the anchor towards the center of the layer
+    pub offset: Vec2,
+    /// Camera of the layer
+    pub layer: Option<Entity>,
+}
+
+/// Solid or textured rectangle on a [`HudLayer`], e.g. a controller hint icon
+#[derive(Component, Debug, Clone, PartialEq)]
+#[require(HudElement)]
+pub struct HudQuad {
+    /// Logical pixels
+    pub size: Vec2,
+    /// Tints the image
+    pub color: Color,
+    pub image: Option<Handle<Image>>,
+}
+
+#[derive(Debug, Default)]
+pub struct HudPlugin;
+
+impl HudElement {
+    pub fn new(anchor: HudAnchor) -> Self {
+        Self {
+            anchor,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: Entity) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    /// Absolute position of the node
+    fn place(&self, node: &mut Node, transform: &mut UiTransform) {
+        use HudAnchor::*;
+
+        node.position_type = PositionType::Absolute;
+        node.left = Val::Auto;
+        node.right = Val::Auto;
+        node.top = Val::Auto;
+        node.bottom = Val::Auto;
+        node.margin = UiRect::DEFAULT;
+        transform.translation = Val2::ZERO;
+
+        match self.anchor {
+            TopLeft | Left | BottomLeft => node.left = Val::Px(self.offset.x),
+            TopRight | Right | BottomRight => node.right = Val::Px(self.offset.x),
+            Top | Center | Bottom => {
+                node.left = Val::Percent(50.0);
+                node.margin.left = Val::Px(self.offset.x);
+                transform.translation.x = Val::Percent(-50.0);
+            }
+        }
+        match self.anchor {
+            TopLeft | Top | TopRight => node.top = Val::Px(self.offset.y),
+            BottomLeft | Bottom | BottomRight => node.bottom = Val::Px(self.offset.y),
+            Left | Center | Right => {
+                node.top = Val::Percent(50.0);
+                node.margin.top = Val::Px(self.offset.y);
+                transform.translation.y = Val::Percent(-50.0);
+            }
+        }
+    }
+}
+
+impl HudQuad {
+    pub fn new(size: Vec2, color: Color) -> Self {
+        Self {
+            size,
+            color,
+            image: None,
+        }
+    }
+
+    pub fn image(size: Vec2, image: Handle<Image>) -> Self {
+        Self {
+            size,
+            color: Color::WHITE,
+            image: Some(image),
+        }
+    }
+}
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (assign_hud_layers, place_hud_elements, update_hud_quads).before(UiSystems::Prepare),
+        );
+    }
+}
+
+fn assign_hud_layers(
+    mut commands: Commands,
+    layers: Query<(Entity, &Camera), With<HudLayer>>,
+    elements: Query<(Entity, &HudElement, Option<&UiTargetCamera>)>,
+) {
+    let default_layer = layers
+        .iter()
+        .min_by_key(|(_, camera)| camera.order)
+        .map(|(entity, _)| entity);
+    for (entity, element, target) in elements.iter() {
+        let Some(layer) = element.layer.or(default_layer) else {
+            continue;
+        };
+        if target.map(UiTargetCamera::entity) != Some(layer) {
+            commands.entity(entity).insert(UiTargetCamera(layer));
+        }
+    }
+}
+
+fn place_hud_elements(
+    mut elements: Query<(&HudElement, &mut Node, &mut UiTransform), Changed<HudElement>>,
+) {
+    for (element, mut node, mut transform) in elements.iter_mut() {
+        element.place(&mut node, &mut transform);
+    }
+}
+
+fn update_hud_quads(
+    mut commands: Commands,
+    mut quads: Query<(Entity, &HudQuad, &mut Node), Changed<HudQuad>>,
+) {
+    for (entity, quad, mut node) in quads.iter_mut() {
+        node.width = Val::Px(quad.size.x);
+        node.height = Val::Px(quad.size.y);
+        let mut entity = commands.entity(entity);
+        match &quad.image {
+            Some(image) => {
+                entity.insert((
+                    ImageNode::new(image.clone()).with_color(quad.color),
+                    BackgroundColor(Color::NONE),
+                ));
+            }
+            None => {
+                entity
+                    .insert(BackgroundColor(quad.color))
+                    .remove::<ImageNode>();
+            }
+        }
+    }
+}
@@ -1 +1,94 @@
+mod ambient_occlusion;
+mod animation;
+mod anisotropy;
+mod asset;
+mod batching;
+mod bindless;
+mod bloom;
+mod camera_order;
+mod clipping;
+mod color_filter;
+#[cfg(feature = "ddgi")]
+mod ddgi;
+mod debug_draw;
+mod depth_prepass;
+mod environment;
+mod frame_graph;
+mod gbuffer;
+mod gltf_export;
+mod gltf_inspect;
+mod gltf_validation;
+mod gpu_query;
+mod highlight;
+mod hot_reload;
+mod hud;
+mod light_culling;
+mod light_probes;
+mod material_variants;
+mod morph;
+mod multisample;
+mod object_id;
+mod occlusion_query;
+mod paint;
+mod raycast;
+mod render_stats;
+mod renderer;
+mod screenshot;
+mod shader_check;
+mod shadow_bias;
+mod sheen;
+mod subsurface;
+mod texture_compression;
+mod transmission;
+mod upscaling;
+mod viewport;
+mod vignette;
 
+pub use ambient_occlusion::*;
+pub use animation::*;
+pub use anisotropy::*;
+pub use asset::*;
+pub use batching::*;
+pub use bindless::*;
+pub use bloom::*;
+pub use camera_order::*;
+pub use clipping::*;
+pub use color_filter::*;
+#[cfg(feature = "ddgi")]
+pub use ddgi::*;
+pub use debug_draw::*;
+pub use depth_prepass::*;
+pub use environment::*;
+pub use frame_graph::*;
+pub use gbuffer::*;
+pub use gltf_export::*;
+pub use gltf_inspect::*;
+pub use gltf_validation::*;
+pub use gpu_query::*;
+pub use highlight::*;
+pub use hot_reload::*;
+pub use hud::*;
+pub use light_culling::*;
+pub use light_probes::*;
+pub use material_variants::*;
+pub use morph::*;
+pub use multisample::*;
+pub use object_id::*;
+pub use occlusion_query::*;
+pub use paint::*;
+pub use raycast::*;
+pub use render_stats::*;
+pub use renderer::*;
+pub use screenshot::*;
+pub use shader_check::*;
+pub use shadow_bias::*;
+pub use sheen::*;
+pub use subsurface::*;
+pub use texture_compression::*;
+pub use transmission::*;
+pub use upscaling::*;
+pub use viewport::*;
+pub use vignette::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,168 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    camera::{
+        primitives::{Frustum, Sphere},
+        visibility::VisibilitySystems,
+    },
+    ecs::entity::EntityHashSet,
+    light::SimulationLightSystems,
+    prelude::*,
+};
+
+/// Limits the number of point and spot lights sent to the GPU each frame
+///
+/// Lights are ranked by their estimated contribution to the views and only
+/// the `max_lights` most important ones are kept. Culled lights are hidden
+/// for the frame only, their `Visibility` is not changed.
+#[derive(Resource, Debug, Clone)]
+pub struct LightBudget {
+    pub enabled: bool,
+    pub max_lights: usize,
+    /// Importance bonus of lights kept in the previous frame. Avoids lights
+    /// popping when two of them have about the same importance
+    pub hysteresis: f32,
+    /// Number of lights culled in the last frame
+    pub culled: usize,
+}
+
+/// Ranks lights by importance and culls them down to the [`LightBudget`]
+#[derive(Debug, Default)]
+pub struct LightCullingPlugin;
+
+impl Default for LightBudget {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_lights: 64,
+            hysteresis: 0.25,
+            culled: 0,
+        }
+    }
+}
+
+impl LightBudget {
+    pub fn new(max_lights: usize) -> Self {
+        Self {
+            max_lights,
+            ..Default::default()
+        }
+    }
+}
+
+/// Estimated contribution of a light at `distance` from the viewer
+///
+/// `intensity` is the luminous power in lumens, weighted by the luminance of
+/// the light color. The falloff matches the windowed inverse square
+/// attenuation used by the renderer and is zero outside the light range.
+pub fn light_importance(intensity: f32, range: f32, radius: f32, distance: f32) -> f32 {
+    if distance >= range || range <= 0.0 {
+        return 0.0;
+    }
+    let window = (1.0 - (distance / range).powi(4)).clamp(0.0, 1.0).powi(2);
+    let distance = distance.max(radius).max(0.01);
+    intensity / (4.0 * PI) * window / (distance * distance)
+}
+
+impl Plugin for LightCullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LightBudget>().add_systems(
+            PostUpdate,
+            cull_lights
+                .after(VisibilitySystems::CheckVisibility)
+                .before(SimulationLightSystems::AssignLightsToClusters),
+        );
+    }
+}
+
+fn cull_lights(
+    mut budget: ResMut<LightBudget>,
+    views: Query<(&GlobalTransform, &Frustum, &Camera)>,
+    mut point_lights: Query<(Entity, &PointLight, &GlobalTransform, &mut ViewVisibility)>,
+    mut spot_lights: Query<
+        (Entity, &SpotLight, &GlobalTransform, &mut ViewVisibility),
+        Without<PointLight>,
+    >,
+    mut candidates: Local<Vec<(Entity, f32)>>,
+    mut kept: Local<EntityHashSet>,
+) {
+    candidates.clear();
+    if !budget.enabled {
+        kept.clear();
+        if budget.culled != 0 {
+            budget.culled = 0;
+        }
+        return;
+    }
+
+    let views = views
+        .iter()
+        .filter(|(_, _, camera)| camera.is_active)
+        .map(|(transform, frustum, _)| (transform.translation(), frustum))
+        .collect::<Vec<_>>();
+    let importance = |color: Color, intensity: f32, range: f32, radius: f32, position: Vec3| {
+        let sphere = Sphere {
+            center: position.into(),
+            radius: range,
+        };
+        let intensity = intensity * LinearRgba::from(color).luminance();
+        views
+            .iter()
+            .filter(|(_, frustum)| frustum.intersects_sphere(&sphere, true))
+            .map(|(view, _)| light_importance(intensity, range, radius, view.distance(position)))
+            .fold(0.0, f32::max)
+    };
+
+    for (entity, light, transform, visibility) in &point_lights {
+        if visibility.get() {
+            let position = transform.translation();
+            let value = importance(
+                light.color,
+                light.intensity,
+                light.range,
+                light.radius,
+                position,
+            );
+            candidates.push((entity, value));
+        }
+    }
+    for (entity, light, transform, visibility) in &spot_lights {
+        if visibility.get() {
+            let position = transform.translation();
+            let value = importance(
+                light.color,
+                light.intensity,
+                light.range,
+                light.radius,
+                position,
+            );
+            candidates.push((entity, value));
+        }
+    }
+
+    let hysteresis = 1.0 + budget.hysteresis.max(0.0);
+    for (entity, value) in candidates.iter_mut() {
+        if kept.contains(entity) {
+            *value *= hysteresis;
+        }
+    }
+    candidates.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
+
+    kept.clear();
+    let mut culled = 0;
+    for (index, (entity, value)) in candidates.iter().enumerate() {
+        if index < budget.max_lights && *value > 0.0 {
+            kept.insert(*entity);
+            continue;
+        }
+        culled += 1;
+        if let Ok((.., mut visibility)) = point_lights.get_mut(*entity) {
+            *visibility = ViewVisibility::HIDDEN;
+        } else if let Ok((.., mut visibility)) = spot_lights.get_mut(*entity) {
+            *visibility = ViewVisibility::HIDDEN;
+        }
+    }
+    if budget.culled != culled {
+        budget.culled = culled;
+    }
+}
@@ -0,0 +1,467 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    light::{IrradianceVolume, LightProbe},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+    transform::TransformSystems,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{light_importance, Raycast, RaycastSettings};
+
+/// Progressive volumes are written by the compute shaders of dynamic GI
+#[cfg(feature = "ddgi")]
+const PROBE_TEXTURE_USAGES: TextureUsages = TextureUsages::STORAGE_BINDING;
+#[cfg(not(feature = "ddgi"))]
+const PROBE_TEXTURE_USAGES: TextureUsages = TextureUsages::empty();
+
+/// Grid of irradiance probes lighting the meshes inside it, e.g. characters
+/// moving through a statically lit interior
+///
+/// The volume is a unit cube centered on the entity and scaled, rotated and
+/// moved by its transform, with `resolution` probes along each axis. Meshes
+/// inside the volume get their ambient diffuse light from the nearest probes
+/// instead of the global ambient light. Lightmapped meshes are left out, their
+/// bounce light is in the lightmap already.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[require(Transform, Visibility)]
+pub struct LightProbeVolume {
+    pub resolution: UVec3,
+    /// Scale of the probe irradiance
+    pub intensity: f32,
+    pub source: LightProbeSource,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LightProbeSource {
+    /// Irradiance baked by an external tool, in the 3D texture layout of
+    /// Bevy's irradiance volumes. `resolution` is not used
+    Baked(Handle<Image>),
+    /// Irradiance gathered from the lights of the scene and the meshes
+    /// around each probe, as selected by [`LightProbeSettings::global_illumination`]
+    Progressive,
+}
+
+/// How [`LightProbeSource::Progressive`] volumes are gathered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GlobalIllumination {
+    /// A few probes per frame on the CPU with a single bounce off the meshes
+    #[default]
+    Progressive,
+    /// All probes every frame in compute shaders, with bounces accumulated
+    /// over frames and probe visibility to avoid leaks through walls. Needs
+    /// the `ddgi` feature, else volumes fall back to `Progressive`
+    Dynamic,
+}
+
+/// Budget and quality of [`LightProbeSource::Progressive`] volumes
+#[derive(Resource, Debug, Clone)]
+pub struct LightProbeSettings {
+    /// Probes gathered per frame, shared by all volumes
+    pub probes_per_frame: usize,
+    pub rays_per_probe: u32,
+    /// Weight of a new gather against the previous value of a probe. Lower
+    /// values hide noise, higher ones follow moving lights faster
+    pub blend: f32,
+    /// Test the triangles of meshes instead of their bounding boxes
+    pub triangles: bool,
+    /// Albedo of meshes without a `StandardMaterial`
+    pub default_albedo: LinearRgba,
+    pub global_illumination: GlobalIllumination,
+}
+
+/// Lights meshes with [`LightProbeVolume`]s
+#[derive(Debug, Default)]
+pub struct LightProbePlugin;
+
+/// Gathered irradiance of a progressive volume, one ambient cube per probe
+#[derive(Component, Debug)]
+pub(crate) struct LightProbeGather {
+    pub(crate) resolution: UVec3,
+    pub(crate) image: Handle<Image>,
+    /// Radiance from -X, +X, -Y, +Y, -Z and +Z
+    probes: Vec<[Vec3; 6]>,
+    /// Next probe to gather
+    cursor: usize,
+    /// All probes were gathered at least once
+    complete: bool,
+}
+
+/// Light of the scene as seen by the gather
+enum GatherLight {
+    Point {
+        position: Vec3,
+        color: Vec3,
+        intensity: f32,
+        range: f32,
+        radius: f32,
+        /// Cosines of the outer and inner angle of spot lights
+        cone: Option<(Vec3, f32, f32)>,
+    },
+    Directional {
+        direction: Vec3,
+        illuminance: Vec3,
+    },
+}
+
+impl Default for LightProbeSettings {
+    fn default() -> Self {
+        Self {
+            probes_per_frame: 16,
+            rays_per_probe: 64,
+            blend: 0.25,
+            triangles: false,
+            default_albedo: LinearRgba::gray(0.5),
+            global_illumination: GlobalIllumination::default(),
+        }
+    }
+}
+
+impl LightProbeVolume {
+    pub fn baked(voxels: Handle<Image>) -> Self {
+        Self {
+            resolution: UVec3::ONE,
+            intensity: 1.0,
+            source: LightProbeSource::Baked(voxels),
+        }
+    }
+
+    pub fn progressive(resolution: UVec3) -> Self {
+        Self {
+            resolution,
+            intensity: 1.0,
+            source: LightProbeSource::Progressive,
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Position of a probe relative to the volume, in -0.5..0.5
+    pub fn probe_position(&self, probe: UVec3) -> Vec3 {
+        (probe.as_vec3() + 0.5) / self.resolution.max(UVec3::ONE).as_vec3() - 0.5
+    }
+}
+
+impl Plugin for LightProbePlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(feature = "ddgi")]
+        app.add_plugins(crate::DynamicGiPlugin);
+
+        app.init_resource::<LightProbeSettings>().add_systems(
+            PostUpdate,
+            (
+                (remove_light_probe_volumes, apply_light_probe_volumes).chain(),
+                gather_light_probes.after(TransformSystems::Propagate),
+            )
+                .run_if(resource_exists::<Assets<Image>>),
+        );
+    }
+}
+
+fn remove_light_probe_volumes(
+    mut commands: Commands,
+    mut removed: RemovedComponents<LightProbeVolume>,
+    changed: Query<Entity, Changed<LightProbeVolume>>,
+) {
+    for entity in removed.read().chain(changed.iter()) {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.remove::<(LightProbe, IrradianceVolume, LightProbeGather)>();
+        }
+    }
+}
+
+fn apply_light_probe_volumes(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    volumes: Query<(Entity, &LightProbeVolume), Changed<LightProbeVolume>>,
+) {
+    for (entity, volume) in &volumes {
+        let voxels = match &volume.source {
+            LightProbeSource::Baked(voxels) => voxels.clone(),
+            LightProbeSource::Progressive => {
+                let resolution = volume.resolution.max(UVec3::ONE);
+                let mut image = Image::new_fill(
+                    Extent3d {
+                        width: resolution.x,
+                        height: resolution.y * 2,
+                        depth_or_array_layers: resolution.z * 3,
+                    },
+                    TextureDimension::D3,
+                    &[0; 8],
+                    TextureFormat::Rgba16Float,
+                    RenderAssetUsages::default(),
+                );
+                image.texture_descriptor.usage |= PROBE_TEXTURE_USAGES;
+                let image = images.add(image);
+                commands.entity(entity).insert(LightProbeGather {
+                    resolution,
+                    image: image.clone(),
+                    probes: vec![[Vec3::ZERO; 6]; resolution.element_product() as usize],
+                    cursor: 0,
+                    complete: false,
+                });
+                image
+            }
+        };
+        commands.entity(entity).insert((
+            LightProbe,
+            IrradianceVolume {
+                voxels,
+                intensity: volume.intensity,
+                affects_lightmapped_meshes: false,
+            },
+        ));
+    }
+}
+
+/// Gathers the next probes of progressive volumes within the frame budget
+fn gather_light_probes(world: &mut World) {
+    let settings = world.resource::<LightProbeSettings>().clone();
+    if settings.global_illumination == GlobalIllumination::Dynamic {
+        if cfg!(feature = "ddgi") {
+            return;
+        }
+        warn_once!(
+            "Dynamic global illumination needs the ddgi feature, gathering probes on the CPU"
+        );
+    }
+    let mut volumes = world.query::<(
+        Entity,
+        &LightProbeVolume,
+        &GlobalTransform,
+        &LightProbeGather,
+    )>();
+    let volumes: Vec<_> = volumes
+        .iter(world)
+        .map(|(entity, volume, transform, gather)| {
+            (
+                entity,
+                volume.clone(),
+                transform.affine(),
+                gather.resolution,
+            )
+        })
+        .collect();
+    if volumes.is_empty() || settings.probes_per_frame == 0 {
+        return;
+    }
+
+    let lights = gather_lights(world);
+    let ambient = world
+        .get_resource::<AmbientLight>()
+        .map(|ambient| ambient.color.to_linear().to_vec3() * ambient.brightness)
+        .unwrap_or_default();
+    let directions = ray_directions(settings.rays_per_probe.max(1));
+    let raycast = RaycastSettings::default().with_triangles(settings.triangles);
+
+    // Spread the budget over the volumes, the first ones get the remainder
+    let mut budget = settings.probes_per_frame;
+    for (index, (entity, volume, transform, resolution)) in volumes.iter().enumerate() {
+        let share = budget.div_ceil(volumes.len() - index);
+        budget -= share;
+        let count = resolution.element_product() as usize;
+        let mut gathered = vec![];
+        let Some(mut cursor) = world.get::<LightProbeGather>(*entity).map(|g| g.cursor) else {
+            continue;
+        };
+        for _ in 0..share.min(count) {
+            let probe = UVec3::new(
+                cursor as u32 % resolution.x,
+                cursor as u32 / resolution.x % resolution.y,
+                cursor as u32 / (resolution.x * resolution.y),
+            );
+            let position = transform.transform_point3(volume.probe_position(probe));
+            let mut sides = [(Vec3::ZERO, 0.0); 6];
+            for direction in &directions {
+                let radiance = match world.raycast_with(position, *direction, &raycast) {
+                    Some(hit) => {
+                        let albedo = albedo(world, hit.entity, settings.default_albedo);
+                        let point = hit.point + hit.normal * 1e-3;
+                        let irradiance =
+                            direct_irradiance(world, &lights, point, hit.normal, &raycast);
+                        albedo * (irradiance / PI + ambient)
+                    }
+                    None => ambient,
+                };
+                for (side, (sum, weight)) in sides.iter_mut().enumerate() {
+                    let cosine = direction.dot(side_normal(side)).max(0.0);
+                    *sum += radiance * cosine;
+                    *weight += cosine;
+                }
+            }
+            gathered.push((
+                cursor,
+                sides.map(|(sum, weight)| {
+                    if weight > 0.0 {
+                        sum / weight
+                    } else {
+                        Vec3::ZERO
+                    }
+                }),
+            ));
+            cursor = (cursor + 1) % count;
+        }
+
+        let Some(mut gather) = world.get_mut::<LightProbeGather>(*entity) else {
+            continue;
+        };
+        for (probe, sides) in gathered {
+            let blend = if gather.complete { settings.blend } else { 1.0 };
+            for (previous, side) in gather.probes[probe].iter_mut().zip(sides) {
+                *previous = previous.lerp(side, blend);
+            }
+            if probe + 1 == count {
+                gather.complete = true;
+            }
+        }
+        gather.cursor = cursor;
+        world.resource_scope(|world, mut images: Mut<Assets<Image>>| {
+            let gather = world.get::<LightProbeGather>(*entity).unwrap();
+            if let Some(image) = images.get_mut(&gather.image) {
+                write_probes(image, *resolution, &gather.probes);
+            }
+        });
+    }
+}
+
+fn gather_lights(world: &mut World) -> Vec<GatherLight> {
+    let mut lights = vec![];
+    let mut points = world.query::<(&PointLight, &GlobalTransform)>();
+    for (light, transform) in points.iter(world) {
+        lights.push(GatherLight::Point {
+            position: transform.translation(),
+            color: light.color.to_linear().to_vec3(),
+            intensity: light.intensity,
+            range: light.range,
+            radius: light.radius,
+            cone: None,
+        });
+    }
+    let mut spots = world.query::<(&SpotLight, &GlobalTransform)>();
+    for (light, transform) in spots.iter(world) {
+        lights.push(GatherLight::Point {
+            position: transform.translation(),
+            color: light.color.to_linear().to_vec3(),
+            intensity: light.intensity,
+            range: light.range,
+            radius: light.radius,
+            cone: Some((
+                *transform.forward(),
+                light.outer_angle.cos(),
+                light.inner_angle.cos(),
+            )),
+        });
+    }
+    let mut directionals = world.query::<(&DirectionalLight, &GlobalTransform)>();
+    for (light, transform) in directionals.iter(world) {
+        lights.push(GatherLight::Directional {
+            direction: *transform.forward(),
+            illuminance: light.color.to_linear().to_vec3() * light.illuminance,
+        });
+    }
+    lights
+}
+
+/// Illuminance in lux at a surface point from the unoccluded lights
+fn direct_irradiance(
+    world: &mut World,
+    lights: &[GatherLight],
+    point: Vec3,
+    normal: Vec3,
+    raycast: &RaycastSettings,
+) -> Vec3 {
+    let mut irradiance = Vec3::ZERO;
+    for light in lights {
+        let (to_light, distance, illuminance) = match light {
+            GatherLight::Point {
+                position,
+                color,
+                intensity,
+                range,
+                radius,
+                cone,
+            } => {
+                let distance = point.distance(*position);
+                let to_light = (*position - point).normalize_or_zero();
+                let mut illuminance = light_importance(*intensity, *range, *radius, distance);
+                if let Some((forward, outer, inner)) = cone {
+                    let cosine = forward.dot(-to_light);
+                    let t = ((cosine - outer) / (inner - outer).max(1e-4)).clamp(0.0, 1.0);
+                    illuminance *= t * t * (3.0 - 2.0 * t);
+                }
+                (to_light, distance, *color * illuminance)
+            }
+            GatherLight::Directional {
+                direction,
+                illuminance,
+            } => (-*direction, f32::INFINITY, *illuminance),
+        };
+        let cosine = normal.dot(to_light);
+        if cosine <= 0.0 || illuminance == Vec3::ZERO {
+            continue;
+        }
+        let occluded = world
+            .raycast_with(point, to_light, &raycast.with_max_distance(distance))
+            .is_some();
+        if !occluded {
+            irradiance += illuminance * cosine;
+        }
+    }
+    irradiance
+}
+
+fn albedo(world: &World, entity: Entity, default: LinearRgba) -> Vec3 {
+    world
+        .get::<MeshMaterial3d<StandardMaterial>>(entity)
+        .and_then(|material| {
+            world
+                .get_resource::<Assets<StandardMaterial>>()?
+                .get(&material.0)
+        })
+        .map_or(default, |material| material.base_color.to_linear())
+        .to_vec3()
+}
+
+/// Evenly spread directions on the unit sphere
+fn ray_directions(count: u32) -> Vec<Vec3> {
+    let golden_angle = PI * (3.0 - 5.0f32.sqrt());
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - (i as f32 + 0.5) / count as f32 * 2.0;
+            let radius = (1.0 - y * y).sqrt();
+            let angle = golden_angle * i as f32;
+            Vec3::new(radius * angle.cos(), y, radius * angle.sin())
+        })
+        .collect()
+}
+
+/// -X, +X, -Y, +Y, -Z and +Z
+fn side_normal(side: usize) -> Vec3 {
+    let mut normal = Vec3::ZERO;
+    normal[side / 2] = if side % 2 == 0 { -1.0 } else { 1.0 };
+    normal
+}
+
+/// Writes ambient cubes in the layout of Bevy's irradiance volumes: positive
+/// sides in the upper half of the height, the axis in thirds of the depth
+fn write_probes(image: &mut Image, resolution: UVec3, probes: &[[Vec3; 6]]) {
+    for (index, sides) in probes.iter().enumerate() {
+        let index = index as u32;
+        let x = index % resolution.x;
+        let y = index / resolution.x % resolution.y;
+        let z = index / (resolution.x * resolution.y);
+        for (side, radiance) in sides.iter().enumerate() {
+            let side = side as u32;
+            let t = y + (side % 2) * resolution.y;
+            let p = z + (side / 2) * resolution.z;
+            // Only fails for out of bounds coordinates
+            let _ =
+                image.set_color_at_3d(x, t, p, Color::LinearRgba(LinearRgba::from_vec3(*radiance)));
+        }
+    }
+}
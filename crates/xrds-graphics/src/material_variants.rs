@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use bevy::{gltf::Gltf, prelude::*};
+
+/// Switches the materials of a glTF scene between its `KHR_materials_variants`
+///
+/// Useful for product configurators showing color or trim options. The glTF
+/// file must be loaded with its source so the variants can be read, the scene
+/// is spawned on the same entity once the file is loaded, e.g.
+///
+/// ```ignore
+/// let gltf = asset_server.load_with_settings("shoe.glb", |settings: &mut GltfLoaderSettings| {
+///     settings.include_source = true;
+/// });
+/// commands.spawn(MaterialVariant::new(gltf).with_variant("midnight"));
+/// ```
+///
+/// Change `variant` to switch at runtime, `None` restores the default materials.
+#[derive(Component, Debug, Clone)]
+#[require(Transform, Visibility, MaterialVariantState)]
+pub struct MaterialVariant {
+    pub gltf: Handle<Gltf>,
+    pub variant: Option<String>,
+}
+
+/// Variants of a glTF file, inserted next to [`MaterialVariant`] once it is loaded
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct GltfMaterialVariants {
+    names: Vec<String>,
+    /// Material index per (mesh, primitive) and variant
+    mappings: HashMap<(usize, usize), HashMap<usize, usize>>,
+}
+
+#[derive(Component, Debug, Clone, Default)]
+struct MaterialVariantState {
+    applied: bool,
+}
+
+/// Material of a primitive before any variant was applied
+#[derive(Component, Debug, Clone)]
+struct DefaultMaterial(Handle<StandardMaterial>);
+
+#[derive(Debug, Default)]
+pub struct MaterialVariantPlugin;
+
+impl MaterialVariant {
+    pub fn new(gltf: Handle<Gltf>) -> Self {
+        Self {
+            gltf,
+            variant: None,
+        }
+    }
+
+    pub fn with_variant(mut self, variant: impl Into<String>) -> Self {
+        self.variant = Some(variant.into());
+        self
+    }
+}
+
+impl GltfMaterialVariants {
+    pub fn from_document(document: &gltf::Document) -> Self {
+        let names = document
+            .variants()
+            .map(|variants| variants.map(|variant| variant.name().to_owned()).collect())
+            .unwrap_or_default();
+
+        let mut mappings = HashMap::new();
+        for mesh in document.meshes() {
+            for primitive in mesh.primitives() {
+                let materials = primitive
+                    .mappings()
+                    .filter_map(|mapping| {
+                        let material = mapping.material().index()?;
+                        Some(
+                            mapping
+                                .variants()
+                                .iter()
+                                .map(move |variant| (*variant as usize, material)),
+                        )
+                    })
+                    .flatten()
+                    .collect::<HashMap<_, _>>();
+                if !materials.is_empty() {
+                    mappings.insert((mesh.index(), primitive.index()), materials);
+                }
+            }
+        }
+
+        Self { names, mappings }
+    }
+
+    /// Names of the variants in the order of the file
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn variant_index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|variant| variant == name)
+    }
+
+    /// Index of the material used by a primitive in a variant, `None` if it keeps its default
+    pub fn material(&self, mesh: usize, primitive: usize, variant: usize) -> Option<usize> {
+        self.mappings
+            .get(&(mesh, primitive))
+            .and_then(|materials| materials.get(&variant))
+            .copied()
+    }
+}
+
+impl Plugin for MaterialVariantPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            apply_material_variants.run_if(resource_exists::<Assets<Gltf>>),
+        );
+    }
+}
+
+/// Mesh and primitive index from a label like `Mesh0/Primitive1`
+fn primitive_index(label: &str) -> Option<(usize, usize)> {
+    let (mesh, primitive) = label.strip_prefix("Mesh")?.split_once("/Primitive")?;
+    Some((mesh.parse().ok()?, primitive.parse().ok()?))
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_material_variants(
+    mut commands: Commands,
+    gltfs: Res<Assets<Gltf>>,
+    asset_server: Res<AssetServer>,
+    mut scenes: Query<(
+        Entity,
+        Ref<MaterialVariant>,
+        &mut MaterialVariantState,
+        Option<&GltfMaterialVariants>,
+        Has<SceneRoot>,
+    )>,
+    children: Query<&Children>,
+    mut primitives: Query<(
+        &Mesh3d,
+        &mut MeshMaterial3d<StandardMaterial>,
+        Option<&DefaultMaterial>,
+    )>,
+) {
+    for (entity, variant, mut state, variants, has_scene) in scenes.iter_mut() {
+        if variant.is_changed() {
+            state.applied = false;
+        }
+        if state.applied {
+            continue;
+        }
+        let Some(gltf) = gltfs.get(&variant.gltf) else {
+            continue;
+        };
+
+        if !has_scene {
+            match gltf.default_scene.as_ref().or(gltf.scenes.first()) {
+                Some(scene) => {
+                    commands.entity(entity).insert(SceneRoot(scene.clone()));
+                }
+                None => {
+                    warn!("glTF file of material variant has no scene");
+                    state.applied = true;
+                }
+            }
+            continue;
+        }
+
+        let variants = match variants {
+            Some(variants) => variants.clone(),
+            None => {
+                let variants = match &gltf.source {
+                    Some(source) => GltfMaterialVariants::from_document(&source.document),
+                    None => {
+                        warn!("glTF file of material variant is not loaded with include_source");
+                        GltfMaterialVariants::default()
+                    }
+                };
+                commands.entity(entity).insert(variants.clone());
+                variants
+            }
+        };
+        let variant_index = variant.variant.as_deref().and_then(|name| {
+            let index = variants.variant_index(name);
+            if index.is_none() {
+                warn!("Unknown material variant {}", name);
+            }
+            index
+        });
+
+        // Primitives only exist once the scene is spawned
+        let mut spawned = false;
+        for descendant in children.iter_descendants(entity) {
+            let Ok((mesh, mut material, default)) = primitives.get_mut(descendant) else {
+                continue;
+            };
+            spawned = true;
+            let Some((mesh_index, primitive)) = asset_server
+                .get_path(&mesh.0)
+                .and_then(|path| primitive_index(path.label()?))
+            else {
+                continue;
+            };
+
+            let default = match default {
+                Some(default) => default.0.clone(),
+                None => {
+                    commands
+                        .entity(descendant)
+                        .insert(DefaultMaterial(material.0.clone()));
+                    material.0.clone()
+                }
+            };
+            let handle = variant_index
+                .and_then(|variant| variants.material(mesh_index, primitive, variant))
+                .and_then(|index| gltf.materials.get(index).cloned())
+                .unwrap_or(default);
+            if material.0 != handle {
+                material.0 = handle;
+            }
+        }
+        state.applied = spawned;
+    }
+}
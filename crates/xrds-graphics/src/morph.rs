@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use bevy::{app::AnimationSystems, mesh::InheritWeightSystems, prelude::*};
+
+/// Morph target (blend shape) weights set by name on a spawned glTF scene
+///
+/// Morph targets of glTF primitives are loaded and blended on the GPU by the
+/// renderer, with their weights in a `MorphWeights` component on each mesh
+/// entity. This sets weights by target name on every mesh of the scene, e.g.
+/// for facial expressions of an avatar:
+///
+/// ```ignore
+/// commands.spawn((
+///     SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("avatar.glb"))),
+///     SceneMorphWeights::default().with_weight("mouthSmile", 0.8),
+/// ));
+/// ```
+///
+/// Weights are applied after animations, targets that are not set are left to them.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct SceneMorphWeights {
+    weights: HashMap<String, f32>,
+}
+
+#[derive(Debug, Default)]
+pub struct SceneMorphWeightsPlugin;
+
+impl SceneMorphWeights {
+    pub fn with_weight(mut self, target: impl Into<String>, weight: f32) -> Self {
+        self.set(target, weight);
+        self
+    }
+
+    pub fn set(&mut self, target: impl Into<String>, weight: f32) {
+        self.weights.insert(target.into(), weight);
+    }
+
+    pub fn get(&self, target: &str) -> Option<f32> {
+        self.weights.get(target).copied()
+    }
+
+    /// Stop overriding a target, leaving its current weight
+    pub fn remove(&mut self, target: &str) -> Option<f32> {
+        self.weights.remove(target)
+    }
+}
+
+impl Plugin for SceneMorphWeightsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            apply_scene_morph_weights
+                .after(AnimationSystems)
+                .before(InheritWeightSystems)
+                .run_if(resource_exists::<Assets<Mesh>>),
+        );
+    }
+}
+
+fn apply_scene_morph_weights(
+    meshes: Res<Assets<Mesh>>,
+    scenes: Query<(Entity, &SceneMorphWeights)>,
+    children: Query<&Children>,
+    mut morph_weights: Query<&mut MorphWeights>,
+) {
+    for (entity, scene_weights) in scenes.iter() {
+        if scene_weights.weights.is_empty() {
+            continue;
+        }
+        for descendant in children.iter_descendants(entity) {
+            let Ok(mut weights) = morph_weights.get_mut(descendant) else {
+                continue;
+            };
+            // Targets are named per mesh, all primitives of a mesh share them
+            let Some(names) = weights
+                .first_mesh()
+                .and_then(|mesh| meshes.get(mesh))
+                .and_then(|mesh| mesh.morph_target_names())
+            else {
+                continue;
+            };
+            let targets = names
+                .iter()
+                .enumerate()
+                .filter_map(|(index, name)| Some((index, scene_weights.get(name)?)))
+                .collect::<Vec<_>>();
+            let changed = targets
+                .iter()
+                .any(|(index, weight)| weights.weights().get(*index) != Some(weight));
+            if !changed {
+                continue;
+            }
+            let weights = weights.weights_mut();
+            for (index, weight) in targets {
+                if let Some(current) = weights.get_mut(index) {
+                    *current = weight;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::{
+    camera::CameraUpdateSystems, core_pipeline::prepass::DeferredPrepass,
+    pbr::ScreenSpaceAmbientOcclusion, prelude::*, render::view::Msaa,
+};
+
+use crate::SubsurfaceScattering;
+
+/// Sample count of cameras that do not set their own
+///
+/// Multisampled color and depth targets are created for the main pass and
+/// resolved into the view target before post-processing. Counts are rounded
+/// down to 1, 2, 4 or 8, where 1 disables multisampling. 2 and 8 samples
+/// depend on the adapter. Cameras with ambient occlusion, subsurface
+/// scattering or a deferred gbuffer are not multisampled.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Multisampling {
+    pub samples: u32,
+}
+
+/// Sample count of a camera, overriding [`Multisampling`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(Camera)]
+pub struct CameraMultisample {
+    pub samples: u32,
+}
+
+/// Camera following the [`Multisampling`] resource
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct DefaultMultisample;
+
+/// Applies [`Multisampling`] and [`CameraMultisample`] to cameras
+#[derive(Debug, Default)]
+pub struct MultisamplePlugin;
+
+impl Default for Multisampling {
+    fn default() -> Self {
+        Self {
+            samples: Msaa::default().samples(),
+        }
+    }
+}
+
+impl Multisampling {
+    pub fn new(samples: u32) -> Self {
+        Self { samples }
+    }
+
+    pub fn msaa(&self) -> Msaa {
+        msaa_from_samples(self.samples)
+    }
+}
+
+impl CameraMultisample {
+    pub fn new(samples: u32) -> Self {
+        Self { samples }
+    }
+
+    pub fn off() -> Self {
+        Self::new(1)
+    }
+
+    pub fn msaa(&self) -> Msaa {
+        msaa_from_samples(self.samples)
+    }
+}
+
+/// Largest supported [`Msaa`] setting with at most `samples` samples
+pub fn msaa_from_samples(samples: u32) -> Msaa {
+    match samples {
+        0..=1 => Msaa::Off,
+        2..=3 => Msaa::Sample2,
+        4..=7 => Msaa::Sample4,
+        _ => Msaa::Sample8,
+    }
+}
+
+impl Plugin for MultisamplePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Multisampling>().add_systems(
+            PostUpdate,
+            (adopt_default_multisample, apply_multisample)
+                .chain()
+                .before(CameraUpdateSystems),
+        );
+    }
+}
+
+/// New 3D cameras left at Bevy's default MSAA follow [`Multisampling`].
+/// Cameras spawned with another `Msaa`, like ID buffer cameras, keep it
+#[allow(clippy::type_complexity)]
+fn adopt_default_multisample(
+    mut commands: Commands,
+    cameras: Query<
+        (Entity, &Msaa),
+        (
+            Added<Camera3d>,
+            Without<CameraMultisample>,
+            Without<DefaultMultisample>,
+        ),
+    >,
+) {
+    for (entity, msaa) in cameras.iter() {
+        if *msaa == Msaa::default() {
+            commands.entity(entity).insert(DefaultMultisample);
+        }
+    }
+}
+
+/// SSAO needs single sampled depth and normals, subsurface scattering
+/// single sampled color and depth and deferred lighting a single sampled
+/// gbuffer, so their cameras are not multisampled
+#[allow(clippy::type_complexity)]
+fn apply_multisample(
+    multisampling: Res<Multisampling>,
+    mut cameras: Query<
+        (
+            &mut Msaa,
+            Option<&CameraMultisample>,
+            Has<DefaultMultisample>,
+            Has<ScreenSpaceAmbientOcclusion>,
+            Has<SubsurfaceScattering>,
+            Has<DeferredPrepass>,
+        ),
+        With<Camera>,
+    >,
+) {
+    for (mut msaa, multisample, follows_default, ambient_occlusion, subsurface, deferred) in
+        cameras.iter_mut()
+    {
+        let target = match multisample {
+            _ if ambient_occlusion || subsurface || deferred => Msaa::Off,
+            Some(multisample) => multisample.msaa(),
+            None if follows_default => multisampling.msaa(),
+            None => continue,
+        };
+        msaa.set_if_neq(target);
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::embedded_asset,
+    camera::{visibility::RenderLayers, RenderTarget},
+    core_pipeline::tonemapping::{DebandDither, Tonemapping},
+    ecs::system::SystemParam,
+    mesh::{skinning::SkinnedMesh, MeshTag},
+    prelude::*,
+    render::{
+        gpu_readback::{Readback, ReadbackComplete},
+        render_resource::{AsBindGroup, Extent3d, TextureFormat, TextureUsages},
+        view::{Hdr, Msaa},
+    },
+    shader::ShaderRef,
+};
+use wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+const OBJECT_ID_SHADER: &str = "embedded://xrds_graphics/object_id.wgsl";
+
+/// Render layer of the object id pass
+pub const OBJECT_ID_LAYER: usize = 31;
+
+/// Ids are stored in two 11 bit halves, exact in 16 bit floats
+const MAX_OBJECT_ID: u32 = (1 << 22) - 1;
+
+/// Renders the ids of the objects seen by the camera for pixel-perfect picking
+///
+/// Every mesh gets an [`ObjectId`] and a child drawing it into an id buffer of
+/// the camera, which is read back each frame. [`ObjectPicker::pick`] then
+/// finds the exact mesh under a pixel, including skinned meshes and dense
+/// scenes where bounding boxes overlap. Results lag the rendered frame by
+/// the readback latency, usually one or two frames. Meshes with
+/// `Pickable::IGNORE` are left out.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[require(Camera)]
+pub struct ObjectIdPicking;
+
+/// Id of a mesh in the object id buffer
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectId(u32);
+
+/// Meshes by [`ObjectId`]
+#[derive(Resource, Debug, Default)]
+pub struct ObjectIds {
+    entities: HashMap<u32, Entity>,
+    free: Vec<u32>,
+    next: u32,
+}
+
+/// Latest object ids read back for an [`ObjectIdPicking`] camera
+#[derive(Component, Debug, Clone, Default)]
+pub struct ObjectIdBuffer {
+    size: UVec2,
+    scale_factor: f32,
+    /// Rows of `Rg16Float` texels, padded for the copy to the readback buffer
+    data: Vec<u8>,
+}
+
+/// Picks the objects under pixels of [`ObjectIdPicking`] cameras
+#[derive(SystemParam)]
+pub struct ObjectPicker<'w, 's> {
+    ids: Res<'w, ObjectIds>,
+    buffers: Query<'w, 's, &'static ObjectIdBuffer>,
+}
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct ObjectIdMaterial {}
+
+/// Child of a mesh drawing its id
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ObjectIdOverlay;
+
+/// Id camera and target of an [`ObjectIdPicking`] camera
+#[derive(Component, Debug, Clone)]
+struct ObjectIdTarget {
+    camera: Entity,
+    image: Handle<Image>,
+}
+
+/// Camera rendering the object ids
+#[derive(Component, Debug, Clone, Copy)]
+struct ObjectIdCamera;
+
+#[derive(Resource, Debug, Clone, Default)]
+struct ObjectIdMaterialHandle(Handle<ObjectIdMaterial>);
+
+#[derive(Debug, Default)]
+pub struct ObjectIdPlugin;
+
+impl ObjectId {
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+impl ObjectIds {
+    pub fn get(&self, id: u32) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    fn insert(&mut self, entity: Entity) -> Option<u32> {
+        // 0 is left for pixels without objects
+        let id = match self.free.pop() {
+            Some(id) => id,
+            None if self.next < MAX_OBJECT_ID => {
+                self.next += 1;
+                self.next
+            }
+            None => return None,
+        };
+        self.entities.insert(id, entity);
+        Some(id)
+    }
+
+    fn remove(&mut self, id: u32) {
+        if self.entities.remove(&id).is_some() {
+            self.free.push(id);
+        }
+    }
+}
+
+impl ObjectIdBuffer {
+    /// Object id at a position in logical pixels of the camera viewport, 0 if there is none
+    pub fn id_at(&self, position: Vec2) -> u32 {
+        let pixel = (position * self.scale_factor).floor();
+        if pixel.x < 0.0 || pixel.y < 0.0 {
+            return 0;
+        }
+        let (x, y) = (pixel.x as usize, pixel.y as usize);
+        let (width, height) = (self.size.x as usize, self.size.y as usize);
+        if x >= width || y >= height {
+            return 0;
+        }
+        let offset = y * self.stride() + x * 4;
+        let Some(texel) = self.data.get(offset..offset + 4) else {
+            return 0;
+        };
+        let low = f16_to_f32(u16::from_le_bytes([texel[0], texel[1]])).round() as u32;
+        let high = f16_to_f32(u16::from_le_bytes([texel[2], texel[3]])).round() as u32;
+        (high << 11) | (low & 0x7ff)
+    }
+
+    /// Bytes per row, rows are aligned for texture copies unless there is only one
+    fn stride(&self) -> usize {
+        let row = self.size.x as usize * 4;
+        if self.size.y > 1 {
+            row.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT as usize)
+        } else {
+            row
+        }
+    }
+}
+
+impl ObjectPicker<'_, '_> {
+    /// Mesh under a position in logical pixels of the viewport of `camera`
+    pub fn pick(&self, camera: Entity, position: Vec2) -> Option<Entity> {
+        let buffer = self.buffers.get(camera).ok()?;
+        self.ids.get(buffer.id_at(position))
+    }
+}
+
+impl Material for ObjectIdMaterial {
+    fn fragment_shader() -> ShaderRef {
+        OBJECT_ID_SHADER.into()
+    }
+}
+
+impl Plugin for ObjectIdPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "object_id.wgsl");
+
+        app.add_plugins(MaterialPlugin::<ObjectIdMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..Default::default()
+        })
+        .init_resource::<ObjectIds>()
+        .add_observer(remove_object_id)
+        .add_systems(Startup, setup_object_id_material)
+        .add_systems(
+            Update,
+            (assign_object_ids, update_object_id_meshes)
+                .chain()
+                .run_if(any_with_component::<ObjectIdPicking>),
+        )
+        .add_systems(
+            PostUpdate,
+            update_object_id_cameras.after(bevy::camera::CameraUpdateSystems),
+        );
+    }
+}
+
+fn setup_object_id_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ObjectIdMaterial>>,
+) {
+    commands.insert_resource(ObjectIdMaterialHandle(materials.add(ObjectIdMaterial {})));
+}
+
+#[allow(clippy::type_complexity)]
+fn assign_object_ids(
+    mut commands: Commands,
+    mut ids: ResMut<ObjectIds>,
+    material: Res<ObjectIdMaterialHandle>,
+    meshes: Query<
+        (Entity, &Mesh3d, Option<&SkinnedMesh>, Option<&Pickable>),
+        (Without<ObjectId>, Without<ObjectIdOverlay>),
+    >,
+) {
+    for (entity, mesh, skinned_mesh, pickable) in meshes.iter() {
+        if pickable == Some(&Pickable::IGNORE) {
+            continue;
+        }
+        let Some(id) = ids.insert(entity) else {
+            warn_once!("Too many meshes for the object id buffer");
+            return;
+        };
+        let mut overlay = commands.spawn((
+            ObjectIdOverlay,
+            Mesh3d(mesh.0.clone()),
+            MeshMaterial3d(material.0.clone()),
+            MeshTag(id),
+            RenderLayers::layer(OBJECT_ID_LAYER),
+            Pickable::IGNORE,
+            ChildOf(entity),
+        ));
+        // Skinned meshes are posed by their joints, not the overlay transform
+        if let Some(skinned_mesh) = skinned_mesh {
+            overlay.insert(skinned_mesh.clone());
+        }
+        commands.entity(entity).insert(ObjectId(id));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_object_id_meshes(
+    meshes: Query<(&Mesh3d, &Children), (With<ObjectId>, Changed<Mesh3d>)>,
+    mut overlays: Query<&mut Mesh3d, (With<ObjectIdOverlay>, Without<ObjectId>)>,
+) {
+    for (mesh, children) in meshes.iter() {
+        let mut overlays = overlays.iter_many_mut(children);
+        while let Some(mut overlay) = overlays.fetch_next() {
+            overlay.0 = mesh.0.clone();
+        }
+    }
+}
+
+fn remove_object_id(
+    remove: On<Remove, ObjectId>,
+    mut ids: ResMut<ObjectIds>,
+    object_ids: Query<&ObjectId>,
+) {
+    if let Ok(id) = object_ids.get(remove.entity) {
+        ids.remove(id.0);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_object_id_cameras(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<
+        (
+            Entity,
+            &Camera,
+            Ref<Projection>,
+            Option<&ObjectIdTarget>,
+            Has<ObjectIdPicking>,
+        ),
+        (
+            Or<(With<ObjectIdPicking>, With<ObjectIdTarget>)>,
+            Without<ObjectIdCamera>,
+        ),
+    >,
+    mut projections: Query<&mut Projection, With<ObjectIdCamera>>,
+    mut buffers: Query<&mut ObjectIdBuffer>,
+) {
+    for (entity, camera, projection, target, picking) in cameras.iter() {
+        let size = camera.physical_viewport_size();
+        match (target, picking, size) {
+            (None, true, Some(size)) => {
+                let mut image = Image::new_target_texture(size.x, size.y, TextureFormat::Rg16Float);
+                image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+                let image = images.add(image);
+                let id_camera = commands
+                    .spawn((
+                        ObjectIdCamera,
+                        Camera3d::default(),
+                        Camera {
+                            target: RenderTarget::Image(image.clone().into()),
+                            clear_color: ClearColorConfig::Custom(Color::NONE),
+                            ..Default::default()
+                        },
+                        (*projection).clone(),
+                        // Ids must be written as they are
+                        Hdr,
+                        Tonemapping::None,
+                        DebandDither::Disabled,
+                        Msaa::Off,
+                        RenderLayers::layer(OBJECT_ID_LAYER),
+                        Readback::texture(image.clone()),
+                        ChildOf(entity),
+                    ))
+                    .observe(read_object_ids)
+                    .id();
+                commands.entity(entity).insert((
+                    ObjectIdTarget {
+                        camera: id_camera,
+                        image,
+                    },
+                    ObjectIdBuffer {
+                        size,
+                        scale_factor: camera.target_scaling_factor().unwrap_or(1.0),
+                        data: Vec::new(),
+                    },
+                ));
+            }
+            (Some(target), true, Some(size)) => {
+                if projection.is_changed() {
+                    if let Ok(mut id_projection) = projections.get_mut(target.camera) {
+                        *id_projection = (*projection).clone();
+                    }
+                }
+                let Ok(mut buffer) = buffers.get_mut(entity) else {
+                    continue;
+                };
+                buffer.scale_factor = camera.target_scaling_factor().unwrap_or(1.0);
+                if buffer.size != size {
+                    if let Some(image) = images.get_mut(&target.image) {
+                        image.resize(Extent3d {
+                            width: size.x,
+                            height: size.y,
+                            depth_or_array_layers: 1,
+                        });
+                    }
+                    buffer.size = size;
+                    buffer.data.clear();
+                }
+            }
+            (Some(target), false, _) => {
+                if let Ok(mut id_camera) = commands.get_entity(target.camera) {
+                    id_camera.despawn();
+                }
+                commands
+                    .entity(entity)
+                    .remove::<(ObjectIdTarget, ObjectIdBuffer)>();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_object_ids(
+    readback: On<ReadbackComplete>,
+    id_cameras: Query<&ChildOf>,
+    mut buffers: Query<&mut ObjectIdBuffer>,
+) {
+    let Ok(child_of) = id_cameras.get(readback.entity) else {
+        return;
+    };
+    if let Ok(mut buffer) = buffers.get_mut(child_of.parent()) {
+        // Skip readbacks started before a resize
+        if readback.data.len() == buffer.stride() * buffer.size.y as usize {
+            buffer.data.clone_from(&readback.data);
+        }
+    }
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f => f32::NAN,
+        _ => sign * (1024.0 + mantissa) * 2f32.powi(exponent - 25),
+    }
+}
@@ -0,0 +1,12 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_functions::get_tag,
+}
+
+// The tag of the mesh is its object id, split in two 11 bit halves that are
+// stored exactly in 16 bit floats
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let id = get_tag(in.instance_index);
+    return vec4(f32(id & 0x7ffu), f32(id >> 11u), 0.0, 1.0);
+}
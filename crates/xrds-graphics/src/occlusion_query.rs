@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{embedded_asset, load_embedded_asset},
+    camera::{primitives::Aabb, visibility::VisibilitySystems},
+    core_pipeline::core_3d::{
+        graph::{Core3d, Node3d},
+        CORE_3D_DEPTH_FORMAT,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        camera::ExtractedCamera,
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{binding_types::uniform_buffer, *},
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        view::{ExtractedView, Msaa, ViewDepthTexture},
+        Extract, ExtractSchedule, Render, RenderApp, RenderStartup, RenderSystems,
+    },
+};
+
+use crate::{GpuQueries, GpuQueryKind, GpuQueryPool, GpuQueryResults};
+
+/// Bounding boxes tested per frame, further boxes are not tested
+const MAX_OCCLUSION_QUERIES: u32 = 1024;
+
+const LABEL_PREFIX: &str = "occlusion/";
+
+/// Hides a mesh while its bounding box is occluded in every view
+///
+/// The bounding box is tested against the depth of the opaque pass with a GPU
+/// occlusion query. Results arrive a few frames late, so a mesh coming out
+/// from behind an occluder can appear late. Needs the [`GpuQueryPlugin`].
+///
+/// [`GpuQueryPlugin`]: crate::GpuQueryPlugin
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct OcclusionQuery;
+
+/// Tests [`OcclusionQuery`] meshes after the opaque pass and hides occluded
+/// ones
+#[derive(Debug, Default)]
+pub struct OcclusionQueryPlugin;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct OcclusionQueryLabel;
+
+#[derive(Clone, Copy, ShaderType)]
+struct OcclusionBoxUniform {
+    clip_from_box: Mat4,
+}
+
+/// World transforms of the unit cubes around the [`OcclusionQuery`] meshes
+#[derive(Resource, Default)]
+struct OcclusionBoxes(Vec<(Entity, Mat4)>);
+
+#[derive(Resource, Default)]
+struct OcclusionBoxUniforms(DynamicUniformBuffer<OcclusionBoxUniform>);
+
+#[derive(Resource)]
+struct OcclusionQueryPipeline {
+    layout: BindGroupLayout,
+    shader: Handle<Shader>,
+    /// Pipelines by sample count of the depth texture
+    pipelines: HashMap<u32, CachedRenderPipelineId>,
+    pool: Option<GpuQueryPool>,
+}
+
+#[derive(Component)]
+struct ViewOcclusionQueries {
+    pipeline: CachedRenderPipelineId,
+    /// Main world mesh and uniform offset of each box
+    boxes: Vec<(Entity, u32)>,
+}
+
+#[derive(Default)]
+struct OcclusionQueryNode;
+
+impl OcclusionQueryPipeline {
+    fn pipeline(&mut self, pipeline_cache: &PipelineCache, samples: u32) -> CachedRenderPipelineId {
+        *self.pipelines.entry(samples).or_insert_with(|| {
+            pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                label: Some("occlusion_query_pipeline".into()),
+                layout: vec![self.layout.clone()],
+                vertex: VertexState {
+                    shader: self.shader.clone(),
+                    ..Default::default()
+                },
+                primitive: PrimitiveState {
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                // Reversed depth, boxes in front of the opaque depth pass
+                depth_stencil: Some(DepthStencilState {
+                    format: CORE_3D_DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::GreaterEqual,
+                    stencil: default(),
+                    bias: default(),
+                }),
+                multisample: MultisampleState {
+                    count: samples,
+                    ..Default::default()
+                },
+                fragment: None,
+                ..Default::default()
+            })
+        })
+    }
+}
+
+impl Plugin for OcclusionQueryPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "occlusion_query.wgsl");
+
+        app.add_systems(
+            PostUpdate,
+            hide_occluded_meshes
+                .after(VisibilitySystems::CheckVisibility)
+                .run_if(resource_exists::<GpuQueryResults>),
+        );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<OcclusionBoxes>()
+            .init_resource::<OcclusionBoxUniforms>()
+            .add_systems(RenderStartup, init_occlusion_query_pipeline)
+            .add_systems(ExtractSchedule, extract_occlusion_boxes)
+            .add_systems(
+                Render,
+                prepare_occlusion_queries.in_set(RenderSystems::PrepareResources),
+            )
+            .add_render_graph_node::<ViewNodeRunner<OcclusionQueryNode>>(
+                Core3d,
+                OcclusionQueryLabel,
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::MainOpaquePass,
+                    OcclusionQueryLabel,
+                    Node3d::MainTransmissivePass,
+                ),
+            );
+    }
+}
+
+impl ViewNode for OcclusionQueryNode {
+    type ViewQuery = (
+        &'static ViewOcclusionQueries,
+        &'static ViewDepthTexture,
+        &'static ExtractedCamera,
+    );
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (queries, depth, camera): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        if queries.boxes.is_empty() {
+            return Ok(());
+        }
+        let occlusion_pipeline = world.resource::<OcclusionQueryPipeline>();
+        let Some(pool) = &occlusion_pipeline.pool else {
+            return Ok(());
+        };
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(queries.pipeline) else {
+            return Ok(());
+        };
+        let uniforms = world.resource::<OcclusionBoxUniforms>();
+        let Some(uniform_binding) = uniforms.0.binding() else {
+            return Ok(());
+        };
+
+        let bind_group = render_context.render_device().create_bind_group(
+            "occlusion_query_bind_group",
+            &occlusion_pipeline.layout,
+            &BindGroupEntries::single(uniform_binding),
+        );
+        let query_set = pool.occlusion_query_set();
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("occlusion_query_pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(depth.get_attachment(StoreOp::Store)),
+            timestamp_writes: None,
+            occlusion_query_set: query_set.as_ref(),
+        });
+        if let Some(viewport) = camera.viewport.as_ref() {
+            render_pass.set_camera_viewport(viewport);
+        }
+        render_pass.set_render_pipeline(pipeline);
+
+        for (entity, offset) in queries.boxes.iter() {
+            let label = occlusion_query_label(*entity, graph.view_entity());
+            pool.scope(&mut render_pass, label, |render_pass| {
+                render_pass.set_bind_group(0, &bind_group, &[*offset]);
+                render_pass.draw(0..36, 0..1);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Label of the query of `entity` in `view`
+pub(crate) fn occlusion_query_label(entity: Entity, view: Entity) -> String {
+    format!("{}{}/{}", LABEL_PREFIX, entity.to_bits(), view.to_bits())
+}
+
+fn init_occlusion_query_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    queries: Option<Res<GpuQueries>>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "occlusion_query_bind_group_layout",
+        &BindGroupLayoutEntries::single(
+            ShaderStages::VERTEX,
+            uniform_buffer::<OcclusionBoxUniform>(true),
+        ),
+    );
+    let pool = queries.and_then(|queries| {
+        queries.create_pool(
+            &render_device,
+            GpuQueryKind::Occlusion,
+            MAX_OCCLUSION_QUERIES,
+        )
+    });
+    if pool.is_none() {
+        warn!("Occlusion queries need the GpuQueryPlugin");
+    }
+
+    commands.insert_resource(OcclusionQueryPipeline {
+        layout,
+        shader: load_embedded_asset!(asset_server.as_ref(), "occlusion_query.wgsl"),
+        pipelines: HashMap::new(),
+        pool,
+    });
+}
+
+#[allow(clippy::type_complexity)]
+fn extract_occlusion_boxes(
+    mut boxes: ResMut<OcclusionBoxes>,
+    meshes: Extract<
+        Query<(Entity, &GlobalTransform, &Aabb, &InheritedVisibility), With<OcclusionQuery>>,
+    >,
+) {
+    boxes.0.clear();
+    for (entity, transform, aabb, visibility) in meshes.iter() {
+        if !visibility.get() {
+            continue;
+        }
+        let local_from_box = Mat4::from_scale_rotation_translation(
+            Vec3::from(aabb.half_extents) * 2.0,
+            Quat::IDENTITY,
+            aabb.center.into(),
+        );
+        boxes
+            .0
+            .push((entity, transform.to_matrix() * local_from_box));
+    }
+}
+
+fn prepare_occlusion_queries(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipeline: ResMut<OcclusionQueryPipeline>,
+    boxes: Res<OcclusionBoxes>,
+    mut uniforms: ResMut<OcclusionBoxUniforms>,
+    views: Query<(Entity, &ExtractedView, &Msaa)>,
+) {
+    uniforms.0.clear();
+    if pipeline.pool.is_none() {
+        return;
+    }
+    if boxes.0.len() > MAX_OCCLUSION_QUERIES as usize {
+        warn_once!(
+            "More than {} meshes with occlusion queries",
+            MAX_OCCLUSION_QUERIES
+        );
+    }
+
+    for (entity, view, msaa) in views.iter() {
+        let clip_from_world = view
+            .clip_from_world
+            .unwrap_or_else(|| view.clip_from_view * view.world_from_view.to_matrix().inverse());
+        let view_boxes = boxes
+            .0
+            .iter()
+            .take(MAX_OCCLUSION_QUERIES as usize)
+            .map(|(mesh, world_from_box)| {
+                let offset = uniforms.0.push(&OcclusionBoxUniform {
+                    clip_from_box: clip_from_world * *world_from_box,
+                });
+                (*mesh, offset)
+            })
+            .collect();
+        commands.entity(entity).insert(ViewOcclusionQueries {
+            pipeline: pipeline.pipeline(&pipeline_cache, msaa.samples()),
+            boxes: view_boxes,
+        });
+    }
+    uniforms.0.write_buffer(&render_device, &render_queue);
+}
+
+/// Hides [`OcclusionQuery`] meshes whose bounding box was occluded in all
+/// views of the latest results
+#[allow(clippy::type_complexity)]
+fn hide_occluded_meshes(
+    results: Res<GpuQueryResults>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut meshes: Query<(Entity, &GlobalTransform, &Aabb, &mut ViewVisibility), With<OcclusionQuery>>,
+) {
+    let mut visible = HashMap::<Entity, bool>::new();
+    for (label, result) in results.iter() {
+        let Some(bits) = label
+            .strip_prefix(LABEL_PREFIX)
+            .and_then(|label| label.split_once('/'))
+            .and_then(|(entity, _)| entity.parse().ok())
+        else {
+            continue;
+        };
+        let (Some(entity), Some(is_visible)) = (Entity::try_from_bits(bits), result.is_visible())
+        else {
+            continue;
+        };
+        *visible.entry(entity).or_default() |= is_visible;
+    }
+
+    for (entity, transform, aabb, mut view_visibility) in meshes.iter_mut() {
+        if !view_visibility.get() || visible.get(&entity) != Some(&false) {
+            continue;
+        }
+        // The box of a camera standing inside it is clipped by the near plane
+        let local_from_world = transform.to_matrix().inverse();
+        let contains_camera = cameras.iter().any(|(camera, camera_transform)| {
+            let position =
+                Vec3A::from(local_from_world.transform_point3(camera_transform.translation()));
+            camera.is_active
+                && (position - aabb.center)
+                    .abs()
+                    .cmple(aabb.half_extents)
+                    .all()
+        });
+        if !contains_camera {
+            *view_visibility = ViewVisibility::HIDDEN;
+        }
+    }
+}
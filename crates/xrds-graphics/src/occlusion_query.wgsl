@@ -0,0 +1,25 @@
+struct OcclusionBox {
+    clip_from_box: mat4x4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> occlusion_box: OcclusionBox;
+
+@vertex
+fn vertex(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // Corners of the unit cube, bit 0 is x, bit 1 is y and bit 2 is z
+    var corners = array<u32, 36>(
+        0u, 2u, 4u, 2u, 6u, 4u,
+        1u, 5u, 3u, 3u, 5u, 7u,
+        0u, 4u, 1u, 1u, 4u, 5u,
+        2u, 3u, 6u, 3u, 7u, 6u,
+        0u, 1u, 2u, 1u, 3u, 2u,
+        4u, 6u, 5u, 5u, 6u, 7u,
+    );
+    let corner = corners[vertex_index];
+    let position = vec3<f32>(
+        f32(corner & 1u),
+        f32((corner >> 1u) & 1u),
+        f32((corner >> 2u) & 1u),
+    ) - 0.5;
+    return occlusion_box.clip_from_box * vec4<f32>(position, 1.0);
+}
@@ -0,0 +1,40 @@
+#import bevy_pbr::{
+    mesh_functions::{get_world_from_local, mesh_normal_local_to_world, mesh_position_local_to_world},
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+
+struct OutlineSettings {
+    color: vec4<f32>,
+    thickness: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> outline: OutlineSettings;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+// Inverted hull: back faces pushed out along the normals show around the mesh
+@vertex
+fn vertex(vertex: Vertex) -> @builtin(position) vec4<f32> {
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    let world_position = mesh_position_local_to_world(world_from_local, vec4(vertex.position, 1.0));
+    let world_normal = mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+    let clip_position = position_world_to_clip(world_position.xyz);
+
+    let clip_normal = (view.clip_from_world * vec4(world_normal, 0.0)).xy;
+    if dot(clip_normal, clip_normal) < 1e-12 {
+        return clip_position;
+    }
+    // Thickness in pixels, the same at any distance
+    let offset = normalize(clip_normal) * outline.thickness * 2.0 / view.viewport.zw;
+    return vec4(clip_position.xy + offset * clip_position.w, clip_position.zw);
+}
+
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return outline.color;
+}
@@ -0,0 +1,266 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+/// Paintable layer over the base color of a mesh, e.g. a whiteboard or an annotated model
+///
+/// A transparent canvas texture of `size` pixels is created for the mesh of
+/// the entity and drawn over it by a child sharing the mesh. Paint into it
+/// with [`PaintStroke`] messages at UV coordinates, e.g. the `uv` of a
+/// `MeshRayCast` hit.
+#[derive(Component, Debug, Clone)]
+#[require(Transform, Visibility)]
+pub struct PaintSurface {
+    pub size: UVec2,
+    canvas: Option<Handle<Image>>,
+}
+
+/// Marks the child drawing the canvas of a [`PaintSurface`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PaintLayer;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Brush {
+    pub color: Color,
+    /// Radius in canvas pixels
+    pub radius: f32,
+    /// Part of the radius painted at full strength, the rest fades out
+    pub hardness: f32,
+    /// Remove paint instead of adding it
+    pub erase: bool,
+}
+
+/// Paint a dab at `to`, or a line when `from` is set, into a [`PaintSurface`]
+#[derive(Message, Debug, Clone, Copy)]
+pub struct PaintStroke {
+    pub surface: Entity,
+    pub from: Option<Vec2>,
+    pub to: Vec2,
+    pub brush: Brush,
+}
+
+/// Remove all paint of a [`PaintSurface`]
+#[derive(Message, Debug, Clone, Copy)]
+pub struct ClearPaint {
+    pub surface: Entity,
+}
+
+#[derive(Debug, Default)]
+pub struct PaintPlugin;
+
+impl PaintSurface {
+    pub fn new(size: UVec2) -> Self {
+        Self { size, canvas: None }
+    }
+
+    /// Canvas texture, created once the mesh of the surface is known
+    pub fn canvas(&self) -> Option<&Handle<Image>> {
+        self.canvas.as_ref()
+    }
+}
+
+impl Brush {
+    pub fn new(color: Color, radius: f32) -> Self {
+        Self {
+            color,
+            radius,
+            hardness: 0.8,
+            erase: false,
+        }
+    }
+
+    pub fn eraser(radius: f32) -> Self {
+        Self {
+            color: Color::NONE,
+            radius,
+            hardness: 1.0,
+            erase: true,
+        }
+    }
+
+    pub fn with_hardness(mut self, hardness: f32) -> Self {
+        self.hardness = hardness;
+        self
+    }
+}
+
+impl PaintStroke {
+    pub fn dab(surface: Entity, uv: Vec2, brush: Brush) -> Self {
+        Self {
+            surface,
+            from: None,
+            to: uv,
+            brush,
+        }
+    }
+
+    pub fn line(surface: Entity, from: Vec2, to: Vec2, brush: Brush) -> Self {
+        Self {
+            surface,
+            from: Some(from),
+            to,
+            brush,
+        }
+    }
+}
+
+impl Plugin for PaintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PaintStroke>()
+            .add_message::<ClearPaint>()
+            .add_systems(
+                Update,
+                (create_paint_canvases, apply_paint)
+                    .chain()
+                    .run_if(resource_exists::<Assets<Image>>)
+                    .run_if(resource_exists::<Assets<StandardMaterial>>),
+            );
+    }
+}
+
+/// Transparent canvas to paint into
+pub fn paint_canvas(size: UVec2) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: size.x.max(1),
+            height: size.y.max(1),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Paint a stroke from `from` to `to` in UV coordinates into an RGBA8 canvas
+pub fn paint_stroke(canvas: &mut Image, from: Option<Vec2>, to: Vec2, brush: &Brush) {
+    let size = canvas.size().as_vec2();
+    let to = to * size;
+    let from = from.map_or(to, |from| from * size);
+
+    // Dabs overlap by three quarters so lines have no gaps
+    let spacing = (brush.radius * 0.25).max(0.5);
+    let steps = (from.distance(to) / spacing).ceil() as u32;
+    for step in 0..=steps {
+        let center = from.lerp(to, step as f32 / steps.max(1) as f32);
+        paint_dab(canvas, center, brush);
+    }
+}
+
+fn paint_dab(canvas: &mut Image, center: Vec2, brush: &Brush) {
+    let width = canvas.width() as i32;
+    let height = canvas.height() as i32;
+    let Some(data) = canvas.data.as_mut() else {
+        return;
+    };
+    let color = brush.color.to_srgba();
+    let hard_radius = brush.radius * brush.hardness.clamp(0.0, 1.0);
+
+    let min = (center - brush.radius).floor().as_ivec2().max(IVec2::ZERO);
+    let max = (center + brush.radius)
+        .ceil()
+        .as_ivec2()
+        .min(IVec2::new(width - 1, height - 1));
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            // Pixel centers
+            let distance = Vec2::new(x as f32 + 0.5, y as f32 + 0.5).distance(center);
+            if distance > brush.radius {
+                continue;
+            }
+            let coverage = if distance <= hard_radius {
+                1.0
+            } else {
+                1.0 - (distance - hard_radius) / (brush.radius - hard_radius)
+            };
+
+            let i = ((y * width + x) * 4) as usize;
+            let Some(pixel) = data.get_mut(i..i + 4) else {
+                continue;
+            };
+            let alpha = pixel[3] as f32 / 255.0;
+            if brush.erase {
+                pixel[3] = ((alpha * (1.0 - coverage)) * 255.0).round() as u8;
+                continue;
+            }
+
+            // Source over destination with straight alpha
+            let src_alpha = color.alpha * coverage;
+            let out_alpha = src_alpha + alpha * (1.0 - src_alpha);
+            if out_alpha <= 0.0 {
+                continue;
+            }
+            for (channel, src) in pixel.iter_mut().zip([color.red, color.green, color.blue]) {
+                let dst = *channel as f32 / 255.0;
+                let value = (src * src_alpha + dst * alpha * (1.0 - src_alpha)) / out_alpha;
+                *channel = (value * 255.0).round() as u8;
+            }
+            pixel[3] = (out_alpha * 255.0).round() as u8;
+        }
+    }
+}
+
+fn create_paint_canvases(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut surfaces: Query<(Entity, &mut PaintSurface, &Mesh3d)>,
+) {
+    for (entity, mut surface, mesh) in surfaces.iter_mut() {
+        if surface.canvas.is_some() {
+            continue;
+        }
+        let canvas = images.add(paint_canvas(surface.size));
+        let material = materials.add(StandardMaterial {
+            base_color_texture: Some(canvas.clone()),
+            alpha_mode: AlphaMode::Blend,
+            // Keep the paint in front of the surface it lies on
+            depth_bias: 1.0,
+            ..Default::default()
+        });
+        commands.entity(entity).with_child((
+            PaintLayer,
+            Mesh3d(mesh.0.clone()),
+            MeshMaterial3d(material),
+            Pickable::IGNORE,
+        ));
+        surface.canvas = Some(canvas);
+    }
+}
+
+fn apply_paint(
+    mut strokes: MessageReader<PaintStroke>,
+    mut clears: MessageReader<ClearPaint>,
+    mut images: ResMut<Assets<Image>>,
+    surfaces: Query<&PaintSurface>,
+) {
+    for clear in clears.read() {
+        let Some(canvas) = surfaces
+            .get(clear.surface)
+            .ok()
+            .and_then(|surface| surface.canvas.as_ref())
+        else {
+            continue;
+        };
+        if let Some(data) = images.get_mut(canvas).and_then(|image| image.data.as_mut()) {
+            data.fill(0);
+        }
+    }
+
+    for stroke in strokes.read() {
+        let Some(canvas) = surfaces
+            .get(stroke.surface)
+            .ok()
+            .and_then(|surface| surface.canvas.as_ref())
+        else {
+            warn!("Paint stroke for unknown surface {:?}", stroke.surface);
+            continue;
+        };
+        if let Some(image) = images.get_mut(canvas) {
+            paint_stroke(image, stroke.from, stroke.to, &stroke.brush);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use bevy::{
+    camera::primitives::Aabb,
+    math::Affine3A,
+    mesh::{Indices, PrimitiveTopology},
+    picking::mesh_picking::ray_cast::{ray_mesh_intersection, Backfaces, RayMeshHit},
+    prelude::*,
+};
+
+/// Casts rays against the spawned meshes of a world, e.g. for a controller
+/// pointer or mouse picking in the window
+///
+/// Rays are tested against the bounding box of each mesh and, if
+/// [`RaycastSettings::triangles`] is set, against its triangles. Hidden
+/// meshes and meshes with `Pickable::IGNORE` are left out. Bounding boxes are
+/// computed by Bevy in `PostUpdate`, so meshes spawned this frame are not hit
+/// yet.
+pub trait Raycast {
+    /// Nearest hit along the ray with the default settings
+    fn raycast(&mut self, origin: Vec3, direction: Vec3) -> Option<RaycastHit> {
+        self.raycast_with(origin, direction, &RaycastSettings::default())
+    }
+
+    fn raycast_with(
+        &mut self,
+        origin: Vec3,
+        direction: Vec3,
+        settings: &RaycastSettings,
+    ) -> Option<RaycastHit>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastSettings {
+    /// Test the triangles of meshes whose bounding box is hit
+    pub triangles: bool,
+    pub max_distance: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    /// Spawned object that was hit, the nearest ancestor with a `SceneRoot`
+    /// or else the mesh entity itself
+    pub spawned: Entity,
+    /// Mesh entity that was hit
+    pub entity: Entity,
+    /// Distance from the origin of the ray
+    pub distance: f32,
+    pub point: Vec3,
+    /// Normal of the bounding box face or triangle in world space
+    pub normal: Vec3,
+}
+
+impl Default for RaycastSettings {
+    fn default() -> Self {
+        Self {
+            triangles: false,
+            max_distance: f32::INFINITY,
+        }
+    }
+}
+
+impl RaycastSettings {
+    pub fn with_triangles(mut self, triangles: bool) -> Self {
+        self.triangles = triangles;
+        self
+    }
+
+    pub fn with_max_distance(mut self, max_distance: f32) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+}
+
+impl Raycast for World {
+    fn raycast_with(
+        &mut self,
+        origin: Vec3,
+        direction: Vec3,
+        settings: &RaycastSettings,
+    ) -> Option<RaycastHit> {
+        let Ok(direction) = Dir3::new(direction) else {
+            return None;
+        };
+        let ray = Ray3d::new(origin, direction);
+
+        let mut meshes = self.query::<(
+            Entity,
+            &Aabb,
+            &GlobalTransform,
+            Option<&Mesh3d>,
+            Option<&InheritedVisibility>,
+            Option<&Pickable>,
+        )>();
+        let mesh_assets = self.get_resource::<Assets<Mesh>>();
+        let mut nearest: Option<RaycastHit> = None;
+        for (entity, aabb, transform, mesh, visibility, pickable) in meshes.iter(self) {
+            if visibility.is_some_and(|visibility| !visibility.get())
+                || pickable == Some(&Pickable::IGNORE)
+            {
+                continue;
+            }
+            let max_distance = nearest.map_or(settings.max_distance, |hit| hit.distance);
+            let transform = transform.affine();
+            let Some((mut distance, mut normal)) = ray_aabb_hit(ray, aabb, &transform) else {
+                continue;
+            };
+            if distance > max_distance {
+                continue;
+            }
+
+            let mesh = mesh.and_then(|mesh| mesh_assets?.get(&mesh.0));
+            if let (true, Some(mesh)) = (settings.triangles, mesh) {
+                let Some(hit) = ray_mesh_hit(ray, mesh, &transform) else {
+                    continue;
+                };
+                if hit.distance > max_distance {
+                    continue;
+                }
+                distance = hit.distance;
+                normal = hit.normal.normalize_or_zero();
+            }
+
+            nearest = Some(RaycastHit {
+                spawned: entity,
+                entity,
+                distance,
+                point: ray.get_point(distance),
+                normal,
+            });
+        }
+
+        let mut hit = nearest?;
+        let mut ancestor = Some(hit.entity);
+        while let Some(entity) = ancestor {
+            if self.get::<SceneRoot>(entity).is_some() {
+                hit.spawned = entity;
+                break;
+            }
+            ancestor = self.get::<ChildOf>(entity).map(ChildOf::parent);
+        }
+        Some(hit)
+    }
+}
+
+/// Distance to the bounding box and normal of the face that was hit, the
+/// ray direction reversed if it starts inside
+fn ray_aabb_hit(ray: Ray3d, aabb: &Aabb, transform: &Affine3A) -> Option<(f32, Vec3)> {
+    // Distances along the ray are the same in mesh space
+    let mesh_from_world = transform.inverse();
+    let origin = mesh_from_world.transform_point3(ray.origin);
+    let direction = mesh_from_world.transform_vector3(*ray.direction);
+    let min = Vec3::from(aabb.min()) - origin;
+    let max = Vec3::from(aabb.max()) - origin;
+
+    let mut near = (0.0, None);
+    let mut far = f32::INFINITY;
+    for axis in 0..3 {
+        if direction[axis] == 0.0 {
+            if min[axis] > 0.0 || max[axis] < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let (t0, t1) = (min[axis] / direction[axis], max[axis] / direction[axis]);
+        let (enter, exit) = (t0.min(t1), t0.max(t1));
+        if enter > near.0 {
+            near = (enter, Some(axis));
+        }
+        far = far.min(exit);
+    }
+    if near.0 > far {
+        return None;
+    }
+
+    let normal = match near.1 {
+        Some(axis) => {
+            let mut normal = Vec3::ZERO;
+            normal[axis] = -direction[axis].signum();
+            (transform.matrix3.inverse().transpose() * Vec3A::from(normal)).normalize_or_zero()
+        }
+        None => Vec3A::from(-*ray.direction),
+    };
+    Some((near.0, normal.into()))
+}
+
+fn ray_mesh_hit(ray: Ray3d, mesh: &Mesh, transform: &Affine3A) -> Option<RayMeshHit> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+    let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION)?.as_float3()?;
+    let normals = mesh
+        .attribute(Mesh::ATTRIBUTE_NORMAL)
+        .and_then(|normals| normals.as_float3());
+    match mesh.indices() {
+        Some(Indices::U16(indices)) => ray_mesh_intersection(
+            ray,
+            transform,
+            positions,
+            normals,
+            Some(indices),
+            None,
+            Backfaces::Include,
+        ),
+        Some(Indices::U32(indices)) => ray_mesh_intersection(
+            ray,
+            transform,
+            positions,
+            normals,
+            Some(indices),
+            None,
+            Backfaces::Include,
+        ),
+        None => ray_mesh_intersection::<usize>(
+            ray,
+            transform,
+            positions,
+            normals,
+            None,
+            None,
+            Backfaces::Include,
+        ),
+    }
+}
@@ -0,0 +1,249 @@
+use std::{fmt, time::Duration};
+
+use bevy::{
+    diagnostic::DiagnosticsStore, platform::time::Instant, prelude::*,
+    render::diagnostic::RenderDiagnosticsPlugin,
+};
+
+/// Group of render passes shown in the stats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderPhase {
+    Shadows,
+    /// Depth, normal and deferred prepasses
+    GBuffer,
+    /// Opaque, transmissive and transparent passes and deferred lighting
+    Lighting,
+    Post,
+    Other,
+}
+
+/// Timings of one render pass of the latest measured frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTiming {
+    /// Span name, nested spans are joined with `/`
+    pub name: String,
+    pub phase: RenderPhase,
+    /// Time spent recording the pass
+    pub cpu: Option<Duration>,
+    /// `None` if the device has no timestamp queries
+    pub gpu: Option<Duration>,
+}
+
+/// CPU and GPU timings of recent frames
+///
+/// Pass timings come from the timestamp queries of
+/// [`RenderDiagnosticsPlugin`], so they are a few frames behind and only have
+/// GPU times on devices supporting timestamp queries inside passes.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RenderStats {
+    /// Time between the last two frames
+    pub frame_time: Duration,
+    /// Time spent in the main schedules of the last frame
+    pub update_time: Duration,
+    pub passes: Vec<PassTiming>,
+    update_started: Option<Instant>,
+    last_measurement: Option<Instant>,
+}
+
+/// UI text showing the [`RenderStats`]
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[require(Text, Node)]
+pub struct RenderStatsOverlay;
+
+/// Collects per pass GPU timestamps and CPU frame timings into [`RenderStats`]
+#[derive(Debug, Default)]
+pub struct RenderStatsPlugin;
+
+impl RenderPhase {
+    pub const ALL: [Self; 5] = [
+        Self::Shadows,
+        Self::GBuffer,
+        Self::Lighting,
+        Self::Post,
+        Self::Other,
+    ];
+
+    /// Phase of a span recorded by the Bevy render passes
+    pub fn of_span(name: &str) -> Self {
+        match name {
+            "shadows" => Self::Shadows,
+            name if name.contains("prepass") || name == "copy_deferred_lighting_id" => {
+                Self::GBuffer
+            }
+            "deferred_lighting"
+            | "main_opaque_pass_3d"
+            | "main_transmissive_pass_3d"
+            | "main_transparent_pass_3d"
+            | "ssao"
+            | "ssr"
+            | "volumetric_lighting"
+            | "render_sky" => Self::Lighting,
+            "tonemapping"
+            | "bloom"
+            | "auto_exposure"
+            | "motion_blur"
+            | "postprocessing"
+            | "fxaa"
+            | "smaa"
+            | "taa"
+            | "contrast_adaptive_sharpening"
+            | "oit_resolve"
+            | "msaa_writeback"
+            | "upscaling" => Self::Post,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for RenderPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Shadows => "shadows",
+            Self::GBuffer => "gbuffer",
+            Self::Lighting => "lighting",
+            Self::Post => "post",
+            Self::Other => "other",
+        })
+    }
+}
+
+impl RenderStats {
+    /// GPU time of all top level passes, `None` without timestamp queries
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.top_level()
+            .map(|pass| pass.gpu)
+            .sum::<Option<Duration>>()
+            .filter(|_| self.top_level().next().is_some())
+    }
+
+    /// GPU time of the passes of `phase`
+    pub fn phase_gpu_time(&self, phase: RenderPhase) -> Option<Duration> {
+        self.top_level()
+            .filter(|pass| pass.phase == phase)
+            .map(|pass| pass.gpu)
+            .sum()
+    }
+
+    /// When the latest pass timings were measured
+    pub fn measured_at(&self) -> Option<Instant> {
+        self.last_measurement
+    }
+
+    pub fn pass(&self, name: &str) -> Option<&PassTiming> {
+        self.passes.iter().find(|pass| pass.name == name)
+    }
+
+    fn top_level(&self) -> impl Iterator<Item = &PassTiming> {
+        self.passes.iter().filter(|pass| !pass.name.contains('/'))
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        writeln!(
+            f,
+            "CPU {:.2} ms (update {:.2} ms)",
+            millis(self.frame_time),
+            millis(self.update_time)
+        )?;
+        let Some(gpu_time) = self.gpu_time() else {
+            return write!(f, "GPU n/a");
+        };
+        write!(f, "GPU {:.2} ms", millis(gpu_time))?;
+        for phase in RenderPhase::ALL {
+            if let Some(time) = self.phase_gpu_time(phase).filter(|time| !time.is_zero()) {
+                write!(f, "\n  {phase} {:.2} ms", millis(time))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Plugin for RenderStatsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<RenderDiagnosticsPlugin>() {
+            app.add_plugins(RenderDiagnosticsPlugin);
+        }
+        app.init_resource::<DiagnosticsStore>()
+            .init_resource::<RenderStats>()
+            .add_systems(First, begin_update_timing)
+            // Render diagnostics are synced into the store in `PreUpdate`
+            .add_systems(Update, collect_pass_timings)
+            .add_systems(
+                Last,
+                (end_update_timing, update_render_stats_overlays).chain(),
+            );
+    }
+}
+
+fn begin_update_timing(time: Res<Time<Real>>, mut stats: ResMut<RenderStats>) {
+    stats.frame_time = time.delta();
+    stats.update_started = Some(Instant::now());
+}
+
+fn end_update_timing(mut stats: ResMut<RenderStats>) {
+    if let Some(started) = stats.update_started.take() {
+        stats.update_time = started.elapsed();
+    }
+}
+
+fn collect_pass_timings(store: Res<DiagnosticsStore>, mut stats: ResMut<RenderStats>) {
+    // Render diagnostics are paths like `render/<span>/<nested span>/elapsed_gpu`
+    let mut passes: Vec<PassTiming> = vec![];
+    let mut latest = stats.last_measurement;
+    for diagnostic in store.iter() {
+        let mut components = diagnostic.path().components().collect::<Vec<_>>();
+        if components.len() < 3 || components[0] != "render" {
+            continue;
+        }
+        let Some(field) = components.pop() else {
+            continue;
+        };
+        let (Some(measurement), "elapsed_cpu" | "elapsed_gpu") = (diagnostic.measurement(), field)
+        else {
+            continue;
+        };
+        latest = latest.max(Some(measurement.time));
+
+        let name = components[1..].join("/");
+        let pass = match passes.iter_mut().find(|pass| pass.name == name) {
+            Some(pass) => pass,
+            None => {
+                passes.push(PassTiming {
+                    phase: RenderPhase::of_span(components[1]),
+                    name,
+                    cpu: None,
+                    gpu: None,
+                });
+                passes.last_mut().unwrap()
+            }
+        };
+        let time = Duration::from_secs_f64(measurement.value.max(0.0) / 1000.0);
+        match field {
+            "elapsed_cpu" => pass.cpu = Some(time),
+            _ => pass.gpu = Some(time),
+        }
+    }
+    if latest == stats.last_measurement {
+        return;
+    }
+    passes.sort_by(|a, b| a.name.cmp(&b.name));
+    stats.passes = passes;
+    stats.last_measurement = latest;
+}
+
+fn update_render_stats_overlays(
+    stats: Res<RenderStats>,
+    mut overlays: Query<&mut Text, With<RenderStatsOverlay>>,
+) {
+    if overlays.is_empty() {
+        return;
+    }
+    let value = stats.to_string();
+    for mut text in overlays.iter_mut() {
+        if text.0 != value {
+            text.0.clone_from(&value);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::sync::Arc;
+
+use bevy::{
+    app::PluginsState,
+    asset::{AssetMetaCheck, AssetPath},
+    camera::{ManualTextureViewHandle, RenderTarget},
+    log::LogPlugin,
+    prelude::*,
+    render::{
+        pipelined_rendering::PipelinedRenderingPlugin,
+        renderer::{
+            RenderAdapter, RenderAdapterInfo, RenderDevice, RenderInstance, RenderQueue,
+            WgpuWrapper,
+        },
+        settings::{RenderCreation, RenderResources},
+        texture::ManualTextureView,
+        RenderApp, RenderPlugin,
+    },
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+
+/// Handle of the texture view the cameras of a [`Renderer`] draw into
+const RENDERER_TARGET: ManualTextureViewHandle =
+    ManualTextureViewHandle(u32::from_le_bytes(*b"XRDR"));
+
+/// wgpu objects the renderer shares with the embedding application
+///
+/// Raw API handles, e.g. the `VkDevice` for another engine, are reached
+/// through `as_hal` of the wgpu objects.
+#[derive(Clone)]
+pub struct RendererDevice {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+/// Builds a [`Renderer`] on the device of the embedding application
+pub struct RendererBuilder {
+    device: RendererDevice,
+    asset_path: String,
+    plugins: Vec<Box<dyn FnOnce(&mut App)>>,
+}
+
+/// Renderer for applications with their own window and event loop
+///
+/// Runs the Bevy renderer without `xrds-runtime`, winit or OpenXR: the
+/// application keeps the wgpu device and surface, loads scenes and calls
+/// [`Self::render`] or [`Self::render_surface`] whenever it wants a frame.
+/// Each call updates the world once and renders it before returning.
+/// Plugins of this crate are added with [`RendererBuilder::with_plugins`].
+pub struct Renderer {
+    app: App,
+    target_size: UVec2,
+}
+
+impl RendererDevice {
+    pub fn new(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+    ) -> Self {
+        Self {
+            instance,
+            adapter,
+            device,
+            queue,
+        }
+    }
+
+    /// Creates a device with all features and limits of the preferred adapter,
+    /// for applications without a device of their own. `surface` picks an
+    /// adapter able to present to it
+    pub fn request(
+        backends: wgpu::Backends,
+        surface: Option<&wgpu::Surface>,
+    ) -> anyhow::Result<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..default()
+        });
+        let adapter =
+            bevy::tasks::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter: false,
+                compatible_surface: surface,
+            }))?;
+        let (device, queue) =
+            bevy::tasks::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+                label: Some("xrds_renderer"),
+                required_features: adapter.features(),
+                required_limits: adapter.limits(),
+                ..default()
+            }))?;
+        Ok(Self::new(instance, adapter, device, queue))
+    }
+
+    /// Objects the Bevy renderer of `render_world` runs on
+    pub fn from_render_world(render_world: &World) -> Self {
+        let instance: &wgpu::Instance = &render_world.resource::<RenderInstance>().0;
+        let adapter: &wgpu::Adapter = &render_world.resource::<RenderAdapter>().0;
+        let queue: &wgpu::Queue = &render_world.resource::<RenderQueue>().0;
+        Self::new(
+            instance.clone(),
+            adapter.clone(),
+            render_world
+                .resource::<RenderDevice>()
+                .wgpu_device()
+                .clone(),
+            queue.clone(),
+        )
+    }
+
+    fn render_resources(&self) -> RenderResources {
+        RenderResources(
+            RenderDevice::new(WgpuWrapper::new(self.device.clone())),
+            RenderQueue(Arc::new(WgpuWrapper::new(self.queue.clone()))),
+            RenderAdapterInfo(WgpuWrapper::new(self.adapter.get_info())),
+            RenderAdapter(Arc::new(WgpuWrapper::new(self.adapter.clone()))),
+            RenderInstance(Arc::new(WgpuWrapper::new(self.instance.clone()))),
+        )
+    }
+}
+
+impl RendererBuilder {
+    /// Folder assets are loaded from, `assets` by default
+    pub fn with_asset_path(mut self, path: impl Into<String>) -> Self {
+        self.asset_path = path.into();
+        self
+    }
+
+    /// Plugins are built after the renderer
+    pub fn with_plugins<M: 'static>(mut self, plugins: impl Plugins<M> + 'static) -> Self {
+        self.plugins.push(Box::new(move |app| {
+            app.add_plugins(plugins);
+        }));
+        self
+    }
+
+    pub fn build(self) -> Renderer {
+        let mut app = App::new();
+        app.add_plugins(
+            DefaultPlugins
+                .build()
+                // The application owns the window, the logger and the frame loop
+                .disable::<WinitPlugin>()
+                .disable::<LogPlugin>()
+                .disable::<PipelinedRenderingPlugin>()
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    ..default()
+                })
+                .set(AssetPlugin {
+                    file_path: self.asset_path,
+                    // `.meta` files hold `ImportSettings`
+                    meta_check: AssetMetaCheck::Never,
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    render_creation: RenderCreation::Manual(self.device.render_resources()),
+                    ..default()
+                }),
+        );
+        for plugins in self.plugins {
+            plugins(&mut app);
+        }
+
+        while app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+
+        Renderer {
+            app,
+            target_size: UVec2::ZERO,
+        }
+    }
+}
+
+impl Renderer {
+    pub fn builder(device: RendererDevice) -> RendererBuilder {
+        RendererBuilder {
+            device,
+            asset_path: "assets".to_string(),
+            plugins: Vec::new(),
+        }
+    }
+
+    pub fn new(device: RendererDevice) -> Self {
+        Self::builder(device).build()
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    /// Device the renderer runs on, to create textures and buffers shared
+    /// with the application or to record its own passes
+    pub fn device(&self) -> RendererDevice {
+        RendererDevice::from_render_world(self.app.sub_app(RenderApp).world())
+    }
+
+    /// Spawns the first scene of a glTF file relative to the asset folder.
+    /// The scene appears once it is loaded, during one of the next frames
+    pub fn load_scene<'a>(&mut self, path: impl Into<AssetPath<'a>>) -> Entity {
+        let path = path.into().into_owned();
+        let world = self.app.world_mut();
+        let scene = world
+            .resource::<AssetServer>()
+            .load(GltfAssetLabel::Scene(0).from_asset(path));
+        world.spawn(SceneRoot(scene)).id()
+    }
+
+    /// Spawns a 3D camera drawing into the targets given to [`Self::render`]
+    pub fn spawn_camera(&mut self, transform: Transform) -> Entity {
+        self.app
+            .world_mut()
+            .spawn((
+                Camera3d::default(),
+                Camera {
+                    target: RenderTarget::TextureView(RENDERER_TARGET),
+                    ..default()
+                },
+                transform,
+            ))
+            .id()
+    }
+
+    /// Size of the last render target, zero before the first frame
+    pub fn target_size(&self) -> UVec2 {
+        self.target_size
+    }
+
+    /// Updates the world and renders one frame into `view`, which must be a
+    /// 2D view of `size` pixels with `format`
+    pub fn render(&mut self, view: &wgpu::TextureView, size: UVec2, format: wgpu::TextureFormat) {
+        self.target_size = size;
+        self.app
+            .world_mut()
+            .resource_mut::<ManualTextureViews>()
+            .insert(
+                RENDERER_TARGET,
+                ManualTextureView {
+                    texture_view: view.clone().into(),
+                    size,
+                    format,
+                },
+            );
+        // Extraction and rendering finish within the update without pipelined rendering
+        self.app.update();
+    }
+
+    /// Renders one frame into the next texture of a configured surface and presents it
+    pub fn render_surface(&mut self, surface: &wgpu::Surface) -> Result<(), wgpu::SurfaceError> {
+        let texture = surface.get_current_texture()?;
+        let view = texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let size = UVec2::new(texture.texture.width(), texture.texture.height());
+        self.render(&view, size, texture.texture.format());
+        texture.present();
+        Ok(())
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+};
+
+use bevy::{
+    core_pipeline::prepass::{DeferredPrepass, ViewPrepassTextures},
+    prelude::*,
+    render::{
+        renderer::{render_system, RenderDevice, RenderQueue},
+        sync_world::RenderEntity,
+        view::screenshot::{Screenshot, ScreenshotCaptured},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSystems,
+    },
+    tasks::{futures::check_ready, IoTaskPool, Task},
+};
+use image::{DynamicImage, ImageFormat};
+use wgpu::{
+    BufferDescriptor, BufferUsages, CommandEncoderDescriptor, MapMode, TexelCopyBufferInfo,
+    TexelCopyBufferLayout,
+};
+
+/// Bytes of a pixel of the `Rgba32Uint` deferred gbuffer
+const GBUFFER_PIXEL_SIZE: usize = 16;
+
+/// Writes a screenshot of `camera` to `path`
+///
+/// The final color target of the camera is copied into a mapped buffer after
+/// the frame is rendered, cropped to the viewport of the camera and encoded
+/// on the IO task pool. The extension of `path` picks the format: `.exr`
+/// files hold linear colors, other formats like `.png` the colors of the
+/// target. [`ScreenshotSaved`] reports the result.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct CaptureScreenshot {
+    pub camera: Entity,
+    pub path: PathBuf,
+    /// Also write the channels of the deferred gbuffer next to the
+    /// screenshot, e.g. `shot.normal.png` for `shot.png`. Only cameras with a
+    /// `DeferredPrepass`, e.g. from [`GBufferLayout::Deferred`](crate::GBufferLayout),
+    /// have a gbuffer
+    pub gbuffer: bool,
+}
+
+/// A screenshot, or the gbuffer channels of one, was written or failed
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct ScreenshotSaved {
+    pub camera: Entity,
+    /// Path of the [`CaptureScreenshot`]
+    pub path: PathBuf,
+    pub gbuffer: bool,
+    pub result: Result<(), String>,
+}
+
+/// Channels of the deferred gbuffer, decoded from its packed layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GBufferChannel {
+    /// sRGB color, the emissive channel holds the color of unlit materials
+    BaseColor,
+    /// Linear color
+    Emissive,
+    /// World space normal mapped to 0..1
+    Normal,
+    PerceptualRoughness,
+    Metallic,
+    Reflectance,
+    Occlusion,
+}
+
+/// Captures screenshots and gbuffers of cameras
+#[derive(Debug, Default)]
+pub struct ScreenshotCapturePlugin;
+
+/// Screenshot entity of Bevy waiting for the color target
+#[derive(Component, Debug, Clone)]
+struct PendingScreenshot {
+    camera: Entity,
+    path: PathBuf,
+    viewport: Option<URect>,
+}
+
+/// Gbuffer of the camera is read back in the frame it is inserted
+#[derive(Component, Debug, Clone)]
+struct CaptureGBuffer {
+    path: PathBuf,
+}
+
+/// Encoding and writing of a screenshot
+#[derive(Component)]
+struct ScreenshotTask {
+    camera: Entity,
+    path: PathBuf,
+    gbuffer: bool,
+    task: Task<Result<(), String>>,
+}
+
+struct GBufferReadback {
+    camera: Entity,
+    path: PathBuf,
+    size: UVec2,
+    /// Rows of packed pixels without padding
+    data: Vec<u8>,
+}
+
+#[derive(Resource)]
+struct GBufferReadbacks(Mutex<Receiver<GBufferReadback>>);
+
+#[derive(Resource)]
+struct GBufferReadbackSender(Sender<GBufferReadback>);
+
+/// Cameras whose gbuffer is read back this frame, by render entity
+#[derive(Resource, Default)]
+struct ExtractedGBufferCaptures(Vec<(Entity, Entity, PathBuf)>);
+
+impl CaptureScreenshot {
+    pub fn new(camera: Entity, path: impl Into<PathBuf>) -> Self {
+        Self {
+            camera,
+            path: path.into(),
+            gbuffer: false,
+        }
+    }
+
+    pub fn with_gbuffer(mut self) -> Self {
+        self.gbuffer = true;
+        self
+    }
+}
+
+impl GBufferChannel {
+    pub const ALL: [Self; 7] = [
+        Self::BaseColor,
+        Self::Emissive,
+        Self::Normal,
+        Self::PerceptualRoughness,
+        Self::Metallic,
+        Self::Reflectance,
+        Self::Occlusion,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BaseColor => "base_color",
+            Self::Emissive => "emissive",
+            Self::Normal => "normal",
+            Self::PerceptualRoughness => "roughness",
+            Self::Metallic => "metallic",
+            Self::Reflectance => "reflectance",
+            Self::Occlusion => "occlusion",
+        }
+    }
+
+    /// File the channel of the screenshot at `path` is written to
+    pub fn path(&self, path: &Path) -> PathBuf {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+        path.with_extension(format!("{}.{}", self.name(), extension))
+    }
+
+    /// Image of the channel from the packed gbuffer `data` of `size` pixels
+    pub fn decode(&self, data: &[u8], size: UVec2) -> Option<DynamicImage> {
+        if data.len() != size.x as usize * size.y as usize * GBUFFER_PIXEL_SIZE {
+            return None;
+        }
+        let words = data
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()));
+        let pixels: Vec<[u32; 4]> = words
+            .collect::<Vec<_>>()
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect();
+        let byte = |word: u32, index: u32| (word >> (index * 8)) as u8;
+        let image = match self {
+            Self::BaseColor => DynamicImage::ImageRgb8(image::RgbImage::from_raw(
+                size.x,
+                size.y,
+                pixels
+                    .iter()
+                    .flat_map(|p| [byte(p[0], 0), byte(p[0], 1), byte(p[0], 2)])
+                    .collect(),
+            )?),
+            Self::Emissive => DynamicImage::ImageRgb32F(image::Rgb32FImage::from_raw(
+                size.x,
+                size.y,
+                pixels.iter().flat_map(|p| rgb9e5_to_vec3(p[1])).collect(),
+            )?),
+            Self::Normal => DynamicImage::ImageRgb32F(image::Rgb32FImage::from_raw(
+                size.x,
+                size.y,
+                pixels
+                    .iter()
+                    .flat_map(|p| (octahedral_decode(p[3]) * 0.5 + 0.5).to_array())
+                    .collect(),
+            )?),
+            Self::PerceptualRoughness | Self::Metallic | Self::Reflectance | Self::Occlusion => {
+                let (word, index) = match self {
+                    Self::PerceptualRoughness => (0, 3),
+                    Self::Reflectance => (2, 0),
+                    Self::Metallic => (2, 1),
+                    _ => (2, 2),
+                };
+                DynamicImage::ImageLuma8(image::GrayImage::from_raw(
+                    size.x,
+                    size.y,
+                    pixels.iter().map(|p| byte(p[word], index)).collect(),
+                )?)
+            }
+        };
+        Some(image)
+    }
+}
+
+impl Plugin for ScreenshotCapturePlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        app.add_message::<CaptureScreenshot>()
+            .add_message::<ScreenshotSaved>()
+            .insert_resource(GBufferReadbacks(Mutex::new(receiver)))
+            .add_systems(First, receive_gbuffer_readbacks)
+            .add_systems(Update, finish_screenshot_tasks)
+            .add_systems(PostUpdate, start_screenshots);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(GBufferReadbackSender(sender))
+            .init_resource::<ExtractedGBufferCaptures>()
+            .add_systems(ExtractSchedule, extract_gbuffer_captures)
+            .add_systems(
+                Render,
+                read_back_gbuffers
+                    .after(render_system)
+                    .in_set(RenderSystems::Render),
+            );
+    }
+}
+
+/// Gbuffers of the previous frame were extracted, so they are only read once
+fn start_screenshots(
+    mut commands: Commands,
+    mut requests: MessageReader<CaptureScreenshot>,
+    mut saved: MessageWriter<ScreenshotSaved>,
+    cameras: Query<(&Camera, Has<DeferredPrepass>)>,
+    gbuffer_captures: Query<Entity, With<CaptureGBuffer>>,
+) {
+    for camera in gbuffer_captures.iter() {
+        commands.entity(camera).remove::<CaptureGBuffer>();
+    }
+
+    for request in requests.read() {
+        let Ok((camera, deferred)) = cameras.get(request.camera) else {
+            saved.write(ScreenshotSaved {
+                camera: request.camera,
+                path: request.path.clone(),
+                gbuffer: false,
+                result: Err(format!("{} is not a camera", request.camera)),
+            });
+            continue;
+        };
+        commands
+            .spawn((
+                Screenshot(camera.target.clone()),
+                PendingScreenshot {
+                    camera: request.camera,
+                    path: request.path.clone(),
+                    viewport: camera.viewport.as_ref().map(|viewport| URect {
+                        min: viewport.physical_position,
+                        max: viewport.physical_position + viewport.physical_size,
+                    }),
+                },
+            ))
+            .observe(encode_screenshot);
+
+        if !request.gbuffer {
+            continue;
+        }
+        if deferred {
+            commands.entity(request.camera).insert(CaptureGBuffer {
+                path: request.path.clone(),
+            });
+        } else {
+            saved.write(ScreenshotSaved {
+                camera: request.camera,
+                path: request.path.clone(),
+                gbuffer: true,
+                result: Err(format!("{} has no deferred gbuffer", request.camera)),
+            });
+        }
+    }
+}
+
+fn encode_screenshot(
+    captured: On<ScreenshotCaptured>,
+    mut commands: Commands,
+    screenshots: Query<&PendingScreenshot>,
+) {
+    let Ok(screenshot) = screenshots.get(captured.entity) else {
+        return;
+    };
+    let image = captured.image.clone();
+    let viewport = screenshot.viewport;
+    let path = screenshot.path.clone();
+    let task = IoTaskPool::get().spawn(async move {
+        let srgb = image.texture_descriptor.format.is_srgb();
+        let mut image = image.try_into_dynamic().map_err(|e| e.to_string())?;
+        if let Some(viewport) = viewport {
+            let size = viewport.size();
+            image = image.crop_imm(viewport.min.x, viewport.min.y, size.x, size.y);
+        }
+        save_image(image, &path, srgb)
+    });
+    commands.spawn(ScreenshotTask {
+        camera: screenshot.camera,
+        path: screenshot.path.clone(),
+        gbuffer: false,
+        task,
+    });
+}
+
+fn receive_gbuffer_readbacks(mut commands: Commands, readbacks: Res<GBufferReadbacks>) {
+    let Ok(receiver) = readbacks.0.lock() else {
+        return;
+    };
+    for readback in receiver.try_iter() {
+        let path = readback.path.clone();
+        let task = IoTaskPool::get().spawn(async move {
+            for channel in GBufferChannel::ALL {
+                let image = channel
+                    .decode(&readback.data, readback.size)
+                    .ok_or("Gbuffer readback has an unexpected size")?;
+                let srgb = channel == GBufferChannel::BaseColor;
+                save_image(image, &channel.path(&path), srgb)?;
+            }
+            Ok(())
+        });
+        commands.spawn(ScreenshotTask {
+            camera: readback.camera,
+            path: readback.path,
+            gbuffer: true,
+            task,
+        });
+    }
+}
+
+fn finish_screenshot_tasks(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut ScreenshotTask)>,
+    mut saved: MessageWriter<ScreenshotSaved>,
+) {
+    for (entity, mut task) in tasks.iter_mut() {
+        let Some(result) = check_ready(&mut task.task) else {
+            continue;
+        };
+        match &result {
+            Ok(()) => info!("Screenshot saved to {}", task.path.display()),
+            Err(error) => error!("Cannot save screenshot {}: {}", task.path.display(), error),
+        }
+        saved.write(ScreenshotSaved {
+            camera: task.camera,
+            path: task.path.clone(),
+            gbuffer: task.gbuffer,
+            result,
+        });
+        commands.entity(entity).despawn();
+    }
+}
+
+fn extract_gbuffer_captures(
+    mut captures: ResMut<ExtractedGBufferCaptures>,
+    cameras: Extract<Query<(Entity, RenderEntity, &CaptureGBuffer)>>,
+) {
+    captures.0 = cameras
+        .iter()
+        .map(|(camera, view, capture)| (view, camera, capture.path.clone()))
+        .collect();
+}
+
+/// Copies the gbuffers after the frame is submitted. The map callback sends
+/// them to the main world once the GPU finished the frame
+fn read_back_gbuffers(
+    captures: Res<ExtractedGBufferCaptures>,
+    sender: Res<GBufferReadbackSender>,
+    views: Query<&ViewPrepassTextures>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+) {
+    for (view, camera, path) in &captures.0 {
+        let Some(deferred) = views.get(*view).ok().and_then(|t| t.deferred.as_ref()) else {
+            warn!("Camera {} has no gbuffer to capture", camera);
+            continue;
+        };
+        let texture = &deferred.texture.texture;
+        let size = texture.size();
+        let row = size.width as usize * GBUFFER_PIXEL_SIZE;
+        let stride = RenderDevice::align_copy_bytes_per_row(row);
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("gbuffer_readback"),
+            size: (stride * size.height as usize) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("gbuffer_readback"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(stride as u32),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        queue.submit([encoder.finish()]);
+
+        let mapped = buffer.clone();
+        let sender = sender.0.clone();
+        let camera = *camera;
+        let path = path.clone();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            if let Err(error) = result {
+                warn!("Could not map the gbuffer of {}: {}", camera, error);
+                return;
+            }
+            let data = mapped
+                .slice(..)
+                .get_mapped_range()
+                .chunks(stride)
+                .flat_map(|chunk| &chunk[..row])
+                .copied()
+                .collect();
+            mapped.unmap();
+            let _ = sender.send(GBufferReadback {
+                camera,
+                path,
+                size: UVec2::new(size.width, size.height),
+                data,
+            });
+        });
+    }
+}
+
+/// 8 bit images are converted to floats for EXR, linearized when `srgb`, and
+/// float images to 8 bit for other formats. Alpha is dropped, HDR targets
+/// keep brightness in it
+fn save_image(image: DynamicImage, path: &Path, srgb: bool) -> Result<(), String> {
+    let format = ImageFormat::from_path(path).map_err(|e| e.to_string())?;
+    let image = match (format, image) {
+        (ImageFormat::OpenExr, image) => {
+            let mut linear = image.to_rgb32f();
+            if srgb {
+                for value in linear.iter_mut() {
+                    *value = Srgba::gamma_function(*value);
+                }
+            }
+            DynamicImage::ImageRgb32F(linear)
+        }
+        (_, DynamicImage::ImageLuma8(image)) => DynamicImage::ImageLuma8(image),
+        (_, image) => DynamicImage::ImageRgb8(image.to_rgb8()),
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    image
+        .save_with_format(path, format)
+        .map_err(|e| e.to_string())
+}
+
+/// Inverse of `vec3_to_rgb9e5_` of Bevy's deferred shaders
+fn rgb9e5_to_vec3(packed: u32) -> [f32; 3] {
+    let scale = 2f32.powi((packed >> 27) as i32 - 15 - 9);
+    [0, 9, 18].map(|offset| ((packed >> offset) & 0x1ff) as f32 * scale)
+}
+
+/// Normal of the 12 bit octahedral encoding in the low 24 bits
+fn octahedral_decode(packed: u32) -> Vec3 {
+    let unorm = |bits: u32| (bits & 0xfff) as f32 / 4095.0;
+    let f = Vec2::new(unorm(packed), unorm(packed >> 12)) * 2.0 - 1.0;
+    let mut n = Vec3::new(f.x, f.y, 1.0 - f.x.abs() - f.y.abs());
+    let t = (-n.z).clamp(0.0, 1.0);
+    n.x += if n.x >= 0.0 { -t } else { t };
+    n.y += if n.y >= 0.0 { -t } else { t };
+    n.normalize()
+}
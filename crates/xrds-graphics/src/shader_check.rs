@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use naga::{
+    back::wgsl::WriterFlags,
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+use naga_oil::compose::{
+    ComposableModuleDescriptor, Composer, NagaModuleDescriptor, ShaderDefValue, ShaderLanguage,
+    ShaderType,
+};
+
+/// Compiles every permutation of a WGSL shader without a GPU
+///
+/// Each combination of the boolean shader defs is preprocessed with the same
+/// composer the renderer uses, validated with naga and written back as WGSL,
+/// so broken permutations are caught in tests instead of on device.
+#[derive(Debug, Clone)]
+pub struct ShaderPermutations {
+    file_path: String,
+    source: String,
+    shader_defs: Vec<String>,
+    constants: Vec<(String, u32)>,
+    imports: Vec<(String, String)>,
+}
+
+/// Result of compiling one permutation
+#[derive(Debug, Clone)]
+pub struct CompiledPermutation {
+    /// Shader defs enabled in this permutation
+    pub shader_defs: Vec<String>,
+    /// Generated WGSL, or the preprocessor or validation error
+    pub result: Result<String, String>,
+}
+
+impl ShaderPermutations {
+    pub fn new(file_path: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            file_path: file_path.into(),
+            source: source.into(),
+            shader_defs: vec![],
+            constants: vec![],
+            imports: vec![],
+        }
+    }
+
+    /// Boolean shader def toggled between permutations
+    pub fn with_shader_def(mut self, shader_def: impl Into<String>) -> Self {
+        self.shader_defs.push(shader_def.into());
+        self
+    }
+
+    /// Shader def with the same value in every permutation, e.g. `MATERIAL_BIND_GROUP`
+    pub fn with_constant(mut self, shader_def: impl Into<String>, value: u32) -> Self {
+        self.constants.push((shader_def.into(), value));
+        self
+    }
+
+    /// Module imported by the shader, e.g. a bevy import
+    pub fn with_import(mut self, file_path: impl Into<String>, source: impl Into<String>) -> Self {
+        self.imports.push((file_path.into(), source.into()));
+        self
+    }
+
+    /// Every subset of the shader defs, starting with none enabled
+    pub fn permutations(&self) -> Vec<Vec<String>> {
+        (0..1usize << self.shader_defs.len())
+            .map(|mask| {
+                self.shader_defs
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask & (1 << i) != 0)
+                    .map(|(_, shader_def)| shader_def.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn compile(&self, shader_defs: &[String]) -> CompiledPermutation {
+        CompiledPermutation {
+            shader_defs: shader_defs.to_vec(),
+            result: self.compile_wgsl(shader_defs),
+        }
+    }
+
+    pub fn compile_all(&self) -> Vec<CompiledPermutation> {
+        self.permutations()
+            .iter()
+            .map(|shader_defs| self.compile(shader_defs))
+            .collect()
+    }
+
+    fn compile_wgsl(&self, shader_defs: &[String]) -> Result<String, String> {
+        let shader_defs: HashMap<_, _> = shader_defs
+            .iter()
+            .map(|shader_def| (shader_def.clone(), ShaderDefValue::Bool(true)))
+            .chain(
+                self.constants
+                    .iter()
+                    .map(|(shader_def, value)| (shader_def.clone(), ShaderDefValue::UInt(*value))),
+            )
+            .collect();
+
+        let mut composer = Composer::default();
+        for (file_path, source) in &self.imports {
+            composer
+                .add_composable_module(ComposableModuleDescriptor {
+                    source,
+                    file_path,
+                    language: ShaderLanguage::Wgsl,
+                    as_name: None,
+                    additional_imports: &[],
+                    shader_defs: shader_defs.clone(),
+                })
+                .map(|_| ())
+                .map_err(|e| e.emit_to_string(&composer))?;
+        }
+        let module = composer
+            .make_naga_module(NagaModuleDescriptor {
+                source: &self.source,
+                file_path: &self.file_path,
+                shader_type: ShaderType::Wgsl,
+                shader_defs,
+                additional_imports: &[],
+            })
+            .map_err(|e| e.emit_to_string(&composer))?;
+
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|e| format!("{}: {:?}", self.file_path, e.into_inner()))?;
+        naga::back::wgsl::write_string(&module, &info, WriterFlags::empty())
+            .map_err(|e| format!("{}: {}", self.file_path, e))
+    }
+}
+
+impl CompiledPermutation {
+    /// Name of the permutation, e.g. `TONEMAP+DITHER` or `default`
+    pub fn name(&self) -> String {
+        if self.shader_defs.is_empty() {
+            "default".to_owned()
+        } else {
+            self.shader_defs.join("+")
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Shadow bias of a point, spot or directional light on the same entity
+///
+/// Too little bias lets surfaces shadow themselves (acne), too much detaches
+/// shadows from their casters (peter-panning). Both offsets move the
+/// position compared against the shadow map of this light: `depth` towards
+/// the light in world units, `normal_offset` along the surface normal in
+/// shadow map texels. The normal offset grows the effective bias with the
+/// angle between surface and light, like a slope scaled bias, which Bevy's
+/// shadow passes do not have per light.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ShadowBias {
+    pub depth: f32,
+    pub normal_offset: f32,
+}
+
+/// Applies [`ShadowBias`] to lights
+#[derive(Debug, Default)]
+pub struct ShadowBiasPlugin;
+
+impl ShadowBias {
+    pub fn new(depth: f32, normal_offset: f32) -> Self {
+        Self {
+            depth,
+            normal_offset,
+        }
+    }
+
+    /// Bevy's default for point lights
+    pub fn point_light() -> Self {
+        Self::new(
+            PointLight::DEFAULT_SHADOW_DEPTH_BIAS,
+            PointLight::DEFAULT_SHADOW_NORMAL_BIAS,
+        )
+    }
+
+    /// Bevy's default for spot lights
+    pub fn spot_light() -> Self {
+        Self::new(
+            SpotLight::DEFAULT_SHADOW_DEPTH_BIAS,
+            SpotLight::DEFAULT_SHADOW_NORMAL_BIAS,
+        )
+    }
+
+    /// Bevy's default for directional lights
+    pub fn directional_light() -> Self {
+        Self::new(
+            DirectionalLight::DEFAULT_SHADOW_DEPTH_BIAS,
+            DirectionalLight::DEFAULT_SHADOW_NORMAL_BIAS,
+        )
+    }
+}
+
+impl Plugin for ShadowBiasPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, apply_shadow_bias);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_shadow_bias(
+    mut lights: Query<
+        (
+            &ShadowBias,
+            Option<&mut PointLight>,
+            Option<&mut SpotLight>,
+            Option<&mut DirectionalLight>,
+        ),
+        Or<(
+            Changed<ShadowBias>,
+            Added<PointLight>,
+            Added<SpotLight>,
+            Added<DirectionalLight>,
+        )>,
+    >,
+) {
+    for (bias, point, spot, directional) in lights.iter_mut() {
+        if let Some(mut light) = point {
+            light.shadow_depth_bias = bias.depth;
+            light.shadow_normal_bias = bias.normal_offset;
+        }
+        if let Some(mut light) = spot {
+            light.shadow_depth_bias = bias.depth;
+            light.shadow_normal_bias = bias.normal_offset;
+        }
+        if let Some(mut light) = directional {
+            light.shadow_depth_bias = bias.depth;
+            light.shadow_normal_bias = bias.normal_offset;
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::embedded_asset,
+    gltf::{Gltf, GltfAssetLabel},
+    pbr::{ExtendedMaterial, MaterialExtension, OpaqueRendererMethod},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderType},
+    shader::ShaderRef,
+};
+
+const SHEEN_SHADER: &str = "embedded://xrds_graphics/sheen.wgsl";
+
+/// Standard material with a `KHR_materials_sheen` layer, e.g. for velvet and other fabrics
+pub type SheenMaterial = ExtendedMaterial<StandardMaterial, SheenExtension>;
+
+/// Sheen layer on top of a standard material
+///
+/// The sheen is a soft retro-reflective lobe with a Charlie distribution.
+/// Its lighting is approximated from the diffuse lighting of the surface,
+/// scaled by the directional albedo of the lobe, so every light, shadow and
+/// environment map contributes. The base layer is darkened by the same
+/// albedo to conserve energy. Drawn in the forward pass.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct SheenExtension {
+    #[uniform(100)]
+    pub settings: SheenSettings,
+    /// Multiplies the sheen color with its RGB channels
+    #[texture(101)]
+    #[sampler(102)]
+    pub color_texture: Option<Handle<Image>>,
+    /// Multiplies the sheen roughness with its alpha channel
+    #[texture(103)]
+    #[sampler(104)]
+    pub roughness_texture: Option<Handle<Image>>,
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy, Default, PartialEq)]
+pub struct SheenSettings {
+    /// Linear sheen color, black disables the sheen
+    pub color: Vec3,
+    pub roughness: f32,
+    /// 1 if `color_texture` is set
+    pub color_texture: u32,
+    /// 1 if `roughness_texture` is set
+    pub roughness_texture: u32,
+}
+
+/// Sheen of the standard materials of loaded glTF files, by material
+#[derive(Resource, Debug, Default)]
+pub struct GltfSheens {
+    sheens: HashMap<AssetId<StandardMaterial>, SheenExtension>,
+    materials: HashMap<AssetId<StandardMaterial>, Handle<SheenMaterial>>,
+}
+
+/// Gives meshes of glTF materials with `KHR_materials_sheen` a [`SheenMaterial`]
+///
+/// Bevy's glTF loader ignores the extension, so it is read from the source
+/// of files loaded with `GltfLoaderSettings::include_source`, e.g. by
+/// [`load_validated_gltf`](crate::load_validated_gltf). Sheen textures must
+/// use the first UV set.
+#[derive(Debug, Default)]
+pub struct SheenPlugin;
+
+impl SheenExtension {
+    pub fn new(color: Color, roughness: f32) -> Self {
+        Self {
+            settings: SheenSettings {
+                color: color.to_linear().to_vec3(),
+                roughness,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn with_color_texture(mut self, texture: Handle<Image>) -> Self {
+        self.color_texture = Some(texture);
+        self.settings.color_texture = 1;
+        self
+    }
+
+    pub fn with_roughness_texture(mut self, texture: Handle<Image>) -> Self {
+        self.roughness_texture = Some(texture);
+        self.settings.roughness_texture = 1;
+        self
+    }
+
+    /// Sheen of a glTF material, `None` without `KHR_materials_sheen`.
+    /// `texture` turns a glTF texture index into an image
+    pub fn from_gltf_material(
+        material: &gltf::Material,
+        mut texture: impl FnMut(usize) -> Handle<Image>,
+    ) -> Option<Self> {
+        let sheen = material.extension_value("KHR_materials_sheen")?;
+        let factor = |key: &str| sheen.get(key).and_then(|value| value.as_f64());
+        let color = sheen
+            .get("sheenColorFactor")
+            .and_then(|value| value.as_array())
+            .map(|color| {
+                let channel = |i: usize| color.get(i).and_then(|c| c.as_f64()).unwrap_or(0.0);
+                Vec3::new(channel(0) as f32, channel(1) as f32, channel(2) as f32)
+            })
+            .unwrap_or(Vec3::ZERO);
+        let texture_index = |key: &str| {
+            sheen
+                .get(key)?
+                .get("index")?
+                .as_u64()
+                .map(|index| index as usize)
+        };
+
+        let mut extension = Self {
+            settings: SheenSettings {
+                color,
+                roughness: factor("sheenRoughnessFactor").unwrap_or(0.0) as f32,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        if let Some(index) = texture_index("sheenColorTexture") {
+            extension = extension.with_color_texture(texture(index));
+        }
+        if let Some(index) = texture_index("sheenRoughnessTexture") {
+            extension = extension.with_roughness_texture(texture(index));
+        }
+        Some(extension)
+    }
+}
+
+impl MaterialExtension for SheenExtension {
+    fn fragment_shader() -> ShaderRef {
+        SHEEN_SHADER.into()
+    }
+}
+
+impl GltfSheens {
+    pub fn get(&self, material: impl Into<AssetId<StandardMaterial>>) -> Option<&SheenExtension> {
+        self.sheens.get(&material.into())
+    }
+
+    pub fn insert(
+        &mut self,
+        material: impl Into<AssetId<StandardMaterial>>,
+        sheen: SheenExtension,
+    ) {
+        let material = material.into();
+        self.materials.remove(&material);
+        self.sheens.insert(material, sheen);
+    }
+}
+
+impl Plugin for SheenPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "sheen.wgsl");
+
+        app.add_plugins(MaterialPlugin::<SheenMaterial>::default())
+            .init_resource::<GltfSheens>()
+            .add_systems(
+                PostUpdate,
+                (
+                    read_gltf_sheens.run_if(resource_exists::<Assets<Gltf>>),
+                    apply_sheen_materials,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn read_gltf_sheens(
+    mut events: MessageReader<AssetEvent<Gltf>>,
+    mut sheens: ResMut<GltfSheens>,
+    gltfs: Res<Assets<Gltf>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(gltf) = gltfs.get(*id) else {
+            continue;
+        };
+        let Some(source) = &gltf.source else {
+            continue;
+        };
+        let path = asset_server.get_path(*id).map(|path| path.into_owned());
+        for material in source.document.materials() {
+            let Some(handle) = material.index().and_then(|index| gltf.materials.get(index)) else {
+                continue;
+            };
+            let sheen = SheenExtension::from_gltf_material(&material, |index| match &path {
+                Some(path) => asset_server.load(
+                    path.clone()
+                        .with_label(GltfAssetLabel::Texture(index).to_string()),
+                ),
+                None => Handle::default(),
+            });
+            if let Some(sheen) = sheen {
+                sheens.insert(handle, sheen);
+            }
+        }
+    }
+}
+
+fn apply_sheen_materials(
+    mut commands: Commands,
+    mut sheens: ResMut<GltfSheens>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<SheenMaterial>>,
+    meshes: Query<(Entity, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    if sheens.sheens.is_empty() {
+        return;
+    }
+    let sheens = &mut *sheens;
+    for (entity, standard) in meshes.iter() {
+        let Some(sheen) = sheens.sheens.get(&standard.id()) else {
+            continue;
+        };
+        let Some(base) = standard_materials.get(&standard.0) else {
+            continue;
+        };
+        let material = sheens
+            .materials
+            .entry(standard.id())
+            .or_insert_with(|| {
+                let mut base = base.clone();
+                base.opaque_render_method = OpaqueRendererMethod::Forward;
+                materials.add(SheenMaterial {
+                    base,
+                    extension: sheen.clone(),
+                })
+            })
+            .clone();
+        commands
+            .entity(entity)
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert(MeshMaterial3d(material));
+    }
+}
@@ -0,0 +1,86 @@
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+
+struct SheenSettings {
+    color: vec3<f32>,
+    roughness: f32,
+    color_texture: u32,
+    roughness_texture: u32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> sheen: SheenSettings;
+@group(#{MATERIAL_BIND_GROUP}) @binding(101) var sheen_color_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(102) var sheen_color_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(103) var sheen_roughness_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(104) var sheen_roughness_sampler: sampler;
+
+const ALBEDO_SIZE: u32 = 8u;
+// Directional albedo of the Charlie sheen lobe with Neubelt visibility, for
+// roughness (rows) and the cosine of the view angle (columns) from 0 to 1
+const SHEEN_ALBEDO: array<f32, 64> = array<f32, 64>(
+    1.0000, 0.3156, 0.0135, 0.0002, 0.0000, 0.0000, 0.0000, 0.0000,
+    1.0000, 0.6457, 0.1697, 0.0370, 0.0058, 0.0005, 0.0000, 0.0000,
+    1.0000, 0.6672, 0.3531, 0.1816, 0.0859, 0.0347, 0.0102, 0.0009,
+    0.9286, 0.5975, 0.3919, 0.2571, 0.1626, 0.0954, 0.0481, 0.0160,
+    0.7704, 0.5425, 0.3939, 0.2892, 0.2087, 0.1444, 0.0920, 0.0490,
+    0.6805, 0.5044, 0.3880, 0.3034, 0.2357, 0.1786, 0.1290, 0.0851,
+    0.6249, 0.4783, 0.3814, 0.3102, 0.2522, 0.2022, 0.1575, 0.1164,
+    0.5882, 0.4599, 0.3756, 0.3137, 0.2629, 0.2188, 0.1788, 0.1414,
+);
+
+fn sheen_albedo(n_dot_v: f32, roughness: f32) -> f32 {
+    var table = SHEEN_ALBEDO;
+    let position = vec2(saturate(n_dot_v), saturate(roughness)) * f32(ALBEDO_SIZE - 1u);
+    let cell = min(vec2<u32>(position), vec2(ALBEDO_SIZE - 2u));
+    let t = position - vec2<f32>(cell);
+    let row = cell.y * ALBEDO_SIZE + cell.x;
+    let low = mix(table[row], table[row + 1u], t.x);
+    let high = mix(table[row + ALBEDO_SIZE], table[row + ALBEDO_SIZE + 1u], t.x);
+    return mix(low, high, t.y);
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var sheen_color = sheen.color;
+    var sheen_roughness = sheen.roughness;
+#ifdef VERTEX_UVS_A
+    if sheen.color_texture != 0u {
+        sheen_color *= textureSample(sheen_color_texture, sheen_color_sampler, in.uv).rgb;
+    }
+    if sheen.roughness_texture != 0u {
+        sheen_roughness *= textureSample(sheen_roughness_texture, sheen_roughness_sampler, in.uv).a;
+    }
+#endif
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+
+    let albedo = sheen_albedo(dot(pbr_input.N, pbr_input.V), sheen_roughness);
+    let sheen_strength = max(sheen_color.r, max(sheen_color.g, sheen_color.b));
+    if sheen_strength > 0.0 {
+        // Diffuse lighting of a white dielectric stands in for the light
+        // reaching the sheen layer
+        var white = pbr_input;
+        white.material.base_color = vec4(1.0);
+        white.material.emissive = vec4(0.0);
+        white.material.metallic = 0.0;
+        white.material.reflectance = vec3(0.0);
+        white.material.perceptual_roughness = 1.0;
+        white.material.specular_transmission = 0.0;
+        white.material.diffuse_transmission = 0.0;
+        white.material.clearcoat = 0.0;
+        let irradiance = apply_pbr_lighting(white).rgb;
+        // Emission is dimmed with the base layer, it is rare on fabrics
+        let base = out.color.rgb * (1.0 - sheen_strength * albedo);
+        out.color = vec4(base + sheen_color * albedo * irradiance, out.color.a);
+    }
+
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
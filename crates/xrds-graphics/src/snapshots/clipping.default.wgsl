@@ -0,0 +1,100 @@
+struct VertexOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+}
+
+struct FragmentOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX {
+    @location(0) color: vec4<f32>,
+}
+
+struct StandardMaterialX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX {
+    base_color: vec4<f32>,
+    flags: u32,
+}
+
+struct PbrInputX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX {
+    material: StandardMaterialX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX,
+}
+
+struct ClipSettings {
+    world_to_clip: mat4x4<f32>,
+    cap_color: vec4<f32>,
+    shape: u32,
+    cap: u32,
+}
+
+const CLIP_NONE: u32 = 0u;
+const CLIP_BOX: u32 = 1u;
+const CLIP_PLANE: u32 = 2u;
+
+@group(3) @binding(100) 
+var<uniform> clip: ClipSettings;
+
+fn pbr_input_from_standard_materialX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOJQWO3LFNZ2AX(in_1: VertexOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX, is_front_1: bool) -> PbrInputX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX {
+    var pbr_input_1: PbrInputX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX;
+
+    pbr_input_1.material.base_color = vec4<f32>(in_1.world_normal, 1f);
+    let _e7 = pbr_input_1;
+    return _e7;
+}
+
+fn alpha_discardX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOVXGG5DJN5XHGX(material: StandardMaterialX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX, output_color: vec4<f32>) -> vec4<f32> {
+    return output_color;
+}
+
+fn apply_pbr_lightingX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOVXGG5DJN5XHGX(in_2: PbrInputX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX) -> vec4<f32> {
+    return in_2.material.base_color;
+}
+
+fn main_pass_post_lighting_processingX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOVXGG5DJN5XHGX(pbr_input_2: PbrInputX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX, input_color: vec4<f32>) -> vec4<f32> {
+    return input_color;
+}
+
+fn is_clipped(world_position: vec4<f32>) -> bool {
+    let _e3 = clip.world_to_clip;
+    let position = (_e3 * world_position).xyz;
+    let _e8 = clip.shape;
+    if (_e8 == CLIP_BOX) {
+        return any((abs(position) > vec3(0.5f)));
+    }
+    let _e18 = clip.shape;
+    if (_e18 == CLIP_PLANE) {
+        return (position.z < 0f);
+    }
+    return false;
+}
+
+@fragment 
+fn fragment(in: VertexOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX, @builtin(front_facing) is_front: bool) -> FragmentOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX {
+    var out: FragmentOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX;
+    var pbr_input: PbrInputX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3UPFYGK4YX;
+
+    let _e2 = is_clipped(in.world_position);
+    if _e2 {
+        discard;
+    }
+    let _e5 = clip.shape;
+    let _e10 = clip.cap;
+    if (((_e5 != CLIP_NONE) && (_e10 != 0u)) && !(is_front)) {
+        let _e21 = clip.cap_color;
+        out.color = _e21;
+        let _e22 = out;
+        return _e22;
+    }
+    let _e23 = pbr_input_from_standard_materialX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOJQWO3LFNZ2AX(in, is_front);
+    pbr_input = _e23;
+    let _e28 = pbr_input.material;
+    let _e31 = pbr_input.material.base_color;
+    let _e32 = alpha_discardX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOVXGG5DJN5XHGX(_e28, _e31);
+    pbr_input.material.base_color = _e32;
+    let _e34 = pbr_input;
+    let _e35 = apply_pbr_lightingX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOVXGG5DJN5XHGX(_e34);
+    out.color = _e35;
+    let _e37 = pbr_input;
+    let _e39 = out.color;
+    let _e40 = main_pass_post_lighting_processingX_naga_oil_mod_XMJSXM6K7OBRHEOR2OBRHEX3GOVXGG5DJN5XHGX(_e37, _e39);
+    out.color = _e40;
+    let _e41 = out;
+    return _e41;
+}
@@ -0,0 +1,88 @@
+struct FullscreenVertexOutputX_naga_oil_mod_XMJSXM6K7MNXXEZK7OBUXAZLMNFXGKOR2MZ2WY3DTMNZGKZLOL53GK4TUMV4F643IMFSGK4QX {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct ColorFilterUniform {
+    mode: u32,
+    strength: f32,
+    contrast: f32,
+    _padding: f32,
+}
+
+const MODE_PROTANOPIA: u32 = 1u;
+const MODE_DEUTERANOPIA: u32 = 2u;
+const MODE_TRITANOPIA: u32 = 3u;
+const MODE_GRAYSCALE: u32 = 4u;
+const RGB_TO_LMS: mat3x3<f32> = mat3x3<f32>(vec3<f32>(17.8824f, 3.45565f, 0.0299566f), vec3<f32>(43.5161f, 27.1554f, 0.184309f), vec3<f32>(4.11935f, 3.86714f, 1.46709f));
+const LMS_TO_RGB: mat3x3<f32> = mat3x3<f32>(vec3<f32>(0.08094445f, -0.010248533f, -0.00036529693f), vec3<f32>(-0.13050441f, 0.05401933f, -0.0041216146f), vec3<f32>(0.116721064f, -0.11361471f, 0.6935114f));
+
+@group(0) @binding(0) 
+var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) 
+var texture_sampler: sampler;
+@group(0) @binding(2) 
+var<uniform> settings: ColorFilterUniform;
+
+fn simulate(color_1: vec3<f32>, mode: u32) -> vec3<f32> {
+    var lms: vec3<f32>;
+
+    lms = (RGB_TO_LMS * color_1);
+    switch mode {
+        case 1u: {
+            let _e7 = lms.y;
+            let _e11 = lms.z;
+            lms.x = ((2.02344f * _e7) - (2.52581f * _e11));
+        }
+        case 2u: {
+            let _e17 = lms.x;
+            let _e21 = lms.z;
+            lms.y = ((0.494207f * _e17) + (1.24827f * _e21));
+        }
+        case 3u: {
+            let _e27 = lms.x;
+            let _e31 = lms.y;
+            lms.z = ((-0.395913f * _e27) + (0.801109f * _e31));
+        }
+        default: {
+        }
+    }
+    let _e36 = lms;
+    return (LMS_TO_RGB * _e36);
+}
+
+fn daltonize(color_2: vec3<f32>, mode_1: u32) -> vec3<f32> {
+    let _e2 = simulate(color_2, mode_1);
+    let error = (color_2 - _e2);
+    let shift = vec3<f32>(0f, ((0.7f * error.x) + error.y), ((0.7f * error.x) + error.z));
+    return clamp((color_2 + shift), vec3(0f), vec3(1f));
+}
+
+@fragment 
+fn fragment(in: FullscreenVertexOutputX_naga_oil_mod_XMJSXM6K7MNXXEZK7OBUXAZLMNFXGKOR2MZ2WY3DTMNZGKZLOL53GK4TUMV4F643IMFSGK4QX) -> @location(0) vec4<f32> {
+    var color: vec3<f32>;
+
+    let source = textureSample(screen_texture, texture_sampler, in.uv);
+    color = source.xyz;
+    let _e9 = settings.mode;
+    if (_e9 == MODE_GRAYSCALE) {
+        let _e12 = color;
+        color = vec3(dot(_e12, vec3<f32>(0.2126f, 0.7152f, 0.0722f)));
+    } else {
+        let _e21 = settings.mode;
+        if (_e21 != 0u) {
+            let _e24 = color;
+            let _e27 = settings.mode;
+            let _e28 = daltonize(_e24, _e27);
+            color = _e28;
+        }
+    }
+    let _e30 = color;
+    let _e33 = settings.strength;
+    color = mix(source.xyz, _e30, _e33);
+    let _e35 = color;
+    let _e41 = settings.contrast;
+    color = max((((_e35 - vec3(0.5f)) * _e41) + vec3(0.5f)), vec3(0f));
+    let _e49 = color;
+    return vec4<f32>(_e49, source.w);
+}
@@ -0,0 +1,45 @@
+struct VertexOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+}
+
+struct ViewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX {
+    clip_from_world: mat4x4<f32>,
+    world_position: vec3<f32>,
+    viewport: vec4<f32>,
+}
+
+struct GlobalsX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX {
+    time: f32,
+}
+
+struct FresnelSettings {
+    color: vec4<f32>,
+    power: f32,
+    pulse_speed: f32,
+}
+
+const TAU: f32 = 6.2831855f;
+
+@group(0) @binding(0) 
+var<uniform> viewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX: ViewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX;
+@group(0) @binding(11) 
+var<uniform> globalsX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX: GlobalsX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX;
+@group(3) @binding(0) 
+var<uniform> fresnel: FresnelSettings;
+
+@fragment 
+fn fragment(in: VertexOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX) -> @location(0) vec4<f32> {
+    let _e3 = viewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX.world_position;
+    let view_direction = normalize((_e3 - in.world_position.xyz));
+    let facing = saturate(dot(normalize(in.world_normal), view_direction));
+    let _e16 = fresnel.power;
+    let rim = pow((1f - facing), _e16);
+    let _e20 = globalsX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX.time;
+    let _e23 = fresnel.pulse_speed;
+    let pulse = (0.75f + (0.25f * cos(((_e20 * _e23) * TAU))));
+    let _e34 = fresnel.color;
+    let _e39 = fresnel.color.w;
+    return vec4<f32>(_e34.xyz, ((_e39 * rim) * pulse));
+}
@@ -0,0 +1,14 @@
+struct VertexOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) instance_index: u32,
+}
+
+fn get_tagX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(instance_index: u32) -> u32 {
+    return instance_index;
+}
+
+@fragment 
+fn fragment(in: VertexOutputX_naga_oil_mod_XMJSXM6K7OBRHEOR2MZXXE53BOJSF62LPX) -> @location(0) vec4<f32> {
+    let _e2 = get_tagX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(in.instance_index);
+    return vec4<f32>(f32((_e2 & 2047u)), f32((_e2 >> 11u)), 0f, 1f);
+}
@@ -0,0 +1,61 @@
+struct ViewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX {
+    clip_from_world: mat4x4<f32>,
+    world_position: vec3<f32>,
+    viewport: vec4<f32>,
+}
+
+struct OutlineSettings {
+    color: vec4<f32>,
+    thickness: f32,
+}
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+@group(0) @binding(0) 
+var<uniform> viewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX: ViewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX;
+@group(3) @binding(0) 
+var<uniform> outline: OutlineSettings;
+
+fn get_world_from_localX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(instance_index: u32) -> mat4x4<f32> {
+    return mat4x4<f32>(vec4<f32>(1f, 0f, 0f, 0f), vec4<f32>(0f, 1f, 0f, 0f), vec4<f32>(0f, 0f, 1f, 0f), vec4<f32>(0f, 0f, 0f, 1f));
+}
+
+fn mesh_position_local_to_worldX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(world_from_local: mat4x4<f32>, vertex_position: vec4<f32>) -> vec4<f32> {
+    return (world_from_local * vertex_position);
+}
+
+fn mesh_normal_local_to_worldX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(vertex_normal: vec3<f32>, instance_index_1: u32) -> vec3<f32> {
+    return vertex_normal;
+}
+
+fn position_world_to_clipX_naga_oil_mod_XMJSXM6K7OBRHEOR2OZUWK527ORZGC3TTMZXXE3LBORUW63TTX(world_pos: vec3<f32>) -> vec4<f32> {
+    let _e2 = viewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX.clip_from_world;
+    return (_e2 * vec4<f32>(world_pos, 1f));
+}
+
+@vertex 
+fn vertex(vertex_1: Vertex) -> @builtin(position) vec4<f32> {
+    let _e2 = get_world_from_localX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(vertex_1.instance_index);
+    let _e6 = mesh_position_local_to_worldX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(_e2, vec4<f32>(vertex_1.position, 1f));
+    let _e9 = mesh_normal_local_to_worldX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7MZ2W4Y3UNFXW44YX(vertex_1.normal, vertex_1.instance_index);
+    let _e11 = position_world_to_clipX_naga_oil_mod_XMJSXM6K7OBRHEOR2OZUWK527ORZGC3TTMZXXE3LBORUW63TTX(_e6.xyz);
+    let _e14 = viewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX.clip_from_world;
+    let clip_normal = (_e14 * vec4<f32>(_e9, 0f)).xy;
+    if (dot(clip_normal, clip_normal) < 0.000000000001f) {
+        return _e11;
+    }
+    let _e25 = outline.thickness;
+    let _e31 = viewX_naga_oil_mod_XMJSXM6K7OBRHEOR2NVSXG2C7OZUWK527MJUW4ZDJNZTXGX.viewport;
+    let offset = (((normalize(clip_normal) * _e25) * 2f) / _e31.zw);
+    return vec4<f32>((_e11.xy + (offset * _e11.w)), _e11.zw);
+}
+
+@fragment 
+fn fragment() -> @location(0) vec4<f32> {
+    let _e2 = outline.color;
+    return _e2;
+}
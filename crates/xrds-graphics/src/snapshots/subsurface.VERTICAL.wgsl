@@ -0,0 +1,90 @@
+struct FullscreenVertexOutputX_naga_oil_mod_XMJSXM6K7MNXXEZK7OBUXAZLMNFXGKOR2MZ2WY3DTMNZGKZLOL53GK4TUMV4F643IMFSGK4QX {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct SubsurfaceScatteringUniform {
+    kernel: array<vec4<f32>, 11>,
+    view_from_clip: mat4x4<f32>,
+    projection_scale: vec2<f32>,
+    width: f32,
+    _padding: f32,
+}
+
+const SAMPLES: u32 = 11u;
+
+@group(0) @binding(0) 
+var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) 
+var texture_sampler: sampler;
+@group(0) @binding(2) 
+var depth_texture: texture_depth_2d;
+@group(0) @binding(3) 
+var<uniform> settings: SubsurfaceScatteringUniform;
+
+fn ndc_depth(uv: vec2<f32>) -> f32 {
+    let size = textureDimensions(depth_texture);
+    let pixel = min(vec2<u32>((uv * vec2<f32>(size))), (size - vec2(1u)));
+    let _e12 = textureLoad(depth_texture, pixel, 0i);
+    return _e12;
+}
+
+fn view_depth(uv_1: vec2<f32>, depth: f32) -> f32 {
+    let _e3 = settings.view_from_clip;
+    let position = (_e3 * vec4<f32>(((uv_1.x * 2f) - 1f), (1f - (uv_1.y * 2f)), depth, 1f));
+    return (-(position.z) / position.w);
+}
+
+@fragment 
+fn fragment(in: FullscreenVertexOutputX_naga_oil_mod_XMJSXM6K7MNXXEZK7OBUXAZLMNFXGKOR2MZ2WY3DTMNZGKZLOL53GK4TUMV4F643IMFSGK4QX) -> @location(0) vec4<f32> {
+    var blurred: vec3<f32>;
+    var i: u32 = 1u;
+    var reject: f32;
+
+    let center = textureSampleLevel(screen_texture, texture_sampler, in.uv, 0f);
+    let strength = (1f - center.w);
+    let _e11 = ndc_depth(in.uv);
+    if ((strength <= 0f) || (_e11 <= 0f)) {
+        return center;
+    }
+    let _e18 = view_depth(in.uv, _e11);
+    let direction = vec2<f32>(0f, 1f);
+    let _e24 = settings.projection_scale;
+    let scale = ((0.5f * _e24) / vec2(_e18));
+    let _e32 = settings.width;
+    let step = ((((direction * scale) * _e32) * strength) / vec2(3f));
+    let _e42 = settings.kernel[0];
+    blurred = (center.xyz * _e42.xyz);
+    loop {
+        let _e47 = i;
+        if (_e47 < SAMPLES) {
+        } else {
+            break;
+        }
+        {
+            let _e53 = i;
+            let _e56 = settings.kernel[_e53].w;
+            let uv_2 = (in.uv + (_e56 * step));
+            let tap = textureSampleLevel(screen_texture, texture_sampler, uv_2, 0f);
+            let _e63 = ndc_depth(uv_2);
+            let _e64 = view_depth(uv_2, _e63);
+            let _e68 = settings.projection_scale.y;
+            let _e73 = settings.width;
+            reject = saturate((((300f * _e68) * _e73) * abs((_e18 - _e64))));
+            if (tap.w >= 1f) {
+                reject = 1f;
+            }
+            let _e86 = i;
+            let _e88 = settings.kernel[_e86];
+            let _e92 = reject;
+            let _e95 = blurred;
+            blurred = (_e95 + (_e88.xyz * mix(tap.xyz, center.xyz, _e92)));
+        }
+        continuing {
+            let _e98 = i;
+            i = (_e98 + 1u);
+        }
+    }
+    let _e100 = blurred;
+    return vec4<f32>(_e100, 1f);
+}
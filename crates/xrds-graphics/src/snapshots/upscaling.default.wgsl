@@ -0,0 +1,86 @@
+struct FullscreenVertexOutputX_naga_oil_mod_XMJSXM6K7MNXXEZK7OBUXAZLMNFXGKOR2MZ2WY3DTMNZGKZLOL53GK4TUMV4F643IMFSGK4QX {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct UpscalingUniform {
+    viewport: vec4<f32>,
+    input_size: vec2<f32>,
+    sharpness: f32,
+    mode: u32,
+}
+
+const MODE_SHARPENED: u32 = 1u;
+const RCAS_LIMIT: f32 = 0.1875f;
+
+@group(0) @binding(0) 
+var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) 
+var texture_sampler: sampler;
+@group(0) @binding(2) 
+var<uniform> settings: UpscalingUniform;
+
+fn sample_source(position: vec2<f32>) -> vec4<f32> {
+    let _e2 = settings.viewport;
+    let min_position = (_e2.xy + vec2(0.5f));
+    let _e9 = settings.viewport;
+    let _e13 = settings.input_size;
+    let max_position = ((_e9.xy + _e13) - vec2(0.5f));
+    let _e21 = textureDimensions(screen_texture);
+    let uv = (clamp(position, min_position, max_position) / vec2<f32>(_e21));
+    let _e27 = textureSampleLevel(screen_texture, texture_sampler, uv, 0f);
+    return _e27;
+}
+
+fn max3_(value: vec3<f32>) -> f32 {
+    return max(value.x, max(value.y, value.z));
+}
+
+fn sharpen(position_1: vec2<f32>, center: vec4<f32>) -> vec4<f32> {
+    let _e5 = sample_source((position_1 + vec2<f32>(0f, -1f)));
+    let n = _e5.xyz;
+    let _e11 = sample_source((position_1 + vec2<f32>(-1f, 0f)));
+    let w = _e11.xyz;
+    let _e17 = sample_source((position_1 + vec2<f32>(1f, 0f)));
+    let e = _e17.xyz;
+    let _e23 = sample_source((position_1 + vec2<f32>(0f, 1f)));
+    let s = _e23.xyz;
+    let c = clamp(center.xyz, vec3(0f), vec3(1f));
+    let cn = clamp(n, vec3(0f), vec3(1f));
+    let cw = clamp(w, vec3(0f), vec3(1f));
+    let ce = clamp(e, vec3(0f), vec3(1f));
+    let cs = clamp(s, vec3(0f), vec3(1f));
+    let min_color = min(min(cn, cw), min(ce, min(cs, c)));
+    let max_color = max(max(cn, cw), max(ce, max(cs, c)));
+    let hit_min = (min_color / ((4f * max_color) + vec3(0.00001f)));
+    let hit_max = ((vec3(1f) - max_color) / (((4f * min_color) - vec3(4f)) - vec3(0.00001f)));
+    let lobe_rgb = max(-(hit_min), hit_max);
+    let _e80 = max3_(lobe_rgb);
+    let _e87 = settings.sharpness;
+    let lobe = (max(-0.1875f, min(_e80, 0f)) * _e87);
+    let color = (((lobe * (((n + w) + e) + s)) + center.xyz) / vec3(((4f * lobe) + 1f)));
+    return vec4<f32>(max(color, vec3(0f)), center.w);
+}
+
+@fragment 
+fn fragment(in: FullscreenVertexOutputX_naga_oil_mod_XMJSXM6K7MNXXEZK7OBUXAZLMNFXGKOR2MZ2WY3DTMNZGKZLOL53GK4TUMV4F643IMFSGK4QX) -> @location(0) vec4<f32> {
+    let _e5 = settings.viewport;
+    let local = (in.position.xy - _e5.xy);
+    let _e14 = settings.viewport;
+    if (any((local < vec2(0f))) || any((local >= _e14.zw))) {
+        let _e23 = textureSampleLevel(screen_texture, texture_sampler, in.uv, 0f);
+        return _e23;
+    }
+    let _e26 = settings.viewport;
+    let _e30 = settings.input_size;
+    let _e34 = settings.viewport;
+    let position_2 = (_e26.xy + ((local * _e30) / _e34.zw));
+    let _e38 = sample_source(position_2);
+    let _e41 = settings.mode;
+    let _e46 = settings.sharpness;
+    if ((_e41 == MODE_SHARPENED) && (_e46 > 0f)) {
+        let _e50 = sharpen(position_2, _e38);
+        return _e50;
+    }
+    return _e38;
+}
@@ -0,0 +1,388 @@
+use bevy::{
+    asset::{embedded_asset, load_embedded_asset},
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        prepass::{DepthPrepass, ViewPrepassTextures},
+        FullscreenShader,
+    },
+    ecs::query::QueryItem,
+    pbr::{ExtendedMaterial, MaterialExtension},
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, texture_depth_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        view::ViewTarget,
+        RenderApp, RenderStartup,
+    },
+    shader::ShaderRef,
+};
+use serde::{Deserialize, Serialize};
+
+const SUBSURFACE_MATERIAL_SHADER: &str = "embedded://xrds_graphics/subsurface_material.wgsl";
+
+/// Taps of each blur pass
+pub const SUBSURFACE_SAMPLES: usize = 11;
+
+/// Screen-space subsurface scattering of a camera, e.g. for skin
+///
+/// After the opaque pass, pixels of [`SubsurfaceMaterial`]s are blurred
+/// horizontally and then vertically by a diffusion profile, scaled by the
+/// strength of the material and the distance to the camera. Taps across
+/// depth edges or on other materials are rejected. The blur runs on all of
+/// the lighting of those pixels, specular included.
+///
+/// Needs the depth pre-pass. The camera renders without MSAA.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[require(Camera3d, DepthPrepass)]
+pub struct SubsurfaceScattering {
+    /// World space distance light scatters under the surface, 0.012 fits
+    /// human skin in meters
+    pub width: f32,
+    /// Part of the light per color channel that scatters, the rest is
+    /// reflected at the surface
+    pub strength: [f32; 3],
+    /// Scattering distance per color channel relative to `width`
+    pub falloff: [f32; 3],
+}
+
+#[derive(Component, Clone, Copy, ShaderType)]
+pub struct SubsurfaceScatteringUniform {
+    /// Weight per color channel and offset in widths
+    kernel: [Vec4; SUBSURFACE_SAMPLES],
+    view_from_clip: Mat4,
+    /// Diagonal of `clip_from_view`, the size of a world unit at a distance of 1
+    projection_scale: Vec2,
+    width: f32,
+    _padding: f32,
+}
+
+/// Standard material that scatters light under its surface when seen by a
+/// camera with [`SubsurfaceScattering`]
+///
+/// The strength is passed to the blur in the alpha channel, so the base
+/// material must be opaque and forward rendered.
+pub type SubsurfaceMaterial = ExtendedMaterial<StandardMaterial, SubsurfaceExtension>;
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct SubsurfaceExtension {
+    #[uniform(100)]
+    pub settings: SubsurfaceSettings,
+    /// Multiplies the strength with its red channel, e.g. to leave out lips
+    /// and eyebrows
+    #[texture(101)]
+    #[sampler(102)]
+    pub mask_texture: Option<Handle<Image>>,
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy, Default, PartialEq)]
+pub struct SubsurfaceSettings {
+    /// From 0 to 1, scales the width of the blur
+    pub strength: f32,
+    /// 1 if `mask_texture` is set
+    pub mask_texture: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct SubsurfacePlugin;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct SubsurfaceLabel;
+
+#[derive(Default)]
+struct SubsurfaceNode;
+
+#[derive(Resource)]
+struct SubsurfacePipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    /// Horizontal and vertical pass, by LDR and HDR
+    pipeline_ids: [[CachedRenderPipelineId; 2]; 2],
+}
+
+impl Default for SubsurfaceScattering {
+    fn default() -> Self {
+        Self {
+            width: 0.012,
+            strength: [0.48, 0.41, 0.28],
+            falloff: [1.0, 0.37, 0.3],
+        }
+    }
+}
+
+impl SubsurfaceScattering {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+
+    /// Separable blur kernel of the skin diffusion profile, after Jimenez et
+    /// al. 2015. The center tap comes first, the weights of each color
+    /// channel sum to 1
+    pub fn kernel(&self) -> [Vec4; SUBSURFACE_SAMPLES] {
+        const RANGE: f32 = 2.0;
+        let falloff = Vec3::from(self.falloff);
+        let strength = Vec3::from(self.strength).clamp(Vec3::ZERO, Vec3::ONE);
+        let gaussian = |variance: f32, r: f32| {
+            let rr = Vec3::splat(r) / (falloff + 0.001);
+            (-(rr * rr) / (2.0 * variance)).exp() / (2.0 * std::f32::consts::PI * variance)
+        };
+        // Sum of Gaussians fitted to skin by d'Eon and Luebke, the narrowest
+        // one is left to the light reflected at the surface
+        let profile = |r: f32| {
+            0.100 * gaussian(0.0484, r)
+                + 0.118 * gaussian(0.187, r)
+                + 0.113 * gaussian(0.567, r)
+                + 0.358 * gaussian(1.99, r)
+                + 0.078 * gaussian(7.41, r)
+        };
+
+        // Offsets are denser around the center
+        let step = 2.0 * RANGE / (SUBSURFACE_SAMPLES - 1) as f32;
+        let offsets: [f32; SUBSURFACE_SAMPLES] = std::array::from_fn(|i| {
+            let o = -RANGE + i as f32 * step;
+            o.signum() * o * o / RANGE
+        });
+        let mut kernel: [Vec4; SUBSURFACE_SAMPLES] = std::array::from_fn(|i| {
+            let before = if i > 0 {
+                offsets[i] - offsets[i - 1]
+            } else {
+                0.0
+            };
+            let after = offsets.get(i + 1).map_or(0.0, |next| next - offsets[i]);
+            ((before + after) / 2.0 * profile(offsets[i])).extend(offsets[i])
+        });
+        kernel[..=SUBSURFACE_SAMPLES / 2].rotate_right(1);
+
+        let sum = kernel
+            .iter()
+            .fold(Vec3::ZERO, |sum, tap| sum + tap.truncate());
+        for (i, tap) in kernel.iter_mut().enumerate() {
+            let mut weight = tap.truncate() / sum * strength;
+            if i == 0 {
+                weight += Vec3::ONE - strength;
+            }
+            *tap = weight.extend(tap.w);
+        }
+        kernel
+    }
+}
+
+impl SubsurfaceExtension {
+    pub fn new(strength: f32) -> Self {
+        Self {
+            settings: SubsurfaceSettings {
+                strength,
+                mask_texture: 0,
+            },
+            mask_texture: None,
+        }
+    }
+
+    pub fn with_mask_texture(mut self, texture: Handle<Image>) -> Self {
+        self.mask_texture = Some(texture);
+        self.settings.mask_texture = 1;
+        self
+    }
+}
+
+impl MaterialExtension for SubsurfaceExtension {
+    fn fragment_shader() -> ShaderRef {
+        SUBSURFACE_MATERIAL_SHADER.into()
+    }
+}
+
+impl ExtractComponent for SubsurfaceScattering {
+    type QueryData = (&'static SubsurfaceScattering, &'static Camera);
+    type QueryFilter = ();
+    type Out = SubsurfaceScatteringUniform;
+
+    fn extract_component(
+        (subsurface, camera): QueryItem<'_, '_, Self::QueryData>,
+    ) -> Option<Self::Out> {
+        if subsurface.width <= 0.0 {
+            return None;
+        }
+        let clip_from_view = camera.clip_from_view();
+        Some(SubsurfaceScatteringUniform {
+            kernel: subsurface.kernel(),
+            view_from_clip: clip_from_view.inverse(),
+            projection_scale: Vec2::new(clip_from_view.x_axis.x, clip_from_view.y_axis.y),
+            width: subsurface.width,
+            _padding: 0.0,
+        })
+    }
+}
+
+impl Plugin for SubsurfacePlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "subsurface.wgsl");
+        embedded_asset!(app, "subsurface_material.wgsl");
+
+        app.add_plugins((
+            MaterialPlugin::<SubsurfaceMaterial>::default(),
+            ExtractComponentPlugin::<SubsurfaceScattering>::default(),
+            UniformComponentPlugin::<SubsurfaceScatteringUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_systems(RenderStartup, init_subsurface_pipeline)
+            .add_render_graph_node::<ViewNodeRunner<SubsurfaceNode>>(Core3d, SubsurfaceLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::MainOpaquePass,
+                    SubsurfaceLabel,
+                    Node3d::MainTransmissivePass,
+                ),
+            );
+    }
+}
+
+impl ViewNode for SubsurfaceNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewPrepassTextures,
+        &'static DynamicUniformIndex<SubsurfaceScatteringUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, prepass_textures, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let subsurface_pipeline = world.resource::<SubsurfacePipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        let pipeline_ids = subsurface_pipeline.pipeline_ids[view_target.is_hdr() as usize];
+        let (Some(horizontal), Some(vertical)) = (
+            pipeline_cache.get_render_pipeline(pipeline_ids[0]),
+            pipeline_cache.get_render_pipeline(pipeline_ids[1]),
+        ) else {
+            return Ok(());
+        };
+        let Some(depth) = prepass_textures.depth_view() else {
+            return Ok(());
+        };
+
+        let uniforms = world.resource::<ComponentUniforms<SubsurfaceScatteringUniform>>();
+        let Some(uniform_binding) = uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        for (pipeline, label) in [
+            (horizontal, "subsurface_horizontal_pass"),
+            (vertical, "subsurface_vertical_pass"),
+        ] {
+            let post_process = view_target.post_process_write();
+            let bind_group = render_context.render_device().create_bind_group(
+                "subsurface_bind_group",
+                &subsurface_pipeline.layout,
+                &BindGroupEntries::sequential((
+                    post_process.source,
+                    &subsurface_pipeline.sampler,
+                    depth,
+                    uniform_binding.clone(),
+                )),
+            );
+
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: post_process.destination,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_render_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
+
+fn init_subsurface_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    fullscreen_shader: Res<FullscreenShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "subsurface_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                texture_depth_2d(),
+                uniform_buffer::<SubsurfaceScatteringUniform>(true),
+            ),
+        ),
+    );
+    let sampler = render_device.create_sampler(&SamplerDescriptor {
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    });
+    let shader = load_embedded_asset!(asset_server.as_ref(), "subsurface.wgsl");
+
+    let queue_pipeline = |format: TextureFormat, vertical: bool| {
+        let shader_defs = if vertical {
+            vec!["VERTICAL".into()]
+        } else {
+            Vec::new()
+        };
+        pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("subsurface_pipeline".into()),
+            layout: vec![layout.clone()],
+            vertex: fullscreen_shader.to_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: shader.clone(),
+                shader_defs,
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    };
+    let pipeline_ids = [
+        TextureFormat::bevy_default(),
+        ViewTarget::TEXTURE_FORMAT_HDR,
+    ]
+    .map(|format| [queue_pipeline(format, false), queue_pipeline(format, true)]);
+
+    commands.insert_resource(SubsurfacePipeline {
+        layout,
+        sampler,
+        pipeline_ids,
+    });
+}
@@ -0,0 +1,70 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+const SAMPLES: u32 = 11u;
+
+struct SubsurfaceScatteringUniform {
+    kernel: array<vec4<f32>, 11>,
+    view_from_clip: mat4x4<f32>,
+    projection_scale: vec2<f32>,
+    width: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var depth_texture: texture_depth_2d;
+@group(0) @binding(3) var<uniform> settings: SubsurfaceScatteringUniform;
+
+fn ndc_depth(uv: vec2<f32>) -> f32 {
+    let size = textureDimensions(depth_texture);
+    let pixel = min(vec2<u32>(uv * vec2<f32>(size)), size - 1u);
+    return textureLoad(depth_texture, pixel, 0);
+}
+
+fn view_depth(uv: vec2<f32>, depth: f32) -> f32 {
+    let position = settings.view_from_clip * vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    return -position.z / position.w;
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let center = textureSampleLevel(screen_texture, texture_sampler, in.uv, 0.0);
+    // Subsurface materials store their strength as 1 - alpha, the
+    // background (reverse Z depth of 0) keeps the alpha of the clear color
+    let strength = 1.0 - center.a;
+    let raw_depth = ndc_depth(in.uv);
+    if strength <= 0.0 || raw_depth <= 0.0 {
+        return center;
+    }
+    let depth = view_depth(in.uv, raw_depth);
+
+#ifdef VERTICAL
+    let direction = vec2(0.0, 1.0);
+#else
+    let direction = vec2(1.0, 0.0);
+#endif
+    // A world unit at this depth in UV space, kernel offsets span 3 widths
+    let scale = 0.5 * settings.projection_scale / depth;
+    let step = direction * scale * settings.width * strength / 3.0;
+
+    var blurred = center.rgb * settings.kernel[0].rgb;
+    for (var i = 1u; i < SAMPLES; i++) {
+        let uv = in.uv + settings.kernel[i].w * step;
+        let tap = textureSampleLevel(screen_texture, texture_sampler, uv, 0.0);
+        // Follow the surface, taps across depth edges or on other materials
+        // fall back to the center color
+        let tap_depth = view_depth(uv, ndc_depth(uv));
+        var reject = saturate(300.0 * settings.projection_scale.y * settings.width * abs(depth - tap_depth));
+        if tap.a >= 1.0 {
+            reject = 1.0;
+        }
+        blurred += settings.kernel[i].rgb * mix(tap.rgb, center.rgb, reject);
+    }
+
+#ifdef VERTICAL
+    // The mask is used up, later passes blend over an opaque surface
+    return vec4(blurred, 1.0);
+#else
+    return vec4(blurred, center.a);
+#endif
+}
@@ -0,0 +1,34 @@
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+
+struct SubsurfaceSettings {
+    strength: f32,
+    mask_texture: u32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> subsurface: SubsurfaceSettings;
+@group(#{MATERIAL_BIND_GROUP}) @binding(101) var subsurface_mask_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(102) var subsurface_mask_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+
+    var strength = subsurface.strength;
+#ifdef VERTEX_UVS_A
+    if subsurface.mask_texture != 0u {
+        strength *= textureSample(subsurface_mask_texture, subsurface_mask_sampler, in.uv).r;
+    }
+#endif
+    // Read by the subsurface scattering pass of the camera
+    out.color.a = 1.0 - saturate(strength);
+    return out;
+}
@@ -0,0 +1,2476 @@
+use bevy::{
+    animation::RepeatAnimation,
+    anti_alias::fxaa::Fxaa,
+    asset::uuid::Uuid,
+    asset::RenderAssetUsages,
+    camera::primitives::{Aabb, Frustum, MeshAabb},
+    core_pipeline::prepass::{DeferredPrepass, DepthPrepass, NormalPrepass},
+    diagnostic::{Diagnostic, DiagnosticMeasurement, DiagnosticPath, DiagnosticsStore},
+    ecs::system::RunSystemOnce,
+    light::IrradianceVolume,
+    mesh::{morph::MorphWeights, Indices, PrimitiveTopology},
+    pbr::{ScreenSpaceAmbientOcclusion, ScreenSpaceAmbientOcclusionQualityLevel},
+    post_process::bloom::Bloom,
+    prelude::*,
+    render::{
+        render_resource::BindlessSlabResourceLimit,
+        settings::{WgpuFeatures, WgpuLimits},
+        view::{Hdr, Msaa},
+    },
+};
+
+use crate::{
+    equirect_to_cubemap, light_importance, load_validated_gltf, msaa_from_samples,
+    occlusion_query_label, paint_canvas, paint_stroke, receive_gpu_query_results,
+    AmbientOcclusionPlugin, AmbientOcclusionQuality, AnisotropyPlugin, AssetImportPlugin,
+    AssetImporter, AssetReloaded, BindlessTextures, BloomPlugin, Brush, CameraAmbientOcclusion,
+    CameraBloom, CameraDepthPrepass, CameraMultisample, CameraOrder, CameraOrderPlugin,
+    CameraTransmission, CameraViewport, CaptureScreenshot, ClipShape, ClipVolume, DebugDraw,
+    DebugDrawPlugin, DebugShape, DepthPrepassPlugin, DepthPrepassing, DrawBatches,
+    DrawBatchesPlugin, EnvironmentLighting, EnvironmentLightingPlugin, FrameGraph, FrameGraphEdge,
+    FrameGraphPasses, Fresnel, GBufferChannel, GBufferLayout, GBufferPlugin, GlobalIllumination,
+    GltfExporter, GltfInspection, GltfMaterialVariants, GltfValidationPlugin,
+    GltfValidationReports, GltfWarning, GpuQueryKind, GpuQueryResult, GpuQueryResults,
+    HalfResolution, Highlight, HighlightOverlay, HighlightPlugin, HighlightStyle, HotReloadPlugin,
+    HudAnchor, HudElement, HudLayer, HudPlugin, HudQuad, LightBudget, LightCullingPlugin,
+    LightProbePlugin, LightProbeSettings, LightProbeVolume, MeshOptimization, MultisamplePlugin,
+    Multisampling, ObjImporter, ObjectId, ObjectIdMaterial, ObjectIdOverlay, ObjectIdPicking,
+    ObjectIdPlugin, ObjectIds, ObjectPicker, OcclusionQuery, OcclusionQueryPlugin, PostProcessing,
+    Raycast, RaycastSettings, ReloadKind, RenderPhase, RenderScale, RenderStats,
+    RenderStatsOverlay, RenderStatsPlugin, SceneAnimation, SceneAnimationPlugin, SceneMorphWeights,
+    SceneMorphWeightsPlugin, ScreenshotCapturePlugin, ScreenshotSaved, SetHighlight,
+    ShaderPermutations, ShadowBias, ShadowBiasPlugin, SheenExtension, SubsurfaceScattering,
+    TextureCompressionPlugin, TextureMemory, TransmissionPlugin, TransmissionQuality,
+    UpscaleFilter, MIN_RENDER_SCALE,
+};
+
+#[test]
+fn light_importance_falls_off_with_distance() {
+    let near = light_importance(1000.0, 10.0, 0.0, 1.0);
+    let far = light_importance(1000.0, 10.0, 0.0, 5.0);
+    assert!(near > far && far > 0.0);
+
+    // Nothing outside the range
+    assert_eq!(light_importance(1000.0, 10.0, 0.0, 10.0), 0.0);
+    assert_eq!(light_importance(1000.0, 10.0, 0.0, 20.0), 0.0);
+
+    // A viewer inside the light radius does not get an infinite importance
+    let inside = light_importance(1000.0, 10.0, 0.5, 0.0);
+    let surface = light_importance(1000.0, 10.0, 0.5, 0.5);
+    assert!((inside - surface).abs() < surface * 1e-3);
+}
+
+#[test]
+fn lights_are_culled_to_budget() {
+    let mut app = App::new();
+    app.add_plugins(LightCullingPlugin)
+        .insert_resource(LightBudget::new(2));
+    app.world_mut().spawn((
+        Camera::default(),
+        Frustum::default(),
+        GlobalTransform::default(),
+    ));
+
+    let mut visible = ViewVisibility::default();
+    visible.set();
+    let lights = [1.0, 4.0, 2.0, 8.0].map(|distance| {
+        app.world_mut()
+            .spawn((
+                PointLight {
+                    range: 20.0,
+                    ..Default::default()
+                },
+                GlobalTransform::from_xyz(0.0, 0.0, -distance),
+                visible,
+            ))
+            .id()
+    });
+
+    app.update();
+    let visibility = lights.map(|light| app.world().get::<ViewVisibility>(light).unwrap().get());
+    assert_eq!(visibility, [true, false, true, false]);
+    assert_eq!(app.world().resource::<LightBudget>().culled, 2);
+}
+
+#[test]
+fn camera_order_is_deterministic() {
+    let mut app = App::new();
+    app.add_plugins(CameraOrderPlugin);
+    let hud = app.world_mut().spawn(CameraOrder::overlay(10)).id();
+    let main = app.world_mut().spawn(CameraOrder::new(0)).id();
+    let spectator = app
+        .world_mut()
+        .spawn(CameraOrder::new(0).with_clear_color(Color::WHITE))
+        .id();
+
+    app.update();
+    let camera = |entity| app.world().get::<Camera>(entity).unwrap().clone();
+    assert_eq!(camera(main).order, 0);
+    assert_eq!(camera(spectator).order, 1);
+    assert_eq!(camera(hud).order, 10);
+    assert!(matches!(camera(hud).clear_color, ClearColorConfig::None));
+    assert!(matches!(
+        camera(spectator).clear_color,
+        ClearColorConfig::Custom(color) if color == Color::WHITE
+    ));
+}
+
+#[test]
+fn camera_viewport_follows_target_size() {
+    let right = CameraViewport::split(2, 1, 1);
+    let viewport = right.to_viewport(UVec2::new(1920, 1080));
+    assert_eq!(viewport.physical_position, UVec2::new(960, 0));
+    assert_eq!(viewport.physical_size, UVec2::new(960, 1080));
+
+    let viewport = right.to_viewport(UVec2::new(1280, 720));
+    assert_eq!(viewport.physical_position, UVec2::new(640, 0));
+    assert_eq!(viewport.physical_size, UVec2::new(640, 720));
+
+    // Degenerate rects still cover a pixel inside the target
+    let empty = CameraViewport::new(Vec2::ONE, Vec2::ONE).to_viewport(UVec2::new(100, 100));
+    assert_eq!(empty.physical_position, UVec2::new(99, 99));
+    assert_eq!(empty.physical_size, UVec2::ONE);
+}
+
+#[test]
+fn bindless_textures_need_binding_arrays() {
+    let slots = BindlessSlabResourceLimit::Auto.resolve();
+    let limits = WgpuLimits {
+        max_samplers_per_shader_stage: 6 * slots,
+        ..WgpuLimits::default()
+    };
+    let features = WgpuFeatures::BUFFER_BINDING_ARRAY | WgpuFeatures::TEXTURE_BINDING_ARRAY;
+    let bindless = BindlessTextures::from_device(features, &limits, 6);
+    assert!(bindless.supported);
+    assert_eq!(bindless.max_textures, slots);
+
+    let fallback = BindlessTextures::from_device(WgpuFeatures::TEXTURE_BINDING_ARRAY, &limits, 6);
+    assert_eq!(fallback, BindlessTextures::default());
+    // Not enough samplers for every slot of a slab
+    let fallback = BindlessTextures::from_device(features, &limits, 7);
+    assert!(!fallback.supported);
+    let fallback = BindlessTextures::from_device(
+        features,
+        &WgpuLimits {
+            max_storage_buffers_per_shader_stage: 0,
+            ..limits.clone()
+        },
+        6,
+    );
+    assert!(!fallback.supported);
+    let fallback =
+        BindlessTextures::from_device(features, &WgpuLimits::downlevel_webgl2_defaults(), 6);
+    assert!(!fallback.supported);
+}
+
+#[test]
+fn scene_animation_starts_when_players_spawn() {
+    let mut app = App::new();
+    app.add_plugins(SceneAnimationPlugin)
+        .insert_resource(Assets::<AnimationGraph>::default());
+    let scene = app
+        .world_mut()
+        .spawn(SceneAnimation::new(Handle::default()).with_speed(2.0))
+        .id();
+
+    // Nothing to play before the scene is spawned
+    app.update();
+    let armature = app
+        .world_mut()
+        .spawn((AnimationPlayer::default(), ChildOf(scene)))
+        .id();
+    app.update();
+
+    assert!(app.world().get::<AnimationGraphHandle>(armature).is_some());
+    let player = app.world().get::<AnimationPlayer>(armature).unwrap();
+    let (_, active) = player.playing_animations().next().unwrap();
+    assert_eq!(active.speed(), 2.0);
+    assert_eq!(active.repeat_mode(), RepeatAnimation::Forever);
+}
+
+#[test]
+fn material_variants_map_primitives() {
+    let json = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_materials_variants"],
+        "extensions": {
+            "KHR_materials_variants": {
+                "variants": [{ "name": "red" }, { "name": "blue" }, { "name": "chrome" }]
+            }
+        },
+        "materials": [{ "name": "default" }, { "name": "red" }, { "name": "blue" }],
+        "buffers": [{ "byteLength": 36 }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "accessors": [{
+            "bufferView": 0, "count": 3, "componentType": 5126, "type": "VEC3",
+            "min": [0, 0, 0], "max": [1, 1, 1]
+        }],
+        "meshes": [{
+            "primitives": [{
+                "attributes": { "POSITION": 0 },
+                "material": 0,
+                "extensions": {
+                    "KHR_materials_variants": {
+                        "mappings": [
+                            { "material": 1, "variants": [0] },
+                            { "material": 2, "variants": [1] }
+                        ]
+                    }
+                }
+            }, {
+                "attributes": { "POSITION": 0 },
+                "material": 0
+            }]
+        }]
+    }"#;
+    let gltf = gltf::Gltf::from_slice(json.as_bytes()).unwrap();
+    let variants = GltfMaterialVariants::from_document(&gltf.document);
+
+    assert_eq!(variants.names(), ["red", "blue", "chrome"]);
+    assert_eq!(variants.variant_index("blue"), Some(1));
+    assert_eq!(variants.material(0, 0, 0), Some(1));
+    assert_eq!(variants.material(0, 0, 1), Some(2));
+    // Unmapped variants and primitives keep their default material
+    assert_eq!(variants.material(0, 0, 2), None);
+    assert_eq!(variants.material(0, 1, 0), None);
+}
+
+#[test]
+fn morph_weights_are_set_by_target_name() {
+    let mut app = App::new();
+    app.add_plugins(SceneMorphWeightsPlugin)
+        .insert_resource(Assets::<Mesh>::default());
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+    mesh.set_morph_target_names(vec!["blink".to_owned(), "smile".to_owned()]);
+    let mesh = app.world_mut().resource_mut::<Assets<Mesh>>().add(mesh);
+
+    let avatar = app
+        .world_mut()
+        .spawn(SceneMorphWeights::default().with_weight("smile", 0.8))
+        .id();
+    let face = app
+        .world_mut()
+        .spawn((
+            MorphWeights::new(vec![0.5, 0.0], Some(mesh)).unwrap(),
+            ChildOf(avatar),
+        ))
+        .id();
+    app.update();
+    // Targets that are not set keep their weight
+    let weights = app.world().get::<MorphWeights>(face).unwrap();
+    assert_eq!(weights.weights(), [0.5, 0.8]);
+
+    app.world_mut()
+        .get_mut::<SceneMorphWeights>(avatar)
+        .unwrap()
+        .set("blink", 1.0);
+    app.update();
+    let weights = app.world().get::<MorphWeights>(face).unwrap();
+    assert_eq!(weights.weights(), [1.0, 0.8]);
+}
+
+#[test]
+fn paint_strokes_blend_into_canvas() {
+    let mut canvas = paint_canvas(UVec2::new(64, 32));
+    let pixel = |canvas: &Image, x: u32, y: u32| {
+        let i = ((y * 64 + x) * 4) as usize;
+        <[u8; 4]>::try_from(&canvas.data.as_ref().unwrap()[i..i + 4]).unwrap()
+    };
+
+    let brush = Brush::new(Color::srgb(1.0, 0.0, 0.0), 4.0);
+    paint_stroke(
+        &mut canvas,
+        Some(Vec2::new(0.25, 0.5)),
+        Vec2::new(0.75, 0.5),
+        &brush,
+    );
+    // The whole line is painted, nothing outside of the brush radius
+    assert_eq!(pixel(&canvas, 16, 16), [255, 0, 0, 255]);
+    assert_eq!(pixel(&canvas, 32, 16), [255, 0, 0, 255]);
+    assert_eq!(pixel(&canvas, 48, 16), [255, 0, 0, 255]);
+    assert_eq!(pixel(&canvas, 32, 24)[3], 0);
+
+    // Translucent paint blends over the existing color
+    let blue = Brush::new(Color::srgba(0.0, 0.0, 1.0, 0.5), 2.0).with_hardness(1.0);
+    paint_stroke(&mut canvas, None, Vec2::new(0.5, 0.5), &blue);
+    assert_eq!(pixel(&canvas, 32, 16), [128, 0, 128, 255]);
+
+    paint_stroke(&mut canvas, None, Vec2::new(0.5, 0.5), &Brush::eraser(3.0));
+    assert_eq!(pixel(&canvas, 32, 16)[3], 0);
+    assert_eq!(pixel(&canvas, 40, 16), [255, 0, 0, 255]);
+}
+
+#[test]
+fn draw_batches_group_shared_meshes_and_materials() {
+    let mut app = App::new();
+    app.add_plugins(DrawBatchesPlugin);
+    let mut visible = ViewVisibility::default();
+    visible.set();
+    let mesh = Handle::<Mesh>::default();
+    let material = Handle::<StandardMaterial>::default();
+    for _ in 0..100 {
+        app.world_mut().spawn((
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            visible,
+        ));
+    }
+    app.update();
+    assert_eq!(
+        *app.world().resource::<DrawBatches>(),
+        DrawBatches {
+            instances: 100,
+            batches: 1,
+            largest: 100,
+        }
+    );
+
+    // Invisible entities are not drawn, other materials need another draw
+    let other = Handle::<StandardMaterial>::Uuid(Uuid::from_u128(1), default());
+    app.world_mut()
+        .spawn((Mesh3d(mesh.clone()), MeshMaterial3d(other), visible));
+    app.world_mut().spawn((
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        ViewVisibility::default(),
+    ));
+    app.update();
+    let batches = app.world().resource::<DrawBatches>();
+    assert_eq!((batches.instances, batches.batches), (101, 2));
+
+    // Material ids of different material types never share a draw
+    app.world_mut().spawn((
+        Mesh3d(Handle::<Mesh>::default()),
+        MeshMaterial3d(Handle::<ObjectIdMaterial>::default()),
+        visible,
+    ));
+    app.update();
+    let batches = app.world().resource::<DrawBatches>();
+    assert_eq!((batches.instances, batches.batches), (102, 3));
+    assert_eq!(batches.largest, 100);
+}
+
+/// Compare `content` with `src/snapshots/<name>`. Set `XRDS_UPDATE_SNAPSHOTS`
+/// to write the snapshot after an intended change
+fn assert_snapshot(name: &str, content: &str) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/snapshots")
+        .join(name);
+    if std::env::var_os("XRDS_UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        expected == content,
+        "{} changed. Set XRDS_UPDATE_SNAPSHOTS=1 to update it",
+        path.display()
+    );
+}
+
+const FULLSCREEN_VERTEX_OUTPUT: &str = r#"
+#define_import_path bevy_core_pipeline::fullscreen_vertex_shader
+
+struct FullscreenVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+"#;
+
+#[test]
+fn shader_permutations_compile() {
+    let color_filter =
+        ShaderPermutations::new("color_filter.wgsl", include_str!("color_filter.wgsl"))
+            .with_import("fullscreen.wgsl", FULLSCREEN_VERTEX_OUTPUT);
+    for permutation in color_filter.compile_all() {
+        let wgsl = permutation.result.as_ref().unwrap();
+        assert_snapshot(&format!("color_filter.{}.wgsl", permutation.name()), wgsl);
+    }
+    let vignette = ShaderPermutations::new("vignette.wgsl", include_str!("vignette.wgsl"))
+        .with_import("fullscreen.wgsl", FULLSCREEN_VERTEX_OUTPUT);
+    for permutation in vignette.compile_all() {
+        let wgsl = permutation.result.as_ref().unwrap();
+        assert_snapshot(&format!("vignette.{}.wgsl", permutation.name()), wgsl);
+    }
+
+    // Every combination of defs is compiled and broken ones are reported
+    let shader = ShaderPermutations::new(
+        "test.wgsl",
+        r#"
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    var color = vec4<f32>(1.0);
+#ifdef GRAYSCALE
+    color = vec4<f32>(vec3<f32>(dot(color.rgb, vec3<f32>(0.3, 0.59, 0.11))), 1.0);
+#endif
+#ifdef DITHER
+#ifdef GRAYSCALE
+    color = color + undefined_noise;
+#endif
+#endif
+    return color;
+}
+"#,
+    )
+    .with_shader_def("GRAYSCALE")
+    .with_shader_def("DITHER");
+    let compiled = shader.compile_all();
+    let names = compiled.iter().map(|p| p.name()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["default", "GRAYSCALE", "DITHER", "GRAYSCALE+DITHER"]
+    );
+    let failed = compiled
+        .iter()
+        .filter(|p| p.result.is_err())
+        .map(|p| p.name())
+        .collect::<Vec<_>>();
+    assert_eq!(failed, ["GRAYSCALE+DITHER"]);
+}
+
+const PBR_STUBS: [(&str, &str); 4] = [
+    (
+        "pbr_types.wgsl",
+        r#"
+#define_import_path bevy_pbr::pbr_types
+
+struct StandardMaterial {
+    base_color: vec4<f32>,
+    flags: u32,
+};
+
+struct PbrInput {
+    material: StandardMaterial,
+};
+"#,
+    ),
+    (
+        "forward_io.wgsl",
+        r#"
+#define_import_path bevy_pbr::forward_io
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+};
+"#,
+    ),
+    (
+        "pbr_functions.wgsl",
+        r#"
+#define_import_path bevy_pbr::pbr_functions
+
+#import bevy_pbr::pbr_types
+
+fn alpha_discard(material: pbr_types::StandardMaterial, output_color: vec4<f32>) -> vec4<f32> {
+    return output_color;
+}
+
+fn apply_pbr_lighting(in: pbr_types::PbrInput) -> vec4<f32> {
+    return in.material.base_color;
+}
+
+fn main_pass_post_lighting_processing(pbr_input: pbr_types::PbrInput, input_color: vec4<f32>) -> vec4<f32> {
+    return input_color;
+}
+"#,
+    ),
+    (
+        "pbr_fragment.wgsl",
+        r#"
+#define_import_path bevy_pbr::pbr_fragment
+
+#import bevy_pbr::{forward_io::VertexOutput, pbr_types}
+
+fn pbr_input_from_standard_material(in: VertexOutput, is_front: bool) -> pbr_types::PbrInput {
+    var pbr_input: pbr_types::PbrInput;
+    pbr_input.material.base_color = vec4(in.world_normal, 1.0);
+    return pbr_input;
+}
+"#,
+    ),
+];
+
+#[test]
+fn clip_volumes_cut_outside_geometry() {
+    let transform = GlobalTransform::from(
+        Transform::from_xyz(0.0, 1.0, 0.0).with_scale(Vec3::new(2.0, 1.0, 1.0)),
+    );
+    let clip_box = ClipVolume::clip_box();
+    assert!(!clip_box.clips(&transform, Vec3::new(0.9, 1.0, 0.0)));
+    assert!(clip_box.clips(&transform, Vec3::new(1.1, 1.0, 0.0)));
+    assert!(clip_box.clips(&transform, Vec3::new(0.0, 1.6, 0.0)));
+
+    // Planes cut away what is in front of them
+    let plane = ClipVolume::plane();
+    assert!(plane.clips(&transform, Vec3::new(0.0, 1.0, -0.1)));
+    assert!(!plane.clips(&transform, Vec3::new(5.0, 1.0, 0.1)));
+
+    let disabled = ClipVolume {
+        enabled: false,
+        ..ClipVolume::clip_box()
+    };
+    assert!(!disabled.clips(&transform, Vec3::splat(10.0)));
+    assert_eq!(disabled.settings(&transform).shape, 0);
+
+    let settings = ClipVolume::plane()
+        .with_cap(Color::WHITE)
+        .settings(&transform);
+    assert_eq!((settings.shape, settings.cap), (2, 1));
+    assert_eq!(ClipVolume::default().shape, ClipShape::Box);
+
+    let mut shader = ShaderPermutations::new("clipping.wgsl", include_str!("clipping.wgsl"))
+        .with_constant("MATERIAL_BIND_GROUP", 3);
+    for (file_path, source) in PBR_STUBS {
+        shader = shader.with_import(file_path, source);
+    }
+    for permutation in shader.compile_all() {
+        let wgsl = permutation.result.as_ref().unwrap();
+        assert_snapshot(&format!("clipping.{}.wgsl", permutation.name()), wgsl);
+    }
+}
+
+#[test]
+fn environment_lighting_from_equirect_map() {
+    // Red sky, blue ground and a green patch straight ahead (-Z)
+    let mut map = Image::new_fill(
+        bevy::render::render_resource::Extent3d {
+            width: 8,
+            height: 4,
+            depth_or_array_layers: 1,
+        },
+        bevy::render::render_resource::TextureDimension::D2,
+        &[0; 16],
+        bevy::render::render_resource::TextureFormat::Rgba32Float,
+        RenderAssetUsages::all(),
+    );
+    for y in 0..4 {
+        for x in 0..8 {
+            let color = match (y, x) {
+                (0, _) => LinearRgba::RED,
+                (3, _) => LinearRgba::BLUE,
+                (_, 3 | 4) => LinearRgba::GREEN,
+                _ => LinearRgba::WHITE,
+            };
+            map.set_color_at(x, y, color.into()).unwrap();
+        }
+    }
+
+    let cubemap = equirect_to_cubemap(&map, 4);
+    let texel = |face| cubemap.get_color_at_3d(2, 2, face).unwrap().to_linear();
+    assert_eq!(texel(2), LinearRgba::RED);
+    assert_eq!(texel(3), LinearRgba::BLUE);
+    // Bevy samples cubemaps with z flipped, so +Z is in front
+    assert_eq!(texel(4), LinearRgba::GREEN);
+    assert_eq!(texel(5), LinearRgba::WHITE);
+
+    let mut app = App::new();
+    app.add_plugins(EnvironmentLightingPlugin)
+        .init_resource::<Assets<Image>>();
+    let source = app.world_mut().resource_mut::<Assets<Image>>().add(map);
+    let camera = app
+        .world_mut()
+        .spawn(EnvironmentLighting::new(source).with_intensity(500.0))
+        .id();
+    app.update();
+
+    let light = app
+        .world()
+        .get::<bevy::light::GeneratedEnvironmentMapLight>(camera)
+        .unwrap();
+    assert_eq!(light.intensity, 500.0);
+    let skybox = app
+        .world()
+        .get::<bevy::core_pipeline::Skybox>(camera)
+        .unwrap();
+    assert_eq!(skybox.image, light.environment_map);
+    let images = app.world().resource::<Assets<Image>>();
+    assert_eq!(images.get(&skybox.image).unwrap().width(), 512);
+}
+
+#[cfg(feature = "basis-universal")]
+#[test]
+fn basis_universal_transcode_target() {
+    use bevy::{image::CompressedImageFormats, render::render_resource::TextureFormat};
+
+    use crate::transcode_target;
+
+    assert_eq!(
+        transcode_target(CompressedImageFormats::BC, true),
+        TextureFormat::Bc7RgbaUnormSrgb
+    );
+    assert!(matches!(
+        transcode_target(
+            CompressedImageFormats::ASTC_LDR | CompressedImageFormats::BC,
+            false
+        ),
+        TextureFormat::Astc { .. }
+    ));
+    assert_eq!(
+        transcode_target(CompressedImageFormats::NONE, true),
+        TextureFormat::Rgba8UnormSrgb
+    );
+}
+
+#[test]
+fn texture_memory_counts_compressed_textures() {
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        TextureCompressionPlugin,
+    ))
+    .init_asset::<Image>();
+
+    let size = Extent3d {
+        width: 4,
+        height: 4,
+        depth_or_array_layers: 1,
+    };
+    let rgba = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[255; 4],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::all(),
+    );
+    // One 16 byte block per mip level
+    let mut bc7 = Image::new(
+        size,
+        TextureDimension::D2,
+        vec![0; 32],
+        TextureFormat::Bc7RgbaUnormSrgb,
+        RenderAssetUsages::all(),
+    );
+    bc7.texture_descriptor.mip_level_count = 2;
+
+    let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+    let _rgba = images.add(rgba);
+    let bc7 = images.add(bc7);
+    app.update();
+
+    let memory = app.world().resource::<TextureMemory>();
+    assert_eq!((memory.compressed, memory.compressed_textures), (32, 1));
+    assert_eq!((memory.uncompressed, memory.uncompressed_textures), (64, 1));
+    assert_eq!(memory.total(), 96);
+
+    drop(bc7);
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<TextureMemory>().compressed, 0);
+
+    // Removed while a handle is still held
+    let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+    images.remove(&_rgba);
+    let gpu_only = images.add(Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[255; 4],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    ));
+    app.update();
+    assert_eq!(app.world().resource::<TextureMemory>().uncompressed, 64);
+
+    // Uploaded images leave `Assets` but stay on the GPU until unused
+    app.world_mut()
+        .resource_mut::<Assets<Image>>()
+        .remove(&gpu_only);
+    app.update();
+    assert_eq!(app.world().resource::<TextureMemory>().uncompressed, 64);
+    drop(gpu_only);
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<TextureMemory>().total(), 0);
+}
+
+const MESH_STUBS: [(&str, &str); 3] = [
+    (
+        "mesh_view_bindings.wgsl",
+        r#"
+#define_import_path bevy_pbr::mesh_view_bindings
+
+struct View {
+    clip_from_world: mat4x4<f32>,
+    world_position: vec3<f32>,
+    viewport: vec4<f32>,
+};
+
+struct Globals {
+    time: f32,
+};
+
+@group(0) @binding(0) var<uniform> view: View;
+@group(0) @binding(11) var<uniform> globals: Globals;
+"#,
+    ),
+    (
+        "mesh_functions.wgsl",
+        r#"
+#define_import_path bevy_pbr::mesh_functions
+
+fn get_world_from_local(instance_index: u32) -> mat4x4<f32> {
+    return mat4x4<f32>(
+        vec4(1.0, 0.0, 0.0, 0.0),
+        vec4(0.0, 1.0, 0.0, 0.0),
+        vec4(0.0, 0.0, 1.0, 0.0),
+        vec4(0.0, 0.0, 0.0, 1.0),
+    );
+}
+
+fn mesh_position_local_to_world(world_from_local: mat4x4<f32>, vertex_position: vec4<f32>) -> vec4<f32> {
+    return world_from_local * vertex_position;
+}
+
+fn mesh_normal_local_to_world(vertex_normal: vec3<f32>, instance_index: u32) -> vec3<f32> {
+    return vertex_normal;
+}
+"#,
+    ),
+    (
+        "view_transformations.wgsl",
+        r#"
+#define_import_path bevy_pbr::view_transformations
+
+#import bevy_pbr::mesh_view_bindings::view
+
+fn position_world_to_clip(world_pos: vec3<f32>) -> vec4<f32> {
+    return view.clip_from_world * vec4(world_pos, 1.0);
+}
+"#,
+    ),
+];
+
+#[test]
+fn highlights_add_overlays_to_meshes() {
+    for (name, source) in [
+        ("outline", include_str!("outline.wgsl")),
+        ("fresnel", include_str!("fresnel.wgsl")),
+    ] {
+        let mut shader = ShaderPermutations::new(format!("{name}.wgsl"), source)
+            .with_constant("MATERIAL_BIND_GROUP", 3);
+        for (file_path, source) in PBR_STUBS.into_iter().chain(MESH_STUBS) {
+            shader = shader.with_import(file_path, source);
+        }
+        for permutation in shader.compile_all() {
+            let wgsl = permutation.result.as_ref().unwrap();
+            assert_snapshot(&format!("{name}.{}.wgsl", permutation.name()), wgsl);
+        }
+    }
+
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        HighlightPlugin,
+    ));
+    let mesh = Handle::<Mesh>::Uuid(Uuid::from_u128(1), default());
+    let root = app.world_mut().spawn_empty().id();
+    let part = app
+        .world_mut()
+        .spawn((Mesh3d(mesh.clone()), ChildOf(root)))
+        .id();
+    let overlays = |app: &mut App| {
+        let mut query = app.world_mut().query::<(&HighlightOverlay, &ChildOf)>();
+        query
+            .iter(app.world())
+            .map(|(overlay, child_of)| (overlay.root, child_of.parent()))
+            .collect::<Vec<_>>()
+    };
+
+    app.world_mut()
+        .set_highlight(root, HighlightStyle::outline(Color::WHITE, 2.0));
+    app.update();
+    assert_eq!(overlays(&mut app), [(root, part)]);
+    app.update();
+    assert_eq!(overlays(&mut app).len(), 1);
+
+    // Both an outline and a rim
+    app.world_mut().set_highlight(
+        root,
+        HighlightStyle::selected().with_fresnel(Fresnel::new(Color::WHITE)),
+    );
+    app.update();
+    assert_eq!(overlays(&mut app), [(root, part), (root, part)]);
+
+    app.world_mut().clear_highlight(root);
+    app.update();
+    assert!(overlays(&mut app).is_empty());
+    assert!(app.world().get::<Highlight>(root).is_none());
+}
+
+#[test]
+fn object_ids_pick_meshes_under_pixels() {
+    let stubs = [
+        (
+            "forward_io.wgsl",
+            r#"
+#define_import_path bevy_pbr::forward_io
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) instance_index: u32,
+};
+"#,
+        ),
+        (
+            "mesh_functions.wgsl",
+            r#"
+#define_import_path bevy_pbr::mesh_functions
+
+fn get_tag(instance_index: u32) -> u32 {
+    return instance_index;
+}
+"#,
+        ),
+    ];
+    let mut shader = ShaderPermutations::new("object_id.wgsl", include_str!("object_id.wgsl"))
+        .with_constant("MATERIAL_BIND_GROUP", 3);
+    for (file_path, source) in stubs {
+        shader = shader.with_import(file_path, source);
+    }
+    for permutation in shader.compile_all() {
+        let wgsl = permutation.result.as_ref().unwrap();
+        assert_snapshot(&format!("object_id.{}.wgsl", permutation.name()), wgsl);
+    }
+
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        ObjectIdPlugin,
+    ))
+    .init_asset::<Image>();
+    let mut camera = Camera::default();
+    camera.computed.target_info = Some(bevy::camera::RenderTargetInfo {
+        physical_size: UVec2::new(4, 2),
+        scale_factor: 1.0,
+    });
+    let camera = app
+        .world_mut()
+        .spawn((Camera3d::default(), camera, ObjectIdPicking))
+        .id();
+    let mesh = Handle::<Mesh>::Uuid(Uuid::from_u128(1), default());
+    let part = app.world_mut().spawn(Mesh3d(mesh.clone())).id();
+    let ignored = app
+        .world_mut()
+        .spawn((Mesh3d(mesh.clone()), Pickable::IGNORE))
+        .id();
+    app.update();
+
+    let id = *app.world().get::<ObjectId>(part).unwrap();
+    assert!(app.world().get::<ObjectId>(ignored).is_none());
+    let children = app.world().get::<Children>(part).unwrap();
+    assert!(app.world().get::<ObjectIdOverlay>(children[0]).is_some());
+
+    // Rows of the readback are aligned to 256 bytes, 1.0 is 0x3c00 as a 16 bit float
+    assert_eq!(id.get(), 1);
+    let mut data = vec![0; 512];
+    data[260..262].copy_from_slice(&0x3c00u16.to_le_bytes());
+    let id_camera = app
+        .world_mut()
+        .query_filtered::<Entity, With<bevy::render::gpu_readback::Readback>>()
+        .single(app.world())
+        .unwrap();
+    app.world_mut()
+        .trigger(bevy::render::gpu_readback::ReadbackComplete {
+            entity: id_camera,
+            data,
+        });
+    let pick = |app: &mut App, position: Vec2| {
+        app.world_mut()
+            .run_system_once(move |picker: ObjectPicker| picker.pick(camera, position))
+            .unwrap()
+    };
+    assert_eq!(pick(&mut app, Vec2::new(1.5, 1.5)), Some(part));
+    assert_eq!(pick(&mut app, Vec2::new(0.5, 1.5)), None);
+    assert_eq!(pick(&mut app, Vec2::new(8.0, 1.0)), None);
+
+    app.world_mut().despawn(part);
+    assert!(app.world().resource::<ObjectIds>().is_empty());
+}
+
+#[test]
+fn hot_reload_reports_reloaded_shaders() {
+    use bevy::asset::io::{
+        memory::{Dir, MemoryAssetReader},
+        AssetSourceBuilder,
+    };
+
+    let dir = Dir::default();
+    dir.insert_asset_text(std::path::Path::new("color.wgsl"), "fn color() {}");
+    let reader_dir = dir.clone();
+    let mut app = App::new();
+    app.register_asset_source(
+        "memory",
+        AssetSourceBuilder::default().with_reader(move || {
+            Box::new(MemoryAssetReader {
+                root: reader_dir.clone(),
+            })
+        }),
+    )
+    .add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        HotReloadPlugin,
+    ))
+    .init_asset::<bevy::shader::Shader>()
+    .init_asset_loader::<bevy::shader::ShaderLoader>();
+    let shader: Handle<bevy::shader::Shader> = app
+        .world()
+        .resource::<AssetServer>()
+        .load("memory://color.wgsl");
+    let update_until = |app: &mut App, done: &dyn Fn(&App) -> bool| {
+        let start = std::time::Instant::now();
+        while !done(app) {
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            app.update();
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    };
+    update_until(&mut app, &|app| {
+        app.world()
+            .resource::<AssetServer>()
+            .is_loaded_with_dependencies(&shader)
+    });
+    assert!(app.world().resource::<Messages<AssetReloaded>>().is_empty());
+
+    // The file watcher reloads changed files the same way
+    dir.insert_asset_text(std::path::Path::new("color.wgsl"), "fn color() { }");
+    app.world()
+        .resource::<AssetServer>()
+        .reload("memory://color.wgsl");
+    update_until(&mut app, &|app| {
+        !app.world().resource::<Messages<AssetReloaded>>().is_empty()
+    });
+    let reloaded = app.world().resource::<Messages<AssetReloaded>>();
+    assert_eq!(
+        reloaded.iter_current_update_messages().next(),
+        Some(&AssetReloaded {
+            path: "memory://color.wgsl".into(),
+            kind: ReloadKind::Shader,
+        })
+    );
+}
+
+#[test]
+fn gltf_inspection_lists_scene_and_warnings() {
+    let json = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["EXT_meshopt_compression", "KHR_texture_transform"],
+        "scene": 0,
+        "scenes": [{ "name": "Main", "nodes": [0] }],
+        "nodes": [
+            { "name": "Robot", "children": [1] },
+            { "name": "Arm", "mesh": 0 }
+        ],
+        "images": [{ "uri": "missing.png" }],
+        "textures": [{ "source": 0 }],
+        "materials": [{
+            "name": "Metal",
+            "normalTexture": { "index": 0 },
+            "doubleSided": true
+        }],
+        "buffers": [{ "byteLength": 36 }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "accessors": [{
+            "bufferView": 0,
+            "componentType": 5126,
+            "count": 3,
+            "type": "VEC3",
+            "min": [0, 0, 0],
+            "max": [1, 1, 0]
+        }],
+        "meshes": [{
+            "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }]
+        }]
+    }"#;
+    let path = std::env::temp_dir().join(format!("xrds-inspect-{}.gltf", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let inspection = GltfInspection::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let robot = &inspection.scenes[0].nodes[0];
+    assert_eq!(robot.name.as_deref(), Some("Robot"));
+    assert_eq!(robot.children[0].mesh, Some(0));
+    assert!(inspection.materials[0].normal_map && inspection.materials[0].double_sided);
+    assert_eq!(inspection.textures[0].source, "missing.png");
+    assert_eq!(inspection.texture_memory(), 0);
+    assert_eq!(
+        inspection.warnings,
+        [
+            GltfWarning::MissingNormals {
+                mesh: 0,
+                primitive: 0
+            },
+            GltfWarning::MissingTangents {
+                mesh: 0,
+                primitive: 0
+            },
+            GltfWarning::UnsupportedExtension("EXT_meshopt_compression".to_owned()),
+            GltfWarning::MissingTexture("missing.png".to_owned()),
+        ]
+    );
+    let report = inspection.to_string();
+    assert!(report.contains("Robot\n    Arm (mesh 0)"));
+}
+
+#[test]
+fn obj_importer_reads_meshes_and_materials() {
+    let obj = "mtllib crate.mtl\n\
+        v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+        vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+        vn 0 0 1\n\
+        o crate\nusemtl wood\nf 1/1/1 2/2/1 3/3/1 4/4/1\n\
+        o label\nusemtl paper\nf -4//1 -3//1 -2//1\n";
+    let mtl = "newmtl wood\nKd 0.5 0.25 0 \nmap_Kd -bm 1 wood.png\nmap_Bump wood_normal.png\n\
+        newmtl paper\nKd 1 1 1\nd 0.5\n";
+    let importer = ObjImporter;
+    assert_eq!(importer.dependencies(obj.as_bytes()), ["crate.mtl"]);
+    let dependencies = [("crate.mtl".to_owned(), mtl.as_bytes().to_vec())].into();
+    let scene = importer.import(obj.as_bytes(), &dependencies).unwrap();
+
+    assert_eq!(scene.meshes.len(), 2);
+    let quad = &scene.meshes[0];
+    assert_eq!((quad.name.as_str(), quad.material), ("crate", Some(0)));
+    // The quad is split into two triangles sharing four vertices
+    assert_eq!(quad.mesh.count_vertices(), 4);
+    assert_eq!(quad.mesh.indices().unwrap().len(), 6);
+    assert!(quad.mesh.contains_attribute(Mesh::ATTRIBUTE_UV_0));
+    assert!(!scene.meshes[1]
+        .mesh
+        .contains_attribute(Mesh::ATTRIBUTE_UV_0));
+    let wood = &scene.materials[0];
+    assert_eq!(wood.base_color, Color::srgb(0.5, 0.25, 0.0));
+    assert_eq!(wood.base_color_texture.as_deref(), Some("wood.png"));
+    assert_eq!(wood.normal_map_texture.as_deref(), Some("wood_normal.png"));
+    assert_eq!(scene.materials[1].base_color.alpha(), 0.5);
+    assert!(importer.import(b"f 1 2 3", &dependencies).is_err());
+
+    // Loaded as a scene through the asset server
+    let dir = bevy::asset::io::memory::Dir::default();
+    dir.insert_asset_text(std::path::Path::new("crate.obj"), obj);
+    let mut app = App::new();
+    app.register_asset_source(
+        "memory",
+        bevy::asset::io::AssetSourceBuilder::default().with_reader(move || {
+            Box::new(bevy::asset::io::memory::MemoryAssetReader { root: dir.clone() })
+        }),
+    )
+    .add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        AssetImportPlugin,
+    ))
+    .init_asset::<Scene>()
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Image>();
+    let scene: Handle<Scene> = app
+        .world()
+        .resource::<AssetServer>()
+        .load("memory://crate.obj");
+    let start = std::time::Instant::now();
+    while !app.world().resource::<AssetServer>().is_loaded(&scene) {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        app.update();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    let mut scenes = app.world_mut().resource_mut::<Assets<Scene>>();
+    let world = &mut scenes.get_mut(&scene).unwrap().world;
+    let mut meshes = world.query::<(&Name, &Mesh3d)>();
+    assert_eq!(meshes.iter(world).count(), 2);
+}
+
+#[test]
+fn import_settings_apply_to_imported_scenes() {
+    let obj = "mtllib crate.mtl\n\
+        v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+        usemtl wood\nf 1 2 3 4\nf 2 5 6 3\n";
+    let mtl = "newmtl wood\nKd 1 1 1\nmap_Kd wood.png\n";
+    let meta = r#"{
+        "scale": 0.5,
+        "mesh_optimization": "VertexCache",
+        "texture_compression": "Ktx2",
+        "materials": { "wood": { "metallic": 1.0, "double_sided": true } }
+    }"#;
+    let dir = bevy::asset::io::memory::Dir::default();
+    dir.insert_asset_text(std::path::Path::new("crate.obj"), obj);
+    dir.insert_asset_text(std::path::Path::new("crate.mtl"), mtl);
+    dir.insert_asset_text(std::path::Path::new("crate.obj.meta"), meta);
+    let mut app = App::new();
+    app.register_asset_source(
+        "memory",
+        bevy::asset::io::AssetSourceBuilder::default().with_reader(move || {
+            Box::new(bevy::asset::io::memory::MemoryAssetReader { root: dir.clone() })
+        }),
+    )
+    .add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin {
+            meta_check: bevy::asset::AssetMetaCheck::Never,
+            ..default()
+        },
+        AssetImportPlugin,
+    ))
+    .init_asset::<Scene>()
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Image>();
+    let scene: Handle<Scene> = app
+        .world()
+        .resource::<AssetServer>()
+        .load("memory://crate.obj");
+    let start = std::time::Instant::now();
+    while !app.world().resource::<AssetServer>().is_loaded(&scene) {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        app.update();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    let mut scenes = app.world_mut().resource_mut::<Assets<Scene>>();
+    let world = &mut scenes.get_mut(&scene).unwrap().world;
+    let (transform, mesh, material) = world
+        .query::<(&Transform, &Mesh3d, &MeshMaterial3d<StandardMaterial>)>()
+        .single(world)
+        .unwrap();
+    assert_eq!(transform.scale, Vec3::splat(0.5));
+    let (mesh, material) = (mesh.0.clone(), material.0.clone());
+
+    let wood = app
+        .world()
+        .resource::<Assets<StandardMaterial>>()
+        .get(&material)
+        .unwrap();
+    assert_eq!(wood.metallic, 1.0);
+    assert!(wood.double_sided && wood.cull_mode.is_none());
+    let texture = wood.base_color_texture.as_ref().unwrap();
+    let texture_path = app.world().resource::<AssetServer>().get_path(texture);
+    assert_eq!(
+        texture_path.unwrap().path(),
+        std::path::Path::new("wood.ktx2")
+    );
+
+    // Reordered, but the same triangles
+    let triangles = |indices: &Indices| {
+        let indices: Vec<usize> = indices.iter().collect();
+        let mut triangles: Vec<[usize; 3]> = indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let first = (0..3).min_by_key(|&i| triangle[i]).unwrap();
+                [0, 1, 2].map(|i| triangle[(first + i) % 3])
+            })
+            .collect();
+        triangles.sort();
+        triangles
+    };
+    let mut quads = Mesh::from(Rectangle::default());
+    let plain = triangles(quads.indices().unwrap());
+    MeshOptimization::VertexCache.apply(&mut quads);
+    assert_eq!(triangles(quads.indices().unwrap()), plain);
+    let mesh = app.world().resource::<Assets<Mesh>>().get(&mesh).unwrap();
+    assert_eq!(triangles(mesh.indices().unwrap()).len(), 4);
+}
+
+#[test]
+fn gltf_validation_reports_fixable_issues() {
+    use bevy::asset::io::{
+        memory::{Dir, MemoryAssetReader},
+        AssetSourceBuilder,
+    };
+
+    let json = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "images": [{ "uri": "missing.png" }],
+        "textures": [{ "source": 0 }],
+        "materials": [{ "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } }],
+        "buffers": [{
+            "byteLength": 36,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+        }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "accessors": [{
+            "bufferView": 0,
+            "componentType": 5126,
+            "count": 3,
+            "type": "VEC3",
+            "min": [0, 0, 0],
+            "max": [1, 1, 0]
+        }],
+        "meshes": [{
+            "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }]
+        }]
+    }"#;
+    let dir = Dir::default();
+    dir.insert_asset_text(std::path::Path::new("triangle.gltf"), json);
+    let mut app = App::new();
+    app.register_asset_source(
+        "memory",
+        AssetSourceBuilder::default()
+            .with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::mesh::MeshPlugin,
+        ImagePlugin::default(),
+        bevy::gltf::GltfPlugin::default(),
+        GltfValidationPlugin,
+    ))
+    .init_asset::<StandardMaterial>()
+    .init_asset::<AnimationClip>()
+    .init_asset::<bevy::mesh::skinning::SkinnedMeshInverseBindposes>();
+    app.finish();
+    let gltf = load_validated_gltf(
+        app.world().resource::<AssetServer>(),
+        "memory://triangle.gltf",
+    );
+    let start = std::time::Instant::now();
+    while app
+        .world()
+        .resource::<GltfValidationReports>()
+        .get(&gltf)
+        .is_none()
+    {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        app.update();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    let report = app
+        .world()
+        .resource::<GltfValidationReports>()
+        .get(&gltf)
+        .unwrap();
+    assert_eq!(
+        report.issues,
+        [
+            GltfWarning::MissingNormals {
+                mesh: 0,
+                primitive: 0
+            },
+            GltfWarning::MissingTexture("missing.png".to_owned()),
+        ]
+    );
+    assert!(!report.issues.iter().any(GltfWarning::is_fatal));
+    assert!(report.to_string().contains("Fix: Export normals"));
+}
+
+#[test]
+fn frame_graph_exports_passes_as_dot_and_json() {
+    use bevy::render::render_graph::{
+        EmptyNode, Node, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel,
+        RenderSubGraph, SlotInfo, SlotType,
+    };
+
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+    enum Pass {
+        Depth,
+        Opaque,
+        Resolve,
+    }
+
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderSubGraph)]
+    struct Eye;
+
+    struct DepthNode;
+
+    impl Node for DepthNode {
+        fn output(&self) -> Vec<SlotInfo> {
+            vec![SlotInfo::new("depth", SlotType::TextureView)]
+        }
+
+        fn run(
+            &self,
+            _graph: &mut RenderGraphContext,
+            _render_context: &mut bevy::render::renderer::RenderContext,
+            _world: &World,
+        ) -> Result<(), NodeRunError> {
+            Ok(())
+        }
+    }
+
+    struct OpaqueNode;
+
+    impl Node for OpaqueNode {
+        fn input(&self) -> Vec<SlotInfo> {
+            vec![SlotInfo::new("depth_in", SlotType::TextureView)]
+        }
+
+        fn run(
+            &self,
+            _graph: &mut RenderGraphContext,
+            _render_context: &mut bevy::render::renderer::RenderContext,
+            _world: &World,
+        ) -> Result<(), NodeRunError> {
+            Ok(())
+        }
+    }
+
+    let mut eye = RenderGraph::default();
+    eye.add_node(Pass::Depth, DepthNode);
+    eye.add_node(Pass::Opaque, OpaqueNode);
+    eye.add_node(Pass::Resolve, EmptyNode);
+    eye.add_slot_edge(Pass::Depth, "depth", Pass::Opaque, "depth_in");
+    eye.add_node_edge(Pass::Opaque, Pass::Resolve);
+    let mut main = RenderGraph::default();
+    main.add_sub_graph(Eye, eye);
+
+    let frame = FrameGraph {
+        graph: FrameGraphPasses::from_render_graph("main", &main),
+        views: vec![],
+    };
+    let eye = &frame.graph.sub_graphs[0];
+    assert_eq!(eye.name, "Eye");
+    assert_eq!(eye.nodes.len(), 3);
+    assert_eq!(eye.nodes[0].outputs, ["depth: TextureView"]);
+    assert_eq!(
+        eye.edges,
+        [
+            FrameGraphEdge {
+                from: "Depth".to_owned(),
+                to: "Opaque".to_owned(),
+                slot: Some(("depth".to_owned(), "depth_in".to_owned())),
+            },
+            FrameGraphEdge {
+                from: "Opaque".to_owned(),
+                to: "Resolve".to_owned(),
+                slot: None,
+            },
+        ]
+    );
+
+    let dot = frame.to_dot();
+    assert!(dot.contains("subgraph cluster_0 {"));
+    assert!(dot.contains("\"Eye/Depth\" -> \"Eye/Opaque\" [label=\"depth -> depth_in\"];"));
+    assert!(dot.contains("\"Eye/Opaque\" -> \"Eye/Resolve\" [style=dashed];"));
+    let json: serde_json::Value = serde_json::from_str(&frame.to_json()).unwrap();
+    assert_eq!(
+        json["graph"]["sub_graphs"][0]["nodes"][1]["label"],
+        "Opaque"
+    );
+}
+
+#[test]
+fn gltf_exporter_writes_world_as_glb() {
+    let mut world = World::new();
+    world.init_resource::<Assets<Mesh>>();
+    world.init_resource::<Assets<StandardMaterial>>();
+    let mesh = world
+        .resource_mut::<Assets<Mesh>>()
+        .add(Cuboid::new(1.0, 2.0, 1.0));
+    let material = world
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(StandardMaterial {
+            base_color: Color::linear_rgb(1.0, 0.0, 0.0),
+            unlit: true,
+            ..default()
+        });
+    let room = world
+        .spawn((Name::new("Room"), Transform::from_xyz(0.0, 1.0, 0.0)))
+        .id();
+    for x in [-1.0, 1.0] {
+        world.spawn((
+            Name::new("Box"),
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            Transform::from_xyz(x, 0.0, 0.0),
+            ChildOf(room),
+        ));
+    }
+    world.spawn((
+        PointLight {
+            intensity: 4.0 * std::f32::consts::PI,
+            ..default()
+        },
+        Transform::default(),
+        ChildOf(room),
+    ));
+    world.spawn((
+        Camera::default(),
+        Projection::Perspective(PerspectiveProjection::default()),
+        Transform::from_xyz(0.0, 0.0, 5.0),
+    ));
+
+    let glb = GltfExporter::new().export(&mut world).unwrap();
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    let scene = gltf.default_scene().unwrap();
+    assert_eq!(scene.nodes().count(), 2);
+    let room = scene
+        .nodes()
+        .find(|node| node.name() == Some("Room"))
+        .unwrap();
+    assert_eq!(room.transform().decomposed().0, [0.0, 1.0, 0.0]);
+    let boxes = room
+        .children()
+        .filter(|node| node.mesh().is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(boxes.len(), 2);
+    // Shared meshes are written once
+    assert_eq!(gltf.meshes().count(), 1);
+    let primitive = gltf.meshes().next().unwrap().primitives().next().unwrap();
+    assert_eq!(
+        primitive.get(&gltf::Semantic::Positions).unwrap().count(),
+        24
+    );
+    assert_eq!(primitive.indices().unwrap().count(), 36);
+    let material = primitive.material();
+    assert_eq!(
+        material.pbr_metallic_roughness().base_color_factor(),
+        [1.0, 0.0, 0.0, 1.0]
+    );
+    assert!(material.unlit());
+    assert_eq!(gltf.cameras().count(), 1);
+    let json: serde_json::Value =
+        serde_json::from_slice(&gltf::binary::Glb::from_slice(&glb).unwrap().json).unwrap();
+    assert_eq!(
+        json["extensions"]["KHR_lights_punctual"]["lights"][0]["intensity"],
+        1.0
+    );
+}
+
+#[test]
+fn render_stats_group_pass_timings() {
+    let mut app = App::new();
+    app.init_resource::<Time<Real>>()
+        .add_plugins(RenderStatsPlugin);
+    let overlay = app.world_mut().spawn(RenderStatsOverlay).id();
+    app.update();
+    assert_eq!(app.world().resource::<RenderStats>().gpu_time(), None);
+
+    // Measurements as recorded by the render diagnostics, in milliseconds
+    let mut store = app.world_mut().resource_mut::<DiagnosticsStore>();
+    for (path, value) in [
+        ("render/shadows/elapsed_gpu", 1.0),
+        ("render/early prepass/elapsed_gpu", 2.0),
+        ("render/main_opaque_pass_3d/elapsed_cpu", 0.25),
+        ("render/main_opaque_pass_3d/elapsed_gpu", 3.0),
+        ("render/tonemapping/elapsed_gpu", 0.5),
+        ("render/tonemapping/elapsed_cpu", 0.1),
+    ] {
+        let path = DiagnosticPath::new(path);
+        store.add(Diagnostic::new(path.clone()).with_suffix("ms"));
+        store
+            .get_mut(&path)
+            .unwrap()
+            .add_measurement(DiagnosticMeasurement {
+                time: bevy::platform::time::Instant::now(),
+                value,
+            });
+    }
+    app.update();
+
+    let stats = app.world().resource::<RenderStats>();
+    let millis = |duration: Option<std::time::Duration>| duration.unwrap().as_secs_f64() * 1000.0;
+    assert!((millis(stats.gpu_time()) - 6.5).abs() < 1e-6);
+    assert!((millis(stats.phase_gpu_time(RenderPhase::GBuffer)) - 2.0).abs() < 1e-6);
+    assert!((millis(stats.phase_gpu_time(RenderPhase::Post)) - 0.5).abs() < 1e-6);
+    let opaque = stats.pass("main_opaque_pass_3d").unwrap();
+    assert_eq!(opaque.phase, RenderPhase::Lighting);
+    assert!((millis(opaque.cpu) - 0.25).abs() < 1e-6);
+
+    let text = &app.world().get::<Text>(overlay).unwrap().0;
+    assert!(text.contains("GPU 6.50 ms"), "{text}");
+    assert!(text.contains("shadows 1.00 ms"), "{text}");
+    assert!(text.contains("lighting 3.00 ms"), "{text}");
+}
+
+#[test]
+fn camera_bloom_follows_post_processing() {
+    let mut app = App::new();
+    app.add_plugins(BloomPlugin);
+    let camera = app
+        .world_mut()
+        .spawn((Camera::default(), CameraBloom::emissive().with_mip_count(6)))
+        .id();
+    app.update();
+    let bloom = app.world().get::<Bloom>(camera).unwrap();
+    assert_eq!(bloom.prefilter.threshold, 1.0);
+    assert_eq!(bloom.max_mip_dimension, 128);
+    assert!(app.world().get::<Hdr>(camera).is_some());
+
+    app.world_mut().resource_mut::<PostProcessing>().enabled = false;
+    app.update();
+    assert!(app.world().get::<Bloom>(camera).is_none());
+    app.world_mut().resource_mut::<PostProcessing>().enabled = true;
+    app.update();
+    assert!(app.world().get::<Bloom>(camera).is_some());
+
+    app.world_mut().entity_mut(camera).remove::<CameraBloom>();
+    app.update();
+    assert!(app.world().get::<Bloom>(camera).is_none());
+}
+
+#[test]
+fn render_scale_shrinks_main_pass() {
+    let native = RenderScale::default();
+    assert_eq!(native.main_pass_size(UVec2::new(1920, 1080)), None);
+
+    let mut scale = RenderScale::new(0.75).with_filter(UpscaleFilter::Bilinear);
+    assert_eq!(
+        scale.main_pass_size(UVec2::new(1920, 1080)),
+        Some(UVec2::new(1440, 810))
+    );
+    scale.dynamic_scale = 0.1;
+    assert_eq!(scale.effective_scale(), MIN_RENDER_SCALE);
+    assert_eq!(
+        scale.main_pass_size(UVec2::new(2000, 2000)),
+        Some(UVec2::new(500, 500))
+    );
+
+    // Half resolution lighting renders at most a quarter of the pixels
+    let half = RenderScale::new(0.75).half_resolution();
+    assert_eq!(half.filter, UpscaleFilter::EdgeAware);
+    assert_eq!(
+        half.main_pass_size(UVec2::new(1920, 1080)),
+        Some(UVec2::new(960, 540))
+    );
+    assert_eq!(scale.half_resolution().effective_scale(), MIN_RENDER_SCALE);
+
+    let shader = ShaderPermutations::new("upscaling.wgsl", include_str!("upscaling.wgsl"))
+        .with_shader_def("EDGE_AWARE")
+        .with_import("fullscreen.wgsl", FULLSCREEN_VERTEX_OUTPUT);
+    for permutation in shader.compile_all() {
+        let wgsl = permutation.result.as_ref().unwrap();
+        assert_snapshot(&format!("upscaling.{}.wgsl", permutation.name()), wgsl);
+    }
+}
+
+#[test]
+fn multisampling_applies_to_cameras() {
+    assert_eq!(msaa_from_samples(0), Msaa::Off);
+    assert_eq!(msaa_from_samples(3), Msaa::Sample2);
+    assert_eq!(msaa_from_samples(16), Msaa::Sample8);
+
+    let mut app = App::new();
+    app.add_plugins(MultisamplePlugin)
+        .insert_resource(Multisampling::new(8))
+        // Registered by the render plugin
+        .register_required_components::<Camera, Msaa>();
+    let main = app.world_mut().spawn(Camera3d::default()).id();
+    let hud = app
+        .world_mut()
+        .spawn((Camera3d::default(), CameraMultisample::off()))
+        .id();
+    let ids = app.world_mut().spawn((Camera3d::default(), Msaa::Off)).id();
+
+    app.update();
+    let msaa = |app: &App, entity| *app.world().get::<Msaa>(entity).unwrap();
+    assert_eq!(msaa(&app, main), Msaa::Sample8);
+    assert_eq!(msaa(&app, hud), Msaa::Off);
+    assert_eq!(msaa(&app, ids), Msaa::Off);
+
+    app.world_mut().resource_mut::<Multisampling>().samples = 2;
+    app.world_mut()
+        .entity_mut(hud)
+        .insert(CameraMultisample::new(4));
+    app.update();
+    assert_eq!(msaa(&app, main), Msaa::Sample2);
+    assert_eq!(msaa(&app, hud), Msaa::Sample4);
+    assert_eq!(msaa(&app, ids), Msaa::Off);
+}
+
+#[test]
+fn ambient_occlusion_disables_msaa() {
+    let mut app = App::new();
+    app.add_plugins((AmbientOcclusionPlugin, MultisamplePlugin))
+        .register_required_components::<Camera, Msaa>();
+    let camera = app
+        .world_mut()
+        .spawn((
+            Camera3d::default(),
+            CameraAmbientOcclusion::default()
+                .with_quality(AmbientOcclusionQuality::Low)
+                .with_thickness(0.5),
+        ))
+        .id();
+    app.update();
+    app.update();
+    let ssao = app
+        .world()
+        .get::<ScreenSpaceAmbientOcclusion>(camera)
+        .unwrap();
+    assert_eq!(ssao.constant_object_thickness, 0.5);
+    assert!(app.world().get::<DepthPrepass>(camera).is_some());
+    assert!(app.world().get::<NormalPrepass>(camera).is_some());
+    assert_eq!(*app.world().get::<Msaa>(camera).unwrap(), Msaa::Off);
+
+    app.world_mut()
+        .entity_mut(camera)
+        .insert(CameraAmbientOcclusion::default());
+    app.world_mut()
+        .resource_mut::<HalfResolution>()
+        .ambient_occlusion = true;
+    app.update();
+    let ssao = app
+        .world()
+        .get::<ScreenSpaceAmbientOcclusion>(camera)
+        .unwrap();
+    assert_eq!(
+        ssao.quality_level,
+        ScreenSpaceAmbientOcclusionQualityLevel::Low
+    );
+
+    app.world_mut().resource_mut::<PostProcessing>().enabled = false;
+    app.update();
+    app.update();
+    assert!(app
+        .world()
+        .get::<ScreenSpaceAmbientOcclusion>(camera)
+        .is_none());
+    assert_eq!(*app.world().get::<Msaa>(camera).unwrap(), Msaa::Sample4);
+}
+
+#[test]
+fn depth_prepass_follows_settings() {
+    let mut app = App::new();
+    app.add_plugins(DepthPrepassPlugin)
+        .insert_resource(DepthPrepassing::new(true));
+    let main = app.world_mut().spawn(Camera3d::default()).id();
+    let hud = app
+        .world_mut()
+        .spawn((Camera3d::default(), CameraDepthPrepass::off()))
+        .id();
+    let manual = app
+        .world_mut()
+        .spawn((Camera3d::default(), DepthPrepass, CameraDepthPrepass::off()))
+        .id();
+
+    app.update();
+    let prepass = |app: &App, entity| app.world().get::<DepthPrepass>(entity).is_some();
+    assert!(prepass(&app, main));
+    assert!(!prepass(&app, hud));
+    assert!(prepass(&app, manual));
+
+    app.world_mut().resource_mut::<DepthPrepassing>().enabled = false;
+    app.world_mut()
+        .entity_mut(hud)
+        .insert(CameraDepthPrepass::on());
+    app.update();
+    assert!(!prepass(&app, main));
+    assert!(prepass(&app, hud));
+    assert!(prepass(&app, manual));
+}
+
+#[test]
+fn deferred_gbuffer_defers_cameras() {
+    let mut app = App::new();
+    app.add_plugins((
+        AssetPlugin::default(),
+        GBufferPlugin,
+        DepthPrepassPlugin,
+        MultisamplePlugin,
+    ))
+    .init_asset::<StandardMaterial>();
+    let camera = app.world_mut().spawn(Camera3d::default()).id();
+    let manual = app
+        .world_mut()
+        .spawn((Camera3d::default(), DeferredPrepass))
+        .id();
+    app.update();
+    assert!(app.world().get::<DeferredPrepass>(camera).is_none());
+
+    app.insert_resource(GBufferLayout::Deferred);
+    app.update();
+    app.update();
+    let world = app.world();
+    assert!(world.get::<DeferredPrepass>(camera).is_some());
+    assert!(world.get::<DepthPrepass>(camera).is_some());
+    assert_eq!(*world.get::<Msaa>(camera).unwrap(), Msaa::Off);
+    // FXAA in place of MSAA
+    assert!(world.get::<Fxaa>(camera).is_some());
+    assert!(world.get::<Fxaa>(manual).is_none());
+
+    app.insert_resource(GBufferLayout::Forward);
+    app.update();
+    app.update();
+    let world = app.world();
+    assert!(world.get::<DeferredPrepass>(camera).is_none());
+    assert!(world.get::<DepthPrepass>(camera).is_none());
+    assert!(world.get::<DeferredPrepass>(manual).is_some());
+    assert!(world.get::<Fxaa>(camera).is_none());
+    assert_eq!(*world.get::<Msaa>(camera).unwrap(), Msaa::Sample4);
+    assert_eq!(
+        serde_json::from_str::<GBufferLayout>(r#""Packed""#).unwrap(),
+        GBufferLayout::Deferred
+    );
+}
+
+#[test]
+fn glass_materials_refract_and_export() {
+    let mut app = App::new();
+    app.add_plugins(TransmissionPlugin);
+    let camera = app
+        .world_mut()
+        .spawn(CameraTransmission::new(2).with_quality(TransmissionQuality::High))
+        .id();
+    app.update();
+    let camera = app.world().get::<Camera3d>(camera).unwrap();
+    assert_eq!(camera.screen_space_specular_transmission_steps, 2);
+    assert_eq!(
+        camera.screen_space_specular_transmission_quality,
+        TransmissionQuality::High.into()
+    );
+
+    let mut world = World::new();
+    world.init_resource::<Assets<Mesh>>();
+    world.init_resource::<Assets<StandardMaterial>>();
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(Sphere::new(0.5));
+    let glass = world
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(StandardMaterial {
+            specular_transmission: 0.9,
+            thickness: 0.2,
+            ior: 1.5,
+            attenuation_color: Color::linear_rgb(0.5, 1.0, 0.5),
+            ..default()
+        });
+    world.spawn((Mesh3d(mesh), MeshMaterial3d(glass)));
+
+    let glb = GltfExporter::new().export(&mut world).unwrap();
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    let material = gltf.materials().next().unwrap();
+    assert_eq!(material.transmission().unwrap().transmission_factor(), 0.9);
+    let volume = material.volume().unwrap();
+    assert_eq!(volume.thickness_factor(), 0.2);
+    assert_eq!(volume.attenuation_color(), [0.5, 1.0, 0.5]);
+    assert_eq!(volume.attenuation_distance(), f32::INFINITY);
+    assert_eq!(material.ior(), Some(1.5));
+}
+
+const SHEEN_PBR_STUBS: [(&str, &str); 2] = [
+    (
+        "pbr_types.wgsl",
+        r#"
+#define_import_path bevy_pbr::pbr_types
+
+struct StandardMaterial {
+    base_color: vec4<f32>,
+    emissive: vec4<f32>,
+    reflectance: vec3<f32>,
+    perceptual_roughness: f32,
+    metallic: f32,
+    diffuse_transmission: f32,
+    specular_transmission: f32,
+    clearcoat: f32,
+    flags: u32,
+};
+
+struct PbrInput {
+    material: StandardMaterial,
+    N: vec3<f32>,
+    V: vec3<f32>,
+};
+"#,
+    ),
+    (
+        "forward_io.wgsl",
+        r#"
+#define_import_path bevy_pbr::forward_io
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+#ifdef VERTEX_UVS_A
+    @location(2) uv: vec2<f32>,
+#endif
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+};
+"#,
+    ),
+];
+
+#[test]
+fn sheen_and_clearcoat_materials_load_and_export() {
+    let document = gltf::Gltf::from_slice(
+        br#"{
+            "asset": { "version": "2.0" },
+            "extensionsUsed": ["KHR_materials_sheen"],
+            "textures": [{ "source": 0 }],
+            "images": [{ "uri": "velvet.png" }],
+            "materials": [
+                {
+                    "name": "Velvet",
+                    "extensions": {
+                        "KHR_materials_sheen": {
+                            "sheenColorFactor": [0.8, 0.2, 0.4],
+                            "sheenRoughnessFactor": 0.6,
+                            "sheenRoughnessTexture": { "index": 0 }
+                        }
+                    }
+                },
+                { "name": "Paint" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let mut materials = document.materials();
+    let velvet = SheenExtension::from_gltf_material(&materials.next().unwrap(), |index| {
+        assert_eq!(index, 0);
+        Handle::default()
+    })
+    .unwrap();
+    assert_eq!(velvet.settings.color, Vec3::new(0.8, 0.2, 0.4));
+    assert_eq!(velvet.settings.roughness, 0.6);
+    assert_eq!(
+        (
+            velvet.settings.color_texture,
+            velvet.settings.roughness_texture
+        ),
+        (0, 1)
+    );
+    assert!(
+        SheenExtension::from_gltf_material(&materials.next().unwrap(), |_| { Handle::default() })
+            .is_none()
+    );
+    assert!(GltfInspection::from_document(&document.document)
+        .warnings
+        .is_empty());
+
+    let mut shader = ShaderPermutations::new("sheen.wgsl", include_str!("sheen.wgsl"))
+        .with_shader_def("VERTEX_UVS_A")
+        .with_constant("MATERIAL_BIND_GROUP", 3);
+    for (file_path, source) in SHEEN_PBR_STUBS.iter().chain(&PBR_STUBS[2..]) {
+        shader = shader.with_import(*file_path, *source);
+    }
+    for permutation in shader.compile_all() {
+        permutation.result.unwrap();
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Assets<Mesh>>();
+    world.init_resource::<Assets<StandardMaterial>>();
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(Sphere::new(0.5));
+    let paint = world
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(StandardMaterial {
+            clearcoat: 1.0,
+            clearcoat_perceptual_roughness: 0.1,
+            ..default()
+        });
+    world.spawn((Mesh3d(mesh), MeshMaterial3d(paint)));
+    let glb = GltfExporter::new().export(&mut world).unwrap();
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    let clearcoat = gltf
+        .materials()
+        .next()
+        .unwrap()
+        .extension_value("KHR_materials_clearcoat")
+        .unwrap()
+        .clone();
+    assert_eq!(clearcoat["clearcoatFactor"], 1.0);
+    assert!(gltf
+        .extensions_used()
+        .any(|name| name == "KHR_materials_clearcoat"));
+}
+
+#[test]
+fn anisotropic_materials_get_tangents_and_export() {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        AnisotropyPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>();
+    let (brushed, plastic) = {
+        let mut meshes = app.world_mut().resource_mut::<Assets<Mesh>>();
+        (meshes.add(Sphere::new(0.5)), meshes.add(Sphere::new(0.5)))
+    };
+    let (metal, paint) = {
+        let mut materials = app.world_mut().resource_mut::<Assets<StandardMaterial>>();
+        (
+            materials.add(StandardMaterial {
+                metallic: 1.0,
+                anisotropy_strength: 0.75,
+                anisotropy_rotation: 0.5,
+                ..default()
+            }),
+            materials.add(StandardMaterial::default()),
+        )
+    };
+    app.world_mut()
+        .spawn((Mesh3d(brushed.clone()), MeshMaterial3d(metal)));
+    app.world_mut()
+        .spawn((Mesh3d(plastic.clone()), MeshMaterial3d(paint.clone())));
+    app.update();
+    let has_tangents = |app: &App, mesh: &Handle<Mesh>| {
+        app.world()
+            .resource::<Assets<Mesh>>()
+            .get(mesh)
+            .unwrap()
+            .contains_attribute(Mesh::ATTRIBUTE_TANGENT)
+    };
+    assert!(has_tangents(&app, &brushed));
+    assert!(!has_tangents(&app, &plastic));
+
+    app.world_mut()
+        .resource_mut::<Assets<StandardMaterial>>()
+        .get_mut(&paint)
+        .unwrap()
+        .anisotropy_strength = 0.3;
+    app.update();
+    assert!(has_tangents(&app, &plastic));
+
+    let glb = GltfExporter::new().export(app.world_mut()).unwrap();
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    let anisotropy = gltf
+        .materials()
+        .find_map(|material| {
+            material
+                .extension_value("KHR_materials_anisotropy")
+                .cloned()
+        })
+        .unwrap();
+    assert_eq!(anisotropy["anisotropyStrength"], 0.75);
+    assert_eq!(anisotropy["anisotropyRotation"], 0.5);
+    assert!(GltfInspection::from_document(&gltf.document)
+        .warnings
+        .is_empty());
+}
+
+#[test]
+fn debug_draw_queues_shapes_for_one_frame() {
+    let mut app = App::new();
+    app.add_plugins(DebugDrawPlugin);
+    {
+        let mut debug_draw = app.world_mut().resource_mut::<DebugDraw>();
+        debug_draw.draw_line(Vec3::ZERO, Vec3::X, Color::WHITE);
+        debug_draw.draw_aabb(
+            &Aabb::from_min_max(Vec3::ZERO, Vec3::ONE),
+            &GlobalTransform::from_xyz(0.0, 2.0, 0.0),
+            Color::BLACK,
+        );
+        debug_draw.draw_sphere(Vec3::Y, 0.5, Color::WHITE);
+        debug_draw.draw_axes(Transform::IDENTITY, 0.1);
+        // Reverse Z like Bevy's projections, near and far are swapped
+        let clip_from_world = Mat4::orthographic_rh(-1.0, 1.0, -2.0, 2.0, 10.0, 0.5);
+        debug_draw.draw_frustum(clip_from_world.inverse(), Color::WHITE);
+    }
+    let shapes = app.world().resource::<DebugDraw>().shapes().to_vec();
+    assert_eq!(shapes.len(), 5);
+    let DebugShape::Box { transform, .. } = shapes[1] else {
+        panic!("expected a box");
+    };
+    assert_eq!(transform.translation(), Vec3::new(0.5, 2.5, 0.5));
+    let DebugShape::Frustum { corners, .. } = shapes[4] else {
+        panic!("expected a frustum");
+    };
+    assert!(corners[0].abs_diff_eq(Vec3::new(-1.0, -2.0, -0.5), 1e-4));
+    assert!(corners[6].abs_diff_eq(Vec3::new(1.0, 2.0, -10.0), 1e-4));
+
+    app.update();
+    assert!(app.world().resource::<DebugDraw>().shapes().is_empty());
+}
+
+#[test]
+fn subsurface_scattering_blurs_skin() {
+    let skin = SubsurfaceScattering::default();
+    let kernel = skin.kernel();
+    assert_eq!(kernel[0].w, 0.0);
+    let sum = kernel
+        .iter()
+        .fold(Vec3::ZERO, |sum, tap| sum + tap.truncate());
+    assert!(sum.abs_diff_eq(Vec3::ONE, 1e-5));
+    // Red scatters furthest, so less of it stays at the center
+    assert!(kernel[0].x < kernel[0].y && kernel[0].y < kernel[0].z);
+    let opaque = SubsurfaceScattering {
+        strength: [0.0; 3],
+        ..skin
+    };
+    assert_eq!(opaque.kernel()[0].truncate(), Vec3::ONE);
+
+    let mut app = App::new();
+    app.add_plugins((MultisamplePlugin, DepthPrepassPlugin))
+        .register_required_components::<Camera, Msaa>();
+    let camera = app.world_mut().spawn(SubsurfaceScattering::new(0.01)).id();
+    app.update();
+    assert_eq!(*app.world().get::<Msaa>(camera).unwrap(), Msaa::Off);
+    assert!(app.world().get::<DepthPrepass>(camera).is_some());
+
+    let blur = ShaderPermutations::new("subsurface.wgsl", include_str!("subsurface.wgsl"))
+        .with_shader_def("VERTICAL")
+        .with_import("fullscreen.wgsl", FULLSCREEN_VERTEX_OUTPUT);
+    for permutation in blur.compile_all() {
+        let wgsl = permutation.result.as_ref().unwrap();
+        assert_snapshot(&format!("subsurface.{}.wgsl", permutation.name()), wgsl);
+    }
+    let mut material = ShaderPermutations::new(
+        "subsurface_material.wgsl",
+        include_str!("subsurface_material.wgsl"),
+    )
+    .with_shader_def("VERTEX_UVS_A")
+    .with_constant("MATERIAL_BIND_GROUP", 3);
+    for (file_path, source) in SHEEN_PBR_STUBS.iter().chain(&PBR_STUBS[2..]) {
+        material = material.with_import(*file_path, *source);
+    }
+    for permutation in material.compile_all() {
+        permutation.result.unwrap();
+    }
+}
+
+#[test]
+fn hud_elements_follow_their_layer() {
+    let mut app = App::new();
+    app.add_plugins(HudPlugin);
+    let preview = app
+        .world_mut()
+        .spawn((
+            HudLayer,
+            Camera {
+                order: 1,
+                ..default()
+            },
+        ))
+        .id();
+    let main = app.world_mut().spawn(HudLayer).id();
+    let fps = app
+        .world_mut()
+        .spawn((
+            Text::new("72 fps"),
+            HudElement::new(HudAnchor::TopRight).with_offset(Vec2::new(8.0, 4.0)),
+        ))
+        .id();
+    let hint = app
+        .world_mut()
+        .spawn((
+            HudQuad::new(Vec2::new(64.0, 32.0), Color::BLACK),
+            HudElement::new(HudAnchor::Bottom).with_layer(preview),
+        ))
+        .id();
+    app.update();
+
+    let world = app.world();
+    assert_eq!(world.get::<UiTargetCamera>(fps).unwrap().entity(), main);
+    let node = world.get::<Node>(fps).unwrap();
+    assert_eq!(node.position_type, PositionType::Absolute);
+    assert_eq!((node.right, node.top), (Val::Px(8.0), Val::Px(4.0)));
+    assert_eq!(node.left, Val::Auto);
+
+    assert_eq!(world.get::<UiTargetCamera>(hint).unwrap().entity(), preview);
+    let node = world.get::<Node>(hint).unwrap();
+    assert_eq!((node.left, node.width), (Val::Percent(50.0), Val::Px(64.0)));
+    assert_eq!(
+        world.get::<UiTransform>(hint).unwrap().translation.x,
+        Val::Percent(-50.0)
+    );
+    assert_eq!(world.get::<BackgroundColor>(hint).unwrap().0, Color::BLACK);
+}
+
+#[test]
+fn shadow_bias_applies_to_any_light() {
+    let mut app = App::new();
+    app.add_plugins(ShadowBiasPlugin);
+    let spot = app
+        .world_mut()
+        .spawn((SpotLight::default(), ShadowBias::new(0.01, 0.5)))
+        .id();
+    let sun = app
+        .world_mut()
+        .spawn((DirectionalLight::default(), ShadowBias::new(0.1, 3.0)))
+        .id();
+    app.update();
+    let light = app.world().get::<SpotLight>(spot).unwrap();
+    assert_eq!(
+        (light.shadow_depth_bias, light.shadow_normal_bias),
+        (0.01, 0.5)
+    );
+    let light = app.world().get::<DirectionalLight>(sun).unwrap();
+    assert_eq!(
+        (light.shadow_depth_bias, light.shadow_normal_bias),
+        (0.1, 3.0)
+    );
+
+    app.world_mut()
+        .entity_mut(sun)
+        .insert(ShadowBias::directional_light());
+    app.update();
+    let light = app.world().get::<DirectionalLight>(sun).unwrap();
+    assert_eq!(
+        light.shadow_normal_bias,
+        DirectionalLight::DEFAULT_SHADOW_NORMAL_BIAS
+    );
+}
+
+#[test]
+fn raycast_hits_nearest_spawned_mesh() {
+    let mut world = World::new();
+    let mut meshes = Assets::<Mesh>::default();
+    let cube = meshes.add(Cuboid::from_length(1.0));
+    let sphere = meshes.add(Sphere::new(0.5).mesh().ico(3).unwrap());
+    let spawn_mesh = |world: &mut World, mesh: &Handle<Mesh>, translation: Vec3| {
+        let aabb = meshes.get(mesh).unwrap().compute_aabb().unwrap();
+        let transform = Transform::from_translation(translation);
+        world
+            .spawn((
+                Mesh3d(mesh.clone()),
+                aabb,
+                transform,
+                GlobalTransform::from(transform),
+            ))
+            .id()
+    };
+    let box_entity = spawn_mesh(&mut world, &cube, Vec3::new(0.0, 0.0, -5.0));
+    let ignored = spawn_mesh(&mut world, &cube, Vec3::new(0.0, 0.0, -2.0));
+    world.entity_mut(ignored).insert(Pickable::IGNORE);
+    let ball = spawn_mesh(&mut world, &sphere, Vec3::new(3.0, 0.0, -5.0));
+    let scene = world.spawn(SceneRoot::default()).add_child(ball).id();
+    world.insert_resource(meshes);
+
+    let hit = world.raycast(Vec3::ZERO, Vec3::NEG_Z).unwrap();
+    assert_eq!((hit.spawned, hit.entity), (box_entity, box_entity));
+    assert!((hit.distance - 4.5).abs() < 1e-5);
+    assert!(hit.point.abs_diff_eq(Vec3::new(0.0, 0.0, -4.5), 1e-5));
+    assert!(hit.normal.abs_diff_eq(Vec3::Z, 1e-5));
+    assert!(world
+        .raycast_with(
+            Vec3::ZERO,
+            Vec3::NEG_Z,
+            &RaycastSettings::default().with_max_distance(4.0)
+        )
+        .is_none());
+
+    // The corner of the bounding box is outside of the sphere
+    let corner = Vec3::new(3.45, 0.45, 0.0);
+    let hit = world.raycast(corner, Vec3::NEG_Z).unwrap();
+    assert_eq!((hit.spawned, hit.entity), (scene, ball));
+    let triangles = RaycastSettings::default().with_triangles(true);
+    assert!(world
+        .raycast_with(corner, Vec3::NEG_Z, &triangles)
+        .is_none());
+    let hit = world
+        .raycast_with(Vec3::new(3.0, 0.0, 0.0), Vec3::NEG_Z, &triangles)
+        .unwrap();
+    assert_eq!(hit.spawned, scene);
+    assert!((hit.distance - 4.5).abs() < 0.01);
+    assert!(hit.normal.abs_diff_eq(Vec3::Z, 0.05));
+}
+
+#[test]
+fn light_probes_gather_bounce_light() {
+    let mut app = App::new();
+    app.add_plugins(LightProbePlugin)
+        .init_resource::<Assets<Image>>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<StandardMaterial>>();
+    let floor = Cuboid::new(10.0, 0.2, 10.0).mesh().build();
+    let aabb = floor.compute_aabb().unwrap();
+    let floor = app.world_mut().resource_mut::<Assets<Mesh>>().add(floor);
+    let red = app
+        .world_mut()
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(Color::linear_rgb(1.0, 0.0, 0.0));
+    app.world_mut().spawn((
+        Mesh3d(floor),
+        MeshMaterial3d(red),
+        aabb,
+        GlobalTransform::from_xyz(0.0, -1.0, 0.0),
+    ));
+    // Shining straight down
+    app.world_mut().spawn((
+        DirectionalLight::default(),
+        GlobalTransform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+    ));
+    let volume = app
+        .world_mut()
+        .spawn(LightProbeVolume::progressive(UVec3::ONE))
+        .id();
+    let baked = Handle::<Image>::default();
+    let baked_volume = app
+        .world_mut()
+        .spawn(LightProbeVolume::baked(baked.clone()).with_intensity(2.0))
+        .id();
+
+    app.update();
+    app.update();
+    let irradiance = app.world().get::<IrradianceVolume>(baked_volume).unwrap();
+    assert_eq!(
+        (irradiance.voxels.id(), irradiance.intensity),
+        (baked.id(), 2.0)
+    );
+    assert!(!irradiance.affects_lightmapped_meshes);
+
+    let voxels = &app.world().get::<IrradianceVolume>(volume).unwrap().voxels;
+    let image = app.world().resource::<Assets<Image>>().get(voxels).unwrap();
+    assert_eq!(image.texture_descriptor.size.height, 2);
+    assert_eq!(image.texture_descriptor.size.depth_or_array_layers, 3);
+    // Red light bounces up from the floor, nothing comes from above
+    let below = image.get_color_at_3d(0, 0, 1).unwrap().to_linear();
+    let above = image.get_color_at_3d(0, 1, 1).unwrap().to_linear();
+    assert!(below.red > 100.0);
+    assert_eq!((below.green, below.blue), (0.0, 0.0));
+    assert_eq!(above.red, 0.0);
+}
+
+#[test]
+fn dynamic_gi_shader_compiles_and_falls_back() {
+    let shader = ShaderPermutations::new("ddgi.wgsl", include_str!("ddgi.wgsl"));
+    let wgsl = shader.compile(&[]).result.unwrap();
+    assert_snapshot("ddgi.default.wgsl", &wgsl);
+
+    let mut app = App::new();
+    app.add_plugins(LightProbePlugin)
+        .init_resource::<Assets<Image>>()
+        .insert_resource(AmbientLight {
+            brightness: 100.0,
+            ..default()
+        });
+    app.world_mut()
+        .resource_mut::<LightProbeSettings>()
+        .global_illumination = GlobalIllumination::Dynamic;
+    let volume = app
+        .world_mut()
+        .spawn(LightProbeVolume::progressive(UVec3::ONE))
+        .id();
+    app.update();
+    app.update();
+
+    // Without a renderer the compute shaders never run, so only the CPU
+    // fallback fills the probes
+    let voxels = &app.world().get::<IrradianceVolume>(volume).unwrap().voxels;
+    let image = app.world().resource::<Assets<Image>>().get(voxels).unwrap();
+    let ambient = image.get_color_at_3d(0, 0, 0).unwrap().to_linear();
+    assert_eq!(ambient.red > 0.0, !cfg!(feature = "ddgi"));
+}
+
+#[test]
+fn screenshots_and_gbuffer_channels_are_written() {
+    // Red base color with roughness 0.5, emissive 1.0, metallic 1.0 and a
+    // normal facing +Z, as Bevy's deferred prepass packs them
+    let pixel = [
+        0x80_00_00_ffu32,
+        (16 << 27) | (256 << 18) | (256 << 9) | 256,
+        0x00_00_ff_00,
+        (2048 << 12) | 2048,
+    ];
+    let data: Vec<u8> = pixel.iter().flat_map(|word| word.to_le_bytes()).collect();
+    let size = UVec2::ONE;
+    let decode = |channel: GBufferChannel| channel.decode(&data, size).unwrap();
+    assert_eq!(
+        decode(GBufferChannel::BaseColor).to_rgb8().into_raw(),
+        [255, 0, 0]
+    );
+    assert_eq!(
+        decode(GBufferChannel::PerceptualRoughness)
+            .to_luma8()
+            .into_raw(),
+        [128]
+    );
+    assert_eq!(
+        decode(GBufferChannel::Metallic).to_luma8().into_raw(),
+        [255]
+    );
+    assert_eq!(
+        decode(GBufferChannel::Emissive).to_rgb32f().into_raw(),
+        [1.0; 3]
+    );
+    let normal = decode(GBufferChannel::Normal).to_rgb32f().into_raw();
+    assert!(Vec3::from_slice(&normal).abs_diff_eq(Vec3::new(0.5, 0.5, 1.0), 1e-3));
+    assert!(GBufferChannel::Normal.decode(&data[..8], size).is_none());
+    assert_eq!(
+        GBufferChannel::Normal.path(std::path::Path::new("shots/robot.exr")),
+        std::path::Path::new("shots/robot.normal.exr")
+    );
+
+    let mut app = App::new();
+    app.add_plugins((TaskPoolPlugin::default(), ScreenshotCapturePlugin));
+    let camera = app.world_mut().spawn(Camera::default()).id();
+    let path = std::env::temp_dir().join(format!("xrds-screenshot-{}.png", std::process::id()));
+    app.world_mut()
+        .write_message(CaptureScreenshot::new(camera, &path).with_gbuffer());
+    app.world_mut()
+        .write_message(CaptureScreenshot::new(Entity::PLACEHOLDER, &path));
+    app.update();
+
+    // The camera has no deferred prepass, so only the color target is captured
+    let saved = app
+        .world_mut()
+        .resource_mut::<Messages<ScreenshotSaved>>()
+        .drain()
+        .collect::<Vec<_>>();
+    assert_eq!(saved.len(), 2);
+    assert!(saved.iter().all(|saved| saved.result.is_err()));
+    let screenshot = app
+        .world_mut()
+        .query_filtered::<Entity, With<bevy::render::view::screenshot::Screenshot>>()
+        .single(app.world())
+        .unwrap();
+    let image = Image::new(
+        bevy::render::render_resource::Extent3d {
+            width: 2,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        bevy::render::render_resource::TextureDimension::D2,
+        vec![255, 0, 0, 255, 0, 0, 255, 255],
+        bevy::render::render_resource::TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD,
+    );
+    app.world_mut()
+        .trigger(bevy::render::view::screenshot::ScreenshotCaptured {
+            entity: screenshot,
+            image,
+        });
+
+    // Encoding runs on the IO task pool
+    let mut saved = None;
+    for _ in 0..100 {
+        app.update();
+        saved = app
+            .world_mut()
+            .resource_mut::<Messages<ScreenshotSaved>>()
+            .drain()
+            .next();
+        if saved.is_some() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let saved = saved.unwrap();
+    assert_eq!((saved.camera, saved.gbuffer), (camera, false));
+    assert_eq!(saved.result, Ok(()));
+    let written = image::open(&path).unwrap().to_rgb8();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written.into_raw(), [255, 0, 0, 0, 0, 255]);
+}
+
+#[test]
+fn gpu_query_results_parse_readback() {
+    let bytes = |values: &[u64]| {
+        values
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>()
+    };
+
+    let hidden = GpuQueryKind::Occlusion.parse(&bytes(&[0]));
+    assert_eq!(hidden, GpuQueryResult::Occlusion { samples: 0 });
+    assert_eq!(hidden.is_visible(), Some(false));
+    let visible = GpuQueryKind::Occlusion.parse(&bytes(&[12]));
+    assert_eq!(visible.is_visible(), Some(true));
+
+    let statistics = GpuQueryKind::PipelineStatistics.parse(&bytes(&[30, 10, 12, 400]));
+    let GpuQueryResult::PipelineStatistics(statistics) = statistics else {
+        panic!("expected pipeline statistics, got {:?}", statistics);
+    };
+    assert_eq!(statistics.vertex_shader_invocations, 30);
+    assert_eq!(statistics.clipper_invocations, 10);
+    assert_eq!(statistics.clipper_primitives_out, 12);
+    assert_eq!(statistics.fragment_shader_invocations, 400);
+    assert_eq!(
+        GpuQueryResult::PipelineStatistics(statistics).is_visible(),
+        None
+    );
+}
+
+#[test]
+fn gpu_query_results_keep_latest_by_label() {
+    let (results, sender) = GpuQueryResults::new();
+    let mut app = App::new();
+    app.insert_resource(results)
+        .add_systems(Update, receive_gpu_query_results);
+
+    app.update();
+    assert!(app
+        .world()
+        .resource::<GpuQueryResults>()
+        .get("door")
+        .is_none());
+
+    let occlusion = |samples| GpuQueryResult::Occlusion { samples };
+    sender.send(("door".to_owned(), occlusion(40))).unwrap();
+    sender.send(("wall".to_owned(), occlusion(3))).unwrap();
+    // A later frame of the same query
+    sender.send(("door".to_owned(), occlusion(0))).unwrap();
+    sender
+        .send((
+            "scene".to_owned(),
+            GpuQueryResult::PipelineStatistics(Default::default()),
+        ))
+        .unwrap();
+    app.update();
+
+    let results = app.world().resource::<GpuQueryResults>();
+    assert_eq!(results.is_visible("door"), Some(false));
+    assert_eq!(results.is_visible("wall"), Some(true));
+    assert_eq!(results.is_visible("scene"), None);
+    assert_eq!(results.is_visible("missing"), None);
+    let mut labels: Vec<_> = results.iter().map(|(label, _)| label).collect();
+    labels.sort();
+    assert_eq!(labels, ["door", "scene", "wall"]);
+}
+
+#[test]
+fn occlusion_query_hides_occluded_meshes() {
+    let (results, sender) = GpuQueryResults::new();
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        OcclusionQueryPlugin,
+    ))
+    .insert_resource(results)
+    .add_systems(First, receive_gpu_query_results);
+
+    let left_eye = app.world_mut().spawn_empty().id();
+    let right_eye = app.world_mut().spawn_empty().id();
+    app.world_mut().spawn(Camera3d::default());
+    let mut spawn_mesh = |translation: Vec3| {
+        let mut view_visibility = ViewVisibility::default();
+        view_visibility.set();
+        app.world_mut()
+            .spawn((
+                OcclusionQuery,
+                Aabb::from_min_max(Vec3::splat(-1.0), Vec3::splat(1.0)),
+                GlobalTransform::from_translation(translation),
+                view_visibility,
+            ))
+            .id()
+    };
+    let behind_wall = spawn_mesh(Vec3::new(0.0, 0.0, -10.0));
+    let around_corner = spawn_mesh(Vec3::new(5.0, 0.0, -10.0));
+    let around_camera = spawn_mesh(Vec3::ZERO);
+
+    let mut send = |entity, view, samples| {
+        sender
+            .send((
+                occlusion_query_label(entity, view),
+                GpuQueryResult::Occlusion { samples },
+            ))
+            .unwrap();
+    };
+    send(behind_wall, left_eye, 0);
+    send(behind_wall, right_eye, 0);
+    // Seen by one eye only
+    send(around_corner, left_eye, 0);
+    send(around_corner, right_eye, 8);
+    // The box around the camera is clipped by the near plane
+    send(around_camera, left_eye, 0);
+    send(around_camera, right_eye, 0);
+    app.update();
+
+    let is_visible = |entity| app.world().get::<ViewVisibility>(entity).unwrap().get();
+    assert!(!is_visible(behind_wall));
+    assert!(is_visible(around_corner));
+    assert!(is_visible(around_camera));
+}
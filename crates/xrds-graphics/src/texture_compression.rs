@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use bevy::{asset::RenderAssetUsages, image::CompressedImageFormatSupport, prelude::*};
+#[cfg(feature = "basis-universal")]
+use bevy::{
+    image::{get_transcoded_formats, CompressedImageFormats, DataFormat},
+    render::render_resource::TextureFormat,
+};
+
+/// Texture memory of the loaded images, including their mipmap chains
+///
+/// KTX2 files are loaded with the asset server like any other image. Block
+/// compressed formats (BCn, ETC2, ASTC) are uploaded as they are. With the
+/// `basis-universal` feature, Basis Universal (UASTC and ETC1S) textures are
+/// transcoded to a compressed format of the GPU, see `transcode_target`.
+/// Mip levels stored in the file are kept, so compressed textures need no
+/// mipmap generation at load.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextureMemory {
+    /// Bytes of block compressed textures
+    pub compressed: usize,
+    pub uncompressed: usize,
+    pub compressed_textures: usize,
+    pub uncompressed_textures: usize,
+    sizes: HashMap<AssetId<Image>, TrackedTexture>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TrackedTexture {
+    size: usize,
+    compressed: bool,
+    /// Stays on the GPU after it left `Assets`
+    render_world_only: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct TextureCompressionPlugin;
+
+impl TextureMemory {
+    pub fn total(&self) -> usize {
+        self.compressed + self.uncompressed
+    }
+
+    fn insert(&mut self, id: AssetId<Image>, texture: TrackedTexture) {
+        self.remove(id);
+        self.sizes.insert(id, texture);
+        if texture.compressed {
+            self.compressed += texture.size;
+            self.compressed_textures += 1;
+        } else {
+            self.uncompressed += texture.size;
+            self.uncompressed_textures += 1;
+        }
+    }
+
+    fn remove(&mut self, id: AssetId<Image>) {
+        match self.sizes.remove(&id) {
+            Some(texture) if texture.compressed => {
+                self.compressed -= texture.size;
+                self.compressed_textures -= 1;
+            }
+            Some(texture) => {
+                self.uncompressed -= texture.size;
+                self.uncompressed_textures -= 1;
+            }
+            None => {}
+        }
+    }
+}
+
+impl Plugin for TextureCompressionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextureMemory>()
+            .add_systems(
+                Startup,
+                log_texture_compression.run_if(resource_exists::<CompressedImageFormatSupport>),
+            )
+            .add_systems(
+                PostUpdate,
+                track_texture_memory.run_if(resource_exists::<Assets<Image>>),
+            );
+    }
+}
+
+/// Format Basis Universal color textures are transcoded to on a GPU
+/// supporting `formats`, as chosen by Bevy's KTX2 loader
+///
+/// ASTC is preferred over BC7, then ETC2, and GPUs without compressed formats
+/// get uncompressed RGBA.
+#[cfg(feature = "basis-universal")]
+pub fn transcode_target(formats: CompressedImageFormats, srgb: bool) -> TextureFormat {
+    get_transcoded_formats(formats, DataFormat::Rgba, srgb).1
+}
+
+fn log_texture_compression(support: Res<CompressedImageFormatSupport>) {
+    #[cfg(feature = "basis-universal")]
+    info!(
+        "Compressed texture formats: {:?}, Basis Universal textures are transcoded to {:?}",
+        support.0,
+        transcode_target(support.0, true)
+    );
+    #[cfg(not(feature = "basis-universal"))]
+    info!("Compressed texture formats: {:?}", support.0);
+}
+
+fn track_texture_memory(
+    mut events: MessageReader<AssetEvent<Image>>,
+    images: Res<Assets<Image>>,
+    mut memory: ResMut<TextureMemory>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                let Some(image) = images.get(*id) else {
+                    continue;
+                };
+                let texture = TrackedTexture {
+                    size: image.data.as_ref().map_or(0, Vec::len),
+                    compressed: image.is_compressed(),
+                    render_world_only: image.asset_usage == RenderAssetUsages::RENDER_WORLD,
+                };
+                memory.insert(*id, texture);
+            }
+            // Images only used on the GPU leave `Assets` once uploaded, but stay
+            // in memory until the last handle is dropped
+            AssetEvent::Removed { id } => {
+                if memory
+                    .sizes
+                    .get(id)
+                    .is_some_and(|texture| !texture.render_world_only)
+                {
+                    memory.remove(*id);
+                }
+            }
+            AssetEvent::Unused { id } => memory.remove(*id),
+            AssetEvent::LoadedWithDependencies { .. } => {}
+        }
+    }
+}
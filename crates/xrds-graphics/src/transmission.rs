@@ -0,0 +1,93 @@
+use bevy::{camera::ScreenSpaceTransmissionQuality, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Blur taps used for rough glass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransmissionQuality {
+    /// 4 taps
+    Low,
+    /// 8 taps
+    #[default]
+    Medium,
+    /// 16 taps
+    High,
+    /// 32 taps
+    Ultra,
+}
+
+/// Refraction through transmissive materials seen by a camera
+///
+/// Materials with `specular_transmission`, e.g. glTF glass using
+/// `KHR_materials_transmission`, are drawn in a forward pass after the
+/// opaque and alpha masked objects. They refract a copy of what was rendered
+/// behind them, blurred by their roughness and tinted by the thickness and
+/// attenuation of `KHR_materials_volume`. Each step copies the view target
+/// again, so glass behind glass needs 2 steps.
+///
+/// With 0 steps only the environment map is refracted, which is cheaper.
+/// An opaque clear color hides the environment map behind glass, use a
+/// transparent one to keep it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[require(Camera3d)]
+pub struct CameraTransmission {
+    pub steps: usize,
+    pub quality: TransmissionQuality,
+}
+
+#[derive(Debug, Default)]
+pub struct TransmissionPlugin;
+
+impl Default for CameraTransmission {
+    fn default() -> Self {
+        Self {
+            steps: 1,
+            quality: TransmissionQuality::default(),
+        }
+    }
+}
+
+impl CameraTransmission {
+    pub fn new(steps: usize) -> Self {
+        Self {
+            steps,
+            ..Default::default()
+        }
+    }
+
+    /// Refracts only the environment map
+    pub fn environment_only() -> Self {
+        Self::new(0)
+    }
+
+    pub fn with_quality(mut self, quality: TransmissionQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+}
+
+impl From<TransmissionQuality> for ScreenSpaceTransmissionQuality {
+    fn from(quality: TransmissionQuality) -> Self {
+        match quality {
+            TransmissionQuality::Low => Self::Low,
+            TransmissionQuality::Medium => Self::Medium,
+            TransmissionQuality::High => Self::High,
+            TransmissionQuality::Ultra => Self::Ultra,
+        }
+    }
+}
+
+impl Plugin for TransmissionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, update_camera_transmission);
+    }
+}
+
+fn update_camera_transmission(
+    mut cameras: Query<(&CameraTransmission, &mut Camera3d), Changed<CameraTransmission>>,
+) {
+    for (transmission, mut camera) in cameras.iter_mut() {
+        camera.screen_space_specular_transmission_steps = transmission.steps;
+        camera.screen_space_specular_transmission_quality = transmission.quality.into();
+    }
+}
@@ -0,0 +1,358 @@
+use bevy::{
+    asset::{embedded_asset, load_embedded_asset},
+    camera::MainPassResolutionOverride,
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        FullscreenShader,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        sync_world::RenderEntity,
+        view::ViewTarget,
+        Extract, ExtractSchedule, RenderApp, RenderStartup,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// Lowest supported render scale, lower values are clamped
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+
+/// Filter used to upscale the main pass to the viewport
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum UpscaleFilter {
+    Bilinear,
+    /// Bilinear followed by contrast adaptive sharpening like FSR1's RCAS
+    #[default]
+    Sharpened,
+    /// Bilinear that does not blur across edges, followed by the sharpening
+    /// of `Sharpened`. Meant for large scale factors like half resolution
+    EdgeAware,
+}
+
+/// Resolution of the main pass of 3D cameras
+///
+/// Below a scale of 1 the opaque, transparent and prepass passes render into
+/// a smaller area of the view target, which is upscaled to the full viewport
+/// before bloom, tonemapping and the other post-processing. Every camera is
+/// scaled from its own viewport, so each eye of an XR headset is upscaled to
+/// its own swapchain image.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct RenderScale {
+    /// Fraction of the viewport size rendered, 1 renders at native resolution
+    pub scale: f32,
+    /// Multiplier set by quality controllers on top of `scale`
+    pub dynamic_scale: f32,
+    pub filter: UpscaleFilter,
+    /// Strength of [`UpscaleFilter::Sharpened`] from 0 to 1
+    pub sharpness: f32,
+}
+
+/// Performance mode for mobile XR that lights fewer pixels
+///
+/// Materials are lit in the main pass, so with `lighting` the main pass of
+/// 3D cameras renders at half the width and height of the viewport, or lower
+/// if the [`RenderScale`] is lower, and is upscaled with
+/// [`UpscaleFilter::EdgeAware`] before bloom and tonemapping. Bevy's ambient
+/// occlusion has no resolution of its own, so `ambient_occlusion` lowers it
+/// to [`AmbientOcclusionQuality::Low`](crate::AmbientOcclusionQuality::Low)
+/// for a similar saving.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HalfResolution {
+    pub lighting: bool,
+    pub ambient_occlusion: bool,
+}
+
+#[derive(Component, Clone, Copy, ShaderType)]
+pub struct UpscalingUniform {
+    viewport: Vec4,
+    input_size: Vec2,
+    sharpness: f32,
+    mode: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct UpscalingPlugin;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct UpscalingLabel;
+
+#[derive(Default)]
+struct UpscalingNode;
+
+/// Upscales a view with [`UpscaleFilter::EdgeAware`]
+#[derive(Component, Clone, Copy)]
+struct EdgeAwareUpscaling;
+
+#[derive(Resource)]
+struct UpscalingPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    /// LDR and HDR, by bilinear and edge aware filtering
+    pipeline_ids: [[CachedRenderPipelineId; 2]; 2],
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            dynamic_scale: 1.0,
+            filter: UpscaleFilter::default(),
+            sharpness: 0.5,
+        }
+    }
+}
+
+impl RenderScale {
+    pub fn new(scale: f32) -> Self {
+        Self {
+            scale,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_filter(mut self, filter: UpscaleFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_sharpness(mut self, sharpness: f32) -> Self {
+        self.sharpness = sharpness;
+        self
+    }
+
+    /// Scale in effect, from [`MIN_RENDER_SCALE`] to 1
+    pub fn effective_scale(&self) -> f32 {
+        let scale = self.scale * self.dynamic_scale;
+        if scale.is_nan() {
+            return 1.0;
+        }
+        scale.clamp(MIN_RENDER_SCALE, 1.0)
+    }
+
+    /// Scale of the main pass with [`HalfResolution::lighting`]
+    pub fn half_resolution(&self) -> Self {
+        Self {
+            scale: self.effective_scale().min(0.5),
+            dynamic_scale: 1.0,
+            filter: UpscaleFilter::EdgeAware,
+            ..*self
+        }
+    }
+
+    /// Size the main pass of a viewport is rendered at, `None` at native resolution
+    pub fn main_pass_size(&self, viewport: UVec2) -> Option<UVec2> {
+        let size = (viewport.as_vec2() * self.effective_scale())
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE)
+            .min(viewport);
+        (size != viewport).then_some(size)
+    }
+}
+
+impl Plugin for UpscalingPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "upscaling.wgsl");
+
+        app.init_resource::<RenderScale>()
+            .init_resource::<HalfResolution>()
+            .add_plugins(UniformComponentPlugin::<UpscalingUniform>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_systems(ExtractSchedule, extract_render_scale)
+            .add_systems(RenderStartup, init_upscaling_pipeline)
+            .add_render_graph_node::<ViewNodeRunner<UpscalingNode>>(Core3d, UpscalingLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::EndMainPass,
+                    UpscalingLabel,
+                    Node3d::StartMainPassPostProcessing,
+                ),
+            );
+    }
+}
+
+fn extract_render_scale(
+    mut commands: Commands,
+    render_scale: Extract<Res<RenderScale>>,
+    half_resolution: Extract<Option<Res<HalfResolution>>>,
+    cameras: Extract<Query<(RenderEntity, &Camera), With<Camera3d>>>,
+) {
+    let render_scale = match half_resolution.as_deref() {
+        Some(HalfResolution { lighting: true, .. }) => render_scale.half_resolution(),
+        _ => **render_scale,
+    };
+    for (entity, camera) in cameras.iter() {
+        let Ok(mut entity) = commands.get_entity(entity) else {
+            continue;
+        };
+        let size = camera.physical_viewport_size();
+        let Some((viewport, input_size)) =
+            size.and_then(|size| Some((size, render_scale.main_pass_size(size)?)))
+        else {
+            entity.remove::<(
+                MainPassResolutionOverride,
+                UpscalingUniform,
+                EdgeAwareUpscaling,
+            )>();
+            continue;
+        };
+        let position = camera
+            .viewport
+            .as_ref()
+            .map_or(UVec2::ZERO, |viewport| viewport.physical_position);
+        entity.insert((
+            MainPassResolutionOverride(input_size),
+            UpscalingUniform {
+                viewport: position.extend(viewport.x).extend(viewport.y).as_vec4(),
+                input_size: input_size.as_vec2(),
+                sharpness: render_scale.sharpness.clamp(0.0, 1.0),
+                mode: match render_scale.filter {
+                    UpscaleFilter::Bilinear => 0,
+                    UpscaleFilter::Sharpened | UpscaleFilter::EdgeAware => 1,
+                },
+            },
+        ));
+        if render_scale.filter == UpscaleFilter::EdgeAware {
+            entity.insert(EdgeAwareUpscaling);
+        } else {
+            entity.remove::<EdgeAwareUpscaling>();
+        }
+    }
+}
+
+impl ViewNode for UpscalingNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<UpscalingUniform>,
+        Has<EdgeAwareUpscaling>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, uniform_index, edge_aware): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let upscaling_pipeline = world.resource::<UpscalingPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        let pipeline_id =
+            upscaling_pipeline.pipeline_ids[view_target.is_hdr() as usize][edge_aware as usize];
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+
+        let uniforms = world.resource::<ComponentUniforms<UpscalingUniform>>();
+        let Some(uniform_binding) = uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "upscaling_bind_group",
+            &upscaling_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &upscaling_pipeline.sampler,
+                uniform_binding.clone(),
+            )),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("upscaling_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+fn init_upscaling_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    fullscreen_shader: Res<FullscreenShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "upscaling_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                uniform_buffer::<UpscalingUniform>(true),
+            ),
+        ),
+    );
+    let sampler = render_device.create_sampler(&SamplerDescriptor {
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    });
+    let shader = load_embedded_asset!(asset_server.as_ref(), "upscaling.wgsl");
+
+    let queue_pipeline = |format: TextureFormat, edge_aware: bool| {
+        let shader_defs = if edge_aware {
+            vec!["EDGE_AWARE".into()]
+        } else {
+            Vec::new()
+        };
+        pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("upscaling_pipeline".into()),
+            layout: vec![layout.clone()],
+            vertex: fullscreen_shader.to_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: shader.clone(),
+                shader_defs,
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    };
+    let pipeline_ids = [
+        TextureFormat::bevy_default(),
+        ViewTarget::TEXTURE_FORMAT_HDR,
+    ]
+    .map(|format| [queue_pipeline(format, false), queue_pipeline(format, true)]);
+
+    commands.insert_resource(UpscalingPipeline {
+        layout,
+        sampler,
+        pipeline_ids,
+    });
+}
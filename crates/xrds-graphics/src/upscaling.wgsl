@@ -0,0 +1,102 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct UpscalingUniform {
+    // Position and size of the camera viewport in physical pixels
+    viewport: vec4<f32>,
+    // Size the main pass was rendered at, in the top left of the viewport
+    input_size: vec2<f32>,
+    sharpness: f32,
+    mode: u32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: UpscalingUniform;
+
+const MODE_SHARPENED: u32 = 1u;
+// Strongest negative lobe of the sharpening kernel, from FSR1's RCAS
+const RCAS_LIMIT: f32 = 0.1875;
+
+// Bilinear sample at a position in source pixels, clamped to the rendered area
+fn sample_source(position: vec2<f32>) -> vec4<f32> {
+    let min_position = settings.viewport.xy + 0.5;
+    let max_position = settings.viewport.xy + settings.input_size - 0.5;
+    let uv = clamp(position, min_position, max_position) / vec2<f32>(textureDimensions(screen_texture));
+    return textureSampleLevel(screen_texture, texture_sampler, uv, 0.0);
+}
+
+fn max3(value: vec3<f32>) -> f32 {
+    return max(value.x, max(value.y, value.z));
+}
+
+// Robust contrast adaptive sharpening: a cross shaped kernel whose negative
+// lobe is limited so the result stays within the neighborhood
+fn sharpen(position: vec2<f32>, center: vec4<f32>) -> vec4<f32> {
+    let n = sample_source(position + vec2<f32>(0.0, -1.0)).rgb;
+    let w = sample_source(position + vec2<f32>(-1.0, 0.0)).rgb;
+    let e = sample_source(position + vec2<f32>(1.0, 0.0)).rgb;
+    let s = sample_source(position + vec2<f32>(0.0, 1.0)).rgb;
+
+    // The limits assume colors from 0 to 1, HDR colors are clamped for them
+    let c = clamp(center.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    let cn = clamp(n, vec3<f32>(0.0), vec3<f32>(1.0));
+    let cw = clamp(w, vec3<f32>(0.0), vec3<f32>(1.0));
+    let ce = clamp(e, vec3<f32>(0.0), vec3<f32>(1.0));
+    let cs = clamp(s, vec3<f32>(0.0), vec3<f32>(1.0));
+    let min_color = min(min(cn, cw), min(ce, min(cs, c)));
+    let max_color = max(max(cn, cw), max(ce, max(cs, c)));
+    let hit_min = min_color / (4.0 * max_color + 1e-5);
+    let hit_max = (1.0 - max_color) / (4.0 * min_color - 4.0 - 1e-5);
+    let lobe_rgb = max(-hit_min, hit_max);
+    let lobe = max(-RCAS_LIMIT, min(max3(lobe_rgb), 0.0)) * settings.sharpness;
+
+    let color = (lobe * (n + w + e + s) + center.rgb) / (4.0 * lobe + 1.0);
+    return vec4<f32>(max(color, vec3<f32>(0.0)), center.a);
+}
+
+#ifdef EDGE_AWARE
+// Relative luminance difference at which a texel counts half
+const EDGE_THRESHOLD: f32 = 0.125;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Bilinear interpolation whose weights fall off for texels unlike the nearest
+// one, so edges of a half resolution image stay sharp instead of blurring
+fn edge_aware(position: vec2<f32>) -> vec4<f32> {
+    let base = floor(position - 0.5) + 0.5;
+    let f = position - base;
+    let a = sample_source(base);
+    let b = sample_source(base + vec2<f32>(1.0, 0.0));
+    let c = sample_source(base + vec2<f32>(0.0, 1.0));
+    let d = sample_source(base + vec2<f32>(1.0, 1.0));
+
+    let nearest = select(select(a, b, f.x >= 0.5), select(c, d, f.x >= 0.5), f.y >= 0.5);
+    let reference = luminance(nearest.rgb);
+    let lumas = vec4<f32>(luminance(a.rgb), luminance(b.rgb), luminance(c.rgb), luminance(d.rgb));
+    let difference = abs(lumas - reference) / (max(lumas, vec4<f32>(reference)) + 1e-3);
+    let bilinear = vec4<f32>((1.0 - f.x) * (1.0 - f.y), f.x * (1.0 - f.y), (1.0 - f.x) * f.y, f.x * f.y);
+    let weights = bilinear / (1.0 + difference / EDGE_THRESHOLD);
+    return (a * weights.x + b * weights.y + c * weights.z + d * weights.w) / dot(weights, vec4<f32>(1.0));
+}
+#endif
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let local = in.position.xy - settings.viewport.xy;
+    if any(local < vec2<f32>(0.0)) || any(local >= settings.viewport.zw) {
+        return textureSampleLevel(screen_texture, texture_sampler, in.uv, 0.0);
+    }
+
+    let position = settings.viewport.xy + local * settings.input_size / settings.viewport.zw;
+#ifdef EDGE_AWARE
+    let color = edge_aware(position);
+#else
+    let color = sample_source(position);
+#endif
+    if settings.mode == MODE_SHARPENED && settings.sharpness > 0.0 {
+        return sharpen(position, color);
+    }
+    return color;
+}
@@ -0,0 +1,90 @@
+use bevy::{
+    camera::{CameraUpdateSystems, Viewport},
+    prelude::*,
+};
+
+/// Renders a camera into a sub-rectangle of its target
+///
+/// The rectangle is given in normalized target coordinates with (0, 0) at the
+/// top-left corner, so it follows window and swapchain resizes. Rasterization
+/// is limited to the rectangle.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(Camera)]
+pub struct CameraViewport {
+    pub rect: Rect,
+}
+
+/// Keeps `Camera::viewport` in sync with [`CameraViewport`]
+#[derive(Debug, Default)]
+pub struct CameraViewportPlugin;
+
+impl Default for CameraViewport {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl CameraViewport {
+    pub const FULL: Self = Self {
+        rect: Rect {
+            min: Vec2::ZERO,
+            max: Vec2::ONE,
+        },
+    };
+
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self {
+            rect: Rect::from_corners(min, max),
+        }
+    }
+
+    /// Viewport `index` of `columns` x `rows` equally sized cells, for split-screen views
+    pub fn split(columns: u32, rows: u32, index: u32) -> Self {
+        let cells = UVec2::new(columns.max(1), rows.max(1));
+        let cell = UVec2::new(index % cells.x, (index / cells.x).min(cells.y - 1));
+        let size = Vec2::ONE / cells.as_vec2();
+        Self::new(cell.as_vec2() * size, (cell + 1).as_vec2() * size)
+    }
+
+    /// Physical viewport in a target of `target_size` pixels. At least one pixel is covered
+    pub fn to_viewport(&self, target_size: UVec2) -> Viewport {
+        let target = target_size.max(UVec2::ONE);
+        let min = self.rect.min.clamp(Vec2::ZERO, Vec2::ONE);
+        let max = self.rect.max.clamp(Vec2::ZERO, Vec2::ONE);
+        let position = (min * target.as_vec2()).round().as_uvec2().min(target - 1);
+        let end = (max * target.as_vec2()).round().as_uvec2().min(target);
+        Viewport {
+            physical_position: position,
+            physical_size: end.saturating_sub(position).max(UVec2::ONE),
+            ..Default::default()
+        }
+    }
+}
+
+impl Plugin for CameraViewportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            apply_camera_viewport.before(CameraUpdateSystems),
+        );
+    }
+}
+
+/// Uses the target size of the previous frame, so a resize is applied one frame late
+fn apply_camera_viewport(mut cameras: Query<(&CameraViewport, &mut Camera)>) {
+    for (viewport, mut camera) in cameras.iter_mut() {
+        let Some(target_size) = camera.physical_target_size() else {
+            continue;
+        };
+        let mut new = viewport.to_viewport(target_size);
+        if let Some(current) = &camera.viewport {
+            new.depth = current.depth.clone();
+            if current.physical_position == new.physical_position
+                && current.physical_size == new.physical_size
+            {
+                continue;
+            }
+        }
+        camera.viewport = Some(new);
+    }
+}
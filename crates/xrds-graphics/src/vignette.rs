@@ -0,0 +1,230 @@
+use bevy::{
+    asset::{embedded_asset, load_embedded_asset},
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        FullscreenShader,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        view::ViewTarget,
+        RenderApp, RenderStartup,
+    },
+};
+
+/// Darkens the border of a camera's view after tonemapping
+///
+/// The pass runs per view, so every eye of an XR headset is darkened around
+/// the center of its own image.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    /// Opacity of the border, 0 skips the pass
+    pub intensity: f32,
+    /// Distance from the center where darkening starts, 1 is the middle of
+    /// the view's edges
+    pub radius: f32,
+    /// Distance over which the border fades in
+    pub smoothness: f32,
+}
+
+#[derive(Component, Clone, Copy, ShaderType)]
+pub struct VignetteUniform {
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+    _padding: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct VignettePlugin;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct VignetteLabel;
+
+#[derive(Default)]
+struct VignetteNode;
+
+#[derive(Resource)]
+struct VignettePipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    ldr_pipeline_id: CachedRenderPipelineId,
+    hdr_pipeline_id: CachedRenderPipelineId,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            intensity: 1.0,
+            radius: 0.55,
+            smoothness: 0.45,
+        }
+    }
+}
+
+impl ExtractComponent for Vignette {
+    type QueryData = &'static Vignette;
+    type QueryFilter = With<Camera>;
+    type Out = VignetteUniform;
+
+    fn extract_component(vignette: QueryItem<'_, '_, Self::QueryData>) -> Option<Self::Out> {
+        if vignette.intensity <= 0.0 {
+            return None;
+        }
+        Some(VignetteUniform {
+            intensity: vignette.intensity.min(1.0),
+            radius: vignette.radius.max(0.0),
+            smoothness: vignette.smoothness.max(f32::EPSILON),
+            _padding: 0.0,
+        })
+    }
+}
+
+impl Plugin for VignettePlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "vignette.wgsl");
+
+        app.add_plugins((
+            ExtractComponentPlugin::<Vignette>::default(),
+            UniformComponentPlugin::<VignetteUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_systems(RenderStartup, init_vignette_pipeline)
+            .add_render_graph_node::<ViewNodeRunner<VignetteNode>>(Core3d, VignetteLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    VignetteLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+}
+
+impl ViewNode for VignetteNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<VignetteUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let vignette_pipeline = world.resource::<VignettePipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        let pipeline_id = if view_target.is_hdr() {
+            vignette_pipeline.hdr_pipeline_id
+        } else {
+            vignette_pipeline.ldr_pipeline_id
+        };
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+
+        let uniforms = world.resource::<ComponentUniforms<VignetteUniform>>();
+        let Some(uniform_binding) = uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "vignette_bind_group",
+            &vignette_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &vignette_pipeline.sampler,
+                uniform_binding.clone(),
+            )),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("vignette_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+fn init_vignette_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    fullscreen_shader: Res<FullscreenShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "vignette_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                uniform_buffer::<VignetteUniform>(true),
+            ),
+        ),
+    );
+    let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+    let shader = load_embedded_asset!(asset_server.as_ref(), "vignette.wgsl");
+
+    let queue_pipeline = |format: TextureFormat| {
+        pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("vignette_pipeline".into()),
+            layout: vec![layout.clone()],
+            vertex: fullscreen_shader.to_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: shader.clone(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    };
+    let ldr_pipeline_id = queue_pipeline(TextureFormat::bevy_default());
+    let hdr_pipeline_id = queue_pipeline(ViewTarget::TEXTURE_FORMAT_HDR);
+
+    commands.insert_resource(VignettePipeline {
+        layout,
+        sampler,
+        ldr_pipeline_id,
+        hdr_pipeline_id,
+    });
+}
@@ -0,0 +1,23 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct VignetteUniform {
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: VignetteUniform;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let source = textureSample(screen_texture, texture_sampler, in.uv);
+
+    // 0 in the center, 1 in the middle of the edges
+    let distance = length(in.uv * 2.0 - 1.0);
+    let border = smoothstep(settings.radius, settings.radius + settings.smoothness, distance);
+
+    return vec4<f32>(source.rgb * (1.0 - border * settings.intensity), source.a);
+}
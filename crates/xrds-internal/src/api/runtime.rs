@@ -29,7 +29,7 @@ impl Runtime {
 
     pub fn run<A>(self, app: A) -> Result<(), RuntimeError>
     where
-        A: RuntimeHandler + Send + Sync,
+        A: RuntimeHandler + Send + Sync + 'static,
     {
         self.inner.run(app)
     }
@@ -1,6 +1,8 @@
 mod client;
 
 mod xrds_websocket;
+#[cfg(feature = "ros2")]
+mod xrds_ros2;
 mod xrds_webrtc {
 	pub mod webrtc_client;
     pub mod webcam_reader;
@@ -18,6 +20,8 @@ mod xrds_webrtc {
 }
 pub use client::*;
 pub use xrds_websocket::*;
+#[cfg(feature = "ros2")]
+pub use xrds_ros2::*;
 pub use xrds_webrtc::*;
 
 
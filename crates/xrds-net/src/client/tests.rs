@@ -1084,4 +1084,56 @@ mod tests {
             .expect("Failed to stop streaming");
         server_handle.abort();
     }
+
+    /* start of ROS2 bridge tests */
+    #[cfg(feature = "ros2")]
+    #[test]
+    fn test_ros2_parse_messages() {
+        use crate::client::{parse_event, Ros2Event, Ros2PointCloud, Ros2Pose, Ros2Transform};
+        use base64::Engine;
+
+        let frame = br#"{"op":"publish","topic":"/tf","msg":{"transforms":[{"header":{"frame_id":"map"},"child_frame_id":"base_link","transform":{"translation":{"x":1.0,"y":2.0,"z":0.0},"rotation":{"x":0.0,"y":0.0,"z":0.0,"w":1.0}}}]}}"#;
+        let Some(Ros2Event::Message(message)) = parse_event(frame) else {
+            panic!("expected a message");
+        };
+        assert_eq!(message.topic, "/tf");
+        let transforms = Ros2Transform::from_tf(&message.msg);
+        assert_eq!(transforms.len(), 1);
+        assert_eq!(transforms[0].child_frame, "base_link");
+        assert_eq!(transforms[0].pose.position, [1.0, 2.0, 0.0]);
+
+        let pose = Ros2Pose::from_msg(&Ros2Pose::IDENTITY.to_pose_stamped("map"));
+        assert_eq!(pose, Some(Ros2Pose::IDENTITY));
+        assert_eq!(
+            parse_event(br#"{"op":"status","level":"error","msg":"no such topic"}"#),
+            Some(Ros2Event::Error("no such topic".to_string()))
+        );
+
+        // Two points of x, y, z and a packed rgb float, the second one invalid
+        let mut data = Vec::new();
+        for (point, rgb) in [([1.0f32, 2.0, 3.0], 0x00ff8000u32), ([f32::NAN, 0.0, 0.0], 0)] {
+            for value in point {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            data.extend_from_slice(&rgb.to_le_bytes());
+        }
+        let msg = serde_json::json!({
+            "header": { "frame_id": "lidar" },
+            "width": 2,
+            "height": 1,
+            "point_step": 16,
+            "is_bigendian": false,
+            "fields": [
+                { "name": "x", "offset": 0, "datatype": 7, "count": 1 },
+                { "name": "y", "offset": 4, "datatype": 7, "count": 1 },
+                { "name": "z", "offset": 8, "datatype": 7, "count": 1 },
+                { "name": "rgb", "offset": 12, "datatype": 7, "count": 1 },
+            ],
+            "data": base64::engine::general_purpose::STANDARD.encode(&data),
+        });
+        let cloud = Ros2PointCloud::from_point_cloud2(&msg).unwrap();
+        assert_eq!(cloud.frame_id, "lidar");
+        assert_eq!(cloud.points, vec![[1.0, 2.0, 3.0]]);
+        assert_eq!(cloud.colors, vec![[255, 128, 0]]);
+    }
 }
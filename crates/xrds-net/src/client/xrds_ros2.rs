@@ -0,0 +1,444 @@
+/*
+Copyright 2025 KETI
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+     https://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! ROS2 bridge over the rosbridge v2 protocol
+//!
+//! Talks JSON over a WebSocket to a `rosbridge_server` running next to the
+//! ROS2 graph, which relays subscriptions and publications to DDS. This keeps
+//! the SDK free of a native DDS stack.
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use base64::Engine;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::client::XrdsWebsocket;
+
+// sensor_msgs/PointField datatypes
+const POINT_FIELD_UINT32: u8 = 6;
+const POINT_FIELD_FLOAT32: u8 = 7;
+const POINT_FIELD_FLOAT64: u8 = 8;
+
+/// Message of a ROS2 topic in the JSON encoding of rosbridge
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ros2Message {
+    pub topic: String,
+    pub msg: Value,
+}
+
+/// Events of a [`Ros2Bridge`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ros2Event {
+    Connected,
+    Message(Ros2Message),
+    /// Status messages of rosbridge and connection errors
+    Error(String),
+    Disconnected,
+}
+
+/// Position and orientation (x, y, z, w) in the ROS frame convention
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ros2Pose {
+    pub position: [f64; 3],
+    pub orientation: [f64; 4],
+}
+
+/// Transform of `child_frame` relative to `parent_frame`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ros2Transform {
+    pub parent_frame: String,
+    pub child_frame: String,
+    pub pose: Ros2Pose,
+}
+
+/// Points of a `sensor_msgs/PointCloud2`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ros2PointCloud {
+    pub frame_id: String,
+    pub points: Vec<[f32; 3]>,
+    /// RGB of each point, empty if the cloud has no `rgb` or `rgba` field
+    pub colors: Vec<[u8; 3]>,
+}
+
+enum Ros2Command {
+    Send(Value),
+    Close,
+}
+
+/// Connection to a rosbridge server
+///
+/// The connection runs on its own thread, so the bridge can be polled from a
+/// frame loop. Requests made before the connection is established are sent
+/// once it is.
+pub struct Ros2Bridge {
+    commands: UnboundedSender<Ros2Command>,
+    events: Receiver<Ros2Event>,
+    next_id: u64,
+}
+
+impl Ros2Bridge {
+    /// Connect to a rosbridge server, e.g. `ws://robot.local:9090`
+    pub fn connect(url: &str) -> Self {
+        let (commands, command_receiver) = unbounded_channel();
+        let (event_sender, events) = mpsc::channel();
+        let url = url.to_string();
+        thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    let _ = event_sender.send(Ros2Event::Error(err.to_string()));
+                    return;
+                }
+            };
+            runtime.block_on(run_bridge(url, command_receiver, event_sender));
+        });
+
+        Ros2Bridge {
+            commands,
+            events,
+            next_id: 0,
+        }
+    }
+
+    pub fn subscribe(&mut self, topic: &str, msg_type: &str) {
+        self.subscribe_throttled(topic, msg_type, 0);
+    }
+
+    /// Subscribe receiving at most one message every `throttle_rate` milliseconds
+    pub fn subscribe_throttled(&mut self, topic: &str, msg_type: &str, throttle_rate: u32) {
+        let id = self.next_id("subscribe");
+        self.send(json!({
+            "op": "subscribe",
+            "id": id,
+            "topic": topic,
+            "type": msg_type,
+            "throttle_rate": throttle_rate,
+        }));
+    }
+
+    pub fn unsubscribe(&mut self, topic: &str) {
+        self.send(json!({ "op": "unsubscribe", "topic": topic }));
+    }
+
+    /// Announce a topic before publishing to it
+    pub fn advertise(&mut self, topic: &str, msg_type: &str) {
+        let id = self.next_id("advertise");
+        self.send(json!({
+            "op": "advertise",
+            "id": id,
+            "topic": topic,
+            "type": msg_type,
+        }));
+    }
+
+    pub fn publish(&mut self, topic: &str, msg: Value) {
+        self.send(json!({ "op": "publish", "topic": topic, "msg": msg }));
+    }
+
+    /// Next event if there is one, never blocks
+    pub fn try_recv(&self) -> Option<Ros2Event> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    pub fn close(&self) {
+        let _ = self.commands.send(Ros2Command::Close);
+    }
+
+    fn next_id(&mut self, op: &str) -> String {
+        self.next_id += 1;
+        format!("xrds_{}_{}", op, self.next_id)
+    }
+
+    fn send(&self, message: Value) {
+        let _ = self.commands.send(Ros2Command::Send(message));
+    }
+}
+
+impl Drop for Ros2Bridge {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+async fn run_bridge(
+    url: String,
+    mut commands: UnboundedReceiver<Ros2Command>,
+    events: mpsc::Sender<Ros2Event>,
+) {
+    let ws = match XrdsWebsocket::new().connect(&url).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            let _ = events.send(Ros2Event::Error(err));
+            let _ = events.send(Ros2Event::Disconnected);
+            return;
+        }
+    };
+    let _ = events.send(Ros2Event::Connected);
+
+    loop {
+        tokio::select! {
+            received = ws.rcv_ws() => {
+                let event = match received {
+                    Ok(data) => parse_event(&data),
+                    Err(err) => {
+                        let _ = events.send(Ros2Event::Error(err));
+                        break;
+                    }
+                };
+                if let Some(event) = event {
+                    if events.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+            command = commands.recv() => {
+                match command {
+                    Some(Ros2Command::Send(message)) => {
+                        let text = message.to_string().into_bytes();
+                        if let Err(err) = ws.send_ws(Some("text"), text).await {
+                            let _ = events.send(Ros2Event::Error(err));
+                        }
+                    }
+                    Some(Ros2Command::Close) | None => {
+                        let _ = ws.close_ws().await;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let _ = events.send(Ros2Event::Disconnected);
+}
+
+/// Event of a rosbridge frame, `None` for operations the bridge ignores
+pub fn parse_event(data: &[u8]) -> Option<Ros2Event> {
+    let message: Value = match serde_json::from_slice(data) {
+        Ok(message) => message,
+        Err(err) => return Some(Ros2Event::Error(err.to_string())),
+    };
+    match message["op"].as_str()? {
+        "publish" => Some(Ros2Event::Message(Ros2Message {
+            topic: message["topic"].as_str()?.to_string(),
+            msg: message["msg"].clone(),
+        })),
+        "status" if message["level"].as_str() == Some("error") => Some(Ros2Event::Error(
+            message["msg"].as_str().unwrap_or_default().to_string(),
+        )),
+        _ => None,
+    }
+}
+
+fn vector(value: &Value, keys: [&str; 3]) -> Option<[f64; 3]> {
+    Some([
+        value[keys[0]].as_f64()?,
+        value[keys[1]].as_f64()?,
+        value[keys[2]].as_f64()?,
+    ])
+}
+
+fn quaternion(value: &Value) -> Option<[f64; 4]> {
+    Some([
+        value["x"].as_f64()?,
+        value["y"].as_f64()?,
+        value["z"].as_f64()?,
+        value["w"].as_f64()?,
+    ])
+}
+
+impl Ros2Pose {
+    pub const IDENTITY: Ros2Pose = Ros2Pose {
+        position: [0.0; 3],
+        orientation: [0.0, 0.0, 0.0, 1.0],
+    };
+
+    /// Pose of a `geometry_msgs` `Pose`, `PoseStamped`, `PoseWithCovariance(Stamped)`,
+    /// `Transform` or `TransformStamped`, or `nav_msgs/Odometry` message
+    pub fn from_msg(msg: &Value) -> Option<Self> {
+        if let (Some(position), Some(orientation)) = (
+            vector(&msg["position"], ["x", "y", "z"]),
+            quaternion(&msg["orientation"]),
+        ) {
+            return Some(Ros2Pose {
+                position,
+                orientation,
+            });
+        }
+        if let (Some(position), Some(orientation)) = (
+            vector(&msg["translation"], ["x", "y", "z"]),
+            quaternion(&msg["rotation"]),
+        ) {
+            return Some(Ros2Pose {
+                position,
+                orientation,
+            });
+        }
+        ["pose", "transform"]
+            .iter()
+            .filter(|key| msg[**key].is_object())
+            .find_map(|key| Ros2Pose::from_msg(&msg[*key]))
+    }
+
+    /// `geometry_msgs/PoseStamped` in `frame_id`
+    pub fn to_pose_stamped(&self, frame_id: &str) -> Value {
+        let [x, y, z] = self.position;
+        let [qx, qy, qz, qw] = self.orientation;
+        json!({
+            "header": { "frame_id": frame_id },
+            "pose": {
+                "position": { "x": x, "y": y, "z": z },
+                "orientation": { "x": qx, "y": qy, "z": qz, "w": qw },
+            },
+        })
+    }
+}
+
+/// `geometry_msgs/Twist` velocity command, e.g. for `/cmd_vel`
+pub fn twist_msg(linear: [f64; 3], angular: [f64; 3]) -> Value {
+    json!({
+        "linear": { "x": linear[0], "y": linear[1], "z": linear[2] },
+        "angular": { "x": angular[0], "y": angular[1], "z": angular[2] },
+    })
+}
+
+impl Ros2Transform {
+    /// Transforms of a `tf2_msgs/TFMessage` from `/tf` or `/tf_static`
+    pub fn from_tf(msg: &Value) -> Vec<Self> {
+        let Some(transforms) = msg["transforms"].as_array() else {
+            return Vec::new();
+        };
+        transforms
+            .iter()
+            .filter_map(|transform| {
+                Some(Ros2Transform {
+                    parent_frame: transform["header"]["frame_id"].as_str()?.to_string(),
+                    child_frame: transform["child_frame_id"].as_str()?.to_string(),
+                    pose: Ros2Pose::from_msg(&transform["transform"])?,
+                })
+            })
+            .collect()
+    }
+}
+
+struct PointField {
+    offset: usize,
+    datatype: u8,
+}
+
+impl Ros2PointCloud {
+    /// Decode a `sensor_msgs/PointCloud2` with `x`, `y`, `z` and optionally `rgb` fields
+    pub fn from_point_cloud2(msg: &Value) -> Result<Self, String> {
+        let field = |name: &str| {
+            msg["fields"].as_array()?.iter().find_map(|field| {
+                (field["name"].as_str()? == name).then(|| PointField {
+                    offset: field["offset"].as_u64().unwrap_or_default() as usize,
+                    datatype: field["datatype"].as_u64().unwrap_or_default() as u8,
+                })
+            })
+        };
+        let (Some(x), Some(y), Some(z)) = (field("x"), field("y"), field("z")) else {
+            return Err("PointCloud2 without x, y and z fields".to_string());
+        };
+        let rgb = field("rgb").or_else(|| field("rgba"));
+        let point_step = msg["point_step"].as_u64().unwrap_or_default() as usize;
+        let count = (msg["width"].as_u64().unwrap_or_default()
+            * msg["height"].as_u64().unwrap_or(1)) as usize;
+        let big_endian = msg["is_bigendian"].as_bool().unwrap_or(false);
+
+        // rosbridge sends uint8[] as base64, some clients as a number array
+        let data = match &msg["data"] {
+            Value::String(data) => base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|err| err.to_string())?,
+            Value::Array(data) => data
+                .iter()
+                .map(|byte| byte.as_u64().unwrap_or_default() as u8)
+                .collect(),
+            _ => return Err("PointCloud2 without data".to_string()),
+        };
+        if point_step == 0 || data.len() < point_step * count {
+            return Err(format!(
+                "PointCloud2 data of {} bytes is too short for {} points",
+                data.len(),
+                count
+            ));
+        }
+
+        let read = |point: &[u8], field: &PointField| -> Option<f64> {
+            let size = if field.datatype == POINT_FIELD_FLOAT64 {
+                8
+            } else {
+                4
+            };
+            let bytes = point.get(field.offset..field.offset + size)?;
+            let value = match (field.datatype, big_endian) {
+                (POINT_FIELD_FLOAT32, false) => f32::from_le_bytes(bytes.try_into().ok()?) as f64,
+                (POINT_FIELD_FLOAT32, true) => f32::from_be_bytes(bytes.try_into().ok()?) as f64,
+                (POINT_FIELD_FLOAT64, false) => f64::from_le_bytes(bytes.try_into().ok()?),
+                (POINT_FIELD_FLOAT64, true) => f64::from_be_bytes(bytes.try_into().ok()?),
+                _ => return None,
+            };
+            Some(value)
+        };
+
+        let mut cloud = Ros2PointCloud {
+            frame_id: msg["header"]["frame_id"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            ..Default::default()
+        };
+        for point in data.chunks_exact(point_step).take(count) {
+            let (Some(px), Some(py), Some(pz)) =
+                (read(point, &x), read(point, &y), read(point, &z))
+            else {
+                continue;
+            };
+            // Invalid points of organized clouds are NaN
+            if !(px.is_finite() && py.is_finite() && pz.is_finite()) {
+                continue;
+            }
+            cloud.points.push([px as f32, py as f32, pz as f32]);
+
+            // PCL packs the color into the bytes of a float or uint32 as 0x00RRGGBB
+            if let Some(rgb) = rgb
+                .as_ref()
+                .filter(|rgb| matches!(rgb.datatype, POINT_FIELD_FLOAT32 | POINT_FIELD_UINT32))
+            {
+                let bytes: [u8; 4] = point
+                    .get(rgb.offset..rgb.offset + 4)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .unwrap_or_default();
+                let packed = if big_endian {
+                    u32::from_be_bytes(bytes)
+                } else {
+                    u32::from_le_bytes(bytes)
+                };
+                cloud
+                    .colors
+                    .push([(packed >> 16) as u8, (packed >> 8) as u8, packed as u8]);
+            }
+        }
+        Ok(cloud)
+    }
+}
@@ -1,17 +1,33 @@
+use std::{ffi::c_void, sync::Arc};
+
+use anyhow::anyhow;
 use bevy::{
     math::Mat4,
-    render::settings::{RenderResources, WgpuSettings},
+    prelude::*,
+    render::{
+        renderer::{
+            RenderAdapter, RenderAdapterInfo, RenderDevice, RenderInstance, RenderQueue,
+            WgpuWrapper,
+        },
+        settings::{RenderResources, WgpuSettings},
+    },
+};
+use wgpu::{DeviceType, Extent3d, Features};
+use windows::{
+    core::Interface,
+    Win32::Graphics::{Direct3D12::ID3D12Resource, Dxgi::Common::*},
 };
-use wgpu::Extent3d;
 
 use crate::{
-    backends::{GraphicsInner, OpenXrGraphicsBackend},
+    backends::{GraphicsInner, OpenXrGraphicsBackend, OpenXrGraphicsBackends},
     openxr::{
         graphics::{OpenXrGraphicsExtend, OpenXrGraphicsFamily, OpenXrGraphicsWrap},
         session::OpenXrSessionCreateInfo,
     },
 };
 
+type D3D12SessionCreateInfo = <openxr::D3D12 as openxr::Graphics>::SessionCreateInfo;
+
 unsafe impl OpenXrGraphicsExtend for openxr::D3D12 {
     fn wrap<G: OpenXrGraphicsFamily>(inner: G::Inner<Self>) -> OpenXrGraphicsWrap<G> {
         OpenXrGraphicsWrap::D3d12(inner)
@@ -22,46 +38,174 @@ impl GraphicsInner<openxr::D3D12> {}
 
 impl OpenXrGraphicsBackend<openxr::D3D12> for GraphicsInner<openxr::D3D12> {
     fn initialize(
-        _openxr_instance: &openxr::Instance,
-        _system_id: openxr::SystemId,
+        openxr_instance: &openxr::Instance,
+        system_id: openxr::SystemId,
         _openxr_appinfo: &openxr::ApplicationInfo,
-        _wgpu_settings: WgpuSettings,
-    ) -> anyhow::Result<super::OpenXrGraphicsBackends> {
-        unimplemented!()
+        wgpu_settings: WgpuSettings,
+    ) -> anyhow::Result<OpenXrGraphicsBackends> {
+        let _span = debug_span!("xrds-openxr::d3d12::initialize");
+        let requirements = openxr_instance.graphics_requirements::<openxr::D3D12>(system_id)?;
+        // Both LUIDs are the 8 bytes of the Windows LUID struct
+        let runtime_luid: [u8; 8] = unsafe { std::mem::transmute_copy(&requirements.adapter_luid) };
+
+        let wgpu_instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::DX12,
+            flags: wgpu_settings.instance_flags,
+            backend_options: wgpu::BackendOptions {
+                dx12: wgpu::Dx12BackendOptions {
+                    shader_compiler: wgpu_settings.dx12_shader_compiler.clone(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        // The runtime composites on one adapter, the device must be created on it
+        let wgpu_adapter = wgpu_instance
+            .enumerate_adapters(wgpu::Backends::DX12)
+            .into_iter()
+            .find(|adapter| {
+                let Some(hal_adapter) = (unsafe { adapter.as_hal::<wgpu_hal::api::Dx12>() }) else {
+                    return false;
+                };
+                let Ok(desc) = (unsafe { hal_adapter.raw_adapter().GetDesc1() }) else {
+                    return false;
+                };
+                let luid: [u8; 8] = unsafe { std::mem::transmute_copy(&desc.AdapterLuid) };
+                luid == runtime_luid
+            })
+            .ok_or_else(|| anyhow!("Could not find the D3D12 adapter of the OpenXR runtime"))?;
+        let wgpu_adapter_info = wgpu_adapter.get_info();
+        info!(
+            "OpenXR runtime device: {}, minimum feature level: {:?}",
+            wgpu_adapter_info.name, requirements.min_feature_level
+        );
+
+        let mut features = wgpu_adapter.features();
+        if wgpu_adapter_info.device_type == DeviceType::DiscreteGpu {
+            features.remove(Features::MAPPABLE_PRIMARY_BUFFERS);
+        }
+        if let Some(disabled_features) = wgpu_settings.disabled_features {
+            features.remove(disabled_features);
+        }
+        features |= wgpu_settings.features & wgpu_adapter.features();
+        let limits = wgpu_adapter.limits();
+        trace!("Limits: {:?}", limits);
+        trace!("Features: {:?}", features);
+
+        let (wgpu_device, wgpu_queue) =
+            bevy::tasks::block_on(wgpu_adapter.request_device(&wgpu::DeviceDescriptor {
+                label: wgpu_settings.device_label.as_deref(),
+                required_features: features,
+                required_limits: limits,
+                memory_hints: wgpu_settings.memory_hints,
+                trace: wgpu::Trace::Off,
+            }))?;
+
+        let inner = GraphicsInner::<openxr::D3D12> {
+            device: wgpu_device,
+            queue: wgpu_queue,
+            adapter: wgpu_adapter,
+            adapter_info: wgpu_adapter_info,
+            instance: wgpu_instance,
+            _phantom: std::marker::PhantomData,
+        };
+
+        Ok(OpenXrGraphicsBackends::from_inner(inner))
     }
 
     fn get_render_resource(&self) -> anyhow::Result<RenderResources> {
-        unimplemented!()
+        Ok(RenderResources(
+            RenderDevice::new(WgpuWrapper::new(self.device.clone())),
+            RenderQueue(Arc::new(WgpuWrapper::new(self.queue.clone()))),
+            RenderAdapterInfo(WgpuWrapper::new(self.adapter_info.clone())),
+            RenderAdapter(Arc::new(WgpuWrapper::new(self.adapter.clone()))),
+            RenderInstance(Arc::new(WgpuWrapper::new(self.instance.clone()))),
+        ))
     }
 
     fn get_session_create_info(&self) -> anyhow::Result<OpenXrSessionCreateInfo> {
-        unimplemented!()
+        let hal_device = unsafe { self.device.as_hal::<wgpu_hal::api::Dx12>() }.unwrap();
+        let raw_device = hal_device.raw_device().as_raw();
+        let raw_queue = hal_device.raw_queue().as_raw();
+
+        Ok(OpenXrSessionCreateInfo::from_inner::<openxr::D3D12>(
+            D3D12SessionCreateInfo {
+                device: raw_device as _,
+                queue: raw_queue as _,
+            },
+        ))
     }
 
     fn get_swapchain_create_info(
         &self,
-        _swapchain_format: wgpu::TextureFormat,
-        _size: Extent3d,
-        _sample_count: u32,
+        format: wgpu::TextureFormat,
+        size: Extent3d,
+        sample_count: u32,
     ) -> anyhow::Result<openxr::SwapchainCreateInfo<openxr::D3D12>> {
-        unimplemented!()
+        Ok(openxr::SwapchainCreateInfo::<openxr::D3D12> {
+            create_flags: openxr::SwapchainCreateFlags::EMPTY,
+            usage_flags: openxr::SwapchainUsageFlags::COLOR_ATTACHMENT
+                | openxr::SwapchainUsageFlags::SAMPLED,
+            sample_count,
+            width: size.width,
+            height: size.height,
+            format: format_wgpu_to_dxgi(format)
+                .expect("Invalid texture format")
+                .0 as _,
+            face_count: 1,
+            array_size: size.depth_or_array_layers,
+            mip_count: 1,
+        })
     }
 
     fn swapchain_image_to_wgpu(
         &self,
-        _swapchain_image: &<openxr::D3D12 as openxr::Graphics>::SwapchainImage,
-        _format: wgpu::TextureFormat,
-        _size: Extent3d,
-        _sample_count: u32,
+        swapchain_image: &<openxr::D3D12 as openxr::Graphics>::SwapchainImage,
+        format: wgpu::TextureFormat,
+        size: Extent3d,
+        sample_count: u32,
     ) -> anyhow::Result<wgpu::Texture> {
-        unimplemented!()
+        debug_span!("swapchain_image_to_wgpu");
+        let raw_image = *swapchain_image as *mut c_void;
+        // Images belong to the runtime, the texture holds its own reference
+        let resource = unsafe { ID3D12Resource::from_raw_borrowed(&raw_image) }
+            .cloned()
+            .ok_or_else(|| anyhow!("Swapchain image is null"))?;
+
+        let texture = unsafe {
+            let hal_texture = wgpu_hal::dx12::Device::texture_from_raw(
+                resource,
+                format,
+                wgpu::TextureDimension::D2,
+                size,
+                1,
+                sample_count,
+            );
+            self.device.create_texture_from_hal::<wgpu_hal::api::Dx12>(
+                hal_texture,
+                &wgpu::TextureDescriptor {
+                    label: Some("OpenXrSwapchain"),
+                    format,
+                    size,
+                    dimension: wgpu::TextureDimension::D2,
+                    mip_level_count: 1,
+                    sample_count,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                },
+            )
+        };
+
+        Ok(texture)
     }
 
     fn format_from_raw(
         &self,
-        _format: &<openxr::D3D12 as openxr::Graphics>::Format,
+        format: &<openxr::D3D12 as openxr::Graphics>::Format,
     ) -> Option<wgpu::TextureFormat> {
-        unimplemented!()
+        format_dxgi_to_wgpu(DXGI_FORMAT(*format as _))
     }
 
     fn calculate_projection_matrix(&self, near: f32, fov: openxr::Fovf) -> Mat4 {
@@ -74,7 +218,7 @@ impl OpenXrGraphicsBackend<openxr::D3D12> for GraphicsInner<openxr::D3D12> {
         let tan_angle_up = fov.angle_up.tan();
 
         let tan_angle_width = tan_angle_right - tan_angle_left;
-        let tan_angle_height = tan_angle_down - tan_angle_up;
+        let tan_angle_height = tan_angle_up - tan_angle_down;
 
         let offset_z = 0.0;
 
@@ -138,3 +282,42 @@ impl OpenXrGraphicsBackend<openxr::D3D12> for GraphicsInner<openxr::D3D12> {
         }
     }
 }
+
+/// Swapchain formats runtimes offer for color and depth
+fn format_dxgi_to_wgpu(format: DXGI_FORMAT) -> Option<wgpu::TextureFormat> {
+    let conv = match format {
+        DXGI_FORMAT_R8G8B8A8_UNORM => wgpu::TextureFormat::Rgba8Unorm,
+        DXGI_FORMAT_R8G8B8A8_UNORM_SRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
+        DXGI_FORMAT_B8G8R8A8_UNORM => wgpu::TextureFormat::Bgra8Unorm,
+        DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => wgpu::TextureFormat::Bgra8UnormSrgb,
+        DXGI_FORMAT_R10G10B10A2_UNORM => wgpu::TextureFormat::Rgb10a2Unorm,
+        DXGI_FORMAT_R11G11B10_FLOAT => wgpu::TextureFormat::Rg11b10Ufloat,
+        DXGI_FORMAT_R16G16B16A16_FLOAT => wgpu::TextureFormat::Rgba16Float,
+        DXGI_FORMAT_R32G32B32A32_FLOAT => wgpu::TextureFormat::Rgba32Float,
+        DXGI_FORMAT_D16_UNORM => wgpu::TextureFormat::Depth16Unorm,
+        DXGI_FORMAT_D24_UNORM_S8_UINT => wgpu::TextureFormat::Depth24PlusStencil8,
+        DXGI_FORMAT_D32_FLOAT => wgpu::TextureFormat::Depth32Float,
+        DXGI_FORMAT_D32_FLOAT_S8X24_UINT => wgpu::TextureFormat::Depth32FloatStencil8,
+        _ => return None,
+    };
+    Some(conv)
+}
+
+fn format_wgpu_to_dxgi(format: wgpu::TextureFormat) -> Option<DXGI_FORMAT> {
+    let conv = match format {
+        wgpu::TextureFormat::Rgba8Unorm => DXGI_FORMAT_R8G8B8A8_UNORM,
+        wgpu::TextureFormat::Rgba8UnormSrgb => DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+        wgpu::TextureFormat::Bgra8Unorm => DXGI_FORMAT_B8G8R8A8_UNORM,
+        wgpu::TextureFormat::Bgra8UnormSrgb => DXGI_FORMAT_B8G8R8A8_UNORM_SRGB,
+        wgpu::TextureFormat::Rgb10a2Unorm => DXGI_FORMAT_R10G10B10A2_UNORM,
+        wgpu::TextureFormat::Rg11b10Ufloat => DXGI_FORMAT_R11G11B10_FLOAT,
+        wgpu::TextureFormat::Rgba16Float => DXGI_FORMAT_R16G16B16A16_FLOAT,
+        wgpu::TextureFormat::Rgba32Float => DXGI_FORMAT_R32G32B32A32_FLOAT,
+        wgpu::TextureFormat::Depth16Unorm => DXGI_FORMAT_D16_UNORM,
+        wgpu::TextureFormat::Depth24PlusStencil8 => DXGI_FORMAT_D24_UNORM_S8_UINT,
+        wgpu::TextureFormat::Depth32Float => DXGI_FORMAT_D32_FLOAT,
+        wgpu::TextureFormat::Depth32FloatStencil8 => DXGI_FORMAT_D32_FLOAT_S8X24_UINT,
+        _ => return None,
+    };
+    Some(conv)
+}
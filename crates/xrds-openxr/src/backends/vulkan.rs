@@ -282,7 +282,7 @@ impl OpenXrGraphicsBackend<openxr::Vulkan> for GraphicsInner<openxr::Vulkan> {
                 .expect("Invalid texture format")
                 .as_raw() as _,
             face_count: 1,
-            array_size: 2,
+            array_size: size.depth_or_array_layers,
             mip_count: 1,
         })
     }
@@ -1,5 +1,6 @@
 use bevy::{
     app::PluginGroupBuilder,
+    asset::AssetPlugin,
     prelude::*,
     render::{pipelined_rendering::PipelinedRenderingPlugin, RenderPlugin},
     state::app::StatesPlugin,
@@ -10,12 +11,29 @@ pub(crate) mod openxr;
 #[cfg(target_os = "windows")]
 mod windows;
 
-pub use openxr::OpenXrCamera;
+pub use openxr::{
+    nearest_refresh_rate, OpenXrButton, OpenXrCamera, OpenXrCameraIndex, OpenXrController,
+    OpenXrControllerInput, OpenXrControllerModel, OpenXrControllerModels, OpenXrDeviceState,
+    OpenXrEnvironmentBlend, OpenXrFoveation, OpenXrFoveationLevel, OpenXrHand, OpenXrHandJoint,
+    OpenXrHandJointEntity, OpenXrHandTracking, OpenXrHaptic, OpenXrInput, OpenXrIpdOffset,
+    OpenXrJointPose, OpenXrLayerPanel, OpenXrMainSessionVisibility, OpenXrOverlay,
+    OpenXrPanelShape, OpenXrPassthrough, OpenXrRefreshRate, OpenXrSession, OpenXrSessionState,
+    OpenXrStageBounds, OpenXrSystemInfo, OPENXR_ASSET_SOURCE,
+};
 
 use crate::openxr::{
-    camera::OpenXrCameraPlugin, init::OpenXrInitPlugin,
-    reference_space::OpenXrReferenceSpacePlugin, render::OpenXrRenderPlugin,
-    session::OpenXrSessionPlugin, swapchain::OpenXrSwapchainPlugin,
+    camera::OpenXrCameraPlugin,
+    controller::{OpenXrControllerModelSourcePlugin, OpenXrControllerPlugin},
+    foveation::OpenXrFoveationPlugin,
+    hand_tracking::OpenXrHandTrackingPlugin,
+    init::OpenXrInitPlugin,
+    panel::OpenXrLayerPanelPlugin,
+    passthrough::OpenXrPassthroughPlugin,
+    reference_space::OpenXrReferenceSpacePlugin,
+    refresh_rate::OpenXrRefreshRatePlugin,
+    render::OpenXrRenderPlugin,
+    session::OpenXrSessionPlugin,
+    swapchain::OpenXrSwapchainPlugin,
 };
 
 pub fn add_plugins<PG: PluginGroup>(base_plugins: PG, app_name: String) -> PluginGroupBuilder {
@@ -24,6 +42,7 @@ pub fn add_plugins<PG: PluginGroup>(base_plugins: PG, app_name: String) -> Plugi
         .disable::<RenderPlugin>()
         .disable::<StatesPlugin>()
         .disable::<PipelinedRenderingPlugin>()
+        .add_before::<AssetPlugin>(OpenXrControllerModelSourcePlugin)
         .add_before::<RenderPlugin>(StatesPlugin)
         .add_before::<RenderPlugin>(OpenXrInitPlugin {
             app_name,
@@ -31,8 +50,14 @@ pub fn add_plugins<PG: PluginGroup>(base_plugins: PG, app_name: String) -> Plugi
         })
         .add(OpenXrSessionPlugin)
         .add(OpenXrReferenceSpacePlugin)
+        .add(OpenXrRefreshRatePlugin)
         .add(OpenXrSwapchainPlugin)
+        .add(OpenXrFoveationPlugin)
         .add(OpenXrCameraPlugin)
+        .add(OpenXrControllerPlugin)
+        .add(OpenXrHandTrackingPlugin)
+        .add(OpenXrPassthroughPlugin)
+        .add(OpenXrLayerPanelPlugin)
         .add(OpenXrRenderPlugin);
 
     #[cfg(feature = "preview_window")]
@@ -49,3 +74,16 @@ pub fn add_plugins<PG: PluginGroup>(base_plugins: PG, app_name: String) -> Plugi
 
     plugin_builder
 }
+
+/// Same as [`add_plugins`], but runs the application as an overlay on top of other XR applications
+pub fn add_overlay_plugins<PG: PluginGroup>(
+    base_plugins: PG,
+    app_name: String,
+    overlay: OpenXrOverlay,
+) -> PluginGroupBuilder {
+    add_plugins(base_plugins, app_name.clone()).set(OpenXrInitPlugin {
+        app_name,
+        overlay: Some(overlay),
+        ..Default::default()
+    })
+}
@@ -24,16 +24,23 @@ impl Default for OpenXrViewProjection {
 #[require(Camera3d, Transform)]
 pub struct OpenXrCamera;
 
+/// Camera rendering the OpenXR view with this index, 0 is the left eye
 #[derive(Component, ExtractComponent, Clone, Copy, Debug, Default)]
 #[require(Camera3d)]
 pub struct OpenXrCameraIndex(pub u32);
 
+/// Correction of the distance between the eye cameras in meters. Each eye
+/// moves half of it along its own x axis, away from the other eye
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct OpenXrIpdOffset(pub f32);
+
 #[derive(Debug, Default)]
 pub struct OpenXrCameraPlugin;
 
 impl Plugin for OpenXrCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ExtractComponentPlugin::<OpenXrCameraIndex>::default());
+        app.add_plugins(ExtractComponentPlugin::<OpenXrCameraIndex>::default())
+            .init_resource::<OpenXrIpdOffset>();
     }
 }
 
@@ -0,0 +1,482 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    asset::io::{
+        memory::{Dir, MemoryAssetReader},
+        AssetSourceBuilder,
+    },
+    prelude::*,
+};
+
+use crate::openxr::{
+    input::{
+        apply_haptics, clear_controller_input, read_controller_input, OpenXrButton, OpenXrInput,
+    },
+    resources::{OpenXrFrameState, OpenXrInstance, OpenXrPrimaryReferenceSpace, OpenXrSpace},
+    schedule::{
+        openxr_in_state_focused, openxr_in_state_synchronized, OpenXrRuntimeSystems,
+        OpenXrSchedules,
+    },
+    session::OpenXrSession,
+    system::OpenXrSystemInfo,
+};
+
+/// Asset source of runtime provided controller models, e.g. `openxr://controllers/1.glb`
+pub const OPENXR_ASSET_SOURCE: &str = "openxr";
+
+/// Bindings suggested for each interaction profile. Bindings without a hand
+/// are suggested for both
+#[rustfmt::skip]
+const PROFILE_BINDINGS: [(&str, &[(ControllerAction, Option<OpenXrHand>, &str)]); 5] = {
+    use ControllerAction::*;
+    use OpenXrButton::{Menu, Primary, Secondary, Thumbstick as ThumbstickClick};
+    [
+        ("/interaction_profiles/khr/simple_controller", &[
+            (GripPose, None, "input/grip/pose"),
+            (AimPose, None, "input/aim/pose"),
+            (Trigger, None, "input/select/click"),
+            (Button(Menu), None, "input/menu/click"),
+            (Haptic, None, "output/haptic"),
+        ]),
+        ("/interaction_profiles/oculus/touch_controller", &[
+            (GripPose, None, "input/grip/pose"),
+            (AimPose, None, "input/aim/pose"),
+            (Trigger, None, "input/trigger/value"),
+            (Squeeze, None, "input/squeeze/value"),
+            (Thumbstick, None, "input/thumbstick"),
+            (Button(ThumbstickClick), None, "input/thumbstick/click"),
+            (Button(Primary), Some(OpenXrHand::Left), "input/x/click"),
+            (Button(Secondary), Some(OpenXrHand::Left), "input/y/click"),
+            (Button(Menu), Some(OpenXrHand::Left), "input/menu/click"),
+            (Button(Primary), Some(OpenXrHand::Right), "input/a/click"),
+            (Button(Secondary), Some(OpenXrHand::Right), "input/b/click"),
+            (Haptic, None, "output/haptic"),
+        ]),
+        ("/interaction_profiles/valve/index_controller", &[
+            (GripPose, None, "input/grip/pose"),
+            (AimPose, None, "input/aim/pose"),
+            (Trigger, None, "input/trigger/value"),
+            (Squeeze, None, "input/squeeze/value"),
+            (Thumbstick, None, "input/thumbstick"),
+            (Button(ThumbstickClick), None, "input/thumbstick/click"),
+            (Button(Primary), None, "input/a/click"),
+            (Button(Secondary), None, "input/b/click"),
+            (Haptic, None, "output/haptic"),
+        ]),
+        ("/interaction_profiles/microsoft/motion_controller", &[
+            (GripPose, None, "input/grip/pose"),
+            (AimPose, None, "input/aim/pose"),
+            (Trigger, None, "input/trigger/value"),
+            (Squeeze, None, "input/squeeze/click"),
+            (Thumbstick, None, "input/thumbstick"),
+            (Button(ThumbstickClick), None, "input/thumbstick/click"),
+            (Button(Menu), None, "input/menu/click"),
+            (Haptic, None, "output/haptic"),
+        ]),
+        // The trackpad stands in for the thumbstick
+        ("/interaction_profiles/htc/vive_controller", &[
+            (GripPose, None, "input/grip/pose"),
+            (AimPose, None, "input/aim/pose"),
+            (Trigger, None, "input/trigger/value"),
+            (Squeeze, None, "input/squeeze/click"),
+            (Thumbstick, None, "input/trackpad"),
+            (Button(ThumbstickClick), None, "input/trackpad/click"),
+            (Button(Menu), None, "input/menu/click"),
+            (Haptic, None, "output/haptic"),
+        ]),
+    ]
+};
+
+/// Actions of the controller action set
+#[derive(Clone, Copy, Debug)]
+enum ControllerAction {
+    GripPose,
+    AimPose,
+    Trigger,
+    Squeeze,
+    Thumbstick,
+    Button(OpenXrButton),
+    Haptic,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OpenXrHand {
+    Left,
+    Right,
+}
+
+/// Follows the grip pose of a tracked controller
+///
+/// One entity per hand is spawned when the session is created. The entity is
+/// hidden while the controller is not tracked.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Transform, Visibility)]
+pub struct OpenXrController {
+    pub hand: OpenXrHand,
+    pub tracked: bool,
+}
+
+/// Runtime provided render model, spawned as a child of [`OpenXrController`]
+#[derive(Component, Clone, Copy, Debug)]
+pub struct OpenXrControllerModel {
+    pub key: u64,
+}
+
+/// Render models of the tracked controllers
+///
+/// Models are fetched with `XR_MSFT_controller_model` and loaded as glTF
+/// scenes. Set `visible` to `false` to hide them, e.g. when hand tracking
+/// takes over.
+#[derive(Resource, Clone)]
+pub struct OpenXrControllerModels {
+    pub visible: bool,
+    dir: Dir,
+}
+
+/// Actions of the controllers and spaces of their poses
+#[derive(Resource)]
+pub(crate) struct OpenXrControllerActions {
+    action_set: openxr::ActionSet,
+    // Destroying the actions invalidates the pose spaces
+    #[allow(dead_code)]
+    grip_pose: openxr::Action<openxr::Posef>,
+    #[allow(dead_code)]
+    aim_pose: openxr::Action<openxr::Posef>,
+    pub(crate) trigger: openxr::Action<f32>,
+    pub(crate) squeeze: openxr::Action<f32>,
+    pub(crate) thumbstick: openxr::Action<openxr::Vector2f>,
+    /// Indexed by [`OpenXrButton::index`]
+    pub(crate) buttons: [openxr::Action<bool>; OpenXrButton::COUNT],
+    pub(crate) haptic: openxr::Action<openxr::Haptic>,
+    pub(crate) hand_paths: [openxr::Path; 2],
+    grip_spaces: [OpenXrSpace; 2],
+    aim_spaces: [OpenXrSpace; 2],
+}
+
+/// Registers the asset source of controller models, must be added before `AssetPlugin`
+pub struct OpenXrControllerModelSourcePlugin;
+
+pub struct OpenXrControllerPlugin;
+
+impl OpenXrHand {
+    pub const ALL: [Self; 2] = [Self::Left, Self::Right];
+
+    pub fn user_path(&self) -> &'static str {
+        match self {
+            Self::Left => "/user/hand/left",
+            Self::Right => "/user/hand/right",
+        }
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Self::Left => 0,
+            Self::Right => 1,
+        }
+    }
+}
+
+impl Plugin for OpenXrControllerModelSourcePlugin {
+    fn build(&self, app: &mut App) {
+        let dir = Dir::new(PathBuf::new());
+        let reader_dir = dir.clone();
+        app.register_asset_source(
+            OPENXR_ASSET_SOURCE,
+            AssetSourceBuilder::default().with_reader(move || {
+                Box::new(MemoryAssetReader {
+                    root: reader_dir.clone(),
+                })
+            }),
+        )
+        .insert_resource(OpenXrControllerModels { visible: true, dir });
+    }
+}
+
+impl Plugin for OpenXrControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OpenXrInput>()
+            .add_systems(
+                OpenXrSchedules::SessionCreate,
+                create_controller_actions.in_set(OpenXrRuntimeSystems::PostSessionCreate),
+            )
+            .add_systems(
+                OpenXrSchedules::Update,
+                sync_controller_actions
+                    .in_set(OpenXrRuntimeSystems::PreFrameLoop)
+                    .run_if(resource_exists::<OpenXrControllerActions>)
+                    .run_if(openxr_in_state_focused),
+            )
+            .add_systems(
+                Update,
+                (load_controller_models, update_controller_model_visibility)
+                    .chain()
+                    .run_if(resource_exists::<OpenXrControllerActions>),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    locate_controllers.run_if(openxr_in_state_synchronized),
+                    read_controller_input.run_if(openxr_in_state_focused),
+                    clear_controller_input.run_if(not(openxr_in_state_focused)),
+                )
+                    .run_if(resource_exists::<OpenXrControllerActions>),
+            )
+            .add_systems(
+                PostUpdate,
+                apply_haptics
+                    .run_if(resource_exists::<OpenXrControllerActions>)
+                    .run_if(openxr_in_state_focused),
+            );
+    }
+}
+
+fn create_controller_actions(world: &mut World) {
+    debug_span!("OpenXrControllerPlugin");
+    let instance = &world.resource::<OpenXrInstance>().instance;
+    let session = world.resource::<OpenXrSession>();
+
+    let actions = (|| -> openxr::Result<OpenXrControllerActions> {
+        let hand_paths = [
+            instance.string_to_path(OpenXrHand::Left.user_path())?,
+            instance.string_to_path(OpenXrHand::Right.user_path())?,
+        ];
+        let action_set = instance.create_action_set("controllers", "Controllers", 0)?;
+        let grip_pose =
+            action_set.create_action::<openxr::Posef>("grip_pose", "Grip pose", &hand_paths)?;
+        let aim_pose =
+            action_set.create_action::<openxr::Posef>("aim_pose", "Aim pose", &hand_paths)?;
+        let trigger = action_set.create_action::<f32>("trigger", "Trigger", &hand_paths)?;
+        let squeeze = action_set.create_action::<f32>("squeeze", "Squeeze", &hand_paths)?;
+        let thumbstick = action_set.create_action::<openxr::Vector2f>(
+            "thumbstick",
+            "Thumbstick",
+            &hand_paths,
+        )?;
+        let buttons = [
+            action_set.create_action::<bool>("primary", "Primary button", &hand_paths)?,
+            action_set.create_action::<bool>("secondary", "Secondary button", &hand_paths)?,
+            action_set.create_action::<bool>("menu", "Menu button", &hand_paths)?,
+            action_set.create_action::<bool>(
+                "thumbstick_click",
+                "Thumbstick click",
+                &hand_paths,
+            )?,
+        ];
+        let haptic = action_set.create_action::<openxr::Haptic>("haptic", "Haptic", &hand_paths)?;
+
+        for (profile, profile_bindings) in PROFILE_BINDINGS {
+            let mut bindings = vec![];
+            for (action, hand, path) in profile_bindings {
+                for user_path in hand.map_or(OpenXrHand::ALL.to_vec(), |hand| vec![hand]) {
+                    let path =
+                        instance.string_to_path(&format!("{}/{}", user_path.user_path(), path))?;
+                    bindings.push(match action {
+                        ControllerAction::GripPose => openxr::Binding::new(&grip_pose, path),
+                        ControllerAction::AimPose => openxr::Binding::new(&aim_pose, path),
+                        ControllerAction::Trigger => openxr::Binding::new(&trigger, path),
+                        ControllerAction::Squeeze => openxr::Binding::new(&squeeze, path),
+                        ControllerAction::Thumbstick => openxr::Binding::new(&thumbstick, path),
+                        ControllerAction::Button(button) => {
+                            openxr::Binding::new(&buttons[button.index()], path)
+                        }
+                        ControllerAction::Haptic => openxr::Binding::new(&haptic, path),
+                    });
+                }
+            }
+            if let Err(e) = instance
+                .string_to_path(profile)
+                .and_then(|path| instance.suggest_interaction_profile_bindings(path, &bindings))
+            {
+                warn!("Could not suggest bindings for {}: {}", profile, e);
+            }
+        }
+
+        session.attach_action_sets(&[&action_set])?;
+        let grip_spaces = [
+            session.create_action_space(&grip_pose, hand_paths[0], openxr::Posef::IDENTITY)?,
+            session.create_action_space(&grip_pose, hand_paths[1], openxr::Posef::IDENTITY)?,
+        ];
+        let aim_spaces = [
+            session.create_action_space(&aim_pose, hand_paths[0], openxr::Posef::IDENTITY)?,
+            session.create_action_space(&aim_pose, hand_paths[1], openxr::Posef::IDENTITY)?,
+        ];
+
+        Ok(OpenXrControllerActions {
+            action_set,
+            grip_pose,
+            aim_pose,
+            trigger,
+            squeeze,
+            thumbstick,
+            buttons,
+            haptic,
+            hand_paths,
+            grip_spaces,
+            aim_spaces,
+        })
+    })();
+
+    match actions {
+        Ok(actions) => {
+            world.insert_resource(actions);
+            for hand in OpenXrHand::ALL {
+                world.spawn((
+                    Name::new(format!("Controller {:?}", hand)),
+                    OpenXrController {
+                        hand,
+                        tracked: false,
+                    },
+                    Visibility::Hidden,
+                ));
+            }
+            info!("OpenXR controller actions attached");
+        }
+        Err(e) => warn!("Could not create controller actions: {}", e),
+    }
+}
+
+fn sync_controller_actions(world: &mut World) {
+    let actions = world.resource::<OpenXrControllerActions>();
+    let session = world.resource::<OpenXrSession>();
+    if let Err(e) = session.sync_actions(&[openxr::ActiveActionSet::new(&actions.action_set)]) {
+        warn!("Could not sync controller actions: {}", e);
+    }
+}
+
+fn locate_controllers(
+    actions: Res<OpenXrControllerActions>,
+    frame_state: Res<OpenXrFrameState>,
+    primary_reference_space: Res<OpenXrPrimaryReferenceSpace>,
+    session: Res<OpenXrSession>,
+    mut input: ResMut<OpenXrInput>,
+    mut controllers: Query<(&mut OpenXrController, &mut Transform, &mut Visibility)>,
+) {
+    for (mut controller, mut transform, mut visibility) in controllers.iter_mut() {
+        let hand = controller.hand;
+        let locate = |space: &OpenXrSpace, transform: &mut Transform| match session.locate_space(
+            space,
+            &primary_reference_space.0,
+            frame_state.0.predicted_display_time,
+        ) {
+            Ok(location) => apply_location(&location, transform),
+            Err(e) => {
+                trace!("Could not locate controller {:?}: {}", hand, e);
+                false
+            }
+        };
+        let tracked = locate(&actions.grip_spaces[hand.index()], &mut transform);
+        let mut aim = Transform::default();
+        let aim_tracked = locate(&actions.aim_spaces[hand.index()], &mut aim);
+
+        let controller_input = input.controller_mut(hand);
+        controller_input.grip = tracked.then_some(*transform);
+        controller_input.aim = aim_tracked.then_some(aim);
+
+        if controller.tracked != tracked {
+            controller.tracked = tracked;
+            *visibility = if tracked {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
+
+/// Applies the valid parts of a located pose, returns whether it is tracked
+fn apply_location(location: &openxr::SpaceLocation, transform: &mut Transform) -> bool {
+    let flags = location.location_flags;
+    if flags.contains(openxr::sys::SpaceLocationFlags::POSITION_VALID) {
+        let position = location.pose.position;
+        transform.translation = Vec3::new(position.x, position.y, position.z);
+    }
+    if flags.contains(openxr::sys::SpaceLocationFlags::ORIENTATION_VALID) {
+        let orientation = location.pose.orientation;
+        transform.rotation = quat(orientation.x, orientation.y, orientation.z, orientation.w);
+    }
+    flags.contains(openxr::sys::SpaceLocationFlags::POSITION_TRACKED)
+        || flags.contains(openxr::sys::SpaceLocationFlags::ORIENTATION_TRACKED)
+}
+
+fn load_controller_models(
+    actions: Res<OpenXrControllerActions>,
+    session: Res<OpenXrSession>,
+    system_info: Res<OpenXrSystemInfo>,
+    models: Res<OpenXrControllerModels>,
+    asset_server: Res<AssetServer>,
+    controllers: Query<(Entity, &OpenXrController, Option<&Children>)>,
+    loaded: Query<(Entity, &OpenXrControllerModel)>,
+    mut commands: Commands,
+) {
+    if !system_info.enabled_extensions.msft_controller_model {
+        return;
+    }
+
+    for (entity, controller, children) in controllers.iter() {
+        if !controller.tracked {
+            continue;
+        }
+        let key = match session.controller_model_key(actions.hand_paths[controller.hand.index()]) {
+            Ok(Some(key)) => key,
+            Ok(None) => continue,
+            Err(e) => {
+                trace!("Could not get controller model key: {}", e);
+                continue;
+            }
+        };
+
+        // Replace the model when the controller changes
+        let current = children
+            .into_iter()
+            .flatten()
+            .filter_map(|child| loaded.get(*child).ok())
+            .collect::<Vec<_>>();
+        if current.iter().any(|(_, model)| model.key == key) {
+            continue;
+        }
+        for (child, _) in current {
+            commands.entity(child).despawn();
+        }
+
+        let path = format!("controllers/{}.glb", key);
+        if models.dir.get_asset(Path::new(&path)).is_none() {
+            match session.load_controller_model(key) {
+                Ok(data) => models.dir.insert_asset(Path::new(&path), data),
+                Err(e) => {
+                    warn!("Could not load controller model {}: {}", key, e);
+                    continue;
+                }
+            }
+        }
+
+        info!("Controller model {} loaded for {:?}", key, controller.hand);
+        let scene = asset_server.load(
+            GltfAssetLabel::Scene(0).from_asset(format!("{}://{}", OPENXR_ASSET_SOURCE, path)),
+        );
+        commands.entity(entity).with_child((
+            OpenXrControllerModel { key },
+            SceneRoot(scene),
+            if models.visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+        ));
+    }
+}
+
+fn update_controller_model_visibility(
+    models: Res<OpenXrControllerModels>,
+    mut query: Query<&mut Visibility, With<OpenXrControllerModel>>,
+) {
+    if !models.is_changed() {
+        return;
+    }
+    for mut visibility in query.iter_mut() {
+        *visibility = if models.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
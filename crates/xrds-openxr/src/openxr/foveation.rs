@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::openxr::{
+    resources::OpenXrSwapchain,
+    schedule::{OpenXrRuntimeSystems, OpenXrSchedules},
+    session::{init_render_resources, OpenXrSession},
+    swapchain::create_swapchain,
+    system::OpenXrSystemInfo,
+};
+
+/// How much the resolution drops towards the edges of the view
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OpenXrFoveationLevel {
+    #[default]
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+/// Fixed foveated rendering by `XR_FB_foveation`
+///
+/// The runtime renders the periphery of the swapchain at a lower resolution,
+/// which saves GPU time on standalone headsets where the lenses blur it
+/// anyway. Change the level at any time, e.g. from the quality settings.
+/// Requests are ignored when the runtime does not support the extension.
+///
+/// Foveation is applied by the driver for OpenGL ES swapchains, as on Quest.
+/// Vulkan swapchains need the fragment density map of
+/// `XR_FB_foveation_vulkan` attached to every render pass, which wgpu does
+/// not expose, so the level has no effect with the Vulkan backend.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct OpenXrFoveation {
+    pub level: OpenXrFoveationLevel,
+    /// Lets the runtime lower the level while the GPU has time to spare
+    pub dynamic: bool,
+    /// Moves the full resolution area up in degrees, negative moves it down
+    pub vertical_offset: f32,
+}
+
+/// Raw handle of the swapchain and the foveation applied to it
+#[derive(Resource)]
+struct OpenXrFoveationState {
+    swapchain: u64,
+    applied: OpenXrFoveation,
+}
+
+pub struct OpenXrFoveationPlugin;
+
+impl OpenXrFoveation {
+    pub fn new(level: OpenXrFoveationLevel) -> Self {
+        Self { level, ..default() }
+    }
+
+    pub fn with_dynamic(mut self, dynamic: bool) -> Self {
+        self.dynamic = dynamic;
+        self
+    }
+}
+
+impl OpenXrFoveationLevel {
+    pub(crate) fn as_raw(&self) -> openxr::sys::FoveationLevelFB {
+        match self {
+            Self::Off => openxr::sys::FoveationLevelFB::NONE,
+            Self::Low => openxr::sys::FoveationLevelFB::LOW,
+            Self::Medium => openxr::sys::FoveationLevelFB::MEDIUM,
+            Self::High => openxr::sys::FoveationLevelFB::HIGH,
+        }
+    }
+}
+
+impl Plugin for OpenXrFoveationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OpenXrFoveation>()
+            .add_systems(
+                OpenXrSchedules::SessionCreate,
+                // The swapchain moves to the render world with the render resources
+                read_swapchain
+                    .after(create_swapchain)
+                    .before(init_render_resources)
+                    .in_set(OpenXrRuntimeSystems::PostSessionCreate),
+            )
+            .add_systems(
+                OpenXrSchedules::Update,
+                apply_foveation
+                    .run_if(resource_exists::<OpenXrFoveationState>)
+                    .in_set(OpenXrRuntimeSystems::UpdateSessionStates),
+            );
+    }
+}
+
+fn foveation_supported(world: &World) -> bool {
+    world
+        .get_resource::<OpenXrSystemInfo>()
+        .is_some_and(|info| {
+            let extensions = &info.enabled_extensions;
+            extensions.fb_foveation
+                && extensions.fb_foveation_configuration
+                && extensions.fb_swapchain_update_state
+        })
+}
+
+fn read_swapchain(world: &mut World) {
+    debug_span!("OpenXrFoveationPlugin");
+    if !foveation_supported(world) {
+        info!("Foveation extensions not supported");
+        return;
+    }
+
+    let swapchain = world.resource::<OpenXrSwapchain>().as_raw();
+    world.insert_resource(OpenXrFoveationState {
+        swapchain: swapchain.into_raw(),
+        // Swapchains start without foveation
+        applied: OpenXrFoveation::default(),
+    });
+}
+
+fn apply_foveation(world: &mut World) {
+    debug_span!("OpenXrFoveationPlugin");
+    let foveation = *world.resource::<OpenXrFoveation>();
+    let state = world.resource::<OpenXrFoveationState>();
+    if state.applied == foveation {
+        return;
+    }
+
+    let session = world.resource::<OpenXrSession>();
+    if let Err(e) = session.update_swapchain_foveation(state.swapchain, &foveation) {
+        warn!("Could not set foveation {:?}: {}", foveation.level, e);
+    } else {
+        info!(
+            "Foveation changed to {:?}, dynamic={}",
+            foveation.level, foveation.dynamic
+        );
+    }
+    // Failed requests are not retried every frame
+    world.resource_mut::<OpenXrFoveationState>().applied = foveation;
+}
@@ -0,0 +1,299 @@
+use bevy::prelude::*;
+
+use crate::openxr::{
+    controller::OpenXrHand,
+    resources::{OpenXrFrameState, OpenXrInstance, OpenXrPrimaryReferenceSpace},
+    schedule::{openxr_in_state_synchronized, OpenXrRuntimeSystems, OpenXrSchedules},
+    session::OpenXrSession,
+    system::OpenXrSystemInfo,
+};
+
+/// Joints of a hand in the order of `XR_EXT_hand_tracking`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OpenXrHandJoint {
+    Palm,
+    Wrist,
+    ThumbMetacarpal,
+    ThumbProximal,
+    ThumbDistal,
+    ThumbTip,
+    IndexMetacarpal,
+    IndexProximal,
+    IndexIntermediate,
+    IndexDistal,
+    IndexTip,
+    MiddleMetacarpal,
+    MiddleProximal,
+    MiddleIntermediate,
+    MiddleDistal,
+    MiddleTip,
+    RingMetacarpal,
+    RingProximal,
+    RingIntermediate,
+    RingDistal,
+    RingTip,
+    LittleMetacarpal,
+    LittleProximal,
+    LittleIntermediate,
+    LittleDistal,
+    LittleTip,
+}
+
+/// Pose of a hand joint in the primary reference space
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OpenXrJointPose {
+    pub position: Vec3,
+    pub rotation: Quat,
+    /// Distance from the joint to the skin in meters
+    pub radius: f32,
+    pub tracked: bool,
+}
+
+/// Joint poses of a tracked hand from `XR_EXT_hand_tracking`
+///
+/// One entity per hand is spawned when the session is created if the system
+/// supports hand tracking, with an [`OpenXrHandJointEntity`] child per joint
+/// to attach meshes to. Poses are updated in `PreUpdate`, so systems in
+/// `Update` see the poses of the current frame. The entity is hidden while
+/// the hand is not tracked.
+#[derive(Component, Clone, Debug)]
+#[require(Transform, Visibility)]
+pub struct OpenXrHandTracking {
+    pub hand: OpenXrHand,
+    pub tracked: bool,
+    joints: [OpenXrJointPose; OpenXrHandJoint::COUNT],
+}
+
+/// Follows a joint of the parent [`OpenXrHandTracking`] entity
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Transform, Visibility)]
+pub struct OpenXrHandJointEntity {
+    pub joint: OpenXrHandJoint,
+}
+
+/// Hand trackers of the session, left and right
+#[derive(Resource)]
+pub(crate) struct OpenXrHandTrackers {
+    trackers: [openxr::HandTracker; 2],
+}
+
+pub struct OpenXrHandTrackingPlugin;
+
+impl OpenXrHandJoint {
+    pub const COUNT: usize = 26;
+
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Palm,
+        Self::Wrist,
+        Self::ThumbMetacarpal,
+        Self::ThumbProximal,
+        Self::ThumbDistal,
+        Self::ThumbTip,
+        Self::IndexMetacarpal,
+        Self::IndexProximal,
+        Self::IndexIntermediate,
+        Self::IndexDistal,
+        Self::IndexTip,
+        Self::MiddleMetacarpal,
+        Self::MiddleProximal,
+        Self::MiddleIntermediate,
+        Self::MiddleDistal,
+        Self::MiddleTip,
+        Self::RingMetacarpal,
+        Self::RingProximal,
+        Self::RingIntermediate,
+        Self::RingDistal,
+        Self::RingTip,
+        Self::LittleMetacarpal,
+        Self::LittleProximal,
+        Self::LittleIntermediate,
+        Self::LittleDistal,
+        Self::LittleTip,
+    ];
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    #[inline]
+    pub fn is_tip(&self) -> bool {
+        matches!(
+            self,
+            Self::ThumbTip | Self::IndexTip | Self::MiddleTip | Self::RingTip | Self::LittleTip
+        )
+    }
+}
+
+impl OpenXrJointPose {
+    #[inline]
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position).with_rotation(self.rotation)
+    }
+}
+
+impl OpenXrHandTracking {
+    fn new(hand: OpenXrHand) -> Self {
+        Self {
+            hand,
+            tracked: false,
+            joints: [OpenXrJointPose::default(); OpenXrHandJoint::COUNT],
+        }
+    }
+
+    #[inline]
+    pub fn joint(&self, joint: OpenXrHandJoint) -> &OpenXrJointPose {
+        &self.joints[joint.index()]
+    }
+
+    /// Poses of all joints, indexed by [`OpenXrHandJoint::index`]
+    #[inline]
+    pub fn joints(&self) -> &[OpenXrJointPose; OpenXrHandJoint::COUNT] {
+        &self.joints
+    }
+
+    /// Distance between the surfaces of the thumb and index finger tips,
+    /// `None` while either is not tracked
+    pub fn pinch_distance(&self) -> Option<f32> {
+        let thumb = self.joint(OpenXrHandJoint::ThumbTip);
+        let index = self.joint(OpenXrHandJoint::IndexTip);
+        (self.tracked && thumb.tracked && index.tracked).then(|| {
+            (thumb.position.distance(index.position) - thumb.radius - index.radius).max(0.0)
+        })
+    }
+}
+
+impl Plugin for OpenXrHandTrackingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OpenXrSchedules::SessionCreate,
+            create_hand_trackers.in_set(OpenXrRuntimeSystems::PostSessionCreate),
+        )
+        .add_systems(
+            PreUpdate,
+            (locate_hand_joints, update_hand_joint_entities)
+                .chain()
+                .run_if(resource_exists::<OpenXrHandTrackers>)
+                .run_if(resource_exists::<OpenXrFrameState>)
+                .run_if(openxr_in_state_synchronized),
+        );
+    }
+}
+
+fn create_hand_trackers(world: &mut World) {
+    debug_span!("OpenXrHandTrackingPlugin");
+    let supported = world
+        .get_resource::<OpenXrSystemInfo>()
+        .is_some_and(|info| info.enabled_extensions.ext_hand_tracking);
+    let instance = world.resource::<OpenXrInstance>();
+    if !supported
+        || !instance
+            .instance
+            .supports_hand_tracking(instance.system_id)
+            .unwrap_or(false)
+    {
+        info!("Hand tracking not supported");
+        return;
+    }
+
+    let session = world.resource::<OpenXrSession>();
+    let trackers = (|| -> openxr::Result<OpenXrHandTrackers> {
+        Ok(OpenXrHandTrackers {
+            trackers: [
+                session.create_hand_tracker(openxr::Hand::LEFT)?,
+                session.create_hand_tracker(openxr::Hand::RIGHT)?,
+            ],
+        })
+    })();
+
+    match trackers {
+        Ok(trackers) => {
+            world.insert_resource(trackers);
+            for hand in OpenXrHand::ALL {
+                world
+                    .spawn((
+                        Name::new(format!("Hand {:?}", hand)),
+                        OpenXrHandTracking::new(hand),
+                        Visibility::Hidden,
+                    ))
+                    .with_children(|parent| {
+                        for joint in OpenXrHandJoint::ALL {
+                            parent.spawn((
+                                Name::new(format!("{:?}", joint)),
+                                OpenXrHandJointEntity { joint },
+                            ));
+                        }
+                    });
+            }
+            info!("OpenXR hand trackers created");
+        }
+        Err(e) => warn!("Could not create hand trackers: {}", e),
+    }
+}
+
+fn locate_hand_joints(
+    trackers: Res<OpenXrHandTrackers>,
+    frame_state: Res<OpenXrFrameState>,
+    primary_reference_space: Res<OpenXrPrimaryReferenceSpace>,
+    session: Res<OpenXrSession>,
+    mut hands: Query<(&mut OpenXrHandTracking, &mut Visibility)>,
+) {
+    for (mut hand, mut visibility) in hands.iter_mut() {
+        let locations = session.locate_hand_joints(
+            &trackers.trackers[hand.hand.index()],
+            &primary_reference_space.0,
+            frame_state.0.predicted_display_time,
+        );
+        let tracked = match locations {
+            Ok(Some(locations)) => {
+                for (pose, location) in hand.joints.iter_mut().zip(locations.iter()) {
+                    let flags = location.location_flags;
+                    if flags.contains(openxr::sys::SpaceLocationFlags::POSITION_VALID) {
+                        let position = location.pose.position;
+                        pose.position = Vec3::new(position.x, position.y, position.z);
+                    }
+                    if flags.contains(openxr::sys::SpaceLocationFlags::ORIENTATION_VALID) {
+                        let orientation = location.pose.orientation;
+                        pose.rotation =
+                            quat(orientation.x, orientation.y, orientation.z, orientation.w);
+                    }
+                    pose.radius = location.radius;
+                    pose.tracked = flags
+                        .contains(openxr::sys::SpaceLocationFlags::POSITION_TRACKED)
+                        || flags.contains(openxr::sys::SpaceLocationFlags::ORIENTATION_TRACKED);
+                }
+                true
+            }
+            Ok(None) => false,
+            Err(e) => {
+                trace!("Could not locate hand joints {:?}: {}", hand.hand, e);
+                false
+            }
+        };
+
+        if !tracked {
+            for pose in hand.joints.iter_mut() {
+                pose.tracked = false;
+            }
+        }
+        if hand.tracked != tracked {
+            hand.tracked = tracked;
+            *visibility = if tracked {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
+
+fn update_hand_joint_entities(
+    hands: Query<&OpenXrHandTracking, Changed<OpenXrHandTracking>>,
+    mut joints: Query<(&OpenXrHandJointEntity, &ChildOf, &mut Transform)>,
+) {
+    for (joint, child_of, mut transform) in joints.iter_mut() {
+        if let Ok(hand) = hands.get(child_of.parent()) {
+            *transform = hand.joint(joint.joint).transform();
+        }
+    }
+}
@@ -18,11 +18,13 @@ use crate::windows::try_load_windows_oxr_runtime;
 use crate::{
     backends::{GraphicsInner, OpenXrGraphicsBackend, OpenXrGraphicsBackends},
     openxr::{
+        overlay::{OpenXrMainSessionVisibility, OpenXrOverlay},
         resources::OpenXrInstance,
         schedule::{
             OpenXrDeviceState, OpenXrMessageCreateSession, OpenXrRuntimeSystems, OpenXrSchedules,
             OpenXrSessionState, OpenXrSystemState,
         },
+        system::OpenXrSystemInfo,
     },
 };
 
@@ -30,6 +32,7 @@ use crate::{
 pub struct OpenXrInitPlugin {
     pub app_name: String,
     pub wgpu_settings: Option<WgpuSettings>,
+    pub overlay: Option<OpenXrOverlay>,
 }
 
 impl Plugin for OpenXrInitPlugin {
@@ -39,16 +42,26 @@ impl Plugin for OpenXrInitPlugin {
         build_system_sets(app);
 
         // Initialize OpenXR system and graphics backend
-        let (openxr_instance, graphics_backends) = self
+        let (openxr_instance, graphics_backends, system_info) = self
             .initialize(&self.app_name)
             .expect("Could not initialize OpenXR and WGPU instance");
 
+        if let Some(overlay) = self.overlay {
+            if system_info.enabled_extensions.extx_overlay {
+                app.insert_resource(overlay)
+                    .init_resource::<OpenXrMainSessionVisibility>();
+            } else {
+                warn!("Overlay session not supported. Run as a regular application");
+            }
+        }
+
         let render_resources = graphics_backends
             .get_render_resources()
             .expect("Could not get render resources");
 
         app.insert_resource(openxr_instance.clone())
             .insert_resource(graphics_backends)
+            .insert_resource(system_info)
             .add_plugins(RenderPlugin {
                 render_creation: RenderCreation::Manual(render_resources),
                 ..Default::default()
@@ -131,13 +144,15 @@ impl OpenXrInitPlugin {
     fn initialize(
         &self,
         app_name: &str,
-    ) -> anyhow::Result<(OpenXrInstance, OpenXrGraphicsBackends)> {
+    ) -> anyhow::Result<(OpenXrInstance, OpenXrGraphicsBackends, OpenXrSystemInfo)> {
         #[cfg(target_os = "windows")]
-        let result = try_load_windows_oxr_runtime();
+        let (entry, _lib) = try_load_windows_oxr_runtime()?;
+        // On Android the loader is packed into the APK next to the application
         #[cfg(not(target_os = "windows"))]
-        let result = unsafe { openxr::Entry::load() };
-
-        let (entry, _lib) = result.unwrap();
+        let entry = unsafe { openxr::Entry::load()? };
+        // Quest and other Android runtimes are found through the activity
+        #[cfg(target_os = "android")]
+        entry.initialize_android_loader()?;
 
         let default_settings = self.wgpu_settings.clone().unwrap_or_default();
         let wgpu_backends = default_settings.backends.unwrap_or(wgpu::Backends::VULKAN);
@@ -156,16 +171,29 @@ impl OpenXrInitPlugin {
             #[cfg(target_os = "android")]
             {
                 openxr_extensions.khr_opengl_es_enable = true;
-                openxr_extensions.khr_android_thread_settings = true;
                 openxr_extensions.khr_android_surface_swapchain = true;
-                openxr_extensions.khr_android_create_instance = true;
-                openxr_extensions.khr_loader_init_android = true;
                 // oxr_extension.fb_swapchain_update_state_android_surface = true;
                 // oxr_extension.oculus_android_session_state_enable = true;
             }
         } else {
             panic!("Unsupported backend");
         };
+        // Needed by every backend, Quest runs Vulkan
+        #[cfg(target_os = "android")]
+        {
+            openxr_extensions.khr_android_create_instance = true;
+            openxr_extensions.khr_loader_init_android = true;
+            openxr_extensions.khr_android_thread_settings = true;
+        }
+        openxr_extensions.fb_display_refresh_rate = true;
+        openxr_extensions.msft_controller_model = true;
+        openxr_extensions.ext_hand_tracking = true;
+        openxr_extensions.fb_passthrough = true;
+        openxr_extensions.fb_foveation = true;
+        openxr_extensions.fb_foveation_configuration = true;
+        openxr_extensions.fb_swapchain_update_state = true;
+        openxr_extensions.khr_composition_layer_cylinder = true;
+        openxr_extensions.extx_overlay = self.overlay.is_some();
         openxr_extensions = intersects_extensions(&entry, openxr_extensions)?;
 
         let application_info = ApplicationInfo {
@@ -182,19 +210,24 @@ impl OpenXrInitPlugin {
             instance.system(FormFactor::HEAD_MOUNTED_DISPLAY),
             instance.system(FormFactor::HANDHELD_DISPLAY),
         );
-        let system_id = match system_id_res {
-            (Ok(hmd_system_id), Ok(_)) | (Ok(hmd_system_id), Err(_)) => hmd_system_id,
-            (Err(_), Ok(handheld_system_id)) => handheld_system_id,
+        let (system_id, form_factor) = match system_id_res {
+            (Ok(hmd_system_id), Ok(_)) | (Ok(hmd_system_id), Err(_)) => {
+                (hmd_system_id, FormFactor::HEAD_MOUNTED_DISPLAY)
+            }
+            (Err(_), Ok(handheld_system_id)) => (handheld_system_id, FormFactor::HANDHELD_DISPLAY),
             (Err(_), Err(_)) => panic!("No xr system found"),
         };
 
-        let system_properties = instance.system_properties(system_id)?;
-        let instance_properties = instance.properties()?;
+        let system_info = OpenXrSystemInfo::new(
+            &instance,
+            system_id,
+            form_factor,
+            openxr_extensions,
+            entry.enumerate_extensions()?,
+        )?;
         info!(
             "OpenXR system: {}, runtime: {}-{}",
-            system_properties.system_name,
-            instance_properties.runtime_name,
-            instance_properties.runtime_version
+            system_info.system_name, system_info.runtime_name, system_info.runtime_version
         );
 
         let graphics_backends = if wgpu_backends.intersects(wgpu::Backends::VULKAN) {
@@ -227,7 +260,7 @@ impl OpenXrInitPlugin {
             system_id,
         };
 
-        Ok((openxr_instance, graphics_backends))
+        Ok((openxr_instance, graphics_backends, system_info))
     }
 }
 
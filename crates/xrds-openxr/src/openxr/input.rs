@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::openxr::{
+    controller::{OpenXrControllerActions, OpenXrHand},
+    session::OpenXrSession,
+};
+
+/// Buttons bound for the common controller profiles
+///
+/// `Primary` and `Secondary` are A/B on the right Touch controller and X/Y
+/// on the left one. On Vive controllers `Thumbstick` is the trackpad click.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OpenXrButton {
+    Primary,
+    Secondary,
+    Menu,
+    Thumbstick,
+}
+
+/// Input state of a controller in the current frame
+#[derive(Clone, Debug, Default)]
+pub struct OpenXrControllerInput {
+    /// The controller is bound and its actions are active
+    pub active: bool,
+    /// Pose of the palm in the primary reference space, `None` while not tracked
+    pub grip: Option<Transform>,
+    /// Pointing pose, forward along -Z, `None` while not tracked
+    pub aim: Option<Transform>,
+    /// Trigger pull in 0..1
+    pub trigger: f32,
+    /// Grip squeeze in 0..1
+    pub squeeze: f32,
+    /// Thumbstick or trackpad position in -1..1, +Y forward
+    pub thumbstick: Vec2,
+    pressed: [bool; OpenXrButton::COUNT],
+    previous: [bool; OpenXrButton::COUNT],
+}
+
+/// Vibration of a controller
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OpenXrHaptic {
+    /// Strength in 0..1
+    pub amplitude: f32,
+    /// `None` for the shortest pulse the runtime supports
+    pub duration: Option<Duration>,
+    /// Frequency in Hz, `None` lets the runtime choose
+    pub frequency: Option<f32>,
+}
+
+/// Controller input of the action system
+///
+/// Poses, buttons and axes of both hands are read in `PreUpdate`, so systems
+/// in `Update` see the input of the current frame. Actions are bound for the
+/// simple, Oculus Touch, Valve Index, Windows Mixed Reality and HTC Vive
+/// profiles and stay inactive while the application is not focused.
+/// Vibrations requested with [`OpenXrInput::vibrate`] are sent to the
+/// runtime at the end of the frame.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct OpenXrInput {
+    controllers: [OpenXrControllerInput; 2],
+    /// `None` stops the vibration of the hand
+    haptics: Vec<(OpenXrHand, Option<OpenXrHaptic>)>,
+}
+
+impl OpenXrButton {
+    pub const COUNT: usize = 4;
+
+    pub const ALL: [Self; Self::COUNT] =
+        [Self::Primary, Self::Secondary, Self::Menu, Self::Thumbstick];
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl OpenXrControllerInput {
+    #[inline]
+    pub fn pressed(&self, button: OpenXrButton) -> bool {
+        self.pressed[button.index()]
+    }
+
+    #[inline]
+    pub fn just_pressed(&self, button: OpenXrButton) -> bool {
+        self.pressed[button.index()] && !self.previous[button.index()]
+    }
+
+    #[inline]
+    pub fn just_released(&self, button: OpenXrButton) -> bool {
+        !self.pressed[button.index()] && self.previous[button.index()]
+    }
+
+    /// Starts a new frame of button states
+    fn set_pressed(&mut self, pressed: [bool; OpenXrButton::COUNT]) {
+        self.previous = self.pressed;
+        self.pressed = pressed;
+    }
+}
+
+impl OpenXrHaptic {
+    pub fn new(amplitude: f32, duration: Duration) -> Self {
+        Self {
+            amplitude,
+            duration: Some(duration),
+            frequency: None,
+        }
+    }
+
+    /// Shortest pulse the runtime supports, e.g. for a click
+    pub fn pulse(amplitude: f32) -> Self {
+        Self {
+            amplitude,
+            duration: None,
+            frequency: None,
+        }
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = Some(frequency);
+        self
+    }
+}
+
+impl OpenXrInput {
+    #[inline]
+    pub fn controller(&self, hand: OpenXrHand) -> &OpenXrControllerInput {
+        &self.controllers[hand.index()]
+    }
+
+    #[inline]
+    pub(crate) fn controller_mut(&mut self, hand: OpenXrHand) -> &mut OpenXrControllerInput {
+        &mut self.controllers[hand.index()]
+    }
+
+    #[inline]
+    pub fn pressed(&self, hand: OpenXrHand, button: OpenXrButton) -> bool {
+        self.controller(hand).pressed(button)
+    }
+
+    #[inline]
+    pub fn just_pressed(&self, hand: OpenXrHand, button: OpenXrButton) -> bool {
+        self.controller(hand).just_pressed(button)
+    }
+
+    #[inline]
+    pub fn just_released(&self, hand: OpenXrHand, button: OpenXrButton) -> bool {
+        self.controller(hand).just_released(button)
+    }
+
+    pub fn vibrate(&mut self, hand: OpenXrHand, haptic: OpenXrHaptic) {
+        self.haptics.push((hand, Some(haptic)));
+    }
+
+    pub fn stop_vibration(&mut self, hand: OpenXrHand) {
+        self.haptics.push((hand, None));
+    }
+}
+
+pub(crate) fn read_controller_input(
+    actions: Res<OpenXrControllerActions>,
+    session: Res<OpenXrSession>,
+    mut input: ResMut<OpenXrInput>,
+) {
+    for hand in OpenXrHand::ALL {
+        let path = actions.hand_paths[hand.index()];
+        let trigger = session.action_state(&actions.trigger, path);
+        let squeeze = session.action_state(&actions.squeeze, path);
+        let thumbstick = session.action_state(&actions.thumbstick, path);
+        let pressed = OpenXrButton::ALL.map(|button| {
+            session
+                .action_state(&actions.buttons[button.index()], path)
+                .is_ok_and(|state| state.is_active && state.current_state)
+        });
+
+        let controller = input.controller_mut(hand);
+        controller.active = trigger.as_ref().is_ok_and(|state| state.is_active)
+            || squeeze.as_ref().is_ok_and(|state| state.is_active)
+            || thumbstick.as_ref().is_ok_and(|state| state.is_active);
+        controller.trigger = trigger
+            .ok()
+            .filter(|state| state.is_active)
+            .map_or(0.0, |state| state.current_state);
+        controller.squeeze = squeeze
+            .ok()
+            .filter(|state| state.is_active)
+            .map_or(0.0, |state| state.current_state);
+        controller.thumbstick = thumbstick
+            .ok()
+            .filter(|state| state.is_active)
+            .map_or(Vec2::ZERO, |state| {
+                Vec2::new(state.current_state.x, state.current_state.y)
+            });
+        controller.set_pressed(pressed);
+    }
+}
+
+/// Clears the input while the application is not focused
+pub(crate) fn clear_controller_input(mut input: ResMut<OpenXrInput>) {
+    for hand in OpenXrHand::ALL {
+        let controller = input.controller_mut(hand);
+        controller.active = false;
+        controller.trigger = 0.0;
+        controller.squeeze = 0.0;
+        controller.thumbstick = Vec2::ZERO;
+        controller.set_pressed([false; OpenXrButton::COUNT]);
+    }
+    input.haptics.clear();
+}
+
+pub(crate) fn apply_haptics(
+    actions: Res<OpenXrControllerActions>,
+    session: Res<OpenXrSession>,
+    mut input: ResMut<OpenXrInput>,
+) {
+    for (hand, haptic) in input.haptics.drain(..) {
+        let path = actions.hand_paths[hand.index()];
+        let result = match haptic {
+            Some(haptic) => {
+                let vibration = openxr::HapticVibration::new()
+                    .amplitude(haptic.amplitude.clamp(0.0, 1.0))
+                    .duration(haptic.duration.map_or(openxr::Duration::MIN_HAPTIC, |d| {
+                        openxr::Duration::from_nanos(d.as_nanos() as i64)
+                    }))
+                    .frequency(
+                        haptic
+                            .frequency
+                            .unwrap_or(openxr::sys::FREQUENCY_UNSPECIFIED),
+                    );
+                session.apply_haptic_feedback(&actions.haptic, path, &vibration)
+            }
+            None => session.stop_haptic_feedback(&actions.haptic, path),
+        };
+        if let Err(e) = result {
+            trace!("Could not apply haptic feedback to {:?}: {}", hand, e);
+        }
+    }
+}
@@ -1,6 +1,10 @@
+use std::ptr;
+
 use crate::openxr::{
     frame::OpenXrFrameWaiter,
-    graphics::openxr_graphics,
+    graphics::{openxr_graphics, OpenXrGraphicsWrap},
+    helper::cvt,
+    overlay::OpenXrOverlay,
     resources::{OpenXrFrameStream, OpenXrInstance},
     session::{OpenXrSession, OpenXrSessionCreateInfo},
 };
@@ -22,6 +26,58 @@ impl OpenXrInstance {
         Ok((session, frame_waiter, frame_stream))
     }
 
+    /// Create a session composited on top of other applications with `XR_EXTX_overlay`
+    pub fn create_overlay_session(
+        &self,
+        info: &OpenXrSessionCreateInfo,
+        overlay: &OpenXrOverlay,
+    ) -> openxr::Result<(OpenXrSession, OpenXrFrameWaiter, OpenXrFrameStream)> {
+        let OpenXrGraphicsWrap::Vulkan(inner) = &info.0 else {
+            return Err(openxr::sys::Result::ERROR_FEATURE_UNSUPPORTED);
+        };
+
+        let overlay_info = openxr::sys::SessionCreateInfoOverlayEXTX {
+            ty: openxr::sys::SessionCreateInfoOverlayEXTX::TYPE,
+            next: ptr::null(),
+            create_flags: openxr::sys::OverlaySessionCreateFlagsEXTX::EMPTY,
+            session_layers_placement: overlay.placement,
+        };
+        let graphics_binding = openxr::sys::GraphicsBindingVulkanKHR {
+            ty: openxr::sys::GraphicsBindingVulkanKHR::TYPE,
+            next: &overlay_info as *const _ as *const _,
+            instance: inner.instance,
+            physical_device: inner.physical_device,
+            device: inner.device,
+            queue_family_index: inner.queue_family_index,
+            queue_index: inner.queue_index,
+        };
+        let create_info = openxr::sys::SessionCreateInfo {
+            ty: openxr::sys::SessionCreateInfo::TYPE,
+            next: &graphics_binding as *const _ as *const _,
+            create_flags: openxr::sys::SessionCreateFlags::EMPTY,
+            system_id: self.system_id,
+        };
+
+        let mut handle = openxr::sys::Session::NULL;
+        unsafe {
+            cvt((self.instance.fp().create_session)(
+                self.instance.as_raw(),
+                &create_info,
+                &mut handle,
+            ))?;
+            let (session, frame_waiter, frame_stream) = openxr::Session::<openxr::Vulkan>::from_raw(
+                self.instance.clone(),
+                handle,
+                Box::new(()),
+            );
+            Ok((
+                OpenXrSession::from_inner(session),
+                OpenXrFrameWaiter::from_inner(frame_waiter),
+                OpenXrFrameStream::from_inner(frame_stream),
+            ))
+        }
+    }
+
     #[inline]
     #[allow(dead_code)]
     pub fn create_action_set(
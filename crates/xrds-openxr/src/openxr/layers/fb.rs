@@ -1,16 +1,52 @@
-use crate::openxr::layers::OpenXrCompositionLayer;
+use std::ptr::null;
+
+use openxr::{sys::CompositionLayerPassthroughFB, CompositionLayerFlags};
+
+use crate::openxr::layers::{OpenXrCompositionLayer, OpenXrLayerBuilder};
 
-#[allow(unused)]
 #[derive(Clone)]
-pub struct OpenXrCompositionLayerPassthroughFB {}
+pub struct OpenXrCompositionLayerPassthroughFB {
+    pub inner: openxr::sys::CompositionLayerPassthroughFB,
+}
+
+/// Passthrough layer of `XR_FB_passthrough` below the projection layer
+#[derive(Clone)]
+pub struct OpenXrCompositionLayerPassthroughFBBuilder {
+    /// Raw `XrPassthroughLayerFB` handle
+    pub layer: u64,
+}
 
 #[allow(unused)]
 #[derive(Clone)]
 pub struct OpenXrCompositionLayerAlphaBlendFB {}
 
+impl OpenXrCompositionLayerPassthroughFB {
+    pub fn new(layer: u64) -> Self {
+        Self {
+            inner: CompositionLayerPassthroughFB {
+                ty: CompositionLayerPassthroughFB::TYPE,
+                next: null(),
+                flags: CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA,
+                // Reconstruction layers are not placed in a space
+                space: openxr::sys::Space::NULL,
+                layer_handle: openxr::sys::PassthroughLayerFB::from_raw(layer),
+            },
+        }
+    }
+}
+
 impl OpenXrCompositionLayer for OpenXrCompositionLayerPassthroughFB {
     fn as_raw(&self) -> &openxr::sys::CompositionLayerBaseHeader {
-        unimplemented!()
+        unsafe {
+            #[allow(clippy::missing_transmute_annotations)]
+            std::mem::transmute(&self.inner)
+        }
+    }
+}
+
+impl OpenXrLayerBuilder for OpenXrCompositionLayerPassthroughFBBuilder {
+    fn build(&self, _world: &bevy::ecs::world::World) -> Box<dyn OpenXrCompositionLayer> {
+        Box::new(OpenXrCompositionLayerPassthroughFB::new(self.layer))
     }
 }
 
@@ -0,0 +1,49 @@
+use std::ptr::null;
+
+use openxr::{
+    sys::{CompositionLayerCylinderKHR, SwapchainSubImage},
+    CompositionLayerFlags, EyeVisibility, Posef,
+};
+
+use crate::openxr::{layers::OpenXrCompositionLayer, resources::OpenXrSpace};
+
+/// Panel curved around the pose of `XR_KHR_composition_layer_cylinder`
+#[derive(Clone)]
+pub struct OpenXrCompositionLayerCylinderKHR {
+    pub inner: openxr::sys::CompositionLayerCylinderKHR,
+}
+
+impl OpenXrCompositionLayerCylinderKHR {
+    pub fn new(
+        space: &OpenXrSpace,
+        sub_image: SwapchainSubImage,
+        pose: Posef,
+        radius: f32,
+        central_angle: f32,
+        aspect_ratio: f32,
+    ) -> Self {
+        Self {
+            inner: CompositionLayerCylinderKHR {
+                ty: CompositionLayerCylinderKHR::TYPE,
+                next: null(),
+                layer_flags: CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA,
+                space: openxr::sys::Space::from_raw(space.0),
+                eye_visibility: EyeVisibility::BOTH,
+                sub_image,
+                pose,
+                radius,
+                central_angle,
+                aspect_ratio,
+            },
+        }
+    }
+}
+
+impl OpenXrCompositionLayer for OpenXrCompositionLayerCylinderKHR {
+    fn as_raw(&self) -> &openxr::sys::CompositionLayerBaseHeader {
+        unsafe {
+            #[allow(clippy::missing_transmute_annotations)]
+            std::mem::transmute(&self.inner)
+        }
+    }
+}
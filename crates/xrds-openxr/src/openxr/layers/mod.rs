@@ -4,6 +4,7 @@ pub mod builder;
 pub mod fb;
 pub mod khr;
 pub mod projection;
+pub mod quad;
 
 pub trait OpenXrLayerBuilder {
     fn build(&self, world: &World) -> Box<dyn OpenXrCompositionLayer>;
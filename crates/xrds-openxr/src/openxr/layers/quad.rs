@@ -0,0 +1,45 @@
+use std::ptr::null;
+
+use openxr::{
+    sys::{CompositionLayerQuad, SwapchainSubImage},
+    CompositionLayerFlags, Extent2Df, EyeVisibility, Posef,
+};
+
+use crate::openxr::{layers::OpenXrCompositionLayer, resources::OpenXrSpace};
+
+/// Flat panel placed in a space, sampled from its own swapchain
+#[derive(Clone)]
+pub struct OpenXrCompositionLayerQuad {
+    pub inner: openxr::sys::CompositionLayerQuad,
+}
+
+impl OpenXrCompositionLayerQuad {
+    pub fn new(
+        space: &OpenXrSpace,
+        sub_image: SwapchainSubImage,
+        pose: Posef,
+        size: Extent2Df,
+    ) -> Self {
+        Self {
+            inner: CompositionLayerQuad {
+                ty: CompositionLayerQuad::TYPE,
+                next: null(),
+                layer_flags: CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA,
+                space: openxr::sys::Space::from_raw(space.0),
+                eye_visibility: EyeVisibility::BOTH,
+                sub_image,
+                pose,
+                size,
+            },
+        }
+    }
+}
+
+impl OpenXrCompositionLayer for OpenXrCompositionLayerQuad {
+    fn as_raw(&self) -> &openxr::sys::CompositionLayerBaseHeader {
+        unsafe {
+            #[allow(clippy::missing_transmute_annotations)]
+            std::mem::transmute(&self.inner)
+        }
+    }
+}
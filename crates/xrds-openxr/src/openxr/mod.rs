@@ -1,15 +1,41 @@
 pub(crate) mod camera;
+pub(crate) mod controller;
+pub(crate) mod foveation;
 pub(crate) mod frame;
 pub(crate) mod graphics;
+pub(crate) mod hand_tracking;
 pub(crate) mod helper;
 pub(crate) mod init;
+pub(crate) mod input;
 pub(crate) mod instance;
 pub(crate) mod layers;
+pub(crate) mod overlay;
+pub(crate) mod panel;
+pub(crate) mod passthrough;
 pub(crate) mod reference_space;
+pub(crate) mod refresh_rate;
 pub(crate) mod render;
 pub(crate) mod resources;
 pub(crate) mod schedule;
 pub(crate) mod session;
 pub(crate) mod swapchain;
+pub(crate) mod system;
 
-pub use camera::OpenXrCamera;
+pub use camera::{OpenXrCamera, OpenXrCameraIndex, OpenXrIpdOffset};
+pub use controller::{
+    OpenXrController, OpenXrControllerModel, OpenXrControllerModels, OpenXrHand,
+    OPENXR_ASSET_SOURCE,
+};
+pub use foveation::{OpenXrFoveation, OpenXrFoveationLevel};
+pub use hand_tracking::{
+    OpenXrHandJoint, OpenXrHandJointEntity, OpenXrHandTracking, OpenXrJointPose,
+};
+pub use input::{OpenXrButton, OpenXrControllerInput, OpenXrHaptic, OpenXrInput};
+pub use overlay::{OpenXrMainSessionVisibility, OpenXrOverlay};
+pub use panel::{OpenXrLayerPanel, OpenXrPanelShape};
+pub use passthrough::{OpenXrEnvironmentBlend, OpenXrPassthrough};
+pub use reference_space::OpenXrStageBounds;
+pub use refresh_rate::{nearest_refresh_rate, OpenXrRefreshRate};
+pub use schedule::{OpenXrDeviceState, OpenXrSessionState};
+pub use session::OpenXrSession;
+pub use system::OpenXrSystemInfo;
@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+
+/// Runs the application as an overlay on top of other XR applications
+///
+/// Requires `XR_EXTX_overlay`, the application runs as a regular session if
+/// the runtime does not support it. Overlays are composited over the main
+/// application in order of `placement`, higher placements on top. Only the
+/// Vulkan backend can create overlay sessions.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpenXrOverlay {
+    pub placement: u32,
+}
+
+/// Visibility of the main application below an overlay
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpenXrMainSessionVisibility {
+    pub visible: bool,
+    /// Main application layers are submitted with depth information
+    pub depth_enabled: bool,
+}
+
+impl Default for OpenXrMainSessionVisibility {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            depth_enabled: false,
+        }
+    }
+}
+
+impl OpenXrOverlay {
+    pub fn new(placement: u32) -> Self {
+        Self { placement }
+    }
+}
@@ -0,0 +1,345 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use bevy::{
+    camera::{ManualTextureViewHandle, RenderTarget},
+    ecs::entity::EntityHashMap,
+    prelude::*,
+    render::{texture::ManualTextureView, MainWorld, Render, RenderApp},
+};
+use openxr::{sys::SwapchainSubImage, Extent2Df, Posef};
+use wgpu::Extent3d;
+
+use crate::{
+    backends::OpenXrGraphicsBackends,
+    openxr::{
+        layers::{
+            khr::OpenXrCompositionLayerCylinderKHR, quad::OpenXrCompositionLayerQuad,
+            OpenXrCompositionLayer,
+        },
+        render::openxr_end_frame,
+        resources::{OpenXrPrimaryReferenceSpace, OpenXrSwapchain},
+        schedule::{OpenXrRenderSystems, OpenXrSessionState},
+        session::OpenXrSession,
+        swapchain::create_swapchain_images,
+        system::OpenXrSystemInfo,
+    },
+};
+
+const OPENXR_PANEL_VIEW_INDEX_BASE: u32 = u32::from_le_bytes(*b"OPXL");
+static NEXT_PANEL_INDEX: AtomicU32 = AtomicU32::new(0);
+
+/// Panel format, like the projection swapchain
+const PANEL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpenXrPanelShape {
+    /// Flat rectangle of `size` meters
+    Quad { size: Vec2 },
+    /// Section of a cylinder whose axis runs through the entity along Y,
+    /// `central_angle` radians wide. The height follows the aspect ratio of
+    /// the resolution
+    Cylinder { radius: f32, central_angle: f32 },
+}
+
+/// UI panel submitted as its own quad or cylinder composition layer
+///
+/// The compositor samples the swapchain of the panel once when it distorts
+/// the frame for the lenses, so text stays sharper than when it is rendered
+/// into the projection. Render into the panel with a camera targeting
+/// [`Self::render_target`]. The panel faces +Z of the [`GlobalTransform`] of
+/// its entity, given in the primary reference space, and is drawn over the
+/// projection layer. Cylinders are skipped when the runtime does not support
+/// `XR_KHR_composition_layer_cylinder`.
+#[derive(Component, Clone, Debug)]
+#[require(Transform)]
+pub struct OpenXrLayerPanel {
+    pub shape: OpenXrPanelShape,
+    resolution: UVec2,
+    handle: ManualTextureViewHandle,
+}
+
+/// Swapchain of a panel, waiting to be moved to the render world
+#[derive(Component)]
+struct OpenXrPendingPanelSwapchain(Option<OpenXrPanelSwapchain>);
+
+struct OpenXrPanelSwapchain {
+    swapchain: OpenXrSwapchain,
+    images: Vec<wgpu::TextureView>,
+    resolution: UVec2,
+    handle: ManualTextureViewHandle,
+}
+
+/// Panel swapchains by panel entity of the main world
+#[derive(Resource, Default)]
+struct OpenXrPanelSwapchains(EntityHashMap<OpenXrPanelSwapchain>);
+
+/// Panels extracted for the current frame
+#[derive(Resource, Default)]
+struct OpenXrPanelLayers(Vec<(Entity, OpenXrPanelShape, Posef)>);
+
+pub struct OpenXrLayerPanelPlugin;
+
+impl OpenXrLayerPanel {
+    /// Flat panel of `size` meters with `resolution` pixels
+    pub fn quad(size: Vec2, resolution: UVec2) -> Self {
+        Self::new(OpenXrPanelShape::Quad { size }, resolution)
+    }
+
+    /// Curved panel `radius` meters around the entity
+    pub fn cylinder(radius: f32, central_angle: f32, resolution: UVec2) -> Self {
+        Self::new(
+            OpenXrPanelShape::Cylinder {
+                radius,
+                central_angle,
+            },
+            resolution,
+        )
+    }
+
+    pub fn new(shape: OpenXrPanelShape, resolution: UVec2) -> Self {
+        let index = NEXT_PANEL_INDEX.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shape,
+            resolution: resolution.max(UVec2::ONE),
+            handle: ManualTextureViewHandle(OPENXR_PANEL_VIEW_INDEX_BASE + index),
+        }
+    }
+
+    #[inline]
+    pub fn resolution(&self) -> UVec2 {
+        self.resolution
+    }
+
+    /// Target for the cameras drawing the content of the panel
+    #[inline]
+    pub fn render_target(&self) -> RenderTarget {
+        RenderTarget::TextureView(self.handle)
+    }
+}
+
+impl Plugin for OpenXrLayerPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            create_panel_swapchains.run_if(resource_exists::<OpenXrSession>),
+        );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .init_resource::<OpenXrPanelSwapchains>()
+            .init_resource::<OpenXrPanelLayers>()
+            .add_systems(ExtractSchedule, extract_panels)
+            .add_systems(
+                Render,
+                acquire_panel_images
+                    .in_set(OpenXrRenderSystems::PreRender)
+                    .run_if(resource_equals(OpenXrSessionState::Running)),
+            )
+            .add_systems(
+                Render,
+                release_panel_images
+                    .before(openxr_end_frame)
+                    .in_set(OpenXrRenderSystems::PostRender)
+                    .run_if(resource_equals(OpenXrSessionState::Running)),
+            );
+    }
+}
+
+fn create_panel_swapchains(world: &mut World) {
+    debug_span!("OpenXrLayerPanelPlugin");
+    let cylinder_supported = world
+        .resource::<OpenXrSystemInfo>()
+        .enabled_extensions
+        .khr_composition_layer_cylinder;
+
+    let mut query =
+        world.query_filtered::<(Entity, &OpenXrLayerPanel), Without<OpenXrPendingPanelSwapchain>>();
+    let panels: Vec<_> = query
+        .iter(world)
+        .map(|(entity, panel)| (entity, panel.clone()))
+        .collect();
+    for (entity, panel) in panels {
+        if matches!(panel.shape, OpenXrPanelShape::Cylinder { .. }) && !cylinder_supported {
+            warn!(
+                "Cylinder layers not supported. Panel {} is not shown",
+                entity
+            );
+            world
+                .entity_mut(entity)
+                .insert(OpenXrPendingPanelSwapchain(None));
+            continue;
+        }
+
+        let size = Extent3d {
+            width: panel.resolution.x,
+            height: panel.resolution.y,
+            depth_or_array_layers: 1,
+        };
+        let (swapchain, images) = create_swapchain_images(
+            world.resource::<OpenXrSession>(),
+            world.resource::<OpenXrGraphicsBackends>(),
+            PANEL_FORMAT,
+            size,
+            1,
+        );
+        let images: Vec<_> = images
+            .into_iter()
+            .filter_map(|(_, mut views)| views.pop())
+            .collect();
+
+        // Cameras are extracted with the size of the main world view
+        world.resource_mut::<ManualTextureViews>().insert(
+            panel.handle,
+            ManualTextureView {
+                texture_view: images[0].clone().into(),
+                size: panel.resolution,
+                format: PANEL_FORMAT,
+            },
+        );
+        world
+            .entity_mut(entity)
+            .insert(OpenXrPendingPanelSwapchain(Some(OpenXrPanelSwapchain {
+                swapchain,
+                images,
+                resolution: panel.resolution,
+                handle: panel.handle,
+            })));
+        info!(
+            "OpenXR panel {} created with {}x{} pixels",
+            entity, panel.resolution.x, panel.resolution.y
+        );
+    }
+}
+
+fn extract_panels(
+    mut main_world: ResMut<MainWorld>,
+    mut swapchains: ResMut<OpenXrPanelSwapchains>,
+    mut layers: ResMut<OpenXrPanelLayers>,
+) {
+    let mut query = main_world.query::<(
+        Entity,
+        &OpenXrLayerPanel,
+        &GlobalTransform,
+        &mut OpenXrPendingPanelSwapchain,
+    )>();
+    layers.0.clear();
+    for (entity, panel, transform, mut pending) in query.iter_mut(&mut main_world) {
+        if let Some(swapchain) = pending.0.take() {
+            swapchains.0.insert(entity, swapchain);
+        }
+        if swapchains.0.contains_key(&entity) {
+            layers.0.push((entity, panel.shape, to_pose(transform)));
+        }
+    }
+
+    // Swapchains of despawned panels are destroyed
+    let layers = &layers.0;
+    swapchains
+        .0
+        .retain(|entity, _| layers.iter().any(|(panel, ..)| panel == entity));
+}
+
+fn to_pose(transform: &GlobalTransform) -> Posef {
+    let (_, rotation, translation) = transform.to_scale_rotation_translation();
+    Posef {
+        orientation: openxr::Quaternionf {
+            x: rotation.x,
+            y: rotation.y,
+            z: rotation.z,
+            w: rotation.w,
+        },
+        position: openxr::Vector3f {
+            x: translation.x,
+            y: translation.y,
+            z: translation.z,
+        },
+    }
+}
+
+fn acquire_panel_images(
+    mut swapchains: ResMut<OpenXrPanelSwapchains>,
+    mut manual_texture_views: ResMut<ManualTextureViews>,
+) {
+    debug_span!("OpenXrLayerPanelPlugin");
+    for panel in swapchains.0.values_mut() {
+        let index = panel
+            .swapchain
+            .acquire_image()
+            .expect("Could not acquire panel swapchain image");
+        panel
+            .swapchain
+            .wait_image(openxr::Duration::INFINITE)
+            .expect("Could not wait panel swapchain image");
+        manual_texture_views.insert(
+            panel.handle,
+            ManualTextureView {
+                texture_view: panel.images[index as usize].clone().into(),
+                size: panel.resolution,
+                format: PANEL_FORMAT,
+            },
+        );
+    }
+}
+
+fn release_panel_images(mut swapchains: ResMut<OpenXrPanelSwapchains>) {
+    debug_span!("OpenXrLayerPanelPlugin");
+    for panel in swapchains.0.values_mut() {
+        panel
+            .swapchain
+            .release_image()
+            .expect("Could not release panel swapchain image");
+    }
+}
+
+/// Layers of the panels, drawn over the layers of the session
+pub(crate) fn build_panel_layers(world: &World) -> Vec<Box<dyn OpenXrCompositionLayer>> {
+    let (Some(layers), Some(swapchains), Some(reference_space)) = (
+        world.get_resource::<OpenXrPanelLayers>(),
+        world.get_resource::<OpenXrPanelSwapchains>(),
+        world.get_resource::<OpenXrPrimaryReferenceSpace>(),
+    ) else {
+        return vec![];
+    };
+
+    layers
+        .0
+        .iter()
+        .filter_map(|(entity, shape, pose)| {
+            let panel = swapchains.0.get(entity)?;
+            let sub_image = SwapchainSubImage {
+                swapchain: panel.swapchain.as_raw(),
+                image_rect: openxr::Rect2Di {
+                    offset: openxr::Offset2Di { x: 0, y: 0 },
+                    extent: openxr::Extent2Di {
+                        width: panel.resolution.x as i32,
+                        height: panel.resolution.y as i32,
+                    },
+                },
+                image_array_index: 0,
+            };
+            let layer: Box<dyn OpenXrCompositionLayer> = match *shape {
+                OpenXrPanelShape::Quad { size } => Box::new(OpenXrCompositionLayerQuad::new(
+                    &reference_space.0,
+                    sub_image,
+                    *pose,
+                    Extent2Df {
+                        width: size.x,
+                        height: size.y,
+                    },
+                )),
+                OpenXrPanelShape::Cylinder {
+                    radius,
+                    central_angle,
+                } => Box::new(OpenXrCompositionLayerCylinderKHR::new(
+                    &reference_space.0,
+                    sub_image,
+                    *pose,
+                    radius,
+                    central_angle,
+                    panel.resolution.x as f32 / panel.resolution.y as f32,
+                )),
+            };
+            Some(layer)
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use openxr::EnvironmentBlendMode;
+
+use crate::openxr::{
+    layers::{
+        builder::OpenXrCompositionLayerBuilder, fb::OpenXrCompositionLayerPassthroughFBBuilder,
+    },
+    resources::OpenXrEnvironmentBlendModes,
+    schedule::{OpenXrRuntimeSystems, OpenXrSchedules},
+    session::{init_render_resources, OpenXrSession},
+};
+
+/// How rendered content is combined with the real world
+///
+/// Read when the session is created. Without [`Self::Opaque`] the XR cameras
+/// clear to transparent black, so the real world shows wherever nothing is
+/// rendered and through translucent content. If the device can not show the
+/// requested blend, the first blend mode of the runtime is used.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OpenXrEnvironmentBlend {
+    #[default]
+    Opaque,
+    /// Optical see-through displays, black is transparent
+    Additive,
+    /// Video see-through by the runtime, blended by the alpha of the content
+    AlphaBlend,
+    /// Camera passthrough under the content. Uses a `XR_FB_passthrough`
+    /// layer if available, e.g. on Quest, otherwise alpha or additive blending
+    Passthrough,
+}
+
+/// `XR_FB_passthrough` layer below the rendered content
+///
+/// Inserted when the session is created with
+/// [`OpenXrEnvironmentBlend::Passthrough`] and the runtime supports the
+/// extension. Set `enabled` to pause the cameras, the background is black then.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpenXrPassthrough {
+    pub enabled: bool,
+}
+
+/// Raw handles of the passthrough, destroyed with the session
+#[derive(Resource)]
+struct OpenXrPassthroughHandles {
+    passthrough: u64,
+    layer: u64,
+    running: bool,
+}
+
+pub struct OpenXrPassthroughPlugin;
+
+impl OpenXrEnvironmentBlend {
+    /// Blend mode of the frames, `None` if the runtime supports none for this blend
+    pub(crate) fn blend_mode(
+        &self,
+        supported: &[EnvironmentBlendMode],
+        fb_passthrough: bool,
+    ) -> Option<EnvironmentBlendMode> {
+        let preferred: &[EnvironmentBlendMode] = match self {
+            Self::Opaque => &[EnvironmentBlendMode::OPAQUE],
+            Self::Additive => &[EnvironmentBlendMode::ADDITIVE],
+            Self::AlphaBlend => &[EnvironmentBlendMode::ALPHA_BLEND],
+            // Passthrough layers are composited in opaque mode
+            Self::Passthrough if fb_passthrough => &[EnvironmentBlendMode::OPAQUE],
+            Self::Passthrough => &[
+                EnvironmentBlendMode::ALPHA_BLEND,
+                EnvironmentBlendMode::ADDITIVE,
+            ],
+        };
+        preferred
+            .iter()
+            .find(|mode| supported.contains(mode))
+            .copied()
+    }
+}
+
+impl Plugin for OpenXrPassthroughPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OpenXrSchedules::SessionCreate,
+            create_passthrough
+                .before(init_render_resources)
+                .in_set(OpenXrRuntimeSystems::PostSessionCreate),
+        )
+        .add_systems(
+            PreUpdate,
+            update_passthrough.run_if(resource_exists_and_changed::<OpenXrPassthrough>),
+        );
+    }
+}
+
+fn create_passthrough(world: &mut World) {
+    debug_span!("OpenXrPassthroughPlugin");
+    let requested = world.get_resource::<OpenXrEnvironmentBlend>()
+        == Some(&OpenXrEnvironmentBlend::Passthrough);
+    let opaque = world
+        .resource::<OpenXrEnvironmentBlendModes>()
+        .current_blend_mode
+        == EnvironmentBlendMode::OPAQUE;
+    if !requested || !opaque {
+        return;
+    }
+
+    match world.resource::<OpenXrSession>().create_passthrough_layer() {
+        Ok((passthrough, layer)) => {
+            // Below the projection layer
+            world
+                .resource_mut::<OpenXrCompositionLayerBuilder>()
+                .insert_layer(
+                    0,
+                    Box::new(OpenXrCompositionLayerPassthroughFBBuilder { layer }),
+                );
+            world.insert_resource(OpenXrPassthroughHandles {
+                passthrough,
+                layer,
+                running: true,
+            });
+            world.insert_resource(OpenXrPassthrough { enabled: true });
+            info!("OpenXR passthrough layer created");
+        }
+        Err(e) => warn!("Could not create passthrough layer: {}", e),
+    }
+}
+
+fn update_passthrough(
+    passthrough: Res<OpenXrPassthrough>,
+    session: Res<OpenXrSession>,
+    mut handles: ResMut<OpenXrPassthroughHandles>,
+) {
+    if handles.running == passthrough.enabled {
+        return;
+    }
+    match session.set_passthrough_running(handles.passthrough, handles.layer, passthrough.enabled) {
+        Ok(()) => handles.running = passthrough.enabled,
+        Err(e) => warn!(
+            "Could not set passthrough running={}: {}",
+            passthrough.enabled, e
+        ),
+    }
+}
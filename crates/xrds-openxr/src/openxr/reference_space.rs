@@ -6,11 +6,36 @@ use crate::openxr::{
     session::OpenXrSession,
 };
 
+/// Play area of the stage reference space, a rectangle centered at its origin
+///
+/// `size` is `None` if the runtime has no stage space or the user has not set
+/// up a play area. Updated when the runtime changes the stage.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct OpenXrStageBounds {
+    /// Width along x and depth along z in meters
+    pub size: Option<Vec2>,
+}
+
 pub struct OpenXrReferenceSpacePlugin;
 
+impl OpenXrStageBounds {
+    /// Corners of the play area on the floor (x, z)
+    pub fn polygon(&self) -> Option<Vec<Vec2>> {
+        self.size.map(|size| {
+            let half = size * 0.5;
+            vec![
+                Vec2::new(-half.x, -half.y),
+                Vec2::new(half.x, -half.y),
+                Vec2::new(half.x, half.y),
+                Vec2::new(-half.x, half.y),
+            ]
+        })
+    }
+}
+
 impl Plugin for OpenXrReferenceSpacePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
+        app.init_resource::<OpenXrStageBounds>().add_systems(
             OpenXrSchedules::SessionCreate,
             create_reference_space.in_set(OpenXrRuntimeSystems::PostSessionCreate),
         );
@@ -38,7 +63,13 @@ fn create_reference_space(world: &mut World) {
         .create_reference_space(primary_space_type, openxr::Posef::IDENTITY)
         .expect("Could not create primary reference space");
 
-    world.insert_resource(OpenXrPrimaryReferenceSpace(primary_space));
+    world.insert_resource(OpenXrPrimaryReferenceSpace(
+        primary_space,
+        primary_space_type,
+    ));
+    if primary_space_type == openxr::ReferenceSpaceType::STAGE {
+        update_stage_bounds(world);
+    }
     info!(
         "OpenXR primary reference space({:?}) and bounds rect created",
         primary_space_type
@@ -50,3 +81,18 @@ fn create_reference_space(world: &mut World) {
 
     info!("OpenXR reference space and bounds rect created");
 }
+
+pub(crate) fn update_stage_bounds(world: &mut World) {
+    let session = world.resource::<OpenXrSession>();
+    let size = match session.reference_space_bounds_rect(openxr::ReferenceSpaceType::STAGE) {
+        Ok(bounds) => bounds.map(|bounds| Vec2::new(bounds.width, bounds.height)),
+        Err(e) => {
+            warn!("Could not get stage bounds: {}", e);
+            None
+        }
+    };
+    info!("Stage bounds: {:?}", size);
+    world
+        .resource_mut::<OpenXrStageBounds>()
+        .set_if_neq(OpenXrStageBounds { size });
+}
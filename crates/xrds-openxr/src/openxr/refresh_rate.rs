@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::openxr::{
+    schedule::{OpenXrRuntimeSystems, OpenXrSchedules},
+    session::OpenXrSession,
+    system::OpenXrSystemInfo,
+};
+
+/// Display refresh rate of the device
+///
+/// Call [`OpenXrRefreshRate::request`] to ask the runtime for another rate,
+/// e.g. 72 Hz for cinematic scenes or 120 Hz for fast interaction. A rate the
+/// device does not support falls back to the nearest supported one. Requests
+/// are ignored when the runtime does not support `XR_FB_display_refresh_rate`.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct OpenXrRefreshRate {
+    requested: Option<f32>,
+    current: Option<f32>,
+}
+
+pub struct OpenXrRefreshRatePlugin;
+
+impl OpenXrRefreshRate {
+    #[inline]
+    pub fn request(&mut self, refresh_rate: f32) {
+        self.requested = Some(refresh_rate);
+    }
+
+    #[inline]
+    pub fn requested(&self) -> Option<f32> {
+        self.requested
+    }
+
+    /// Refresh rate in Hz, `None` if the runtime can not report it
+    #[inline]
+    pub fn current(&self) -> Option<f32> {
+        self.current
+    }
+}
+
+/// Supported rate closest to `requested`
+pub fn nearest_refresh_rate(supported: &[f32], requested: f32) -> Option<f32> {
+    supported
+        .iter()
+        .copied()
+        .min_by(|a, b| (a - requested).abs().total_cmp(&(b - requested).abs()))
+}
+
+impl Plugin for OpenXrRefreshRatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OpenXrRefreshRate>()
+            .add_systems(
+                OpenXrSchedules::SessionCreate,
+                read_refresh_rate.in_set(OpenXrRuntimeSystems::PostSessionCreate),
+            )
+            .add_systems(
+                OpenXrSchedules::Update,
+                apply_refresh_rate
+                    .run_if(resource_changed::<OpenXrRefreshRate>)
+                    .in_set(OpenXrRuntimeSystems::UpdateSessionStates),
+            );
+    }
+}
+
+fn refresh_rate_supported(world: &World) -> bool {
+    world
+        .get_resource::<OpenXrSystemInfo>()
+        .is_some_and(|info| info.enabled_extensions.fb_display_refresh_rate)
+}
+
+fn read_refresh_rate(world: &mut World) {
+    debug_span!("OpenXrRefreshRatePlugin");
+    if !refresh_rate_supported(world) {
+        info!("Display refresh rate extension not supported");
+        return;
+    }
+
+    let session = world.resource::<OpenXrSession>();
+    match session.get_display_refresh_rate() {
+        Ok(refresh_rate) => {
+            info!("Display refresh rate: {} Hz", refresh_rate);
+            world
+                .resource_mut::<OpenXrRefreshRate>()
+                .bypass_change_detection()
+                .current = Some(refresh_rate);
+        }
+        Err(e) => warn!("Could not get display refresh rate: {}", e),
+    }
+}
+
+fn apply_refresh_rate(world: &mut World) {
+    debug_span!("OpenXrRefreshRatePlugin");
+    let refresh_rate = world.resource::<OpenXrRefreshRate>();
+    let (Some(requested), current) = (refresh_rate.requested, refresh_rate.current) else {
+        return;
+    };
+    if !refresh_rate_supported(world) || !world.contains_resource::<OpenXrSession>() {
+        return;
+    }
+
+    let supported = &world.resource::<OpenXrSystemInfo>().refresh_rates;
+    let Some(target) = nearest_refresh_rate(supported, requested) else {
+        return;
+    };
+    if current == Some(target) {
+        return;
+    }
+    if target != requested {
+        warn!(
+            "Display refresh rate {} Hz not supported. Use {} Hz instead",
+            requested, target
+        );
+    }
+
+    let session = world.resource::<OpenXrSession>();
+    if let Err(e) = session.request_display_refresh_rate(target) {
+        warn!(
+            "Could not request display refresh rate {} Hz: {}",
+            target, e
+        );
+        return;
+    }
+    let current = session.get_display_refresh_rate().unwrap_or(target);
+    info!("Display refresh rate changed to {} Hz", current);
+
+    world
+        .resource_mut::<OpenXrRefreshRate>()
+        .bypass_change_detection()
+        .current = Some(current);
+}
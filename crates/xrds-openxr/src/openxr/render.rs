@@ -11,9 +11,10 @@ use wgpu::TextureViewDescriptor;
 use crate::{
     backends::OpenXrGraphicsBackends,
     openxr::{
-        camera::{OpenXrCameraIndex, OpenXrViewProjection},
+        camera::{OpenXrCameraIndex, OpenXrIpdOffset, OpenXrViewProjection},
         frame::OpenXrFrameWaiter,
         layers::builder::OpenXrCompositionLayerBuilder,
+        panel::build_panel_layers,
         resources::{
             OpenXrEnvironmentBlendModes, OpenXrFrameState, OpenXrFrameStream,
             OpenXrPrimaryReferenceSpace, OpenXrRenderResources, OpenXrSwapchain,
@@ -170,6 +171,7 @@ fn openxr_locate_views(
     frame_state: Res<OpenXrFrameState>,
     primary_reference_space: Res<OpenXrPrimaryReferenceSpace>,
     session: Res<OpenXrSession>,
+    ipd_offset: Res<OpenXrIpdOffset>,
     mut openxr_views: ResMut<OpenXrViews>,
 ) {
     debug_span!("OpenXrRenderPlugin");
@@ -189,6 +191,9 @@ fn openxr_locate_views(
         if flags.intersects(openxr::ViewStateFlags::POSITION_VALID) {
             // Update current position
             out.pose.position = views[i].pose.position;
+            if views.len() == 2 && ipd_offset.0 != 0.0 {
+                out.pose.position = offset_eye(&views[i].pose, i, ipd_offset.0);
+            }
         }
         if flags.intersects(openxr::ViewStateFlags::ORIENTATION_VALID) {
             // Update current orientation
@@ -203,6 +208,24 @@ fn openxr_locate_views(
     }
 }
 
+/// Position of the eye moved along its x axis by half of `ipd_offset`, the
+/// left eye (view 0) to -x and the right eye to +x
+fn offset_eye(pose: &openxr::Posef, index: usize, ipd_offset: f32) -> openxr::Vector3f {
+    let orientation = quat(
+        pose.orientation.x,
+        pose.orientation.y,
+        pose.orientation.z,
+        pose.orientation.w,
+    );
+    let side = if index == 0 { -0.5 } else { 0.5 };
+    let offset = orientation * Vec3::X * (side * ipd_offset);
+    openxr::Vector3f {
+        x: pose.position.x + offset.x,
+        y: pose.position.y + offset.y,
+        z: pose.position.z + offset.z,
+    }
+}
+
 #[allow(unused)]
 fn openxr_locate_space(_world: &mut World) {
     debug_span!("OpenXrRenderPlugin");
@@ -378,7 +401,7 @@ fn openxr_release_swapchain_image(mut swapchain: ResMut<OpenXrSwapchain>) {
     trace!("release_swapchain_image");
 }
 
-fn openxr_end_frame(world: &mut World) {
+pub(crate) fn openxr_end_frame(world: &mut World) {
     debug_span!("OpenXrRenderPlugin");
 
     world.resource_scope::<OpenXrFrameStream, ()>(|world, mut frame_stream| {
@@ -386,7 +409,9 @@ fn openxr_end_frame(world: &mut World) {
         let blend_modes = world.resource::<OpenXrEnvironmentBlendModes>();
         let builder = world.resource::<OpenXrCompositionLayerBuilder>();
         let layers = if frame_state.0.should_render {
-            builder.build(world)
+            let mut layers = builder.build(world);
+            layers.extend(build_panel_layers(world));
+            layers
         } else {
             vec![]
         };
@@ -38,7 +38,7 @@ pub struct OpenXrSpace(pub u64);
 pub struct OpenXrReferenceSpace(pub OpenXrSpace);
 
 #[derive(Resource, ExtractResource, Clone)]
-pub struct OpenXrPrimaryReferenceSpace(pub OpenXrSpace);
+pub struct OpenXrPrimaryReferenceSpace(pub OpenXrSpace, pub openxr::ReferenceSpaceType);
 
 #[allow(unused)]
 #[derive(Resource)]
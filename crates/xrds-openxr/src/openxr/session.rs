@@ -11,6 +11,7 @@ use crate::{
     backends::OpenXrGraphicsBackends,
     openxr::{
         camera::{OpenXrCameraIndex, OpenXrViewProjection},
+        foveation::OpenXrFoveation,
         graphics::{
             openxr_graphics, OpenXrGraphicsExtend, OpenXrGraphicsFamily, OpenXrGraphicsWrap,
         },
@@ -19,16 +20,20 @@ use crate::{
             builder::OpenXrCompositionLayerBuilder,
             projection::OpenXrCompositionLayerProjectionBuilder,
         },
+        overlay::{OpenXrMainSessionVisibility, OpenXrOverlay},
+        passthrough::{OpenXrEnvironmentBlend, OpenXrPassthrough},
+        reference_space::update_stage_bounds,
         resources::{
-            OpenXrEnvironmentBlendModes, OpenXrFrameStream, OpenXrInstance, OpenXrRenderResources,
-            OpenXrSpace, OpenXrSwapchain, OpenXrSwapchainImages, OpenXrViewConfigurations,
-            OpenXrViews,
+            OpenXrEnvironmentBlendModes, OpenXrFrameStream, OpenXrInstance,
+            OpenXrPrimaryReferenceSpace, OpenXrRenderResources, OpenXrSpace, OpenXrSwapchain,
+            OpenXrSwapchainImages, OpenXrViewConfigurations, OpenXrViews,
         },
         schedule::{
             openxr_in_state_focused, OpenXrDeviceState, OpenXrRuntimeSystems, OpenXrSchedules,
             OpenXrSessionState, OpenXrSystemState,
         },
         swapchain::view_index,
+        system::OpenXrSystemInfo,
     },
 };
 
@@ -69,6 +74,36 @@ impl OpenXrSession {
         )
     }
 
+    #[inline]
+    pub fn enumerate_display_refresh_rates(&self) -> openxr::Result<Vec<f32>> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                inner.enumerate_display_refresh_rates()
+            }
+        )
+    }
+
+    #[inline]
+    pub fn get_display_refresh_rate(&self) -> openxr::Result<f32> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                inner.get_display_refresh_rate()
+            }
+        )
+    }
+
+    #[inline]
+    pub fn request_display_refresh_rate(&self, display_refresh_rate: f32) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                inner.request_display_refresh_rate(display_refresh_rate)
+            }
+        )
+    }
+
     #[inline]
     pub fn end(&self) -> openxr::Result<openxr::sys::Result> {
         openxr_graphics!(
@@ -79,6 +114,17 @@ impl OpenXrSession {
         )
     }
 
+    /// Asks the runtime to stop the session. Safe to call from any thread
+    #[inline]
+    pub fn request_exit(&self) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                inner.request_exit()
+            }
+        )
+    }
+
     #[inline]
     pub fn locate_views(
         &self,
@@ -178,6 +224,332 @@ impl OpenXrSession {
         )
     }
 
+    #[inline]
+    pub fn attach_action_sets(&self, action_sets: &[&openxr::ActionSet]) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                inner.attach_action_sets(action_sets)
+            }
+        )
+    }
+
+    #[inline]
+    pub fn sync_actions(&self, action_sets: &[openxr::ActiveActionSet]) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                inner.sync_actions(action_sets)
+            }
+        )
+    }
+
+    #[inline]
+    pub fn create_action_space(
+        &self,
+        action: &openxr::Action<Posef>,
+        subaction_path: openxr::Path,
+        pose_in_action_space: Posef,
+    ) -> openxr::Result<OpenXrSpace> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                let mut space = openxr::sys::Space::NULL;
+                unsafe {cvt(
+                    (inner.instance().fp().create_action_space)(
+                        inner.as_raw(), &openxr::sys::ActionSpaceCreateInfo {
+                            ty: StructureType::ACTION_SPACE_CREATE_INFO,
+                            next: null(),
+                            action: action.as_raw(),
+                            subaction_path,
+                            pose_in_action_space
+                        },
+                        &mut space
+                    )
+                )?;}
+                Ok(OpenXrSpace(space.into_raw()))
+            }
+        )
+    }
+
+    #[inline]
+    pub fn action_state<T: openxr::ActionInput>(
+        &self,
+        action: &openxr::Action<T>,
+        subaction_path: openxr::Path,
+    ) -> openxr::Result<openxr::ActionState<T>> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                action.state(inner, subaction_path)
+            }
+        )
+    }
+
+    #[inline]
+    pub fn apply_haptic_feedback(
+        &self,
+        action: &openxr::Action<openxr::Haptic>,
+        subaction_path: openxr::Path,
+        vibration: &openxr::HapticVibration,
+    ) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                action.apply_feedback(inner, subaction_path, vibration)
+            }
+        )
+    }
+
+    #[inline]
+    pub fn stop_haptic_feedback(
+        &self,
+        action: &openxr::Action<openxr::Haptic>,
+        subaction_path: openxr::Path,
+    ) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                action.stop_feedback(inner, subaction_path)
+            }
+        )
+    }
+
+    /// Render model key of the controller held in `user_path`, `None` until it is known
+    #[inline]
+    pub fn controller_model_key(&self, user_path: openxr::Path) -> openxr::Result<Option<u64>> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                let ext = inner
+                    .instance()
+                    .exts()
+                    .msft_controller_model
+                    .as_ref()
+                    .ok_or(openxr::sys::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+                let mut out = openxr::sys::ControllerModelKeyStateMSFT::out(null_mut());
+                let key = unsafe {
+                    cvt((ext.get_controller_model_key)(inner.as_raw(), user_path, out.as_mut_ptr()))?;
+                    (*out.as_ptr()).model_key.into_raw()
+                };
+                Ok((key != 0).then_some(key))
+            }
+        )
+    }
+
+    /// Binary glTF of a controller render model
+    #[inline]
+    pub fn load_controller_model(&self, key: u64) -> openxr::Result<Vec<u8>> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                let ext = inner
+                    .instance()
+                    .exts()
+                    .msft_controller_model
+                    .as_ref()
+                    .ok_or(openxr::sys::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+                get_arr_init(0u8, |capacity, count, buffer| unsafe {
+                    (ext.load_controller_model)(
+                        inner.as_raw(),
+                        openxr::sys::ControllerModelKeyMSFT::from_raw(key),
+                        capacity,
+                        count,
+                        buffer,
+                    )
+                })
+            }
+        )
+    }
+
+    /// Joint tracker of a hand, needs `XR_EXT_hand_tracking`
+    #[inline]
+    pub fn create_hand_tracker(&self, hand: openxr::Hand) -> openxr::Result<openxr::HandTracker> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                inner.create_hand_tracker(hand)
+            }
+        )
+    }
+
+    /// Joint locations of a hand relative to `base`, `None` while the hand is not tracked
+    #[inline]
+    pub fn locate_hand_joints(
+        &self,
+        tracker: &openxr::HandTracker,
+        base: &OpenXrSpace,
+        time: openxr::Time,
+    ) -> openxr::Result<Option<openxr::HandJointLocations>> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                let ext = inner
+                    .instance()
+                    .exts()
+                    .ext_hand_tracking
+                    .as_ref()
+                    .ok_or(openxr::sys::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+                let info = openxr::sys::HandJointsLocateInfoEXT {
+                    ty: openxr::sys::HandJointsLocateInfoEXT::TYPE,
+                    next: null(),
+                    base_space: openxr::sys::Space::from_raw(base.0),
+                    time,
+                };
+                let mut joints = [openxr::HandJointLocation {
+                    location_flags: openxr::sys::SpaceLocationFlags::EMPTY,
+                    pose: Posef::IDENTITY,
+                    radius: 0.0,
+                }; openxr::HAND_JOINT_COUNT];
+                let mut locations = openxr::sys::HandJointLocationsEXT {
+                    ty: openxr::sys::HandJointLocationsEXT::TYPE,
+                    next: null_mut(),
+                    is_active: false.into(),
+                    joint_count: openxr::HAND_JOINT_COUNT as u32,
+                    joint_locations: joints.as_mut_ptr(),
+                };
+                unsafe {
+                    cvt((ext.locate_hand_joints)(tracker.as_raw(), &info, &mut locations))?;
+                }
+                Ok(bool::from(locations.is_active).then_some(joints))
+            }
+        )
+    }
+
+    /// Starts `XR_FB_passthrough` with a layer of the reconstructed
+    /// surroundings. Returns the raw passthrough and layer handles, which are
+    /// destroyed with the session
+    pub fn create_passthrough_layer(&self) -> openxr::Result<(u64, u64)> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                let ext = inner
+                    .instance()
+                    .exts()
+                    .fb_passthrough
+                    .as_ref()
+                    .ok_or(openxr::sys::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+                let info = openxr::sys::PassthroughCreateInfoFB {
+                    ty: openxr::sys::PassthroughCreateInfoFB::TYPE,
+                    next: null(),
+                    flags: openxr::sys::PassthroughFlagsFB::IS_RUNNING_AT_CREATION,
+                };
+                let mut passthrough = openxr::sys::PassthroughFB::NULL;
+                unsafe {
+                    cvt((ext.create_passthrough)(inner.as_raw(), &info, &mut passthrough))?;
+                }
+                let layer_info = openxr::sys::PassthroughLayerCreateInfoFB {
+                    ty: openxr::sys::PassthroughLayerCreateInfoFB::TYPE,
+                    next: null(),
+                    passthrough,
+                    flags: openxr::sys::PassthroughFlagsFB::IS_RUNNING_AT_CREATION,
+                    purpose: openxr::sys::PassthroughLayerPurposeFB::RECONSTRUCTION,
+                };
+                let mut layer = openxr::sys::PassthroughLayerFB::NULL;
+                let result = unsafe {
+                    cvt((ext.create_passthrough_layer)(inner.as_raw(), &layer_info, &mut layer))
+                };
+                if let Err(e) = result {
+                    unsafe {
+                        (ext.destroy_passthrough)(passthrough);
+                    }
+                    return Err(e);
+                }
+                Ok((passthrough.into_raw(), layer.into_raw()))
+            }
+        )
+    }
+
+    /// Pauses or resumes a passthrough created by [`Self::create_passthrough_layer`]
+    pub fn set_passthrough_running(
+        &self,
+        passthrough: u64,
+        layer: u64,
+        running: bool,
+    ) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                let ext = inner
+                    .instance()
+                    .exts()
+                    .fb_passthrough
+                    .as_ref()
+                    .ok_or(openxr::sys::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+                let passthrough = openxr::sys::PassthroughFB::from_raw(passthrough);
+                let layer = openxr::sys::PassthroughLayerFB::from_raw(layer);
+                unsafe {
+                    if running {
+                        cvt((ext.passthrough_start)(passthrough))?;
+                        cvt((ext.passthrough_layer_resume)(layer))?;
+                    } else {
+                        cvt((ext.passthrough_layer_pause)(layer))?;
+                        cvt((ext.passthrough_pause)(passthrough))?;
+                    }
+                }
+                Ok(())
+            }
+        )
+    }
+
+    /// Applies a foveation profile to the raw swapchain handle `swapchain`
+    pub fn update_swapchain_foveation(
+        &self,
+        swapchain: u64,
+        foveation: &OpenXrFoveation,
+    ) -> openxr::Result<()> {
+        openxr_graphics!(
+            &self.0;
+            inner => {
+                let exts = inner.instance().exts();
+                let (Some(foveation_ext), Some(update_ext)) = (
+                    exts.fb_foveation.as_ref(),
+                    exts.fb_swapchain_update_state.as_ref(),
+                ) else {
+                    return Err(openxr::sys::Result::ERROR_EXTENSION_NOT_PRESENT);
+                };
+
+                let mut level_info = openxr::sys::FoveationLevelProfileCreateInfoFB {
+                    ty: openxr::sys::FoveationLevelProfileCreateInfoFB::TYPE,
+                    next: null_mut(),
+                    level: foveation.level.as_raw(),
+                    vertical_offset: foveation.vertical_offset,
+                    dynamic: if foveation.dynamic {
+                        openxr::sys::FoveationDynamicFB::LEVEL_ENABLED
+                    } else {
+                        openxr::sys::FoveationDynamicFB::DISABLED
+                    },
+                };
+                let info = openxr::sys::FoveationProfileCreateInfoFB {
+                    ty: openxr::sys::FoveationProfileCreateInfoFB::TYPE,
+                    next: ptr::addr_of_mut!(level_info).cast(),
+                };
+                let mut profile = openxr::sys::FoveationProfileFB::NULL;
+                unsafe {
+                    cvt((foveation_ext.create_foveation_profile)(inner.as_raw(), &info, &mut profile))?;
+                }
+
+                let state = openxr::sys::SwapchainStateFoveationFB {
+                    ty: openxr::sys::SwapchainStateFoveationFB::TYPE,
+                    next: null_mut(),
+                    flags: openxr::sys::SwapchainStateFoveationFlagsFB::EMPTY,
+                    profile,
+                };
+                // The swapchain keeps the foveation after the profile is destroyed
+                let result = unsafe {
+                    cvt((update_ext.update_swapchain)(
+                        openxr::sys::Swapchain::from_raw(swapchain),
+                        ptr::addr_of!(state).cast(),
+                    ))
+                };
+                unsafe {
+                    (foveation_ext.destroy_foveation_profile)(profile);
+                }
+                result.map(|_| ())
+            }
+        )
+    }
+
     #[inline]
     pub fn enumerate_reference_space_types(
         &self,
@@ -217,7 +589,6 @@ impl OpenXrSession {
     }
 
     #[inline]
-    #[allow(unused)]
     pub fn reference_space_bounds_rect(
         &self,
         ty: openxr::ReferenceSpaceType,
@@ -306,7 +677,22 @@ fn initialize_view_and_blend_mode(world: &mut World) {
     let blend_modes = openxr_instance
         .enumerate_environment_blend_modes(view_configuration_type)
         .expect("Could not enumerate environment blend modes");
-    let blend_mode = blend_modes.first().expect("There is no blend modes");
+    let fb_passthrough = world
+        .get_resource::<OpenXrSystemInfo>()
+        .is_some_and(|info| info.enabled_extensions.fb_passthrough);
+    let environment_blend = world
+        .get_resource::<OpenXrEnvironmentBlend>()
+        .copied()
+        .unwrap_or_default();
+    let blend_mode = environment_blend
+        .blend_mode(&blend_modes, fb_passthrough)
+        .unwrap_or_else(|| {
+            warn!(
+                "Environment blend {:?} not supported, supported modes: {:?}",
+                environment_blend, blend_modes
+            );
+            *blend_modes.first().expect("There is no blend modes")
+        });
 
     let openxr_views = OpenXrViews(vec![
         openxr::View::default();
@@ -319,10 +705,15 @@ fn initialize_view_and_blend_mode(world: &mut World) {
     };
 
     let openxr_blend_modes = OpenXrEnvironmentBlendModes {
-        current_blend_mode: *blend_mode,
+        current_blend_mode: blend_mode,
         blend_modes,
     };
 
+    if let Some(mut system_info) = world.get_resource_mut::<OpenXrSystemInfo>() {
+        system_info.set_views(&openxr_view_configurations.view_configuration_views);
+        system_info.blend_modes = openxr_blend_modes.blend_modes.clone();
+    }
+
     let mut openxr_layer_builder = OpenXrCompositionLayerBuilder::new();
     openxr_layer_builder.insert_layer(0, Box::new(OpenXrCompositionLayerProjectionBuilder));
 
@@ -344,11 +735,28 @@ fn initialize_openxr_session(world: &mut World) {
         .get_session_create_info()
         .expect("Could not get openxr session create info");
 
-    let (session, frame_waiter, frame_stream) = openxr_instance
-        .create_session(&session_create_info)
-        .expect("Could not create OpenXR session");
+    let (session, frame_waiter, frame_stream) = match world.get_resource::<OpenXrOverlay>() {
+        Some(overlay) => {
+            info!(
+                "Create OpenXR overlay session, placement: {}",
+                overlay.placement
+            );
+            openxr_instance.create_overlay_session(&session_create_info, overlay)
+        }
+        None => openxr_instance.create_session(&session_create_info),
+    }
+    .expect("Could not create OpenXR session");
     info!("OpenXR session created");
 
+    if let Some(mut system_info) = world.get_resource_mut::<OpenXrSystemInfo>() {
+        if system_info.enabled_extensions.fb_display_refresh_rate {
+            match session.enumerate_display_refresh_rates() {
+                Ok(refresh_rates) => system_info.refresh_rates = refresh_rates,
+                Err(e) => warn!("Could not enumerate display refresh rates: {}", e),
+            }
+        }
+    }
+
     world.insert_resource(session);
     world.insert_resource(frame_waiter);
     world.insert_resource(frame_stream);
@@ -442,6 +850,28 @@ fn handle_events(world: &mut World) {
                     "  reference space change pending: time={:?}, prev_pose={:?}, valid={:?}, type={:?}",
                     reference_space_change_pending.change_time(), reference_space_change_pending.pose_in_previous_space(), reference_space_change_pending.pose_valid(), reference_space_change_pending.reference_space_type()
                 );
+                // The bounds are only used in a stage primary space
+                let primary_space_type = world
+                    .get_resource::<OpenXrPrimaryReferenceSpace>()
+                    .map(|primary_space| primary_space.1);
+                if reference_space_change_pending.reference_space_type()
+                    == openxr::ReferenceSpaceType::STAGE
+                    && primary_space_type == Some(openxr::ReferenceSpaceType::STAGE)
+                {
+                    update_stage_bounds(world);
+                }
+            }
+            openxr::Event::MainSessionVisibilityChangedEXTX(main_session_visibility) => {
+                info!(
+                    "  main session visibility changed: {}",
+                    main_session_visibility.visible()
+                );
+                world.insert_resource(OpenXrMainSessionVisibility {
+                    visible: main_session_visibility.visible(),
+                    depth_enabled: main_session_visibility.flags().contains(
+                        openxr::sys::OverlayMainSessionFlagsEXTX::ENABLED_COMPOSITION_LAYER_INFO_DEPTH,
+                    ),
+                });
             }
             openxr::Event::EventsLost(event_lost) => {
                 warn!("  events lost: {}", event_lost.lost_event_count());
@@ -462,7 +892,7 @@ fn handle_events(world: &mut World) {
     }
 }
 
-fn init_render_resources(world: &mut World) {
+pub(crate) fn init_render_resources(world: &mut World) {
     let frame_stream = world
         .remove_resource::<OpenXrFrameStream>()
         .expect("OpenXrFrameStream resource not exists");
@@ -484,6 +914,9 @@ fn init_render_resources(world: &mut World) {
 fn spawn_camera(
     swapchain_images: Res<OpenXrSwapchainImages>,
     mut manual_texture_views: ResMut<ManualTextureViews>,
+    overlay: Option<Res<OpenXrOverlay>>,
+    blend_modes: Res<OpenXrEnvironmentBlendModes>,
+    passthrough: Option<Res<OpenXrPassthrough>>,
     mut commands: Commands,
 ) {
     debug_span!("OpenXrCameraPlugin");
@@ -514,7 +947,16 @@ fn spawn_camera(
         commands.spawn((
             Camera {
                 target: RenderTarget::TextureView(handle),
-                clear_color: ClearColorConfig::Custom(Color::srgb_u8(128, 128, 255)),
+                // Overlays must not hide the application below them, and
+                // the background is left to the real world when it shows
+                clear_color: if overlay.is_some()
+                    || passthrough.is_some()
+                    || blend_modes.current_blend_mode != openxr::EnvironmentBlendMode::OPAQUE
+                {
+                    ClearColorConfig::Custom(Color::NONE)
+                } else {
+                    ClearColorConfig::Custom(Color::srgb_u8(128, 128, 255))
+                },
                 ..Default::default()
             },
             OpenXrCameraIndex(i as u32),
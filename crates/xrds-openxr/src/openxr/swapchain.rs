@@ -87,7 +87,7 @@ impl Plugin for OpenXrSwapchainPlugin {
     }
 }
 
-fn create_swapchain(world: &mut World) {
+pub(crate) fn create_swapchain(world: &mut World) {
     debug_span!("OpenXrSessionPlugin");
 
     // Must exists
@@ -114,18 +114,53 @@ fn create_swapchain(world: &mut World) {
 
     let sample_count = view_configuration_view.recommended_swapchain_sample_count;
 
-    let (swapchain, swapchain_images, swapchain_info) = openxr_graphics!(
+    let swapchain_formats: Vec<_> = openxr_graphics!(
         &session.0;
         session => {
-            let swapchain_formats: Vec<_> = session.enumerate_swapchain_formats()
+            session.enumerate_swapchain_formats()
                 .expect("Could not enumerate swapchain formats")
                 .iter()
                 .filter_map(|f| graphics_backends.format_from_raw::<Api>(f) )
-                .collect();
-            info!("Available swapchain formats: {:?}", swapchain_formats);
+                .collect()
+        }
+    );
+    info!("Available swapchain formats: {:?}", swapchain_formats);
+
+    let swapchain_format = wgpu::TextureFormat::Rgba8UnormSrgb; // TODO: Select format from list. Prior Srgb
+    info!("Selected swapchain format: {:?}", swapchain_format);
+    let (swapchain, images) = create_swapchain_images(
+        session,
+        graphics_backends,
+        swapchain_format,
+        size,
+        sample_count,
+    );
+    let swapchain_images = OpenXrSwapchainImages(images);
+    let swapchain_info = OpenXrSwapchainInfo {
+        format: swapchain_format,
+        size,
+    };
+
+    world.insert_resource(swapchain);
+    world.insert_resource(swapchain_images);
+    world.insert_resource(swapchain_info);
+    info!("OpenXR swapchain and swapchain images initialized");
+}
 
-            let swapchain_format = wgpu::TextureFormat::Rgba8UnormSrgb;  // TODO: Select format from list. Prior Srgb
-            info!("Selected swapchain format: {:?}", swapchain_format);
+/// Creates a swapchain and a 2D view of every array layer of its images
+pub(crate) fn create_swapchain_images(
+    session: &OpenXrSession,
+    graphics_backends: &OpenXrGraphicsBackends,
+    swapchain_format: wgpu::TextureFormat,
+    size: Extent3d,
+    sample_count: u32,
+) -> (
+    OpenXrSwapchain,
+    Vec<(wgpu::Texture, Vec<wgpu::TextureView>)>,
+) {
+    openxr_graphics!(
+        &session.0;
+        session => {
             let swapchain_create_info = graphics_backends.get_swapchain_create_info(swapchain_format, size, sample_count)
                 .expect("Could not get swapchain create info");
             let swapchain = session.create_swapchain(swapchain_create_info.as_inner::<Api>())
@@ -153,23 +188,9 @@ fn create_swapchain(world: &mut World) {
                 )
                 .collect();
 
-            let info = OpenXrSwapchainInfo {
-                format: swapchain_format,
-                size
-            };
-
-            (
-                OpenXrSwapchain::from_inner(swapchain),
-                OpenXrSwapchainImages(swapchain_images),
-                info
-            )
+            (OpenXrSwapchain::from_inner(swapchain), swapchain_images)
         }
-    );
-
-    world.insert_resource(swapchain);
-    world.insert_resource(swapchain_images);
-    world.insert_resource(swapchain_info);
-    info!("OpenXR swapchain and swapchain images initialized");
+    )
 }
 
 const OPENXR_SWAPCHAIN_VIEW_INDEX_BASE: u32 = u32::from_le_bytes(*b"OPXR");
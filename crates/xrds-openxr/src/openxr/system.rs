@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+/// Information about the XR runtime and the device it drives
+///
+/// Inserted when OpenXR is initialized. View sizes and blend modes are filled
+/// in when the session is created, refresh rates after that if the runtime
+/// supports `XR_FB_display_refresh_rate`.
+#[derive(Resource, Clone, Debug)]
+pub struct OpenXrSystemInfo {
+    pub runtime_name: String,
+    pub runtime_version: openxr::Version,
+    pub system_name: String,
+    pub vendor_id: u32,
+    pub form_factor: openxr::FormFactor,
+    pub orientation_tracking: bool,
+    pub position_tracking: bool,
+    pub max_swapchain_size: UVec2,
+    pub max_layer_count: u32,
+    /// Recommended render target size of each view
+    pub recommended_view_sizes: Vec<UVec2>,
+    /// Maximum render target size of each view
+    pub max_view_sizes: Vec<UVec2>,
+    /// Display refresh rates in Hz, empty if the runtime can not report them
+    pub refresh_rates: Vec<f32>,
+    pub blend_modes: Vec<openxr::EnvironmentBlendMode>,
+    /// Extensions enabled on the instance
+    pub enabled_extensions: openxr::ExtensionSet,
+    /// Extensions the runtime supports
+    pub supported_extensions: openxr::ExtensionSet,
+}
+
+impl OpenXrSystemInfo {
+    pub(crate) fn new(
+        instance: &openxr::Instance,
+        system_id: openxr::SystemId,
+        form_factor: openxr::FormFactor,
+        enabled_extensions: openxr::ExtensionSet,
+        supported_extensions: openxr::ExtensionSet,
+    ) -> openxr::Result<Self> {
+        let instance_properties = instance.properties()?;
+        let system_properties = instance.system_properties(system_id)?;
+        let graphics = system_properties.graphics_properties;
+        let tracking = system_properties.tracking_properties;
+
+        Ok(Self {
+            runtime_name: instance_properties.runtime_name,
+            runtime_version: instance_properties.runtime_version,
+            system_name: system_properties.system_name,
+            vendor_id: system_properties.vendor_id,
+            form_factor,
+            orientation_tracking: tracking.orientation_tracking,
+            position_tracking: tracking.position_tracking,
+            max_swapchain_size: UVec2::new(
+                graphics.max_swapchain_image_width,
+                graphics.max_swapchain_image_height,
+            ),
+            max_layer_count: graphics.max_layer_count,
+            recommended_view_sizes: Vec::new(),
+            max_view_sizes: Vec::new(),
+            refresh_rates: Vec::new(),
+            blend_modes: Vec::new(),
+            enabled_extensions,
+            supported_extensions,
+        })
+    }
+
+    pub(crate) fn set_views(&mut self, views: &[openxr::ViewConfigurationView]) {
+        self.recommended_view_sizes = views
+            .iter()
+            .map(|view| {
+                UVec2::new(
+                    view.recommended_image_rect_width,
+                    view.recommended_image_rect_height,
+                )
+            })
+            .collect();
+        self.max_view_sizes = views
+            .iter()
+            .map(|view| UVec2::new(view.max_image_rect_width, view.max_image_rect_height))
+            .collect();
+    }
+
+    #[inline]
+    pub fn supports_blend_mode(&self, blend_mode: openxr::EnvironmentBlendMode) -> bool {
+        self.blend_modes.contains(&blend_mode)
+    }
+
+    /// Whether the real world can be visible behind the rendered content,
+    /// by the display or a `XR_FB_passthrough` layer
+    #[inline]
+    pub fn supports_passthrough(&self) -> bool {
+        self.supports_blend_mode(openxr::EnvironmentBlendMode::ADDITIVE)
+            || self.supports_blend_mode(openxr::EnvironmentBlendMode::ALPHA_BLEND)
+            || self.enabled_extensions.fb_passthrough
+    }
+
+    #[inline]
+    pub fn max_refresh_rate(&self) -> Option<f32> {
+        self.refresh_rates.iter().copied().reduce(f32::max)
+    }
+}
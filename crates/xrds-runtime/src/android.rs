@@ -0,0 +1,53 @@
+#[cfg(all(feature = "android-game-activity", feature = "android-native-activity"))]
+compile_error!("the android-game-activity and android-native-activity features exclude each other");
+
+#[cfg(target_os = "android")]
+pub use bevy::android::android_activity::AndroidApp;
+
+#[cfg(target_os = "android")]
+use crate::RuntimeBuilder;
+
+/// Defines the `android_main` entry of an Android application, which calls
+/// `$main` like `main` on desktop. Expands to nothing on other platforms
+///
+/// The crate has to be built as a `cdylib` with one of the
+/// `android-game-activity` or `android-native-activity` features. The
+/// activity is handed to the runtime before `$main` builds it.
+///
+/// ```ignore
+/// fn main() {
+///     Runtime::new(RuntimeParameters::default()).run(MyApp).unwrap();
+/// }
+///
+/// xrds_runtime::android_main!(main);
+/// ```
+#[macro_export]
+macro_rules! android_main {
+    ($main:path) => {
+        #[cfg(target_os = "android")]
+        #[no_mangle]
+        fn android_main(app: $crate::AndroidApp) {
+            $crate::set_android_app(app);
+            $main();
+        }
+    };
+}
+
+/// Activity the window and the OpenXR loader are created for. Only the
+/// first activity is kept, the process runs one runtime
+#[cfg(target_os = "android")]
+pub fn set_android_app(app: AndroidApp) {
+    if bevy::android::ANDROID_APP.set(app).is_err() {
+        log::warn!("Android activity is already set");
+    }
+}
+
+#[cfg(target_os = "android")]
+impl RuntimeBuilder {
+    /// Activity passed to `android_main`, for applications with their own
+    /// entry instead of [`android_main!`](crate::android_main)
+    pub fn with_android_app(self, app: AndroidApp) -> Self {
+        set_android_app(app);
+        self
+    }
+}
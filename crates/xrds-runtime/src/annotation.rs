@@ -0,0 +1,343 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use xrds_components::{PaletteRole, ThemedText};
+
+use crate::UserProfile;
+
+/// Sticky note placed on an object
+///
+/// Annotations are children of the annotated object, their transform is the
+/// offset from it. They are saved with the world when the object is
+/// [`crate::Persistent`] and synced to other participants through
+/// [`AnnotationSync`].
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[require(Transform, Visibility)]
+pub struct Annotation {
+    /// Unique across the participants of a session
+    pub id: String,
+    pub text: String,
+    pub author: String,
+    /// Milliseconds since the Unix epoch
+    pub created: u64,
+    pub modified: u64,
+}
+
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum AnnotationCommand {
+    /// Place a note on `target`, `offset` is in its local space
+    Place {
+        target: Entity,
+        offset: Vec3,
+        text: String,
+    },
+    Edit {
+        id: String,
+        text: String,
+    },
+    Remove {
+        id: String,
+    },
+}
+
+/// Change of an annotation sent to the other participants
+///
+/// Objects are matched by their `Name`, so annotated objects need the same
+/// name on every participant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationUpdate {
+    Upsert {
+        annotation: Annotation,
+        target: String,
+        offset: Vec3,
+    },
+    Remove {
+        id: String,
+    },
+}
+
+/// Connection to the other participants of a session
+pub trait AnnotationTransport: Send + Sync {
+    fn send(&self, update: &AnnotationUpdate);
+    /// Return the updates received since the last call
+    fn try_recv(&self) -> Vec<AnnotationUpdate>;
+}
+
+/// In-process transport connecting two participants
+pub struct ChannelAnnotationTransport {
+    sender: Mutex<Sender<AnnotationUpdate>>,
+    receiver: Mutex<Receiver<AnnotationUpdate>>,
+}
+
+/// Shares annotation changes when inserted
+#[derive(Resource, Clone)]
+pub struct AnnotationSync(pub Arc<dyn AnnotationTransport>);
+
+/// UI text showing an [`Annotation`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct AnnotationLabel {
+    pub annotation: Entity,
+}
+
+#[derive(Debug, Default)]
+pub struct AnnotationPlugin;
+
+impl Annotation {
+    /// Label text with the author
+    pub fn label(&self) -> String {
+        format!("{}\n- {}", self.text, self.author)
+    }
+}
+
+impl ChannelAnnotationTransport {
+    pub fn pair() -> (Self, Self) {
+        let (a_sender, b_receiver) = mpsc::channel();
+        let (b_sender, a_receiver) = mpsc::channel();
+        (
+            Self {
+                sender: Mutex::new(a_sender),
+                receiver: Mutex::new(a_receiver),
+            },
+            Self {
+                sender: Mutex::new(b_sender),
+                receiver: Mutex::new(b_receiver),
+            },
+        )
+    }
+}
+
+impl AnnotationTransport for ChannelAnnotationTransport {
+    fn send(&self, update: &AnnotationUpdate) {
+        if let Ok(sender) = self.sender.lock() {
+            let _ = sender.send(update.clone());
+        }
+    }
+
+    fn try_recv(&self) -> Vec<AnnotationUpdate> {
+        self.receiver
+            .lock()
+            .map(|receiver| receiver.try_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Plugin for AnnotationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<AnnotationCommand>()
+            .add_systems(
+                Update,
+                (
+                    receive_annotation_updates.run_if(resource_exists::<AnnotationSync>),
+                    apply_annotation_commands,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                update_annotation_labels.after(TransformSystems::Propagate),
+            );
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+fn apply_annotation_commands(
+    mut commands: Commands,
+    mut annotation_commands: MessageReader<AnnotationCommand>,
+    mut annotations: Query<(Entity, &mut Annotation, &Transform, &ChildOf)>,
+    names: Query<&Name>,
+    profile: Option<Res<UserProfile>>,
+    sync: Option<Res<AnnotationSync>>,
+    mut placed: Local<u64>,
+) {
+    let send = |annotation: &Annotation, target: Entity, offset: Vec3| {
+        let Some(sync) = &sync else {
+            return;
+        };
+        match names.get(target) {
+            Ok(name) => sync.0.send(&AnnotationUpdate::Upsert {
+                annotation: annotation.clone(),
+                target: name.to_string(),
+                offset,
+            }),
+            Err(_) => warn!(
+                "Annotation {} is on an unnamed object, not synced",
+                annotation.id
+            ),
+        }
+    };
+
+    for command in annotation_commands.read() {
+        match command {
+            AnnotationCommand::Place {
+                target,
+                offset,
+                text,
+            } => {
+                let author = profile
+                    .as_ref()
+                    .map_or_else(|| "default".to_owned(), |profile| profile.name.clone());
+                let created = now_millis();
+                *placed += 1;
+                let annotation = Annotation {
+                    id: format!("{author}-{created}-{}", *placed),
+                    text: text.clone(),
+                    author,
+                    created,
+                    modified: created,
+                };
+                send(&annotation, *target, *offset);
+                commands.spawn((
+                    annotation,
+                    Transform::from_translation(*offset),
+                    ChildOf(*target),
+                ));
+            }
+            AnnotationCommand::Edit { id, text } => {
+                let Some((_, mut annotation, transform, child_of)) = annotations
+                    .iter_mut()
+                    .find(|(_, annotation, ..)| annotation.id == *id)
+                else {
+                    continue;
+                };
+                annotation.text.clone_from(text);
+                annotation.modified = now_millis();
+                send(&annotation, child_of.parent(), transform.translation);
+            }
+            AnnotationCommand::Remove { id } => {
+                let Some((entity, ..)) = annotations
+                    .iter()
+                    .find(|(_, annotation, ..)| annotation.id == *id)
+                else {
+                    continue;
+                };
+                commands.entity(entity).despawn();
+                if let Some(sync) = &sync {
+                    sync.0.send(&AnnotationUpdate::Remove { id: id.clone() });
+                }
+            }
+        }
+    }
+}
+
+fn receive_annotation_updates(
+    mut commands: Commands,
+    sync: Res<AnnotationSync>,
+    mut annotations: Query<(Entity, &mut Annotation, &mut Transform)>,
+    names: Query<(Entity, &Name)>,
+) {
+    for update in sync.0.try_recv() {
+        match update {
+            AnnotationUpdate::Upsert {
+                annotation,
+                target,
+                offset,
+            } => {
+                if let Some((_, mut existing, mut transform)) = annotations
+                    .iter_mut()
+                    .find(|(_, existing, _)| existing.id == annotation.id)
+                {
+                    // The latest edit wins
+                    if annotation.modified >= existing.modified {
+                        *existing = annotation;
+                        transform.translation = offset;
+                    }
+                    continue;
+                }
+                let Some((target, _)) = names.iter().find(|(_, name)| name.as_str() == target)
+                else {
+                    warn!("Annotation {} is on unknown object {target}", annotation.id);
+                    continue;
+                };
+                commands.spawn((
+                    annotation,
+                    Transform::from_translation(offset),
+                    ChildOf(target),
+                ));
+            }
+            AnnotationUpdate::Remove { id } => {
+                if let Some((entity, ..)) = annotations
+                    .iter()
+                    .find(|(_, annotation, _)| annotation.id == id)
+                {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+    }
+}
+
+fn update_annotation_labels(
+    mut commands: Commands,
+    annotations: Query<(Entity, &Annotation, &GlobalTransform)>,
+    mut labels: Query<(
+        Entity,
+        &AnnotationLabel,
+        &mut Text,
+        &mut Node,
+        &mut Visibility,
+    )>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    let camera = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .min_by_key(|(camera, _)| camera.order);
+
+    let mut labeled = vec![];
+    for (label_entity, label, mut text, mut node, mut visibility) in labels.iter_mut() {
+        let Ok((_, annotation, transform)) = annotations.get(label.annotation) else {
+            commands.entity(label_entity).despawn();
+            continue;
+        };
+        labeled.push(label.annotation);
+
+        let value = annotation.label();
+        if value != text.0 {
+            text.0 = value;
+        }
+        // Follow the annotation on screen
+        let position = camera.and_then(|(camera, camera_transform)| {
+            camera
+                .world_to_viewport(camera_transform, transform.translation())
+                .ok()
+        });
+        match position {
+            Some(position) => {
+                node.left = Val::Px(position.x);
+                node.top = Val::Px(position.y);
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+
+    for (entity, ..) in annotations.iter() {
+        if labeled.contains(&entity) {
+            continue;
+        }
+        commands.spawn((
+            AnnotationLabel { annotation: entity },
+            Text::default(),
+            ThemedText(PaletteRole::Text),
+            Node {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            Visibility::Hidden,
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+use bevy::prelude::*;
+use xrds_openxr::OpenXrStageBounds;
+
+use crate::{CalibrationOffsets, UserProfile};
+
+/// Minimum distance between two sampled boundary points in meters
+const BOUNDARY_SPACING: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CalibrationStep {
+    #[default]
+    Inactive,
+    /// Place the probe on the floor and confirm
+    FloorHeight,
+    /// Walk along the play area edge with the probe and confirm
+    Boundary,
+}
+
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub enum CalibrationCommand {
+    Start,
+    /// Accept the current step and move to the next one
+    Confirm,
+    Cancel,
+    /// Move the floor by the given amount in meters
+    AdjustFloor(f32),
+    ClearBoundary,
+    /// Use the current probe position and heading as the content origin
+    Recenter,
+}
+
+/// Written when a calibration is finished and stored in the user profile
+#[derive(Message, Debug, Clone)]
+pub struct CalibrationFinished {
+    pub offsets: CalibrationOffsets,
+}
+
+/// Tracked entity used to measure the floor and the boundary, usually a controller
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct CalibrationProbe;
+
+/// Entity whose transform follows [`CalibratedSpace`]. Parent content to it to
+/// keep it aligned across sessions
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[require(Transform, Visibility)]
+pub struct CalibratedAnchor;
+
+/// Guided calibration state
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Calibration {
+    step: CalibrationStep,
+    /// Play area provided by the XR runtime, filled from [`OpenXrStageBounds`].
+    /// The boundary step is skipped when set
+    pub runtime_boundary: Option<Vec<Vec2>>,
+    measured_floor: Option<f32>,
+    floor_adjustment: f32,
+    boundary: Vec<Vec2>,
+}
+
+/// Transform from the tracking space to the calibrated content space
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CalibratedSpace {
+    pub transform: Transform,
+    /// Play area polygon on the floor (x, z) in tracking space
+    pub boundary: Vec<Vec2>,
+}
+
+#[derive(Debug, Default)]
+pub struct CalibrationPlugin;
+
+impl Plugin for CalibrationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Calibration>()
+            .init_resource::<CalibratedSpace>()
+            .init_resource::<UserProfile>()
+            .add_message::<CalibrationCommand>()
+            .add_message::<CalibrationFinished>()
+            .add_systems(
+                Update,
+                (
+                    apply_stage_bounds.run_if(resource_exists_and_changed::<OpenXrStageBounds>),
+                    handle_calibration_commands,
+                    sample_calibration_probe,
+                    update_calibrated_space,
+                    apply_calibrated_anchors,
+                )
+                    .chain(),
+            );
+    }
+}
+
+impl Calibration {
+    pub fn step(&self) -> CalibrationStep {
+        self.step
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.step != CalibrationStep::Inactive
+    }
+
+    /// Floor height measured in the current calibration
+    pub fn floor_height(&self) -> Option<f32> {
+        self.measured_floor
+            .map(|floor| floor + self.floor_adjustment)
+    }
+
+    /// Boundary points sampled in the current calibration
+    pub fn boundary(&self) -> &[Vec2] {
+        &self.boundary
+    }
+}
+
+impl CalibratedSpace {
+    pub fn from_offsets(offsets: &CalibrationOffsets) -> Self {
+        Self {
+            transform: Transform::from_xyz(
+                offsets.origin.x,
+                offsets.height_offset,
+                offsets.origin.y,
+            )
+            .with_rotation(Quat::from_rotation_y(offsets.yaw)),
+            boundary: offsets.boundary.clone(),
+        }
+    }
+
+    /// Returns true if the point on the floor (x, z) is inside the play area.
+    /// Always true when no boundary is set
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.boundary.len() < 3 || polygon_contains(&self.boundary, point)
+    }
+}
+
+/// Even-odd rule point in polygon test
+pub fn polygon_contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn handle_calibration_commands(
+    mut commands: MessageReader<CalibrationCommand>,
+    mut finished: MessageWriter<CalibrationFinished>,
+    mut calibration: ResMut<Calibration>,
+    mut profile: ResMut<UserProfile>,
+    probes: Query<&GlobalTransform, With<CalibrationProbe>>,
+) {
+    for command in commands.read() {
+        match (*command, calibration.step) {
+            (CalibrationCommand::Start, _) => {
+                info!("Calibration started");
+                calibration.step = CalibrationStep::FloorHeight;
+                calibration.measured_floor = None;
+                calibration.floor_adjustment = 0.0;
+                calibration.boundary.clear();
+            }
+            (CalibrationCommand::Cancel, _) => {
+                calibration.step = CalibrationStep::Inactive;
+            }
+            (CalibrationCommand::AdjustFloor(delta), CalibrationStep::Inactive) => {
+                profile.calibration.height_offset += delta;
+            }
+            (CalibrationCommand::AdjustFloor(delta), _) => {
+                calibration.floor_adjustment += delta;
+            }
+            (CalibrationCommand::ClearBoundary, _) => {
+                calibration.boundary.clear();
+            }
+            (CalibrationCommand::Recenter, _) => {
+                let Some(probe) = probes.iter().next() else {
+                    warn!("Recenter requested without a calibration probe");
+                    continue;
+                };
+                let (_, rotation, translation) = probe.to_scale_rotation_translation();
+                let forward = rotation * Vec3::NEG_Z;
+                profile.calibration.origin = translation.xz();
+                profile.calibration.yaw = (-forward.x).atan2(-forward.z);
+            }
+            (CalibrationCommand::Confirm, CalibrationStep::FloorHeight) => {
+                if calibration.measured_floor.is_none() {
+                    warn!("Floor height is not measured yet");
+                    continue;
+                }
+                calibration.step = if calibration.runtime_boundary.is_some() {
+                    finish(&mut calibration, &mut profile, &mut finished);
+                    CalibrationStep::Inactive
+                } else {
+                    CalibrationStep::Boundary
+                };
+            }
+            (CalibrationCommand::Confirm, CalibrationStep::Boundary) => {
+                if calibration.boundary.len() < 3 {
+                    warn!("Boundary needs at least 3 points");
+                    continue;
+                }
+                finish(&mut calibration, &mut profile, &mut finished);
+                calibration.step = CalibrationStep::Inactive;
+            }
+            (CalibrationCommand::Confirm, CalibrationStep::Inactive) => {}
+        }
+    }
+}
+
+fn apply_stage_bounds(bounds: Res<OpenXrStageBounds>, mut calibration: ResMut<Calibration>) {
+    calibration.runtime_boundary = bounds.polygon();
+}
+
+fn finish(
+    calibration: &mut Calibration,
+    profile: &mut UserProfile,
+    finished: &mut MessageWriter<CalibrationFinished>,
+) {
+    if let Some(floor) = calibration.floor_height() {
+        profile.calibration.height_offset = floor;
+    }
+    profile.calibration.boundary = calibration
+        .runtime_boundary
+        .clone()
+        .unwrap_or_else(|| calibration.boundary.clone());
+    info!(
+        "Calibration finished. floor={}, boundary points={}",
+        profile.calibration.height_offset,
+        profile.calibration.boundary.len()
+    );
+    finished.write(CalibrationFinished {
+        offsets: profile.calibration.clone(),
+    });
+}
+
+fn sample_calibration_probe(
+    mut calibration: ResMut<Calibration>,
+    probes: Query<&GlobalTransform, With<CalibrationProbe>>,
+) {
+    let Some(position) = probes.iter().next().map(GlobalTransform::translation) else {
+        return;
+    };
+    match calibration.step {
+        CalibrationStep::FloorHeight => {
+            // The lowest point reached by the probe is the floor
+            let floor = calibration
+                .measured_floor
+                .map_or(position.y, |floor| floor.min(position.y));
+            if calibration.measured_floor != Some(floor) {
+                calibration.measured_floor = Some(floor);
+            }
+        }
+        CalibrationStep::Boundary => {
+            let point = position.xz();
+            let far_enough = calibration
+                .boundary
+                .last()
+                .is_none_or(|last| last.distance(point) >= BOUNDARY_SPACING);
+            if far_enough {
+                calibration.boundary.push(point);
+            }
+        }
+        CalibrationStep::Inactive => {}
+    }
+}
+
+fn update_calibrated_space(profile: Res<UserProfile>, mut space: ResMut<CalibratedSpace>) {
+    if profile.is_changed() {
+        *space = CalibratedSpace::from_offsets(&profile.calibration);
+    }
+}
+
+fn apply_calibrated_anchors(
+    space: Res<CalibratedSpace>,
+    mut anchors: Query<(&mut Transform, Ref<CalibratedAnchor>)>,
+) {
+    for (mut transform, anchor) in anchors.iter_mut() {
+        if space.is_changed() || anchor.is_added() {
+            *transform = space.transform;
+        }
+    }
+}
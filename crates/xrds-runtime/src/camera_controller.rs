@@ -0,0 +1,289 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{input::mouse::MouseScrollUnit, platform::collections::HashSet, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::RuntimeEvent;
+
+/// Pitch stays this far from straight up or down
+const PITCH_LIMIT: f32 = FRAC_PI_2 - 0.01;
+const MIN_DISTANCE: f32 = 0.05;
+/// Scroll distance of one line for touchpads reporting pixels
+const PIXELS_PER_LINE: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CameraControllerMode {
+    /// Drag with the left button to orbit around `focus`, with the middle
+    /// button to pan and scroll to zoom
+    Orbit { focus: Vec3 },
+    /// Hold the right button to look around, move in the view direction with
+    /// WASD, down and up with Q and E and faster with shift. Scrolling
+    /// changes the speed
+    Fly,
+}
+
+/// Desktop camera movement driven by [`RuntimeEvent`]s
+///
+/// Starts from the current transform of the entity, e.g. an orbit camera
+/// keeps its distance to the focus. In XR the head pose drives the cameras,
+/// so the controller is meant for desktop cameras or the
+/// [`PlayerRig`](crate::PlayerRig).
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[require(Transform, CameraControllerState)]
+#[serde(default)]
+pub struct CameraController {
+    pub enabled: bool,
+    pub mode: CameraControllerMode,
+    /// Fly speed in m/s. Orbit panning follows the distance to the focus
+    pub speed: f32,
+    /// Speed multiplier while shift is held
+    pub boost: f32,
+    /// Rotation in radians per pixel of mouse movement
+    pub sensitivity: f32,
+    /// Fraction of the distance, or of the fly speed, per scrolled line
+    pub zoom_sensitivity: f32,
+    /// Time in seconds to cover about two thirds of the remaining movement.
+    /// 0 follows the input directly
+    pub damping: f32,
+}
+
+/// Target and smoothed pose of a [`CameraController`]
+#[derive(Component, Debug, Clone, Default)]
+struct CameraControllerState {
+    initialized: bool,
+    orbit: bool,
+    target: ControllerPose,
+    current: ControllerPose,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ControllerPose {
+    yaw: f32,
+    pitch: f32,
+    /// Camera position when flying, focus when orbiting
+    position: Vec3,
+    distance: f32,
+}
+
+/// Buttons held according to the [`RuntimeEvent`]s read so far
+#[derive(Resource, Debug, Default)]
+struct ControllerInput {
+    keys: HashSet<KeyCode>,
+    buttons: HashSet<MouseButton>,
+}
+
+#[derive(Debug, Default)]
+pub struct CameraControllerPlugin;
+
+impl Plugin for CameraControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControllerInput>()
+            .add_message::<RuntimeEvent>()
+            .add_systems(Update, update_camera_controllers);
+    }
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: CameraControllerMode::Fly,
+            speed: 3.0,
+            boost: 4.0,
+            sensitivity: 0.003,
+            zoom_sensitivity: 0.1,
+            damping: 0.08,
+        }
+    }
+}
+
+impl CameraController {
+    pub fn orbit(focus: Vec3) -> Self {
+        Self {
+            mode: CameraControllerMode::Orbit { focus },
+            ..default()
+        }
+    }
+
+    pub fn fly() -> Self {
+        Self::default()
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+}
+
+impl ControllerPose {
+    fn from_transform(transform: &Transform, mode: CameraControllerMode) -> Self {
+        match mode {
+            CameraControllerMode::Orbit { focus } => {
+                let offset = transform.translation - focus;
+                let distance = offset.length().max(MIN_DISTANCE);
+                let direction = offset.try_normalize().unwrap_or(Vec3::Z);
+                Self {
+                    yaw: direction.x.atan2(direction.z),
+                    pitch: (-direction.y).asin().clamp(-PITCH_LIMIT, PITCH_LIMIT),
+                    position: focus,
+                    distance,
+                }
+            }
+            CameraControllerMode::Fly => {
+                let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+                Self {
+                    yaw,
+                    pitch: pitch.clamp(-PITCH_LIMIT, PITCH_LIMIT),
+                    position: transform.translation,
+                    distance: 0.0,
+                }
+            }
+        }
+    }
+
+    fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0)
+    }
+
+    fn transform(&self, orbit: bool) -> Transform {
+        let rotation = self.rotation();
+        let translation = if orbit {
+            self.position + rotation * Vec3::Z * self.distance
+        } else {
+            self.position
+        };
+        Transform::from_translation(translation).with_rotation(rotation)
+    }
+
+    fn approach(&mut self, target: &Self, t: f32) {
+        self.yaw = self.yaw.lerp(target.yaw, t);
+        self.pitch = self.pitch.lerp(target.pitch, t);
+        self.position = self.position.lerp(target.position, t);
+        self.distance = self.distance.lerp(target.distance, t);
+    }
+}
+
+fn update_camera_controllers(
+    time: Res<Time>,
+    mut input: ResMut<ControllerInput>,
+    mut events: MessageReader<RuntimeEvent>,
+    mut controllers: Query<(
+        &mut CameraController,
+        &mut CameraControllerState,
+        &mut Transform,
+    )>,
+) {
+    let mut motion = Vec2::ZERO;
+    let mut scroll = 0.0;
+    for event in events.read() {
+        match event {
+            RuntimeEvent::Key { key, pressed, .. } => {
+                if *pressed {
+                    input.keys.insert(*key);
+                } else {
+                    input.keys.remove(key);
+                }
+            }
+            RuntimeEvent::MouseButton { button, pressed } => {
+                if *pressed {
+                    input.buttons.insert(*button);
+                } else {
+                    input.buttons.remove(button);
+                }
+            }
+            RuntimeEvent::MouseMotion { delta } => motion += *delta,
+            RuntimeEvent::Scroll { delta, unit } => {
+                scroll += match unit {
+                    MouseScrollUnit::Line => delta.y,
+                    MouseScrollUnit::Pixel => delta.y / PIXELS_PER_LINE,
+                }
+            }
+            // Releases are not reported without focus
+            RuntimeEvent::FocusLost => {
+                input.keys.clear();
+                input.buttons.clear();
+            }
+            _ => {}
+        }
+    }
+
+    let dt = time.delta_secs();
+    let key = |code: KeyCode| input.keys.contains(&code);
+    for (mut controller, mut state, mut transform) in controllers.iter_mut() {
+        if !controller.enabled {
+            state.initialized = false;
+            continue;
+        }
+        let orbit = matches!(controller.mode, CameraControllerMode::Orbit { .. });
+        if !state.initialized || state.orbit != orbit {
+            let pose = ControllerPose::from_transform(&transform, controller.mode);
+            *state = CameraControllerState {
+                initialized: true,
+                orbit,
+                target: pose,
+                current: pose,
+            };
+        }
+
+        let look = (orbit && input.buttons.contains(&MouseButton::Left))
+            || (!orbit && input.buttons.contains(&MouseButton::Right));
+        if look {
+            let target = &mut state.target;
+            target.yaw -= motion.x * controller.sensitivity;
+            target.pitch =
+                (target.pitch - motion.y * controller.sensitivity).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+        }
+
+        match controller.mode {
+            CameraControllerMode::Orbit { mut focus } => {
+                if input.buttons.contains(&MouseButton::Middle) && motion != Vec2::ZERO {
+                    // Moves the focus with the cursor at the focus distance
+                    let pan = state.target.rotation() * Vec3::new(-motion.x, motion.y, 0.0);
+                    focus += pan * controller.sensitivity * state.target.distance;
+                    controller.mode = CameraControllerMode::Orbit { focus };
+                }
+                let target = &mut state.target;
+                target.position = focus;
+                target.distance = (target.distance
+                    * (1.0 - controller.zoom_sensitivity).powf(scroll))
+                .max(MIN_DISTANCE);
+            }
+            CameraControllerMode::Fly => {
+                if scroll != 0.0 {
+                    controller.speed *= (1.0 + controller.zoom_sensitivity).powf(scroll);
+                }
+                let forward = Vec3::new(
+                    (key(KeyCode::KeyD) as i8 - key(KeyCode::KeyA) as i8) as f32,
+                    0.0,
+                    (key(KeyCode::KeyS) as i8 - key(KeyCode::KeyW) as i8) as f32,
+                );
+                let up = (key(KeyCode::KeyE) as i8 - key(KeyCode::KeyQ) as i8) as f32;
+                let direction = state.target.rotation() * forward + Vec3::Y * up;
+                let mut speed = controller.speed;
+                if key(KeyCode::ShiftLeft) || key(KeyCode::ShiftRight) {
+                    speed *= controller.boost;
+                }
+                state.target.position += direction.normalize_or_zero() * speed * dt;
+            }
+        }
+
+        let t = if controller.damping > 0.0 {
+            1.0 - (-dt / controller.damping).exp()
+        } else {
+            1.0
+        };
+        let target = state.target;
+        state.current.approach(&target, t);
+        *transform = state.current.transform(orbit).with_scale(transform.scale);
+    }
+}
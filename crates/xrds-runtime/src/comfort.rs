@@ -0,0 +1,427 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use xrds_components::UiTheme;
+use xrds_graphics::{ColorFilter, Vignette};
+
+/// Root of the user's tracking space. Locomotion, snap turn and world scale
+/// are applied to this entity and cameras should be its children
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[require(Transform, Visibility)]
+pub struct PlayerRig;
+
+/// Tunnel vision while the player rig moves. Drawn as a [`Vignette`] on every
+/// 3D camera, so each eye of an XR headset is darkened in its own view
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VignetteSettings {
+    pub enabled: bool,
+    /// Maximum opacity of the vignette
+    pub strength: f32,
+    /// Linear speed (m/s) at which the vignette reaches full strength
+    pub max_linear_speed: f32,
+    /// Angular speed (rad/s) at which the vignette reaches full strength
+    pub max_angular_speed: f32,
+    /// Time in seconds to fade in/out
+    pub fade_time: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapTurnSettings {
+    pub enabled: bool,
+    pub angle_degrees: f32,
+    /// Minimum time between two turns
+    pub cooldown: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleSettings {
+    pub enabled: bool,
+    pub font_size: f32,
+    pub text_color: Color,
+    pub background_color: Color,
+}
+
+/// Runtime configurable comfort and accessibility options
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComfortSettings {
+    pub vignette: VignetteSettings,
+    pub snap_turn: SnapTurnSettings,
+    /// Apparent scale of the world. 2.0 makes the world look twice as big
+    pub world_scale: f32,
+    pub subtitles: SubtitleSettings,
+    /// Color filter applied to every 3D camera after the camera's own filter
+    pub color_filter: ColorFilter,
+    pub high_contrast_ui: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapTurnDirection {
+    Left,
+    Right,
+}
+
+/// Request a snap turn of the player rig
+#[derive(Message, Debug, Clone, Copy)]
+pub struct SnapTurn(pub SnapTurnDirection);
+
+/// Show a caption on the subtitle layer
+#[derive(Message, Debug, Clone)]
+pub struct Subtitle {
+    pub text: String,
+    pub duration: Duration,
+}
+
+/// Caption shown while the entity (usually an audio source) is spawned
+#[derive(Component, Debug, Clone)]
+pub struct Caption {
+    pub text: String,
+    pub duration: Duration,
+}
+
+#[derive(Resource, Debug, Default)]
+struct SubtitleQueue {
+    queue: VecDeque<Subtitle>,
+    remaining: Option<Duration>,
+}
+
+/// Filter of the camera without [`ComfortSettings::color_filter`]
+#[derive(Component, Debug, Clone, Copy)]
+struct AccessibilityFilter {
+    own: ColorFilter,
+    applied: ColorFilter,
+}
+
+/// Camera whose [`Vignette`] is driven by [`VignetteSettings`]. Cameras with
+/// their own vignette are left alone
+#[derive(Component)]
+struct ComfortVignette;
+
+#[derive(Component)]
+struct SubtitleOverlay;
+
+#[derive(Component)]
+struct SubtitleText;
+
+#[derive(Debug, Default)]
+pub struct ComfortPlugin;
+
+impl Plugin for ComfortPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ComfortSettings>()
+            .init_resource::<SubtitleQueue>()
+            .add_message::<SnapTurn>()
+            .add_message::<Subtitle>()
+            .add_systems(Startup, spawn_comfort_overlays)
+            .add_systems(
+                Update,
+                (
+                    apply_snap_turn,
+                    apply_world_scale,
+                    update_vignette,
+                    queue_captions,
+                    update_subtitles,
+                    apply_accessibility,
+                )
+                    .chain(),
+            );
+    }
+}
+
+impl Default for VignetteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strength: 0.8,
+            max_linear_speed: 3.0,
+            max_angular_speed: std::f32::consts::PI,
+            fade_time: 0.2,
+        }
+    }
+}
+
+impl Default for SnapTurnSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            angle_degrees: 30.0,
+            cooldown: Duration::from_millis(250),
+        }
+    }
+}
+
+impl Default for SubtitleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            font_size: 24.0,
+            text_color: Color::WHITE,
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6),
+        }
+    }
+}
+
+impl Default for ComfortSettings {
+    fn default() -> Self {
+        Self {
+            vignette: Default::default(),
+            snap_turn: Default::default(),
+            world_scale: 1.0,
+            subtitles: Default::default(),
+            color_filter: Default::default(),
+            high_contrast_ui: false,
+        }
+    }
+}
+
+fn spawn_comfort_overlays(mut commands: Commands, settings: Res<ComfortSettings>) {
+    commands
+        .spawn((
+            SubtitleOverlay,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Percent(8.0),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            Visibility::Hidden,
+            GlobalZIndex(i32::MAX),
+            Pickable::IGNORE,
+        ))
+        .with_child((
+            SubtitleText,
+            Text::default(),
+            TextFont {
+                font_size: settings.subtitles.font_size,
+                ..Default::default()
+            },
+            TextColor(settings.subtitles.text_color),
+            TextLayout::new_with_justify(Justify::Center),
+            BackgroundColor(settings.subtitles.background_color),
+            Node {
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..Default::default()
+            },
+        ));
+}
+
+fn apply_snap_turn(
+    mut snap_turns: MessageReader<SnapTurn>,
+    mut rigs: Query<(Entity, &mut Transform, &GlobalTransform), With<PlayerRig>>,
+    children: Query<&Children>,
+    cameras: Query<&GlobalTransform, With<Camera3d>>,
+    settings: Res<ComfortSettings>,
+    time: Res<Time>,
+    mut last_turn: Local<Option<Duration>>,
+) {
+    for SnapTurn(direction) in snap_turns.read() {
+        if !settings.snap_turn.enabled {
+            continue;
+        }
+        let now = time.elapsed();
+        if last_turn.is_some_and(|last| now.saturating_sub(last) < settings.snap_turn.cooldown) {
+            continue;
+        }
+        *last_turn = Some(now);
+
+        let angle = settings.snap_turn.angle_degrees.to_radians();
+        let angle = match direction {
+            SnapTurnDirection::Left => angle,
+            SnapTurnDirection::Right => -angle,
+        };
+        for (rig, mut transform, global_transform) in rigs.iter_mut() {
+            // Turn around the head, between the eyes, so the user stays in place
+            let (sum, count) = cameras
+                .iter_many(children.iter_descendants(rig))
+                .fold((Vec3::ZERO, 0), |(sum, count), camera| {
+                    (sum + camera.translation(), count + 1)
+                });
+            if count == 0 {
+                transform.rotate_y(angle);
+                continue;
+            }
+            let head_in_rig = global_transform
+                .affine()
+                .inverse()
+                .transform_point3(sum / count as f32);
+            let head = transform.transform_point(head_in_rig);
+            transform.rotate_around(head, Quat::from_rotation_y(angle));
+        }
+    }
+}
+
+fn apply_world_scale(
+    settings: Res<ComfortSettings>,
+    mut rigs: Query<&mut Transform, With<PlayerRig>>,
+    added: Query<(), Added<PlayerRig>>,
+) {
+    if !settings.is_changed() && added.is_empty() {
+        return;
+    }
+    let world_scale = settings.world_scale.max(f32::EPSILON);
+    for mut transform in rigs.iter_mut() {
+        // Shrinking the user makes the world look bigger
+        transform.scale = Vec3::splat(1.0 / world_scale);
+    }
+}
+
+fn update_vignette(
+    mut commands: Commands,
+    settings: Res<ComfortSettings>,
+    time: Res<Time>,
+    rigs: Query<&GlobalTransform, With<PlayerRig>>,
+    mut cameras: Query<(Entity, Option<&mut Vignette>, Has<ComfortVignette>), With<Camera3d>>,
+    mut previous: Local<Option<(Vec3, Quat)>>,
+    mut intensity: Local<f32>,
+) {
+    let delta = time.delta_secs();
+    let vignette = &settings.vignette;
+
+    let target = match (rigs.iter().next(), delta > 0.0) {
+        (Some(rig), true) => {
+            let (_, rotation, translation) = rig.to_scale_rotation_translation();
+            let target = match *previous {
+                Some((prev_translation, prev_rotation)) => {
+                    let linear = translation.distance(prev_translation) / delta;
+                    let angular = prev_rotation.angle_between(rotation) / delta;
+                    (linear / vignette.max_linear_speed.max(f32::EPSILON))
+                        .max(angular / vignette.max_angular_speed.max(f32::EPSILON))
+                        .min(1.0)
+                }
+                None => 0.0,
+            };
+            *previous = Some((translation, rotation));
+            target
+        }
+        _ => 0.0,
+    };
+    let target = if vignette.enabled {
+        target * vignette.strength
+    } else {
+        0.0
+    };
+
+    let step = if vignette.fade_time > 0.0 {
+        delta / vignette.fade_time
+    } else {
+        1.0
+    };
+    *intensity += (target - *intensity).clamp(-step, step);
+
+    for (entity, camera_vignette, comfort) in cameras.iter_mut() {
+        match camera_vignette {
+            Some(mut camera_vignette) if comfort => {
+                if camera_vignette.intensity != *intensity {
+                    camera_vignette.intensity = *intensity;
+                }
+            }
+            Some(_) => {}
+            None => {
+                commands.entity(entity).insert((
+                    ComfortVignette,
+                    Vignette {
+                        intensity: *intensity,
+                        ..Default::default()
+                    },
+                ));
+            }
+        }
+    }
+}
+
+fn queue_captions(
+    captions: Query<&Caption, Added<Caption>>,
+    mut subtitles: MessageWriter<Subtitle>,
+) {
+    for caption in captions.iter() {
+        subtitles.write(Subtitle {
+            text: caption.text.clone(),
+            duration: caption.duration,
+        });
+    }
+}
+
+fn update_subtitles(
+    mut subtitles: MessageReader<Subtitle>,
+    mut queue: ResMut<SubtitleQueue>,
+    settings: Res<ComfortSettings>,
+    time: Res<Time>,
+    mut overlays: Query<&mut Visibility, With<SubtitleOverlay>>,
+    mut texts: Query<
+        (
+            &mut Text,
+            &mut TextFont,
+            &mut TextColor,
+            &mut BackgroundColor,
+        ),
+        With<SubtitleText>,
+    >,
+) {
+    queue.queue.extend(subtitles.read().cloned());
+
+    if let Some(remaining) = queue.remaining {
+        let remaining = remaining.saturating_sub(time.delta());
+        queue.remaining = (!remaining.is_zero()).then_some(remaining);
+    }
+
+    let next = if queue.remaining.is_none() {
+        queue.queue.pop_front()
+    } else {
+        None
+    };
+    if let Some(subtitle) = &next {
+        queue.remaining = Some(subtitle.duration);
+    }
+
+    let visible = settings.subtitles.enabled && queue.remaining.is_some();
+    for mut visibility in overlays.iter_mut() {
+        *visibility = if visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    for (mut text, mut font, mut color, mut background) in texts.iter_mut() {
+        if let Some(subtitle) = &next {
+            text.0 = subtitle.text.clone();
+        }
+        if settings.is_changed() {
+            font.font_size = settings.subtitles.font_size;
+            color.0 = settings.subtitles.text_color;
+            background.0 = settings.subtitles.background_color;
+        }
+    }
+}
+
+fn apply_accessibility(
+    mut commands: Commands,
+    settings: Res<ComfortSettings>,
+    mut theme: ResMut<UiTheme>,
+    cameras: Query<(Entity, Option<&ColorFilter>, Option<&AccessibilityFilter>), With<Camera3d>>,
+) {
+    if settings.is_changed() && theme.is_high_contrast() != settings.high_contrast_ui {
+        theme.set_high_contrast(settings.high_contrast_ui);
+    }
+    for (entity, filter, accessibility) in cameras.iter() {
+        let own = match accessibility {
+            Some(accessibility) if filter == Some(&accessibility.applied) => {
+                if !settings.is_changed() {
+                    continue;
+                }
+                accessibility.own
+            }
+            // New camera, or the application changed the camera's filter
+            _ => filter.copied().unwrap_or_default(),
+        };
+        let applied = own.then(&settings.color_filter);
+        commands
+            .entity(entity)
+            .insert((applied, AccessibilityFilter { own, applied }));
+    }
+}
@@ -0,0 +1,244 @@
+use std::path::{Component, Path, PathBuf};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::anyhow;
+use bevy::{
+    asset::io::{
+        memory::{Dir, MemoryAssetReader},
+        AssetSourceBuilder,
+    },
+    prelude::*,
+};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+/// Asset source of mounted packages, e.g. `package://models/robot.glb`
+pub const CONTENT_PACKAGE_SOURCE: &str = "package";
+
+const MAGIC: &[u8; 8] = b"XRDSPKG1";
+const FLAG_ENCRYPTED: u8 = 1;
+const SIGNATURE_LENGTH: usize = 64;
+const NONCE_LENGTH: usize = 12;
+
+/// Files of a content package
+///
+/// Layout: `magic | flags | ed25519 signature | [nonce] | body` where the
+/// signature covers everything except itself and the body is AES-256-GCM
+/// encrypted when the encrypted flag is set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContentPackage {
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+/// Keys for opening content packages and the asset source they are mounted to
+#[derive(Resource, Clone)]
+pub struct ContentProtection {
+    key: Option<[u8; 32]>,
+    trusted_keys: Vec<VerifyingKey>,
+    /// Accept packages without a trusted signature. Only for development
+    pub allow_unsigned: bool,
+    dir: Dir,
+}
+
+/// Registers the `package://` asset source. Must be added before `AssetPlugin`
+pub struct ContentProtectionPlugin {
+    pub protection: ContentProtection,
+}
+
+impl ContentPackage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: impl Into<String>, data: Vec<u8>) {
+        self.files.push((path.into(), data));
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut body = vec![];
+        body.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+        for (path, data) in &self.files {
+            body.extend_from_slice(&(path.len() as u32).to_le_bytes());
+            body.extend_from_slice(path.as_bytes());
+            body.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            body.extend_from_slice(data);
+        }
+        body
+    }
+
+    fn decode(mut body: &[u8]) -> anyhow::Result<Self> {
+        fn take<'a>(body: &mut &'a [u8], len: usize) -> anyhow::Result<&'a [u8]> {
+            if body.len() < len {
+                return Err(anyhow!("Truncated content package"));
+            }
+            let (head, tail) = body.split_at(len);
+            *body = tail;
+            Ok(head)
+        }
+
+        let count = u32::from_le_bytes(take(&mut body, 4)?.try_into()?);
+        let mut files = Vec::with_capacity(count.min(1024) as usize);
+        for _ in 0..count {
+            let path_len = u32::from_le_bytes(take(&mut body, 4)?.try_into()?) as usize;
+            let path = std::str::from_utf8(take(&mut body, path_len)?)?.to_owned();
+            let data_len = u64::from_le_bytes(take(&mut body, 8)?.try_into()?) as usize;
+            let data = take(&mut body, data_len)?.to_vec();
+            files.push((path, data));
+        }
+        if !body.is_empty() {
+            return Err(anyhow!("Trailing data in content package"));
+        }
+        Ok(Self { files })
+    }
+
+    /// Sign and optionally encrypt the package. `nonce` must never be reused with the same key
+    pub fn seal(
+        &self,
+        signing_key: &[u8; 32],
+        encryption: Option<(&[u8; 32], [u8; NONCE_LENGTH])>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut signed = MAGIC.to_vec();
+        match encryption {
+            Some((key, nonce)) => {
+                signed.push(FLAG_ENCRYPTED);
+                signed.extend_from_slice(&nonce);
+                let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+                let body = cipher
+                    .encrypt(
+                        Nonce::from_slice(&nonce),
+                        Payload {
+                            msg: &self.encode(),
+                            aad: MAGIC,
+                        },
+                    )
+                    .map_err(|_| anyhow!("Could not encrypt content package"))?;
+                signed.extend_from_slice(&body);
+            }
+            None => {
+                signed.push(0);
+                signed.extend_from_slice(&self.encode());
+            }
+        }
+
+        let signature = SigningKey::from_bytes(signing_key).sign(&signed);
+        let mut package = signed[..MAGIC.len() + 1].to_vec();
+        package.extend_from_slice(&signature.to_bytes());
+        package.extend_from_slice(&signed[MAGIC.len() + 1..]);
+        Ok(package)
+    }
+}
+
+impl Default for ContentProtection {
+    fn default() -> Self {
+        Self {
+            key: None,
+            trusted_keys: vec![],
+            allow_unsigned: false,
+            dir: Dir::new(PathBuf::new()),
+        }
+    }
+}
+
+impl ContentProtection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// AES-256-GCM key used to decrypt packages
+    pub fn with_key(mut self, key: [u8; 32]) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Accept packages signed by the ed25519 public key
+    pub fn with_trusted_key(mut self, public_key: &[u8; 32]) -> anyhow::Result<Self> {
+        self.trusted_keys
+            .push(VerifyingKey::from_bytes(public_key)?);
+        Ok(self)
+    }
+
+    /// Verify the signature and decrypt a package
+    pub fn open(&self, package: &[u8]) -> anyhow::Result<ContentPackage> {
+        let header = MAGIC.len() + 1;
+        if package.len() < header + SIGNATURE_LENGTH || &package[..MAGIC.len()] != MAGIC {
+            return Err(anyhow!("Not a content package"));
+        }
+        let flags = package[MAGIC.len()];
+        let signature =
+            Signature::from_bytes(package[header..header + SIGNATURE_LENGTH].try_into()?);
+        let rest = &package[header + SIGNATURE_LENGTH..];
+
+        let mut signed = package[..header].to_vec();
+        signed.extend_from_slice(rest);
+        let trusted = self
+            .trusted_keys
+            .iter()
+            .any(|key| key.verify_strict(&signed, &signature).is_ok());
+        if !trusted {
+            if !self.allow_unsigned {
+                return Err(anyhow!("Content package signature is not trusted"));
+            }
+            warn!("Opening content package without a trusted signature");
+        }
+
+        if flags & FLAG_ENCRYPTED == 0 {
+            return ContentPackage::decode(rest);
+        }
+        let key = self
+            .key
+            .ok_or_else(|| anyhow!("Content package is encrypted but no key is set"))?;
+        if rest.len() < NONCE_LENGTH {
+            return Err(anyhow!("Truncated content package"));
+        }
+        let (nonce, body) = rest.split_at(NONCE_LENGTH);
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let body = cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: body,
+                    aad: MAGIC,
+                },
+            )
+            .map_err(|_| anyhow!("Could not decrypt content package"))?;
+        ContentPackage::decode(&body)
+    }
+
+    /// Open a package and make its files loadable from `package://<mount point>/<path>`
+    pub fn mount(&self, mount_point: &str, package: &[u8]) -> anyhow::Result<usize> {
+        let package = self.open(package)?;
+        let root = Path::new(mount_point);
+        for (path, _) in &package.files {
+            let valid = Path::new(path)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+            if !valid {
+                return Err(anyhow!("Invalid path in content package: {}", path));
+            }
+        }
+        let count = package.files.len();
+        for (path, data) in package.files {
+            self.dir.insert_asset(&root.join(path), data);
+        }
+        info!("Mounted {} files at package://{}", count, mount_point);
+        Ok(count)
+    }
+
+    pub fn mount_file(&self, mount_point: &str, path: impl AsRef<Path>) -> anyhow::Result<usize> {
+        self.mount(mount_point, &std::fs::read(path)?)
+    }
+}
+
+impl Plugin for ContentProtectionPlugin {
+    fn build(&self, app: &mut App) {
+        let dir = self.protection.dir.clone();
+        app.register_asset_source(
+            CONTENT_PACKAGE_SOURCE,
+            AssetSourceBuilder::default()
+                .with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+        )
+        .insert_resource(self.protection.clone());
+    }
+}
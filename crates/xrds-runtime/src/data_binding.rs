@@ -0,0 +1,655 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use xrds_components::{Chart, ChartSeries};
+use xrds_net::{client::ClientBuilder, common::enums::PROTOCOLS};
+
+/// How often files are checked for changes with [`DataRefresh::OnChange`]
+const FILE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataFormat {
+    #[default]
+    Csv,
+    /// An array of objects, each object is a row
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataRefresh {
+    #[default]
+    Once,
+    Interval(Duration),
+    /// Reload files when they are modified. URLs are loaded once
+    OnChange,
+}
+
+/// Rows of named columns, cells are kept as text
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub enum DataSourceError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Csv { line: usize, message: String },
+    Http(String),
+}
+
+/// Table loaded from a CSV or JSON file or URL
+///
+/// URLs are fetched through `xrds-net` on a background thread. Entities with
+/// a [`DataBinding`] or [`ChartBinding`] are updated whenever the table is
+/// (re)loaded.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct DataSource {
+    /// File path or `http://`/`https://` URL
+    pub location: String,
+    pub format: DataFormat,
+    pub refresh: DataRefresh,
+    table: Option<DataTable>,
+    loading: bool,
+    loaded: bool,
+    since_load: Duration,
+    modified: Option<SystemTime>,
+}
+
+/// Written when a [`DataSource`] finished loading
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct DataSourceLoaded {
+    pub source: Entity,
+    /// The error if loading failed, the previous table is kept
+    pub error: Option<String>,
+}
+
+/// Row of a table used by a [`DataBinding`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataRow {
+    Index(usize),
+    /// First row with `value` in `column`
+    Key {
+        column: String,
+        value: String,
+    },
+}
+
+/// Visual property set from a column
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundProperty {
+    /// Scale along y
+    Height,
+    /// Translation along an axis, 0 for x, 1 for y and 2 for z
+    Position(usize),
+    /// Base color of the `StandardMaterial`, from `low` at the start of the
+    /// range to `high` at its end. The material should not be shared
+    Color { low: Color, high: Color },
+}
+
+/// Maps a column to a property, values in `range` are mapped to `output`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyBinding {
+    pub column: String,
+    pub property: BoundProperty,
+    pub range: (f32, f32),
+    pub output: (f32, f32),
+}
+
+/// Sets properties of the entity from a row of a [`DataSource`]
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct DataBinding {
+    pub source: Entity,
+    pub row: DataRow,
+    pub properties: Vec<PropertyBinding>,
+}
+
+/// Column plotted as a series of a [`Chart`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartColumn {
+    pub column: String,
+    pub color: Color,
+}
+
+/// Replaces the series of the [`Chart`] of the entity with columns of a [`DataSource`]
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct ChartBinding {
+    pub source: Entity,
+    pub series: Vec<ChartColumn>,
+    /// Column with the x values. Rows are numbered if `None`
+    pub x: Option<String>,
+    /// Column with the category names of bar and line charts
+    pub categories: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct DataBindingPlugin;
+
+#[derive(Resource)]
+struct DataFetches {
+    sender: Sender<(Entity, Result<String, DataSourceError>)>,
+    receiver: Mutex<Receiver<(Entity, Result<String, DataSourceError>)>>,
+}
+
+impl DataFormat {
+    /// JSON for `.json` files, CSV otherwise
+    pub fn from_location(location: &str) -> Self {
+        let path = location.split(['?', '#']).next().unwrap_or(location);
+        if path.to_ascii_lowercase().ends_with(".json") {
+            Self::Json
+        } else {
+            Self::Csv
+        }
+    }
+}
+
+impl DataTable {
+    /// Comma separated values with a header row, fields may be quoted
+    pub fn from_csv(text: &str) -> Result<Self, DataSourceError> {
+        let mut records = vec![];
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut line = 1;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if field.is_empty() => quoted = true,
+                ',' if !quoted => record.push(std::mem::take(&mut field)),
+                '\n' if !quoted => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                    line += 1;
+                }
+                '\r' if !quoted => {}
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+        }
+        if quoted {
+            return Err(DataSourceError::Csv {
+                line,
+                message: "unterminated quote".to_owned(),
+            });
+        }
+        if !field.is_empty() || !record.is_empty() {
+            record.push(field);
+            records.push(record);
+        }
+        // Skip blank lines
+        records.retain(|record| !(record.len() == 1 && record[0].trim().is_empty()));
+
+        let mut records = records.into_iter();
+        let columns = records
+            .next()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|column| column.trim().to_owned())
+            .collect::<Vec<_>>();
+        let rows = records.collect::<Vec<_>>();
+        if let Some(index) = rows.iter().position(|row| row.len() != columns.len()) {
+            return Err(DataSourceError::Csv {
+                line: index + 2,
+                message: format!(
+                    "{} fields in a table of {} columns",
+                    rows[index].len(),
+                    columns.len()
+                ),
+            });
+        }
+        Ok(Self { columns, rows })
+    }
+
+    /// Array of objects, the columns are the keys in order of appearance
+    pub fn from_json(text: &str) -> Result<Self, DataSourceError> {
+        let objects: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(text)?;
+        let mut columns: Vec<String> = vec![];
+        for key in objects.iter().flat_map(|object| object.keys()) {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        let rows = objects
+            .iter()
+            .map(|object| {
+                columns
+                    .iter()
+                    .map(|column| match object.get(column) {
+                        Some(serde_json::Value::String(text)) => text.clone(),
+                        Some(serde_json::Value::Null) | None => String::new(),
+                        Some(value) => value.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Self { columns, rows })
+    }
+
+    pub fn parse(text: &str, format: DataFormat) -> Result<Self, DataSourceError> {
+        match format {
+            DataFormat::Csv => Self::from_csv(text),
+            DataFormat::Json => Self::from_json(text),
+        }
+    }
+
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+
+    pub fn get(&self, row: usize, column: &str) -> Option<&str> {
+        let column = self.column(column)?;
+        self.rows.get(row)?.get(column).map(String::as_str)
+    }
+
+    pub fn number(&self, row: usize, column: &str) -> Option<f32> {
+        self.get(row, column)?.trim().parse().ok()
+    }
+
+    /// Values of a column, cells that are not numbers are skipped
+    pub fn numbers(&self, column: &str) -> Vec<f32> {
+        (0..self.rows.len())
+            .filter_map(|row| self.number(row, column))
+            .collect()
+    }
+
+    pub fn find_row(&self, row: &DataRow) -> Option<usize> {
+        match row {
+            DataRow::Index(index) => (*index < self.rows.len()).then_some(*index),
+            DataRow::Key { column, value } => {
+                (0..self.rows.len()).find(|row| self.get(*row, column) == Some(value.as_str()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for DataSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read data: {error}"),
+            Self::Json(error) => write!(f, "Invalid JSON data: {error}"),
+            Self::Csv { line, message } => write!(f, "Invalid CSV data on line {line}: {message}"),
+            Self::Http(error) => write!(f, "Could not fetch data: {error}"),
+        }
+    }
+}
+
+impl Error for DataSourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Json(error) => Some(error),
+            Self::Csv { .. } | Self::Http(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DataSourceError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for DataSourceError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl DataSource {
+    /// The format is guessed from the extension
+    pub fn new(location: impl Into<String>) -> Self {
+        let location = location.into();
+        Self {
+            format: DataFormat::from_location(&location),
+            location,
+            refresh: DataRefresh::Once,
+            table: None,
+            loading: false,
+            loaded: false,
+            since_load: Duration::ZERO,
+            modified: None,
+        }
+    }
+
+    pub fn with_format(mut self, format: DataFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_refresh(mut self, refresh: DataRefresh) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Latest table, `None` until the first load finished
+    pub fn table(&self) -> Option<&DataTable> {
+        self.table.as_ref()
+    }
+
+    /// Load again on the next update
+    pub fn reload(&mut self) {
+        self.loaded = false;
+    }
+
+    pub fn is_url(&self) -> bool {
+        self.location.starts_with("http://") || self.location.starts_with("https://")
+    }
+}
+
+impl PropertyBinding {
+    /// Column values from `range` mapped linearly to `output`
+    pub fn new(column: impl Into<String>, property: BoundProperty) -> Self {
+        Self {
+            column: column.into(),
+            property,
+            range: (0.0, 1.0),
+            output: (0.0, 1.0),
+        }
+    }
+
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = (min, max);
+        self
+    }
+
+    pub fn with_output(mut self, min: f32, max: f32) -> Self {
+        self.output = (min, max);
+        self
+    }
+
+    /// Position of `value` in the range, clamped to 0..=1
+    fn factor(&self, value: f32) -> f32 {
+        let (min, max) = self.range;
+        if max == min {
+            return 0.0;
+        }
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    }
+}
+
+impl DataBinding {
+    pub fn new(source: Entity, row: DataRow) -> Self {
+        Self {
+            source,
+            row,
+            properties: vec![],
+        }
+    }
+
+    pub fn with_property(mut self, property: PropertyBinding) -> Self {
+        self.properties.push(property);
+        self
+    }
+}
+
+impl ChartBinding {
+    pub fn new(source: Entity) -> Self {
+        Self {
+            source,
+            series: vec![],
+            x: None,
+            categories: None,
+        }
+    }
+
+    pub fn with_series(mut self, column: impl Into<String>, color: Color) -> Self {
+        self.series.push(ChartColumn {
+            column: column.into(),
+            color,
+        });
+        self
+    }
+
+    pub fn with_x(mut self, column: impl Into<String>) -> Self {
+        self.x = Some(column.into());
+        self
+    }
+
+    pub fn with_categories(mut self, column: impl Into<String>) -> Self {
+        self.categories = Some(column.into());
+        self
+    }
+}
+
+impl Plugin for DataBindingPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        app.insert_resource(DataFetches {
+            sender,
+            receiver: Mutex::new(receiver),
+        })
+        .add_message::<DataSourceLoaded>()
+        .add_systems(
+            Update,
+            (
+                (load_data_sources, receive_data_fetches).chain(),
+                (apply_data_bindings, apply_chart_bindings),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn fetch_url(url: &str) -> Result<String, DataSourceError> {
+    let protocol = if url.starts_with("https://") {
+        PROTOCOLS::HTTPS
+    } else {
+        PROTOCOLS::HTTP
+    };
+    let response = ClientBuilder::new()
+        .set_protocol(protocol)
+        .build()
+        .set_url(url)
+        .request();
+    if let Some(error) = response.error {
+        return Err(DataSourceError::Http(error));
+    }
+    if !(200..300).contains(&response.status_code) {
+        return Err(DataSourceError::Http(format!(
+            "status {}",
+            response.status_code
+        )));
+    }
+    String::from_utf8(response.body).map_err(|error| DataSourceError::Http(error.to_string()))
+}
+
+/// Table from a fetched or read text, the previous one is kept on errors
+fn finish_load(
+    entity: Entity,
+    source: &mut DataSource,
+    text: Result<String, DataSourceError>,
+    loaded: &mut MessageWriter<DataSourceLoaded>,
+) {
+    source.loading = false;
+    let table = text.and_then(|text| DataTable::parse(&text, source.format));
+    let error = match table {
+        Ok(table) => {
+            source.table = Some(table);
+            None
+        }
+        Err(error) => {
+            warn!("Data source {}: {error}", source.location);
+            Some(error.to_string())
+        }
+    };
+    loaded.write(DataSourceLoaded {
+        source: entity,
+        error,
+    });
+}
+
+fn load_data_sources(
+    time: Res<Time<Real>>,
+    fetches: Res<DataFetches>,
+    mut sources: Query<(Entity, &mut DataSource)>,
+    mut loaded: MessageWriter<DataSourceLoaded>,
+) {
+    for (entity, mut source) in sources.iter_mut() {
+        let source = source.bypass_change_detection();
+        source.since_load += time.delta();
+        if source.loading {
+            continue;
+        }
+        let due = match source.refresh {
+            _ if !source.loaded => true,
+            DataRefresh::Once => false,
+            DataRefresh::Interval(interval) => source.since_load >= interval,
+            DataRefresh::OnChange if source.is_url() => false,
+            DataRefresh::OnChange if source.since_load >= FILE_POLL_INTERVAL => {
+                source.since_load = Duration::ZERO;
+                let modified = std::fs::metadata(&source.location)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                modified.is_some() && modified != source.modified
+            }
+            DataRefresh::OnChange => false,
+        };
+        if !due {
+            continue;
+        }
+        source.loaded = true;
+        source.since_load = Duration::ZERO;
+
+        if source.is_url() {
+            source.loading = true;
+            let sender = fetches.sender.clone();
+            let url = source.location.clone();
+            thread::spawn(move || {
+                let _ = sender.send((entity, fetch_url(&url)));
+            });
+        } else {
+            source.modified = std::fs::metadata(&source.location)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            let text = std::fs::read_to_string(&source.location).map_err(DataSourceError::from);
+            finish_load(entity, source, text, &mut loaded);
+        }
+    }
+}
+
+fn receive_data_fetches(
+    fetches: Res<DataFetches>,
+    mut sources: Query<&mut DataSource>,
+    mut loaded: MessageWriter<DataSourceLoaded>,
+) {
+    let Ok(receiver) = fetches.receiver.lock() else {
+        return;
+    };
+    for (entity, text) in receiver.try_iter() {
+        if let Ok(mut source) = sources.get_mut(entity) {
+            finish_load(entity, &mut source, text, &mut loaded);
+        }
+    }
+}
+
+/// Bindings of sources loaded this frame and bindings added since
+fn is_due(source: Entity, added: bool, loaded: &[Entity]) -> bool {
+    added || loaded.contains(&source)
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_data_bindings(
+    mut loaded: MessageReader<DataSourceLoaded>,
+    sources: Query<&DataSource>,
+    mut bindings: Query<(
+        Ref<DataBinding>,
+        &mut Transform,
+        Option<&MeshMaterial3d<StandardMaterial>>,
+    )>,
+    mut materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    let loaded = loaded
+        .read()
+        .filter(|loaded| loaded.error.is_none())
+        .map(|loaded| loaded.source)
+        .collect::<Vec<_>>();
+    for (binding, mut transform, material) in bindings.iter_mut() {
+        if !is_due(binding.source, binding.is_added(), &loaded) {
+            continue;
+        }
+        let Some(table) = sources.get(binding.source).ok().and_then(DataSource::table) else {
+            continue;
+        };
+        let Some(row) = table.find_row(&binding.row) else {
+            continue;
+        };
+        for property in &binding.properties {
+            let Some(value) = table.number(row, &property.column) else {
+                continue;
+            };
+            let factor = property.factor(value);
+            let output = property.output.0 + (property.output.1 - property.output.0) * factor;
+            match &property.property {
+                BoundProperty::Height => transform.scale.y = output,
+                BoundProperty::Position(axis) => transform.translation[(*axis).min(2)] = output,
+                BoundProperty::Color { low, high } => {
+                    let (Some(material), Some(materials)) = (material, materials.as_mut()) else {
+                        continue;
+                    };
+                    if let Some(material) = materials.get_mut(&material.0) {
+                        material.base_color = low.mix(high, factor);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn apply_chart_bindings(
+    mut loaded: MessageReader<DataSourceLoaded>,
+    sources: Query<&DataSource>,
+    mut charts: Query<(Ref<ChartBinding>, &mut Chart)>,
+) {
+    let loaded = loaded
+        .read()
+        .filter(|loaded| loaded.error.is_none())
+        .map(|loaded| loaded.source)
+        .collect::<Vec<_>>();
+    for (binding, mut chart) in charts.iter_mut() {
+        if !is_due(binding.source, binding.is_added(), &loaded) {
+            continue;
+        }
+        let Some(table) = sources.get(binding.source).ok().and_then(DataSource::table) else {
+            continue;
+        };
+        chart.series = binding
+            .series
+            .iter()
+            .map(|column| {
+                let points = (0..table.rows.len()).filter_map(|row| {
+                    let y = table.number(row, &column.column)?;
+                    let x = match &binding.x {
+                        Some(x) => table.number(row, x)?,
+                        None => row as f32,
+                    };
+                    Some(Vec3::new(x, y, 0.0))
+                });
+                ChartSeries::new(column.column.clone(), column.color).with_points(points)
+            })
+            .collect();
+        if let Some(categories) = &binding.categories {
+            chart.categories = (0..table.rows.len())
+                .map(|row| table.get(row, categories).unwrap_or_default().to_owned())
+                .collect();
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    EguiContext, EguiContextSettings, EguiGlobalSettings, EguiInputEvent, EguiPlugin,
+    EguiPreUpdateSet, EguiPrimaryContextPass, PrimaryEguiContext,
+};
+use xrds_openxr::{OpenXrHand, OpenXrInput, OpenXrLayerPanel, OpenXrPanelShape};
+
+use crate::{PluginContext, UiInputCapture, XrdsPlugin};
+
+pub use bevy_egui::{egui, EguiContexts};
+
+/// Where the primary egui context is drawn
+#[derive(Clone, Debug, PartialEq)]
+pub enum EguiTarget {
+    /// Over the final image of the first window camera, after tonemapping
+    Window,
+    /// Onto a quad [`OpenXrLayerPanel`], for XR sessions without a window.
+    /// `size` is in meters and the quad faces +Z of `transform`. The aim rays
+    /// of the controllers move the egui pointer over the panel and the
+    /// triggers click
+    Panel {
+        size: Vec2,
+        resolution: UVec2,
+        transform: Transform,
+    },
+}
+
+/// Draws egui with the runtime
+///
+/// UI is built every frame by systems in [`EguiPrimaryContextPass`], added
+/// with [`PluginContext::add_egui_systems`]. While egui wants the pointer or
+/// the keyboard, e.g. over a window or while a text field has focus, the
+/// input is kept from [`crate::RuntimeEvent`]s through [`UiInputCapture`].
+/// Can be added to a `RuntimeBuilder` with `with_plugin`.
+#[derive(Clone, Debug)]
+pub struct XrdsEguiPlugin {
+    pub target: EguiTarget,
+}
+
+/// Layer panel showing the primary egui context of [`EguiTarget::Panel`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct EguiPanel;
+
+/// Trigger pull that presses the egui pointer
+const TRIGGER_PRESS: f32 = 0.6;
+/// Trigger pull that releases it again, lower to avoid flicker
+const TRIGGER_RELEASE: f32 = 0.4;
+
+/// Controller pointing at the panel and whether its trigger is pressed
+#[derive(Default)]
+struct PanelPointer {
+    hand: Option<OpenXrHand>,
+    pressed: bool,
+}
+
+impl XrdsEguiPlugin {
+    pub fn window() -> Self {
+        Self {
+            target: EguiTarget::Window,
+        }
+    }
+
+    /// 1.2 x 0.8 meter panel two meters in front of the origin at eye height
+    pub fn panel() -> Self {
+        Self {
+            target: EguiTarget::Panel {
+                size: Vec2::new(1.2, 0.8),
+                resolution: UVec2::new(1200, 800),
+                transform: Transform::from_xyz(0.0, 1.5, -2.0),
+            },
+        }
+    }
+}
+
+impl XrdsPlugin for XrdsEguiPlugin {
+    fn build(&self, context: &mut PluginContext) {
+        context
+            .add_plugins(EguiPlugin::default())
+            .add_systems(Last, update_input_capture);
+
+        if let EguiTarget::Panel {
+            size,
+            resolution,
+            transform,
+        } = self.target.clone()
+        {
+            // The first camera would get the context, which is an XR view here
+            context
+                .app()
+                .world_mut()
+                .insert_resource(EguiGlobalSettings {
+                    auto_create_primary_context: false,
+                    ..default()
+                });
+            context
+                .add_systems(Startup, move |mut commands: Commands| {
+                    spawn_panel(&mut commands, size, resolution, transform);
+                })
+                .add_systems(
+                    PreUpdate,
+                    write_panel_pointer_events.before(EguiPreUpdateSet::ProcessInput),
+                );
+        }
+    }
+}
+
+impl PluginContext<'_> {
+    /// Add systems building the egui UI of every frame, e.g. with
+    /// `EguiContexts::ctx_mut`
+    pub fn add_egui_systems<M>(
+        &mut self,
+        systems: impl IntoScheduleConfigs<bevy::ecs::system::ScheduleSystem, M>,
+    ) -> &mut Self {
+        self.add_systems(EguiPrimaryContextPass, systems)
+    }
+}
+
+fn spawn_panel(commands: &mut Commands, size: Vec2, resolution: UVec2, transform: Transform) {
+    let panel = OpenXrLayerPanel::quad(size, resolution);
+    commands.spawn((
+        Camera2d,
+        Camera {
+            target: panel.render_target(),
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        PrimaryEguiContext,
+    ));
+    commands.spawn((EguiPanel, panel, transform));
+}
+
+/// Position in pixels of the panel where `ray` hits the front of a quad
+/// panel
+pub fn panel_hit(
+    panel: &OpenXrLayerPanel,
+    transform: &GlobalTransform,
+    ray: Ray3d,
+) -> Option<Vec2> {
+    let OpenXrPanelShape::Quad { size } = panel.shape else {
+        return None;
+    };
+    let local_from_world = transform.affine().inverse();
+    let origin = local_from_world.transform_point3(ray.origin);
+    let direction = local_from_world.transform_vector3(*ray.direction);
+    if direction.z >= 0.0 || origin.z <= 0.0 {
+        return None;
+    }
+    let hit = origin + direction * (-origin.z / direction.z);
+    let uv = Vec2::new(hit.x / size.x + 0.5, 0.5 - hit.y / size.y);
+    if uv.cmplt(Vec2::ZERO).any() || uv.cmpgt(Vec2::ONE).any() {
+        return None;
+    }
+    Some(uv * panel.resolution().as_vec2())
+}
+
+/// Turns the controller aim ray hitting the panel into egui pointer events
+fn write_panel_pointer_events(
+    input: Option<Res<OpenXrInput>>,
+    panels: Query<(&OpenXrLayerPanel, &GlobalTransform), With<EguiPanel>>,
+    contexts: Query<(Entity, &EguiContextSettings), With<PrimaryEguiContext>>,
+    mut events: MessageWriter<EguiInputEvent>,
+    mut pointer: Local<PanelPointer>,
+) {
+    let (Some(input), Ok((panel, transform)), Ok((context, settings))) =
+        (input, panels.single(), contexts.single())
+    else {
+        return;
+    };
+    let hit = |hand| {
+        let aim = input.controller(hand).aim?;
+        panel_hit(panel, transform, Ray3d::new(aim.translation, aim.forward()))
+    };
+    let button = |pos, pressed| egui::Event::PointerButton {
+        pos,
+        button: egui::PointerButton::Primary,
+        pressed,
+        modifiers: egui::Modifiers::default(),
+    };
+
+    // The hand already pointing at the panel keeps the pointer
+    let hands = pointer
+        .hand
+        .into_iter()
+        .chain([OpenXrHand::Right, OpenXrHand::Left]);
+    let Some((hand, position)) = hands.filter_map(|hand| Some((hand, hit(hand)?))).next() else {
+        if pointer.hand.take().is_some() {
+            if std::mem::take(&mut pointer.pressed) {
+                events.write(EguiInputEvent {
+                    context,
+                    event: button(egui::Pos2::ZERO, false),
+                });
+            }
+            events.write(EguiInputEvent {
+                context,
+                event: egui::Event::PointerGone,
+            });
+        }
+        return;
+    };
+
+    let position = position / settings.scale_factor;
+    let pos = egui::pos2(position.x, position.y);
+    let trigger = input.controller(hand).trigger;
+    let pressed = if pointer.hand == Some(hand) && pointer.pressed {
+        trigger > TRIGGER_RELEASE
+    } else {
+        trigger > TRIGGER_PRESS
+    };
+    let mut pointer_events = vec![egui::Event::PointerMoved(pos)];
+    if pointer.hand != Some(hand) && pointer.pressed {
+        pointer_events.insert(0, button(pos, false));
+        pointer.pressed = false;
+    }
+    if pressed != pointer.pressed {
+        pointer_events.push(button(pos, pressed));
+    }
+    pointer.hand = Some(hand);
+    pointer.pressed = pressed;
+    events.write_batch(
+        pointer_events
+            .into_iter()
+            .map(|event| EguiInputEvent { context, event }),
+    );
+}
+
+fn update_input_capture(
+    mut capture: ResMut<UiInputCapture>,
+    mut contexts: Query<&mut EguiContext, With<PrimaryEguiContext>>,
+) {
+    let next = match contexts.single_mut() {
+        Ok(mut context) => {
+            let context = context.get_mut();
+            UiInputCapture {
+                pointer: context.wants_pointer_input(),
+                keyboard: context.wants_keyboard_input(),
+            }
+        }
+        Err(_) => UiInputCapture::default(),
+    };
+    capture.set_if_neq(next);
+}
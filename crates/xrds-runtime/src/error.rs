@@ -4,12 +4,16 @@ use std::{error::Error, fmt::Debug};
 #[derive(Debug)]
 pub enum RuntimeError {
     OPENXR,
+    HANG,
+    CAPTURE,
 }
 
 impl fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::OPENXR => write!(f, "OpenXR error"),
+            Self::HANG => write!(f, "Frame hang detected"),
+            Self::CAPTURE => write!(f, "Frame capture failed"),
         }
     }
 }
@@ -0,0 +1,192 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        mouse::{MouseButtonInput, MouseScrollUnit, MouseWheel},
+        ButtonState, InputSystems,
+    },
+    prelude::*,
+    window::{AppLifecycle, WindowEvent},
+};
+
+use crate::RuntimeHandler;
+
+/// Keyboard and mouse input of the desktop window
+///
+/// Delivered to [`RuntimeHandler::on_event`] in the order the window system
+/// reported them, and written as a message for systems. Events of all
+/// windows are included, e.g. of the mirror window of an XR session.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum RuntimeEvent {
+    Key {
+        /// Position of the key, independent of the keyboard layout
+        key: KeyCode,
+        /// Key after the keyboard layout is applied
+        logical_key: Key,
+        pressed: bool,
+        /// Repeated press of a held key
+        repeat: bool,
+        /// Text typed by the press, if any
+        text: Option<String>,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// Cursor position in logical pixels from the top left of the window
+    CursorMoved {
+        position: Vec2,
+        /// Movement since the previous position, `None` after the cursor
+        /// entered the window
+        delta: Option<Vec2>,
+    },
+    /// Raw mouse movement, also while the cursor is grabbed or at the edge
+    /// of the window, e.g. for a fly camera
+    MouseMotion {
+        delta: Vec2,
+    },
+    Scroll {
+        /// Positive `y` scrolls up, positive `x` to the right
+        delta: Vec2,
+        unit: MouseScrollUnit,
+    },
+    CursorLeft,
+    /// The windows lost keyboard focus, held keys will not report a release
+    FocusLost,
+}
+
+/// Input a UI such as egui took during the previous frame
+///
+/// While set, presses, mouse motion and scrolling are not turned into
+/// [`RuntimeEvent`]s, so clicking a button does not also move the camera.
+/// Releases always pass, so nothing is left held.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UiInputCapture {
+    pub pointer: bool,
+    pub keyboard: bool,
+}
+
+/// Collects [`RuntimeEvent`]s from the window events in `PreUpdate`
+#[derive(Debug, Default)]
+pub struct RuntimeEventPlugin;
+
+/// Handler passed to [`Runtime::run`](crate::Runtime::run), called in `Update`
+#[derive(Resource, Clone)]
+pub(crate) struct RuntimeHandlerSlot(pub(crate) Arc<Mutex<dyn RuntimeHandler + Send>>);
+
+impl RuntimeEvent {
+    fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::KeyboardInput(KeyboardInput {
+                key_code,
+                logical_key,
+                state,
+                text,
+                repeat,
+                ..
+            }) => Self::Key {
+                key: *key_code,
+                logical_key: logical_key.clone(),
+                pressed: *state == ButtonState::Pressed,
+                repeat: *repeat,
+                text: text.as_ref().map(ToString::to_string),
+            },
+            WindowEvent::MouseButtonInput(MouseButtonInput { button, state, .. }) => {
+                Self::MouseButton {
+                    button: *button,
+                    pressed: *state == ButtonState::Pressed,
+                }
+            }
+            WindowEvent::CursorMoved(moved) => Self::CursorMoved {
+                position: moved.position,
+                delta: moved.delta,
+            },
+            WindowEvent::MouseMotion(motion) => Self::MouseMotion {
+                delta: motion.delta,
+            },
+            WindowEvent::MouseWheel(MouseWheel { unit, x, y, .. }) => Self::Scroll {
+                delta: Vec2::new(*x, *y),
+                unit: *unit,
+            },
+            WindowEvent::CursorLeft(_) => Self::CursorLeft,
+            WindowEvent::KeyboardFocusLost(_) => Self::FocusLost,
+            _ => return None,
+        })
+    }
+}
+
+impl UiInputCapture {
+    fn passes(&self, event: &RuntimeEvent) -> bool {
+        match event {
+            RuntimeEvent::Key { pressed, .. } => !pressed || !self.keyboard,
+            RuntimeEvent::MouseButton { pressed, .. } => !pressed || !self.pointer,
+            RuntimeEvent::MouseMotion { .. } | RuntimeEvent::Scroll { .. } => !self.pointer,
+            RuntimeEvent::CursorMoved { .. }
+            | RuntimeEvent::CursorLeft
+            | RuntimeEvent::FocusLost => true,
+        }
+    }
+}
+
+impl Plugin for RuntimeEventPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiInputCapture>()
+            .add_message::<RuntimeEvent>()
+            .add_message::<WindowEvent>()
+            .add_message::<AppLifecycle>()
+            .add_systems(PreUpdate, collect_runtime_events.after(InputSystems))
+            .add_systems(
+                Update,
+                dispatch_runtime_events.run_if(resource_exists::<RuntimeHandlerSlot>),
+            );
+    }
+}
+
+fn collect_runtime_events(
+    capture: Res<UiInputCapture>,
+    mut window_events: MessageReader<WindowEvent>,
+    mut events: MessageWriter<RuntimeEvent>,
+) {
+    events.write_batch(
+        window_events
+            .read()
+            .filter_map(RuntimeEvent::from_window_event)
+            .filter(|event| capture.passes(event)),
+    );
+}
+
+/// Calls [`RuntimeHandler::on_event`] for the events of this frame, then
+/// [`RuntimeHandler::on_update`]
+///
+/// [`RuntimeHandler::on_suspended`] is called when the application goes to
+/// the background, e.g. when a headset is taken off, and the window surface
+/// is destroyed. [`RuntimeHandler::on_resumed`] follows once it is recreated
+fn dispatch_runtime_events(
+    handler: Res<RuntimeHandlerSlot>,
+    mut lifecycle: MessageReader<AppLifecycle>,
+    mut events: MessageReader<RuntimeEvent>,
+    mut suspended: Local<bool>,
+) {
+    let Ok(mut handler) = handler.0.lock() else {
+        return;
+    };
+    for state in lifecycle.read() {
+        match state {
+            AppLifecycle::Suspended if !*suspended => {
+                *suspended = true;
+                handler.on_suspended();
+            }
+            // Also reported when the application starts
+            AppLifecycle::Running if *suspended => {
+                *suspended = false;
+                handler.on_resumed();
+            }
+            _ => {}
+        }
+    }
+    for event in events.read() {
+        handler.on_event(event);
+    }
+    handler.on_update();
+}
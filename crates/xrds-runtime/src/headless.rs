@@ -0,0 +1,246 @@
+use std::{io::Cursor, time::Duration};
+
+use bevy::{
+    camera::RenderTarget,
+    prelude::*,
+    render::{
+        gpu_readback::{Readback, ReadbackComplete},
+        render_resource::{TextureFormat, TextureUsages},
+        renderer::RenderDevice,
+    },
+    window::WindowRef,
+};
+
+/// Format of the offscreen framebuffer, what [`CaptureFormat::Rgba8`] returns
+const HEADLESS_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// Rendering into an offscreen framebuffer, without a window or OpenXR
+///
+/// Cameras targeting the primary window draw into the framebuffer instead,
+/// and [`HeadlessFrames`] reads frames back, e.g. for image comparisons in
+/// CI or to stream them from a render server.
+#[derive(Clone, Debug)]
+pub struct HeadlessSettings {
+    /// Size of the framebuffer in pixels
+    pub size: UVec2,
+    /// Time between frames when the runtime runs. Frames are rendered as
+    /// fast as possible if zero
+    pub frame_interval: Duration,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CaptureFormat {
+    /// Rows of sRGB RGBA pixels, top row first
+    #[default]
+    Rgba8,
+    /// PNG file
+    Png,
+}
+
+/// Frame read back from the framebuffer
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct FrameCaptured {
+    /// Frame number, as returned by [`HeadlessFrames::request_capture`]
+    pub frame: u64,
+    pub size: UVec2,
+    pub format: CaptureFormat,
+    pub data: Vec<u8>,
+}
+
+/// Framebuffer of a headless runtime and the frames to read back
+#[derive(Resource, Debug)]
+pub struct HeadlessFrames {
+    image: Handle<Image>,
+    size: UVec2,
+    frame: u64,
+    requests: Vec<(u64, CaptureFormat)>,
+}
+
+/// Readback of one frame
+#[derive(Component, Debug)]
+struct FrameCapture {
+    frame: u64,
+    format: CaptureFormat,
+}
+
+pub struct HeadlessPlugin {
+    pub settings: HeadlessSettings,
+}
+
+impl HeadlessSettings {
+    pub fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            frame_interval: Duration::ZERO,
+        }
+    }
+
+    /// Frames are rendered at most `fps` times per second
+    pub fn with_frame_rate(mut self, fps: f64) -> Self {
+        self.frame_interval = Duration::from_secs_f64(1.0 / fps);
+        self
+    }
+}
+
+impl Default for HeadlessSettings {
+    fn default() -> Self {
+        Self::new(UVec2::new(1280, 720))
+    }
+}
+
+impl HeadlessFrames {
+    /// Image of the framebuffer, e.g. to show it in another camera
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Number of the frame being updated
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Reads back the frame rendered `after_frames` frames after the current
+    /// one and returns its number. The [`FrameCaptured`] message arrives a
+    /// few frames later, once the GPU has finished it
+    pub fn request_capture(&mut self, after_frames: u32, format: CaptureFormat) -> u64 {
+        let frame = self.frame + after_frames as u64;
+        self.requests.push((frame, format));
+        frame
+    }
+
+    /// Whether frames are requested but not read back yet
+    pub fn is_capturing(&self) -> bool {
+        !self.requests.is_empty()
+    }
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        let size = self.settings.size.max(UVec2::ONE);
+        let mut image = Image::new_target_texture(size.x, size.y, HEADLESS_FORMAT);
+        image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+        let image = app.world_mut().resource_mut::<Assets<Image>>().add(image);
+
+        app.insert_resource(HeadlessFrames {
+            image,
+            size,
+            frame: 0,
+            requests: Vec::new(),
+        })
+        .add_message::<FrameCaptured>()
+        .add_systems(
+            PostUpdate,
+            target_headless_framebuffer.before(bevy::camera::CameraUpdateSystems),
+        )
+        .add_systems(Last, start_frame_captures)
+        .add_observer(read_frame_capture);
+    }
+}
+
+/// Cameras of the primary window draw into the framebuffer
+fn target_headless_framebuffer(frames: Res<HeadlessFrames>, mut cameras: Query<&mut Camera>) {
+    for mut camera in cameras.iter_mut() {
+        if matches!(camera.target, RenderTarget::Window(WindowRef::Primary)) {
+            camera.target = RenderTarget::Image(frames.image.clone().into());
+        }
+    }
+}
+
+/// Readbacks are copied after the frame is rendered, so captures of earlier
+/// frames stop reading before the next one
+fn start_frame_captures(
+    mut commands: Commands,
+    mut frames: ResMut<HeadlessFrames>,
+    captures: Query<(Entity, &FrameCapture), With<Readback>>,
+) {
+    let frame = frames.frame;
+    for (entity, capture) in captures.iter() {
+        if capture.frame < frame {
+            commands.entity(entity).remove::<Readback>();
+        }
+    }
+
+    let image = frames.image.clone();
+    frames.requests.retain(|&(requested, format)| {
+        if requested > frame {
+            return true;
+        }
+        commands.spawn((
+            FrameCapture { frame, format },
+            Readback::texture(image.clone()),
+        ));
+        false
+    });
+    frames.frame += 1;
+}
+
+fn read_frame_capture(
+    readback: On<ReadbackComplete>,
+    mut commands: Commands,
+    frames: Res<HeadlessFrames>,
+    captures: Query<&FrameCapture>,
+    mut captured: MessageWriter<FrameCaptured>,
+) {
+    let Ok(capture) = captures.get(readback.entity) else {
+        return;
+    };
+    commands.entity(readback.entity).despawn();
+
+    let Some(pixels) = unpad_rows(&readback.data, frames.size) else {
+        warn!(
+            "Readback of frame {} has {} bytes, expected {}x{} pixels",
+            capture.frame,
+            readback.data.len(),
+            frames.size.x,
+            frames.size.y
+        );
+        return;
+    };
+    let data = match capture.format {
+        CaptureFormat::Rgba8 => pixels,
+        CaptureFormat::Png => match encode_png(pixels, frames.size) {
+            Ok(png) => png,
+            Err(error) => {
+                warn!("Could not encode frame {}: {}", capture.frame, error);
+                return;
+            }
+        },
+    };
+    captured.write(FrameCaptured {
+        frame: capture.frame,
+        size: frames.size,
+        format: capture.format,
+        data,
+    });
+}
+
+/// Rows of readbacks are aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`
+fn unpad_rows(data: &[u8], size: UVec2) -> Option<Vec<u8>> {
+    let row = size.x as usize * 4;
+    let stride = RenderDevice::align_copy_bytes_per_row(row);
+    if data.len() < stride * (size.y as usize - 1) + row {
+        return None;
+    }
+    Some(
+        data.chunks(stride)
+            .take(size.y as usize)
+            .flat_map(|chunk| &chunk[..row])
+            .copied()
+            .collect(),
+    )
+}
+
+pub(crate) fn encode_png(pixels: Vec<u8>, size: UVec2) -> Result<Vec<u8>, image::ImageError> {
+    let image = image::RgbaImage::from_raw(size.x, size.y, pixels).ok_or_else(|| {
+        image::ImageError::Parameter(image::error::ParameterError::from_kind(
+            image::error::ParameterErrorKind::DimensionMismatch,
+        ))
+    })?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
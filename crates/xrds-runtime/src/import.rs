@@ -0,0 +1,253 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    asset::{
+        io::{file::FileAssetReader, AssetSourceBuilder, AssetSourceId},
+        AssetPath,
+    },
+    camera::RenderTarget,
+    prelude::*,
+    window::FileDragAndDrop,
+};
+use xrds_graphics::EnvironmentLighting;
+
+use crate::AssetStreaming;
+
+/// Asset source of files dropped onto the window, rooted at the file system root
+pub const DROPPED_FILE_SOURCE: &str = "dropped";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    /// glTF scene spawned at the origin
+    Scene,
+    /// Equirectangular HDR map lighting the window cameras
+    Environment,
+}
+
+/// Entity spawned for a dropped file, or the camera it was applied to
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct ImportedFile(pub PathBuf);
+
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct FileImported {
+    pub path: PathBuf,
+    pub kind: ImportKind,
+    pub entity: Entity,
+}
+
+/// Text clipboard of the platform
+pub trait ClipboardProvider: Send + Sync {
+    fn get_text(&self) -> Option<String>;
+    fn set_text(&self, text: &str);
+}
+
+/// Clipboard shared within the application, for platforms without a provider
+#[derive(Debug, Default)]
+pub struct LocalClipboard(Mutex<Option<String>>);
+
+#[derive(Resource, Clone)]
+pub struct Clipboard {
+    provider: Arc<dyn ClipboardProvider>,
+}
+
+/// Copies and pastes transforms as text through the [`Clipboard`]
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardCommand {
+    CopyTransform(Entity),
+    PasteTransform(Entity),
+}
+
+/// Imports files dropped onto the desktop window and adds a clipboard for transforms
+///
+/// Must be added before the asset plugin, as it registers the
+/// [`DROPPED_FILE_SOURCE`] asset source. Dropped files are loaded from their
+/// place on disk, so `.gltf` files find their buffers and textures. On
+/// Windows the source is rooted at the current drive.
+#[derive(Default)]
+pub struct WindowImportPlugin {
+    /// [`LocalClipboard`] is used if `None`
+    pub clipboard: Option<Arc<dyn ClipboardProvider>>,
+}
+
+impl ImportKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gltf" | "glb" => Some(Self::Scene),
+            "hdr" => Some(Self::Environment),
+            _ => None,
+        }
+    }
+}
+
+impl ClipboardProvider for LocalClipboard {
+    fn get_text(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set_text(&self, text: &str) {
+        *self.0.lock().unwrap() = Some(text.to_owned());
+    }
+}
+
+impl Clipboard {
+    pub fn new(provider: Arc<dyn ClipboardProvider>) -> Self {
+        Self { provider }
+    }
+
+    pub fn get_text(&self) -> Option<String> {
+        self.provider.get_text()
+    }
+
+    pub fn set_text(&self, text: &str) {
+        self.provider.set_text(text);
+    }
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Self::new(Arc::new(LocalClipboard::default()))
+    }
+}
+
+impl Plugin for WindowImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_source(
+            DROPPED_FILE_SOURCE,
+            AssetSourceBuilder::default().with_reader(|| Box::new(FileAssetReader::new("/"))),
+        )
+        .insert_resource(match &self.clipboard {
+            Some(provider) => Clipboard::new(provider.clone()),
+            None => Clipboard::default(),
+        })
+        .add_message::<FileImported>()
+        .add_message::<ClipboardCommand>()
+        .add_systems(
+            Update,
+            (
+                import_dropped_files.run_if(on_message::<FileDragAndDrop>),
+                apply_clipboard_commands,
+            ),
+        );
+    }
+}
+
+/// Path of a file in [`DROPPED_FILE_SOURCE`]
+pub fn dropped_asset_path(path: &Path) -> AssetPath<'static> {
+    let relative = path
+        .components()
+        .filter(|component| !matches!(component, Component::Prefix(_) | Component::RootDir))
+        .collect::<PathBuf>();
+    AssetPath::from_path_buf(relative)
+        .with_source(AssetSourceId::from(DROPPED_FILE_SOURCE))
+        .into_owned()
+}
+
+/// Transform as text, e.g. `{"translation":[0.0,1.0,0.0],"rotation":[0.0,0.0,0.0,1.0],"scale":[1.0,1.0,1.0]}`
+pub fn transform_to_text(transform: &Transform) -> String {
+    serde_json::to_string(transform).unwrap_or_default()
+}
+
+pub fn transform_from_text(text: &str) -> Option<Transform> {
+    serde_json::from_str(text.trim()).ok()
+}
+
+fn import_dropped_files(
+    mut commands: Commands,
+    mut drops: MessageReader<FileDragAndDrop>,
+    mut imported: MessageWriter<FileImported>,
+    asset_server: Res<AssetServer>,
+    mut streaming: Option<ResMut<AssetStreaming>>,
+    cameras: Query<(Entity, &Camera, Option<&EnvironmentLighting>), With<Camera3d>>,
+) {
+    for drop in drops.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+        let Some(kind) = ImportKind::from_path(path_buf) else {
+            warn!("Cannot import {}", path_buf.display());
+            continue;
+        };
+        let asset_path = dropped_asset_path(path_buf);
+        info!("Importing {}", path_buf.display());
+        match kind {
+            ImportKind::Scene => {
+                let scene = asset_server.load(GltfAssetLabel::Scene(0).from_asset(asset_path));
+                if let Some(streaming) = streaming.as_mut() {
+                    streaming.track(scene.clone());
+                }
+                let name = path_buf.file_name().unwrap_or_default().to_string_lossy();
+                let entity = commands
+                    .spawn((
+                        SceneRoot(scene),
+                        Name::new(name.into_owned()),
+                        ImportedFile(path_buf.clone()),
+                    ))
+                    .id();
+                imported.write(FileImported {
+                    path: path_buf.clone(),
+                    kind,
+                    entity,
+                });
+            }
+            ImportKind::Environment => {
+                let image: Handle<Image> = asset_server.load(asset_path);
+                if let Some(streaming) = streaming.as_mut() {
+                    streaming.track(image.clone());
+                }
+                // Cameras rendering to textures, e.g. for picking, keep their setup
+                for (entity, camera, lighting) in cameras.iter() {
+                    if !matches!(camera.target, RenderTarget::Window(_)) {
+                        continue;
+                    }
+                    let lighting = match lighting {
+                        Some(lighting) => EnvironmentLighting {
+                            source: image.clone(),
+                            ..lighting.clone()
+                        },
+                        None => EnvironmentLighting::new(image.clone()),
+                    };
+                    commands
+                        .entity(entity)
+                        .insert((lighting, ImportedFile(path_buf.clone())));
+                    imported.write(FileImported {
+                        path: path_buf.clone(),
+                        kind,
+                        entity,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn apply_clipboard_commands(
+    mut clipboard_commands: MessageReader<ClipboardCommand>,
+    clipboard: Res<Clipboard>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for command in clipboard_commands.read() {
+        match *command {
+            ClipboardCommand::CopyTransform(entity) => {
+                if let Ok(transform) = transforms.get(entity) {
+                    clipboard.set_text(&transform_to_text(transform));
+                }
+            }
+            ClipboardCommand::PasteTransform(entity) => {
+                let Some(text) = clipboard.get_text() else {
+                    continue;
+                };
+                let Some(pasted) = transform_from_text(&text) else {
+                    warn!("Clipboard does not hold a transform");
+                    continue;
+                };
+                if let Ok(mut transform) = transforms.get_mut(entity) {
+                    *transform = pasted;
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,64 @@
+mod android;
+mod annotation;
+mod calibration;
+mod camera_controller;
+mod comfort;
+mod content;
+mod data_binding;
+#[cfg(feature = "egui")]
+mod egui_integration;
 mod error;
+mod event;
+mod headless;
+mod import;
+mod physics;
+mod playback;
+mod plugin;
+mod power;
+mod quality;
+mod remote;
+#[cfg(feature = "ros2")]
+mod ros2;
 mod runtime;
+mod scene_layers;
+mod settings;
+mod stable_id;
+mod streaming;
+mod tour;
+mod viewpoint;
+mod watchdog;
+mod world_file;
 
+pub use android::*;
+pub use annotation::*;
+pub use calibration::*;
+pub use camera_controller::*;
+pub use comfort::*;
+pub use content::*;
+pub use data_binding::*;
+#[cfg(feature = "egui")]
+pub use egui_integration::*;
 pub use error::*;
+pub use event::*;
+pub use headless::*;
+pub use import::*;
+pub use physics::*;
+pub use playback::*;
+pub use plugin::*;
+pub use power::*;
+pub use quality::*;
+pub use remote::*;
+#[cfg(feature = "ros2")]
+pub use ros2::*;
 pub use runtime::*;
+pub use scene_layers::*;
+pub use settings::*;
+pub use stable_id::*;
+pub use streaming::*;
+pub use tour::*;
+pub use viewpoint::*;
+pub use watchdog::*;
+pub use world_file::*;
+
+#[cfg(test)]
+mod tests;
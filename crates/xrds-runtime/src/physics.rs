@@ -0,0 +1,405 @@
+use bevy::{
+    camera::{primitives::Aabb, visibility::VisibilitySystems},
+    prelude::*,
+    transform::TransformSystems,
+};
+
+/// Body simulated by the [`PhysicsPlugin`], collides through its [`Collider`]
+///
+/// Bodies are stepped in `FixedUpdate` and move their `Transform`, so bodies
+/// that collide should share a parent, usually none. Add
+/// `TransformInterpolation` to smooth them at frame rates above the fixed
+/// timestep. Entities with a collider and no body are static.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[require(Transform, Velocity, Mass, PhysicsForces)]
+pub enum RigidBody {
+    /// Moved by gravity, forces and collisions
+    #[default]
+    Dynamic,
+    /// Moved by its velocity only, pushes dynamic bodies
+    Kinematic,
+    Static,
+}
+
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Velocity {
+    pub linear: Vec3,
+    /// Axis scaled by radians per second
+    pub angular: Vec3,
+}
+
+/// Mass of a [`RigidBody`] in kilograms
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Mass(pub f32);
+
+/// Forces applied until the next step, see [`ApplyForce`]
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhysicsForces {
+    pub force: Vec3,
+    pub torque: Vec3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColliderShape {
+    Cuboid { half_extents: Vec3 },
+    Sphere { radius: f32 },
+}
+
+/// Shape that bodies collide with, scaled by the `Transform`
+///
+/// Cuboids collide by their bounding box in the space of the parent, so
+/// rotated cuboids are larger than they look.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[require(Transform)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    /// Center of the shape in the space of the entity
+    pub offset: Vec3,
+    /// From 0 for no bounce to 1 for elastic collisions
+    pub restitution: f32,
+    /// From 0 for no friction to 1 to stop sliding at once
+    pub friction: f32,
+}
+
+/// Derives a cuboid [`Collider`] from the bounding boxes of the meshes of the
+/// entity and its descendants, e.g. a glTF scene, once they are loaded
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct ColliderFromMeshes;
+
+/// Applies forces and impulses to [`RigidBody`]s
+pub trait ApplyForce {
+    /// Force in newtons, applied over the next step
+    fn apply_force(&mut self, entity: Entity, force: Vec3);
+    /// Torque in newton meters, applied over the next step
+    fn apply_torque(&mut self, entity: Entity, torque: Vec3);
+    /// Impulse in newton seconds, changes the velocity at once
+    fn apply_impulse(&mut self, entity: Entity, impulse: Vec3);
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsSettings {
+    pub gravity: Vec3,
+}
+
+/// Simple rigid body physics of spheres and boxes, stepped at the timestep of
+/// `FixedUpdate`
+///
+/// Bodies do not rotate from collisions, and rotation from torque treats the
+/// mass as if it were at a distance of 1 m from the center.
+#[derive(Debug, Default)]
+pub struct PhysicsPlugin;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicsSystems;
+
+/// Collider of an entity in the space of its parent
+struct PlacedCollider {
+    entity: Entity,
+    shape: ColliderShape,
+    center: Vec3,
+    restitution: f32,
+    friction: f32,
+    inverse_mass: f32,
+}
+
+impl Default for Mass {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl Collider {
+    pub fn cuboid(half_extents: Vec3) -> Self {
+        Self::new(ColliderShape::Cuboid { half_extents })
+    }
+
+    pub fn sphere(radius: f32) -> Self {
+        Self::new(ColliderShape::Sphere { radius })
+    }
+
+    /// Cuboid filling a mesh bounding box
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::cuboid(aabb.half_extents.into()).with_offset(aabb.center.into())
+    }
+
+    fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            offset: Vec3::ZERO,
+            restitution: 0.0,
+            friction: 0.5,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            gravity: Vec3::new(0.0, -9.81, 0.0),
+        }
+    }
+}
+
+impl ApplyForce for World {
+    fn apply_force(&mut self, entity: Entity, force: Vec3) {
+        if let Some(mut forces) = self.get_mut::<PhysicsForces>(entity) {
+            forces.force += force;
+        }
+    }
+
+    fn apply_torque(&mut self, entity: Entity, torque: Vec3) {
+        if let Some(mut forces) = self.get_mut::<PhysicsForces>(entity) {
+            forces.torque += torque;
+        }
+    }
+
+    fn apply_impulse(&mut self, entity: Entity, impulse: Vec3) {
+        let Ok(mut entity) = self.get_entity_mut(entity) else {
+            return;
+        };
+        let (Some(body), Some(mass)) = (entity.get::<RigidBody>(), entity.get::<Mass>()) else {
+            return;
+        };
+        if *body == RigidBody::Dynamic && mass.0 > 0.0 {
+            let change = impulse / mass.0;
+            if let Some(mut velocity) = entity.get_mut::<Velocity>() {
+                velocity.linear += change;
+            }
+        }
+    }
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsSettings>()
+            .add_systems(
+                FixedUpdate,
+                (integrate_bodies, resolve_collisions)
+                    .chain()
+                    .in_set(PhysicsSystems),
+            )
+            .add_systems(
+                PostUpdate,
+                derive_mesh_colliders
+                    .after(TransformSystems::Propagate)
+                    .after(VisibilitySystems::CalculateBounds),
+            );
+    }
+}
+
+fn integrate_bodies(
+    time: Res<Time>,
+    settings: Res<PhysicsSettings>,
+    mut bodies: Query<(
+        &RigidBody,
+        &Mass,
+        &mut Velocity,
+        &mut PhysicsForces,
+        &mut Transform,
+    )>,
+) {
+    let dt = time.delta_secs();
+    for (body, mass, mut velocity, mut forces, mut transform) in bodies.iter_mut() {
+        let forces = std::mem::take(forces.as_mut());
+        match body {
+            RigidBody::Static => continue,
+            RigidBody::Kinematic => {}
+            RigidBody::Dynamic if mass.0 > 0.0 => {
+                velocity.linear += (settings.gravity + forces.force / mass.0) * dt;
+                velocity.angular += forces.torque / mass.0 * dt;
+            }
+            RigidBody::Dynamic => {}
+        }
+        transform.translation += velocity.linear * dt;
+        transform.rotation =
+            (Quat::from_scaled_axis(velocity.angular * dt) * transform.rotation).normalize();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn resolve_collisions(
+    mut colliders: Query<(
+        Entity,
+        &Collider,
+        &mut Transform,
+        Option<&RigidBody>,
+        Option<&Mass>,
+        Option<&mut Velocity>,
+    )>,
+) {
+    let placed: Vec<_> = colliders
+        .iter()
+        .map(|(entity, collider, transform, body, mass, _)| {
+            let inverse_mass = match (body, mass) {
+                (Some(RigidBody::Dynamic), Some(mass)) if mass.0 > 0.0 => mass.0.recip(),
+                _ => 0.0,
+            };
+            PlacedCollider::new(entity, collider, transform, inverse_mass)
+        })
+        .collect();
+
+    for (i, a) in placed.iter().enumerate() {
+        for b in &placed[i + 1..] {
+            let inverse_mass = a.inverse_mass + b.inverse_mass;
+            if inverse_mass <= 0.0 {
+                continue;
+            }
+            let Some((normal, depth)) = a.contact(b) else {
+                continue;
+            };
+            let velocity = |entity| {
+                let velocity = colliders.get(entity).ok().and_then(|(.., v)| v);
+                velocity.map_or(Vec3::ZERO, |velocity| velocity.linear)
+            };
+            let relative = velocity(b.entity) - velocity(a.entity);
+            let speed = relative.dot(normal);
+            let impulse = if speed < 0.0 {
+                let restitution = a.restitution.max(b.restitution);
+                let friction = (a.friction * b.friction).sqrt();
+                let across = relative - normal * speed;
+                (-(1.0 + restitution) * speed * normal - friction * across) / inverse_mass
+            } else {
+                Vec3::ZERO
+            };
+
+            // Push apart by the share of the mass
+            for (collider, sign) in [(a, -1.0), (b, 1.0)] {
+                if collider.inverse_mass <= 0.0 {
+                    continue;
+                }
+                let Ok((_, _, mut transform, _, _, Some(mut velocity))) =
+                    colliders.get_mut(collider.entity)
+                else {
+                    continue;
+                };
+                let share = collider.inverse_mass / inverse_mass;
+                transform.translation += sign * normal * depth * share;
+                velocity.linear += sign * impulse * collider.inverse_mass;
+            }
+        }
+    }
+}
+
+fn derive_mesh_colliders(
+    mut commands: Commands,
+    roots: Query<(Entity, &GlobalTransform), With<ColliderFromMeshes>>,
+    children: Query<&Children>,
+    meshes: Query<(&Aabb, &GlobalTransform)>,
+) {
+    for (root, root_transform) in roots.iter() {
+        let local_from_world = root_transform.affine().inverse();
+        let mut min = Vec3::INFINITY;
+        let mut max = Vec3::NEG_INFINITY;
+        for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+            let Ok((aabb, transform)) = meshes.get(entity) else {
+                continue;
+            };
+            let local_from_mesh = local_from_world * transform.affine();
+            for corner in 0..8 {
+                let sign = Vec3::new(
+                    if corner & 1 == 0 { -1.0 } else { 1.0 },
+                    if corner & 2 == 0 { -1.0 } else { 1.0 },
+                    if corner & 4 == 0 { -1.0 } else { 1.0 },
+                );
+                let point = Vec3::from(aabb.center) + sign * Vec3::from(aabb.half_extents);
+                let point = local_from_mesh.transform_point3(point);
+                min = min.min(point);
+                max = max.max(point);
+            }
+        }
+        // Wait for the meshes of the scene
+        if min.x > max.x {
+            continue;
+        }
+        commands
+            .entity(root)
+            .insert(Collider::from_aabb(&Aabb::from_min_max(min, max)))
+            .remove::<ColliderFromMeshes>();
+    }
+}
+
+impl PlacedCollider {
+    fn new(entity: Entity, collider: &Collider, transform: &Transform, inverse_mass: f32) -> Self {
+        let shape = match collider.shape {
+            ColliderShape::Cuboid { half_extents } => {
+                let axes =
+                    Mat3::from_quat(transform.rotation) * Mat3::from_diagonal(transform.scale);
+                let half_extents = axes.abs() * half_extents;
+                ColliderShape::Cuboid { half_extents }
+            }
+            ColliderShape::Sphere { radius } => ColliderShape::Sphere {
+                radius: radius * transform.scale.abs().max_element(),
+            },
+        };
+        Self {
+            entity,
+            shape,
+            center: transform.transform_point(collider.offset),
+            restitution: collider.restitution,
+            friction: collider.friction,
+            inverse_mass,
+        }
+    }
+
+    /// Normal from `self` towards `other` and the depth they overlap
+    fn contact(&self, other: &PlacedCollider) -> Option<(Vec3, f32)> {
+        use ColliderShape::*;
+
+        let offset = other.center - self.center;
+        match (self.shape, other.shape) {
+            (Sphere { radius: a }, Sphere { radius: b }) => {
+                let distance = offset.length();
+                let depth = a + b - distance;
+                (depth > 0.0).then(|| (offset.try_normalize().unwrap_or(Vec3::Y), depth))
+            }
+            (Cuboid { half_extents: a }, Cuboid { half_extents: b }) => {
+                let overlap = a + b - offset.abs();
+                if overlap.min_element() <= 0.0 {
+                    return None;
+                }
+                let axis = overlap.min_position();
+                let mut normal = Vec3::ZERO;
+                normal[axis] = if offset[axis] < 0.0 { -1.0 } else { 1.0 };
+                Some((normal, overlap[axis]))
+            }
+            (Cuboid { half_extents }, Sphere { radius }) => {
+                box_sphere_contact(half_extents, offset, radius)
+            }
+            (Sphere { radius }, Cuboid { half_extents }) => {
+                box_sphere_contact(half_extents, -offset, radius)
+                    .map(|(normal, depth)| (-normal, depth))
+            }
+        }
+    }
+}
+
+/// Contact of a box at the origin with a sphere at `center`
+fn box_sphere_contact(half_extents: Vec3, center: Vec3, radius: f32) -> Option<(Vec3, f32)> {
+    let closest = center.clamp(-half_extents, half_extents);
+    if closest != center {
+        let offset = center - closest;
+        let distance = offset.length();
+        return (distance < radius).then(|| (offset / distance, radius - distance));
+    }
+    // The center is inside, leave through the nearest face
+    let overlap = half_extents - center.abs();
+    let axis = overlap.min_position();
+    let mut normal = Vec3::ZERO;
+    normal[axis] = if center[axis] < 0.0 { -1.0 } else { 1.0 };
+    Some((normal, overlap[axis] + radius))
+}
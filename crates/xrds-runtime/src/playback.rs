@@ -0,0 +1,531 @@
+use std::{error::Error, fmt, path::Path, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{DataSourceError, DataTable};
+
+/// Columns and fields holding the sample time in seconds
+const TIME_KEYS: [&str; 3] = ["time", "t", "timestamp"];
+/// Columns and fields naming the recorded track, e.g. a tracked device or topic
+const TRACK_KEYS: [&str; 2] = ["track", "topic"];
+/// Columns of the position and rotation of CSV recordings
+const POSE_COLUMNS: [&str; 7] = ["x", "y", "z", "qx", "qy", "qz", "qw"];
+
+/// Poses and sensor values recorded over time
+///
+/// CSV recordings have a `time` column in seconds, an optional `track`
+/// column, the position in `x`, `y`, `z` and the rotation in `qx`, `qy`,
+/// `qz`, `qw`. JSONL recordings have one object per line with `time`, an
+/// optional `track` or `topic`, and `position` and `rotation` arrays. Other
+/// numeric columns and fields are recorded as sensor channels named
+/// `<track>/<name>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub tracks: Vec<PoseTrack>,
+    pub channels: Vec<SensorChannel>,
+}
+
+/// Poses of one tracked object, sorted by time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoseTrack {
+    pub name: String,
+    pub samples: Vec<PoseSample>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoseSample {
+    /// Seconds from the start of the recording
+    pub time: f32,
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+/// Values of one sensor, sorted by time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SensorChannel {
+    pub name: String,
+    /// Time in seconds and value
+    pub samples: Vec<(f32, f32)>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    Csv(DataSourceError),
+    Json {
+        line: usize,
+        error: serde_json::Error,
+    },
+    /// A sample without a time
+    MissingTime {
+        line: usize,
+    },
+}
+
+/// Plays a [`Recording`] back on a timeline
+///
+/// Entities with a [`PlaybackTarget`] follow a track of the recording and
+/// entities with [`PlaybackFrames`] show the video frame of the current time,
+/// so video and telemetry stay in sync while scrubbing.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct RecordingPlayback {
+    pub recording: Recording,
+    /// Current position on the timeline
+    pub time: Duration,
+    pub speed: f32,
+    pub playing: bool,
+    /// Start over at the end instead of pausing
+    pub looping: bool,
+}
+
+/// Moves the entity along a track of a [`RecordingPlayback`]
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PlaybackTarget {
+    pub player: Entity,
+    pub track: String,
+}
+
+/// Image sequence shown as the base color texture of the entity's material,
+/// in sync with a [`RecordingPlayback`]
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PlaybackFrames {
+    pub player: Entity,
+    /// Frames with their time in seconds, sorted by time
+    pub frames: Vec<(f32, Handle<Image>)>,
+    /// Time of the recording at which the first frame was taken
+    pub offset: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct PlaybackPlugin;
+
+impl Recording {
+    /// CSV or JSONL file, depending on the extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "json" | "ndjson") => Self::from_jsonl(&text),
+            _ => Self::from_csv(&text),
+        }
+    }
+
+    pub fn from_csv(text: &str) -> Result<Self, RecordingError> {
+        let table = DataTable::from_csv(text).map_err(RecordingError::Csv)?;
+        let time_column = TIME_KEYS
+            .into_iter()
+            .find(|key| table.column(key).is_some());
+        let track_column = TRACK_KEYS
+            .into_iter()
+            .find(|key| table.column(key).is_some());
+        let has_pose = ["x", "y", "z"]
+            .into_iter()
+            .all(|column| table.column(column).is_some());
+
+        let mut recording = Self::default();
+        for row in 0..table.rows.len() {
+            let Some(time) = time_column.and_then(|column| table.number(row, column)) else {
+                return Err(RecordingError::MissingTime { line: row + 2 });
+            };
+            let track = track_column
+                .and_then(|column| table.get(row, column))
+                .unwrap_or("pose");
+            if has_pose {
+                let value = |column| table.number(row, column).unwrap_or_default();
+                let rotation = match table.number(row, "qw") {
+                    Some(w) => Quat::from_xyzw(value("qx"), value("qy"), value("qz"), w),
+                    None => Quat::IDENTITY,
+                };
+                recording.push_pose(
+                    track,
+                    PoseSample {
+                        time,
+                        translation: Vec3::new(value("x"), value("y"), value("z")),
+                        rotation: rotation.normalize(),
+                    },
+                );
+            }
+            for column in &table.columns {
+                let key = column.as_str();
+                if TIME_KEYS.contains(&key)
+                    || TRACK_KEYS.contains(&key)
+                    || (has_pose && POSE_COLUMNS.contains(&key))
+                {
+                    continue;
+                }
+                if let Some(value) = table.number(row, key) {
+                    recording.push_value(&format!("{track}/{key}"), time, value);
+                }
+            }
+        }
+        recording.sort();
+        Ok(recording)
+    }
+
+    pub fn from_jsonl(text: &str) -> Result<Self, RecordingError> {
+        let mut recording = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(line)
+                .map_err(|error| RecordingError::Json {
+                    line: index + 1,
+                    error,
+                })?;
+            let Some(time) = TIME_KEYS
+                .into_iter()
+                .find_map(|key| object.get(key)?.as_f64())
+            else {
+                return Err(RecordingError::MissingTime { line: index + 1 });
+            };
+            let time = time as f32;
+            let track = TRACK_KEYS
+                .into_iter()
+                .find_map(|key| object.get(key)?.as_str())
+                .unwrap_or("pose");
+
+            let floats = |key: &str| {
+                object.get(key)?.as_array().map(|values| {
+                    values
+                        .iter()
+                        .map(|value| value.as_f64().unwrap_or_default() as f32)
+                        .collect::<Vec<_>>()
+                })
+            };
+            if let Some([x, y, z]) = floats("position").as_deref() {
+                let rotation = match floats("rotation").as_deref() {
+                    Some(&[x, y, z, w]) => Quat::from_xyzw(x, y, z, w).normalize(),
+                    _ => Quat::IDENTITY,
+                };
+                recording.push_pose(
+                    track,
+                    PoseSample {
+                        time,
+                        translation: Vec3::new(*x, *y, *z),
+                        rotation,
+                    },
+                );
+            }
+            for (key, value) in &object {
+                if TIME_KEYS.contains(&key.as_str()) || TRACK_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                if let Some(value) = value.as_f64() {
+                    recording.push_value(&format!("{track}/{key}"), time, value as f32);
+                }
+            }
+        }
+        recording.sort();
+        Ok(recording)
+    }
+
+    /// Time of the last sample
+    pub fn duration(&self) -> Duration {
+        let tracks = self
+            .tracks
+            .iter()
+            .filter_map(|track| track.samples.last().map(|sample| sample.time));
+        let channels = self
+            .channels
+            .iter()
+            .filter_map(|channel| channel.samples.last().map(|(time, _)| *time));
+        Duration::from_secs_f32(tracks.chain(channels).fold(0.0, f32::max).max(0.0))
+    }
+
+    pub fn track(&self, name: &str) -> Option<&PoseTrack> {
+        self.tracks.iter().find(|track| track.name == name)
+    }
+
+    pub fn channel(&self, name: &str) -> Option<&SensorChannel> {
+        self.channels.iter().find(|channel| channel.name == name)
+    }
+
+    fn push_pose(&mut self, track: &str, sample: PoseSample) {
+        match self.tracks.iter_mut().find(|pose| pose.name == track) {
+            Some(pose) => pose.samples.push(sample),
+            None => self.tracks.push(PoseTrack {
+                name: track.to_owned(),
+                samples: vec![sample],
+            }),
+        }
+    }
+
+    fn push_value(&mut self, channel: &str, time: f32, value: f32) {
+        match self
+            .channels
+            .iter_mut()
+            .find(|sensor| sensor.name == channel)
+        {
+            Some(sensor) => sensor.samples.push((time, value)),
+            None => self.channels.push(SensorChannel {
+                name: channel.to_owned(),
+                samples: vec![(time, value)],
+            }),
+        }
+    }
+
+    fn sort(&mut self) {
+        for track in &mut self.tracks {
+            track.samples.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+        for channel in &mut self.channels {
+            channel.samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+    }
+}
+
+/// Index of the last sample at or before `time` and the factor towards the next one
+fn interpolation(times: impl ExactSizeIterator<Item = f32> + Clone, time: f32) -> (usize, f32) {
+    let count = times.len();
+    let next = times
+        .clone()
+        .position(|sample| sample > time)
+        .unwrap_or(count);
+    if next == 0 || next == count {
+        return (next.saturating_sub(1), 0.0);
+    }
+    let mut times = times.skip(next - 1);
+    let (start, end) = (times.next().unwrap(), times.next().unwrap());
+    (next - 1, (time - start) / (end - start))
+}
+
+impl PoseTrack {
+    /// Pose at `time`, interpolated between the samples around it
+    pub fn sample(&self, time: f32) -> Option<Transform> {
+        let (index, factor) = interpolation(self.samples.iter().map(|sample| sample.time), time);
+        let start = self.samples.get(index)?;
+        let end = self.samples.get(index + 1).unwrap_or(start);
+        Some(
+            Transform::from_translation(start.translation.lerp(end.translation, factor))
+                .with_rotation(start.rotation.slerp(end.rotation, factor)),
+        )
+    }
+}
+
+impl SensorChannel {
+    /// Value at `time`, interpolated between the samples around it
+    pub fn value_at(&self, time: f32) -> Option<f32> {
+        let (index, factor) = interpolation(self.samples.iter().map(|(time, _)| *time), time);
+        let (_, start) = *self.samples.get(index)?;
+        let (_, end) = self.samples.get(index + 1).copied().unwrap_or((0.0, start));
+        Some(start + (end - start) * factor)
+    }
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read recording: {error}"),
+            Self::Csv(error) => write!(f, "Invalid recording: {error}"),
+            Self::Json { line, error } => write!(f, "Invalid recording on line {line}: {error}"),
+            Self::MissingTime { line } => write!(f, "Sample without a time on line {line}"),
+        }
+    }
+}
+
+impl Error for RecordingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Csv(error) => Some(error),
+            Self::Json { error, .. } => Some(error),
+            Self::MissingTime { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl RecordingPlayback {
+    /// Paused at the start of the recording
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            time: Duration::ZERO,
+            speed: 1.0,
+            playing: false,
+            looping: false,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn play(&mut self) {
+        if self.time >= self.recording.duration() {
+            self.time = Duration::ZERO;
+        }
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Jump to `time`, clamped to the recording
+    pub fn seek(&mut self, time: Duration) {
+        self.time = time.min(self.recording.duration());
+    }
+
+    /// Jump to a fraction of the recording, e.g. from a timeline slider
+    pub fn scrub(&mut self, fraction: f32) {
+        self.seek(self.recording.duration().mul_f32(fraction.clamp(0.0, 1.0)));
+    }
+
+    /// Current position on the timeline from 0 to 1
+    pub fn progress(&self) -> f32 {
+        let duration = self.recording.duration();
+        if duration.is_zero() {
+            return 0.0;
+        }
+        self.time.as_secs_f32() / duration.as_secs_f32()
+    }
+
+    /// Value of a sensor channel at the current time
+    pub fn value(&self, channel: &str) -> Option<f32> {
+        self.recording
+            .channel(channel)?
+            .value_at(self.time.as_secs_f32())
+    }
+
+    /// Pose of a track at the current time
+    pub fn pose(&self, track: &str) -> Option<Transform> {
+        self.recording.track(track)?.sample(self.time.as_secs_f32())
+    }
+}
+
+impl PlaybackTarget {
+    pub fn new(player: Entity, track: impl Into<String>) -> Self {
+        Self {
+            player,
+            track: track.into(),
+        }
+    }
+}
+
+impl PlaybackFrames {
+    /// Frames at a fixed rate starting at the beginning of the recording
+    pub fn from_rate(
+        player: Entity,
+        frames: impl IntoIterator<Item = Handle<Image>>,
+        fps: f32,
+    ) -> Self {
+        Self {
+            player,
+            frames: frames
+                .into_iter()
+                .enumerate()
+                .map(|(index, frame)| (index as f32 / fps.max(f32::EPSILON), frame))
+                .collect(),
+            offset: 0.0,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Last frame taken at or before `time` of the recording
+    pub fn frame_at(&self, time: f32) -> Option<&Handle<Image>> {
+        let time = time - self.offset;
+        let next = self.frames.iter().position(|(frame, _)| *frame > time);
+        match next {
+            Some(0) => self.frames.first(),
+            Some(next) => self.frames.get(next - 1),
+            None => self.frames.last(),
+        }
+        .map(|(_, frame)| frame)
+    }
+}
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                advance_playbacks,
+                (
+                    move_playback_targets,
+                    show_playback_frames.run_if(resource_exists::<Assets<StandardMaterial>>),
+                ),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn advance_playbacks(time: Res<Time>, mut players: Query<&mut RecordingPlayback>) {
+    for mut player in players.iter_mut() {
+        if !player.playing {
+            continue;
+        }
+        let duration = player.recording.duration();
+        let mut position = player.time.as_secs_f32() + time.delta_secs() * player.speed;
+        if position >= duration.as_secs_f32() {
+            if player.looping && !duration.is_zero() {
+                position %= duration.as_secs_f32();
+            } else {
+                position = duration.as_secs_f32();
+                player.playing = false;
+            }
+        }
+        player.time = Duration::from_secs_f32(position.max(0.0));
+    }
+}
+
+fn move_playback_targets(
+    players: Query<Ref<RecordingPlayback>>,
+    mut targets: Query<(&PlaybackTarget, &mut Transform)>,
+) {
+    for (target, mut transform) in targets.iter_mut() {
+        let Ok(player) = players.get(target.player) else {
+            continue;
+        };
+        if !player.is_changed() {
+            continue;
+        }
+        if let Some(pose) = player.pose(&target.track) {
+            transform.translation = pose.translation;
+            transform.rotation = pose.rotation;
+        }
+    }
+}
+
+fn show_playback_frames(
+    players: Query<Ref<RecordingPlayback>>,
+    frames: Query<(&PlaybackFrames, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (frames, material) in frames.iter() {
+        let Ok(player) = players.get(frames.player) else {
+            continue;
+        };
+        if !player.is_changed() {
+            continue;
+        }
+        let Some(frame) = frames.frame_at(player.time.as_secs_f32()) else {
+            continue;
+        };
+        let current = materials
+            .get(&material.0)
+            .and_then(|material| material.base_color_texture.as_ref());
+        if current != Some(frame) {
+            if let Some(material) = materials.get_mut(&material.0) {
+                material.base_color_texture = Some(frame.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::{
+    app::Plugins,
+    asset::AssetLoader,
+    core_pipeline::core_3d::graph::Core3d,
+    ecs::{schedule::ScheduleLabel, system::ScheduleSystem},
+    prelude::*,
+    render::{
+        render_graph::{RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner},
+        RenderApp,
+    },
+};
+
+/// Extension of the runtime, registered with [`RuntimeBuilder::with_plugin`]
+///
+/// Third-party crates implement this to add components, systems, render
+/// passes and asset loaders without changing the runtime.
+pub trait XrdsPlugin: Send + Sync + 'static {
+    /// Plugins with the same name are only registered once
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn build(&self, context: &mut PluginContext);
+}
+
+/// Registration interface handed to [`XrdsPlugin::build`]
+pub struct PluginContext<'a> {
+    app: &'a mut App,
+}
+
+/// Runs an [`XrdsPlugin`] as a bevy plugin
+pub(crate) struct XrdsPluginAdapter(pub(crate) Box<dyn XrdsPlugin>);
+
+impl<'a> PluginContext<'a> {
+    pub fn register_component<C: Component>(&mut self) -> &mut Self {
+        self.app.world_mut().register_component::<C>();
+        self
+    }
+
+    pub fn insert_resource<R: Resource>(&mut self, resource: R) -> &mut Self {
+        self.app.insert_resource(resource);
+        self
+    }
+
+    pub fn init_resource<R: Resource + FromWorld>(&mut self) -> &mut Self {
+        self.app.init_resource::<R>();
+        self
+    }
+
+    pub fn add_message<M: Message>(&mut self) -> &mut Self {
+        self.app.add_message::<M>();
+        self
+    }
+
+    pub fn add_systems<M>(
+        &mut self,
+        schedule: impl ScheduleLabel,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self {
+        self.app.add_systems(schedule, systems);
+        self
+    }
+
+    /// Add a 3D render pass running between `after` and `before`,
+    /// e.g. `Node3d::Tonemapping` and `Node3d::EndMainPassPostProcessing`
+    pub fn add_render_pass<N: ViewNode + FromWorld + Send + Sync + 'static>(
+        &mut self,
+        label: impl RenderLabel + Clone,
+        after: impl RenderLabel,
+        before: impl RenderLabel,
+    ) -> &mut Self {
+        let Some(render_app) = self.app.get_sub_app_mut(RenderApp) else {
+            warn!("Render pass {:?} ignored, rendering is disabled", label);
+            return self;
+        };
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<N>>(Core3d, label.clone())
+            .add_render_graph_edges(Core3d, (after, label, before));
+        self
+    }
+
+    /// Add systems to the render world
+    pub fn add_render_systems<M>(
+        &mut self,
+        schedule: impl ScheduleLabel,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self {
+        if let Some(render_app) = self.app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(schedule, systems);
+        }
+        self
+    }
+
+    pub fn init_asset<A: Asset>(&mut self) -> &mut Self {
+        self.app.init_asset::<A>();
+        self
+    }
+
+    pub fn register_asset_loader<L: AssetLoader>(&mut self, loader: L) -> &mut Self {
+        self.app.register_asset_loader(loader);
+        self
+    }
+
+    /// Add bevy plugins, e.g. when wrapping an existing bevy crate
+    pub fn add_plugins<M>(&mut self, plugins: impl Plugins<M>) -> &mut Self {
+        self.app.add_plugins(plugins);
+        self
+    }
+
+    /// Direct access to the app for anything not covered above
+    pub fn app(&mut self) -> &mut App {
+        self.app
+    }
+}
+
+impl Plugin for XrdsPluginAdapter {
+    fn build(&self, app: &mut App) {
+        info!("Registering plugin {}", self.0.name());
+        self.0.build(&mut PluginContext { app });
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer};
+
+use crate::QualityLadder;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThermalState {
+    #[default]
+    Nominal,
+    Fair,
+    Serious,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryStatus {
+    /// Remaining charge in 0.0..=1.0
+    pub level: f32,
+    pub charging: bool,
+}
+
+/// Platform source of battery and thermal information
+pub trait PowerStatusProvider: Send + Sync {
+    fn battery(&self) -> Option<BatteryStatus>;
+    /// Android style headroom. 1.0 means the device starts throttling
+    fn thermal_headroom(&self) -> Option<f32>;
+}
+
+/// Reads `/sys/class/power_supply` and `/sys/class/thermal` (desktop Linux)
+#[derive(Debug, Clone)]
+pub struct SysfsPowerProvider {
+    root: PathBuf,
+}
+
+/// Asks `PowerManager` and `BatteryManager` of the Android activity
+///
+/// The headroom is `PowerManager.getThermalHeadroom` (API 30), devices
+/// without it report `getCurrentThermalStatus` (API 29) mapped to a headroom.
+/// Nothing is reported before the activity is set
+#[cfg(target_os = "android")]
+#[derive(Debug, Clone)]
+pub struct AndroidPowerProvider {
+    /// Seconds ahead the headroom is forecast, 0 to 60
+    pub forecast_seconds: i32,
+}
+
+/// Platforms without a provider report nothing
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+struct UnknownPowerProvider;
+
+/// Battery and thermal state of the device
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DevicePower {
+    pub battery: Option<BatteryStatus>,
+    pub thermal_headroom: Option<f32>,
+    pub thermal_state: ThermalState,
+    /// Battery level under which quality is reduced while discharging
+    pub low_battery_level: f32,
+}
+
+/// Written when the thermal state changes
+#[derive(Message, Debug, Clone, Copy)]
+pub struct ThermalStateChanged {
+    pub from: ThermalState,
+    pub to: ThermalState,
+}
+
+#[derive(Resource)]
+struct PowerStatusSource {
+    provider: Arc<dyn PowerStatusProvider>,
+}
+
+#[derive(Default)]
+pub struct PowerPlugin {
+    /// Platform provider is used if `None`
+    pub provider: Option<Arc<dyn PowerStatusProvider>>,
+}
+
+impl ThermalState {
+    pub fn from_headroom(headroom: f32) -> Self {
+        if headroom >= 1.0 {
+            Self::Critical
+        } else if headroom >= 0.85 {
+            Self::Serious
+        } else if headroom >= 0.7 {
+            Self::Fair
+        } else {
+            Self::Nominal
+        }
+    }
+}
+
+impl DevicePower {
+    pub fn is_battery_low(&self) -> bool {
+        self.battery
+            .is_some_and(|b| !b.charging && b.level <= self.low_battery_level)
+    }
+
+    /// Lowest quality ladder level allowed in the current power state
+    pub fn min_quality_level(&self, max_level: usize) -> usize {
+        let thermal = match self.thermal_state {
+            ThermalState::Nominal => 0,
+            ThermalState::Fair => max_level / 4,
+            ThermalState::Serious => max_level / 2,
+            ThermalState::Critical => max_level,
+        };
+        let battery = if self.is_battery_low() {
+            max_level / 4
+        } else {
+            0
+        };
+        thermal.max(battery)
+    }
+}
+
+impl Default for SysfsPowerProvider {
+    fn default() -> Self {
+        Self::with_root("/sys/class")
+    }
+}
+
+impl SysfsPowerProvider {
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn read(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok().map(|s| s.trim().to_owned())
+    }
+
+    fn read_number(path: &Path) -> Option<f32> {
+        Self::read(path)?.parse().ok()
+    }
+}
+
+#[cfg(target_os = "android")]
+impl Default for AndroidPowerProvider {
+    fn default() -> Self {
+        Self {
+            forecast_seconds: 10,
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+impl AndroidPowerProvider {
+    /// Run `f` with the activity on this thread, Java exceptions are cleared
+    /// and reported as `None`
+    fn with_activity<T>(
+        f: impl FnOnce(&mut jni::JNIEnv, &jni::objects::JObject) -> jni::errors::Result<T>,
+    ) -> Option<T> {
+        let app = bevy::android::ANDROID_APP.get()?;
+        // SAFETY: the pointers are the VM and activity of the running
+        // application, which outlive the process' use of them
+        let vm = unsafe { jni::JavaVM::from_raw(app.vm_as_ptr().cast()) }.ok()?;
+        let activity = unsafe { jni::objects::JObject::from_raw(app.activity_as_ptr().cast()) };
+        let mut env = vm.attach_current_thread().ok()?;
+        let result = env.with_local_frame(16, |env| f(env, &activity));
+        if env.exception_check().unwrap_or(false) {
+            let _ = env.exception_clear();
+        }
+        result.ok()
+    }
+
+    fn system_service<'local>(
+        env: &mut jni::JNIEnv<'local>,
+        activity: &jni::objects::JObject,
+        name: &str,
+    ) -> jni::errors::Result<jni::objects::JObject<'local>> {
+        let name = env
+            .get_static_field("android/content/Context", name, "Ljava/lang/String;")?
+            .l()?;
+        env.call_method(
+            activity,
+            "getSystemService",
+            "(Ljava/lang/String;)Ljava/lang/Object;",
+            &[(&name).into()],
+        )?
+        .l()
+    }
+
+    /// `PowerManager.THERMAL_STATUS_*` as a headroom, SEVERE is where
+    /// the headroom reaches 1.0
+    fn status_headroom(status: i32) -> Option<f32> {
+        match status {
+            0 => Some(0.0),
+            1 => Some(0.7),
+            2 => Some(0.85),
+            3..=6 => Some(1.0),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+impl PowerStatusProvider for AndroidPowerProvider {
+    fn battery(&self) -> Option<BatteryStatus> {
+        // BatteryManager.BATTERY_PROPERTY_CAPACITY
+        const CAPACITY: i32 = 4;
+        Self::with_activity(|env, activity| {
+            let battery = Self::system_service(env, activity, "BATTERY_SERVICE")?;
+            let capacity = env
+                .call_method(&battery, "getIntProperty", "(I)I", &[CAPACITY.into()])?
+                .i()?;
+            let charging = env.call_method(&battery, "isCharging", "()Z", &[])?.z()?;
+            Ok((capacity, charging))
+        })
+        // Integer.MIN_VALUE if the property is not supported
+        .filter(|(capacity, _)| *capacity >= 0)
+        .map(|(capacity, charging)| BatteryStatus {
+            level: (capacity as f32 / 100.0).clamp(0.0, 1.0),
+            charging,
+        })
+    }
+
+    fn thermal_headroom(&self) -> Option<f32> {
+        let forecast = self.forecast_seconds.clamp(0, 60);
+        Self::with_activity(|env, activity| {
+            let power = Self::system_service(env, activity, "POWER_SERVICE")?;
+            // NaN if not supported or asked more than once a second
+            let headroom = env
+                .call_method(&power, "getThermalHeadroom", "(I)F", &[forecast.into()])
+                .and_then(|headroom| headroom.f());
+            if env.exception_check()? {
+                env.exception_clear()?;
+            }
+            match headroom {
+                Ok(headroom) if headroom.is_finite() => return Ok(Some(headroom)),
+                _ => {}
+            }
+            let status = env
+                .call_method(&power, "getCurrentThermalStatus", "()I", &[])?
+                .i()?;
+            Ok(Self::status_headroom(status))
+        })
+        .flatten()
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+impl PowerStatusProvider for UnknownPowerProvider {
+    fn battery(&self) -> Option<BatteryStatus> {
+        None
+    }
+
+    fn thermal_headroom(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// Provider of the platform the runtime is built for
+fn platform_provider() -> Arc<dyn PowerStatusProvider> {
+    #[cfg(target_os = "android")]
+    {
+        Arc::new(AndroidPowerProvider::default())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Arc::new(SysfsPowerProvider::default())
+    }
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    {
+        Arc::new(UnknownPowerProvider)
+    }
+}
+
+impl PowerStatusProvider for SysfsPowerProvider {
+    fn battery(&self) -> Option<BatteryStatus> {
+        let supplies = fs::read_dir(self.root.join("power_supply")).ok()?;
+        supplies.flatten().map(|e| e.path()).find_map(|supply| {
+            if Self::read(&supply.join("type")).as_deref() != Some("Battery") {
+                return None;
+            }
+            let capacity = Self::read_number(&supply.join("capacity"))?;
+            let status = Self::read(&supply.join("status")).unwrap_or_default();
+            Some(BatteryStatus {
+                level: (capacity / 100.0).clamp(0.0, 1.0),
+                charging: status == "Charging" || status == "Full",
+            })
+        })
+    }
+
+    fn thermal_headroom(&self) -> Option<f32> {
+        let zones = fs::read_dir(self.root.join("thermal")).ok()?;
+        zones
+            .flatten()
+            .map(|e| e.path())
+            .filter(|zone| {
+                zone.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("thermal_zone"))
+            })
+            .filter_map(|zone| {
+                // Values are in millidegree Celsius
+                let temp = Self::read_number(&zone.join("temp"))?;
+                let trip = (0..8)
+                    .filter(|i| {
+                        Self::read(&zone.join(format!("trip_point_{}_type", i))).as_deref()
+                            == Some("passive")
+                    })
+                    .find_map(|i| {
+                        Self::read_number(&zone.join(format!("trip_point_{}_temp", i)))
+                    })?;
+                (trip > 0.0 && temp > 0.0).then(|| temp / trip)
+            })
+            .reduce(f32::max)
+    }
+}
+
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut App) {
+        let provider = match &self.provider {
+            Some(provider) => provider.clone(),
+            None => platform_provider(),
+        };
+        app.insert_resource(DevicePower {
+            low_battery_level: 0.15,
+            ..Default::default()
+        })
+        .insert_resource(PowerStatusSource { provider })
+        .add_message::<ThermalStateChanged>()
+        .add_systems(PreStartup, poll_power_status)
+        .add_systems(
+            PreUpdate,
+            (
+                poll_power_status.run_if(on_real_timer(POLL_INTERVAL)),
+                limit_quality,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn poll_power_status(
+    source: Res<PowerStatusSource>,
+    mut power: ResMut<DevicePower>,
+    mut changed: MessageWriter<ThermalStateChanged>,
+) {
+    let battery = source.provider.battery();
+    let thermal_headroom = source.provider.thermal_headroom();
+    let thermal_state = thermal_headroom
+        .map(ThermalState::from_headroom)
+        .unwrap_or_default();
+
+    if power.thermal_state != thermal_state {
+        info!(
+            "Thermal state {:?} -> {:?} (headroom {:?})",
+            power.thermal_state, thermal_state, thermal_headroom
+        );
+        changed.write(ThermalStateChanged {
+            from: power.thermal_state,
+            to: thermal_state,
+        });
+    }
+    power.battery = battery;
+    power.thermal_headroom = thermal_headroom;
+    power.thermal_state = thermal_state;
+}
+
+fn limit_quality(power: Res<DevicePower>, ladder: Option<ResMut<QualityLadder>>) {
+    let Some(mut ladder) = ladder else {
+        return;
+    };
+    if !power.is_changed() {
+        return;
+    }
+    let min_level = power.min_quality_level(ladder.max_level());
+    if ladder.min_level != min_level {
+        ladder.min_level = min_level;
+    }
+}
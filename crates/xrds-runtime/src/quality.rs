@@ -0,0 +1,466 @@
+use std::time::Duration;
+
+use bevy::{
+    camera::visibility::VisibilityRange,
+    light::{DirectionalLightShadowMap, PointLightShadowMap},
+    platform::time::Instant,
+    prelude::*,
+};
+use xrds_graphics::{
+    GBufferLayout, HalfResolution, LightProbeSettings, PostProcessing, RenderScale, RenderStats,
+};
+use xrds_openxr::OpenXrRefreshRate;
+
+use crate::UserProfile;
+
+/// Weight of the newest sample in the smoothed frame times
+const SMOOTHING: f32 = 0.1;
+
+/// Fraction of the refresh interval a frame may be over before it counts as
+/// late, for the jitter of frame times
+const LATE_MARGIN: f32 = 0.1;
+
+/// Scaled shadow maps never go below this, unless configured smaller
+const MIN_SHADOW_MAP_SIZE: usize = 256;
+
+/// Frame timing used by the quality controller
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FrameStats {
+    pub frame_time: Duration,
+    /// GPU time of the last frame if GPU timing is available
+    pub gpu_time: Option<Duration>,
+    pub smoothed_frame_time: Duration,
+    pub smoothed_gpu_time: Option<Duration>,
+    /// Time between two refreshes of the display, when known
+    pub refresh_interval: Option<Duration>,
+    pub frame_count: u64,
+    gpu_measured_at: Option<Instant>,
+}
+
+/// One step of the degradation ladder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QualityKnob {
+    RenderScale(f32),
+    /// Fraction of the shadow map sizes configured by the application
+    ShadowMapScale(f32),
+    PostProcessing(bool),
+    /// Multiplier of the distances of [`VisibilityRange`]s. Below 1 lower
+    /// levels of detail take over closer to the camera
+    LodBias(f32),
+}
+
+/// Load of the device the quality controller reacts to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameLoad {
+    /// GPU time of a frame, compared against [`QualityLadder::gpu_budget`]
+    Gpu(Duration),
+    /// Frame time, which vsync caps at the refresh interval. Late frames
+    /// step down, frames on time count as headroom since vsync hides any
+    /// more
+    Vsync {
+        frame_time: Duration,
+        refresh_interval: Duration,
+    },
+}
+
+/// Quality settings currently in effect
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct QualityState {
+    /// Number of ladder steps applied. 0 is full quality
+    pub level: usize,
+    /// Multiplier on top of the render scale of the user profile
+    pub render_scale: f32,
+    /// Multiplier of the directional and point light shadow map sizes
+    pub shadow_map_scale: f32,
+    pub post_processing: bool,
+    /// Multiplier of the distances of [`VisibilityRange`]s
+    pub lod_bias: f32,
+}
+
+/// Automatic quality controller
+///
+/// Steps down the ladder when the GPU time stays over budget and steps back
+/// up when there is enough headroom. Without GPU timing the frame time is
+/// compared against the refresh interval, and the level is left alone when
+/// that is not known either.
+#[derive(Resource, Debug, Clone)]
+pub struct QualityLadder {
+    pub enabled: bool,
+    /// Knobs applied in order when stepping down
+    pub steps: Vec<QualityKnob>,
+    pub gpu_budget: Duration,
+    /// Fraction of the budget the GPU time must stay under before stepping up
+    pub headroom: f32,
+    pub downgrade_after: Duration,
+    pub upgrade_after: Duration,
+    /// Levels below this are not used, e.g. because the device is hot
+    pub min_level: usize,
+    base: QualityState,
+    over_budget: Duration,
+    under_budget: Duration,
+}
+
+/// Shadow map size configured by the application and the size last written
+/// for it
+#[derive(Debug, Clone, Copy)]
+struct ShadowMapSize {
+    configured: usize,
+    applied: usize,
+}
+
+/// Range of the entity without [`QualityState::lod_bias`]
+#[derive(Component, Debug, Clone)]
+struct LodRange {
+    own: VisibilityRange,
+    applied: VisibilityRange,
+}
+
+/// Written whenever the quality controller changes the quality level
+#[derive(Message, Debug, Clone)]
+pub struct QualityChanged {
+    pub from: usize,
+    pub to: usize,
+    pub state: QualityState,
+}
+
+#[derive(Debug, Default)]
+pub struct QualityPlugin;
+
+impl Plugin for QualityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameStats>()
+            .init_resource::<QualityLadder>()
+            .init_resource::<QualityState>()
+            .add_message::<QualityChanged>()
+            .add_systems(Startup, apply_profile_quality)
+            .add_systems(
+                Last,
+                (
+                    update_frame_stats,
+                    update_quality_level,
+                    apply_quality_state,
+                    apply_lod_bias,
+                )
+                    .chain(),
+            );
+    }
+}
+
+impl FrameStats {
+    /// Record the GPU time of the last frame. Called by GPU timing providers
+    pub fn set_gpu_time(&mut self, gpu_time: Duration) {
+        self.gpu_time = Some(gpu_time);
+        self.smoothed_gpu_time = Some(match self.smoothed_gpu_time {
+            Some(smoothed) => smoothed.mul_f32(1.0 - SMOOTHING) + gpu_time.mul_f32(SMOOTHING),
+            None => gpu_time,
+        });
+    }
+
+    /// GPU time if available, frame time against the refresh interval
+    /// otherwise
+    pub fn load(&self) -> Option<FrameLoad> {
+        if let Some(gpu_time) = self.smoothed_gpu_time {
+            return Some(FrameLoad::Gpu(gpu_time));
+        }
+        self.refresh_interval
+            .map(|refresh_interval| FrameLoad::Vsync {
+                frame_time: self.smoothed_frame_time,
+                refresh_interval,
+            })
+    }
+}
+
+impl Default for QualityState {
+    fn default() -> Self {
+        Self {
+            level: 0,
+            render_scale: 1.0,
+            shadow_map_scale: 1.0,
+            post_processing: true,
+            lod_bias: 1.0,
+        }
+    }
+}
+
+impl QualityState {
+    pub fn apply(&mut self, knob: QualityKnob) {
+        match knob {
+            QualityKnob::RenderScale(scale) => self.render_scale = scale,
+            QualityKnob::ShadowMapScale(scale) => self.shadow_map_scale = scale,
+            QualityKnob::PostProcessing(enabled) => self.post_processing = enabled,
+            QualityKnob::LodBias(bias) => self.lod_bias = bias,
+        }
+    }
+}
+
+impl Default for QualityLadder {
+    fn default() -> Self {
+        Self::new(vec![
+            QualityKnob::RenderScale(0.9),
+            QualityKnob::RenderScale(0.8),
+            QualityKnob::ShadowMapScale(0.5),
+            QualityKnob::PostProcessing(false),
+            QualityKnob::LodBias(0.75),
+            QualityKnob::RenderScale(0.7),
+            QualityKnob::ShadowMapScale(0.25),
+            QualityKnob::LodBias(0.5),
+        ])
+    }
+}
+
+impl QualityLadder {
+    pub fn new(steps: Vec<QualityKnob>) -> Self {
+        Self {
+            enabled: true,
+            steps,
+            // 90 Hz with a small margin
+            gpu_budget: Duration::from_micros(10_000),
+            headroom: 0.75,
+            downgrade_after: Duration::from_millis(500),
+            upgrade_after: Duration::from_secs(3),
+            min_level: 0,
+            base: QualityState::default(),
+            over_budget: Duration::ZERO,
+            under_budget: Duration::ZERO,
+        }
+    }
+
+    /// Quality used at level 0
+    pub fn with_base(mut self, base: QualityState) -> Self {
+        self.base = base;
+        self
+    }
+
+    pub fn max_level(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Quality settings at `level`
+    pub fn state(&self, level: usize) -> QualityState {
+        let level = level.min(self.max_level());
+        let mut state = self.base.clone();
+        for knob in &self.steps[..level] {
+            state.apply(*knob);
+        }
+        state.level = level;
+        state
+    }
+
+    /// Feed one frame into the controller and return the new level if it
+    /// changed. Without a load only `min_level` is enforced
+    pub fn update(
+        &mut self,
+        level: usize,
+        load: Option<FrameLoad>,
+        delta: Duration,
+    ) -> Option<usize> {
+        let min_level = self.min_level.min(self.max_level());
+        if level < min_level {
+            self.over_budget = Duration::ZERO;
+            self.under_budget = Duration::ZERO;
+            return Some(min_level);
+        }
+
+        let (over, under) = match load {
+            Some(FrameLoad::Gpu(gpu_time)) => (
+                gpu_time > self.gpu_budget,
+                gpu_time < self.gpu_budget.mul_f32(self.headroom),
+            ),
+            Some(FrameLoad::Vsync {
+                frame_time,
+                refresh_interval,
+            }) => {
+                let late = frame_time > refresh_interval.mul_f32(1.0 + LATE_MARGIN);
+                (late, !late)
+            }
+            None => {
+                self.over_budget = Duration::ZERO;
+                self.under_budget = Duration::ZERO;
+                return None;
+            }
+        };
+        if over {
+            self.over_budget += delta;
+            self.under_budget = Duration::ZERO;
+        } else if under {
+            self.under_budget += delta;
+            self.over_budget = Duration::ZERO;
+        } else {
+            self.over_budget = Duration::ZERO;
+            self.under_budget = Duration::ZERO;
+        }
+
+        if self.over_budget >= self.downgrade_after && level < self.max_level() {
+            self.over_budget = Duration::ZERO;
+            return Some(level + 1);
+        }
+        if self.under_budget >= self.upgrade_after && level > min_level {
+            self.under_budget = Duration::ZERO;
+            return Some(level - 1);
+        }
+        None
+    }
+}
+
+fn apply_profile_quality(
+    profile: Option<Res<UserProfile>>,
+    light_probes: Option<ResMut<LightProbeSettings>>,
+    half_resolution: Option<ResMut<HalfResolution>>,
+    gbuffer_layout: Option<ResMut<GBufferLayout>>,
+) {
+    let Some(profile) = profile else {
+        return;
+    };
+    if let Some(mut light_probes) = light_probes {
+        light_probes.global_illumination = profile.render.global_illumination;
+    }
+    if let Some(mut half_resolution) = half_resolution {
+        *half_resolution = profile.render.half_resolution;
+    }
+    if let Some(mut gbuffer_layout) = gbuffer_layout {
+        gbuffer_layout.set_if_neq(profile.render.quality.gbuffer_layout());
+    }
+}
+
+fn update_frame_stats(
+    time: Res<Time<Real>>,
+    render_stats: Option<Res<RenderStats>>,
+    refresh_rate: Option<Res<OpenXrRefreshRate>>,
+    mut stats: ResMut<FrameStats>,
+) {
+    stats.refresh_interval = refresh_rate
+        .and_then(|refresh_rate| refresh_rate.current())
+        .filter(|hz| *hz > 0.0)
+        .map(|hz| Duration::from_secs_f32(1.0 / hz));
+
+    // Pass timings only change when new timestamps were read back
+    if let Some(render_stats) = render_stats.filter(|render_stats| {
+        render_stats.measured_at().is_some() && render_stats.measured_at() != stats.gpu_measured_at
+    }) {
+        stats.gpu_measured_at = render_stats.measured_at();
+        if let Some(gpu_time) = render_stats.gpu_time() {
+            stats.set_gpu_time(gpu_time);
+        }
+    }
+
+    let frame_time = time.delta();
+    stats.frame_time = frame_time;
+    stats.smoothed_frame_time = if stats.frame_count == 0 {
+        frame_time
+    } else {
+        stats.smoothed_frame_time.mul_f32(1.0 - SMOOTHING) + frame_time.mul_f32(SMOOTHING)
+    };
+    stats.frame_count += 1;
+}
+
+fn update_quality_level(
+    time: Res<Time<Real>>,
+    stats: Res<FrameStats>,
+    mut ladder: ResMut<QualityLadder>,
+    mut state: ResMut<QualityState>,
+    mut changed: MessageWriter<QualityChanged>,
+) {
+    if !ladder.enabled {
+        return;
+    }
+    let from = state.level;
+    let Some(to) = ladder.update(from, stats.load(), time.delta()) else {
+        return;
+    };
+    *state = ladder.state(to);
+    info!("Quality level {} -> {} (load {:?})", from, to, stats.load());
+    changed.write(QualityChanged {
+        from,
+        to,
+        state: state.clone(),
+    });
+}
+
+/// `current` size scaled from the configured one. A size that differs from
+/// the last applied one was set by the application and becomes the new
+/// configuration
+fn scale_shadow_map_size(current: usize, size: &mut Option<ShadowMapSize>, scale: f32) -> usize {
+    let configured = match *size {
+        Some(size) if size.applied == current => size.configured,
+        _ => current,
+    };
+    let applied = ((configured as f32 * scale.clamp(0.0, 1.0)).round() as usize)
+        .max(configured.min(MIN_SHADOW_MAP_SIZE));
+    *size = Some(ShadowMapSize {
+        configured,
+        applied,
+    });
+    applied
+}
+
+fn apply_quality_state(
+    state: Res<QualityState>,
+    directional: Option<ResMut<DirectionalLightShadowMap>>,
+    point: Option<ResMut<PointLightShadowMap>>,
+    post_processing: Option<ResMut<PostProcessing>>,
+    render_scale: Option<ResMut<RenderScale>>,
+    mut shadow_map_sizes: Local<(Option<ShadowMapSize>, Option<ShadowMapSize>)>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    if let Some(mut directional) = directional {
+        let size = scale_shadow_map_size(
+            directional.size,
+            &mut shadow_map_sizes.0,
+            state.shadow_map_scale,
+        );
+        if directional.size != size {
+            directional.size = size;
+        }
+    }
+    if let Some(mut point) = point {
+        let size =
+            scale_shadow_map_size(point.size, &mut shadow_map_sizes.1, state.shadow_map_scale);
+        if point.size != size {
+            point.size = size;
+        }
+    }
+    if let Some(mut post_processing) = post_processing {
+        post_processing.set_if_neq(PostProcessing {
+            enabled: state.post_processing,
+        });
+    }
+    if let Some(mut render_scale) = render_scale {
+        if render_scale.dynamic_scale != state.render_scale {
+            render_scale.dynamic_scale = state.render_scale;
+        }
+    }
+}
+
+fn scale_visibility_range(range: &VisibilityRange, bias: f32) -> VisibilityRange {
+    let bias = bias.max(0.0);
+    VisibilityRange {
+        start_margin: range.start_margin.start * bias..range.start_margin.end * bias,
+        end_margin: range.end_margin.start * bias..range.end_margin.end * bias,
+        use_aabb: range.use_aabb,
+    }
+}
+
+fn apply_lod_bias(
+    mut commands: Commands,
+    state: Res<QualityState>,
+    ranges: Query<(Entity, &VisibilityRange, Option<&LodRange>)>,
+) {
+    for (entity, range, lod) in ranges.iter() {
+        let own = match lod {
+            Some(lod) if *range == lod.applied => {
+                if !state.is_changed() {
+                    continue;
+                }
+                lod.own.clone()
+            }
+            // New entity, or the application changed the range
+            _ => range.clone(),
+        };
+        let applied = scale_visibility_range(&own, state.lod_bias);
+        commands
+            .entity(entity)
+            .insert((applied.clone(), LodRange { own, applied }));
+    }
+}
@@ -0,0 +1,542 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    camera::visibility::RenderLayers,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    transform::TransformSystems,
+};
+use tokio::sync::watch;
+use xrds_net::client::XrdsWebsocket;
+use xrds_openxr::OpenXrCameraIndex;
+
+use crate::HeadlessSettings;
+
+/// Views a remote frame can have, one per eye
+pub const MAX_REMOTE_VIEWS: usize = 2;
+
+/// First render layer of the remote displays, view `i` is on `REMOTE_LAYER + i`
+pub const REMOTE_LAYER: usize = 30;
+
+/// Frames the network side may queue before the oldest are dropped
+const FRAME_QUEUE: usize = 4;
+
+/// Where frames are rendered
+#[derive(Clone, Default)]
+pub enum RuntimeTarget {
+    #[default]
+    Local,
+    /// Frames are rendered on a server and reprojected on the device
+    Remote(RemoteRenderSettings),
+    /// Frames are rendered offscreen and read back, without winit or OpenXR
+    Headless(HeadlessSettings),
+}
+
+#[derive(Clone)]
+pub struct RemoteRenderSettings {
+    /// Vertical field of view the server renders with
+    pub fov_y: f32,
+    /// Distance of the display plane from the viewer in meters
+    pub plane_distance: f32,
+    pub transport: Arc<dyn RemoteFrameTransport>,
+}
+
+/// Poses the server renders the next frame with, one per view
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemotePose {
+    pub frame_id: u64,
+    pub views: Vec<Transform>,
+}
+
+/// View of a frame rendered by the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteView {
+    /// Pose the view was rendered with
+    pub pose: Transform,
+    pub size: UVec2,
+    /// RGBA8 sRGB pixels
+    pub pixels: Vec<u8>,
+}
+
+/// Frame rendered by the server, with a view per eye in XR
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteFrame {
+    pub frame_id: u64,
+    pub views: Vec<RemoteView>,
+}
+
+/// Connection to the render server
+pub trait RemoteFrameTransport: Send + Sync {
+    /// Send the latest poses to the server
+    fn send_pose(&self, pose: &RemotePose);
+    /// Return the newest decoded frame if one arrived
+    fn try_recv_frame(&self) -> Option<RemoteFrame>;
+}
+
+/// In-process transport. The network side holds the [`RemoteFrameEndpoint`]
+pub struct ChannelTransport {
+    poses: watch::Sender<Option<RemotePose>>,
+    frames: Mutex<Receiver<RemoteFrame>>,
+}
+
+/// Network side of a [`ChannelTransport`]
+pub struct RemoteFrameEndpoint {
+    /// Only the latest pose is kept, older ones are replaced
+    pub poses: watch::Receiver<Option<RemotePose>>,
+    /// Bounded, `try_send` fails while the runtime is behind
+    pub frames: SyncSender<RemoteFrame>,
+}
+
+/// Transport over an xrds-net WebSocket
+///
+/// Poses are sent as binary messages of [`RemotePose::to_bytes`] and frames
+/// are received as [`RemoteFrame::to_bytes`]. The connection runs on its own
+/// thread, a pose that was not sent yet is replaced by a newer one.
+pub struct WebSocketTransport {
+    poses: watch::Sender<Option<RemotePose>>,
+    frame: Arc<Mutex<Option<RemoteFrame>>>,
+}
+
+/// Camera whose pose is streamed to the render server
+///
+/// The camera only renders the remote display of its view, the local scene
+/// is not rendered while streaming. OpenXR eye cameras are viewers of their
+/// view, without OpenXR the first 3D camera is used unless the application
+/// chose one.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct RemoteViewer {
+    /// Index of the view, 0 is the left eye
+    pub view: u32,
+}
+
+#[derive(Component)]
+struct RemoteDisplay {
+    view: usize,
+    image: Handle<Image>,
+}
+
+#[derive(Resource)]
+struct RemoteRenderState {
+    settings: RemoteRenderSettings,
+    next_frame_id: u64,
+    last_frame: Option<u64>,
+}
+
+pub struct RemoteRenderPlugin {
+    pub settings: RemoteRenderSettings,
+}
+
+impl RemotePose {
+    /// Little endian frame id, view count and per view translation and
+    /// rotation as `f32`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + self.views.len() * 28);
+        bytes.extend_from_slice(&self.frame_id.to_le_bytes());
+        bytes.push(self.views.len() as u8);
+        for view in &self.views {
+            write_transform(&mut bytes, view);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader(bytes);
+        let frame_id = reader.u64()?;
+        let count = reader.u8()? as usize;
+        let views = (0..count)
+            .map(|_| reader.transform())
+            .collect::<Option<Vec<_>>>()?;
+        reader.0.is_empty().then_some(Self { frame_id, views })
+    }
+}
+
+impl RemoteFrame {
+    /// Little endian frame id, view count and per view the pose, width,
+    /// height and pixels
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let pixels: usize = self.views.iter().map(|view| view.pixels.len()).sum();
+        let mut bytes = Vec::with_capacity(9 + self.views.len() * 36 + pixels);
+        bytes.extend_from_slice(&self.frame_id.to_le_bytes());
+        bytes.push(self.views.len() as u8);
+        for view in &self.views {
+            write_transform(&mut bytes, &view.pose);
+            bytes.extend_from_slice(&view.size.x.to_le_bytes());
+            bytes.extend_from_slice(&view.size.y.to_le_bytes());
+            bytes.extend_from_slice(&view.pixels);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader(bytes);
+        let frame_id = reader.u64()?;
+        let count = reader.u8()? as usize;
+        let views = (0..count)
+            .map(|_| {
+                let pose = reader.transform()?;
+                let size = UVec2::new(reader.u32()?, reader.u32()?);
+                let len = (size.x as usize)
+                    .checked_mul(size.y as usize)?
+                    .checked_mul(4)?;
+                let pixels = reader.take(len)?.to_vec();
+                Some(RemoteView { pose, size, pixels })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        reader.0.is_empty().then_some(Self { frame_id, views })
+    }
+}
+
+fn write_transform(bytes: &mut Vec<u8>, transform: &Transform) {
+    for value in transform
+        .translation
+        .to_array()
+        .into_iter()
+        .chain(transform.rotation.to_array())
+    {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn transform(&mut self) -> Option<Transform> {
+        let translation = Vec3::new(self.f32()?, self.f32()?, self.f32()?);
+        let rotation = Quat::from_xyzw(self.f32()?, self.f32()?, self.f32()?, self.f32()?);
+        Some(Transform::from_translation(translation).with_rotation(rotation))
+    }
+}
+
+impl ChannelTransport {
+    pub fn new() -> (Self, RemoteFrameEndpoint) {
+        let (pose_sender, pose_receiver) = watch::channel(None);
+        let (frame_sender, frame_receiver) = mpsc::sync_channel(FRAME_QUEUE);
+        (
+            Self {
+                poses: pose_sender,
+                frames: Mutex::new(frame_receiver),
+            },
+            RemoteFrameEndpoint {
+                poses: pose_receiver,
+                frames: frame_sender,
+            },
+        )
+    }
+}
+
+impl RemoteFrameTransport for ChannelTransport {
+    fn send_pose(&self, pose: &RemotePose) {
+        self.poses.send_replace(Some(pose.clone()));
+    }
+
+    fn try_recv_frame(&self) -> Option<RemoteFrame> {
+        let frames = self.frames.lock().ok()?;
+        // Only the newest frame is displayed
+        frames.try_iter().last()
+    }
+}
+
+impl WebSocketTransport {
+    /// Connect to a render server, e.g. `ws://render.local:9000`
+    pub fn connect(url: &str) -> Self {
+        let (poses, pose_receiver) = watch::channel(None);
+        let frame = Arc::new(Mutex::new(None));
+        let url = url.to_string();
+        let received = frame.clone();
+        thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    error!("Remote rendering runtime failed: {}", err);
+                    return;
+                }
+            };
+            runtime.block_on(run_websocket(url, pose_receiver, received));
+        });
+        Self { poses, frame }
+    }
+}
+
+impl RemoteFrameTransport for WebSocketTransport {
+    fn send_pose(&self, pose: &RemotePose) {
+        self.poses.send_replace(Some(pose.clone()));
+    }
+
+    fn try_recv_frame(&self) -> Option<RemoteFrame> {
+        self.frame.lock().ok()?.take()
+    }
+}
+
+async fn run_websocket(
+    url: String,
+    mut poses: watch::Receiver<Option<RemotePose>>,
+    frame: Arc<Mutex<Option<RemoteFrame>>>,
+) {
+    let ws = match XrdsWebsocket::new().connect(&url).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            error!("Remote render server {} is not reachable: {}", url, err);
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            received = ws.rcv_ws() => {
+                let data = match received {
+                    Ok(data) => data,
+                    Err(err) => {
+                        error!("Remote render server closed: {}", err);
+                        break;
+                    }
+                };
+                match RemoteFrame::from_bytes(&data) {
+                    Some(received) => {
+                        if let Ok(mut frame) = frame.lock() {
+                            *frame = Some(received);
+                        }
+                    }
+                    None => warn!("Malformed remote frame of {} bytes", data.len()),
+                }
+            }
+            changed = poses.changed() => {
+                // The transport was dropped
+                if changed.is_err() {
+                    let _ = ws.close_ws().await;
+                    break;
+                }
+                let pose = poses.borrow_and_update().clone();
+                if let Some(pose) = pose {
+                    if let Err(err) = ws.send_ws(Some("binary"), pose.to_bytes()).await {
+                        warn!("Sending the remote pose failed: {}", err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl RemoteRenderSettings {
+    pub fn new(transport: Arc<dyn RemoteFrameTransport>) -> Self {
+        Self {
+            fov_y: 90f32.to_radians(),
+            plane_distance: 10.0,
+            transport,
+        }
+    }
+}
+
+impl Plugin for RemoteRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RemoteRenderState {
+            settings: self.settings.clone(),
+            next_frame_id: 0,
+            last_frame: None,
+        })
+        .add_systems(Startup, spawn_remote_displays)
+        .add_systems(
+            PostUpdate,
+            (select_remote_viewers, send_viewer_pose)
+                .chain()
+                .after(TransformSystems::Propagate),
+        )
+        .add_systems(Update, receive_remote_frame);
+    }
+}
+
+fn spawn_remote_displays(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = meshes.add(Rectangle::new(1.0, 1.0));
+    for view in 0..MAX_REMOTE_VIEWS {
+        let image = images.add(Image::new_fill(
+            Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+        ));
+        commands.spawn((
+            RemoteDisplay {
+                view,
+                image: image.clone(),
+            },
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color_texture: Some(image),
+                unlit: true,
+                ..Default::default()
+            })),
+            Transform::default(),
+            Visibility::Hidden,
+            RenderLayers::layer(REMOTE_LAYER + view),
+        ));
+    }
+}
+
+/// Make the OpenXR eye cameras viewers of their view, or the first 3D camera
+/// if there are none and the application did not choose a viewer. Viewers
+/// only see the display of their view
+fn select_remote_viewers(
+    mut commands: Commands,
+    viewers: Query<(), With<RemoteViewer>>,
+    eyes: Query<(Entity, &OpenXrCameraIndex), Without<RemoteViewer>>,
+    cameras: Query<Entity, With<Camera3d>>,
+    added: Query<(Entity, &RemoteViewer), Added<RemoteViewer>>,
+) {
+    for (entity, index) in eyes.iter() {
+        commands
+            .entity(entity)
+            .insert(RemoteViewer { view: index.0 });
+    }
+    if viewers.is_empty() && eyes.is_empty() {
+        if let Some(camera) = cameras.iter().next() {
+            commands.entity(camera).insert(RemoteViewer::default());
+        }
+    }
+    for (entity, viewer) in added.iter() {
+        commands
+            .entity(entity)
+            .insert(RenderLayers::layer(REMOTE_LAYER + viewer.view as usize));
+    }
+}
+
+fn send_viewer_pose(
+    mut state: ResMut<RemoteRenderState>,
+    viewers: Query<(&GlobalTransform, &RemoteViewer)>,
+) {
+    let mut views: Vec<_> = viewers
+        .iter()
+        .filter(|(_, viewer)| (viewer.view as usize) < MAX_REMOTE_VIEWS)
+        .map(|(transform, viewer)| (viewer.view, transform.compute_transform()))
+        .collect();
+    if views.is_empty() {
+        return;
+    }
+    views.sort_by_key(|(view, _)| *view);
+    // A mirror of the left eye shares its view
+    views.dedup_by_key(|(view, _)| *view);
+
+    let frame_id = state.next_frame_id;
+    state.next_frame_id += 1;
+    state.settings.transport.send_pose(&RemotePose {
+        frame_id,
+        views: views.into_iter().map(|(_, pose)| pose).collect(),
+    });
+}
+
+/// Place each view where it was rendered. The display planes stay world
+/// locked, so head rotation since the server rendered the frame is
+/// compensated (rotational reprojection)
+fn receive_remote_frame(
+    mut state: ResMut<RemoteRenderState>,
+    mut images: ResMut<Assets<Image>>,
+    mut displays: Query<(&RemoteDisplay, &mut Transform, &mut Visibility)>,
+) {
+    let Some(frame) = state.settings.transport.try_recv_frame() else {
+        return;
+    };
+    if state.last_frame.is_some_and(|last| frame.frame_id <= last) {
+        return;
+    }
+    if frame.views.is_empty() || frame.views.len() > MAX_REMOTE_VIEWS {
+        warn!(
+            "Remote frame {} has {} views",
+            frame.frame_id,
+            frame.views.len()
+        );
+        return;
+    }
+    for view in &frame.views {
+        let expected = (view.size.x * view.size.y * 4) as usize;
+        if view.pixels.len() != expected {
+            warn!(
+                "Remote frame {} has {} bytes, expected {}",
+                frame.frame_id,
+                view.pixels.len(),
+                expected
+            );
+            return;
+        }
+    }
+    state.last_frame = Some(frame.frame_id);
+
+    let distance = state.settings.plane_distance;
+    let height = 2.0 * distance * (state.settings.fov_y * 0.5).tan();
+    // A mono frame is shown to both eyes
+    let shared = frame.views.len() < MAX_REMOTE_VIEWS;
+    let mut views: Vec<_> = frame.views.into_iter().map(Some).collect();
+    for (display, mut transform, mut visibility) in displays.iter_mut() {
+        let index = display.view.min(views.len() - 1);
+        let Some(view) = (if shared {
+            views[index].clone()
+        } else {
+            views[index].take()
+        }) else {
+            continue;
+        };
+        let aspect = view.size.x as f32 / view.size.y.max(1) as f32;
+        *transform = Transform {
+            translation: view.pose.translation + view.pose.forward() * distance,
+            rotation: view.pose.rotation,
+            scale: Vec3::new(height * aspect, height, 1.0),
+        };
+        *visibility = Visibility::Visible;
+
+        if let Some(image) = images.get_mut(&display.image) {
+            *image = Image::new(
+                Extent3d {
+                    width: view.size.x,
+                    height: view.size.y,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                view.pixels,
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+            );
+        }
+    }
+}
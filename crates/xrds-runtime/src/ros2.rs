@@ -0,0 +1,379 @@
+use std::{collections::HashSet, sync::Mutex, time::Duration};
+
+use bevy::{asset::RenderAssetUsages, mesh::PrimitiveTopology, prelude::*};
+use serde_json::Value;
+use xrds_net::client::{Ros2Bridge, Ros2Event, Ros2PointCloud, Ros2Pose, Ros2Transform};
+
+use crate::{PluginContext, XrdsPlugin};
+
+/// Topics of the TF tree
+const TF_TOPICS: [&str; 2] = ["/tf", "/tf_static"];
+const TF_MSG_TYPE: &str = "tf2_msgs/msg/TFMessage";
+const POSE_MSG_TYPE: &str = "geometry_msgs/msg/PoseStamped";
+const POINT_CLOUD_MSG_TYPE: &str = "sensor_msgs/msg/PointCloud2";
+
+/// Connects to a ROS2 graph through a `rosbridge_server`
+///
+/// Entities follow poses and TF frames of the robot and show its point
+/// clouds, and poses of XR devices are published back for teleoperation.
+/// Positions are converted between the ROS convention (x forward, y left,
+/// z up) and Bevy's (x right, y up, -z forward). Can be added to a
+/// `RuntimeBuilder` with `with_plugin`.
+#[derive(Debug, Clone)]
+pub struct Ros2Plugin {
+    /// rosbridge WebSocket URL, e.g. `ws://robot.local:9090`
+    pub url: String,
+}
+
+/// Connection to the rosbridge server
+#[derive(Resource)]
+pub struct Ros2Connection {
+    bridge: Mutex<Ros2Bridge>,
+    connected: bool,
+    subscribed: HashSet<String>,
+    advertised: HashSet<String>,
+}
+
+/// Written for every message received on a subscribed topic
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct Ros2Received {
+    pub topic: String,
+    pub msg: Value,
+}
+
+/// Publishes a message, the topic is advertised on first use
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct Ros2Publish {
+    pub topic: String,
+    /// e.g. `geometry_msgs/msg/Twist`
+    pub msg_type: String,
+    pub msg: Value,
+}
+
+/// Moves the entity to the poses of a topic
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct Ros2PoseSource {
+    pub topic: String,
+    /// Any message with a pose, e.g. `nav_msgs/msg/Odometry`
+    pub msg_type: String,
+}
+
+/// Moves the entity to a frame of the TF tree
+///
+/// TF transforms are relative to the parent frame, so the entity should be a
+/// child of the entity of that frame.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct Ros2Frame(pub String);
+
+/// Shows the latest point cloud of a topic as the mesh of the entity
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct Ros2PointCloudSource {
+    pub topic: String,
+}
+
+/// Publishes the pose of the entity as `geometry_msgs/msg/PoseStamped`
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Ros2PosePublisher {
+    pub topic: String,
+    pub frame_id: String,
+    /// Time between two poses
+    pub interval: Duration,
+    since_publish: Duration,
+}
+
+impl Ros2Plugin {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+impl Ros2Connection {
+    pub fn connect(url: &str) -> Self {
+        Self {
+            bridge: Mutex::new(Ros2Bridge::connect(url)),
+            connected: false,
+            subscribed: HashSet::new(),
+            advertised: HashSet::new(),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Subscribe to a topic, received messages are written as [`Ros2Received`]
+    pub fn subscribe(&mut self, topic: &str, msg_type: &str) {
+        if !self.subscribed.insert(topic.to_owned()) {
+            return;
+        }
+        if let Ok(mut bridge) = self.bridge.lock() {
+            bridge.subscribe(topic, msg_type);
+        }
+    }
+
+    pub fn publish(&mut self, topic: &str, msg_type: &str, msg: Value) {
+        let Ok(mut bridge) = self.bridge.lock() else {
+            return;
+        };
+        if self.advertised.insert(topic.to_owned()) {
+            bridge.advertise(topic, msg_type);
+        }
+        bridge.publish(topic, msg);
+    }
+}
+
+impl Ros2PoseSource {
+    /// Poses of a `geometry_msgs/msg/PoseStamped` topic
+    pub fn new(topic: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+            msg_type: POSE_MSG_TYPE.to_owned(),
+        }
+    }
+
+    pub fn with_type(mut self, msg_type: impl Into<String>) -> Self {
+        self.msg_type = msg_type.into();
+        self
+    }
+}
+
+impl Ros2PointCloudSource {
+    pub fn new(topic: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+        }
+    }
+}
+
+impl Ros2PosePublisher {
+    /// Publish 30 poses per second
+    pub fn new(topic: impl Into<String>, frame_id: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+            frame_id: frame_id.into(),
+            interval: Duration::from_secs_f64(1.0 / 30.0),
+            since_publish: Duration::ZERO,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// Transform of a pose in ROS coordinates
+pub fn transform_from_ros(pose: &Ros2Pose) -> Transform {
+    let [x, y, z] = pose.position.map(|value| value as f32);
+    let [qx, qy, qz, qw] = pose.orientation.map(|value| value as f32);
+    Transform::from_xyz(-y, z, -x).with_rotation(Quat::from_xyzw(-qy, qz, -qx, qw).normalize())
+}
+
+/// Pose in ROS coordinates of a transform
+pub fn transform_to_ros(transform: &Transform) -> Ros2Pose {
+    let [x, y, z] = transform.translation.to_array();
+    let [qx, qy, qz, qw] = transform.rotation.to_array();
+    Ros2Pose {
+        position: [-z, -x, y].map(f64::from),
+        orientation: [-qz, -qx, qy, qw].map(f64::from),
+    }
+}
+
+impl Plugin for Ros2Plugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Ros2Connection::connect(&self.url))
+            .add_message::<Ros2Received>()
+            .add_message::<Ros2Publish>()
+            .add_systems(
+                Update,
+                (
+                    subscribe_ros2_topics,
+                    receive_ros2_messages,
+                    (
+                        apply_ros2_poses,
+                        apply_ros2_point_clouds.run_if(resource_exists::<Assets<Mesh>>),
+                    ),
+                    (publish_ros2_poses, send_ros2_publications).chain(),
+                )
+                    .chain(),
+            );
+    }
+}
+
+impl XrdsPlugin for Ros2Plugin {
+    fn build(&self, context: &mut PluginContext) {
+        context.add_plugins(self.clone());
+    }
+}
+
+fn subscribe_ros2_topics(
+    mut connection: ResMut<Ros2Connection>,
+    poses: Query<&Ros2PoseSource, Added<Ros2PoseSource>>,
+    frames: Query<(), Added<Ros2Frame>>,
+    clouds: Query<&Ros2PointCloudSource, Added<Ros2PointCloudSource>>,
+) {
+    for pose in poses.iter() {
+        connection.subscribe(&pose.topic, &pose.msg_type);
+    }
+    if !frames.is_empty() {
+        for topic in TF_TOPICS {
+            connection.subscribe(topic, TF_MSG_TYPE);
+        }
+    }
+    for cloud in clouds.iter() {
+        connection.subscribe(&cloud.topic, POINT_CLOUD_MSG_TYPE);
+    }
+}
+
+fn receive_ros2_messages(
+    mut connection: ResMut<Ros2Connection>,
+    mut received: MessageWriter<Ros2Received>,
+) {
+    let connection = connection.as_mut();
+    let Ok(bridge) = connection.bridge.get_mut() else {
+        return;
+    };
+    while let Some(event) = bridge.try_recv() {
+        match event {
+            Ros2Event::Connected => {
+                info!("Connected to ROS2");
+                connection.connected = true;
+            }
+            Ros2Event::Message(message) => {
+                received.write(Ros2Received {
+                    topic: message.topic,
+                    msg: message.msg,
+                });
+            }
+            Ros2Event::Error(error) => warn!("ROS2: {error}"),
+            Ros2Event::Disconnected => {
+                warn!("Disconnected from ROS2");
+                connection.connected = false;
+            }
+        }
+    }
+}
+
+fn apply_ros2_poses(
+    mut received: MessageReader<Ros2Received>,
+    mut poses: Query<(&Ros2PoseSource, &mut Transform), Without<Ros2Frame>>,
+    mut frames: Query<(&Ros2Frame, &mut Transform), Without<Ros2PoseSource>>,
+) {
+    for message in received.read() {
+        if TF_TOPICS.contains(&message.topic.as_str()) {
+            let transforms = Ros2Transform::from_tf(&message.msg);
+            for (frame, mut transform) in frames.iter_mut() {
+                if let Some(tf) = transforms.iter().find(|tf| tf.child_frame == frame.0) {
+                    *transform = transform_from_ros(&tf.pose).with_scale(transform.scale);
+                }
+            }
+            continue;
+        }
+        let Some(pose) = Ros2Pose::from_msg(&message.msg) else {
+            continue;
+        };
+        for (source, mut transform) in poses.iter_mut() {
+            if source.topic == message.topic {
+                *transform = transform_from_ros(&pose).with_scale(transform.scale);
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_ros2_point_clouds(
+    mut commands: Commands,
+    mut received: MessageReader<Ros2Received>,
+    clouds: Query<(
+        Entity,
+        &Ros2PointCloudSource,
+        Option<&Mesh3d>,
+        Option<&MeshMaterial3d<StandardMaterial>>,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    for message in received.read() {
+        let mut cloud = None;
+        for (entity, source, mesh, material) in clouds.iter() {
+            if source.topic != message.topic {
+                continue;
+            }
+            let cloud = match cloud
+                .get_or_insert_with(|| Ros2PointCloud::from_point_cloud2(&message.msg))
+            {
+                Ok(cloud) => cloud,
+                Err(error) => {
+                    warn!("Point cloud on {}: {error}", message.topic);
+                    break;
+                }
+            };
+
+            let positions = cloud
+                .points
+                .iter()
+                .map(|&[x, y, z]| [-y, z, -x])
+                .collect::<Vec<_>>();
+            let mut point_mesh = Mesh::new(PrimitiveTopology::PointList, RenderAssetUsages::all())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+            if cloud.colors.len() == cloud.points.len() {
+                let colors = cloud
+                    .colors
+                    .iter()
+                    .map(|&[r, g, b]| LinearRgba::from(Color::srgb_u8(r, g, b)).to_f32_array())
+                    .collect::<Vec<_>>();
+                point_mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+            }
+
+            match mesh.and_then(|mesh| meshes.get_mut(&mesh.0)) {
+                Some(mesh) => *mesh = point_mesh,
+                None => {
+                    commands
+                        .entity(entity)
+                        .insert(Mesh3d(meshes.add(point_mesh)));
+                }
+            }
+            if let (None, Some(materials)) = (material, materials.as_mut()) {
+                commands
+                    .entity(entity)
+                    .insert(MeshMaterial3d(materials.add(StandardMaterial {
+                        unlit: true,
+                        ..Default::default()
+                    })));
+            }
+        }
+    }
+}
+
+fn publish_ros2_poses(
+    time: Res<Time>,
+    mut connection: ResMut<Ros2Connection>,
+    mut publishers: Query<(&mut Ros2PosePublisher, &GlobalTransform)>,
+) {
+    for (mut publisher, transform) in publishers.iter_mut() {
+        publisher.since_publish += time.delta();
+        if publisher.since_publish < publisher.interval || !connection.connected {
+            continue;
+        }
+        publisher.since_publish = Duration::ZERO;
+        let pose = transform_to_ros(&transform.compute_transform());
+        connection.publish(
+            &publisher.topic,
+            POSE_MSG_TYPE,
+            pose.to_pose_stamped(&publisher.frame_id),
+        );
+    }
+}
+
+fn send_ros2_publications(
+    mut connection: ResMut<Ros2Connection>,
+    mut publications: MessageReader<Ros2Publish>,
+) {
+    for publication in publications.read() {
+        connection.publish(
+            &publication.topic,
+            &publication.msg_type,
+            publication.msg.clone(),
+        );
+    }
+}
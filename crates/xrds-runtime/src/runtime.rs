@@ -1,11 +1,37 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
 use crate::*;
 use bevy::{
+    app::{PluginsState, ScheduleRunnerPlugin},
+    asset::AssetMetaCheck,
     log::{Level, LogPlugin},
     prelude::*,
+    render::pipelined_rendering::PipelinedRenderingPlugin,
+    window::ExitCondition,
+    winit::WinitPlugin,
 };
 
 use error::RuntimeError;
-use xrds_openxr::OpenXrCamera;
+use xrds_components::{PaletteRole, ThemedText, XrdsComponentsPlugin};
+use xrds_graphics::{
+    AmbientOcclusionPlugin, AnisotropyPlugin, AssetImportPlugin, BindlessTexturesPlugin,
+    BloomPlugin, CameraOrderPlugin, CameraViewportPlugin, ClippingPlugin, ColorFilterPlugin,
+    DebugDrawPlugin, DepthPrepassPlugin, DepthPrepassing, DrawBatchesPlugin,
+    EnvironmentLightingPlugin, FrameGraphPlugin, GBufferPlugin, GltfValidationPlugin,
+    GpuQueryPlugin, HighlightPlugin, HotReloadPlugin, HudAnchor, HudElement, HudPlugin,
+    LightCullingPlugin, LightProbePlugin, MaterialVariantPlugin, MultisamplePlugin, Multisampling,
+    ObjectIdPlugin, OcclusionQueryPlugin, PaintPlugin, RenderStatsOverlay, RenderStatsPlugin,
+    SceneAnimationPlugin, SceneMorphWeightsPlugin, ScreenshotCapturePlugin, ShadowBiasPlugin,
+    SheenPlugin, SubsurfacePlugin, TextureCompressionPlugin, TransmissionPlugin, UpscalingPlugin,
+    VignettePlugin,
+};
+use xrds_openxr::{OpenXrCamera, OpenXrEnvironmentBlend, OpenXrOverlay};
+
+/// Frames [`Runtime::capture_frame`] waits for the readback
+const CAPTURE_FRAME_LIMIT: u64 = 16;
 
 pub trait RuntimeHandler {
     fn on_construct(&mut self) {}
@@ -13,7 +39,10 @@ pub trait RuntimeHandler {
     fn on_resumed(&mut self) {}
     fn on_suspended(&mut self) {}
     fn on_end(&mut self) {}
+    /// Called once per frame in `Update`, after the events of the frame
     fn on_update(&mut self) {}
+    /// Keyboard and mouse input of the window
+    fn on_event(&mut self, _event: &RuntimeEvent) {}
     fn on_deconstruct(&mut self) {}
 }
 
@@ -24,10 +53,86 @@ pub struct Runtime {
 pub struct RuntimeParameters {
     pub app_name: String,
     pub enable_xr: bool,
+    /// User profile loaded at startup. "default" is used if `None`
+    pub profile: Option<String>,
+    pub target: RuntimeTarget,
+    /// Keys for encrypted and signed content packages
+    pub content: ContentProtection,
+    /// Timestep of `FixedUpdate`. Bevy's default of 64 Hz is used if `None`
+    pub fixed_timestep: Option<Duration>,
+    /// Run as an overlay on top of other XR applications, with the given
+    /// placement. Higher placements are composited on top
+    pub overlay: Option<u32>,
+    /// Bytes of meshes and textures uploaded to the GPU per frame while
+    /// streaming assets. Unlimited if `None`
+    pub upload_budget: Option<usize>,
+    /// Show CPU and GPU frame timings in the corner of the window
+    pub stats_overlay: bool,
+    /// MSAA sample count of 3D cameras, 1 disables multisampling. Cameras can
+    /// override it with `CameraMultisample`
+    pub msaa_samples: u32,
+    /// Draw a depth-only pass before the main pass of 3D cameras, so only
+    /// visible fragments are shaded. Helps scenes with heavy overdraw
+    pub depth_prepass: bool,
+    /// Simulate `RigidBody`s and `Collider`s in `FixedUpdate`
+    pub physics: bool,
 }
 
-impl Runtime {
+impl Default for RuntimeParameters {
+    fn default() -> Self {
+        Self {
+            app_name: "".to_owned(),
+            enable_xr: false,
+            profile: None,
+            target: RuntimeTarget::Local,
+            content: ContentProtection::default(),
+            fixed_timestep: None,
+            overlay: None,
+            upload_budget: None,
+            stats_overlay: false,
+            msaa_samples: 4,
+            depth_prepass: false,
+            physics: false,
+        }
+    }
+}
+
+/// Builds a [`Runtime`] with additional [`XrdsPlugin`]s
+pub struct RuntimeBuilder {
+    params: RuntimeParameters,
+    plugins: Vec<Box<dyn XrdsPlugin>>,
+    environment_blend: OpenXrEnvironmentBlend,
+}
+
+impl RuntimeBuilder {
     pub fn new(params: RuntimeParameters) -> Self {
+        Self {
+            params,
+            plugins: vec![],
+            environment_blend: OpenXrEnvironmentBlend::default(),
+        }
+    }
+
+    /// How XR content is combined with the real world, e.g.
+    /// [`OpenXrEnvironmentBlend::Passthrough`] for mixed reality. Cameras
+    /// clear to transparent unless it is opaque
+    pub fn with_environment_blend(mut self, environment_blend: OpenXrEnvironmentBlend) -> Self {
+        self.environment_blend = environment_blend;
+        self
+    }
+
+    /// Plugins are built in the order they are added, after the built-in subsystems
+    pub fn with_plugin(mut self, plugin: impl XrdsPlugin) -> Self {
+        if self.plugins.iter().any(|p| p.name() == plugin.name()) {
+            warn!("Plugin {} is already added", plugin.name());
+            return self;
+        }
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    pub fn build(self) -> Runtime {
+        let params = self.params;
         let mut app = App::new();
 
         // Add log plugin first for logging in plugin build phase
@@ -36,39 +141,252 @@ impl Runtime {
             filter: "bevy=info,wgpu=warn,naga=info".to_owned(),
             ..Default::default()
         });
-        if params.enable_xr {
-            app.add_plugins(xrds_openxr::add_plugins(
-                DefaultPlugins.build().disable::<LogPlugin>(),
-                if params.app_name.is_empty() {
-                    "OpenXRDS".to_owned()
-                } else {
-                    params.app_name.clone()
-                },
+        // Asset sources must be registered before the asset plugin
+        app.add_plugins(ContentProtectionPlugin {
+            protection: params.content.clone(),
+        });
+        if !params.enable_xr {
+            app.add_plugins(WindowImportPlugin::default());
+        }
+
+        let app_name = if params.app_name.is_empty() {
+            "OpenXRDS".to_owned()
+        } else {
+            params.app_name.clone()
+        };
+        // `.meta` files next to assets hold `ImportSettings` instead of loader settings
+        let asset_plugin = AssetPlugin {
+            meta_check: AssetMetaCheck::Never,
+            ..default()
+        };
+        if let RuntimeTarget::Headless(settings) = &params.target {
+            if params.enable_xr {
+                warn!("XR is disabled for the headless target");
+            }
+            // Frames are driven by the schedule runner instead of winit, and
+            // rendered within the update so captures are deterministic
+            app.add_plugins((
+                DefaultPlugins
+                    .build()
+                    .disable::<LogPlugin>()
+                    .disable::<WinitPlugin>()
+                    .disable::<PipelinedRenderingPlugin>()
+                    .set(WindowPlugin {
+                        primary_window: None,
+                        exit_condition: ExitCondition::DontExit,
+                        ..default()
+                    })
+                    .set(asset_plugin),
+                ScheduleRunnerPlugin::run_loop(settings.frame_interval),
             ));
+        } else if params.enable_xr {
+            let base_plugins = DefaultPlugins
+                .build()
+                .disable::<LogPlugin>()
+                .set(asset_plugin);
+            app.add_plugins(match params.overlay {
+                Some(placement) => xrds_openxr::add_overlay_plugins(
+                    base_plugins,
+                    app_name.clone(),
+                    OpenXrOverlay::new(placement),
+                ),
+                None => xrds_openxr::add_plugins(base_plugins, app_name.clone()),
+            });
+            app.insert_resource(self.environment_blend);
         } else {
-            app.add_plugins(DefaultPlugins.build().disable::<LogPlugin>());
+            app.add_plugins(
+                DefaultPlugins
+                    .build()
+                    .disable::<LogPlugin>()
+                    .set(asset_plugin),
+            );
+        }
+        if let Some(timestep) = params.fixed_timestep {
+            app.insert_resource(Time::<Fixed>::from_duration(timestep));
+        }
+
+        app.add_plugins((
+            SettingsPlugin::new(app_name, params.profile.as_deref().unwrap_or("default")),
+            RuntimeEventPlugin,
+            XrdsComponentsPlugin,
+            (
+                AssetImportPlugin,
+                GltfValidationPlugin,
+                BindlessTexturesPlugin,
+                DrawBatchesPlugin,
+                TextureCompressionPlugin,
+            ),
+            (
+                ColorFilterPlugin,
+                VignettePlugin,
+                UpscalingPlugin,
+                DebugDrawPlugin,
+                HudPlugin,
+            ),
+            (LightCullingPlugin, ShadowBiasPlugin),
+            (
+                CameraOrderPlugin,
+                CameraViewportPlugin,
+                MultisamplePlugin,
+                DepthPrepassPlugin,
+                GBufferPlugin,
+            ),
+            (
+                (GpuQueryPlugin, OcclusionQueryPlugin),
+                FrameGraphPlugin,
+                RenderStatsPlugin,
+                ScreenshotCapturePlugin,
+            ),
+            (
+                SceneAnimationPlugin,
+                SceneMorphWeightsPlugin,
+                MaterialVariantPlugin,
+                PaintPlugin,
+                ClippingPlugin,
+                (EnvironmentLightingPlugin, LightProbePlugin),
+                HighlightPlugin,
+                ObjectIdPlugin,
+                HotReloadPlugin,
+                (BloomPlugin, AmbientOcclusionPlugin, TransmissionPlugin),
+                SheenPlugin,
+                AnisotropyPlugin,
+                SubsurfacePlugin,
+            ),
+            (
+                ComfortPlugin,
+                CameraControllerPlugin,
+                ViewpointPlugin {
+                    transition: if params.enable_xr {
+                        ViewpointSettings::teleport().transition
+                    } else {
+                        ViewpointSettings::default().transition
+                    },
+                },
+                TourPlugin,
+                WorldFilePlugin,
+                AnnotationPlugin,
+                DataBindingPlugin,
+                PlaybackPlugin,
+                SceneLayersPlugin,
+                StableIdPlugin,
+            ),
+            AssetStreamingPlugin {
+                upload_budget: params.upload_budget,
+            },
+            CalibrationPlugin,
+            QualityPlugin,
+            PowerPlugin::default(),
+            WatchdogPlugin::default(),
+        ));
+
+        if params.physics {
+            app.add_plugins(PhysicsPlugin);
+        }
+        match params.target {
+            RuntimeTarget::Local => {}
+            RuntimeTarget::Remote(settings) => {
+                app.add_plugins(RemoteRenderPlugin { settings });
+            }
+            RuntimeTarget::Headless(settings) => {
+                app.add_plugins(HeadlessPlugin { settings });
+            }
+        }
+
+        app.insert_resource(Multisampling::new(params.msaa_samples));
+        app.insert_resource(DepthPrepassing::new(params.depth_prepass));
+        if params.stats_overlay {
+            app.add_systems(Startup, spawn_stats_overlay);
+        }
+
+        for plugin in self.plugins {
+            app.add_plugins(XrdsPluginAdapter(plugin));
         }
 
         app.add_systems(Startup, test_setup);
-        Self { app }
+        Runtime { app }
+    }
+}
+
+impl Runtime {
+    pub fn new(params: RuntimeParameters) -> Self {
+        RuntimeBuilder::new(params).build()
+    }
+
+    pub fn builder(params: RuntimeParameters) -> RuntimeBuilder {
+        RuntimeBuilder::new(params)
     }
 
     pub fn run<A>(mut self, mut app: A) -> Result<(), RuntimeError>
     where
-        A: RuntimeHandler + Send + Sync,
+        A: RuntimeHandler + Send + Sync + 'static,
     {
         app.on_begin();
 
-        // Pseudo Code
-        // app.on_begin(self.world);
-        // app.world.build_startup(&mut self.app);
-
+        let handler = Arc::new(Mutex::new(app));
+        self.app
+            .insert_resource(RuntimeHandlerSlot(handler.clone()));
         self.app.run();
 
-        app.on_end();
+        handler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .on_end();
 
         Ok(())
     }
+
+    /// Renders `frames` frames of a runtime with the headless target and
+    /// returns the last one, e.g. to compare it with a reference image in CI.
+    /// The world keeps its state, so further frames can be captured. The
+    /// runtime handler is not called
+    pub fn capture_frame(
+        &mut self,
+        frames: u32,
+        format: CaptureFormat,
+    ) -> Result<FrameCaptured, RuntimeError> {
+        self.finish_plugins();
+        let frame = self
+            .app
+            .world_mut()
+            .get_resource_mut::<HeadlessFrames>()
+            .ok_or(RuntimeError::CAPTURE)?
+            .request_capture(frames.saturating_sub(1), format);
+
+        // The readback arrives a few frames after the frame is rendered
+        for _ in 0..frames as u64 + CAPTURE_FRAME_LIMIT {
+            self.app.update();
+            let captured = self
+                .app
+                .world_mut()
+                .resource_mut::<Messages<FrameCaptured>>()
+                .drain()
+                .find(|captured| captured.frame == frame);
+            if let Some(captured) = captured {
+                return Ok(captured);
+            }
+        }
+        Err(RuntimeError::CAPTURE)
+    }
+
+    /// [`App::run`] finishes the plugins of runtimes which are run
+    fn finish_plugins(&mut self) {
+        if self.app.plugins_state() == PluginsState::Cleaned {
+            return;
+        }
+        while self.app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        self.app.finish();
+        self.app.cleanup();
+    }
+}
+
+fn spawn_stats_overlay(mut commands: Commands) {
+    commands.spawn((
+        RenderStatsOverlay,
+        ThemedText(PaletteRole::Text),
+        HudElement::new(HudAnchor::TopLeft).with_offset(Vec2::splat(8.0)),
+    ));
 }
 
 fn test_setup(
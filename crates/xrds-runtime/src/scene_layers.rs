@@ -0,0 +1,257 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::WorldFileError;
+
+/// Opinions of a layer about one entity of a scene
+///
+/// Unset fields keep the value of weaker layers or the base scene. Material
+/// swaps are inherited by descendants without their own material override.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneOverride {
+    pub transform: Option<Transform>,
+    /// Asset path of a `StandardMaterial`, e.g. `car.glb#Material2`
+    pub material: Option<String>,
+    pub visible: Option<bool>,
+}
+
+/// Named set of overrides applied on top of a base scene without changing it
+///
+/// Overrides are keyed by the path of entity names below the layered entity,
+/// separated by `/`, e.g. `Body/Door_L`. Unnamed entities are skipped in paths.
+/// Layers are stored in their own files, so review configurations can share
+/// one scene and asset set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverrideLayer {
+    pub name: String,
+    pub enabled: bool,
+    pub overrides: BTreeMap<String, SceneOverride>,
+}
+
+/// Stack of [`OverrideLayer`]s applied to the descendants of this entity
+///
+/// Later layers are stronger. Toggling or removing a layer restores the
+/// values of the weaker layers and the base scene.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct SceneLayers {
+    pub layers: Vec<OverrideLayer>,
+}
+
+/// Base scene values of an entity changed by a layer
+#[derive(Component, Debug, Clone)]
+struct LayerBaseValues {
+    transform: Transform,
+    visibility: Option<Visibility>,
+    material: Option<Handle<StandardMaterial>>,
+}
+
+/// Applies [`SceneLayers`] to spawned scenes
+#[derive(Debug, Default)]
+pub struct SceneLayersPlugin;
+
+impl OverrideLayer {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            enabled: true,
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_transform(mut self, path: impl Into<String>, transform: Transform) -> Self {
+        self.overrides.entry(path.into()).or_default().transform = Some(transform);
+        self
+    }
+
+    pub fn with_material(mut self, path: impl Into<String>, material: impl Into<String>) -> Self {
+        self.overrides.entry(path.into()).or_default().material = Some(material.into());
+        self
+    }
+
+    pub fn with_visibility(mut self, path: impl Into<String>, visible: bool) -> Self {
+        self.overrides.entry(path.into()).or_default().visible = Some(visible);
+        self
+    }
+
+    pub fn to_json(&self) -> Result<String, WorldFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, WorldFileError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WorldFileError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, self.to_json()?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WorldFileError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl SceneLayers {
+    pub fn new(layers: impl IntoIterator<Item = OverrideLayer>) -> Self {
+        Self {
+            layers: layers.into_iter().collect(),
+        }
+    }
+
+    /// Adds a layer stronger than the existing ones, replacing a layer with the same name
+    pub fn push(&mut self, layer: OverrideLayer) {
+        self.layers.retain(|existing| existing.name != layer.name);
+        self.layers.push(layer);
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut OverrideLayer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// Returns false if there is no layer with this name
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.layer_mut(name) {
+            Some(layer) => {
+                layer.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Composed opinions of the enabled layers about the entity at `path`
+    pub fn resolve(&self, path: &str) -> SceneOverride {
+        let mut resolved = SceneOverride::default();
+        for layer in self.layers.iter().filter(|layer| layer.enabled) {
+            let Some(opinion) = layer.overrides.get(path) else {
+                continue;
+            };
+            if opinion.transform.is_some() {
+                resolved.transform = opinion.transform;
+            }
+            if opinion.material.is_some() {
+                resolved.material.clone_from(&opinion.material);
+            }
+            if opinion.visible.is_some() {
+                resolved.visible = opinion.visible;
+            }
+        }
+        resolved
+    }
+}
+
+impl Plugin for SceneLayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            apply_scene_layers.before(TransformSystems::Propagate),
+        );
+    }
+}
+
+type LayerTarget<'a> = (
+    &'a mut Transform,
+    Option<&'a mut Visibility>,
+    Option<&'a mut MeshMaterial3d<StandardMaterial>>,
+    Option<&'a LayerBaseValues>,
+);
+
+/// Layers are applied again when they change or when scenes finish spawning
+#[allow(clippy::too_many_arguments)]
+fn apply_scene_layers(
+    mut commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    roots: Query<(Entity, Ref<SceneLayers>)>,
+    spawned: Query<(), Added<Name>>,
+    hierarchy: Query<&Children>,
+    names: Query<&Name>,
+    mut targets: Query<LayerTarget>,
+    mut stack: Local<Vec<(Entity, String, Option<String>)>>,
+) {
+    let spawned = !spawned.is_empty();
+    for (root, layers) in roots.iter() {
+        if !spawned && !layers.is_changed() {
+            continue;
+        }
+        stack.clear();
+        for child in hierarchy.get(root).into_iter().flatten() {
+            stack.push((*child, String::new(), None));
+        }
+
+        while let Some((entity, parent_path, inherited_material)) = stack.pop() {
+            let path = match names.get(entity) {
+                Ok(name) if parent_path.is_empty() => name.to_string(),
+                Ok(name) => format!("{parent_path}/{name}"),
+                Err(_) => parent_path,
+            };
+            let resolved = if path.is_empty() {
+                SceneOverride::default()
+            } else {
+                layers.resolve(&path)
+            };
+            let material = resolved.material.clone().or(inherited_material);
+            for child in hierarchy.get(entity).into_iter().flatten() {
+                stack.push((*child, path.clone(), material.clone()));
+            }
+
+            let Ok((mut transform, visibility, mesh_material, base)) = targets.get_mut(entity)
+            else {
+                continue;
+            };
+            let overridden = resolved.transform.is_some()
+                || resolved.visible.is_some()
+                || (material.is_some() && mesh_material.is_some());
+            let base = match (base, overridden) {
+                (Some(base), _) => base.clone(),
+                (None, true) => {
+                    let base = LayerBaseValues {
+                        transform: *transform,
+                        visibility: visibility.as_deref().copied(),
+                        material: mesh_material.as_ref().map(|material| material.0.clone()),
+                    };
+                    commands.entity(entity).insert(base.clone());
+                    base
+                }
+                (None, false) => continue,
+            };
+
+            transform.set_if_neq(resolved.transform.unwrap_or(base.transform));
+            let new_visibility = match resolved.visible {
+                Some(true) => Visibility::Inherited,
+                Some(false) => Visibility::Hidden,
+                None => base.visibility.unwrap_or_default(),
+            };
+            match visibility {
+                Some(mut visibility) => {
+                    visibility.set_if_neq(new_visibility);
+                }
+                None => {
+                    commands.entity(entity).insert(new_visibility);
+                }
+            }
+            if let Some(mut mesh_material) = mesh_material {
+                let swapped = match (&material, &asset_server) {
+                    (Some(path), Some(asset_server)) => Some(asset_server.load(path.clone())),
+                    _ => None,
+                };
+                if let Some(handle) = swapped.or_else(|| base.material.clone()) {
+                    if mesh_material.0 != handle {
+                        mesh_material.0 = handle;
+                    }
+                }
+            }
+            if !overridden {
+                commands.entity(entity).remove::<LayerBaseValues>();
+            }
+        }
+    }
+}
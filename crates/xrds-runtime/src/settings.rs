@@ -0,0 +1,395 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use bevy::{app::AppExit, audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
+use xrds_graphics::{GBufferLayout, GlobalIllumination, HalfResolution, RenderScale};
+use xrds_openxr::OpenXrIpdOffset;
+
+use crate::ComfortSettings;
+
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QualityLevel {
+    Low,
+    Medium,
+    #[default]
+    High,
+    Ultra,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderQualitySettings {
+    pub quality: QualityLevel,
+    /// Eye buffer resolution multiplier, applied as `RenderScale::scale`. The
+    /// quality controller scales it further
+    pub render_scale: f32,
+    /// Gathering of progressive light probe volumes. `Dynamic` is meant for
+    /// high-end desktops and needs the `ddgi` feature
+    pub global_illumination: GlobalIllumination,
+    /// Passes rendered at half resolution, a performance mode for mobile XR
+    pub half_resolution: HalfResolution,
+}
+
+/// Linear volume multipliers in 0.0..=1.0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioVolumes {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+    pub voice: f32,
+}
+
+/// Volume of an audio source taken from [`AudioVolumes`]. Sources without it
+/// only follow the master volume
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCategory {
+    Music,
+    Effects,
+    Voice,
+}
+
+/// User specific corrections on top of the tracking space
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalibrationOffsets {
+    /// Floor height correction in meters
+    pub height_offset: f32,
+    /// IPD correction in meters, added to the distance between the eye cameras
+    pub ipd_offset: f32,
+    /// Content origin on the floor (x, z)
+    pub origin: Vec2,
+    /// Content heading around the up axis in radians
+    pub yaw: f32,
+    /// Manually traced play area (x, z). Empty if the XR runtime provides one
+    pub boundary: Vec<Vec2>,
+}
+
+/// Settings of one user. Changes are written back to the profile store
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserProfile {
+    pub name: String,
+    pub comfort: ComfortSettings,
+    pub render: RenderQualitySettings,
+    pub audio: AudioVolumes,
+    pub calibration: CalibrationOffsets,
+}
+
+/// Directory containing one JSON file per user profile
+#[derive(Resource, Debug, Clone)]
+pub struct ProfileStore {
+    root: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct SettingsPlugin {
+    pub app_name: String,
+    pub profile: String,
+    /// Profile directory. Platform config directory is used if `None`
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Resource, Debug, Default)]
+struct ProfileSaveState {
+    pending: Option<Duration>,
+}
+
+impl QualityLevel {
+    /// Lower tiers stay forward, a gbuffer costs more bandwidth than mobile
+    /// GPUs can spare. Ultra is deferred for scenes with many lights, and
+    /// trades MSAA for FXAA with it
+    pub fn gbuffer_layout(&self) -> GBufferLayout {
+        match self {
+            Self::Low | Self::Medium | Self::High => GBufferLayout::Forward,
+            Self::Ultra => GBufferLayout::Deferred,
+        }
+    }
+}
+
+impl Default for RenderQualitySettings {
+    fn default() -> Self {
+        Self {
+            quality: QualityLevel::default(),
+            render_scale: 1.0,
+            global_illumination: GlobalIllumination::default(),
+            half_resolution: HalfResolution::default(),
+        }
+    }
+}
+
+impl Default for AudioVolumes {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 1.0,
+            effects: 1.0,
+            voice: 1.0,
+        }
+    }
+}
+
+impl AudioVolumes {
+    /// Linear volume of a source in `category`, including the master volume
+    pub fn volume(&self, category: Option<AudioCategory>) -> f32 {
+        self.master
+            * match category {
+                Some(AudioCategory::Music) => self.music,
+                Some(AudioCategory::Effects) => self.effects,
+                Some(AudioCategory::Voice) => self.voice,
+                None => 1.0,
+            }
+    }
+}
+
+impl Default for UserProfile {
+    fn default() -> Self {
+        Self::new("default")
+    }
+}
+
+impl UserProfile {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            comfort: Default::default(),
+            render: Default::default(),
+            audio: Default::default(),
+            calibration: Default::default(),
+        }
+    }
+}
+
+/// Platform specific directory for user configuration files
+pub fn config_dir() -> Option<PathBuf> {
+    let env_path = |key: &str| {
+        std::env::var_os(key)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(target_os = "windows") {
+        env_path("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_path("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else if cfg!(target_os = "android") {
+        // There is no user home on Android. The activity must pass its files directory
+        None
+    } else {
+        env_path("XDG_CONFIG_HOME").or_else(|| env_path("HOME").map(|home| home.join(".config")))
+    }
+}
+
+impl ProfileStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// `<config dir>/OpenXRDS/<app name>/profiles`
+    pub fn for_app(app_name: &str) -> Option<Self> {
+        config_dir().map(|dir| Self::new(dir.join("OpenXRDS").join(app_name).join("profiles")))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, name: &str) -> anyhow::Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+            && !name.starts_with('.');
+        if !valid {
+            return Err(anyhow!("Invalid profile name '{}'", name));
+        }
+        Ok(self.root.join(format!("{}.json", name)))
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.path(name).is_ok_and(|path| path.is_file())
+    }
+
+    pub fn load(&self, name: &str) -> anyhow::Result<UserProfile> {
+        let source = fs::read_to_string(self.path(name)?)?;
+        let mut profile: UserProfile = serde_json::from_str(&source)?;
+        profile.name = name.to_owned();
+        Ok(profile)
+    }
+
+    /// Load the profile or create a default one if it does not exist
+    pub fn load_or_default(&self, name: &str) -> anyhow::Result<UserProfile> {
+        if self.exists(name) {
+            self.load(name)
+        } else {
+            Ok(UserProfile::new(name))
+        }
+    }
+
+    pub fn save(&self, profile: &UserProfile) -> anyhow::Result<()> {
+        let path = self.path(&profile.name)?;
+        fs::create_dir_all(&self.root)?;
+        // Write to a temporary file first so a crash never leaves a truncated profile
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(profile)?)?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, name: &str) -> anyhow::Result<()> {
+        fs::remove_file(self.path(name)?)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> anyhow::Result<Vec<String>> {
+        if !self.root.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut names = vec![];
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(stem.to_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+impl SettingsPlugin {
+    pub fn new(app_name: impl Into<String>, profile: impl Into<String>) -> Self {
+        Self {
+            app_name: app_name.into(),
+            profile: profile.into(),
+            directory: None,
+        }
+    }
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let store = match &self.directory {
+            Some(directory) => Some(ProfileStore::new(directory)),
+            None => ProfileStore::for_app(&self.app_name),
+        };
+
+        let profile = match &store {
+            Some(store) => store
+                .load_or_default(&self.profile)
+                .inspect(|_| info!("Loaded user profile '{}'", self.profile))
+                .unwrap_or_else(|e| {
+                    warn!("Could not load user profile '{}': {}", self.profile, e);
+                    UserProfile::new(self.profile.clone())
+                }),
+            None => {
+                warn!("No config directory. User profile will not be saved");
+                UserProfile::new(self.profile.clone())
+            }
+        };
+
+        app.insert_resource(profile.comfort.clone())
+            .insert_resource(profile)
+            .init_resource::<ProfileSaveState>()
+            .add_systems(PreUpdate, (sync_profile, apply_profile).chain())
+            .add_systems(Last, (apply_audio_volumes, save_profile));
+        if let Some(store) = store {
+            app.insert_resource(store);
+        }
+    }
+}
+
+fn sync_profile(mut comfort: ResMut<ComfortSettings>, mut profile: ResMut<UserProfile>) {
+    if profile.comfort == *comfort {
+        return;
+    }
+    if comfort.is_changed() {
+        profile.comfort = comfort.clone();
+    } else if profile.is_changed() {
+        *comfort = profile.comfort.clone();
+    }
+}
+
+fn apply_profile(
+    profile: Res<UserProfile>,
+    render_scale: Option<ResMut<RenderScale>>,
+    global_volume: Option<ResMut<GlobalVolume>>,
+    ipd_offset: Option<ResMut<OpenXrIpdOffset>>,
+) {
+    if !profile.is_changed() {
+        return;
+    }
+    if let Some(mut render_scale) = render_scale {
+        if render_scale.scale != profile.render.render_scale {
+            render_scale.scale = profile.render.render_scale;
+        }
+    }
+    if let Some(mut global_volume) = global_volume {
+        // New sinks start at the master volume until their category is applied
+        global_volume.volume = Volume::Linear(profile.audio.master);
+    }
+    if let Some(mut ipd_offset) = ipd_offset {
+        ipd_offset.set_if_neq(OpenXrIpdOffset(profile.calibration.ipd_offset));
+    }
+}
+
+/// Sinks take their volume once when they are created, so new sinks get their
+/// category volume here and all sinks follow changes of the profile
+fn apply_audio_volumes(
+    profile: Res<UserProfile>,
+    mut sinks: Query<(&mut AudioSink, &PlaybackSettings, Option<&AudioCategory>)>,
+    mut spatial_sinks: Query<(
+        &mut SpatialAudioSink,
+        &PlaybackSettings,
+        Option<&AudioCategory>,
+    )>,
+) {
+    let volume = |settings: &PlaybackSettings, category: Option<&AudioCategory>| {
+        settings.volume * Volume::Linear(profile.audio.volume(category.copied()))
+    };
+    for (mut sink, settings, category) in sinks.iter_mut() {
+        if profile.is_changed() || sink.is_added() {
+            sink.set_volume(volume(settings, category));
+        }
+    }
+    for (mut sink, settings, category) in spatial_sinks.iter_mut() {
+        if profile.is_changed() || sink.is_added() {
+            sink.set_volume(volume(settings, category));
+        }
+    }
+}
+
+fn save_profile(
+    profile: Res<UserProfile>,
+    store: Option<Res<ProfileStore>>,
+    time: Res<Time<Real>>,
+    mut state: ResMut<ProfileSaveState>,
+    mut exit: MessageReader<AppExit>,
+) {
+    let Some(store) = store else {
+        return;
+    };
+    let now = time.elapsed();
+    if profile.is_changed() && !profile.is_added() {
+        // Delay saving so that continuous changes (e.g. sliders) are written once
+        state.pending = Some(now + SAVE_DELAY);
+    }
+
+    let exiting = exit.read().count() > 0;
+    let due = state.pending.is_some_and(|due| now >= due || exiting);
+    if due {
+        state.pending = None;
+        match store.save(&profile) {
+            Ok(()) => debug!("Saved user profile '{}'", profile.name),
+            Err(e) => error!("Could not save user profile '{}': {}", profile.name, e),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::{collections::HashMap, fmt};
+
+use bevy::{prelude::*, scene::SceneInstanceReady};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Namespace of the IDs when the application sets none
+pub const DEFAULT_STABLE_ID_NAMESPACE: Uuid =
+    Uuid::from_u128(0x5f3c_2a4e_8d1b_4c07_9e61_0b7a_d2f4_c813);
+
+/// ID of an entity that is the same in every run and on every peer
+///
+/// Bevy identifies scene instances with random UUIDs, so references to
+/// "the door of the building" break when the scene is loaded again. Entities
+/// of a scene instance get an ID derived from the asset path of the scene
+/// and their node path in it, e.g. `Building/Door`, through a version 5
+/// UUID in the [`StableIdNamespace`]. When the `SceneRoot` has an ID of its
+/// own, it is the namespace instead, so several instances of one scene get
+/// different IDs. World files keep the IDs of the entities they save.
+#[derive(
+    Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct StableId(pub Uuid);
+
+/// Namespace of the derived [`StableId`]s, e.g. one per project so equal
+/// asset paths of different projects do not collide
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableIdNamespace(pub Uuid);
+
+/// Entities by [`StableId`]
+#[derive(Resource, Debug, Default)]
+pub struct StableIds {
+    entities: HashMap<StableId, Entity>,
+}
+
+/// Assigns [`StableId`]s to the entities of scene instances
+#[derive(Debug, Default)]
+pub struct StableIdPlugin;
+
+impl StableId {
+    /// ID of the node at `node_path` in the asset with `asset_key`
+    pub fn derive(namespace: Uuid, asset_key: &str, node_path: &str) -> Self {
+        Self(Uuid::new_v5(
+            &namespace,
+            format!("{asset_key}#{node_path}").as_bytes(),
+        ))
+    }
+}
+
+impl fmt::Display for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Default for StableIdNamespace {
+    fn default() -> Self {
+        Self(DEFAULT_STABLE_ID_NAMESPACE)
+    }
+}
+
+impl StableIds {
+    pub fn entity(&self, id: StableId) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+impl Plugin for StableIdPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StableIdNamespace>()
+            .init_resource::<StableIds>()
+            .add_observer(index_stable_id)
+            .add_observer(unindex_stable_id)
+            .add_observer(assign_scene_ids);
+    }
+}
+
+/// Gives the descendants of `root` IDs from their node paths in the asset
+/// with `asset_key`
+///
+/// Node paths join the names from below `root`, unnamed nodes are named by
+/// their index among the children and later siblings with the name of an
+/// earlier one get an index suffix, e.g. `Wheel[1]`. Entities with an ID
+/// keep it, and nested scene instances get their IDs from their own scene.
+pub fn assign_stable_ids(world: &mut World, root: Entity, asset_key: &str) {
+    let namespace = match world.get::<StableId>(root) {
+        Some(id) => id.0,
+        None => {
+            world
+                .get_resource::<StableIdNamespace>()
+                .copied()
+                .unwrap_or_default()
+                .0
+        }
+    };
+    let mut pending = vec![(root, String::new())];
+    let mut assigned = Vec::new();
+    while let Some((parent, parent_path)) = pending.pop() {
+        let Some(children) = world.get::<Children>(parent) else {
+            continue;
+        };
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for (index, child) in children.iter().enumerate() {
+            let name = world
+                .get::<Name>(child)
+                .map_or_else(|| format!("#{index}"), |name| name.as_str().to_owned());
+            let count = seen.entry(name.clone()).or_default();
+            let segment = match *count {
+                0 => name,
+                count => format!("{name}[{count}]"),
+            };
+            *count += 1;
+            let path = if parent_path.is_empty() {
+                segment
+            } else {
+                format!("{parent_path}/{segment}")
+            };
+
+            if !world.entity(child).contains::<StableId>() {
+                assigned.push((child, StableId::derive(namespace, asset_key, &path)));
+            }
+            if !world.entity(child).contains::<SceneRoot>() {
+                pending.push((child, path));
+            }
+        }
+    }
+    for (entity, id) in assigned {
+        world.entity_mut(entity).insert(id);
+    }
+}
+
+fn assign_scene_ids(
+    ready: On<SceneInstanceReady>,
+    mut commands: Commands,
+    roots: Query<&SceneRoot>,
+    asset_server: Res<AssetServer>,
+) {
+    let root = ready.entity;
+    let Ok(scene) = roots.get(root) else {
+        return;
+    };
+    let Some(path) = asset_server.get_path(scene.id()) else {
+        debug!("Scene of {} has no path, no stable ids assigned", root);
+        return;
+    };
+    let asset_key = path.to_string();
+    commands.queue(move |world: &mut World| assign_stable_ids(world, root, &asset_key));
+}
+
+fn index_stable_id(
+    insert: On<Insert, StableId>,
+    mut ids: ResMut<StableIds>,
+    query: Query<&StableId>,
+) {
+    let Ok(id) = query.get(insert.entity) else {
+        return;
+    };
+    if let Some(other) = ids.entities.insert(*id, insert.entity) {
+        // E.g. instances of one scene without ids of their own
+        if other != insert.entity {
+            warn_once!(
+                "Stable id {} of {} is also used by {}, further duplicates are not reported",
+                id,
+                insert.entity,
+                other
+            );
+        }
+    }
+}
+
+fn unindex_stable_id(
+    replace: On<Replace, StableId>,
+    mut ids: ResMut<StableIds>,
+    query: Query<&StableId>,
+) {
+    let Ok(id) = query.get(replace.entity) else {
+        return;
+    };
+    if ids.entities.get(id) == Some(&replace.entity) {
+        ids.entities.remove(id);
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::{
+    asset::{RecursiveDependencyLoadState, UntypedAssetId},
+    prelude::*,
+    render::render_asset::RenderAssetBytesPerFrame,
+};
+
+/// Progress of assets loading in the background
+///
+/// `AssetServer::load` returns a handle at once and loads the asset and its
+/// dependencies, e.g. the buffers, textures and materials of a glTF file, on
+/// background tasks. Scenes appear once loaded, while the app keeps
+/// rendering. Track the handles a level needs to show a loading indicator
+/// or wait for all of them with [`AssetsStreamed`].
+#[derive(Resource, Debug, Clone, Default)]
+pub struct AssetStreaming {
+    pending: Vec<UntypedHandle>,
+    loaded: usize,
+    failed: usize,
+}
+
+/// Written when every tracked asset is loaded or failed
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetsStreamed {
+    pub loaded: usize,
+    pub failed: usize,
+}
+
+/// Limits GPU uploads per frame so loading big scenes does not hitch rendering
+#[derive(Debug, Default)]
+pub struct AssetStreamingPlugin {
+    /// Bytes of meshes and textures uploaded per frame. Unlimited if `None`
+    pub upload_budget: Option<usize>,
+}
+
+impl AssetStreaming {
+    /// Tracks the asset and its dependencies until they are loaded
+    pub fn track(&mut self, handle: impl Into<UntypedHandle>) {
+        // Start counting again for the next batch
+        if self.pending.is_empty() {
+            self.loaded = 0;
+            self.failed = 0;
+        }
+        let handle = handle.into();
+        if !self.pending.contains(&handle) {
+            self.pending.push(handle);
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.loaded
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Finished part of the tracked assets, 1.0 when nothing is loading
+    pub fn progress(&self) -> f32 {
+        let finished = self.loaded + self.failed;
+        let total = finished + self.pending.len();
+        if total == 0 {
+            return 1.0;
+        }
+        finished as f32 / total as f32
+    }
+}
+
+impl Plugin for AssetStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetStreaming>()
+            .add_message::<AssetsStreamed>()
+            .add_systems(
+                PreUpdate,
+                update_asset_streaming.run_if(resource_exists::<AssetServer>),
+            );
+        if let Some(budget) = self.upload_budget {
+            app.insert_resource(RenderAssetBytesPerFrame::new(budget));
+        }
+    }
+}
+
+fn update_asset_streaming(
+    asset_server: Res<AssetServer>,
+    mut streaming: ResMut<AssetStreaming>,
+    mut streamed: MessageWriter<AssetsStreamed>,
+) {
+    if streaming.pending.is_empty() {
+        return;
+    }
+    let mut loaded = 0;
+    let mut failed = 0;
+    streaming.pending.retain(|handle| {
+        let id: UntypedAssetId = handle.id();
+        // Assets added directly, not through the server, have no load state
+        match asset_server.get_recursive_dependency_load_state(id) {
+            Some(RecursiveDependencyLoadState::Loaded) | None => loaded += 1,
+            Some(RecursiveDependencyLoadState::Failed(error)) => {
+                warn!("Could not load {:?}: {error}", handle.path());
+                failed += 1;
+            }
+            Some(_) => return true,
+        }
+        false
+    });
+    streaming.loaded += loaded;
+    streaming.failed += failed;
+
+    if streaming.pending.is_empty() {
+        streamed.write(AssetsStreamed {
+            loaded: streaming.loaded,
+            failed: streaming.failed,
+        });
+    }
+}
@@ -0,0 +1,1871 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        mouse::{MouseButtonInput, MouseScrollUnit, MouseWheel},
+        ButtonState,
+    },
+    prelude::*,
+    window::{AppLifecycle, WindowEvent},
+};
+use uuid::Uuid;
+use xrds_components::{AssetUnits, AssetUnitsPlugin, Chart, UiTheme};
+use xrds_core::LengthUnit;
+use xrds_graphics::{
+    CameraDepthPrepass, ColorFilter, ColorFilterMode, RenderScale, ShadowBias, Vignette,
+};
+use xrds_openxr::{OpenXrIpdOffset, OpenXrStageBounds};
+
+use crate::{
+    assign_stable_ids, dropped_asset_path, transform_from_text, Annotation, AnnotationCommand,
+    AnnotationPlugin, AnnotationSync, ApplyForce, AssetStreaming, AssetStreamingPlugin,
+    AssetsStreamed, AudioCategory, BoundProperty, CalibratedAnchor, CalibratedSpace, Calibration,
+    CalibrationCommand, CalibrationPlugin, CalibrationProbe, CalibrationStep, CameraController,
+    CameraControllerPlugin, CaptureFormat, ChannelAnnotationTransport, ChannelTransport,
+    ChartBinding, ClipboardCommand, Collider, ColliderFromMeshes, ColliderShape, ComfortPlugin,
+    ComfortSettings, ContentPackage, ContentProtection, DataBinding, DataBindingPlugin,
+    DataRefresh, DataRow, DataSource, DataSourceError, DataTable, DevicePower, FrameCaptured,
+    FrameHangRecovered, FrameLoad, FrameStats, GuidedTour, HeadlessFrames, HeadlessPlugin,
+    HeadlessSettings, ImportedFile, Mass, OverrideLayer, Persistent, PhysicsPlugin, PlaybackFrames,
+    PlaybackPlugin, PlaybackTarget, PlayerRig, PluginContext, PowerStatusProvider, ProfileStore,
+    PropertyBinding, QualityKnob, QualityLadder, QualityLevel, QualityPlugin, QualityState,
+    Recording, RecordingError, RecordingPlayback, RemoteFrame, RemoteFrameTransport, RemotePose,
+    RemoteView, RigidBody, RuntimeEvent, RuntimeEventPlugin, RuntimeHandler, RuntimeHandlerSlot,
+    SavedWorld, SceneLayers, SceneLayersPlugin, SettingsPlugin, SnapTurn, SnapTurnDirection,
+    StableId, StableIdPlugin, StableIds, SysfsPowerProvider, ThermalState, TourCommand,
+    TourFinished, TourHighlight, TourPlugin, TourStep, UiInputCapture, UserProfile, Velocity,
+    ViewpointCommand, ViewpointPlugin, ViewpointTransition, Viewpoints, WatchdogPlugin,
+    WatchdogSettings, WindowImportPlugin, WorldFileCommand, WorldFileError, WorldFilePlugin,
+    WorldLoaded, XrdsPlugin, XrdsPluginAdapter, DEFAULT_STABLE_ID_NAMESPACE, WORLD_FORMAT_VERSION,
+};
+
+#[test]
+fn profile_store_round_trip() {
+    let root = std::env::temp_dir().join(format!("xrds-profile-test-{}", std::process::id()));
+    let store = ProfileStore::new(&root);
+
+    let mut profile = store.load_or_default("player-1").unwrap();
+    assert_eq!(profile, UserProfile::new("player-1"));
+
+    profile.comfort.snap_turn.angle_degrees = 45.0;
+    profile.render.quality = QualityLevel::Low;
+    profile.audio.music = 0.25;
+    profile.calibration.height_offset = -0.05;
+    store.save(&profile).unwrap();
+
+    assert_eq!(store.list().unwrap(), vec!["player-1".to_owned()]);
+    assert_eq!(store.load("player-1").unwrap(), profile);
+    assert!(store.save(&UserProfile::new("../escape")).is_err());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn profile_missing_fields_use_defaults() {
+    let profile: UserProfile = serde_json::from_str(r#"{ "audio": { "master": 0.5 } }"#).unwrap();
+    assert_eq!(profile.audio.master, 0.5);
+    assert_eq!(profile.audio.voice, 1.0);
+    assert_eq!(profile.comfort, Default::default());
+}
+
+#[test]
+fn profile_settings_are_applied() {
+    let root = std::env::temp_dir().join(format!("xrds-profile-apply-{}", std::process::id()));
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        SettingsPlugin {
+            directory: Some(root.clone()),
+            ..SettingsPlugin::new("test", "player-1")
+        },
+    ))
+    .init_resource::<RenderScale>()
+    .init_resource::<GlobalVolume>()
+    .init_resource::<OpenXrIpdOffset>();
+
+    {
+        let mut profile = app.world_mut().resource_mut::<UserProfile>();
+        profile.render.render_scale = 0.75;
+        profile.audio.master = 0.5;
+        profile.audio.voice = 0.5;
+        profile.calibration.ipd_offset = 0.002;
+    }
+    app.update();
+
+    assert_eq!(app.world().resource::<RenderScale>().scale, 0.75);
+    assert_eq!(
+        app.world().resource::<GlobalVolume>().volume.to_linear(),
+        0.5
+    );
+    assert_eq!(app.world().resource::<OpenXrIpdOffset>().0, 0.002);
+
+    let audio = &app.world().resource::<UserProfile>().audio;
+    assert_eq!(audio.volume(Some(AudioCategory::Voice)), 0.25);
+    assert_eq!(audio.volume(Some(AudioCategory::Music)), 0.5);
+    assert_eq!(audio.volume(None), 0.5);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn calibration_floor_and_boundary() {
+    let mut app = App::new();
+    app.add_plugins(CalibrationPlugin);
+    let probe = app
+        .world_mut()
+        .spawn((CalibrationProbe, GlobalTransform::from_xyz(0.0, 1.0, 0.0)))
+        .id();
+    let anchor = app.world_mut().spawn(CalibratedAnchor).id();
+
+    app.world_mut().write_message(CalibrationCommand::Start);
+    app.update();
+    for y in [0.5, -0.1, 0.3] {
+        *app.world_mut().get_mut::<GlobalTransform>(probe).unwrap() =
+            GlobalTransform::from_xyz(0.0, y, 0.0);
+        app.update();
+    }
+    assert_eq!(
+        app.world().resource::<Calibration>().floor_height(),
+        Some(-0.1)
+    );
+
+    app.world_mut().write_message(CalibrationCommand::Confirm);
+    app.update();
+    assert_eq!(
+        app.world().resource::<Calibration>().step(),
+        CalibrationStep::Boundary
+    );
+
+    for (x, z) in [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)] {
+        *app.world_mut().get_mut::<GlobalTransform>(probe).unwrap() =
+            GlobalTransform::from_xyz(x, 0.0, z);
+        app.update();
+    }
+    app.world_mut().write_message(CalibrationCommand::Confirm);
+    app.update();
+
+    assert!(!app.world().resource::<Calibration>().is_active());
+    let profile = app.world().resource::<UserProfile>();
+    assert_eq!(profile.calibration.height_offset, -0.1);
+    assert_eq!(profile.calibration.boundary.len(), 5);
+
+    let space = app.world().resource::<CalibratedSpace>();
+    assert!(space.contains(Vec2::new(-0.5, 0.0)));
+    assert!(!space.contains(Vec2::new(2.0, 0.0)));
+    let anchor = app.world().get::<Transform>(anchor).unwrap();
+    assert_eq!(anchor.translation.y, -0.1);
+}
+
+#[test]
+fn calibration_uses_stage_bounds() {
+    let mut app = App::new();
+    app.add_plugins(CalibrationPlugin)
+        .insert_resource(OpenXrStageBounds {
+            size: Some(Vec2::new(2.0, 3.0)),
+        });
+    app.world_mut()
+        .spawn((CalibrationProbe, GlobalTransform::from_xyz(0.0, 0.02, 0.0)));
+    app.update();
+    assert_eq!(
+        app.world()
+            .resource::<Calibration>()
+            .runtime_boundary
+            .as_ref()
+            .map(Vec::len),
+        Some(4)
+    );
+
+    // The boundary step is skipped
+    app.world_mut().write_message(CalibrationCommand::Start);
+    app.update();
+    app.world_mut().write_message(CalibrationCommand::Confirm);
+    app.update();
+    assert!(!app.world().resource::<Calibration>().is_active());
+    let space = app.world().resource::<CalibratedSpace>();
+    assert!(space.contains(Vec2::new(0.9, -1.4)));
+    assert!(!space.contains(Vec2::new(1.1, 0.0)));
+
+    app.world_mut().resource_mut::<OpenXrStageBounds>().size = None;
+    app.update();
+    assert!(app
+        .world()
+        .resource::<Calibration>()
+        .runtime_boundary
+        .is_none());
+}
+
+#[test]
+fn watchdog_detects_stalled_frame() {
+    let attempts = Arc::new(AtomicU32::new(0));
+    let hook_attempts = attempts.clone();
+
+    let mut app = App::new();
+    app.add_plugins(WatchdogPlugin {
+        settings: WatchdogSettings {
+            frame_deadline: Duration::from_millis(40),
+            recovery_attempts: 1,
+            exit_on_hang: false,
+            recovery: Some(Arc::new(move |_| {
+                hook_attempts.fetch_add(1, Ordering::Relaxed);
+            })),
+            ..Default::default()
+        },
+    });
+    app.update();
+
+    std::thread::sleep(Duration::from_millis(200));
+    app.update();
+
+    assert!(attempts.load(Ordering::Relaxed) >= 1);
+    let recovered = app.world().resource::<Messages<FrameHangRecovered>>();
+    assert_eq!(recovered.len(), 1);
+}
+
+#[test]
+fn watchdog_default_recovery_without_session() {
+    let mut app = App::new();
+    app.add_plugins(WatchdogPlugin {
+        settings: WatchdogSettings {
+            frame_deadline: Duration::from_millis(40),
+            recovery_attempts: 1,
+            exit_on_hang: false,
+            report_dir: Some(std::env::temp_dir().join("xrds-watchdog-test")),
+            ..Default::default()
+        },
+    });
+    app.update();
+
+    std::thread::sleep(Duration::from_millis(200));
+    app.update();
+
+    let recovered = app.world().resource::<Messages<FrameHangRecovered>>();
+    assert_eq!(recovered.len(), 1);
+}
+
+#[test]
+fn watchdog_pauses_while_suspended() {
+    let attempts = Arc::new(AtomicU32::new(0));
+    let hook_attempts = attempts.clone();
+
+    let mut app = App::new();
+    app.add_plugins(WatchdogPlugin {
+        settings: WatchdogSettings {
+            frame_deadline: Duration::from_millis(40),
+            exit_on_hang: false,
+            recovery: Some(Arc::new(move |_| {
+                hook_attempts.fetch_add(1, Ordering::Relaxed);
+            })),
+            ..Default::default()
+        },
+    });
+    assert!(WatchdogSettings::default().exit_on_hang);
+    app.update();
+    app.world_mut().write_message(AppLifecycle::Suspended);
+    app.update();
+
+    std::thread::sleep(Duration::from_millis(200));
+    app.world_mut().write_message(AppLifecycle::Running);
+    app.update();
+
+    assert_eq!(attempts.load(Ordering::Relaxed), 0);
+    let recovered = app.world().resource::<Messages<FrameHangRecovered>>();
+    assert!(recovered.is_empty());
+}
+
+#[test]
+fn quality_ladder_steps_down_and_up() {
+    let mut ladder = QualityLadder::new(vec![
+        QualityKnob::RenderScale(0.8),
+        QualityKnob::PostProcessing(false),
+    ]);
+    let frame = Duration::from_millis(100);
+    let slow = Some(FrameLoad::Gpu(ladder.gpu_budget * 2));
+    let fast = Some(FrameLoad::Gpu(ladder.gpu_budget / 2));
+
+    let mut level = 0;
+    for _ in 0..20 {
+        if let Some(next) = ladder.update(level, slow, frame) {
+            level = next;
+        }
+    }
+    assert_eq!(level, 2);
+    let state = ladder.state(level);
+    assert_eq!(state.render_scale, 0.8);
+    assert!(!state.post_processing);
+
+    for _ in 0..30 {
+        if let Some(next) = ladder.update(level, fast, frame) {
+            level = next;
+        }
+    }
+    assert_eq!(level, 1);
+
+    ladder.min_level = 2;
+    assert_eq!(ladder.update(level, fast, frame), Some(2));
+}
+
+#[test]
+fn quality_ladder_without_gpu_timing() {
+    let mut ladder = QualityLadder::default();
+    let frame = Duration::from_millis(100);
+
+    // Frame times capped at 60 Hz are over the GPU budget but on time
+    let mut stats = FrameStats::default();
+    stats.smoothed_frame_time = Duration::from_micros(16_667);
+    for _ in 0..50 {
+        assert_eq!(ladder.update(0, stats.load(), frame), None);
+    }
+
+    stats.refresh_interval = Some(Duration::from_micros(16_667));
+    for _ in 0..50 {
+        assert_eq!(ladder.update(0, stats.load(), frame), None);
+    }
+
+    // Missed refreshes
+    stats.smoothed_frame_time = Duration::from_micros(25_000);
+    let mut level = 0;
+    for _ in 0..10 {
+        if let Some(next) = ladder.update(level, stats.load(), frame) {
+            level = next;
+        }
+    }
+    assert_eq!(level, 2);
+}
+
+#[test]
+fn quality_state_scales_configured_shadow_maps() {
+    use bevy::light::{DirectionalLightShadowMap, PointLightShadowMap};
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, QualityPlugin))
+        .insert_resource(DirectionalLightShadowMap { size: 4096 })
+        .insert_resource(PointLightShadowMap { size: 1024 });
+    let sizes = |app: &App| {
+        (
+            app.world().resource::<DirectionalLightShadowMap>().size,
+            app.world().resource::<PointLightShadowMap>().size,
+        )
+    };
+
+    // Full quality keeps the sizes of the application
+    app.update();
+    assert_eq!(sizes(&app), (4096, 1024));
+
+    app.world_mut()
+        .resource_mut::<QualityState>()
+        .shadow_map_scale = 0.5;
+    app.update();
+    assert_eq!(sizes(&app), (2048, 512));
+
+    // Sizes changed by the application are scaled from
+    app.world_mut().resource_mut::<PointLightShadowMap>().size = 2048;
+    app.world_mut()
+        .resource_mut::<QualityState>()
+        .shadow_map_scale = 0.25;
+    app.update();
+    assert_eq!(sizes(&app), (1024, 512));
+
+    app.world_mut()
+        .resource_mut::<QualityState>()
+        .shadow_map_scale = 1.0;
+    app.update();
+    assert_eq!(sizes(&app), (4096, 2048));
+}
+
+#[test]
+fn quality_state_biases_visibility_ranges() {
+    use bevy::camera::visibility::VisibilityRange;
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, QualityPlugin));
+    let entity = app
+        .world_mut()
+        .spawn(VisibilityRange::abrupt(0.0, 20.0))
+        .id();
+    let end = |app: &App| {
+        app.world()
+            .get::<VisibilityRange>(entity)
+            .unwrap()
+            .end_margin
+            .clone()
+    };
+
+    app.update();
+    assert_eq!(end(&app), 20.0..20.0);
+
+    app.world_mut().resource_mut::<QualityState>().lod_bias = 0.5;
+    app.update();
+    assert_eq!(end(&app), 10.0..10.0);
+
+    // Ranges changed by the application are biased from
+    app.world_mut()
+        .entity_mut(entity)
+        .insert(VisibilityRange::abrupt(0.0, 40.0));
+    app.update();
+    assert_eq!(end(&app), 20.0..20.0);
+
+    app.world_mut().resource_mut::<QualityState>().lod_bias = 1.0;
+    app.update();
+    assert_eq!(end(&app), 40.0..40.0);
+}
+
+#[test]
+fn sysfs_power_status() {
+    let root = std::env::temp_dir().join(format!("xrds-sysfs-test-{}", std::process::id()));
+    let write = |path: &str, value: &str| {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, value).unwrap();
+    };
+    write("power_supply/AC/type", "Mains\n");
+    write("power_supply/battery/type", "Battery\n");
+    write("power_supply/battery/capacity", "12\n");
+    write("power_supply/battery/status", "Discharging\n");
+    write("thermal/thermal_zone0/temp", "45000\n");
+    write("thermal/thermal_zone0/trip_point_0_type", "critical\n");
+    write("thermal/thermal_zone0/trip_point_0_temp", "95000\n");
+    write("thermal/thermal_zone0/trip_point_1_type", "passive\n");
+    write("thermal/thermal_zone0/trip_point_1_temp", "50000\n");
+
+    let provider = SysfsPowerProvider::with_root(&root);
+    let battery = provider.battery().unwrap();
+    assert!((battery.level - 0.12).abs() < 1e-6);
+    assert!(!battery.charging);
+    let headroom = provider.thermal_headroom().unwrap();
+    assert!((headroom - 0.9).abs() < 1e-6);
+
+    let power = DevicePower {
+        battery: Some(battery),
+        thermal_headroom: Some(headroom),
+        thermal_state: ThermalState::from_headroom(headroom),
+        low_battery_level: 0.15,
+    };
+    assert_eq!(power.thermal_state, ThermalState::Serious);
+    assert!(power.is_battery_low());
+    assert_eq!(power.min_quality_level(8), 4);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn channel_transport_keeps_latest_frame() {
+    let (transport, mut endpoint) = ChannelTransport::new();
+    let head = Transform::from_xyz(0.0, 1.6, 0.0);
+    for frame_id in 5..8 {
+        transport.send_pose(&RemotePose {
+            frame_id,
+            views: vec![head, head.with_translation(Vec3::new(0.064, 1.6, 0.0))],
+        });
+    }
+    // Older poses are replaced
+    let pose = endpoint.poses.borrow_and_update().clone().unwrap();
+    assert_eq!(pose.frame_id, 7);
+    assert_eq!(pose.views.len(), 2);
+    assert!(!endpoint.poses.has_changed().unwrap());
+
+    for frame_id in 0..3 {
+        endpoint
+            .frames
+            .try_send(RemoteFrame {
+                frame_id,
+                views: vec![RemoteView {
+                    pose: head,
+                    size: UVec2::ONE,
+                    pixels: vec![0; 4],
+                }],
+            })
+            .unwrap();
+    }
+    assert_eq!(transport.try_recv_frame().unwrap().frame_id, 2);
+    assert!(transport.try_recv_frame().is_none());
+}
+
+#[test]
+fn remote_messages_round_trip() {
+    let left = Transform::from_xyz(-0.032, 1.6, 0.0).with_rotation(Quat::from_rotation_y(0.3));
+    let right = Transform::from_xyz(0.032, 1.6, 0.0);
+    let pose = RemotePose {
+        frame_id: 42,
+        views: vec![left, right],
+    };
+    assert_eq!(RemotePose::from_bytes(&pose.to_bytes()), Some(pose));
+
+    let frame = RemoteFrame {
+        frame_id: 42,
+        views: vec![
+            RemoteView {
+                pose: left,
+                size: UVec2::new(2, 1),
+                pixels: vec![1; 8],
+            },
+            RemoteView {
+                pose: right,
+                size: UVec2::new(2, 1),
+                pixels: vec![2; 8],
+            },
+        ],
+    };
+    let bytes = frame.to_bytes();
+    assert_eq!(RemoteFrame::from_bytes(&bytes), Some(frame));
+    // Truncated pixels
+    assert_eq!(RemoteFrame::from_bytes(&bytes[..bytes.len() - 1]), None);
+}
+
+#[test]
+fn content_package_signature_and_encryption() {
+    let signing_key = [7u8; 32];
+    let public_key = ed25519_dalek::SigningKey::from_bytes(&signing_key)
+        .verifying_key()
+        .to_bytes();
+    let key = [42u8; 32];
+
+    let mut package = ContentPackage::new();
+    package.add("models/cube.gltf", b"{}".to_vec());
+    package.add("textures/a.png", vec![1, 2, 3]);
+    let sealed = package.seal(&signing_key, Some((&key, [1; 12]))).unwrap();
+
+    let protection = ContentProtection::new()
+        .with_key(key)
+        .with_trusted_key(&public_key)
+        .unwrap();
+    assert_eq!(protection.open(&sealed).unwrap(), package);
+    assert_eq!(protection.mount("level1", &sealed).unwrap(), 2);
+
+    // Untrusted signer
+    let untrusted = ContentProtection::new().with_key(key);
+    assert!(untrusted.open(&sealed).is_err());
+
+    // Tampered body
+    let mut tampered = sealed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(protection.open(&tampered).is_err());
+
+    // Missing key
+    let no_key = ContentProtection::new()
+        .with_trusted_key(&public_key)
+        .unwrap();
+    assert!(no_key.open(&sealed).is_err());
+
+    let mut escape = ContentPackage::new();
+    escape.add("../secret", vec![]);
+    let sealed = escape.seal(&signing_key, None).unwrap();
+    assert!(protection.mount("level1", &sealed).is_err());
+}
+
+#[test]
+fn xrds_plugin_registers_systems() {
+    #[derive(Resource, Default)]
+    struct Counter(u32);
+
+    #[derive(Component)]
+    struct Marker;
+
+    struct CounterPlugin;
+
+    impl XrdsPlugin for CounterPlugin {
+        fn build(&self, context: &mut PluginContext) {
+            context
+                .register_component::<Marker>()
+                .init_resource::<Counter>()
+                .add_systems(Update, |mut counter: ResMut<Counter>| counter.0 += 1);
+        }
+    }
+
+    let mut app = App::new();
+    app.add_plugins(XrdsPluginAdapter(Box::new(CounterPlugin)));
+    assert!(app.world().components().component_id::<Marker>().is_some());
+
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Counter>().0, 2);
+}
+
+#[test]
+fn viewpoints_fly_rig_between_bookmarks() {
+    let mut app = App::new();
+    app.add_plugins((
+        bevy::time::TimePlugin,
+        ViewpointPlugin {
+            transition: ViewpointTransition::Fly { duration: 1.0 },
+        },
+    ))
+    .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+        Duration::from_millis(250),
+    ));
+    let rig = app.world_mut().spawn(crate::PlayerRig).id();
+    app.update();
+
+    app.world_mut().write_message(ViewpointCommand::Save {
+        name: "entrance".to_owned(),
+        render: false,
+    });
+    app.update();
+    *app.world_mut().get_mut::<Transform>(rig).unwrap() =
+        Transform::from_xyz(4.0, 0.0, 0.0).looking_to(Vec3::X, Vec3::Y);
+    app.world_mut().write_message(ViewpointCommand::Save {
+        name: "stage".to_owned(),
+        render: false,
+    });
+    app.update();
+
+    let viewpoints = app.world().resource::<Viewpoints>();
+    assert_eq!(
+        viewpoints.names().collect::<Vec<_>>(),
+        ["entrance", "stage"]
+    );
+
+    app.world_mut().write_message(ViewpointCommand::Next);
+    let mut halfway = None;
+    for _ in 0..6 {
+        app.update();
+        let x = app.world().get::<Transform>(rig).unwrap().translation.x;
+        if halfway.is_none() && x < 4.0 - f32::EPSILON {
+            halfway = Some(x);
+        }
+    }
+    let halfway = halfway.unwrap();
+    assert!(halfway > 0.0 && halfway < 4.0);
+    assert!(app
+        .world()
+        .get::<Transform>(rig)
+        .unwrap()
+        .translation
+        .abs_diff_eq(Vec3::ZERO, 1e-5));
+
+    assert_eq!(
+        app.world().resource::<Viewpoints>().current().unwrap().name,
+        "entrance"
+    );
+}
+
+#[test]
+fn guided_tour_steps_through_highlights() {
+    let mut app = App::new();
+    app.add_plugins((bevy::time::TimePlugin, TourPlugin))
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(250),
+        ));
+    let engine = app.world_mut().spawn_empty().id();
+    let wheel = app.world_mut().spawn_empty().id();
+    app.insert_resource(GuidedTour::new(vec![
+        TourStep::new()
+            .with_viewpoint("overview")
+            .with_highlight(engine)
+            .with_caption("The engine"),
+        TourStep::new()
+            .with_highlight(wheel)
+            .with_duration(Duration::from_secs(1)),
+    ]));
+    let highlighted = |app: &mut App| {
+        let mut query = app
+            .world_mut()
+            .query_filtered::<Entity, With<TourHighlight>>();
+        query.iter(app.world()).collect::<Vec<_>>()
+    };
+
+    app.world_mut().write_message(TourCommand::Start);
+    app.update();
+    assert_eq!(app.world().resource::<GuidedTour>().current(), Some(0));
+    assert_eq!(highlighted(&mut app), [engine]);
+    let viewpoints = app.world().resource::<Messages<ViewpointCommand>>();
+    assert_eq!(
+        viewpoints.iter_current_update_messages().next(),
+        Some(&ViewpointCommand::GoTo("overview".to_owned()))
+    );
+
+    app.world_mut().write_message(TourCommand::Next);
+    app.update();
+    assert_eq!(highlighted(&mut app), [wheel]);
+
+    // The last step ends the tour after its duration
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<GuidedTour>().current(), None);
+    assert!(highlighted(&mut app).is_empty());
+    assert!(!app.world().resource::<Messages<TourFinished>>().is_empty());
+}
+
+fn comfort_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, ComfortPlugin))
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(100),
+        ))
+        .init_resource::<UiTheme>();
+    app
+}
+
+#[test]
+fn comfort_vignette_follows_rig_speed() {
+    let mut app = comfort_app();
+    app.world_mut()
+        .resource_mut::<ComfortSettings>()
+        .vignette
+        .fade_time = 0.0;
+    let rig = app
+        .world_mut()
+        .spawn((PlayerRig, GlobalTransform::IDENTITY))
+        .id();
+    let eyes = [
+        app.world_mut().spawn(Camera3d::default()).id(),
+        app.world_mut().spawn(Camera3d::default()).id(),
+    ];
+    let own = Vignette {
+        intensity: 0.3,
+        ..Default::default()
+    };
+    let custom = app.world_mut().spawn((Camera3d::default(), own)).id();
+    let intensity =
+        |app: &App, camera: Entity| app.world().get::<Vignette>(camera).unwrap().intensity;
+
+    app.update();
+    app.update();
+    for eye in eyes {
+        assert_eq!(intensity(&app, eye), 0.0);
+    }
+
+    // Every eye gets its own vignette at full strength above the max speed
+    *app.world_mut().get_mut::<GlobalTransform>(rig).unwrap() =
+        GlobalTransform::from_xyz(1.0, 0.0, 0.0);
+    app.update();
+    for eye in eyes {
+        assert_eq!(intensity(&app, eye), 0.8);
+    }
+    assert_eq!(*app.world().get::<Vignette>(custom).unwrap(), own);
+
+    app.update();
+    for eye in eyes {
+        assert_eq!(intensity(&app, eye), 0.0);
+    }
+
+    app.world_mut()
+        .resource_mut::<ComfortSettings>()
+        .vignette
+        .enabled = false;
+    *app.world_mut().get_mut::<GlobalTransform>(rig).unwrap() =
+        GlobalTransform::from_xyz(2.0, 0.0, 0.0);
+    app.update();
+    for eye in eyes {
+        assert_eq!(intensity(&app, eye), 0.0);
+    }
+}
+
+#[test]
+fn comfort_snap_turn_and_world_scale() {
+    let mut app = comfort_app();
+    let rig = app.world_mut().spawn(PlayerRig).id();
+    app.world_mut()
+        .resource_mut::<ComfortSettings>()
+        .world_scale = 2.0;
+    app.update();
+    let transform = |app: &App| *app.world().get::<Transform>(rig).unwrap();
+    assert_eq!(transform(&app).scale, Vec3::splat(0.5));
+
+    let yaw = |app: &App| transform(app).rotation.to_euler(EulerRot::YXZ).0;
+    app.world_mut()
+        .write_message(SnapTurn(SnapTurnDirection::Left));
+    app.update();
+    assert!((yaw(&app) - 30f32.to_radians()).abs() < 1e-5);
+
+    // Turns within the cooldown are dropped
+    app.world_mut()
+        .write_message(SnapTurn(SnapTurnDirection::Right));
+    app.update();
+    assert!((yaw(&app) - 30f32.to_radians()).abs() < 1e-5);
+
+    app.update();
+    app.update();
+    app.world_mut()
+        .write_message(SnapTurn(SnapTurnDirection::Right));
+    app.update();
+    assert!(yaw(&app).abs() < 1e-5);
+
+    app.world_mut()
+        .resource_mut::<ComfortSettings>()
+        .snap_turn
+        .enabled = false;
+    app.update();
+    app.update();
+    app.update();
+    app.world_mut()
+        .write_message(SnapTurn(SnapTurnDirection::Left));
+    app.update();
+    assert!(yaw(&app).abs() < 1e-5);
+    assert_eq!(transform(&app).scale, Vec3::splat(0.5));
+}
+
+#[test]
+fn comfort_snap_turn_keeps_head_in_place() {
+    let mut app = comfort_app();
+    app.add_plugins(TransformPlugin);
+    let rig = app
+        .world_mut()
+        .spawn((PlayerRig, Transform::from_xyz(2.0, 0.0, 1.0)))
+        .id();
+    let eyes = [-0.03, 0.03].map(|x| {
+        app.world_mut()
+            .spawn((
+                Camera3d::default(),
+                Transform::from_xyz(0.5 + x, 1.6, -0.2),
+                ChildOf(rig),
+            ))
+            .id()
+    });
+    let head = |app: &App| {
+        eyes.iter()
+            .map(|eye| {
+                app.world()
+                    .get::<GlobalTransform>(*eye)
+                    .unwrap()
+                    .translation()
+            })
+            .sum::<Vec3>()
+            / 2.0
+    };
+    app.update();
+    let start = head(&app);
+
+    app.world_mut()
+        .write_message(SnapTurn(SnapTurnDirection::Left));
+    app.update();
+    assert!(head(&app).distance(start) < 1e-5);
+    let yaw = app
+        .world()
+        .get::<Transform>(rig)
+        .unwrap()
+        .rotation
+        .to_euler(EulerRot::YXZ)
+        .0;
+    assert!((yaw - 30f32.to_radians()).abs() < 1e-5);
+}
+
+#[test]
+fn accessibility_filter_composes_with_camera_filter() {
+    let mut app = comfort_app();
+    let own = ColorFilter {
+        contrast: 0.5,
+        ..ColorFilter::new(ColorFilterMode::Grayscale)
+    };
+    let filtered = app.world_mut().spawn((Camera3d::default(), own)).id();
+    let plain = app.world_mut().spawn(Camera3d::default()).id();
+
+    app.update();
+    assert_eq!(*app.world().get::<ColorFilter>(filtered).unwrap(), own);
+    assert!(app.world().get::<ColorFilter>(plain).unwrap().is_identity());
+
+    let mut settings = app.world_mut().resource_mut::<ComfortSettings>();
+    settings.color_filter = ColorFilter {
+        contrast: 1.5,
+        ..ColorFilter::new(ColorFilterMode::Deuteranopia)
+    };
+    settings.high_contrast_ui = true;
+    app.update();
+    let composed = *app.world().get::<ColorFilter>(filtered).unwrap();
+    assert_eq!(composed.mode, ColorFilterMode::Deuteranopia);
+    assert_eq!(composed.contrast, 0.75);
+    assert_eq!(app.world().get::<ColorFilter>(plain).unwrap().contrast, 1.5);
+    assert!(app.world().resource::<UiTheme>().is_high_contrast());
+
+    // Filters set by the application are kept and composed again
+    app.world_mut()
+        .entity_mut(filtered)
+        .insert(ColorFilter::default());
+    app.update();
+    let composed = *app.world().get::<ColorFilter>(filtered).unwrap();
+    assert_eq!(composed.mode, ColorFilterMode::Deuteranopia);
+    assert_eq!(composed.contrast, 1.5);
+
+    app.world_mut()
+        .resource_mut::<ComfortSettings>()
+        .color_filter = ColorFilter::default();
+    app.update();
+    assert!(app
+        .world()
+        .get::<ColorFilter>(filtered)
+        .unwrap()
+        .is_identity());
+}
+
+#[test]
+fn asset_streaming_tracks_background_loads() {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        AssetStreamingPlugin::default(),
+    ))
+    .init_asset::<Image>();
+    let added = app
+        .world_mut()
+        .resource_mut::<Assets<Image>>()
+        .add(Image::default());
+    let missing = app
+        .world()
+        .resource::<AssetServer>()
+        .load::<Image>("missing/texture.png");
+    let mut streaming = app.world_mut().resource_mut::<AssetStreaming>();
+    streaming.track(added);
+    streaming.track(missing);
+    assert_eq!(streaming.pending(), 2);
+    assert_eq!(streaming.progress(), 0.0);
+
+    let start = std::time::Instant::now();
+    while !app.world().resource::<AssetStreaming>().is_done() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        app.update();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let streaming = app.world().resource::<AssetStreaming>();
+    assert_eq!(streaming.progress(), 1.0);
+    assert_eq!((streaming.loaded(), streaming.failed()), (1, 1));
+    let streamed = app.world().resource::<Messages<AssetsStreamed>>();
+    assert_eq!(
+        streamed.iter_current_update_messages().next(),
+        Some(&AssetsStreamed {
+            loaded: 1,
+            failed: 1
+        })
+    );
+}
+
+#[test]
+fn window_import_spawns_dropped_scenes() {
+    assert_eq!(
+        dropped_asset_path(std::path::Path::new("/home/user/robot.glb")).to_string(),
+        "dropped://home/user/robot.glb"
+    );
+
+    let mut app = App::new();
+    // The asset source is registered before the asset plugin
+    app.add_plugins((
+        WindowImportPlugin::default(),
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+    ))
+    .init_asset::<Scene>()
+    .add_message::<bevy::window::FileDragAndDrop>();
+    for path in ["/models/robot.glb", "/models/notes.txt"] {
+        app.world_mut()
+            .write_message(bevy::window::FileDragAndDrop::DroppedFile {
+                window: Entity::PLACEHOLDER,
+                path_buf: path.into(),
+            });
+    }
+    app.update();
+    let mut imported = app.world_mut().query::<(&ImportedFile, &SceneRoot)>();
+    let imported = imported
+        .iter(app.world())
+        .map(|(file, _)| file.0.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(imported, [std::path::PathBuf::from("/models/robot.glb")]);
+
+    let transform = Transform::from_xyz(1.0, 2.0, 3.0).with_scale(Vec3::splat(2.0));
+    let source = app.world_mut().spawn(transform).id();
+    let target = app.world_mut().spawn(Transform::IDENTITY).id();
+    app.world_mut()
+        .write_message(ClipboardCommand::CopyTransform(source));
+    app.update();
+    app.world_mut()
+        .write_message(ClipboardCommand::PasteTransform(target));
+    app.update();
+    assert_eq!(app.world().get::<Transform>(target), Some(&transform));
+    assert_eq!(transform_from_text("not a transform"), None);
+}
+
+#[test]
+fn saved_world_round_trips_persistent_entities() {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        WorldFilePlugin,
+    ))
+    .init_asset::<Scene>();
+    let scene = app
+        .world()
+        .resource::<AssetServer>()
+        .load::<Scene>("robot.gltf#Scene0");
+    let world = app.world_mut();
+    let root = world
+        .spawn((
+            Name::new("Room"),
+            Transform::from_xyz(1.0, 0.0, 0.0),
+            Persistent,
+        ))
+        .id();
+    world.spawn((
+        Name::new("Lamp"),
+        PointLight {
+            intensity: 800.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        ShadowBias::new(0.05, 0.4),
+        Transform::from_xyz(0.0, 2.0, 0.0),
+        ChildOf(root),
+    ));
+    let robot = world
+        .spawn((SceneRoot(scene), Transform::default(), ChildOf(root)))
+        .id();
+    // Spawned by the scene, so not saved
+    world.spawn((Name::new("Arm"), Transform::default(), ChildOf(robot)));
+    world.spawn((Name::new("Not saved"), Transform::default()));
+    // Dense vegetation, drawn depth only first
+    world.spawn((
+        Camera3d::default(),
+        CameraDepthPrepass::on(),
+        Transform::from_xyz(0.0, 1.6, 4.0),
+        ChildOf(root),
+    ));
+
+    let saved = SavedWorld::capture(world);
+    assert_eq!(saved.version, WORLD_FORMAT_VERSION);
+    assert_eq!(saved.entities.len(), 4);
+    assert_eq!(saved.entities[3].camera.unwrap().depth_prepass, Some(true));
+    assert_eq!(saved.entities[1].parent, Some(0));
+    assert_eq!(
+        saved.entities[2].scene.as_deref(),
+        Some("robot.gltf#Scene0")
+    );
+    let json = saved.to_json().unwrap();
+    assert_eq!(SavedWorld::from_json(&json).unwrap(), saved);
+    assert!(matches!(
+        SavedWorld::from_json(r#"{ "version": 99, "entities": [] }"#),
+        Err(WorldFileError::UnsupportedVersion(99))
+    ));
+
+    let path = std::env::temp_dir().join(format!("xrds-world-{}.json", std::process::id()));
+    saved.save(&path).unwrap();
+    app.world_mut()
+        .write_message(WorldFileCommand::Load(path.clone()));
+    app.update();
+    std::fs::remove_file(&path).unwrap();
+    let loaded = app.world().resource::<Messages<WorldLoaded>>();
+    let entities = &loaded
+        .iter_current_update_messages()
+        .next()
+        .unwrap()
+        .entities;
+    let world = app.world();
+    let lamp = world.entity(entities[1]);
+    assert_eq!(
+        lamp.get::<ChildOf>().map(ChildOf::parent),
+        Some(entities[0])
+    );
+    assert_eq!(lamp.get::<PointLight>().unwrap().intensity, 800.0);
+    assert_eq!(lamp.get::<ShadowBias>(), Some(&ShadowBias::new(0.05, 0.4)));
+    assert_eq!(
+        lamp.get::<Transform>().unwrap().translation,
+        Vec3::new(0.0, 2.0, 0.0)
+    );
+    assert!(world.entity(entities[0]).contains::<Persistent>());
+    assert!(world.entity(entities[2]).contains::<SceneRoot>());
+    assert_eq!(
+        world.entity(entities[3]).get::<CameraDepthPrepass>(),
+        Some(&CameraDepthPrepass::on())
+    );
+}
+
+#[test]
+fn saved_world_keeps_scene_unit_scale() {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        WorldFilePlugin,
+        AssetUnitsPlugin,
+    ))
+    .init_asset::<Scene>();
+    app.world_mut()
+        .resource_mut::<AssetUnits>()
+        .declare("models/cad/", LengthUnit::Millimeters);
+    let scene = app
+        .world()
+        .resource::<AssetServer>()
+        .load::<Scene>("models/cad/engine.gltf#Scene0");
+    let engine = app
+        .world_mut()
+        .spawn((SceneRoot(scene), Transform::default(), Persistent))
+        .id();
+    app.update();
+    let scale = |app: &App, entity| app.world().get::<Transform>(entity).unwrap().scale;
+    assert_eq!(scale(&app, engine), Vec3::splat(0.001));
+
+    let saved = SavedWorld::capture(app.world_mut());
+    assert!(saved.entities[0].scaled_to_meters);
+    let path = std::env::temp_dir().join(format!("xrds-units-{}.json", std::process::id()));
+    saved.save(&path).unwrap();
+    app.world_mut()
+        .write_message(WorldFileCommand::Load(path.clone()));
+    app.update();
+    std::fs::remove_file(&path).unwrap();
+    let loaded = app.world().resource::<Messages<WorldLoaded>>();
+    let loaded = loaded
+        .iter_current_update_messages()
+        .next()
+        .unwrap()
+        .entities[0];
+    app.update();
+    assert_eq!(scale(&app, loaded), Vec3::splat(0.001));
+}
+
+#[test]
+fn annotations_sync_between_participants_and_persist() {
+    let (a, b) = ChannelAnnotationTransport::pair();
+    let participant = |transport: ChannelAnnotationTransport, author: &str| {
+        let mut app = App::new();
+        app.add_plugins(AnnotationPlugin)
+            .insert_resource(UserProfile::new(author))
+            .insert_resource(AnnotationSync(Arc::new(transport)));
+        let table = app
+            .world_mut()
+            .spawn((Name::new("Table"), Transform::default(), Persistent))
+            .id();
+        (app, table)
+    };
+    let (mut alice, alice_table) = participant(a, "alice");
+    let (mut bob, bob_table) = participant(b, "bob");
+    let notes = |app: &mut App| {
+        app.world_mut()
+            .query::<(&Annotation, &Transform, &ChildOf)>()
+            .iter(app.world())
+            .map(|(annotation, transform, child_of)| {
+                (annotation.clone(), transform.translation, child_of.parent())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    alice.world_mut().write_message(AnnotationCommand::Place {
+        target: alice_table,
+        offset: Vec3::Y,
+        text: "Check the legs".to_owned(),
+    });
+    alice.update();
+    bob.update();
+    let received = notes(&mut bob);
+    assert_eq!(received.len(), 1);
+    let (note, offset, parent) = &received[0];
+    assert_eq!(
+        (note.text.as_str(), note.author.as_str()),
+        ("Check the legs", "alice")
+    );
+    assert_eq!((*offset, *parent), (Vec3::Y, bob_table));
+    assert_eq!(note.created, notes(&mut alice)[0].0.created);
+
+    bob.world_mut().write_message(AnnotationCommand::Edit {
+        id: note.id.clone(),
+        text: "Legs fixed".to_owned(),
+    });
+    bob.update();
+    alice.update();
+    assert_eq!(notes(&mut alice)[0].0.text, "Legs fixed");
+
+    let saved = SavedWorld::capture(alice.world_mut());
+    assert_eq!(
+        saved.entities[1]
+            .annotation
+            .as_ref()
+            .map(|note| note.text.as_str()),
+        Some("Legs fixed")
+    );
+
+    alice.world_mut().write_message(AnnotationCommand::Remove {
+        id: note.id.clone(),
+    });
+    alice.update();
+    bob.update();
+    assert!(notes(&mut alice).is_empty() && notes(&mut bob).is_empty());
+}
+
+#[test]
+fn data_sources_drive_bindings() {
+    let table = DataTable::from_csv("city,sales\n\"Seoul, KR\",3\nBusan,\"1\"\n").unwrap();
+    assert_eq!(table.columns, vec!["city", "sales"]);
+    assert_eq!(table.get(0, "city"), Some("Seoul, KR"));
+    assert_eq!(table.numbers("sales"), vec![3.0, 1.0]);
+    assert!(matches!(
+        DataTable::from_csv("a,b\n1\n"),
+        Err(DataSourceError::Csv { line: 2, .. })
+    ));
+    let json = DataTable::from_json(r#"[{ "city": "Seoul", "sales": 3 }, { "sales": 1.5 }]"#);
+    assert_eq!(json.unwrap().rows[1], vec!["".to_owned(), "1.5".to_owned()]);
+
+    let path = std::env::temp_dir().join(format!("xrds-data-test-{}.csv", std::process::id()));
+    std::fs::write(&path, "city,sales\nSeoul,3\nBusan,1\n").unwrap();
+
+    let mut app = App::new();
+    app.add_plugins((bevy::time::TimePlugin, DataBindingPlugin))
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(600),
+        ))
+        .init_resource::<Assets<StandardMaterial>>();
+    let source = app
+        .world_mut()
+        .spawn(DataSource::new(path.to_string_lossy()).with_refresh(DataRefresh::OnChange))
+        .id();
+    let material = app
+        .world_mut()
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(StandardMaterial::default());
+    let bar = app
+        .world_mut()
+        .spawn((
+            Transform::default(),
+            MeshMaterial3d(material.clone()),
+            DataBinding::new(
+                source,
+                DataRow::Key {
+                    column: "city".to_owned(),
+                    value: "Busan".to_owned(),
+                },
+            )
+            .with_property(
+                PropertyBinding::new("sales", BoundProperty::Height)
+                    .with_range(0.0, 4.0)
+                    .with_output(0.0, 2.0),
+            )
+            .with_property(
+                PropertyBinding::new(
+                    "sales",
+                    BoundProperty::Color {
+                        low: Color::BLACK,
+                        high: Color::WHITE,
+                    },
+                )
+                .with_range(0.0, 4.0),
+            ),
+        ))
+        .id();
+    let chart = app
+        .world_mut()
+        .spawn((
+            Chart::bar(),
+            ChartBinding::new(source)
+                .with_series("sales", Color::WHITE)
+                .with_categories("city"),
+        ))
+        .id();
+    app.update();
+
+    let height = |app: &App| app.world().get::<Transform>(bar).unwrap().scale.y;
+    assert_eq!(height(&app), 0.5);
+    let color = app
+        .world()
+        .resource::<Assets<StandardMaterial>>()
+        .get(&material)
+        .unwrap()
+        .base_color;
+    assert_eq!(color, Color::BLACK.mix(&Color::WHITE, 0.25));
+    let bound = app.world().get::<Chart>(chart).unwrap();
+    assert_eq!(bound.categories, vec!["Seoul", "Busan"]);
+    assert_eq!(
+        bound.series[0].points,
+        vec![Vec3::new(0.0, 3.0, 0.0), Vec3::new(1.0, 1.0, 0.0)]
+    );
+
+    std::fs::write(&path, "city,sales\nSeoul,3\nBusan,4\n").unwrap();
+    let modified = std::time::SystemTime::now() + Duration::from_secs(1);
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(modified))
+        .unwrap();
+    app.update();
+    assert_eq!(height(&app), 2.0);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn recordings_play_back_poses_and_frames() {
+    let csv = "time,track,x,y,z,qx,qy,qz,qw,speed\n\
+               0,robot,0,0,0,0,0,0,1,1\n\
+               2,robot,4,0,0,0,0,0,1,3\n";
+    let recording = Recording::from_csv(csv).unwrap();
+    assert_eq!(recording.duration(), Duration::from_secs(2));
+    assert_eq!(
+        recording.channel("robot/speed").unwrap().value_at(1.0),
+        Some(2.0)
+    );
+    let jsonl = "{\"t\": 0.5, \"topic\": \"head\", \"position\": [0, 1, 0], \"battery\": 0.9}\n\n\
+                 {\"t\": 0.0, \"topic\": \"head\", \"position\": [0, 2, 0]}\n";
+    let head = Recording::from_jsonl(jsonl).unwrap();
+    assert_eq!(head.track("head").unwrap().samples[0].time, 0.0);
+    assert_eq!(
+        head.channel("head/battery").unwrap().samples,
+        vec![(0.5, 0.9)]
+    );
+    assert!(matches!(
+        Recording::from_jsonl("{\"position\": [0, 0, 0]}"),
+        Err(RecordingError::MissingTime { line: 1 })
+    ));
+
+    let mut app = App::new();
+    app.add_plugins((bevy::time::TimePlugin, PlaybackPlugin))
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(250),
+        ))
+        .init_resource::<Assets<StandardMaterial>>()
+        .init_resource::<Assets<Image>>();
+    let mut playback = RecordingPlayback::new(recording);
+    playback.play();
+    let player = app.world_mut().spawn(playback).id();
+    let robot = app
+        .world_mut()
+        .spawn((Transform::default(), PlaybackTarget::new(player, "robot")))
+        .id();
+    let frames = {
+        let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+        [images.add(Image::default()), images.add(Image::default())]
+    };
+    let material = app
+        .world_mut()
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(StandardMaterial::default());
+    app.world_mut().spawn((
+        MeshMaterial3d(material.clone()),
+        PlaybackFrames::from_rate(player, frames.clone(), 1.0),
+    ));
+    // The first update only starts the clock
+    for _ in 0..5 {
+        app.update();
+    }
+    let x = |app: &App| app.world().get::<Transform>(robot).unwrap().translation.x;
+    assert_eq!(x(&app), 2.0);
+    let texture = |app: &App| {
+        let materials = app.world().resource::<Assets<StandardMaterial>>();
+        materials.get(&material).unwrap().base_color_texture.clone()
+    };
+    assert_eq!(texture(&app), Some(frames[1].clone()));
+
+    let mut playback = app
+        .world_mut()
+        .get_mut::<RecordingPlayback>(player)
+        .unwrap();
+    playback.pause();
+    playback.scrub(0.25);
+    app.update();
+    let playback = app.world().get::<RecordingPlayback>(player).unwrap();
+    assert_eq!(playback.time, Duration::from_millis(500));
+    assert_eq!(playback.value("robot/speed"), Some(1.5));
+    assert_eq!(x(&app), 1.0);
+    assert_eq!(texture(&app), Some(frames[0].clone()));
+}
+
+#[test]
+fn override_layers_compose_and_restore() {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        SceneLayersPlugin,
+    ))
+    .init_asset::<StandardMaterial>();
+    let paint = app
+        .world_mut()
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(StandardMaterial::default());
+    let world = app.world_mut();
+    let root = world
+        .spawn((Transform::default(), SceneLayers::default()))
+        .id();
+    let body = world
+        .spawn((Name::new("Body"), Transform::default(), ChildOf(root)))
+        .id();
+    // Unnamed primitive entities are skipped in paths
+    let primitive = world
+        .spawn((
+            MeshMaterial3d(paint.clone()),
+            Transform::default(),
+            ChildOf(body),
+        ))
+        .id();
+    let door = world
+        .spawn((
+            Name::new("Door"),
+            Transform::from_xyz(1.0, 0.0, 0.0),
+            Visibility::Visible,
+            ChildOf(body),
+        ))
+        .id();
+
+    let open = Transform::from_xyz(1.5, 0.0, 0.5);
+    let review = OverrideLayer::new("review")
+        .with_transform("Body/Door", open)
+        .with_material("Body", "car.glb#Material1");
+    let hidden = OverrideLayer::new("no doors").with_visibility("Body/Door", false);
+    let json = review.to_json().unwrap();
+    assert_eq!(OverrideLayer::from_json(&json).unwrap(), review);
+
+    app.world_mut()
+        .entity_mut(root)
+        .insert(SceneLayers::new([review, hidden]));
+    app.update();
+    let world = app.world();
+    assert_eq!(*world.get::<Transform>(door).unwrap(), open);
+    assert_eq!(*world.get::<Visibility>(door).unwrap(), Visibility::Hidden);
+    let swapped = world
+        .get::<MeshMaterial3d<StandardMaterial>>(primitive)
+        .unwrap();
+    assert_eq!(
+        swapped.0.path().map(|path| path.to_string()),
+        Some("car.glb#Material1".to_owned())
+    );
+
+    // Disabling layers restores the base scene
+    let mut layers = app.world_mut().get_mut::<SceneLayers>(root).unwrap();
+    assert!(layers.set_enabled("review", false));
+    assert!(layers.set_enabled("no doors", false));
+    assert!(!layers.set_enabled("missing", false));
+    app.update();
+    let world = app.world();
+    assert_eq!(
+        *world.get::<Transform>(door).unwrap(),
+        Transform::from_xyz(1.0, 0.0, 0.0)
+    );
+    assert_eq!(*world.get::<Visibility>(door).unwrap(), Visibility::Visible);
+    assert_eq!(
+        world
+            .get::<MeshMaterial3d<StandardMaterial>>(primitive)
+            .unwrap()
+            .0,
+        paint
+    );
+}
+
+#[cfg(feature = "egui")]
+#[test]
+fn egui_panel_is_hit_by_aim_rays() {
+    use xrds_openxr::OpenXrLayerPanel;
+
+    let panel = OpenXrLayerPanel::quad(Vec2::new(2.0, 1.0), UVec2::new(200, 100));
+    let transform = GlobalTransform::from_xyz(0.0, 1.5, -2.0);
+    let ray = |x, y| Ray3d::new(Vec3::new(x, y, 0.0), Dir3::NEG_Z);
+    let hit = |ray| crate::panel_hit(&panel, &transform, ray);
+    assert_eq!(hit(ray(0.0, 1.5)), Some(Vec2::new(100.0, 50.0)));
+    assert_eq!(hit(ray(-0.5, 1.75)), Some(Vec2::new(50.0, 25.0)));
+    assert_eq!(hit(ray(1.5, 1.5)), None);
+    // From behind the panel
+    assert_eq!(hit(Ray3d::new(Vec3::new(0.0, 1.5, -3.0), Dir3::Z)), None);
+}
+
+#[cfg(feature = "ros2")]
+#[test]
+fn ros2_poses_convert_to_bevy_axes() {
+    use xrds_net::client::Ros2Pose;
+
+    // One meter forward and a quarter turn to the left around ROS's z up
+    let (sin, cos) = std::f64::consts::FRAC_PI_4.sin_cos();
+    let pose = Ros2Pose {
+        position: [1.0, 0.0, 0.0],
+        orientation: [0.0, 0.0, sin, cos],
+    };
+    let transform = crate::transform_from_ros(&pose);
+    assert!(transform.translation.abs_diff_eq(Vec3::NEG_Z, 1e-6));
+    assert!((transform.rotation * Vec3::NEG_Z).abs_diff_eq(Vec3::NEG_X, 1e-6));
+
+    let back = crate::transform_to_ros(&transform);
+    for (a, b) in back.position.iter().zip(pose.position) {
+        assert!((a - b).abs() < 1e-6);
+    }
+    for (a, b) in back.orientation.iter().zip(pose.orientation) {
+        assert!((a - b).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn physics_drops_bodies_onto_colliders() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, TransformPlugin, PhysicsPlugin))
+        .insert_resource(Time::<Fixed>::from_hz(100.0))
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(10),
+        ));
+    let floor = app
+        .world_mut()
+        .spawn((
+            Collider::cuboid(Vec3::new(5.0, 0.5, 5.0)),
+            Transform::from_xyz(0.0, -0.5, 0.0),
+        ))
+        .id();
+    let ball = app
+        .world_mut()
+        .spawn((
+            RigidBody::Dynamic,
+            Collider::sphere(0.25),
+            Transform::from_xyz(0.0, 2.0, 0.0),
+        ))
+        .id();
+    let crate_entity = app
+        .world_mut()
+        .spawn((
+            RigidBody::Dynamic,
+            Mass(2.0),
+            ColliderFromMeshes,
+            Transform::from_xyz(3.0, 1.0, 0.0),
+        ))
+        .with_child((
+            bevy::camera::primitives::Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(0.5)),
+            Transform::from_scale(Vec3::splat(0.5)),
+        ))
+        .id();
+
+    for _ in 0..200 {
+        app.update();
+    }
+    let resting = |app: &App, entity| app.world().get::<Transform>(entity).unwrap().translation;
+    assert!((resting(&app, ball).y - 0.25).abs() < 0.02);
+    assert!(resting(&app, floor).abs_diff_eq(Vec3::new(0.0, -0.5, 0.0), 0.0));
+    let collider = app.world().get::<Collider>(crate_entity).unwrap();
+    assert_eq!(
+        collider.shape,
+        ColliderShape::Cuboid {
+            half_extents: Vec3::splat(0.25)
+        }
+    );
+    assert!((resting(&app, crate_entity).y - 0.25).abs() < 0.02);
+
+    // 2 kg pushed at 1 m/s slides to a stop on the floor
+    app.world_mut().apply_impulse(crate_entity, Vec3::X * 2.0);
+    assert_eq!(
+        app.world().get::<Velocity>(crate_entity).unwrap().linear.x,
+        1.0
+    );
+    for _ in 0..100 {
+        app.update();
+    }
+    let velocity = app.world().get::<Velocity>(crate_entity).unwrap().linear;
+    assert!(velocity.length() < 0.01);
+    assert!(resting(&app, crate_entity).x > 3.0);
+}
+
+#[test]
+fn window_input_reaches_runtime_handler() {
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<RuntimeEvent>,
+        updates: u32,
+    }
+    impl RuntimeHandler for Recorder {
+        fn on_update(&mut self) {
+            self.updates += 1;
+        }
+        fn on_event(&mut self, event: &RuntimeEvent) {
+            self.events.push(event.clone());
+        }
+    }
+
+    let mut app = App::new();
+    let recorder = Arc::new(Mutex::new(Recorder::default()));
+    app.add_plugins(RuntimeEventPlugin)
+        .insert_resource(RuntimeHandlerSlot(recorder.clone()));
+    let window = app.world_mut().spawn_empty().id();
+    app.world_mut().write_message_batch([
+        WindowEvent::KeyboardInput(KeyboardInput {
+            key_code: KeyCode::KeyW,
+            logical_key: Key::Character("w".into()),
+            state: ButtonState::Pressed,
+            text: Some("w".into()),
+            repeat: false,
+            window,
+        }),
+        WindowEvent::MouseButtonInput(MouseButtonInput {
+            button: MouseButton::Right,
+            state: ButtonState::Released,
+            window,
+        }),
+        WindowEvent::MouseWheel(MouseWheel {
+            unit: MouseScrollUnit::Line,
+            x: 0.0,
+            y: -1.0,
+            window,
+        }),
+    ]);
+    app.update();
+    app.update();
+
+    // Delivered once and in order
+    let recorder = recorder.lock().unwrap();
+    assert_eq!(recorder.updates, 2);
+    assert_eq!(
+        recorder.events,
+        [
+            RuntimeEvent::Key {
+                key: KeyCode::KeyW,
+                logical_key: Key::Character("w".into()),
+                pressed: true,
+                repeat: false,
+                text: Some("w".to_owned()),
+            },
+            RuntimeEvent::MouseButton {
+                button: MouseButton::Right,
+                pressed: false,
+            },
+            RuntimeEvent::Scroll {
+                delta: Vec2::new(0.0, -1.0),
+                unit: MouseScrollUnit::Line,
+            },
+        ]
+    );
+}
+
+#[test]
+fn lifecycle_reaches_runtime_handler() {
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<&'static str>,
+    }
+    impl RuntimeHandler for Recorder {
+        fn on_resumed(&mut self) {
+            self.calls.push("resumed");
+        }
+        fn on_suspended(&mut self) {
+            self.calls.push("suspended");
+        }
+    }
+
+    let mut app = App::new();
+    let recorder = Arc::new(Mutex::new(Recorder::default()));
+    app.add_plugins(RuntimeEventPlugin)
+        .insert_resource(RuntimeHandlerSlot(recorder.clone()));
+    for state in [
+        AppLifecycle::Running,
+        AppLifecycle::Suspended,
+        AppLifecycle::Running,
+    ] {
+        app.world_mut().write_message(state);
+        app.update();
+    }
+
+    // Starting is not a resume
+    assert_eq!(recorder.lock().unwrap().calls, ["suspended", "resumed"]);
+}
+
+#[test]
+fn captured_input_is_kept_from_runtime_events() {
+    let mut app = App::new();
+    app.add_plugins(RuntimeEventPlugin)
+        .insert_resource(UiInputCapture {
+            pointer: true,
+            keyboard: false,
+        });
+    let window = app.world_mut().spawn_empty().id();
+    app.world_mut().write_message_batch([
+        WindowEvent::MouseButtonInput(MouseButtonInput {
+            button: MouseButton::Left,
+            state: ButtonState::Pressed,
+            window,
+        }),
+        WindowEvent::MouseWheel(MouseWheel {
+            unit: MouseScrollUnit::Line,
+            x: 0.0,
+            y: 1.0,
+            window,
+        }),
+        WindowEvent::MouseButtonInput(MouseButtonInput {
+            button: MouseButton::Left,
+            state: ButtonState::Released,
+            window,
+        }),
+        WindowEvent::KeyboardInput(KeyboardInput {
+            key_code: KeyCode::KeyW,
+            logical_key: Key::Character("w".into()),
+            state: ButtonState::Pressed,
+            text: Some("w".into()),
+            repeat: false,
+            window,
+        }),
+    ]);
+    app.update();
+
+    let events: Vec<_> = app
+        .world_mut()
+        .resource_mut::<Messages<RuntimeEvent>>()
+        .drain()
+        .collect();
+    // Releases pass so nothing stays held
+    assert_eq!(
+        events,
+        [
+            RuntimeEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: false,
+            },
+            RuntimeEvent::Key {
+                key: KeyCode::KeyW,
+                logical_key: Key::Character("w".into()),
+                pressed: true,
+                repeat: false,
+                text: Some("w".to_owned()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn stable_ids_follow_node_paths() {
+    let mut app = App::new();
+    app.add_plugins(StableIdPlugin);
+    let world = app.world_mut();
+    let spawn_car = |world: &mut World, id: Option<StableId>| {
+        let root = world.spawn_empty().id();
+        if let Some(id) = id {
+            world.entity_mut(root).insert(id);
+        }
+        let door = world.spawn((Name::new("Door"), ChildOf(root))).id();
+        let handle = world.spawn((Name::new("Handle"), ChildOf(door))).id();
+        let wheels = [
+            world.spawn((Name::new("Wheel"), ChildOf(root))).id(),
+            world.spawn((Name::new("Wheel"), ChildOf(root))).id(),
+        ];
+        let unnamed = world.spawn(ChildOf(root)).id();
+        assign_stable_ids(world, root, "models/car.glb");
+        let id = |entity| *world.get::<StableId>(entity).unwrap();
+        [door, handle, wheels[0], wheels[1], unnamed].map(id)
+    };
+
+    let car = spawn_car(world, None);
+    let derive = |path| StableId::derive(DEFAULT_STABLE_ID_NAMESPACE, "models/car.glb", path);
+    assert_eq!(
+        car,
+        ["Door", "Door/Handle", "Wheel", "Wheel[1]", "#4"].map(derive)
+    );
+    let door = world.resource::<StableIds>().entity(car[0]).unwrap();
+    assert_eq!(world.get::<Name>(door).unwrap().as_str(), "Door");
+
+    // Instances with ids of their own do not share the ids of their nodes
+    let owned = spawn_car(world, Some(StableId(Uuid::from_u128(1))));
+    let again = spawn_car(world, Some(StableId(Uuid::from_u128(1))));
+    assert_eq!(owned, again);
+    assert!(owned.iter().all(|id| !car.contains(id)));
+
+    world.despawn(door);
+    assert_eq!(world.resource::<StableIds>().entity(car[1]), None);
+    assert!(world.resource::<StableIds>().entity(car[2]).is_some());
+}
+
+#[test]
+fn camera_controller_flies_and_orbits() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, CameraControllerPlugin))
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(250),
+        ));
+    let fly = app
+        .world_mut()
+        .spawn(CameraController::fly().with_damping(0.0))
+        .id();
+    let orbit = app
+        .world_mut()
+        .spawn((
+            CameraController::orbit(Vec3::ZERO)
+                .with_sensitivity(0.01)
+                .with_damping(0.0),
+            Transform::from_xyz(0.0, 0.0, 5.0),
+        ))
+        .id();
+    app.update();
+    app.update();
+
+    let key = |key: KeyCode, pressed: bool| RuntimeEvent::Key {
+        key,
+        logical_key: Key::Unidentified(bevy::input::keyboard::NativeKey::Unidentified),
+        pressed,
+        repeat: false,
+        text: None,
+    };
+    app.world_mut().write_message(key(KeyCode::KeyW, true));
+    app.update();
+    let translation = app.world().get::<Transform>(fly).unwrap().translation;
+    assert!(translation.abs_diff_eq(Vec3::new(0.0, 0.0, -0.75), 1e-4));
+
+    // Held keys are released when the window loses focus
+    app.world_mut().write_message(RuntimeEvent::FocusLost);
+    app.update();
+    let stopped = app.world().get::<Transform>(fly).unwrap().translation;
+    assert!(stopped.abs_diff_eq(translation, 1e-4));
+
+    app.world_mut().write_message_batch([
+        RuntimeEvent::Scroll {
+            delta: Vec2::Y,
+            unit: MouseScrollUnit::Line,
+        },
+        RuntimeEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed: true,
+        },
+        RuntimeEvent::MouseMotion {
+            delta: Vec2::new(100.0, 0.0),
+        },
+    ]);
+    app.update();
+    let transform = app.world().get::<Transform>(orbit).unwrap();
+    assert!((transform.translation.length() - 4.5).abs() < 1e-4);
+    assert!(transform.translation.x < 0.0);
+    // Still looking at the focus
+    assert!(transform
+        .forward()
+        .abs_diff_eq(-transform.translation.normalize(), 1e-4));
+}
+
+#[test]
+fn headless_frames_are_captured_after_frames() {
+    let mut app = App::new();
+    app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+        .init_asset::<Image>();
+    app.add_plugins(HeadlessPlugin {
+        settings: HeadlessSettings::new(UVec2::new(2, 2)),
+    });
+    let camera = app.world_mut().spawn(Camera::default()).id();
+    let frame = app
+        .world_mut()
+        .resource_mut::<HeadlessFrames>()
+        .request_capture(1, CaptureFormat::Png);
+    assert_eq!(frame, 1);
+    app.update();
+
+    let image = app.world().resource::<HeadlessFrames>().image().clone();
+    assert!(matches!(
+        &app.world().get::<Camera>(camera).unwrap().target,
+        bevy::camera::RenderTarget::Image(target) if target.handle == image
+    ));
+    let mut readbacks = app
+        .world_mut()
+        .query_filtered::<Entity, With<bevy::render::gpu_readback::Readback>>();
+    assert_eq!(readbacks.iter(app.world()).count(), 0);
+    app.update();
+    let capture = readbacks.single(app.world()).unwrap();
+
+    // Rows of the readback are aligned to 256 bytes
+    let pixels: Vec<u8> = (0..16).collect();
+    let mut data = vec![0; 512];
+    data[..8].copy_from_slice(&pixels[..8]);
+    data[256..264].copy_from_slice(&pixels[8..]);
+    app.world_mut()
+        .trigger(bevy::render::gpu_readback::ReadbackComplete {
+            entity: capture,
+            data,
+        });
+    app.update();
+    assert!(app.world().get_entity(capture).is_err());
+    assert!(!app.world().resource::<HeadlessFrames>().is_capturing());
+
+    let captured = app
+        .world_mut()
+        .resource_mut::<Messages<FrameCaptured>>()
+        .drain()
+        .collect::<Vec<_>>();
+    assert_eq!(captured.len(), 1);
+    assert_eq!(captured[0].frame, 1);
+    assert_eq!(captured[0].size, UVec2::new(2, 2));
+    let png = image::load_from_memory(&captured[0].data).unwrap();
+    assert_eq!(png.to_rgba8().into_raw(), pixels);
+}
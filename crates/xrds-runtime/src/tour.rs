@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use bevy::{camera::primitives::Aabb, gizmos::config::GizmoConfigStore, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{AudioCategory, Subtitle, ViewpointCommand};
+
+/// How long captions without a step duration are shown
+const CAPTION_DURATION: Duration = Duration::from_secs(5);
+
+/// One stop of a [`GuidedTour`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TourStep {
+    /// Name of a saved [`crate::Viewpoint`] to move to
+    pub viewpoint: Option<String>,
+    /// Entities outlined while the step is active
+    pub highlight: Vec<Entity>,
+    pub caption: Option<String>,
+    pub voiceover: Option<Handle<AudioSource>>,
+    /// Move to the next step automatically after this time
+    pub duration: Option<Duration>,
+}
+
+/// Ordered steps of a guided presentation
+///
+/// Steps are started with [`TourCommand`]s, written by UI buttons or
+/// received from the network, e.g. a presenter controlling the tour of
+/// every attendee.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GuidedTour {
+    pub steps: Vec<TourStep>,
+    /// Start over after the last step instead of stopping
+    pub looping: bool,
+    pub highlight_color: Color,
+    current: Option<usize>,
+    elapsed: Duration,
+}
+
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TourCommand {
+    /// Start at the first step
+    Start,
+    Next,
+    Previous,
+    GoTo(usize),
+    Stop,
+}
+
+/// Written when a step of the tour starts
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TourStepStarted {
+    pub index: usize,
+}
+
+/// Written when the tour stops, by command or after the last step
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TourFinished;
+
+/// Entity highlighted by the current step
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TourHighlight;
+
+/// Voiceover of the current step
+#[derive(Component, Debug, Clone, Copy)]
+struct TourVoiceover;
+
+#[derive(Debug, Default)]
+pub struct TourPlugin;
+
+impl TourStep {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_viewpoint(mut self, viewpoint: impl Into<String>) -> Self {
+        self.viewpoint = Some(viewpoint.into());
+        self
+    }
+
+    pub fn with_highlight(mut self, entity: Entity) -> Self {
+        self.highlight.push(entity);
+        self
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    pub fn with_voiceover(mut self, voiceover: Handle<AudioSource>) -> Self {
+        self.voiceover = Some(voiceover);
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+}
+
+impl GuidedTour {
+    pub fn new(steps: Vec<TourStep>) -> Self {
+        Self {
+            steps,
+            looping: false,
+            highlight_color: Color::srgb(1.0, 0.8, 0.2),
+            current: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Index of the active step, `None` when the tour is not running
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn current_step(&self) -> Option<&TourStep> {
+        self.steps.get(self.current?)
+    }
+
+    /// Step a command moves to, `None` to stop
+    fn target(&self, command: TourCommand) -> Option<usize> {
+        let len = self.steps.len();
+        let index = match (command, self.current) {
+            (TourCommand::Start, _) => 0,
+            (TourCommand::Stop, _) => return None,
+            (TourCommand::GoTo(index), _) => index,
+            (TourCommand::Next, None) => 0,
+            (TourCommand::Next, Some(current)) if current + 1 >= len && self.looping => 0,
+            (TourCommand::Next, Some(current)) => current + 1,
+            (TourCommand::Previous, None | Some(0)) => 0,
+            (TourCommand::Previous, Some(current)) => current - 1,
+        };
+        (index < len).then_some(index)
+    }
+}
+
+impl Default for GuidedTour {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl Plugin for TourPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GuidedTour>()
+            .add_message::<TourCommand>()
+            .add_message::<TourStepStarted>()
+            .add_message::<TourFinished>()
+            .add_message::<ViewpointCommand>()
+            .add_message::<Subtitle>()
+            .add_systems(
+                Update,
+                (
+                    advance_tour,
+                    run_tour_commands,
+                    draw_tour_highlights.run_if(resource_exists::<GizmoConfigStore>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Moves to the next step when the duration of the current one is over
+fn advance_tour(
+    time: Res<Time>,
+    mut tour: ResMut<GuidedTour>,
+    mut commands: MessageWriter<TourCommand>,
+) {
+    let Some(duration) = tour.current_step().and_then(|step| step.duration) else {
+        return;
+    };
+    tour.elapsed += time.delta();
+    if tour.elapsed >= duration {
+        tour.elapsed = Duration::ZERO;
+        commands.write(TourCommand::Next);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_tour_commands(
+    mut commands: Commands,
+    mut tour_commands: MessageReader<TourCommand>,
+    mut tour: ResMut<GuidedTour>,
+    mut started: MessageWriter<TourStepStarted>,
+    mut finished: MessageWriter<TourFinished>,
+    mut viewpoints: MessageWriter<ViewpointCommand>,
+    mut subtitles: MessageWriter<Subtitle>,
+    highlighted: Query<Entity, With<TourHighlight>>,
+    voiceovers: Query<Entity, With<TourVoiceover>>,
+) {
+    // Only the last command of a frame matters, e.g. a double click on next
+    let mut target = None;
+    for command in tour_commands.read() {
+        target = Some(tour.target(*command));
+    }
+    let Some(target) = target else {
+        return;
+    };
+
+    // Leave the current step
+    for entity in highlighted.iter() {
+        commands.entity(entity).remove::<TourHighlight>();
+    }
+    for entity in voiceovers.iter() {
+        commands.entity(entity).despawn();
+    }
+    tour.elapsed = Duration::ZERO;
+
+    let Some(index) = target else {
+        if tour.current.take().is_some() {
+            finished.write(TourFinished);
+        }
+        return;
+    };
+    tour.current = Some(index);
+    let step = &tour.steps[index];
+
+    if let Some(viewpoint) = &step.viewpoint {
+        viewpoints.write(ViewpointCommand::GoTo(viewpoint.clone()));
+    }
+    for entity in step.highlight.iter() {
+        if let Ok(mut entity) = commands.get_entity(*entity) {
+            entity.insert(TourHighlight);
+        }
+    }
+    if let Some(caption) = &step.caption {
+        subtitles.write(Subtitle {
+            text: caption.clone(),
+            duration: step.duration.unwrap_or(CAPTION_DURATION),
+        });
+    }
+    if let Some(voiceover) = &step.voiceover {
+        commands.spawn((
+            TourVoiceover,
+            AudioCategory::Voice,
+            AudioPlayer(voiceover.clone()),
+            PlaybackSettings::DESPAWN,
+        ));
+    }
+    started.write(TourStepStarted { index });
+}
+
+fn draw_tour_highlights(
+    mut gizmos: Gizmos,
+    tour: Res<GuidedTour>,
+    highlighted: Query<Entity, With<TourHighlight>>,
+    children: Query<&Children>,
+    bounds: Query<(&GlobalTransform, &Aabb)>,
+) {
+    for entity in highlighted.iter() {
+        // Scenes have their meshes, and bounds, below the root
+        for descendant in std::iter::once(entity).chain(children.iter_descendants(entity)) {
+            let Ok((transform, aabb)) = bounds.get(descendant) else {
+                continue;
+            };
+            let local = Transform::from_translation(aabb.center.into())
+                .with_scale(Vec3::from(aabb.half_extents) * 2.0);
+            gizmos.cuboid(transform.mul_transform(local), tour.highlight_color);
+        }
+    }
+}
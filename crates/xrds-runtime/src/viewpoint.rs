@@ -0,0 +1,380 @@
+use bevy::{camera::Exposure, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::PlayerRig;
+
+/// Named pose of the [`PlayerRig`], e.g. a stop of a guided presentation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Viewpoint {
+    pub name: String,
+    pub transform: Transform,
+    /// Applied to the cameras of the rig on arrival
+    pub render: Option<ViewpointRender>,
+}
+
+/// Camera settings stored with a [`Viewpoint`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewpointRender {
+    /// Exposure value at ISO 100
+    pub exposure: Option<f32>,
+    pub clear_color: Option<Color>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ViewpointTransition {
+    /// Jump to the viewpoint
+    Cut,
+    /// Fly the rig along a straight line, for desktop
+    Fly { duration: f32 },
+    /// Fade out, teleport and fade in again, for XR
+    Fade { duration: f32 },
+}
+
+/// Saved viewpoints in the order they were added
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Viewpoints {
+    viewpoints: Vec<Viewpoint>,
+    #[serde(skip)]
+    current: Option<usize>,
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ViewpointSettings {
+    pub transition: ViewpointTransition,
+    pub easing: EaseFunction,
+    /// Color the view fades to in [`ViewpointTransition::Fade`]
+    pub fade_color: Color,
+}
+
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum ViewpointCommand {
+    /// Save the current pose of the rig, with the camera settings if `render` is set
+    Save {
+        name: String,
+        render: bool,
+    },
+    Remove(String),
+    GoTo(String),
+    /// Go to the viewpoint after the current one, wrapping around
+    Next,
+    Previous,
+}
+
+/// Written when the rig reaches a viewpoint
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct ViewpointReached {
+    pub name: String,
+}
+
+/// Transition in progress
+#[derive(Resource, Debug, Clone, Default)]
+struct ViewpointFlight {
+    flight: Option<Flight>,
+}
+
+#[derive(Debug, Clone)]
+struct Flight {
+    from: Transform,
+    to: Viewpoint,
+    transition: ViewpointTransition,
+    elapsed: f32,
+    arrived: bool,
+}
+
+#[derive(Component)]
+struct ViewpointFade;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewpointPlugin {
+    /// Initial transition of [`ViewpointSettings`]
+    pub transition: ViewpointTransition,
+}
+
+impl Viewpoint {
+    pub fn new(name: impl Into<String>, transform: Transform) -> Self {
+        Self {
+            name: name.into(),
+            transform,
+            render: None,
+        }
+    }
+
+    pub fn with_render(mut self, render: ViewpointRender) -> Self {
+        self.render = Some(render);
+        self
+    }
+}
+
+impl Viewpoints {
+    /// Adds the viewpoint, replacing one with the same name in place
+    pub fn save(&mut self, viewpoint: Viewpoint) {
+        match self
+            .viewpoints
+            .iter_mut()
+            .find(|saved| saved.name == viewpoint.name)
+        {
+            Some(saved) => *saved = viewpoint,
+            None => self.viewpoints.push(viewpoint),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Viewpoint> {
+        let index = self.index(name)?;
+        self.current = match self.current {
+            Some(current) if current == index => None,
+            Some(current) if current > index => Some(current - 1),
+            current => current,
+        };
+        Some(self.viewpoints.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Viewpoint> {
+        self.viewpoints
+            .iter()
+            .find(|viewpoint| viewpoint.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Viewpoint> {
+        self.viewpoints.iter()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.viewpoints
+            .iter()
+            .map(|viewpoint| viewpoint.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.viewpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.viewpoints.is_empty()
+    }
+
+    /// Viewpoint last moved to
+    pub fn current(&self) -> Option<&Viewpoint> {
+        self.viewpoints.get(self.current?)
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.viewpoints
+            .iter()
+            .position(|viewpoint| viewpoint.name == name)
+    }
+
+    /// Viewpoint `offset` steps from the current one, starting at the first
+    fn step(&self, offset: isize) -> Option<usize> {
+        let len = self.viewpoints.len() as isize;
+        if len == 0 {
+            return None;
+        }
+        let index = match self.current {
+            Some(current) => (current as isize + offset).rem_euclid(len),
+            None if offset < 0 => len - 1,
+            None => 0,
+        };
+        Some(index as usize)
+    }
+}
+
+impl ViewpointSettings {
+    /// Teleport with a fade, more comfortable in XR than flying
+    pub fn teleport() -> Self {
+        Self {
+            transition: ViewpointTransition::Fade { duration: 0.6 },
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for ViewpointSettings {
+    fn default() -> Self {
+        Self {
+            transition: ViewpointTransition::Fly { duration: 1.5 },
+            easing: EaseFunction::CubicInOut,
+            fade_color: Color::BLACK,
+        }
+    }
+}
+
+impl Default for ViewpointPlugin {
+    fn default() -> Self {
+        Self {
+            transition: ViewpointSettings::default().transition,
+        }
+    }
+}
+
+impl Plugin for ViewpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Viewpoints>()
+            .init_resource::<ViewpointFlight>()
+            .insert_resource(ViewpointSettings {
+                transition: self.transition,
+                ..Default::default()
+            })
+            .add_message::<ViewpointCommand>()
+            .add_message::<ViewpointReached>()
+            .add_systems(Startup, spawn_viewpoint_fade)
+            .add_systems(
+                Update,
+                (handle_viewpoint_commands, fly_to_viewpoint).chain(),
+            );
+    }
+}
+
+fn spawn_viewpoint_fade(mut commands: Commands) {
+    commands.spawn((
+        ViewpointFade,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..Default::default()
+        },
+        BackgroundColor(Color::NONE),
+        // Below the subtitles
+        GlobalZIndex(i32::MAX - 2),
+        Pickable::IGNORE,
+    ));
+}
+
+fn handle_viewpoint_commands(
+    mut commands: MessageReader<ViewpointCommand>,
+    mut viewpoints: ResMut<Viewpoints>,
+    mut flight: ResMut<ViewpointFlight>,
+    settings: Res<ViewpointSettings>,
+    rigs: Query<(Entity, &Transform), With<PlayerRig>>,
+    children: Query<&Children>,
+    cameras: Query<(&Camera, Option<&Exposure>), With<Camera3d>>,
+) {
+    for command in commands.read() {
+        let index = match command {
+            ViewpointCommand::Save { name, render } => {
+                let Ok((rig, transform)) = rigs.single() else {
+                    warn!("Viewpoint {name} is not saved without a player rig");
+                    continue;
+                };
+                let mut viewpoint = Viewpoint::new(name.clone(), *transform);
+                if *render {
+                    let camera = children
+                        .iter_descendants(rig)
+                        .find_map(|entity| cameras.get(entity).ok());
+                    if let Some((camera, exposure)) = camera {
+                        viewpoint.render = Some(ViewpointRender {
+                            exposure: exposure.map(|exposure| exposure.ev100),
+                            clear_color: match camera.clear_color {
+                                ClearColorConfig::Custom(color) => Some(color),
+                                _ => None,
+                            },
+                        });
+                    }
+                }
+                viewpoints.save(viewpoint);
+                continue;
+            }
+            ViewpointCommand::Remove(name) => {
+                viewpoints.remove(name);
+                continue;
+            }
+            ViewpointCommand::GoTo(name) => viewpoints.index(name),
+            ViewpointCommand::Next => viewpoints.step(1),
+            ViewpointCommand::Previous => viewpoints.step(-1),
+        };
+        let Some(index) = index else {
+            warn!("Unknown viewpoint in {command:?}");
+            continue;
+        };
+        let Ok((_, transform)) = rigs.single() else {
+            continue;
+        };
+        viewpoints.current = Some(index);
+        // A new target takes over from where the rig is now
+        flight.flight = Some(Flight {
+            from: *transform,
+            to: viewpoints.viewpoints[index].clone(),
+            transition: settings.transition,
+            elapsed: 0.0,
+            arrived: false,
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fly_to_viewpoint(
+    time: Res<Time>,
+    settings: Res<ViewpointSettings>,
+    mut flight: ResMut<ViewpointFlight>,
+    mut reached: MessageWriter<ViewpointReached>,
+    mut rigs: Query<(Entity, &mut Transform), With<PlayerRig>>,
+    children: Query<&Children>,
+    mut cameras: Query<(Entity, &mut Camera), With<Camera3d>>,
+    mut fades: Query<&mut BackgroundColor, With<ViewpointFade>>,
+    mut commands: Commands,
+) {
+    let Some(current) = flight.flight.as_mut() else {
+        return;
+    };
+    let Ok((rig, mut transform)) = rigs.single_mut() else {
+        flight.flight = None;
+        return;
+    };
+
+    current.elapsed += time.delta_secs();
+    let duration = match current.transition {
+        ViewpointTransition::Cut => 0.0,
+        ViewpointTransition::Fly { duration } | ViewpointTransition::Fade { duration } => duration,
+    };
+    let t = if duration > 0.0 {
+        (current.elapsed / duration).min(1.0)
+    } else {
+        1.0
+    };
+
+    let arrive = match current.transition {
+        ViewpointTransition::Fly { .. } => {
+            let s = settings.easing.sample_clamped(t);
+            let to = current.to.transform;
+            *transform = Transform {
+                translation: current.from.translation.lerp(to.translation, s),
+                rotation: current.from.rotation.slerp(to.rotation, s),
+                scale: current.from.scale.lerp(to.scale, s),
+            };
+            t >= 1.0
+        }
+        // Teleport when the view is fully covered
+        ViewpointTransition::Fade { .. } => {
+            let alpha = settings.easing.sample_clamped(1.0 - (2.0 * t - 1.0).abs());
+            for mut fade in fades.iter_mut() {
+                fade.0 = settings.fade_color.with_alpha(alpha);
+            }
+            t >= 0.5
+        }
+        ViewpointTransition::Cut => true,
+    };
+
+    if arrive && !current.arrived {
+        current.arrived = true;
+        *transform = current.to.transform;
+        if let Some(render) = current.to.render {
+            for entity in children.iter_descendants(rig) {
+                let Ok((entity, mut camera)) = cameras.get_mut(entity) else {
+                    continue;
+                };
+                if let Some(color) = render.clear_color {
+                    camera.clear_color = ClearColorConfig::Custom(color);
+                }
+                if let Some(ev100) = render.exposure {
+                    commands.entity(entity).insert(Exposure { ev100 });
+                }
+            }
+        }
+        reached.write(ViewpointReached {
+            name: current.to.name.clone(),
+        });
+    }
+    if t >= 1.0 {
+        flight.flight = None;
+    }
+}
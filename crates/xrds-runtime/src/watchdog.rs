@@ -0,0 +1,362 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*, window::AppLifecycle};
+use serde::Serialize;
+use xrds_openxr::{OpenXrDeviceState, OpenXrSession, OpenXrSessionState};
+
+use crate::RuntimeError;
+
+/// Exit code used when the watchdog terminates the process
+pub const WATCHDOG_EXIT_CODE: i32 = 70;
+
+/// Called from the watchdog thread for every recovery attempt instead of the
+/// default recovery
+pub type RecoveryHook = Arc<dyn Fn(&HangReport) + Send + Sync>;
+
+const STAGES: [&str; 6] = [
+    "Startup",
+    "First",
+    "PreUpdate",
+    "Update",
+    "PostUpdate",
+    "Last",
+];
+
+/// Settings of the frame watchdog
+///
+/// The watchdog is paused while the application is suspended and while an
+/// XR session is not running with focus, since no frames are expected then.
+/// On a hang the running XR session is asked to stop, which releases a main
+/// thread blocked in the XR frame loop. `recovery` replaces this default.
+/// When the main thread is still stalled after all attempts, the hang report
+/// is written and the process exits unless `exit_on_hang` is unset.
+#[derive(Clone)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    /// A frame taking longer than this is considered hung
+    pub frame_deadline: Duration,
+    /// Number of recovery attempts before the process exits. Each attempt waits
+    /// another `frame_deadline`
+    pub recovery_attempts: u32,
+    /// Exit the process when recovery fails
+    pub exit_on_hang: bool,
+    /// Directory for hang reports. The temporary directory is used if `None`
+    pub report_dir: Option<PathBuf>,
+    pub recovery: Option<RecoveryHook>,
+}
+
+/// Diagnostic state collected by the watchdog
+#[derive(Debug, Clone, Serialize)]
+pub struct HangReport {
+    pub error: String,
+    pub frame: u64,
+    /// Last schedule entered by the main thread
+    pub stage: &'static str,
+    pub stalled_for_ms: u128,
+    pub last_frame_time_ms: u128,
+    pub recovery_attempts: u32,
+    pub timestamp: u64,
+}
+
+/// Written on the main thread after a detected hang resolved by itself
+/// or through a recovery hook
+#[derive(Message, Debug, Clone, Copy)]
+pub struct FrameHangRecovered {
+    pub stalled_for: Duration,
+}
+
+#[derive(Default)]
+pub struct WatchdogPlugin {
+    pub settings: WatchdogSettings,
+}
+
+struct WatchdogState {
+    epoch: Instant,
+    /// Microseconds since `epoch`. 0 until the first frame started
+    heartbeat: AtomicU64,
+    frame_start: AtomicU64,
+    last_frame_time: AtomicU64,
+    frame: AtomicU64,
+    stage: AtomicUsize,
+    hang_detected: AtomicBool,
+    /// No frames are expected, e.g. while suspended
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    /// Target of the default recovery
+    session: Mutex<Option<OpenXrSession>>,
+}
+
+#[derive(Resource, Clone)]
+struct Watchdog(Arc<WatchdogState>);
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            frame_deadline: Duration::from_secs(2),
+            recovery_attempts: 2,
+            exit_on_hang: true,
+            report_dir: None,
+            recovery: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for WatchdogSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchdogSettings")
+            .field("enabled", &self.enabled)
+            .field("frame_deadline", &self.frame_deadline)
+            .field("recovery_attempts", &self.recovery_attempts)
+            .field("exit_on_hang", &self.exit_on_hang)
+            .field("report_dir", &self.report_dir)
+            .field("recovery", &self.recovery.is_some())
+            .finish()
+    }
+}
+
+impl WatchdogState {
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+
+    fn beat(&self, stage: usize) {
+        self.stage.store(stage, Ordering::Relaxed);
+        self.heartbeat.store(self.now().max(1), Ordering::Release);
+    }
+}
+
+impl Plugin for WatchdogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<FrameHangRecovered>();
+        if !self.settings.enabled {
+            return;
+        }
+
+        let state = Arc::new(WatchdogState {
+            epoch: Instant::now(),
+            heartbeat: AtomicU64::new(0),
+            frame_start: AtomicU64::new(0),
+            last_frame_time: AtomicU64::new(0),
+            frame: AtomicU64::new(0),
+            stage: AtomicUsize::new(0),
+            hang_detected: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            session: Mutex::new(None),
+        });
+
+        let settings = self.settings.clone();
+        let thread_state = state.clone();
+        if let Err(e) = thread::Builder::new()
+            .name("xrds-watchdog".to_owned())
+            .spawn(move || watchdog_thread(thread_state, settings))
+        {
+            error!("Could not start watchdog thread: {}", e);
+            return;
+        }
+
+        app.add_message::<AppLifecycle>()
+            .insert_resource(Watchdog(state))
+            .add_systems(
+                First,
+                (track_session, pause_when_inactive, frame_begin, beat::<1>).chain(),
+            )
+            .add_systems(PreUpdate, beat::<2>)
+            .add_systems(Update, beat::<3>)
+            .add_systems(PostUpdate, beat::<4>)
+            .add_systems(Last, (beat::<5>, stop_on_exit));
+    }
+}
+
+fn track_session(watchdog: Res<Watchdog>, session: Option<Res<OpenXrSession>>) {
+    if session
+        .as_ref()
+        .is_some_and(|session| !session.is_changed())
+    {
+        return;
+    }
+    if let Ok(mut tracked) = watchdog.0.session.lock() {
+        *tracked = session.map(|session| session.clone());
+    }
+}
+
+/// Pauses the watchdog while suspended or while the XR session is not focused
+fn pause_when_inactive(
+    watchdog: Res<Watchdog>,
+    mut lifecycle: MessageReader<AppLifecycle>,
+    mut suspended: Local<bool>,
+    session_state: Option<Res<OpenXrSessionState>>,
+    device_state: Option<Res<OpenXrDeviceState>>,
+) {
+    for event in lifecycle.read() {
+        *suspended = !event.is_active();
+    }
+    let session_inactive = session_state.is_some_and(|session_state| {
+        *session_state != OpenXrSessionState::Unknown
+            && (*session_state != OpenXrSessionState::Running
+                || device_state
+                    .is_none_or(|device_state| *device_state != OpenXrDeviceState::Focused))
+    });
+    let paused = *suspended || session_inactive;
+
+    let state = &watchdog.0;
+    if paused != state.paused.load(Ordering::Acquire) {
+        if !paused {
+            // The time spent paused is not a stall
+            state.beat(0);
+            state.frame_start.store(0, Ordering::Relaxed);
+        }
+        state.paused.store(paused, Ordering::Release);
+        debug!("Watchdog {}", if paused { "paused" } else { "resumed" });
+    }
+}
+
+fn frame_begin(watchdog: Res<Watchdog>, mut recovered: MessageWriter<FrameHangRecovered>) {
+    let state = &watchdog.0;
+    let now = state.now();
+    let last = state.heartbeat.load(Ordering::Acquire);
+    let frame_start = state.frame_start.swap(now, Ordering::Relaxed);
+    if frame_start != 0 {
+        state
+            .last_frame_time
+            .store(now.saturating_sub(frame_start), Ordering::Relaxed);
+    }
+    if state.hang_detected.swap(false, Ordering::AcqRel) {
+        let stalled_for = Duration::from_micros(now.saturating_sub(last));
+        warn!("Main thread recovered after {:?}", stalled_for);
+        recovered.write(FrameHangRecovered { stalled_for });
+    }
+    state.frame.fetch_add(1, Ordering::Relaxed);
+}
+
+fn beat<const STAGE: usize>(watchdog: Res<Watchdog>) {
+    watchdog.0.beat(STAGE);
+}
+
+fn stop_on_exit(watchdog: Res<Watchdog>, mut exit: MessageReader<AppExit>) {
+    if exit.read().count() > 0 {
+        watchdog.0.stopped.store(true, Ordering::Release);
+    }
+}
+
+fn watchdog_thread(state: Arc<WatchdogState>, settings: WatchdogSettings) {
+    let interval = (settings.frame_deadline / 4).max(Duration::from_millis(10));
+    let deadline = settings.frame_deadline.as_micros() as u64;
+    let mut attempts = 0;
+
+    loop {
+        thread::sleep(interval);
+        // The app was dropped or is exiting
+        if Arc::strong_count(&state) == 1 || state.stopped.load(Ordering::Acquire) {
+            break;
+        }
+
+        let last = state.heartbeat.load(Ordering::Acquire);
+        if last == 0 || state.paused.load(Ordering::Acquire) {
+            attempts = 0;
+            continue;
+        }
+        let stalled_for = state.now().saturating_sub(last);
+        if stalled_for < deadline * (attempts as u64 + 1) {
+            if stalled_for < deadline {
+                attempts = 0;
+            }
+            continue;
+        }
+
+        state.hang_detected.store(true, Ordering::Release);
+        let report = HangReport {
+            error: RuntimeError::HANG.to_string(),
+            frame: state.frame.load(Ordering::Relaxed),
+            stage: STAGES[state.stage.load(Ordering::Relaxed).min(STAGES.len() - 1)],
+            stalled_for_ms: Duration::from_micros(stalled_for).as_millis(),
+            last_frame_time_ms: Duration::from_micros(
+                state.last_frame_time.load(Ordering::Relaxed),
+            )
+            .as_millis(),
+            recovery_attempts: attempts,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        error!(
+            "Frame {} is stalled for {} ms in {} (last frame time {} ms)",
+            report.frame, report.stalled_for_ms, report.stage, report.last_frame_time_ms
+        );
+
+        if attempts < settings.recovery_attempts {
+            attempts += 1;
+            warn!(
+                "Attempting recovery {}/{}",
+                attempts, settings.recovery_attempts
+            );
+            match &settings.recovery {
+                Some(recovery) => recovery(&report),
+                None => request_session_exit(&state),
+            }
+            continue;
+        }
+
+        write_report(&report, &settings);
+        if settings.exit_on_hang {
+            error!("Recovery failed. Exiting");
+            std::process::exit(WATCHDOG_EXIT_CODE);
+        }
+        // Wait until the main thread is alive again
+        while state.hang_detected.load(Ordering::Acquire) && Arc::strong_count(&state) > 1 {
+            thread::sleep(interval);
+        }
+        attempts = 0;
+    }
+}
+
+fn request_session_exit(state: &WatchdogState) {
+    let session = state
+        .session
+        .lock()
+        .ok()
+        .and_then(|session| session.clone());
+    let Some(session) = session else {
+        warn!("No XR session to recover");
+        return;
+    };
+    match session.request_exit() {
+        Ok(()) => warn!("Requested the XR session to stop"),
+        Err(e) => error!("Could not request the XR session to stop: {}", e),
+    }
+}
+
+fn write_report(report: &HangReport, settings: &WatchdogSettings) {
+    let json = match serde_json::to_string_pretty(report) {
+        Ok(json) => json,
+        Err(e) => {
+            error!("Could not serialize hang report: {}", e);
+            return;
+        }
+    };
+    error!("Hang report:\n{}", json);
+
+    let dir = settings
+        .report_dir
+        .clone()
+        .unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!(
+        "xrds-hang-{}-{}.json",
+        std::process::id(),
+        report.timestamp
+    ));
+    match std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, json)) {
+        Ok(()) => error!("Hang report written to {}", path.display()),
+        Err(e) => error!("Could not write hang report to {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,518 @@
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{asset::UntypedAssetId, ecs::message::MessageCursor, prelude::*};
+use serde::{Deserialize, Serialize};
+use xrds_components::ScaledToMeters;
+use xrds_graphics::{CameraDepthPrepass, CameraMultisample, GltfExporter, ShadowBias};
+
+use crate::{Annotation, StableId};
+
+/// Version written to new world files. Older files are migrated on load
+pub const WORLD_FORMAT_VERSION: u32 = 1;
+
+/// Entity saved with the world, with its descendants
+///
+/// Descendants of a `SceneRoot` are not saved, they are spawned again from
+/// the scene file when the world is loaded.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Persistent;
+
+/// Entities spawned by the user, as stored in a world file
+///
+/// Transforms, names, hierarchy, lights, cameras and annotations are stored
+/// by value. Scenes, meshes and materials are stored as asset paths, so only
+/// assets loaded from files are kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedWorld {
+    pub version: u32,
+    pub entities: Vec<SavedEntity>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedEntity {
+    pub id: Option<StableId>,
+    pub name: Option<String>,
+    /// Index of the parent in [`SavedWorld::entities`], which comes first
+    pub parent: Option<usize>,
+    pub transform: Transform,
+    pub scene: Option<String>,
+    /// The transform already scales the scene to meters
+    pub scaled_to_meters: bool,
+    pub mesh: Option<String>,
+    /// Path of a `StandardMaterial`
+    pub material: Option<String>,
+    pub light: Option<SavedLight>,
+    /// Shadow bias of the light, `None` keeps Bevy's default
+    pub shadow_bias: Option<ShadowBias>,
+    pub annotation: Option<Annotation>,
+    pub camera: Option<SavedCamera>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SavedLight {
+    Point {
+        color: Color,
+        intensity: f32,
+        range: f32,
+        radius: f32,
+        shadows: bool,
+    },
+    Spot {
+        color: Color,
+        intensity: f32,
+        range: f32,
+        radius: f32,
+        shadows: bool,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+    Directional {
+        color: Color,
+        illuminance: f32,
+        shadows: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedCamera {
+    pub order: isize,
+    pub active: bool,
+    pub projection: SavedProjection,
+    /// MSAA sample count, `None` follows the runtime default
+    #[serde(default)]
+    pub msaa: Option<u32>,
+    /// Depth pre-pass, for scenes with heavy overdraw such as alpha tested
+    /// vegetation. `None` follows the runtime default
+    #[serde(default)]
+    pub depth_prepass: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SavedProjection {
+    Perspective {
+        fov: f32,
+        near: f32,
+        far: f32,
+    },
+    /// Height of the view in world units
+    Orthographic {
+        height: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+#[derive(Debug)]
+pub enum WorldFileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Written by a newer runtime
+    UnsupportedVersion(u32),
+}
+
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub enum WorldFileCommand {
+    /// Save the [`Persistent`] entities
+    Save(PathBuf),
+    /// Spawn the entities of a world file next to the existing ones
+    Load(PathBuf),
+    /// Export the [`Persistent`] entities, with scene instances, as a `.glb` file
+    ExportGlb(PathBuf),
+}
+
+/// Written after the entities of a world file are spawned
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct WorldLoaded {
+    pub path: PathBuf,
+    pub entities: Vec<Entity>,
+}
+
+/// Saves and loads world files with [`WorldFileCommand`]s
+#[derive(Debug, Default)]
+pub struct WorldFilePlugin;
+
+impl fmt::Display for WorldFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not access world file: {error}"),
+            Self::Json(error) => write!(f, "Invalid world file: {error}"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "World file version {version} is newer than {WORLD_FORMAT_VERSION}"
+            ),
+        }
+    }
+}
+
+impl Error for WorldFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Json(error) => Some(error),
+            Self::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WorldFileError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for WorldFileError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl Default for SavedWorld {
+    fn default() -> Self {
+        Self {
+            version: WORLD_FORMAT_VERSION,
+            entities: Vec::new(),
+        }
+    }
+}
+
+impl SavedWorld {
+    /// Captures the [`Persistent`] entities and their descendants
+    pub fn capture(world: &mut World) -> Self {
+        let mut saved = Self::default();
+        for root in persistent_roots(world) {
+            saved.capture_entity(world, root, None);
+        }
+        saved
+    }
+
+    fn capture_entity(&mut self, world: &World, entity: Entity, parent: Option<usize>) {
+        let entity_ref = world.entity(entity);
+        let path = |id: UntypedAssetId| {
+            let asset_server = world.get_resource::<AssetServer>()?;
+            Some(asset_server.get_path(id)?.to_string())
+        };
+        let scene = entity_ref
+            .get::<SceneRoot>()
+            .and_then(|scene| path(scene.id().untyped()));
+        let light = if let Some(light) = entity_ref.get::<PointLight>() {
+            Some(SavedLight::Point {
+                color: light.color,
+                intensity: light.intensity,
+                range: light.range,
+                radius: light.radius,
+                shadows: light.shadows_enabled,
+            })
+        } else if let Some(light) = entity_ref.get::<SpotLight>() {
+            Some(SavedLight::Spot {
+                color: light.color,
+                intensity: light.intensity,
+                range: light.range,
+                radius: light.radius,
+                shadows: light.shadows_enabled,
+                inner_angle: light.inner_angle,
+                outer_angle: light.outer_angle,
+            })
+        } else {
+            entity_ref
+                .get::<DirectionalLight>()
+                .map(|light| SavedLight::Directional {
+                    color: light.color,
+                    illuminance: light.illuminance,
+                    shadows: light.shadows_enabled,
+                })
+        };
+        let camera = entity_ref.get::<Camera>().and_then(|camera| {
+            let projection = match entity_ref.get::<Projection>()? {
+                Projection::Perspective(projection) => SavedProjection::Perspective {
+                    fov: projection.fov,
+                    near: projection.near,
+                    far: projection.far,
+                },
+                Projection::Orthographic(projection) => SavedProjection::Orthographic {
+                    height: projection.area.height(),
+                    near: projection.near,
+                    far: projection.far,
+                },
+                Projection::Custom(_) => return None,
+            };
+            Some(SavedCamera {
+                order: camera.order,
+                active: camera.is_active,
+                projection,
+                msaa: entity_ref
+                    .get::<CameraMultisample>()
+                    .map(|multisample| multisample.samples),
+                depth_prepass: entity_ref
+                    .get::<CameraDepthPrepass>()
+                    .map(|prepass| prepass.enabled),
+            })
+        });
+
+        self.entities.push(SavedEntity {
+            id: entity_ref.get::<StableId>().copied(),
+            name: entity_ref.get::<Name>().map(|name| name.to_string()),
+            parent,
+            transform: entity_ref.get::<Transform>().copied().unwrap_or_default(),
+            scene,
+            scaled_to_meters: entity_ref.contains::<ScaledToMeters>(),
+            mesh: entity_ref
+                .get::<Mesh3d>()
+                .and_then(|mesh| path(mesh.id().untyped())),
+            material: entity_ref
+                .get::<MeshMaterial3d<StandardMaterial>>()
+                .and_then(|material| path(material.id().untyped())),
+            light,
+            shadow_bias: entity_ref.get::<ShadowBias>().copied(),
+            annotation: entity_ref.get::<Annotation>().cloned(),
+            camera,
+        });
+        // Scene instances are spawned again from the scene
+        if entity_ref.contains::<SceneRoot>() {
+            return;
+        }
+        let index = self.entities.len() - 1;
+        if let Some(children) = entity_ref.get::<Children>() {
+            for child in children.iter() {
+                self.capture_entity(world, child, Some(index));
+            }
+        }
+    }
+
+    /// Spawns the saved entities, roots are marked [`Persistent`]
+    pub fn spawn(&self, world: &mut World) -> Vec<Entity> {
+        let asset_server = world.get_resource::<AssetServer>().cloned();
+        let mut entities: Vec<Entity> = Vec::with_capacity(self.entities.len());
+        for saved in &self.entities {
+            let mut entity = world.spawn(saved.transform);
+            if let Some(id) = saved.id {
+                entity.insert(id);
+            }
+            if let Some(name) = &saved.name {
+                entity.insert(Name::new(name.clone()));
+            }
+            match saved.parent.and_then(|parent| entities.get(parent)) {
+                Some(parent) => {
+                    entity.insert(ChildOf(*parent));
+                }
+                None => {
+                    entity.insert(Persistent);
+                }
+            }
+            if saved.scaled_to_meters {
+                entity.insert(ScaledToMeters);
+            }
+            if let Some(asset_server) = &asset_server {
+                if let Some(path) = &saved.scene {
+                    entity.insert(SceneRoot(asset_server.load(path.clone())));
+                }
+                if let Some(path) = &saved.mesh {
+                    entity.insert(Mesh3d(asset_server.load(path.clone())));
+                }
+                if let Some(path) = &saved.material {
+                    entity.insert(MeshMaterial3d::<StandardMaterial>(
+                        asset_server.load(path.clone()),
+                    ));
+                }
+            }
+            if let Some(annotation) = &saved.annotation {
+                entity.insert(annotation.clone());
+            }
+            match saved.light {
+                Some(SavedLight::Point {
+                    color,
+                    intensity,
+                    range,
+                    radius,
+                    shadows,
+                }) => {
+                    entity.insert(PointLight {
+                        color,
+                        intensity,
+                        range,
+                        radius,
+                        shadows_enabled: shadows,
+                        ..default()
+                    });
+                }
+                Some(SavedLight::Spot {
+                    color,
+                    intensity,
+                    range,
+                    radius,
+                    shadows,
+                    inner_angle,
+                    outer_angle,
+                }) => {
+                    entity.insert(SpotLight {
+                        color,
+                        intensity,
+                        range,
+                        radius,
+                        shadows_enabled: shadows,
+                        inner_angle,
+                        outer_angle,
+                        ..default()
+                    });
+                }
+                Some(SavedLight::Directional {
+                    color,
+                    illuminance,
+                    shadows,
+                }) => {
+                    entity.insert(DirectionalLight {
+                        color,
+                        illuminance,
+                        shadows_enabled: shadows,
+                        ..default()
+                    });
+                }
+                None => {}
+            }
+            if let Some(bias) = saved.shadow_bias {
+                entity.insert(bias);
+            }
+            if let Some(camera) = saved.camera {
+                let projection = match camera.projection {
+                    SavedProjection::Perspective { fov, near, far } => {
+                        Projection::Perspective(PerspectiveProjection {
+                            fov,
+                            near,
+                            far,
+                            ..default()
+                        })
+                    }
+                    SavedProjection::Orthographic { height, near, far } => {
+                        Projection::Orthographic(OrthographicProjection {
+                            scaling_mode: bevy::camera::ScalingMode::FixedVertical {
+                                viewport_height: height,
+                            },
+                            near,
+                            far,
+                            ..OrthographicProjection::default_3d()
+                        })
+                    }
+                };
+                entity.insert((
+                    Camera3d::default(),
+                    Camera {
+                        order: camera.order,
+                        is_active: camera.active,
+                        ..default()
+                    },
+                    projection,
+                ));
+                if let Some(samples) = camera.msaa {
+                    entity.insert(CameraMultisample::new(samples));
+                }
+                if let Some(enabled) = camera.depth_prepass {
+                    entity.insert(CameraDepthPrepass { enabled });
+                }
+            }
+            entities.push(entity.id());
+        }
+        entities
+    }
+
+    pub fn to_json(&self) -> Result<String, WorldFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a world file, migrating it from older versions
+    pub fn from_json(text: &str) -> Result<Self, WorldFileError> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        let version = value
+            .get("version")
+            .and_then(|version| version.as_u64())
+            .unwrap_or(0) as u32;
+        if version > WORLD_FORMAT_VERSION {
+            return Err(WorldFileError::UnsupportedVersion(version));
+        }
+        // Migrations of older versions go here, each bumping `version`
+        let mut world: Self = serde_json::from_value(value)?;
+        world.version = WORLD_FORMAT_VERSION;
+        Ok(world)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WorldFileError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first so a crash never leaves a truncated world
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, self.to_json()?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WorldFileError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl Plugin for WorldFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<WorldFileCommand>()
+            .add_message::<WorldLoaded>()
+            .add_systems(
+                PostUpdate,
+                apply_world_file_commands.run_if(on_message::<WorldFileCommand>),
+            );
+    }
+}
+
+/// [`Persistent`] entities without a persistent ancestor
+pub fn persistent_roots(world: &mut World) -> Vec<Entity> {
+    let mut roots = world.query_filtered::<Entity, With<Persistent>>();
+    let mut ancestors = world.query::<&ChildOf>();
+    let persistent = roots.iter(world).collect::<Vec<_>>();
+    let ancestors = ancestors.query(world);
+    // Persistent descendants of persistent entities are saved with their root
+    persistent
+        .iter()
+        .copied()
+        .filter(|entity| {
+            !ancestors
+                .iter_ancestors(*entity)
+                .any(|ancestor| persistent.contains(&ancestor))
+        })
+        .collect()
+}
+
+fn apply_world_file_commands(
+    world: &mut World,
+    mut cursor: Local<MessageCursor<WorldFileCommand>>,
+) {
+    let commands = cursor
+        .read(world.resource::<Messages<WorldFileCommand>>())
+        .cloned()
+        .collect::<Vec<_>>();
+    for command in commands {
+        match command {
+            WorldFileCommand::Save(path) => match SavedWorld::capture(world).save(&path) {
+                Ok(()) => info!("Saved world to {}", path.display()),
+                Err(error) => warn!("Could not save world to {}: {error}", path.display()),
+            },
+            WorldFileCommand::Load(path) => match SavedWorld::load(&path) {
+                Ok(saved) => {
+                    let entities = saved.spawn(world);
+                    info!("Loaded {} entities from {}", entities.len(), path.display());
+                    world.write_message(WorldLoaded { path, entities });
+                }
+                Err(error) => warn!("Could not load world from {}: {error}", path.display()),
+            },
+            WorldFileCommand::ExportGlb(path) => {
+                let exporter = GltfExporter::new().with_roots(persistent_roots(world));
+                match exporter.save(world, &path) {
+                    Ok(()) => info!("Exported world to {}", path.display()),
+                    Err(error) => warn!("Could not export world to {}: {error}", path.display()),
+                }
+            }
+        }
+    }
+}
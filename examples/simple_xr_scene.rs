@@ -8,6 +8,8 @@ pub fn main() {
     let runtime = Runtime::new(RuntimeParameters {
         app_name: "SimpleXRScene".to_owned(),
         enable_xr: true,
+        stats_overlay: true,
+        ..Default::default()
     });
     let app = App {};
 